@@ -11,19 +11,21 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::path::Path;
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
 use failure::bail;
 use lazy_static::lazy_static;
-use log::{info, trace};
+use log::{error, info, trace};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use dataflow_types::{SinkConnector, SourceConnector};
 use expr::{EvalEnv, GlobalId, Id, IdHumanizer, OptimizedRelationExpr, ScalarExpr};
-use repr::RelationDesc;
+use repr::{RelationDesc, ScalarType};
 
 use crate::names::{DatabaseSpecifier, FullName, PartialName};
 
+mod migrate;
 pub mod names;
 
 pub mod sql;
@@ -90,6 +92,12 @@ pub struct CatalogEntry {
     used_by: Vec<GlobalId>,
     id: GlobalId,
     name: FullName,
+    comment: Option<String>,
+    tags: BTreeMap<String, String>,
+    /// Why the item cannot be used, if it has failed. Unlike the other fields,
+    /// this is not persisted, as it is determined anew each time the catalog
+    /// is loaded.
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +106,7 @@ pub enum CatalogItem {
     View(View),
     Sink(Sink),
     Index(Index),
+    Type(Type),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +121,9 @@ pub struct Sink {
     pub create_sql: String,
     pub from: GlobalId,
     pub connector: SinkConnector,
+    /// The compute cluster that runs the sink, or `None` for the default
+    /// cluster.
+    pub cluster: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +132,10 @@ pub struct View {
     pub expr: OptimizedRelationExpr,
     pub eval_env: EvalEnv,
     pub desc: RelationDesc,
+    pub refresh_interval: Option<Duration>,
+    pub changes_only: bool,
+    pub emit_final_after: Option<usize>,
+    pub max_lag: Option<Duration>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +144,21 @@ pub struct Index {
     pub on: GlobalId,
     pub keys: Vec<ScalarExpr>,
     pub eval_env: EvalEnv,
+    /// The compute cluster that maintains the index, or `None` for the
+    /// default cluster.
+    pub cluster: Option<String>,
+    /// The compute clusters that maintain read-only replicas of the index,
+    /// which serve peeks but are never read by other dataflows.
+    pub replicas: Vec<String>,
+}
+
+/// A user-defined enum type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Type {
+    pub create_sql: String,
+    /// The labels of the type's values. Values are stored as the position of
+    /// their label in this list.
+    pub labels: Vec<String>,
 }
 
 impl CatalogItem {
@@ -138,25 +169,46 @@ impl CatalogItem {
             CatalogItem::Sink(_) => "sink",
             CatalogItem::View(_) => "view",
             CatalogItem::Index(_) => "index",
+            CatalogItem::Type(_) => "type",
         }
     }
 
     /// Collects the identifiers of the dataflows that this item depends
-    /// upon.
+    /// upon, along with the types of its enum columns, whose labels are
+    /// needed to render its rows.
     pub fn uses(&self) -> Vec<GlobalId> {
         match self {
-            CatalogItem::Source(_) => vec![],
+            CatalogItem::Source(src) => type_uses(&src.desc),
             CatalogItem::Sink(sink) => vec![sink.from],
             CatalogItem::View(view) => {
                 let mut out = Vec::new();
                 view.expr.as_ref().global_uses(&mut out);
+                out.extend(type_uses(&view.desc));
                 out
             }
             CatalogItem::Index(idx) => vec![idx.on],
+            CatalogItem::Type(_) => vec![],
         }
     }
 }
 
+/// Collects the identifiers of the types of the enum columns in `desc`, without
+/// duplicates.
+fn type_uses(desc: &RelationDesc) -> Vec<GlobalId> {
+    let mut out: Vec<_> = desc
+        .typ()
+        .column_types
+        .iter()
+        .filter_map(|typ| match typ.scalar_type {
+            ScalarType::Enum(id) => Some(GlobalId::User(id)),
+            _ => None,
+        })
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
 impl CatalogEntry {
     /// Reports the description of the datums produced by this catalog item.
     pub fn desc(&self) -> Result<&RelationDesc, failure::Error> {
@@ -171,6 +223,10 @@ impl CatalogEntry {
                 "catalog item '{}' is an index and so cannot be depended upon",
                 self.name
             ),
+            CatalogItem::Type(_) => bail!(
+                "catalog item '{}' is a type and so cannot be depended upon",
+                self.name
+            ),
         }
     }
 
@@ -199,6 +255,21 @@ impl CatalogEntry {
     pub fn used_by(&self) -> &[GlobalId] {
         &self.used_by
     }
+
+    /// Returns the comment attached to this catalog entry, if any.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Returns the tags attached to this catalog entry.
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.tags
+    }
+
+    /// Returns the reason this catalog entry has failed, if it has.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
 }
 
 impl Catalog {
@@ -260,6 +331,7 @@ impl Catalog {
         f(&mut catalog);
 
         let items = catalog.storage().load_items()?;
+        let quarantined = !catalog.storage().load_quarantined_items()?.is_empty();
         for (id, name, def) in items {
             // TODO(benesch): a better way of detecting when a view has depended
             // upon a non-existent logging view. This is fine for now because
@@ -268,6 +340,8 @@ impl Catalog {
             lazy_static! {
                 static ref LOGGING_ERROR: Regex =
                     Regex::new("catalog item 'mz_catalog.[^']*' does not exist").unwrap();
+                static ref MISSING_ERROR: Regex =
+                    Regex::new("catalog item '[^']*' does not exist").unwrap();
             }
             let item = match S::deserialize(&catalog, def) {
                 Ok(item) => item,
@@ -275,11 +349,38 @@ impl Catalog {
                     "catalog item '{}' depends on system logging, but logging is disabled",
                     name
                 ),
+                // Items are loaded in the order in which they were created,
+                // so an item's dependencies can only be missing if they
+                // were quarantined. The item cannot be loaded without them,
+                // so it is quarantined too.
+                Err(e) if quarantined && MISSING_ERROR.is_match(&e.to_string()) => {
+                    error!(
+                        "catalog item {} depends on a quarantined item; quarantining it",
+                        name
+                    );
+                    catalog
+                        .storage()
+                        .quarantine_item(id, &format!("depends on a quarantined item: {}", e))?;
+                    continue;
+                }
                 Err(e) => bail!("corrupt catalog: failed to deserialize item: {}", e),
             };
             catalog.insert_item(id, name, item);
         }
 
+        let comments = catalog.storage().load_comments()?;
+        for (id, comment) in comments {
+            if let Some(entry) = catalog.by_id.get_mut(&id) {
+                entry.comment = Some(comment);
+            }
+        }
+        let tags = catalog.storage().load_tags()?;
+        for (id, key, value) in tags {
+            if let Some(entry) = catalog.by_id.get_mut(&id) {
+                entry.tags.insert(key, value);
+            }
+        }
+
         Ok(catalog)
     }
 
@@ -375,6 +476,32 @@ impl Catalog {
         &self.by_id[id]
     }
 
+    pub fn try_get_by_id(&self, id: &GlobalId) -> Option<&CatalogEntry> {
+        self.by_id.get(id)
+    }
+
+    /// Marks the item identified by `id` as failed for the specified reason.
+    /// Items that depend on a failed item cannot be computed.
+    pub fn set_error(&mut self, id: GlobalId, error: String) {
+        if let Some(entry) = self.by_id.get_mut(&id) {
+            entry.error = Some(error);
+        }
+    }
+
+    /// Returns the failed item, if any, that the item identified by `id`
+    /// depends upon, either directly or transitively. A failed item is its own
+    /// failed dependency.
+    pub fn failed_dependency(&self, id: GlobalId) -> Option<&CatalogEntry> {
+        let entry = self.by_id.get(&id)?;
+        if entry.error.is_some() {
+            return Some(entry);
+        }
+        entry
+            .uses()
+            .into_iter()
+            .find_map(|id| self.failed_dependency(id))
+    }
+
     /// Returns an iterator over the name of each database in the catalog.
     pub fn databases(&self) -> impl Iterator<Item = &str> {
         self.by_name.keys().map(String::as_str)
@@ -432,6 +559,9 @@ impl Catalog {
             name,
             id,
             used_by: Vec::new(),
+            comment: None,
+            tags: BTreeMap::new(),
+            error: None,
         };
         for u in entry.uses() {
             match self.by_id.get_mut(&u) {
@@ -553,6 +683,14 @@ impl Catalog {
                 schema_name: String,
             },
             DropItem(GlobalId),
+            SetComment {
+                id: GlobalId,
+                comment: Option<String>,
+            },
+            SetTags {
+                id: GlobalId,
+                tags: Vec<(String, Option<String>)>,
+            },
         }
 
         let mut actions = Vec::with_capacity(ops.len());
@@ -619,6 +757,16 @@ impl Catalog {
                     tx.remove_item(id)?;
                     Action::DropItem(id)
                 }
+                Op::SetComment { id, comment } => {
+                    tx.set_comment(id, comment.as_deref())?;
+                    Action::SetComment { id, comment }
+                }
+                Op::SetTags { id, tags } => {
+                    for (key, value) in &tags {
+                        tx.set_tag(id, key, value.as_deref())?;
+                    }
+                    Action::SetTags { id, tags }
+                }
             })
         }
         tx.commit()?;
@@ -714,6 +862,25 @@ impl Catalog {
                     }
                     OpStatus::DroppedItem(metadata)
                 }
+
+                Action::SetComment { id, comment } => {
+                    self.by_id
+                        .get_mut(&id)
+                        .expect("catalog out of sync")
+                        .comment = comment;
+                    OpStatus::UpdatedItem(id)
+                }
+
+                Action::SetTags { id, tags } => {
+                    let entry = self.by_id.get_mut(&id).expect("catalog out of sync");
+                    for (key, value) in tags {
+                        match value {
+                            Some(value) => entry.tags.insert(key, value),
+                            None => entry.tags.remove(&key),
+                        };
+                    }
+                    OpStatus::UpdatedItem(id)
+                }
             })
             .collect())
     }
@@ -732,6 +899,93 @@ impl Catalog {
     pub fn dump(&self) -> String {
         serde_json::to_string(&self.by_name).expect("serialization cannot fail")
     }
+
+    /// Dumps the user-defined contents of the catalog as a script of SQL
+    /// statements that recreates them when executed.
+    ///
+    /// Databases and schemas are created first, if they do not already exist.
+    /// Items follow in order of increasing ID, which guarantees that each item
+    /// is created after all of the items that it depends upon. Each item is
+    /// immediately followed by its comment and tags, if it has any.
+    pub fn dump_sql(&self) -> String {
+        use sql_parser::ast::{Ident, ObjectName, ObjectType, SqlOption, Statement, Value};
+
+        let mut stmts = Vec::new();
+        for (database_name, database) in &self.by_name {
+            stmts.push(
+                Statement::CreateDatabase {
+                    name: Ident::with_quote('"', database_name.as_str()),
+                    if_not_exists: true,
+                }
+                .to_string(),
+            );
+            for schema_name in database.schemas.keys() {
+                stmts.push(
+                    Statement::CreateSchema {
+                        name: ObjectName(vec![
+                            Ident::with_quote('"', database_name.as_str()),
+                            Ident::with_quote('"', schema_name.as_str()),
+                        ]),
+                        if_not_exists: true,
+                    }
+                    .to_string(),
+                );
+            }
+        }
+        for entry in self.iter() {
+            if let GlobalId::User(_) = entry.id {
+                let (object_type, create_sql) = match entry.item() {
+                    CatalogItem::Source(source) => (ObjectType::Source, &source.create_sql),
+                    CatalogItem::View(view) => (ObjectType::View, &view.create_sql),
+                    CatalogItem::Sink(sink) => (ObjectType::Sink, &sink.create_sql),
+                    CatalogItem::Index(index) => (ObjectType::Index, &index.create_sql),
+                    CatalogItem::Type(typ) => (ObjectType::Type, &typ.create_sql),
+                };
+                stmts.push(create_sql.clone());
+
+                let mut name = vec![];
+                if let DatabaseSpecifier::Name(database) = &entry.name.database {
+                    name.push(Ident::with_quote('"', database.as_str()));
+                }
+                name.push(Ident::with_quote('"', entry.name.schema.as_str()));
+                name.push(Ident::with_quote('"', entry.name.item.as_str()));
+                let name = ObjectName(name);
+                if let Some(comment) = &entry.comment {
+                    stmts.push(
+                        Statement::Comment {
+                            object_type,
+                            name: name.clone(),
+                            comment: Some(comment.clone()),
+                        }
+                        .to_string(),
+                    );
+                }
+                if !entry.tags.is_empty() {
+                    stmts.push(
+                        Statement::AlterTags {
+                            object_type,
+                            name,
+                            tags: entry
+                                .tags
+                                .iter()
+                                .map(|(key, value)| SqlOption {
+                                    name: Ident::with_quote('"', key.as_str()),
+                                    value: Value::SingleQuotedString(value.clone()),
+                                })
+                                .collect(),
+                        }
+                        .to_string(),
+                    );
+                }
+            }
+        }
+        let mut out = String::new();
+        for stmt in stmts {
+            out.push_str(&stmt);
+            out.push_str(";\n");
+        }
+        out
+    }
 }
 
 impl fmt::Debug for Catalog {
@@ -779,6 +1033,17 @@ pub enum Op {
     /// IDs come from the output of `plan_remove`; otherwise consistency rules
     /// may be violated.
     DropItem(GlobalId),
+    /// Replaces the comment on the identified item. A comment of `None`
+    /// removes the existing comment.
+    SetComment {
+        id: GlobalId,
+        comment: Option<String>,
+    },
+    /// Sets tags on the identified item. A value of `None` removes the tag.
+    SetTags {
+        id: GlobalId,
+        tags: Vec<(String, Option<String>)>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -789,6 +1054,7 @@ pub enum OpStatus {
     DroppedDatabase,
     DroppedSchema,
     DroppedItem(CatalogEntry),
+    UpdatedItem(GlobalId),
 }
 
 /// A helper for resolving schema and item names within one database.
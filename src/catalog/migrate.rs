@@ -0,0 +1,224 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Upgrades of the on-disk catalog format.
+//!
+//! The catalog is the only state that Materialize persists. Besides the
+//! catalog items themselves, it records the timestamps that were assigned to
+//! source offsets, so upgrading the catalog upgrades all persisted state.
+//!
+//! The version of the on-disk format is stored in SQLite's `user_version`
+//! header field. Catalogs that were created before the format was versioned
+//! have version zero. Each time the catalog is opened, the migrations that
+//! the catalog has not yet seen are applied in order, in the same transaction
+//! that opens the catalog, so a failed upgrade leaves the catalog untouched.
+//! Catalogs written by a newer version of Materialize are refused, as this
+//! version cannot know what the newer migrations changed.
+//!
+//! Since format version two, each catalog item and timestamp binding carries
+//! a checksum of its contents, which is verified when the row is loaded. Rows
+//! that do not match their checksum are moved to a quarantine table rather
+//! than replayed, and are reported by `CHECK CATALOG`.
+
+use failure::bail;
+use log::info;
+use rusqlite::{params, Transaction};
+
+use crate::sql;
+
+/// A change to the on-disk format.
+struct Migration {
+    /// A short description of the change, for the logs.
+    description: &'static str,
+    /// The SQL that upgrades a catalog from the previous version.
+    sql: &'static str,
+    /// A function that completes the upgrade after `sql` runs, for changes
+    /// that SQL cannot express.
+    fixup: Option<fn(&Transaction) -> Result<(), failure::Error>>,
+}
+
+/// The migrations, in order. Migration `i` upgrades a catalog from version
+/// `i` to version `i + 1`.
+///
+/// Migrations must never be changed or removed once released, as catalogs
+/// created by older versions of Materialize rely on them. To change the
+/// format, append a new migration instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "add item comments and tags",
+        // Catalogs created before the format was versioned may already have
+        // these tables.
+        sql: "
+CREATE TABLE IF NOT EXISTS item_comments (
+    gid     blob PRIMARY KEY,
+    comment text NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS item_tags (
+    gid   blob NOT NULL,
+    key   text NOT NULL,
+    value text NOT NULL,
+    PRIMARY KEY (gid, key)
+);
+",
+        fixup: None,
+    },
+    Migration {
+        description: "add checksums to items and timestamp bindings",
+        // The quarantine tables have no constraints, as the rows they hold
+        // cannot be trusted to satisfy any.
+        sql: "
+ALTER TABLE items ADD COLUMN checksum integer NOT NULL DEFAULT 0;
+ALTER TABLE timestamps ADD COLUMN checksum integer NOT NULL DEFAULT 0;
+
+CREATE TABLE quarantined_items (
+    gid        blob,
+    schema_id  integer,
+    name       text,
+    definition blob,
+    checksum   integer,
+    reason     text NOT NULL
+);
+
+CREATE TABLE quarantined_timestamps (
+    sid       blob,
+    vid       blob,
+    timestamp integer,
+    offset    blob,
+    checksum  integer,
+    reason    text NOT NULL
+);
+",
+        fixup: Some(compute_checksums),
+    },
+    Migration {
+        description: "add partitions to timestamp bindings",
+        // A binding now covers one partition of its source instance, so the
+        // primary key must include the partition, which SQLite can only
+        // change by rebuilding the table. Existing bindings cover partition
+        // zero, the only partition that sources used to read, and keep their
+        // checksums.
+        sql: "
+CREATE TABLE timestamps_new (
+    sid          blob NOT NULL,
+    vid          blob NOT NULL,
+    timestamp    integer NOT NULL,
+    offset       blob NOT NULL,
+    checksum     integer NOT NULL DEFAULT 0,
+    partition_id integer NOT NULL DEFAULT 0,
+    PRIMARY KEY (sid, vid, partition_id, timestamp)
+);
+INSERT INTO timestamps_new (sid, vid, timestamp, offset, checksum)
+    SELECT sid, vid, timestamp, offset, checksum FROM timestamps;
+DROP TABLE timestamps;
+ALTER TABLE timestamps_new RENAME TO timestamps;
+
+CREATE TABLE quarantined_timestamps_new (
+    sid          blob,
+    vid          blob,
+    timestamp    integer,
+    offset       blob,
+    checksum     integer,
+    partition_id integer,
+    reason       text NOT NULL
+);
+INSERT INTO quarantined_timestamps_new
+    SELECT sid, vid, timestamp, offset, checksum, 0, reason FROM quarantined_timestamps;
+DROP TABLE quarantined_timestamps;
+ALTER TABLE quarantined_timestamps_new RENAME TO quarantined_timestamps;
+",
+        fixup: None,
+    },
+];
+
+/// The version of the on-disk format that this version of Materialize
+/// writes.
+const CURRENT_VERSION: i32 = MIGRATIONS.len() as i32;
+
+/// Upgrades the catalog that `tx` belongs to to [`CURRENT_VERSION`].
+///
+/// Returns an error if the catalog was written by a newer version of
+/// Materialize.
+pub(crate) fn migrate(tx: &Transaction) -> Result<(), failure::Error> {
+    let version: i32 = tx.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+    if version < 0 {
+        bail!("catalog has invalid format version {}", version);
+    } else if version > CURRENT_VERSION {
+        bail!(
+            "catalog has format version {}, but this version of materialized only \
+             supports versions up to {}; downgrading is not supported",
+            version,
+            CURRENT_VERSION
+        );
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!(
+            "migrating catalog to format version {}: {}",
+            i + 1,
+            migration.description
+        );
+        tx.execute_batch(migration.sql)?;
+        if let Some(fixup) = migration.fixup {
+            fixup(tx)?;
+        }
+    }
+    tx.execute(
+        &format!("PRAGMA user_version = {}", CURRENT_VERSION),
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Computes the checksums of the existing items and timestamp bindings, which
+/// were written before the catalog had checksums.
+fn compute_checksums(tx: &Transaction) -> Result<(), failure::Error> {
+    let items = tx
+        .prepare("SELECT rowid, gid, schema_id, name, definition FROM items")?
+        .query_and_then(params![], |row| -> Result<_, failure::Error> {
+            let rowid: i64 = row.get(0)?;
+            let gid: Vec<u8> = row.get(1)?;
+            let schema_id: i64 = row.get(2)?;
+            let name: String = row.get(3)?;
+            let definition: Vec<u8> = row.get(4)?;
+            Ok((
+                rowid,
+                sql::item_checksum(&gid, schema_id, &name, &definition),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (rowid, checksum) in items {
+        tx.execute(
+            "UPDATE items SET checksum = ? WHERE rowid = ?",
+            params![checksum, rowid],
+        )?;
+    }
+
+    let bindings = tx
+        .prepare("SELECT rowid, sid, vid, timestamp, offset FROM timestamps")?
+        .query_and_then(params![], |row| -> Result<_, failure::Error> {
+            let rowid: i64 = row.get(0)?;
+            let sid: Vec<u8> = row.get(1)?;
+            let vid: Vec<u8> = row.get(2)?;
+            let timestamp: Vec<u8> = row.get(3)?;
+            let offset: Vec<u8> = row.get(4)?;
+            Ok((
+                rowid,
+                sql::timestamp_checksum(&sid, &vid, &timestamp, &offset),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (rowid, checksum) in bindings {
+        tx.execute(
+            "UPDATE timestamps SET checksum = ? WHERE rowid = ?",
+            params![checksum, rowid],
+        )?;
+    }
+
+    Ok(())
+}
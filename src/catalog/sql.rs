@@ -7,19 +7,26 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashMap;
 use std::path::Path;
 
 use failure::bail;
+use log::error;
 use rusqlite::params;
 use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, Value, ValueRef};
 use serde::{Deserialize, Serialize};
 
-use expr::GlobalId;
+use dataflow_types::Timestamp;
+use expr::{GlobalId, SourceInstanceId};
 
+use crate::migrate;
 use crate::names::{DatabaseSpecifier, FullName};
 
 const APPLICATION_ID: i32 = 0x1854_47dc;
 
+/// The schema of a newly created catalog, which has format version zero.
+/// Later changes to the schema are made by the migrations in
+/// [`crate::migrate`].
 const SCHEMA: &str = "
 CREATE TABLE gid_alloc (
     next_gid integer NOT NULL
@@ -61,6 +68,17 @@ INSERT INTO schemas VALUES
     (3, 1, 'public');
 ";
 
+/// The rows removed by [`Connection::compact`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompactionStats {
+    /// The number of timestamp bindings that were superseded by later
+    /// bindings.
+    pub superseded_bindings: usize,
+    /// The number of timestamp bindings, comments, and tags that referred to
+    /// items that no longer exist.
+    pub orphaned_rows: usize,
+}
+
 #[derive(Debug)]
 pub struct Connection {
     inner: rusqlite::Connection,
@@ -87,6 +105,7 @@ impl Connection {
         } else {
             bail!("incorrect application_id in catalog");
         };
+        migrate::migrate(&tx)?;
         tx.commit()?;
 
         Ok(Connection { inner: sqlite })
@@ -119,30 +138,213 @@ impl Connection {
             .collect()
     }
 
-    pub fn load_items(&self) -> Result<Vec<(GlobalId, FullName, Vec<u8>)>, failure::Error> {
+    /// Loads the catalog items, in the order in which they were created.
+    ///
+    /// Items that do not match their checksum are moved to the
+    /// `quarantined_items` table and omitted.
+    pub fn load_items(&mut self) -> Result<Vec<(GlobalId, FullName, Vec<u8>)>, failure::Error> {
+        let mut items = vec![];
+        for row in self.read_items()? {
+            if row.valid {
+                items.push((serde_json::from_slice(&row.gid)?, row.name, row.definition));
+            } else {
+                error!(
+                    "catalog item {} does not match its checksum; quarantining it",
+                    row.name
+                );
+                self.quarantine("items", row.rowid, "checksum mismatch")?;
+            }
+        }
+        Ok(items)
+    }
+
+    fn read_items(&self) -> Result<Vec<ItemRow>, failure::Error> {
         self.inner
             .prepare(
-                "SELECT items.gid, databases.name, schemas.name, items.name, items.definition
+                "SELECT items.rowid, items.gid, items.schema_id, databases.name, schemas.name,
+                    items.name, items.definition, items.checksum
                 FROM items
                 JOIN schemas ON items.schema_id = schemas.id
                 JOIN databases ON schemas.database_id = databases.id
                 ORDER BY items.rowid",
             )?
             .query_and_then(params![], |row| -> Result<_, failure::Error> {
-                let id: SqlVal<GlobalId> = row.get(0)?;
-                let database: Option<String> = row.get(1)?;
-                let schema: String = row.get(2)?;
-                let item: String = row.get(3)?;
-                let definition: Vec<u8> = row.get(4)?;
-                Ok((
-                    id.0,
-                    FullName {
+                let rowid: i64 = row.get(0)?;
+                let gid: Vec<u8> = row.get(1)?;
+                let schema_id: i64 = row.get(2)?;
+                let database: Option<String> = row.get(3)?;
+                let schema: String = row.get(4)?;
+                let item: String = row.get(5)?;
+                let definition: Vec<u8> = row.get(6)?;
+                let checksum: i64 = row.get(7)?;
+                Ok(ItemRow {
+                    rowid,
+                    valid: checksum == item_checksum(&gid, schema_id, &item, &definition),
+                    gid,
+                    name: FullName {
                         database: DatabaseSpecifier::from(database),
                         schema,
                         item,
                     },
                     definition,
-                ))
+                })
+            })?
+            .collect()
+    }
+
+    /// Loads the names of the quarantined catalog items, along with the
+    /// reason that each was quarantined.
+    pub fn load_quarantined_items(&self) -> Result<Vec<(String, String)>, failure::Error> {
+        self.inner
+            .prepare("SELECT name, reason FROM quarantined_items")?
+            .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                let name: String = row.get(0)?;
+                let reason: String = row.get(1)?;
+                Ok((name, reason))
+            })?
+            .collect()
+    }
+
+    /// Moves the catalog item with the specified ID to the
+    /// `quarantined_items` table.
+    pub fn quarantine_item(&mut self, id: GlobalId, reason: &str) -> Result<(), failure::Error> {
+        let rowid: i64 = self.inner.query_row(
+            "SELECT rowid FROM items WHERE gid = ?",
+            params![SqlVal(id)],
+            |row| row.get(0),
+        )?;
+        self.quarantine("items", rowid, reason)
+    }
+
+    /// Records the binding of `timestamp` to `offset` in `partition` for the
+    /// source instance `id`.
+    pub fn insert_timestamp_binding(
+        &self,
+        id: SourceInstanceId,
+        partition: i32,
+        timestamp: Timestamp,
+        offset: i64,
+    ) -> Result<(), failure::Error> {
+        let sid = serde_json::to_vec(&id.sid)?;
+        let vid = serde_json::to_vec(&id.vid)?;
+        let timestamp = serde_json::to_vec(&timestamp)?;
+        let offset = serde_json::to_vec(&offset)?;
+        self.inner
+            .prepare_cached(
+                "INSERT INTO timestamps (sid, vid, timestamp, offset, checksum, partition_id)
+                VALUES (?, ?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                sid,
+                vid,
+                timestamp,
+                offset,
+                partitioned_timestamp_checksum(&sid, &vid, partition, &timestamp, &offset),
+                partition,
+            ])?;
+        Ok(())
+    }
+
+    /// Loads the timestamp bindings of the source instance `id`, as
+    /// `(partition, timestamp, offset)` triples in timestamp order.
+    ///
+    /// Bindings that do not match their checksum are moved to the
+    /// `quarantined_timestamps` table and omitted. The offsets that such a
+    /// binding covered are assigned the timestamp of the next binding
+    /// instead, or a new timestamp if there is no next binding.
+    pub fn load_timestamp_bindings(
+        &mut self,
+        id: SourceInstanceId,
+    ) -> Result<Vec<(i32, Timestamp, i64)>, failure::Error> {
+        let rows = self
+            .inner
+            .prepare(
+                "SELECT rowid, sid, vid, timestamp, offset, checksum, partition_id
+                FROM timestamps WHERE sid = ? AND vid = ?",
+            )?
+            .query_and_then(
+                params![SqlVal(&id.sid), SqlVal(&id.vid)],
+                |row| -> Result<_, failure::Error> {
+                    let rowid: i64 = row.get(0)?;
+                    let sid: Vec<u8> = row.get(1)?;
+                    let vid: Vec<u8> = row.get(2)?;
+                    let timestamp: Vec<u8> = row.get(3)?;
+                    let offset: Vec<u8> = row.get(4)?;
+                    let checksum: i64 = row.get(5)?;
+                    let partition: i32 = row.get(6)?;
+                    let expected =
+                        partitioned_timestamp_checksum(&sid, &vid, partition, &timestamp, &offset);
+                    if checksum == expected {
+                        Ok(Ok((
+                            partition,
+                            serde_json::from_slice(&timestamp)?,
+                            serde_json::from_slice(&offset)?,
+                        )))
+                    } else {
+                        Ok(Err(rowid))
+                    }
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut bindings = vec![];
+        for row in rows {
+            match row {
+                Ok(binding) => bindings.push(binding),
+                Err(rowid) => {
+                    error!(
+                        "timestamp binding for source instance {} does not match its \
+                         checksum; quarantining it",
+                        id
+                    );
+                    self.quarantine("timestamps", rowid, "checksum mismatch")?;
+                }
+            }
+        }
+        // Timestamps are stored as JSON blobs, which SQLite cannot order
+        // numerically.
+        bindings.sort_by_key(|(partition, timestamp, _)| (*timestamp, *partition));
+        Ok(bindings)
+    }
+
+    /// Moves the row of `table` with the specified rowid to the quarantine
+    /// table for `table`, where it is kept for inspection but otherwise
+    /// ignored.
+    fn quarantine(&mut self, table: &str, rowid: i64, reason: &str) -> Result<(), failure::Error> {
+        let tx = self.inner.transaction()?;
+        tx.execute(
+            &format!(
+                "INSERT INTO quarantined_{0} SELECT *, ? FROM {0} WHERE rowid = ?",
+                table
+            ),
+            params![reason, rowid],
+        )?;
+        tx.execute(
+            &format!("DELETE FROM {} WHERE rowid = ?", table),
+            params![rowid],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn load_comments(&self) -> Result<Vec<(GlobalId, String)>, failure::Error> {
+        self.inner
+            .prepare("SELECT gid, comment FROM item_comments")?
+            .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                let comment: String = row.get(1)?;
+                Ok((id.0, comment))
+            })?
+            .collect()
+    }
+
+    pub fn load_tags(&self) -> Result<Vec<(GlobalId, String, String)>, failure::Error> {
+        self.inner
+            .prepare("SELECT gid, key, value FROM item_tags")?
+            .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                let key: String = row.get(1)?;
+                let value: String = row.get(2)?;
+                Ok((id.0, key, value))
             })?
             .collect()
     }
@@ -175,6 +377,236 @@ impl Connection {
             inner: self.inner.transaction()?,
         })
     }
+
+    /// Removes the rows that are no longer needed to restore the catalog.
+    ///
+    /// The timestamper records a binding of a timestamp to an offset in each
+    /// partition each time it advances a source instance, and replays the
+    /// bindings when the instance is recreated. Of the bindings at or before
+    /// `since` for a partition of an instance, only the latest is kept:
+    /// replaying it places the earlier offsets at its timestamp, which no
+    /// query at or after `since` can distinguish from their original
+    /// timestamps.
+    ///
+    /// Timestamp bindings, comments, and tags that refer to an item for which
+    /// `exists` returns false are removed too. These can be left behind if
+    /// the server stops while dropping an item.
+    pub fn compact<F, G>(&mut self, exists: F, since: G) -> Result<CompactionStats, failure::Error>
+    where
+        F: Fn(GlobalId) -> bool,
+        G: Fn(SourceInstanceId) -> Timestamp,
+    {
+        let tx = self.inner.transaction()?;
+        let mut stats = CompactionStats::default();
+
+        // Timestamps are stored as JSON blobs, which SQLite cannot order
+        // numerically, so the superseded bindings are found here.
+        let bindings = tx
+            .prepare("SELECT rowid, sid, vid, timestamp, partition_id FROM timestamps")?
+            .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                let rowid: i64 = row.get(0)?;
+                let sid: SqlVal<GlobalId> = row.get(1)?;
+                let vid: SqlVal<GlobalId> = row.get(2)?;
+                let timestamp: SqlVal<Timestamp> = row.get(3)?;
+                let partition: i32 = row.get(4)?;
+                Ok((
+                    rowid,
+                    SourceInstanceId {
+                        sid: sid.0,
+                        vid: vid.0,
+                    },
+                    partition,
+                    timestamp.0,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut latest = HashMap::new();
+        for (_, id, partition, timestamp) in &bindings {
+            if *timestamp <= since(*id) {
+                let entry = latest.entry((*id, *partition)).or_insert(*timestamp);
+                if *timestamp > *entry {
+                    *entry = *timestamp;
+                }
+            }
+        }
+        for (rowid, id, partition, timestamp) in bindings {
+            if !exists(id.sid) || !exists(id.vid) {
+                stats.orphaned_rows += 1;
+            } else if latest
+                .get(&(id, partition))
+                .map_or(false, |latest| timestamp < *latest)
+            {
+                stats.superseded_bindings += 1;
+            } else {
+                continue;
+            }
+            tx.prepare_cached("DELETE FROM timestamps WHERE rowid = ?")?
+                .execute(params![rowid])?;
+        }
+
+        for table in &["item_comments", "item_tags"] {
+            let orphans = tx
+                .prepare(&format!("SELECT rowid, gid FROM {}", table))?
+                .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                    let rowid: i64 = row.get(0)?;
+                    let id: SqlVal<GlobalId> = row.get(1)?;
+                    Ok((rowid, id.0))
+                })?
+                .filter(|row| match row {
+                    Ok((_, id)) => !exists(*id),
+                    Err(_) => true,
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (rowid, _) in orphans {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE rowid = ?", table),
+                    params![rowid],
+                )?;
+                stats.orphaned_rows += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    /// Rewrites the catalog's database file to return the space freed by
+    /// [`Connection::compact`] to the operating system.
+    ///
+    /// The database cannot be read or written until the rewrite completes.
+    pub fn vacuum(&self) -> Result<(), failure::Error> {
+        Ok(self.inner.execute_batch("VACUUM")?)
+    }
+
+    /// Checks the consistency of the catalog's database, returning a
+    /// description of each problem found.
+    ///
+    /// In addition to checking the integrity of the database file itself, this
+    /// reports rows that refer to missing rows, including timestamp bindings,
+    /// comments, and tags whose item does not exist according to `exists`,
+    /// and an ID allocator that would reallocate the ID of an existing item.
+    pub fn check_integrity<F>(&self, exists: F) -> Result<Vec<String>, failure::Error>
+    where
+        F: Fn(GlobalId) -> bool,
+    {
+        let mut problems = vec![];
+
+        let messages = self
+            .inner
+            .prepare("PRAGMA integrity_check")?
+            .query_and_then(params![], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        problems.extend(messages.into_iter().filter(|m| m != "ok"));
+
+        let violations = self
+            .inner
+            .prepare("PRAGMA foreign_key_check")?
+            .query_and_then(params![], |row| -> Result<_, failure::Error> {
+                let table: String = row.get(0)?;
+                let rowid: Option<i64> = row.get(1)?;
+                let parent: String = row.get(2)?;
+                Ok(format!(
+                    "row {} of {} refers to a missing row of {}",
+                    rowid.unwrap_or(0),
+                    table,
+                    parent
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        problems.extend(violations);
+
+        let next_gid: i64 =
+            self.inner
+                .query_row("SELECT next_gid FROM gid_alloc", params![], |row| {
+                    row.get(0)
+                })?;
+        for row in self.read_items()? {
+            if !row.valid {
+                problems.push(format!("item {} does not match its checksum", row.name));
+                continue;
+            }
+            if let GlobalId::User(id) = serde_json::from_slice(&row.gid)? {
+                if id >= next_gid as u64 {
+                    problems.push(format!(
+                        "item u{} was allocated after the next ID, u{}",
+                        id, next_gid
+                    ));
+                }
+            }
+        }
+
+        let mut stmt = self.inner.prepare(
+            "SELECT sid, vid, timestamp, offset, checksum, partition_id FROM timestamps",
+        )?;
+        let bindings = stmt.query_and_then(params![], |row| -> Result<_, failure::Error> {
+            let sid: Vec<u8> = row.get(0)?;
+            let vid: Vec<u8> = row.get(1)?;
+            let timestamp: Vec<u8> = row.get(2)?;
+            let offset: Vec<u8> = row.get(3)?;
+            let checksum: i64 = row.get(4)?;
+            let partition: i32 = row.get(5)?;
+            Ok(checksum
+                == partitioned_timestamp_checksum(&sid, &vid, partition, &timestamp, &offset))
+        })?;
+        let mut corrupt_bindings = 0;
+        for valid in bindings {
+            if !valid? {
+                corrupt_bindings += 1;
+            }
+        }
+        if corrupt_bindings > 0 {
+            problems.push(format!(
+                "{} timestamp bindings do not match their checksums",
+                corrupt_bindings
+            ));
+        }
+
+        for (name, reason) in self.load_quarantined_items()? {
+            problems.push(format!("item {} is quarantined: {}", name, reason));
+        }
+        let quarantined_bindings: i64 = self.inner.query_row(
+            "SELECT count(*) FROM quarantined_timestamps",
+            params![],
+            |row| row.get(0),
+        )?;
+        if quarantined_bindings > 0 {
+            problems.push(format!(
+                "{} timestamp bindings are quarantined",
+                quarantined_bindings
+            ));
+        }
+
+        let mut stmt = self
+            .inner
+            .prepare("SELECT DISTINCT sid, vid FROM timestamps")?;
+        let instances = stmt.query_and_then(params![], |row| -> Result<_, failure::Error> {
+            let sid: SqlVal<GlobalId> = row.get(0)?;
+            let vid: SqlVal<GlobalId> = row.get(1)?;
+            Ok((sid.0, vid.0))
+        })?;
+        for instance in instances {
+            let (sid, vid) = instance?;
+            if !exists(sid) || !exists(vid) {
+                problems.push(format!(
+                    "timestamp bindings refer to missing source instance {}/{}",
+                    sid, vid
+                ));
+            }
+        }
+
+        for (id, _) in self.load_comments()? {
+            if !exists(id) {
+                problems.push(format!("comment refers to missing item {}", id));
+            }
+        }
+        for (id, key, _) in self.load_tags()? {
+            if !exists(id) {
+                problems.push(format!("tag {} refers to missing item {}", key, id));
+            }
+        }
+
+        Ok(problems)
+    }
 }
 
 pub struct Transaction<'a> {
@@ -251,12 +683,15 @@ impl Transaction<'_> {
         item_name: &str,
         item: &[u8],
     ) -> Result<(), failure::Error> {
+        let gid = serde_json::to_vec(&id)?;
+        let checksum = item_checksum(&gid, schema_id, item_name, item);
         match self
             .inner
             .prepare_cached(
-                "INSERT INTO items (gid, schema_id, name, definition) VALUES (?, ?, ?, ?)",
+                "INSERT INTO items (gid, schema_id, name, definition, checksum)
+                VALUES (?, ?, ?, ?, ?)",
             )?
-            .execute(params![SqlVal(&id), schema_id, item_name, item])
+            .execute(params![gid, schema_id, item_name, item, checksum])
         {
             Ok(_) => Ok(()),
             Err(err) if is_constraint_violation(&err) => {
@@ -299,6 +734,49 @@ impl Transaction<'_> {
         if n != 1 {
             bail!("item {} does not exist", id);
         }
+        self.inner
+            .prepare_cached("DELETE FROM item_comments WHERE gid = ?")?
+            .execute(params![SqlVal(id)])?;
+        self.inner
+            .prepare_cached("DELETE FROM item_tags WHERE gid = ?")?
+            .execute(params![SqlVal(id)])?;
+        Ok(())
+    }
+
+    pub fn set_comment(&self, id: GlobalId, comment: Option<&str>) -> Result<(), failure::Error> {
+        match comment {
+            Some(comment) => self
+                .inner
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO item_comments (gid, comment) VALUES (?, ?)",
+                )?
+                .execute(params![SqlVal(id), comment])?,
+            None => self
+                .inner
+                .prepare_cached("DELETE FROM item_comments WHERE gid = ?")?
+                .execute(params![SqlVal(id)])?,
+        };
+        Ok(())
+    }
+
+    pub fn set_tag(
+        &self,
+        id: GlobalId,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<(), failure::Error> {
+        match value {
+            Some(value) => self
+                .inner
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO item_tags (gid, key, value) VALUES (?, ?, ?)",
+                )?
+                .execute(params![SqlVal(id), key, value])?,
+            None => self
+                .inner
+                .prepare_cached("DELETE FROM item_tags WHERE gid = ? AND key = ?")?
+                .execute(params![SqlVal(id), key])?,
+        };
         Ok(())
     }
 
@@ -307,6 +785,56 @@ impl Transaction<'_> {
     }
 }
 
+/// A row of the `items` table.
+struct ItemRow {
+    rowid: i64,
+    /// Whether the row matches its checksum.
+    valid: bool,
+    gid: Vec<u8>,
+    name: FullName,
+    definition: Vec<u8>,
+}
+
+/// Computes the checksum of a row of the `items` table.
+pub fn item_checksum(gid: &[u8], schema_id: i64, name: &str, definition: &[u8]) -> i64 {
+    checksum(&[gid, &schema_id.to_le_bytes(), name.as_bytes(), definition])
+}
+
+/// Computes the checksum of a row of the `timestamps` table from the
+/// serialized values of its columns.
+pub fn timestamp_checksum(sid: &[u8], vid: &[u8], timestamp: &[u8], offset: &[u8]) -> i64 {
+    checksum(&[sid, vid, timestamp, offset])
+}
+
+/// Computes the checksum of a timestamp binding in `partition`. Bindings in
+/// partition zero are checksummed as they were before bindings had
+/// partitions, so that the checksums of existing bindings remain valid.
+pub fn partitioned_timestamp_checksum(
+    sid: &[u8],
+    vid: &[u8],
+    partition: i32,
+    timestamp: &[u8],
+    offset: &[u8],
+) -> i64 {
+    if partition == 0 {
+        timestamp_checksum(sid, vid, timestamp, offset)
+    } else {
+        checksum(&[sid, vid, timestamp, offset, &partition.to_le_bytes()])
+    }
+}
+
+/// Computes a CRC-32 checksum over `columns`. Each column is prefixed with
+/// its length, so that bytes cannot move between adjacent columns without
+/// changing the checksum.
+fn checksum(columns: &[&[u8]]) -> i64 {
+    let mut hasher = crc32fast::Hasher::new();
+    for column in columns {
+        hasher.update(&(column.len() as u64).to_le_bytes());
+        hasher.update(column);
+    }
+    i64::from(hasher.finalize())
+}
+
 fn is_constraint_violation(err: &rusqlite::Error) -> bool {
     match err {
         rusqlite::Error::SqliteFailure(err, _) => {
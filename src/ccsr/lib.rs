@@ -13,6 +13,7 @@
 
 use std::error::Error;
 use std::fmt;
+use std::time::Duration;
 
 use futures::executor::block_on;
 use reqwest::Url;
@@ -37,7 +38,18 @@ impl AsyncClient {
     /// Creates a new API client that will send requests to the schema registry
     /// at the provided URL.
     pub fn new(url: Url) -> Self {
-        let inner = reqwest::Client::builder()
+        Self::build(url, reqwest::Client::builder())
+    }
+
+    /// Creates a new API client like [`AsyncClient::new`], whose requests
+    /// fail with a transport error if the schema registry does not respond
+    /// within `timeout`.
+    pub fn with_timeout(url: Url, timeout: Duration) -> Self {
+        Self::build(url, reqwest::Client::builder().timeout(timeout))
+    }
+
+    fn build(url: Url, builder: reqwest::ClientBuilder) -> Self {
+        let inner = builder
             .redirect(reqwest::redirect::Policy::none())
             .build()
             .unwrap();
@@ -109,6 +121,14 @@ impl Client {
         Client { inner }
     }
 
+    /// Creates a new API client like [`Client::new`], whose requests fail
+    /// with a transport error if the schema registry does not respond within
+    /// `timeout`.
+    pub fn with_timeout(url: Url, timeout: Duration) -> Client {
+        let inner = AsyncClient::with_timeout(url, timeout);
+        Client { inner }
+    }
+
     /// Gets the schema with the associated ID.
     pub fn get_schema_by_id(&self, id: i32) -> Result<Schema, GetByIdError> {
         block_on(self.inner.get_schema_by_id(id))
@@ -8,6 +8,8 @@
 // by the Apache License, Version 2.0.
 
 use std::env;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
 
 use hyper::server::conn::AddrIncoming;
 use hyper::service;
@@ -227,6 +229,24 @@ fn test_server_errors() -> Result<(), failure::Error> {
     })
 }
 
+#[test]
+fn test_timeout() -> Result<(), failure::Error> {
+    // A registry that accepts connections but never responds.
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url: reqwest::Url = format!("http://{}", listener.local_addr()?).parse()?;
+    let client = Client::with_timeout(url, Duration::from_millis(100));
+
+    Runtime::new()?.enter(|| {
+        let start = Instant::now();
+        match client.get_schema_by_subject("foo") {
+            Err(GetBySubjectError::Transport(err)) if err.is_timeout() => (),
+            res => panic!("expected GetBySubjectError::Transport, got {:?}", res),
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+        Ok(())
+    })
+}
+
 fn start_server(runtime: &Runtime, status_code: StatusCode, body: &'static str) -> Client {
     let addr = runtime.enter(|| {
         let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
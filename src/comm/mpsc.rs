@@ -76,6 +76,17 @@ impl<D> Sender<D> {
         }
     }
 
+    /// Returns a sender for the same channel that connects to the receiver at
+    /// `addr`, rather than at the address of the switchboard that created the
+    /// channel.
+    ///
+    /// This is useful when the receiving process is reachable from the sending
+    /// process only at a different address than the one it knows itself by,
+    /// e.g., because the two are separated by a proxy.
+    pub fn with_addr(&self, addr: impl Into<Addr>) -> Sender<D> {
+        Sender::new(addr, self.uuid)
+    }
+
     /// Connects this `Sender` to the receiver so that transmissions can begin.
     ///
     /// The returned future resolves to a sink. Messages pushed into the sink
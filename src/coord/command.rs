@@ -11,8 +11,10 @@ use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 
-use dataflow_types::{PeekResponse, Update};
-use sql::Session;
+use dataflow_types::{PeekMessage, PeekMetadata, PeekResponse, Timestamp, Update};
+use futures::{Stream, StreamExt};
+use repr::RelationDesc;
+use sql::{ObjectType, Session};
 
 /// The requests the client can make of a [`Coordinator`](crate::Coordinator).
 #[derive(Debug)]
@@ -20,9 +22,16 @@ pub enum Command {
     /// Notify the coordinator of a new client session.
     Startup {
         session: Session,
+        conn_id: u32,
+        /// The channel on which to deliver the notifications for which the
+        /// session listens.
+        notify_tx: futures::channel::mpsc::UnboundedSender<Notification>,
         tx: futures::channel::oneshot::Sender<Response<Vec<StartupMessage>>>,
     },
 
+    /// Notify the coordinator that a client session has ended.
+    Terminate { conn_id: u32 },
+
     /// Parse the specified SQL into a prepared statement.
     ///
     /// The prepared statement is saved in the connection's [`sql::Session`]
@@ -49,6 +58,23 @@ pub enum Command {
     DumpCatalog {
         tx: futures::channel::oneshot::Sender<String>,
     },
+
+    /// Dump the user-defined contents of the catalog to a script of SQL
+    /// statements that recreates them.
+    DumpCatalogSql {
+        tx: futures::channel::oneshot::Sender<String>,
+    },
+
+    /// Compute the changes to a materialized source or view since a previous
+    /// read, from the history that its index retains.
+    PeekChanges {
+        /// The name of the source or view, resolved as in a new session.
+        name: String,
+        /// The timestamp of the previous read, or `None` to read the entire
+        /// contents of the source or view.
+        since: Option<Timestamp>,
+        tx: futures::channel::oneshot::Sender<Result<ChangesResponse, failure::Error>>,
+    },
 }
 
 #[derive(Debug)]
@@ -59,13 +85,73 @@ pub struct Response<T> {
 
 pub type RowsFuture = Pin<Box<dyn Future<Output = Result<PeekResponse, comm::Error>> + Send>>;
 
+/// Resolves to the response to a query, along with a description of how the
+/// dataflow workers computed it. The description is absent if the response
+/// did not require a peek, e.g., because the query was a constant.
+pub type PeekFuture =
+    Pin<Box<dyn Future<Output = Result<(PeekResponse, Option<PeekMetadata>), comm::Error>> + Send>>;
+
+/// Yields the batches of rows that answer a query, in their deterministic
+/// order, as they arrive from the dataflow workers, followed by a single
+/// [`PeekMessage::Done`] that describes how the workers computed them.
+pub type PeekStream = Pin<Box<dyn Stream<Item = Result<PeekMessage, comm::Error>> + Send>>;
+
+/// Collects the batches of rows that `stream` yields into a single response,
+/// for clients that have no use for the rows until all have arrived.
+pub fn collect_rows(mut stream: PeekStream) -> PeekFuture {
+    Box::pin(async move {
+        let mut rows = vec![];
+        while let Some(message) = stream.next().await {
+            match message? {
+                PeekMessage::Batch(PeekResponse::Rows(batch)) => rows.extend(batch),
+                PeekMessage::Batch(response) => return Ok((response, None)),
+                PeekMessage::Run(_) => unreachable!("peek streams merge the workers' runs"),
+                PeekMessage::Done(metadata) => {
+                    return Ok((PeekResponse::Rows(rows), Some(metadata)));
+                }
+            }
+        }
+        unreachable!("peek streams end with their metadata or an error")
+    })
+}
+
 #[derive(Debug)]
 pub enum StartupMessage {
     UnknownSessionDatabase,
 }
 
+/// A notification delivered to the sessions that listen on its channel.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Response from the queue to a `PeekChanges` command.
+pub struct ChangesResponse {
+    /// The timestamp as of which the changes are computed, which the client
+    /// should supply as `since` in its next request.
+    pub timestamp: Timestamp,
+    /// The description of the changed rows.
+    pub desc: RelationDesc,
+    /// Resolves to the net change to each row that changed, as a
+    /// [`PeekResponse::Changes`].
+    pub changes: RowsFuture,
+}
+
+impl fmt::Debug for ChangesResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ChangesResponse {{ timestamp: {}, desc: {:?}, changes: <rx> }}",
+            self.timestamp, self.desc
+        )
+    }
+}
+
 /// Response from the queue to an `Execute` command.
 pub enum ExecuteResponse {
+    AlteredObject(ObjectType),
     /// The current session has been taken out of transaction mode by COMMIT
     Commit,
     CreatedDatabase {
@@ -86,7 +172,9 @@ pub enum ExecuteResponse {
     CreatedTable {
         existed: bool,
     },
+    CreatedType,
     CreatedView,
+    Commented,
     Deleted(usize),
     DroppedDatabase,
     DroppedSchema,
@@ -95,11 +183,21 @@ pub enum ExecuteResponse {
     DroppedView,
     DroppedIndex,
     DroppedSink,
+    DroppedType,
     EmptyQuery,
     Inserted(usize),
+    ImportedTrace(usize),
+    InstalledDemo,
+    Listened,
+    /// The instance has resumed after being quiesced.
+    Resumed,
     /// The current session has been taken out of transaction mode by ROLLBACK
     Rollback,
-    SendRows(RowsFuture),
+    SendRows(PeekFuture),
+    /// Like `SendRows`, but the rows are yielded in batches as the workers
+    /// send them, rather than once all have arrived. Only produced for
+    /// queries whose rows need not be ordered or limited.
+    StreamRows(PeekStream),
     SetVariable {
         name: String,
     },
@@ -108,12 +206,17 @@ pub enum ExecuteResponse {
     Tailing {
         rx: comm::mpsc::Receiver<Vec<Update>>,
     },
+    Unlistened,
     Updated(usize),
 }
 
 impl fmt::Debug for ExecuteResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            ExecuteResponse::AlteredObject(o) => {
+                write!(f, "ExecuteResponse::AlteredObject({})", o)
+            }
+            ExecuteResponse::Commented => f.write_str("ExecuteResponse::Commented"),
             ExecuteResponse::CreatedDatabase { existed } => write!(
                 f,
                 "ExecuteResponse::CreatedDatabase {{ existed: {} }}",
@@ -142,6 +245,7 @@ impl fmt::Debug for ExecuteResponse {
                 "ExecuteResponse::CreatedTable {{ existed: {} }}",
                 existed
             ),
+            ExecuteResponse::CreatedType => f.write_str("ExecuteResponse::CreatedType"),
             ExecuteResponse::CreatedView => f.write_str("ExecuteResponse::CreatedView"),
             ExecuteResponse::Deleted(n) => write!(f, "ExecuteResponse::Deleted({})", n),
             ExecuteResponse::DroppedDatabase => f.write_str("ExecuteResponse::DroppedDatabase"),
@@ -150,17 +254,24 @@ impl fmt::Debug for ExecuteResponse {
             ExecuteResponse::DroppedSink => f.write_str("ExecuteResponse::DroppedSink"),
             ExecuteResponse::DroppedSource => f.write_str("ExecuteResponse::DroppedSource"),
             ExecuteResponse::DroppedTable => f.write_str("ExecuteResponse::DroppedTable"),
+            ExecuteResponse::DroppedType => f.write_str("ExecuteResponse::DroppedType"),
             ExecuteResponse::DroppedView => f.write_str("ExecuteResponse::DroppedView"),
             ExecuteResponse::EmptyQuery => f.write_str("ExecuteResponse::EmptyQuery"),
             ExecuteResponse::Commit => f.write_str("ExecuteResponse::Commit"),
+            ExecuteResponse::Resumed => f.write_str("ExecuteResponse::Resumed"),
             ExecuteResponse::Rollback => f.write_str("ExecuteResponse::Rollback"),
             ExecuteResponse::Inserted(n) => write!(f, "ExecuteResponse::Inserted({})", n),
+            ExecuteResponse::ImportedTrace(n) => write!(f, "ExecuteResponse::ImportedTrace({})", n),
+            ExecuteResponse::InstalledDemo => f.write_str("ExecuteResponse::InstalledDemo"),
+            ExecuteResponse::Listened => f.write_str("ExecuteResponse::Listened"),
             ExecuteResponse::SendRows(_) => write!(f, "ExecuteResponse::SendRows(<rx>)"),
+            ExecuteResponse::StreamRows(_) => write!(f, "ExecuteResponse::StreamRows(<rx>)"),
             ExecuteResponse::SetVariable { name } => {
                 write!(f, "ExecuteResponse::SetVariable({})", name)
             }
             ExecuteResponse::StartTransaction => f.write_str("ExecuteResponse::StartTransaction"),
             ExecuteResponse::Tailing { rx: _ } => f.write_str("ExecuteResponse::Tailing"),
+            ExecuteResponse::Unlistened => f.write_str("ExecuteResponse::Unlistened"),
             ExecuteResponse::Updated(n) => write!(f, "ExecuteResponse::Updated({})", n),
         }
     }
@@ -16,12 +16,13 @@
 //! which the maintained view will be correct, as any timestamps in advance of the frontier
 //! must accumulate to the same value as would an un-compacted trace.
 
-use std::collections::{BTreeMap, HashMap};
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::iter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use failure::bail;
 use futures::executor::block_on;
@@ -29,37 +30,113 @@ use futures::future::FutureExt;
 use futures::future::{self, TryFutureExt};
 use futures::sink::SinkExt;
 use futures::stream::{self, StreamExt, TryStreamExt};
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
 use timely::progress::frontier::{Antichain, AntichainRef, MutableAntichain};
 use timely::progress::ChangeBatch;
+use url::Url;
 
-use catalog::names::{DatabaseSpecifier, FullName};
+use catalog::names::{DatabaseSpecifier, FullName, PartialName};
+use catalog::sql::CompactionStats;
 use catalog::{Catalog, CatalogItem};
 use dataflow::logging::materialized::MaterializedEvent;
-use dataflow::{SequencedCommand, WorkerFeedback, WorkerFeedbackWithMeta};
+use dataflow::{Inventory, SequencedCommand, WorkerFeedback, WorkerFeedbackWithMeta};
 use dataflow_types::logging::LoggingConfig;
 use dataflow_types::{
-    DataflowDesc, IndexDesc, PeekResponse, PeekWhen, SinkConnector, TailSinkConnector, Timestamp,
-    Update,
+    Consistency, DataEncoding, DataflowDesc, ExternalSourceConnector, IndexDesc, PeekMessage,
+    PeekMetadata, PeekResponse, PeekWhen, RowSetFinishing, SinkConnector, SourceConnector,
+    TailSinkConnector, Timestamp, Update, PEEK_BATCH_SIZE,
 };
-use expr::transform::Optimizer;
-use expr::{EvalEnv, GlobalId, Id, IdHumanizer, RelationExpr, ScalarExpr, SourceInstanceId};
-use futures::Stream;
+use expr::transform::{OptimizedRelationExpr, Optimizer};
+use expr::{
+    BinaryFunc, EvalEnv, GlobalId, Id, IdHumanizer, RelationExpr, ScalarExpr, SourceInstanceId,
+};
+use futures::{Future, Stream};
 use ore::thread::JoinHandleExt;
 use ore::{collections::CollectionExt, future::MaybeFuture};
-use repr::{ColumnName, Datum, RelationDesc, RelationType, Row};
+use repr::{
+    ColumnName, ColumnType, Datum, Interval, RelationDesc, RelationType, Row, RowPacker, ScalarType,
+};
 use sql::{MutationKind, ObjectType, Plan, Session};
 use sql::{Params, PreparedStatement};
 
+use crate::command_log::CommandLog;
+use crate::demo;
+use crate::hook::{self, PlanContext, PlanKind};
 use crate::persistence::SqlSerializer;
-use crate::timestamp::{TimestampChannel, TimestampConfig, TimestampMessage, Timestamper};
-use crate::{Command, ExecuteResponse, Response, StartupMessage};
+use crate::timestamp::{
+    LocalInputTimestamps, TimestampChannel, TimestampConfig, TimestampMessage, Timestamper,
+};
+use crate::trace;
+use crate::{
+    collect_rows, ChangesResponse, Command, ExecuteResponse, Notification, Response, StartupMessage,
+};
 
 type ClientTx = futures::channel::oneshot::Sender<Response<ExecuteResponse>>;
 
+/// How often the progress of an index that is catching up with its inputs is
+/// reported to `mz_view_backfills`.
+const BACKFILL_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the skew of each source with event-time timestamps is reported
+/// to `mz_source_skew`.
+const SKEW_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far a source's event times may run ahead of or behind the wall clock,
+/// or behind the event times of the most advanced source, before a warning is
+/// logged.
+const MAX_SOURCE_SKEW_MS: i64 = 60_000;
+
+/// How long to wait for a schema registry while validating the schemas of
+/// sources at startup.
+const SCHEMA_VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref VIEW_LAG_MS: IntGaugeVec = register_int_gauge_vec!(
+        "mz_view_lag_ms",
+        "how far the view trails the wall clock, for views with a freshness objective",
+        &["view_id"]
+    )
+    .unwrap();
+    static ref SOURCE_SKEW_MS: IntGaugeVec = register_int_gauge_vec!(
+        "mz_source_skew_ms",
+        "how far the event times of the source run ahead of (or, if negative, behind) the wall clock",
+        &["source_id"]
+    )
+    .unwrap();
+    static ref VIEW_FRESHNESS_VIOLATIONS: IntCounterVec = register_int_counter_vec!(
+        "mz_view_freshness_violations_total",
+        "the number of times the view has fallen further behind the wall clock than its max_lag",
+        &["view_id"]
+    )
+    .unwrap();
+    static ref VIEW_DIVERGENCES: IntCounterVec = register_int_counter_vec!(
+        "mz_view_divergences_total",
+        "the number of rows in which the view has differed from a reference evaluation of its definition",
+        &["view_id"]
+    )
+    .unwrap();
+    static ref ARRANGEMENT_RECORDS: IntGaugeVec = register_int_gauge_vec!(
+        "mz_arrangement_records",
+        "the number of updates held in the arrangement of the index, across all workers",
+        &["index_id"]
+    )
+    .unwrap();
+    static ref ARRANGEMENT_BYTES: IntGaugeVec = register_int_gauge_vec!(
+        "mz_arrangement_bytes",
+        "the approximate number of bytes held in the arrangements of the index's dataflow, \
+         across all workers",
+        &["index_id"]
+    )
+    .unwrap();
+}
+
 enum Message {
     Command(Command),
     Worker(WorkerFeedbackWithMeta),
     PlanReady(Session, ClientTx, Result<Plan, failure::Error>, u32),
+    AdvanceLocalInputs,
     Shutdown,
 }
 
@@ -69,11 +146,32 @@ where
 {
     pub switchboard: comm::Switchboard<C>,
     pub num_timely_workers: usize,
+    /// The name and number of workers of each compute cluster besides the
+    /// default cluster.
+    pub clusters: &'a [(String, usize)],
     pub symbiosis_url: Option<&'a str>,
     pub logging: Option<&'a LoggingConfig>,
     pub data_directory: Option<&'a Path>,
     pub executor: &'a tokio::runtime::Handle,
     pub timestamp: Option<TimestampConfig>,
+    /// The interval at which to remove history that is no longer needed
+    /// from the catalog, or `None` to remove it only on request.
+    pub catalog_compaction_interval: Option<Duration>,
+    /// How long a peek may wait for its timestamp to become available before
+    /// it fails, or `None` to wait indefinitely.
+    pub peek_timeout: Option<Duration>,
+    /// The number of bytes that the arrangements of all dataflows may hold
+    /// before new temporary dataflows are refused, or `None` for no limit.
+    pub arrangement_memory_limit: Option<usize>,
+    /// The interval at which to compare each materialized view with a
+    /// reference evaluation of its definition, or `None` to compare views
+    /// only on request.
+    pub consistency_check_interval: Option<Duration>,
+    /// How to assign timestamps to the rows written to tables.
+    pub local_input_timestamps: LocalInputTimestamps,
+    /// The file to which to append each executed statement, or `None` to not
+    /// record executed statements.
+    pub command_log: Option<&'a Path>,
 }
 
 /// Glues the external world to the Timely workers.
@@ -82,8 +180,12 @@ where
     C: comm::Connection,
 {
     switchboard: comm::Switchboard<C>,
+    /// The command channel to the default cluster.
     broadcast_tx: comm::broadcast::Sender<SequencedCommand>,
     num_timely_workers: usize,
+    /// Maps (name of compute cluster) -> (command channel and size), for
+    /// clusters besides the default cluster.
+    clusters: HashMap<String, Cluster>,
     optimizer: Optimizer,
     catalog: Catalog,
     symbiosis: Option<symbiosis::Postgres>,
@@ -91,6 +193,9 @@ where
     views: HashMap<GlobalId, ViewState>,
     /// Maps (global Id of arrangement) -> (frontier information)
     indexes: HashMap<GlobalId, IndexState>,
+    /// Maps (global Id of sink) -> (frontier up to which the sink has emitted
+    /// its output)
+    sink_frontiers: HashMap<GlobalId, MutableAntichain<Timestamp>>,
     since_updates: Vec<(GlobalId, Vec<Timestamp>)>,
     /// For each connection running a TAIL command, the name of the dataflow
     /// that is servicing the TAIL. A connection can only run one TAIL at a
@@ -100,9 +205,63 @@ where
     /// Instance count: number of times sources have been instantiated in views. This is used
     /// to associate each new instance of a source with a unique instance id (iid)
     local_input_time: Timestamp,
+    local_input_timestamps: LocalInputTimestamps,
     log: bool,
     executor: Option<tokio::runtime::Handle>,
-    feedback_rx: Option<comm::mpsc::Receiver<WorkerFeedbackWithMeta>>,
+    /// Feedback from the workers of each cluster.
+    feedback_rxs: Vec<comm::mpsc::Receiver<WorkerFeedbackWithMeta>>,
+    /// Rotates peeks among the replicas of an index that can serve them.
+    replica_rotation: usize,
+    catalog_compaction_interval: Option<Duration>,
+    last_catalog_compaction: Instant,
+    peek_timeout: Option<Duration>,
+    arrangement_memory_limit: Option<usize>,
+    consistency_check_interval: Option<Duration>,
+    last_consistency_check: Instant,
+    /// Maps (connection ID) -> (channels on which the connection listens)
+    listeners: HashMap<u32, Listener>,
+    /// The time at which the instance is quiesced, if it is. Neither sources
+    /// nor tables advance past this time until the instance resumes.
+    quiesced: Option<Timestamp>,
+    /// Maps (instance of a source with event-time timestamps) -> (latest
+    /// timestamp assigned to its records, if any)
+    event_time_sources: HashMap<SourceInstanceId, Option<Timestamp>>,
+    /// Maps (instance of a Kafka source) -> (number of workers, across all
+    /// clusters, that have instantiated it). Every worker that instantiates a
+    /// source reports it, and replicas of an index on other clusters
+    /// instantiate the same source instances, but the timestamper must track
+    /// each instance just once, until the last worker drops it.
+    source_instance_workers: HashMap<SourceInstanceId, usize>,
+    /// Maps (global Id of source) -> (skew last reported to `mz_source_skew`)
+    source_skews: HashMap<GlobalId, SourceSkew>,
+    last_skew_report: Instant,
+    /// The next identifier to allocate in the transient namespace.
+    transient_id_counter: u64,
+    command_log: Option<CommandLog>,
+    /// The `FLUSH SINK` commands that are waiting for sinks to catch up.
+    pending_flushes: Vec<PendingFlush>,
+}
+
+/// A `FLUSH SINK` or `FLUSH SINKS` command that is waiting for sinks to emit
+/// their output for the input that was available when it was issued.
+struct PendingFlush {
+    /// The name and ID of each sink, and the frontier that its input had
+    /// reached when the command was issued.
+    sinks: Vec<(String, GlobalId, Antichain<Timestamp>)>,
+    tx: futures::channel::oneshot::Sender<(PeekResponse, Option<PeekMetadata>)>,
+}
+
+/// A client session that can receive notifications.
+struct Listener {
+    tx: futures::channel::mpsc::UnboundedSender<Notification>,
+    channels: HashSet<String>,
+}
+
+/// A compute cluster: a set of Timely workers, separate from those of other
+/// clusters, that maintains the dataflows assigned to it.
+struct Cluster {
+    broadcast_tx: comm::broadcast::Sender<SequencedCommand>,
+    num_workers: usize,
 }
 
 impl<C> Coordinator<C>
@@ -173,8 +332,11 @@ where
                                 view_name,
                                 &log_src.schema(),
                                 &log_src.index_by(),
+                                None,
                             ),
                             eval_env: EvalEnv::default(),
+                            cluster: None,
+                            replicas: vec![],
                         }),
                     );
                 }
@@ -193,6 +355,7 @@ where
                             view,
                             replace,
                             materialize,
+                            cluster: _,
                         }))) => {
                             assert!(replace.is_none());
                             assert!(materialize);
@@ -202,6 +365,10 @@ where
                                 expr: optimizer.optimize(view.expr, catalog.indexes(), &eval_env),
                                 eval_env,
                                 desc: view.desc,
+                                refresh_interval: view.refresh_interval,
+                                changes_only: view.changes_only,
+                                emit_final_after: view.emit_final_after,
+                                max_lag: view.max_lag,
                             };
                             let view_name = FullName {
                                 database: DatabaseSpecifier::Ambient,
@@ -214,6 +381,7 @@ where
                                 view_name.clone(),
                                 &view,
                                 log_view.id,
+                                None,
                             );
                             catalog.insert_item(log_view.id, view_name, CatalogItem::View(view));
                             catalog.insert_item(
@@ -240,24 +408,67 @@ where
         let executor = config.executor;
         executor.enter(move || {
             let logging = config.logging;
+            let mut feedback_rxs = vec![];
             let (tx, rx) = config.switchboard.mpsc_limited(config.num_timely_workers);
             broadcast(&mut broadcast_tx, SequencedCommand::EnableFeedback(tx));
+            feedback_rxs.push(rx);
+            let mut clusters = HashMap::new();
+            for (name, num_workers) in config.clusters {
+                let mut broadcast_tx = config
+                    .switchboard
+                    .broadcast_tx(dataflow::ClusterBroadcastToken(name.clone()));
+                let (tx, rx) = config.switchboard.mpsc_limited(*num_workers);
+                broadcast(&mut broadcast_tx, SequencedCommand::EnableFeedback(tx));
+                feedback_rxs.push(rx);
+                clusters.insert(
+                    name.clone(),
+                    Cluster {
+                        broadcast_tx,
+                        num_workers: *num_workers,
+                    },
+                );
+            }
             let mut coord = Self {
                 switchboard: config.switchboard,
                 broadcast_tx,
                 num_timely_workers: config.num_timely_workers,
+                clusters,
                 optimizer: Default::default(),
                 catalog,
                 symbiosis,
                 views: HashMap::new(),
                 indexes: HashMap::new(),
+                sink_frontiers: HashMap::new(),
                 since_updates: Vec::new(),
                 active_tails: HashMap::new(),
-                local_input_time: 1,
+                local_input_time: match config.local_input_timestamps {
+                    LocalInputTimestamps::Counter => 1,
+                    LocalInputTimestamps::WallClock { granularity } => wall_clock_tick(granularity),
+                },
+                local_input_timestamps: config.local_input_timestamps,
                 log: config.logging.is_some(),
                 executor: Some(config.executor.clone()),
                 timestamp_config: config.timestamp,
-                feedback_rx: Some(rx),
+                feedback_rxs,
+                replica_rotation: 0,
+                catalog_compaction_interval: config.catalog_compaction_interval,
+                last_catalog_compaction: Instant::now(),
+                peek_timeout: config.peek_timeout,
+                arrangement_memory_limit: config.arrangement_memory_limit,
+                consistency_check_interval: config.consistency_check_interval,
+                last_consistency_check: Instant::now(),
+                listeners: HashMap::new(),
+                quiesced: None,
+                event_time_sources: HashMap::new(),
+                source_instance_workers: HashMap::new(),
+                source_skews: HashMap::new(),
+                last_skew_report: Instant::now(),
+                transient_id_counter: 1,
+                command_log: match config.command_log {
+                    Some(path) => Some(CommandLog::open(path)?),
+                    None => None,
+                },
+                pending_flushes: Vec::new(),
             };
 
             let catalog_entries: Vec<_> = coord
@@ -265,25 +476,69 @@ where
                 .iter()
                 .map(|entry| (entry.id(), entry.name().clone(), entry.item().clone()))
                 .collect();
+            let mut unreachable_registries = HashSet::new();
             for (id, name, item) in catalog_entries {
+                // An item assigned to a cluster that is no longer configured
+                // is marked as failed, rather than rendered elsewhere, so
+                // that it cannot interfere with the workloads of other
+                // clusters.
+                let cluster = match &item {
+                    CatalogItem::Index(index) => index.cluster.as_ref(),
+                    CatalogItem::Sink(sink) => sink.cluster.as_ref(),
+                    _ => None,
+                };
+                if let Some(cluster) = cluster {
+                    if !coord.clusters.contains_key(cluster) {
+                        coord
+                            .catalog
+                            .set_error(id, format!("cluster '{}' does not exist", cluster));
+                    }
+                }
                 match item {
                     //currently catalog item rebuild assumes that sinks and
                     //indexes are always built individually and does not store information
                     //about how it was built. If we start building multiple sinks and/or indexes
                     //using a single dataflow, we have to make sure the rebuild process re-runs
                     //the same multiple-build dataflow.
-                    CatalogItem::Source(_) => {
+                    CatalogItem::Source(source) => {
                         coord.views.insert(id, ViewState::new(false, vec![]));
+                        // A source whose upstream schema has changed
+                        // incompatibly, or whose plugin is no longer
+                        // registered, is marked as failed, rather than
+                        // rendered, so that it and the items that depend on
+                        // it fail without taking down the whole instance.
+                        if let Err(e) = validate_source_schema(&source, &mut unreachable_registries)
+                            .and_then(|()| validate_source_plugin(&source))
+                        {
+                            error!("source {} failed: {}", name, e);
+                            coord.catalog.set_error(id, e.to_string());
+                        }
                     }
                     CatalogItem::View(view) => {
                         coord.insert_view(id, &view);
                     }
-                    CatalogItem::Sink(sink) => {
-                        coord.create_sink_dataflow(name.to_string(), id, sink);
-                    }
+                    CatalogItem::Sink(sink) => match coord.check_available(&[id]) {
+                        Ok(()) => match validate_sink_plugin(&sink) {
+                            Ok(()) => coord.create_sink_dataflow(name.to_string(), id, sink),
+                            Err(e) => {
+                                error!("sink {} failed: {}", name, e);
+                                coord.catalog.set_error(id, e.to_string());
+                            }
+                        },
+                        Err(e) => error!("not starting sink {}: {}", name, e),
+                    },
                     CatalogItem::Index(index) => match id {
-                        GlobalId::User(_) => {
-                            coord.create_index_dataflow(name.to_string(), id, index)
+                        GlobalId::User(_) => match coord.check_available(&[id]) {
+                            Ok(()) => {
+                                let name = name.to_string();
+                                let replicas = coord.allocate_index_replicas(&name, &index)?;
+                                coord.create_index_dataflow(name.clone(), id, index.clone());
+                                coord.create_index_replicas(&name, id, &index, replicas);
+                            }
+                            Err(e) => error!("not building index {}: {}", name, e),
+                        },
+                        GlobalId::Transient(_) => {
+                            unreachable!("transient IDs are never stored in the catalog")
                         }
                         GlobalId::System(_) => {
                             // TODO(benesch): a smarter way to determine whether this system index
@@ -301,6 +556,7 @@ where
                             }
                         }
                     },
+                    CatalogItem::Type(_) => (),
                 }
             }
 
@@ -338,6 +594,12 @@ where
                     }
                 }
             }
+
+            // Verify that recovery installed exactly the dataflow objects that
+            // the catalog calls for. Commands are processed in order, so the
+            // workers' reports reflect the state after bootstrapping.
+            coord.broadcast_all(SequencedCommand::ReportInventory);
+
             Ok(coord)
         })
     }
@@ -363,16 +625,33 @@ where
             .take()
             .expect("serve called twice on coordinator")
             .enter(|| {
-                let streams: Vec<Box<dyn Stream<Item = Result<Message, comm::Error>> + Unpin>> = vec![
+                let mut streams: Vec<Box<dyn Stream<Item = Result<Message, comm::Error>> + Unpin>> = vec![
                                         Box::new(
                                             cmd_rx
                                                 .map(Message::Command)
                                                 .chain(stream::once(future::ready(Message::Shutdown)))
                                                 .map(Ok),
                                         ),
-                                        Box::new(self.feedback_rx.take().unwrap().map_ok(Message::Worker)),
+                                        Box::new(stream::select_all(self.feedback_rxs.drain(..)).map_ok(Message::Worker)),
                                     ];
 
+                // Advance idle tables with the wall clock, so that the
+                // frontiers of the views that read them, and thus peeks and
+                // TAILs, keep up with real time. The ticks stop once
+                // `stop_ticks_tx` is dropped, so that they do not hold up the
+                // final drain of the worker messages.
+                let (stop_ticks_tx, stop_ticks_rx) = futures::channel::oneshot::channel::<()>();
+                if let LocalInputTimestamps::WallClock { granularity } = self.local_input_timestamps {
+                    streams.push(Box::new(
+                        stream::select(
+                            tokio::time::interval(Duration::from_millis(granularity_millis(granularity))).map(|_| Some(Message::AdvanceLocalInputs)),
+                            stop_ticks_rx.into_stream().map(|_| None),
+                        )
+                        .take_while(|msg| future::ready(msg.is_some()))
+                        .map(|msg| Ok(msg.unwrap())),
+                    ));
+                }
+
                 let mut messages = stream::select_all(streams);
 
                while let Some(msg) = block_on(messages.next()) {
@@ -380,22 +659,24 @@ where
                     while let Ok(update) = source_rx.try_recv() {
                         match update {
                             TimestampMessage::BatchedUpdate(timestamp, updates) => {
-                                for (id, offset) in updates {
-                                    broadcast(
-                                        &mut self.broadcast_tx,
+                                for (id, partition, offset) in updates {
+                                    self.observe_source_timestamp(id, timestamp);
+                                    self.broadcast_all(
                                         SequencedCommand::AdvanceSourceTimestamp {
                                             id,
+                                            partition,
                                             timestamp,
                                             offset,
                                         },
                                     );
                                 }
                             }
-                            TimestampMessage::Update(id, timestamp, offset) => {
-                                broadcast(
-                                    &mut self.broadcast_tx,
+                            TimestampMessage::Update(id, partition, timestamp, offset) => {
+                                self.observe_source_timestamp(id, timestamp);
+                                self.broadcast_all(
                                     SequencedCommand::AdvanceSourceTimestamp {
                                         id,
+                                        partition,
                                         timestamp,
                                         offset,
                                     },
@@ -408,8 +689,17 @@ where
                     match msg.expect("coordinator message receiver failed") {
                         Message::Command(Command::Startup {
                             session,
+                            conn_id,
+                            notify_tx,
                             tx,
                         }) => {
+                            self.listeners.insert(
+                                conn_id,
+                                Listener {
+                                    tx: notify_tx,
+                                    channels: HashSet::new(),
+                                },
+                            );
                             let mut messages = vec![];
                             if self.catalog.database_resolver(session.database()).is_err() {
                                 messages.push(StartupMessage::UnknownSessionDatabase);
@@ -422,13 +712,16 @@ where
                             conn_id,
                             tx,
                         }) => {
-                            let result = self.handle_begin_execute(session, portal_name, tx);
+                            let result =
+                                self.handle_begin_execute(session, portal_name, conn_id, tx);
                             match result {
                                 MaybeFuture::Immediate(val) => {
                                     let (mut session, tx, result) = val.unwrap();
-                                    let result = result.and_then(|plan| {
-                                        self.sequence_plan(&mut session, plan, conn_id)
-                                    });
+                                    let result = result
+                                        .and_then(|plan| {
+                                            self.sequence_plan(&mut session, plan, conn_id)
+                                        })
+                                        .map_err(|e| classify(e, dataflow_types::Error::Plan));
                                     let _ = tx.send(Response { result, session });
                                 }
                                 MaybeFuture::Future(fut) => {
@@ -451,7 +744,8 @@ where
 
                         Message::PlanReady(mut session, tx, result, conn_id) => {
                             let result = result
-                                .and_then(|plan| self.sequence_plan(&mut session, plan, conn_id));
+                                .and_then(|plan| self.sequence_plan(&mut session, plan, conn_id))
+                                .map_err(|e| classify(e, dataflow_types::Error::Plan));
 
                             let _ = tx.send(Response { result, session });
                         }
@@ -462,10 +756,16 @@ where
                             mut session,
                             tx,
                         }) => {
-                            let result = self.handle_parse(&mut session, name, sql);
+                            let result = self
+                                .handle_parse(&mut session, name, sql)
+                                .map_err(|e| classify(e, dataflow_types::Error::Plan));
                             let _ = tx.send(Response { result, session });
                         }
 
+                        Message::Command(Command::Terminate { conn_id }) => {
+                            self.listeners.remove(&conn_id);
+                        }
+
                         Message::Command(Command::CancelRequest { conn_id }) => {
                             self.sequence_cancel(conn_id);
                         }
@@ -474,6 +774,16 @@ where
                             let _ = tx.send(self.catalog.dump());
                         }
 
+                        Message::Command(Command::DumpCatalogSql { tx }) => {
+                            let _ = tx.send(self.catalog.dump_sql());
+                        }
+
+                        Message::Command(Command::PeekChanges { name, since, tx }) => {
+                            let _ = tx.send(self.sequence_peek_changes(&name, since));
+                        }
+
+                        Message::AdvanceLocalInputs => self.advance_local_inputs(),
+
                         Message::Shutdown => {
                             ts_tx.send(TimestampMessage::Shutdown).unwrap();
                             self.shutdown();
@@ -493,27 +803,59 @@ where
                         Message::Worker(WorkerFeedbackWithMeta {
                             worker_id: _,
                             message: WorkerFeedback::DroppedSource(source_id)}) => {
-                            // Notify timestamping thread that source has been dropped
-                            ts_tx
-                                .send(TimestampMessage::DropInstance(source_id))
-                                .expect("Failed to send Drop Instance notice to timestamper");
+                            let workers =
+                                self.source_instance_workers.entry(source_id).or_insert(0);
+                            *workers = workers.saturating_sub(1);
+                            if *workers == 0 {
+                                self.source_instance_workers.remove(&source_id);
+                                self.event_time_sources.remove(&source_id);
+                                // Notify timestamping thread that source has been dropped
+                                ts_tx
+                                    .send(TimestampMessage::DropInstance(source_id))
+                                    .expect("Failed to send Drop Instance notice to timestamper");
+                            }
                         },
                         Message::Worker(WorkerFeedbackWithMeta {
                                             worker_id: _,
                                             message: WorkerFeedback::CreateSource(source_id,ksc,consistency)}) => {
-                            ts_tx
-                                .send(TimestampMessage::Add(source_id, ksc.url, ksc.topic, ksc.ssl_certificate_file, consistency))
-                                .expect("Failed to send CREATE Instance notice to timestamper");
+                            let workers =
+                                self.source_instance_workers.entry(source_id).or_insert(0);
+                            *workers += 1;
+                            if *workers == 1 {
+                                if let Consistency::BringYourOwn(_) = &consistency {
+                                    self.event_time_sources.entry(source_id).or_insert(None);
+                                }
+                                ts_tx
+                                    .send(TimestampMessage::Add(source_id, ksc, consistency))
+                                    .expect("Failed to send CREATE Instance notice to timestamper");
+                            }
+                        }
+
+                        Message::Worker(WorkerFeedbackWithMeta {
+                            worker_id,
+                            message: WorkerFeedback::Inventory(inventory),
+                        }) => {
+                            self.check_inventory(worker_id, inventory);
+                        }
+
+                        Message::Worker(WorkerFeedbackWithMeta {
+                            worker_id,
+                            message: WorkerFeedback::ArrangementSizes(sizes),
+                        }) => {
+                            self.update_arrangement_sizes(worker_id, sizes);
                         }
                     }
                 }
 
                 // Cleanly drain any pending messages from the worker before shutting
                 // down.
+                drop(stop_ticks_tx);
                 while let Some(msg) = block_on(messages.next()) {
                     match msg.expect("coordinator message receiver failed") {
                         Message::Command(_) | Message::Shutdown => unreachable!(),
-                        Message::Worker(_) | Message::PlanReady(_, _, _, _) => (),
+                        Message::Worker(_)
+                        | Message::PlanReady(_, _, _, _)
+                        | Message::AdvanceLocalInputs => (),
                    }
                 }
             });
@@ -533,14 +875,143 @@ where
             // because if a TAIL is active, a PEEK cannot be.
             self.drop_sinks(vec![name]);
         } else {
-            // No TAIL is known to be active, so drop the PEEK that may be
-            // active on this connection. This is a no-op if no PEEKs are
-            // active.
-            broadcast(
-                &mut self.broadcast_tx,
-                SequencedCommand::CancelPeek { conn_id },
-            );
+            // No TAIL dataflow is known to be active, so drop the PEEK or the
+            // TAIL served from an index that may be active on this connection.
+            // This is a no-op if neither is active.
+            self.broadcast_all(SequencedCommand::CancelPeek { conn_id });
+        }
+    }
+
+    /// Computes the changes to the materialized source or view `name` after
+    /// `since` and up to the latest complete timestamp of its index, or its
+    /// entire contents at that timestamp if `since` is `None`.
+    ///
+    /// The changes are read from the history that the index retains, so they
+    /// are only available while `since` is not beyond the index's compaction
+    /// frontier.
+    pub fn sequence_peek_changes(
+        &mut self,
+        name: &str,
+        since: Option<Timestamp>,
+    ) -> Result<ChangesResponse, failure::Error> {
+        let mut parts: Vec<_> = name.split('.').map(String::from).collect();
+        if parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+            bail!("invalid name: {:?}", name);
+        }
+        let item = parts.pop().unwrap();
+        let schema = parts.pop();
+        let database = parts.pop();
+        let session = Session::default();
+        let name = self.catalog.resolve(
+            session.database(),
+            session.search_path(),
+            &PartialName {
+                database,
+                schema,
+                item,
+            },
+        )?;
+        let entry = self.catalog.get(&name)?;
+        let id = entry.id();
+        let desc = entry.desc()?.clone();
+        let context = PlanContext::new(PlanKind::Changes, 0, None, &self.catalog);
+        hook::check_direct_read(&context, id)?;
+        self.check_available(&[id])?;
+
+        let index_id = match self.views.get(&id).map(|v| &v.default_idx) {
+            Some(Some((index_id, _))) => *index_id,
+            _ => bail!("{} is not materialized", name),
+        };
+        let timestamp = match self.upper_of(&index_id).unwrap().get(0) {
+            Some(0) => bail!("{} has no complete timestamps yet", name),
+            Some(upper) => upper - 1,
+            None => Timestamp::max_value(),
+        };
+        if let Some(since) = since {
+            if since >= timestamp {
+                // Nothing can have changed since the client's last read.
+                return Ok(ChangesResponse {
+                    timestamp: since,
+                    desc,
+                    changes: Box::pin(future::ok::<_, comm::Error>(PeekResponse::Changes(vec![]))),
+                });
+            }
+            if !self.indexes[&index_id].since.less_equal(&since) {
+                bail!(
+                    "changes to {} since {} are no longer available; \
+                     read its entire contents instead",
+                    name,
+                    since
+                );
+            }
         }
+
+        let cluster = self.indexes[&index_id].cluster.clone();
+        let num_workers = self.cluster_workers(&cluster);
+        let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(num_workers);
+        broadcast(
+            self.cluster_tx(&cluster),
+            SequencedCommand::Peek {
+                id: index_id,
+                // Peeks at changes are never canceled, so they need not belong
+                // to a real connection.
+                conn_id: 0,
+                tx: rows_tx,
+                timestamp,
+                finishing: RowSetFinishing {
+                    order_by: vec![],
+                    limit: None,
+                    offset: 0,
+                    project: (0..desc.typ().column_types.len()).collect(),
+                },
+                project: None,
+                filter: vec![],
+                eval_env: EvalEnv {
+                    wall_time: Some(chrono::Utc::now()),
+                    logical_time: Some(timestamp),
+                },
+                key: None,
+                since,
+                history: None,
+                timeout: self.peek_timeout,
+                suspendable: false,
+                worker_txs: vec![],
+            },
+        );
+
+        // Each worker reports the changes to the rows it holds, so the
+        // responses can simply be concatenated. A read of the entire contents
+        // is reported as the insertion of every row.
+        let changes = fold_peek_results(
+            rows_rx,
+            num_workers,
+            PeekResponse::Changes(vec![]),
+            |memo, resp| match (memo, resp) {
+                (PeekResponse::Changes(mut memo), PeekResponse::Changes(changes)) => {
+                    memo.extend(changes);
+                    PeekResponse::Changes(memo)
+                }
+                (PeekResponse::Changes(mut memo), PeekResponse::Rows(rows)) => {
+                    memo.extend(rows.into_iter().map(|row| (row, 1)));
+                    PeekResponse::Changes(memo)
+                }
+                (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => PeekResponse::Error(e),
+                _ => PeekResponse::Canceled,
+            },
+        )
+        .map_ok(|(mut resp, _metadata)| {
+            if let PeekResponse::Changes(changes) = &mut resp {
+                differential_dataflow::consolidation::consolidate(changes);
+            }
+            resp
+        })
+        .err_into();
+
+        Ok(ChangesResponse {
+            timestamp,
+            desc,
+            changes: Box::pin(changes),
+        })
     }
 
     pub fn sequence_plan(
@@ -652,6 +1123,8 @@ where
                     connector: source.connector,
                     desc: source.desc,
                 };
+                validate_source_plugin(&source)
+                    .map_err(|e| dataflow_types::Error::Connector(e.to_string()))?;
                 let source_id = self.catalog.allocate_id()?;
                 let mut ops = vec![catalog::Op::CreateItem {
                     id: source_id,
@@ -702,11 +1175,19 @@ where
                 sink,
                 if_not_exists,
             } => {
+                let context =
+                    PlanContext::new(PlanKind::Sink, conn_id, Some(&*session), &self.catalog);
+                hook::check_direct_read(&context, sink.from)?;
+                self.check_available(&[sink.from])?;
+                self.check_cluster(&sink.cluster, &[sink.from])?;
                 let sink = catalog::Sink {
                     create_sql: sink.create_sql,
                     from: sink.from,
                     connector: sink.connector,
+                    cluster: sink.cluster,
                 };
+                validate_sink_plugin(&sink)
+                    .map_err(|e| dataflow_types::Error::Connector(e.to_string()))?;
                 let id = self.catalog.allocate_id()?;
                 let op = catalog::Op::CreateItem {
                     id,
@@ -725,10 +1206,45 @@ where
 
             Plan::CreateView {
                 name,
-                view,
+                mut view,
                 replace,
                 materialize,
+                cluster,
             } => {
+                let context =
+                    PlanContext::new(PlanKind::View, conn_id, Some(&*session), &self.catalog);
+                hook::apply_plan_hooks(&context, &mut view.expr)?;
+                let mut uses = vec![];
+                view.expr.global_uses(&mut uses);
+                if materialize {
+                    self.check_available(&uses)?;
+                    self.check_cluster(&cluster, &uses)?;
+                }
+                if view.max_lag.is_some() && self.depends_on_table(&uses) {
+                    bail!(
+                        "max_lag cannot be set on views that depend on tables, \
+                         as tables do not advance with the wall clock"
+                    );
+                }
+                // Both options interpret the timestamps of the view's inputs
+                // as milliseconds of wall-clock time.
+                let clock_option = if view.refresh_interval.is_some() {
+                    Some("refresh_interval")
+                } else if view.emit_final_after.is_some() {
+                    Some("emit_final_after")
+                } else {
+                    None
+                };
+                if let Some(option) = clock_option {
+                    if let Some(id) = self.logical_time_input(&uses) {
+                        bail!(
+                            "{} requires inputs that are timestamped by the wall clock, \
+                             but {} is timestamped by a logical clock",
+                            option,
+                            self.catalog.humanize_id(Id::Global(id)).unwrap()
+                        );
+                    }
+                }
                 let mut ops = vec![];
                 if let Some(id) = replace {
                     ops.extend(self.catalog.drop_items_ops(&[id]));
@@ -742,6 +1258,10 @@ where
                         .optimize(view.expr, self.catalog.indexes(), &eval_env),
                     desc: view.desc,
                     eval_env,
+                    refresh_interval: view.refresh_interval,
+                    changes_only: view.changes_only,
+                    emit_final_after: view.emit_final_after,
+                    max_lag: view.max_lag,
                 };
                 ops.push(catalog::Op::CreateItem {
                     id: view_id,
@@ -756,6 +1276,7 @@ where
                         name.clone(),
                         &view,
                         view_id,
+                        cluster.clone(),
                     );
                     let index_id = self.catalog.allocate_id()?;
                     ops.push(catalog::Op::CreateItem {
@@ -771,6 +1292,7 @@ where
                 self.insert_view(view_id, &view);
                 if materialize {
                     let mut dataflow = DataflowDesc::new(name.to_string());
+                    dataflow.cluster = cluster;
                     self.build_view_collection(&view_id, &view, &mut dataflow);
                     self.build_arrangement(
                         &index_id.unwrap(),
@@ -787,13 +1309,32 @@ where
                 index,
                 if_not_exists,
             } => {
+                self.check_available(&[index.on])?;
+                self.check_cluster(&index.cluster, &[index.on])?;
+                for (i, replica) in index.replicas.iter().enumerate() {
+                    if Some(replica) == index.cluster.as_ref() {
+                        bail!(
+                            "index cannot be replicated on its own cluster '{}'",
+                            replica
+                        );
+                    }
+                    if index.replicas[..i].contains(replica) {
+                        bail!("index replicated on cluster '{}' more than once", replica);
+                    }
+                    self.check_cluster(&Some(replica.clone()), &[index.on])?;
+                }
                 let index = catalog::Index {
                     create_sql: index.create_sql,
                     keys: index.keys,
                     on: index.on,
                     eval_env: EvalEnv::default(),
+                    cluster: index.cluster,
+                    replicas: index.replicas,
                 };
                 let id = self.catalog.allocate_id()?;
+                // Allocate the replicas' identifiers before committing the
+                // index, so that nothing can fail once it is committed.
+                let replicas = self.allocate_index_replicas(&name.to_string(), &index)?;
                 let op = catalog::Op::CreateItem {
                     id,
                     name: name.clone(),
@@ -801,7 +1342,8 @@ where
                 };
                 match self.catalog_transact(vec![op]) {
                     Ok(()) => {
-                        self.create_index_dataflow(name.to_string(), id, index);
+                        self.create_index_dataflow(name.to_string(), id, index.clone());
+                        self.create_index_replicas(&name.to_string(), id, &index, replicas);
                         Ok(ExecuteResponse::CreatedIndex { existed: false })
                     }
                     Err(_) if if_not_exists => Ok(ExecuteResponse::CreatedIndex { existed: true }),
@@ -809,6 +1351,20 @@ where
                 }
             }
 
+            Plan::CreateType { name, typ } => {
+                let typ = catalog::Type {
+                    create_sql: typ.create_sql,
+                    labels: typ.labels,
+                };
+                let id = self.catalog.allocate_id()?;
+                self.catalog_transact(vec![catalog::Op::CreateItem {
+                    id,
+                    name,
+                    item: CatalogItem::Type(typ),
+                }])?;
+                Ok(ExecuteResponse::CreatedType)
+            }
+
             Plan::DropDatabase { name } => {
                 let ops = self.catalog.drop_database_ops(name);
                 self.catalog_transact(ops)?;
@@ -834,9 +1390,24 @@ where
                     ObjectType::Table => ExecuteResponse::DroppedTable,
                     ObjectType::Sink => ExecuteResponse::DroppedSink,
                     ObjectType::Index => ExecuteResponse::DroppedIndex,
+                    ObjectType::Type => ExecuteResponse::DroppedType,
                 })
             }
 
+            Plan::SetComment { id, comment } => {
+                self.catalog_transact(vec![catalog::Op::SetComment { id, comment }])?;
+                Ok(ExecuteResponse::Commented)
+            }
+
+            Plan::SetTags {
+                id,
+                object_type,
+                tags,
+            } => {
+                self.catalog_transact(vec![catalog::Op::SetTags { id, tags }])?;
+                Ok(ExecuteResponse::AlteredObject(object_type))
+            }
+
             Plan::EmptyQuery => Ok(ExecuteResponse::EmptyQuery),
 
             Plan::ShowAllVariables => Ok(send_immediate_rows(
@@ -880,11 +1451,17 @@ where
             }
 
             Plan::Peek {
-                source,
+                mut source,
                 when,
                 finishing,
                 materialize,
             } => {
+                let context =
+                    PlanContext::new(PlanKind::Peek, conn_id, Some(&*session), &self.catalog);
+                hook::apply_plan_hooks(&context, &mut source)?;
+                let mut uses = vec![];
+                source.global_uses(&mut uses);
+                self.check_available(&uses)?;
                 let timestamp = self.determine_timestamp(&source, when)?;
                 let eval_env = EvalEnv {
                     wall_time: Some(chrono::Utc::now()),
@@ -923,21 +1500,29 @@ where
                     // Choose a timestamp for all workers to use in the peek.
                     // We minimize over all participating views, to ensure that the query will not
                     // need to block on the arrival of further input data.
-                    let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(self.num_timely_workers);
-
                     let (project, filter) = Self::plan_peek(source.as_mut());
 
-                    let (fast_path, index_id) = if let RelationExpr::Get {
+                    // The peek is serviced by the cluster that maintains the
+                    // arrangement it reads. If the filter pins the key of one
+                    // of the view's indexes, the peek looks up that key in
+                    // the index rather than scanning the default index.
+                    let (fast_path, index_id, cluster, key) = if let RelationExpr::Get {
                         id: Id::Global(id),
                         typ: _,
                     } = source.as_ref()
                     {
-                        if let Some(Some((index_id, _))) =
+                        if let Some((index_id, key)) = self.lookup_key(*id, &filter, &eval_env) {
+                            let index_id = self.choose_replica(index_id, timestamp);
+                            let cluster = self.indexes[&index_id].cluster.clone();
+                            (true, index_id, cluster, Some(key))
+                        } else if let Some(Some((index_id, _))) =
                             self.views.get(&id).map(|v| &v.default_idx)
                         {
-                            (true, *index_id)
+                            let index_id = self.choose_replica(*index_id, timestamp);
+                            let cluster = self.indexes[&index_id].cluster.clone();
+                            (true, index_id, cluster, None)
                         } else if materialize {
-                            (false, self.catalog.allocate_id()?)
+                            (false, self.allocate_transient_id()?, None, None)
                         } else {
                             bail!(
                                 "{} is not materialized",
@@ -945,52 +1530,40 @@ where
                             )
                         }
                     } else {
-                        (false, self.catalog.allocate_id()?)
+                        let cluster = self.transient_cluster(source.as_ref());
+                        (false, self.allocate_transient_id()?, cluster, None)
+                    };
+                    let num_workers = self.cluster_workers(&cluster);
+                    let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(num_workers);
+                    // A client that fetches the rows a few at a time may leave
+                    // the rest of a streamed response unread for a while.
+                    let suspendable = session.fetching_incrementally() && finishing.is_trivial();
+                    // Rows that need not be ordered by any column or limited
+                    // are merged into a deterministic order and forwarded to
+                    // the client as the workers send them. Each worker
+                    // answers through a channel of its own, which is only
+                    // read once its rows are needed to continue the merge.
+                    let (worker_txs, worker_rxs): (Vec<_>, Vec<_>) = if finishing.is_trivial() {
+                        (0..num_workers)
+                            .map(|_| self.switchboard.mpsc_limited(1))
+                            .unzip()
+                    } else {
+                        (vec![], vec![])
                     };
 
                     let index = if !fast_path {
                         // Slow path. We need to perform some computation, so build
                         // a new transient dataflow that will be dropped after the
                         // peek completes.
-                        let typ = source.as_ref().typ();
-                        let ncols = typ.column_types.len();
-                        // Cheat a little bit here to get a relation description. A
-                        // relation description is just a relation type with column
-                        // names, but we don't know the column names for `source`
-                        // here. Nothing in the dataflow layer cares about column
-                        // names, so just set them all to `None`. The column names
-                        // will ultimately be correctly transmitted to the client
-                        // because they are safely stashed in the connection's
-                        // session.
-                        let desc = RelationDesc::new(
-                            typ.clone(),
-                            iter::repeat::<Option<ColumnName>>(None).take(ncols),
-                        );
-                        let view_id = self.catalog.allocate_id()?;
-                        let view_name = FullName {
-                            database: DatabaseSpecifier::Ambient,
-                            schema: "temp".into(),
-                            item: format!("temp-view-{}", view_id),
-                        };
-                        let index_name = format!("temp-index-on-{}", view_id);
-                        let mut dataflow = DataflowDesc::new(view_name.to_string());
-                        dataflow.as_of(Some(vec![timestamp.clone()]));
-                        let view = catalog::View {
-                            create_sql: "<none>".into(),
-                            expr: source,
-                            desc,
-                            eval_env: eval_env.clone(),
-                        };
-                        self.build_view_collection(&view_id, &view, &mut dataflow);
-                        let index = auto_generate_view_idx(index_name, view_name, &view, view_id);
-                        self.build_arrangement(&index_id, index.clone(), typ, dataflow);
-                        Some(index)
+                        Some(self.build_transient_index(
+                            index_id, source, timestamp, &eval_env, &cluster, false,
+                        )?)
                     } else {
                         None
                     };
 
                     broadcast(
-                        &mut self.broadcast_tx,
+                        self.cluster_tx(&cluster),
                         SequencedCommand::Peek {
                             id: index_id,
                             conn_id,
@@ -1000,6 +1573,12 @@ where
                             project,
                             filter,
                             eval_env,
+                            key,
+                            since: None,
+                            history: None,
+                            timeout: self.peek_timeout,
+                            suspendable,
+                            worker_txs,
                         },
                     );
 
@@ -1007,31 +1586,64 @@ where
                         self.drop_indexes(vec![(index_id, &index.unwrap())]);
                     }
 
-                    let rows_rx = rows_rx
-                        .try_fold(PeekResponse::Rows(vec![]), |memo, resp| {
-                            match (memo, resp) {
-                                (PeekResponse::Rows(mut memo), PeekResponse::Rows(rows)) => {
-                                    memo.extend(rows);
-                                    let out: Result<_, comm::Error> = Ok(PeekResponse::Rows(memo));
-                                    future::ready(out)
+                    // Otherwise every row must arrive before any can be sent.
+                    if finishing.is_trivial() {
+                        let rows_rx = stream_peek_results(worker_rxs, finishing);
+                        return Ok(ExecuteResponse::StreamRows(Box::pin(rows_rx)));
+                    }
+
+                    // Each worker holds only its own share of the index, so
+                    // the workers' results are disjoint, and each worker
+                    // limits its results on its own. Together they may still
+                    // hold many more rows than the finished result, though,
+                    // so discard the rows that cannot survive finishing as
+                    // the batches arrive.
+                    let merge_finishing = finishing.clone();
+                    let rows_rx = fold_peek_results(
+                        rows_rx,
+                        num_workers,
+                        PeekResponse::Rows(vec![]),
+                        move |memo, resp| match (memo, resp) {
+                            (PeekResponse::Rows(mut memo), PeekResponse::Rows(rows)) => {
+                                memo.extend(rows);
+                                if let Some(limit) = merge_finishing.limit {
+                                    if memo.len() > 2 * (merge_finishing.offset + limit) {
+                                        merge_finishing.truncate(&mut memo);
+                                    }
                                 }
-                                _ => future::ok(PeekResponse::Canceled),
+                                PeekResponse::Rows(memo)
                             }
-                        })
-                        .map_ok(move |mut resp| {
-                            if let PeekResponse::Rows(rows) = &mut resp {
-                                finishing.finish(rows)
+                            (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => {
+                                PeekResponse::Error(e)
                             }
-                            resp
-                        })
-                        .err_into();
+                            _ => PeekResponse::Canceled,
+                        },
+                    )
+                    .map_ok(move |(mut resp, mut metadata)| {
+                        if let PeekResponse::Rows(rows) = &mut resp {
+                            finishing.finish(rows);
+                            if let Some(metadata) = &mut metadata {
+                                metadata.rows = rows.len();
+                            }
+                        }
+                        (resp, metadata)
+                    })
+                    .err_into();
 
                     Ok(ExecuteResponse::SendRows(Box::pin(rows_rx)))
                 }
             }
 
-            Plan::Tail(source) => {
+            Plan::Tail {
+                source,
+                filter,
+                project,
+            } => {
                 let source_id = source.id();
+                let context =
+                    PlanContext::new(PlanKind::Tail, conn_id, Some(&*session), &self.catalog);
+                hook::check_direct_read(&context, source_id)?;
+                self.check_available(&[source_id])?;
                 let index_id = if let Some(Some((index_id, _))) = self
                     .views
                     .get(&source_id)
@@ -1042,25 +1654,73 @@ where
                     bail!("Cannot tail a view that has not been materialized.")
                 };
 
+                let eval_env = EvalEnv {
+                    wall_time: Some(chrono::Utc::now()),
+                    logical_time: None,
+                };
+                // A TAIL that pins every key column of one of the view's
+                // indexes is served directly from that index's arrangement,
+                // rather than by a dataflow of its own.
+                let (index_id, key) = match self.lookup_key(source_id, &filter, &eval_env) {
+                    Some((index_id, key)) => (index_id, Some(key)),
+                    None => (*index_id, None),
+                };
+
+                // The TAIL runs on the cluster that maintains the index.
+                let cluster = self.indexes[&index_id].cluster.clone();
+                let (tx, rx) = self
+                    .switchboard
+                    .mpsc_limited(self.cluster_workers(&cluster));
+                let since = self
+                    .upper_of(&index_id)
+                    .expect("name missing at coordinator")
+                    .get(0)
+                    .copied()
+                    .unwrap_or(Timestamp::max_value());
+                let eval_env = EvalEnv {
+                    logical_time: Some(since),
+                    ..eval_env
+                };
+
+                if let Some(key) = key {
+                    // The subscription is canceled along with any peek on the
+                    // connection, so it need not be tracked as an active TAIL.
+                    broadcast(
+                        self.cluster_tx(&cluster),
+                        SequencedCommand::Subscribe {
+                            id: index_id,
+                            conn_id,
+                            key,
+                            tx,
+                            since,
+                            project,
+                            filter,
+                            eval_env,
+                        },
+                    );
+                    return Ok(ExecuteResponse::Tailing { rx });
+                }
+
+                self.check_arrangement_budget()?;
                 let sink_name = format!(
                     "tail-source-{}",
                     self.catalog
                         .humanize_id(Id::Global(source_id))
                         .expect("Source id is known to exist in catalog")
                 );
-                let sink_id = self.catalog.allocate_id()?;
+                let sink_id = self.allocate_transient_id()?;
                 self.active_tails.insert(conn_id, sink_id);
-                let (tx, rx) = self.switchboard.mpsc_limited(self.num_timely_workers);
-                let since = self
-                    .upper_of(index_id)
-                    .expect("name missing at coordinator")
-                    .get(0)
-                    .copied()
-                    .unwrap_or(Timestamp::max_value());
                 let sink = catalog::Sink {
                     create_sql: "<ignored>".into(),
                     from: source_id,
-                    connector: SinkConnector::Tail(TailSinkConnector { tx, since }),
+                    connector: SinkConnector::Tail(TailSinkConnector {
+                        tx,
+                        since,
+                        filter,
+                        project,
+                        eval_env,
+                    }),
+                    cluster,
                 };
                 self.create_sink_dataflow(sink_name, sink_id, sink);
                 Ok(ExecuteResponse::Tailing { rx })
@@ -1081,23 +1741,115 @@ where
                 Ok(send_immediate_rows(rows))
             }
 
+            Plan::ExplainAnalyze(mut source) => {
+                // The workers observe the statistics of operators through
+                // the events that they log.
+                if !self.log {
+                    bail!("EXPLAIN ANALYZE requires logging to be enabled");
+                }
+                let context =
+                    PlanContext::new(PlanKind::Peek, conn_id, Some(&*session), &self.catalog);
+                hook::apply_plan_hooks(&context, &mut source)?;
+                let mut uses = vec![];
+                source.global_uses(&mut uses);
+                self.check_available(&uses)?;
+                let timestamp = self.determine_timestamp(&source, PeekWhen::Immediately)?;
+                let eval_env = EvalEnv {
+                    wall_time: Some(chrono::Utc::now()),
+                    logical_time: Some(timestamp),
+                };
+                let source = self
+                    .optimizer
+                    .optimize(source, self.catalog.indexes(), &eval_env);
+
+                if let RelationExpr::Constant { .. } = source.as_ref() {
+                    // A constant query runs no dataflow, and so has no
+                    // operators to report.
+                    Ok(send_immediate_rows(vec![]))
+                } else {
+                    // Unlike a peek, the query always runs in a new
+                    // dataflow, even if it could read an existing index
+                    // directly, as the point is to observe the dataflow.
+                    let cluster = self.transient_cluster(source.as_ref());
+                    let num_workers = self.cluster_workers(&cluster);
+                    let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(num_workers);
+                    let index_id = self.allocate_transient_id()?;
+                    let arity = source.as_ref().arity();
+                    let index = self.build_transient_index(
+                        index_id, source, timestamp, &eval_env, &cluster, true,
+                    )?;
+                    broadcast(
+                        self.cluster_tx(&cluster),
+                        SequencedCommand::Peek {
+                            id: index_id,
+                            conn_id,
+                            tx: rows_tx,
+                            timestamp,
+                            finishing: RowSetFinishing {
+                                order_by: vec![],
+                                limit: None,
+                                offset: 0,
+                                project: (0..arity).collect(),
+                            },
+                            project: None,
+                            filter: vec![],
+                            eval_env,
+                            key: None,
+                            since: None,
+                            history: None,
+                            timeout: self.peek_timeout,
+                            suspendable: false,
+                            worker_txs: vec![],
+                        },
+                    );
+                    self.drop_indexes(vec![(index_id, &index)]);
+
+                    let rows_rx = fold_peek_results(
+                        rows_rx,
+                        num_workers,
+                        PeekResponse::Rows(vec![]),
+                        |memo, resp| match (memo, resp) {
+                            (PeekResponse::Rows(mut memo), PeekResponse::Rows(rows)) => {
+                                memo.extend(rows);
+                                PeekResponse::Rows(memo)
+                            }
+                            (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => {
+                                PeekResponse::Error(e)
+                            }
+                            _ => PeekResponse::Canceled,
+                        },
+                    )
+                    .map_ok(|(resp, metadata)| match resp {
+                        PeekResponse::Rows(rows) => {
+                            (PeekResponse::Rows(combine_profiles(rows)), metadata)
+                        }
+                        resp => (resp, metadata),
+                    })
+                    .err_into();
+
+                    Ok(ExecuteResponse::SendRows(Box::pin(rows_rx)))
+                }
+            }
+
             Plan::SendDiffs {
                 id,
                 updates,
                 affected_rows,
                 kind,
             } => {
+                if self.quiesced.is_some() {
+                    bail!("cannot write to tables while the instance is quiesced");
+                }
+                let timestamp = self.next_local_input_time();
                 let updates = updates
                     .into_iter()
                     .map(|(row, diff)| Update {
                         row,
                         diff,
-                        timestamp: self.local_input_time,
+                        timestamp,
                     })
                     .collect();
 
-                self.local_input_time += 1;
-
                 broadcast(
                     &mut self.broadcast_tx,
                     SequencedCommand::Insert {
@@ -1126,6 +1878,7 @@ where
                         let class = match id {
                             GlobalId::System(_) => "SYSTEM",
                             GlobalId::User(_) => "USER",
+                            GlobalId::Transient(_) => "TRANSIENT",
                         };
                         if let Some(view_state) = self.views.get(&id) {
                             if !limit_materialized || view_state.default_idx.is_some() {
@@ -1163,54 +1916,594 @@ where
                 rows.sort_unstable_by(move |a, b| a.unpack_first().cmp(&b.unpack_first()));
                 Ok(send_immediate_rows(rows))
             }
-        }
-    }
 
-    fn catalog_transact(&mut self, ops: Vec<catalog::Op>) -> Result<(), failure::Error> {
-        let mut sources_to_drop = vec![];
-        let mut views_to_drop = vec![];
-        let mut sinks_to_drop = vec![];
-        let mut indexes_to_drop = vec![];
+            Plan::ShowSinkProgress { ids } => {
+                // A sink whose frontier is empty has emitted all of its
+                // output, which is reported as a NULL frontier.
+                let mut rows = ids
+                    .into_iter()
+                    .filter_map(|(name, id)| {
+                        let frontier = self.sink_frontiers.get(&id)?;
+                        let time = frontier.frontier().get(0).copied();
+                        Some(Row::pack(&[
+                            Datum::from(name.as_str()),
+                            Datum::from(time.map(|time| time as i64)),
+                        ]))
+                    })
+                    .collect::<Vec<_>>();
+                rows.sort_unstable_by(move |a, b| a.unpack_first().cmp(&b.unpack_first()));
+                Ok(send_immediate_rows(rows))
+            }
 
-        let statuses = self.catalog.transact(ops)?;
-        for status in &statuses {
-            match status {
-                catalog::OpStatus::CreatedItem(id) => {
-                    let name = self.catalog.humanize_id(expr::Id::Global(*id)).unwrap();
-                    self.report_catalog_update(*id, name, true);
+            Plan::FlushSinks { ids } => self.sequence_flush_sinks(ids),
+
+            Plan::ShowArrangements => Ok(self.sequence_show_arrangements()),
+
+            Plan::CompactCatalog { vacuum } => {
+                let stats = self.compact_catalog()?;
+                if vacuum {
+                    self.catalog
+                        .storage_handle()
+                        .lock()
+                        .expect("lock poisoned")
+                        .vacuum()?;
                 }
-                catalog::OpStatus::DroppedItem(entry) => {
-                    self.report_catalog_update(entry.id(), entry.name().to_string(), false);
-                    match entry.item() {
-                        CatalogItem::Source(_) => {
-                            sources_to_drop.push(entry.id());
-                            views_to_drop.push(entry.id());
+                Ok(send_immediate_rows(vec![Row::pack(&[
+                    Datum::Int64(stats.superseded_bindings as i64),
+                    Datum::Int64(stats.orphaned_rows as i64),
+                ])]))
+            }
+
+            Plan::CheckCatalog => {
+                let storage = self.catalog.storage_handle();
+                let problems = storage
+                    .lock()
+                    .expect("lock poisoned")
+                    .check_integrity(|id| self.id_in_use(id))?;
+                let rows = problems
+                    .iter()
+                    .map(|problem| Row::pack(&[Datum::String(problem)]))
+                    .collect();
+                Ok(send_immediate_rows(rows))
+            }
+
+            Plan::CheckView { id } => {
+                let source = self.consistency_check(id)?;
+                let arity = source.arity();
+                self.sequence_plan(
+                    session,
+                    Plan::Peek {
+                        source,
+                        when: PeekWhen::Immediately,
+                        finishing: RowSetFinishing {
+                            order_by: vec![],
+                            limit: None,
+                            offset: 0,
+                            project: (0..arity).collect(),
+                        },
+                        materialize: true,
+                    },
+                    conn_id,
+                )
+            }
+
+            Plan::Listen { channel } => {
+                match self.listeners.get_mut(&conn_id) {
+                    Some(listener) => {
+                        listener.channels.insert(channel);
+                    }
+                    None => bail!("this connection cannot receive notifications"),
+                }
+                Ok(ExecuteResponse::Listened)
+            }
+
+            Plan::Unlisten { channel } => {
+                if let Some(listener) = self.listeners.get_mut(&conn_id) {
+                    match channel {
+                        Some(channel) => {
+                            listener.channels.remove(&channel);
                         }
-                        CatalogItem::View(_) => views_to_drop.push(entry.id()),
-                        CatalogItem::Sink(_) => sinks_to_drop.push(entry.id()),
-                        CatalogItem::Index(idx) => indexes_to_drop.push((entry.id(), idx)),
+                        None => listener.channels.clear(),
                     }
                 }
-                _ => (),
+                Ok(ExecuteResponse::Unlistened)
             }
-        }
 
-        if !sources_to_drop.is_empty() {
-            broadcast(
+            Plan::InstallDemo { name } => {
+                let script = match demo::script(&name) {
+                    Some(script) => script,
+                    None => bail!(
+                        "unknown demo '{}'; the available demos are: {}",
+                        name,
+                        demo::DEMOS.join(", ")
+                    ),
+                };
+                let params = Params {
+                    datums: Row::pack(&[]),
+                    types: vec![],
+                };
+                for stmt in sql::parse(script.to_owned())? {
+                    let plan = match sql::plan(&self.catalog, &*session, stmt, &params) {
+                        MaybeFuture::Immediate(Some(plan)) => plan?,
+                        _ => bail!("demo '{}' could not be planned", name),
+                    };
+                    self.sequence_plan(session, plan, conn_id)?;
+                }
+                Ok(ExecuteResponse::InstalledDemo)
+            }
+
+            Plan::ExportTrace { id, path, history } => {
+                let context = PlanContext::new(
+                    PlanKind::ExportTrace,
+                    conn_id,
+                    Some(&*session),
+                    &self.catalog,
+                );
+                hook::check_direct_read(&context, id)?;
+                self.sequence_export_trace(id, path, history, conn_id)
+            }
+
+            Plan::ImportTrace { name, path } => {
+                let (header, updates) = trace::read(&path)?;
+                // A table cannot hold negative multiplicities, so the history
+                // of a trace is imported as one row per update, with the
+                // update's time and diff in additional columns.
+                let (desc, updates) = if header.history {
+                    let desc = header
+                        .desc
+                        .add_column("mz_timestamp", ScalarType::Int64)
+                        .add_column("mz_diff", ScalarType::Int64);
+                    let updates = updates
+                        .into_iter()
+                        .map(|(row, time, diff)| {
+                            let mut packer = RowPacker::new();
+                            packer.extend_by_row(&row);
+                            packer.push(Datum::Int64(time as i64));
+                            packer.push(Datum::Int64(diff as i64));
+                            (packer.finish(), 1)
+                        })
+                        .collect::<Vec<_>>();
+                    (desc, updates)
+                } else {
+                    let mut contents = vec![];
+                    for (row, _time, diff) in updates {
+                        if diff < 0 {
+                            bail!("{} holds a row with negative multiplicity", path.display());
+                        }
+                        contents.push((row, diff));
+                    }
+                    (header.desc, contents)
+                };
+                let affected_rows: usize = updates.iter().map(|(_, diff)| *diff as usize).sum();
+                self.sequence_plan(
+                    session,
+                    Plan::CreateTable {
+                        name: name.clone(),
+                        desc,
+                        if_not_exists: false,
+                    },
+                    conn_id,
+                )?;
+                let id = self.catalog.get(&name)?.id();
+                self.sequence_plan(
+                    session,
+                    Plan::SendDiffs {
+                        id,
+                        updates,
+                        affected_rows,
+                        kind: MutationKind::Insert,
+                    },
+                    conn_id,
+                )?;
+                Ok(ExecuteResponse::ImportedTrace(affected_rows))
+            }
+
+            Plan::Quiesce { as_of } => {
+                let until = self.sequence_quiesce(as_of)?;
+                Ok(send_immediate_rows(vec![Row::pack(&[Datum::Int64(
+                    until as i64,
+                )])]))
+            }
+
+            Plan::Resume => {
+                if self.quiesced.take().is_none() {
+                    bail!("instance is not quiesced");
+                }
+                self.broadcast_all(SequencedCommand::Quiesce { until: None });
+                self.advance_local_inputs();
+                Ok(ExecuteResponse::Resumed)
+            }
+        }
+    }
+
+    /// Quiesces the instance at `as_of`, or at the current time if `as_of` is
+    /// `None`, and returns the time at which the instance is quiesced.
+    ///
+    /// The frontiers of all sources and tables are held at the following
+    /// time, so the contents of every view are complete at the quiesced time
+    /// and sinks report nothing after it. The quiesced time cannot precede
+    /// the frontier of any user index, as the updates after the frontier
+    /// have already been reported.
+    fn sequence_quiesce(&mut self, as_of: Option<Timestamp>) -> Result<Timestamp, failure::Error> {
+        if let Some(until) = self.quiesced {
+            bail!("instance is already quiesced at {}", until);
+        }
+        let uppers: Vec<_> = self
+            .indexes
+            .iter()
+            .filter(|(id, _)| id.is_user())
+            .filter_map(|(id, index_state)| {
+                index_state
+                    .upper
+                    .frontier()
+                    .iter()
+                    .min()
+                    .map(|time| (*id, *time))
+            })
+            .collect();
+        let until = match as_of {
+            Some(as_of) => as_of,
+            None => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("System time seems to be before 1970.")
+                    .as_millis() as Timestamp;
+                uppers
+                    .iter()
+                    .map(|(_, time)| time.saturating_sub(1))
+                    .fold(cmp::max(now, self.local_input_time), cmp::max)
+            }
+        };
+        if until == Timestamp::max_value() {
+            bail!("cannot quiesce at {}: timestamp out of range", until);
+        }
+        if let Some((id, time)) = uppers.iter().find(|(_, time)| *time > until + 1) {
+            let name = match self.catalog.try_get_by_id(id) {
+                Some(entry) => entry.name().to_string(),
+                None => id.to_string(),
+            };
+            bail!(
+                "cannot quiesce at {}: {} has already advanced to {}",
+                until,
+                name,
+                time
+            );
+        }
+        if self.local_input_time > until + 1 {
+            bail!(
+                "cannot quiesce at {}: tables have already advanced to {}",
+                until,
+                self.local_input_time
+            );
+        }
+        self.local_input_time = until + 1;
+        broadcast(
+            &mut self.broadcast_tx,
+            SequencedCommand::AdvanceLocalInputs {
+                advance_to: self.local_input_time,
+            },
+        );
+        self.quiesced = Some(until);
+        self.broadcast_all(SequencedCommand::Quiesce { until: Some(until) });
+        info!("quiesced at {}", until);
+        Ok(until)
+    }
+
+    /// Asks the workers of every cluster to describe the arrangements they
+    /// maintain, and responds with their descriptions once all have answered.
+    ///
+    /// The workers identify indexes and collections by ID, and only know the
+    /// key types of the arrangements that dataflows share, so the names of
+    /// indexes and collections and the key types of indexes are filled in
+    /// from the catalog. Arrangements of objects that have since been dropped
+    /// keep their IDs.
+    fn sequence_show_arrangements(&mut self) -> ExecuteResponse {
+        let num_workers = self.num_timely_workers
+            + self
+                .clusters
+                .values()
+                .map(|cluster| cluster.num_workers)
+                .sum::<usize>();
+        let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(num_workers);
+        self.broadcast_all(SequencedCommand::ReportArrangements { tx: rows_tx });
+
+        let mut names = HashMap::new();
+        let mut indexes = HashMap::new();
+        for entry in self.catalog.iter() {
+            names.insert(entry.id().to_string(), entry.name().to_string());
+            if let CatalogItem::Index(index) = entry.item() {
+                let on = self.catalog.get_by_id(&index.on);
+                let key_types = match on.desc() {
+                    Ok(desc) => index
+                        .keys
+                        .iter()
+                        .map(|key| key.typ(desc.typ()).to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    Err(_) => continue,
+                };
+                indexes.insert(entry.id().to_string(), (on.id().to_string(), key_types));
+            }
+        }
+        // Replicas of an index are described as the index they replicate.
+        for (id, index_state) in &self.indexes {
+            if let Some(replica_of) = index_state.replica_of {
+                let replica_of = replica_of.to_string();
+                if let Some(name) = names.get(&replica_of).cloned() {
+                    names.insert(id.to_string(), name);
+                }
+                if let Some(index) = indexes.get(&replica_of).cloned() {
+                    indexes.insert(id.to_string(), index);
+                }
+            }
+        }
+
+        let rows_rx = fold_peek_results(
+            rows_rx,
+            num_workers,
+            PeekResponse::Rows(vec![]),
+            |memo, resp| match (memo, resp) {
+                (PeekResponse::Rows(mut memo), PeekResponse::Rows(rows)) => {
+                    memo.extend(rows);
+                    PeekResponse::Rows(memo)
+                }
+                (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => PeekResponse::Error(e),
+                _ => PeekResponse::Canceled,
+            },
+        )
+        .map_ok(move |(resp, metadata)| match resp {
+            PeekResponse::Rows(rows) => {
+                let mut rows: Vec<_> = rows
+                    .iter()
+                    .map(|row| {
+                        let datums = row.unpack();
+                        let index_id = datums[2].unwrap_str();
+                        let (on_id, key_types) = match (datums[3], datums[5]) {
+                            (Datum::String(on_id), Datum::String(key_types)) => (on_id, key_types),
+                            _ => match indexes.get(index_id) {
+                                Some((on_id, key_types)) => (on_id.as_str(), key_types.as_str()),
+                                None => ("?", "?"),
+                            },
+                        };
+                        Row::pack(&[
+                            datums[0],
+                            datums[1],
+                            Datum::String(names.get(index_id).map_or(index_id, |s| s.as_str())),
+                            Datum::String(names.get(on_id).map_or(on_id, |s| s.as_str())),
+                            datums[4],
+                            Datum::String(key_types),
+                            datums[6],
+                            datums[7],
+                            datums[8],
+                        ])
+                    })
+                    .collect();
+                rows.sort_by(|a, b| a.unpack().cmp(&b.unpack()));
+                (PeekResponse::Rows(rows), metadata)
+            }
+            resp => (resp, metadata),
+        })
+        .err_into();
+
+        ExecuteResponse::SendRows(Box::pin(rows_rx))
+    }
+
+    /// Writes the contents of the default index of the view or source `id` to
+    /// a trace file at `path`, along with all of the history that the index
+    /// retains if `history` is set.
+    ///
+    /// The file is written once the workers have sent the contents of the
+    /// index, and the response reports the number of updates written.
+    fn sequence_export_trace(
+        &mut self,
+        id: GlobalId,
+        path: PathBuf,
+        history: bool,
+        conn_id: u32,
+    ) -> Result<ExecuteResponse, failure::Error> {
+        self.check_available(&[id])?;
+        let entry = self.catalog.get_by_id(&id);
+        let name = entry.name().to_string();
+        let desc = entry.desc()?.clone();
+        let index_id = match self.views.get(&id).map(|v| &v.default_idx) {
+            Some(Some((index_id, _))) => *index_id,
+            _ => bail!("{} is not materialized", name),
+        };
+        let timestamp = match self.upper_of(&index_id).unwrap().get(0) {
+            Some(0) => bail!("{} has no complete timestamps yet", name),
+            Some(upper) => upper - 1,
+            None => Timestamp::max_value(),
+        };
+        // Updates before the compaction frontier cannot be told apart, so
+        // they are reported at the frontier. Without history, every update
+        // is reported at `timestamp`, which yields the contents of the index.
+        let since = if history {
+            match self.indexes[&index_id].since.elements().get(0) {
+                Some(since) => cmp::min(*since, timestamp),
+                None => timestamp,
+            }
+        } else {
+            timestamp
+        };
+
+        let cluster = self.indexes[&index_id].cluster.clone();
+        let num_workers = self.cluster_workers(&cluster);
+        let (rows_tx, rows_rx) = self.switchboard.mpsc_limited(num_workers);
+        broadcast(
+            self.cluster_tx(&cluster),
+            SequencedCommand::Peek {
+                id: index_id,
+                conn_id,
+                tx: rows_tx,
+                timestamp,
+                finishing: RowSetFinishing {
+                    order_by: vec![],
+                    limit: None,
+                    offset: 0,
+                    project: (0..desc.typ().column_types.len()).collect(),
+                },
+                project: None,
+                filter: vec![],
+                eval_env: EvalEnv {
+                    wall_time: Some(chrono::Utc::now()),
+                    logical_time: Some(timestamp),
+                },
+                key: None,
+                since: None,
+                history: Some(since),
+                timeout: self.peek_timeout,
+                suspendable: false,
+                worker_txs: vec![],
+            },
+        );
+
+        let header = trace::Header {
+            version: trace::VERSION,
+            name,
+            desc,
+            timestamp,
+            history,
+        };
+        let rows_rx = fold_peek_results(
+            rows_rx,
+            num_workers,
+            PeekResponse::Updates(vec![]),
+            |memo, resp| match (memo, resp) {
+                (PeekResponse::Updates(mut memo), PeekResponse::Updates(updates)) => {
+                    memo.extend(updates);
+                    PeekResponse::Updates(memo)
+                }
+                (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => PeekResponse::Error(e),
+                _ => PeekResponse::Canceled,
+            },
+        )
+        .map_ok(move |(resp, _metadata)| match resp {
+            PeekResponse::Updates(mut updates) => {
+                updates.sort_by_key(|(_row, time, _diff)| *time);
+                let resp = match trace::write(&path, &header, &updates) {
+                    Ok(()) => {
+                        let count = Row::pack(&[Datum::Int64(updates.len() as i64)]);
+                        PeekResponse::Rows(vec![count])
+                    }
+                    Err(e) => {
+                        let msg = format!("writing trace to {}: {}", path.display(), e);
+                        PeekResponse::Error(dataflow_types::Error::Resource(msg))
+                    }
+                };
+                (resp, None)
+            }
+            resp => (resp, None),
+        })
+        .err_into();
+
+        Ok(ExecuteResponse::SendRows(Box::pin(rows_rx)))
+    }
+
+    /// Removes the rows from the catalog's storage that are no longer needed
+    /// to restore the catalog.
+    ///
+    /// Each source instance's timestamp bindings are compacted up to the
+    /// time to which the index that it feeds is being compacted, as queries
+    /// cannot distinguish earlier times. The bindings of instances that feed
+    /// sinks, and of indexes whose compaction is disabled, are kept in full.
+    fn compact_catalog(&self) -> Result<CompactionStats, failure::Error> {
+        let storage = self.catalog.storage_handle();
+        let mut storage = storage.lock().expect("lock poisoned");
+        storage.compact(
+            |id| self.id_in_use(id),
+            |instance| match self.indexes.get(&instance.vid) {
+                Some(index_state) => index_state.compaction_since(),
+                None => 0,
+            },
+        )
+    }
+
+    /// Allocates an identifier in the transient namespace for the dataflow of
+    /// a peek or `TAIL`. Unlike catalog identifiers, these cost no write to
+    /// the catalog's storage, as they need only be unique while the process
+    /// is running.
+    fn allocate_transient_id(&mut self) -> Result<GlobalId, failure::Error> {
+        let id = self.transient_id_counter;
+        if id == u64::max_value() {
+            bail!("transient id counter overflows a u64");
+        }
+        self.transient_id_counter += 1;
+        Ok(GlobalId::Transient(id))
+    }
+
+    /// Reports whether the catalog's storage may still refer to `id`, either
+    /// because it identifies a catalog item or because it identifies a
+    /// running dataflow, like that of a `TAIL`. System items are installed
+    /// anew each time the catalog is opened, so they are always in use.
+    fn id_in_use(&self, id: GlobalId) -> bool {
+        id.is_system()
+            || self.catalog.try_get_by_id(&id).is_some()
+            || self.indexes.contains_key(&id)
+            || self.sink_frontiers.contains_key(&id)
+    }
+
+    fn catalog_transact(&mut self, ops: Vec<catalog::Op>) -> Result<(), failure::Error> {
+        let mut sources_to_drop = vec![];
+        let mut views_to_drop = vec![];
+        let mut sinks_to_drop = vec![];
+        let mut indexes_to_drop = vec![];
+
+        let statuses = self.catalog.transact(ops)?;
+        for status in &statuses {
+            match status {
+                catalog::OpStatus::CreatedItem(id) => {
+                    let name = self.catalog.humanize_id(expr::Id::Global(*id)).unwrap();
+                    let item_type = self.catalog.get_by_id(id).item().type_string();
+                    self.notify("mz_catalog", format!("create {} {}", item_type, name));
+                    self.report_catalog_update(*id, name, true);
+                }
+                catalog::OpStatus::UpdatedItem(id) => {
+                    let entry = self.catalog.get_by_id(id);
+                    let payload = format!("alter {} {}", entry.item().type_string(), entry.name());
+                    self.notify("mz_catalog", payload);
+                }
+                catalog::OpStatus::DroppedItem(entry) => {
+                    let payload = format!("drop {} {}", entry.item().type_string(), entry.name());
+                    self.notify("mz_catalog", payload);
+                    self.report_catalog_update(entry.id(), entry.name().to_string(), false);
+                    match entry.item() {
+                        CatalogItem::Source(_) => {
+                            sources_to_drop.push(entry.id());
+                            views_to_drop.push(entry.id());
+                        }
+                        CatalogItem::View(_) => views_to_drop.push(entry.id()),
+                        CatalogItem::Sink(_) => sinks_to_drop.push(entry.id()),
+                        CatalogItem::Index(idx) => indexes_to_drop.push((entry.id(), idx)),
+                        CatalogItem::Type(_) => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !sources_to_drop.is_empty() {
+            broadcast(
                 &mut self.broadcast_tx,
                 SequencedCommand::DropSources(sources_to_drop),
             );
         }
         if !views_to_drop.is_empty() {
             for id in views_to_drop {
-                self.views.remove(&id);
+                let _ = VIEW_DIVERGENCES.remove_label_values(&[&id.to_string()]);
+                if let Some(ViewState {
+                    freshness: Some(freshness),
+                    ..
+                }) = self.views.remove(&id)
+                {
+                    self.report_freshness(id, &freshness, false);
+                    let label = id.to_string();
+                    let _ = VIEW_LAG_MS.remove_label_values(&[&label]);
+                    let _ = VIEW_FRESHNESS_VIOLATIONS.remove_label_values(&[&label]);
+                }
             }
         }
         if !sinks_to_drop.is_empty() {
-            broadcast(
-                &mut self.broadcast_tx,
-                SequencedCommand::DropSinks(sinks_to_drop),
-            );
+            self.drop_sinks(sinks_to_drop);
         }
         if !indexes_to_drop.is_empty() {
             self.drop_indexes(indexes_to_drop);
@@ -1230,22 +2523,14 @@ where
         {
             return;
         }
-        if let Some((index_id, keys)) = &self.views[id].default_idx {
-            let index_desc = IndexDesc {
-                on_id: *id,
-                keys: keys.to_vec(),
-            };
+        if let Some((index_id, keys)) = self.index_in_cluster(id, &dataflow.cluster) {
+            let index_desc = IndexDesc { on_id: *id, keys };
             match self.catalog.get_by_id(id).item() {
                 CatalogItem::View(view) => {
-                    dataflow.add_index_import(*index_id, index_desc, view.desc.typ().clone(), *id);
+                    dataflow.add_index_import(index_id, index_desc, view.desc.typ().clone(), *id);
                 }
                 CatalogItem::Source(source) => {
-                    dataflow.add_index_import(
-                        *index_id,
-                        index_desc,
-                        source.desc.typ().clone(),
-                        *id,
-                    );
+                    dataflow.add_index_import(index_id, index_desc, source.desc.typ().clone(), *id);
                 }
                 _ => unreachable!(),
             }
@@ -1290,6 +2575,7 @@ where
             }
         });
         // Collect sources, views, and indexes used.
+        let cluster = dataflow.cluster.clone();
         view.expr.as_ref().visit(&mut |e| {
             if let RelationExpr::ArrangeBy { input, keys } = e {
                 if let RelationExpr::Get {
@@ -1306,13 +2592,10 @@ where
                         // case we should import the source to be sure that we have access
                         // to the collection to arrange it ourselves.
                         if let Some(view) = self.views.get(on_id) {
-                            if let Some(ids) = view.primary_idxes.get(key_set) {
-                                dataflow.add_index_import(
-                                    *ids.first().unwrap(),
-                                    index_desc,
-                                    typ.clone(),
-                                    *view_id,
-                                );
+                            if let Some(id) = view.primary_idxes.get(key_set).and_then(|ids| {
+                                ids.iter().find(|id| self.indexes[*id].cluster == cluster)
+                            }) {
+                                dataflow.add_index_import(*id, index_desc, typ.clone(), *view_id);
                             }
                         }
                     }
@@ -1324,6 +2607,9 @@ where
             view.expr.clone(),
             view.eval_env.clone(),
             view.desc.typ().clone(),
+            view.refresh_interval,
+            view.changes_only,
+            view.emit_final_after,
         );
     }
 
@@ -1332,8 +2618,27 @@ where
         id: &GlobalId,
         index: catalog::Index,
         on_type: RelationType,
-        mut dataflow: DataflowDesc,
+        dataflow: DataflowDesc,
     ) {
+        let backfill = self.export_arrangement(id, &index, on_type, dataflow);
+        self.insert_index(*id, &index, None);
+        // Only the progress of the indexes that users create is tracked, not
+        // that of system indexes or of the temporary indexes of peeks.
+        if let (GlobalId::User(_), Some(_)) = (id, self.catalog.try_get_by_id(id)) {
+            self.indexes.get_mut(id).unwrap().backfill = Some(backfill);
+        }
+    }
+
+    /// Instructs the cluster that maintains `index` to build the arrangement
+    /// for it, under the identifier `id`, in `dataflow`, and returns the
+    /// progress that the arrangement must make to catch up with its inputs.
+    fn export_arrangement(
+        &mut self,
+        id: &GlobalId,
+        index: &catalog::Index,
+        on_type: RelationType,
+        mut dataflow: DataflowDesc,
+    ) -> Backfill {
         self.import_source_or_view(id, &index.on, &mut dataflow);
         dataflow.add_index_to_build(
             *id,
@@ -1343,17 +2648,49 @@ where
             index.eval_env.clone(),
         );
         dataflow.add_index_export(*id, index.on, on_type, index.keys.clone());
+        dataflow.push_down_source_operators();
+        dataflow.fuse_source_unions();
+        let backfill = self.plan_backfill(&dataflow);
         // TODO: should we still support creating multiple dataflows with a single command,
         // Or should it all be compacted into a single DataflowDesc with multiple exports?
         broadcast(
-            &mut self.broadcast_tx,
+            self.cluster_tx(&index.cluster),
             SequencedCommand::CreateDataflows(vec![dataflow]),
         );
-        self.insert_index(*id, &index, None);
+        backfill
+    }
+
+    /// Determines how far a new dataflow must advance to catch up with its
+    /// inputs: to the frontiers that the indexes it imports have reached, and,
+    /// if it reads sources directly, to the current time.
+    fn plan_backfill(&self, dataflow: &DataflowDesc) -> Backfill {
+        let mut target = 0;
+        for id in dataflow.index_imports.keys() {
+            if let Some(time) = self
+                .upper_of(id)
+                .and_then(|upper| upper.iter().min().copied())
+            {
+                target = cmp::max(target, time);
+            }
+        }
+        if !dataflow.source_imports.is_empty() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time seems to be before 1970.")
+                .as_millis() as Timestamp;
+            target = cmp::max(target, now);
+        }
+        Backfill {
+            started: Instant::now(),
+            target,
+            inputs: dataflow.index_imports.keys().cloned().collect(),
+            reported: None,
+        }
     }
 
     fn create_index_dataflow(&mut self, name: String, id: GlobalId, index: catalog::Index) {
-        let dataflow = DataflowDesc::new(name);
+        let mut dataflow = DataflowDesc::new(name);
+        dataflow.cluster = index.cluster.clone();
         let on_type = self
             .catalog
             .get_by_id(&index.on)
@@ -1364,28 +2701,197 @@ where
         self.build_arrangement(&id, index, on_type, dataflow);
     }
 
+    /// Allocates an identifier for each replica of the index named `name`.
+    ///
+    /// Replicas are not recorded in the catalog and are assigned fresh
+    /// identifiers each time they are built. Replicas on clusters that are
+    /// not configured are skipped, as the index itself remains usable.
+    fn allocate_index_replicas(
+        &mut self,
+        name: &str,
+        index: &catalog::Index,
+    ) -> Result<Vec<(String, GlobalId)>, failure::Error> {
+        let mut replicas = vec![];
+        for cluster in &index.replicas {
+            if self.clusters.contains_key(cluster) {
+                replicas.push((cluster.clone(), self.catalog.allocate_id()?));
+            } else {
+                warn!(
+                    "not building replica of index {} on cluster '{}', which does not exist",
+                    name, cluster
+                );
+            }
+        }
+        Ok(replicas)
+    }
+
+    /// Builds the `replicas` of the index identified by `id`, as allocated by
+    /// [`Coordinator::allocate_index_replicas`].
+    ///
+    /// Each replica is a copy of the index's arrangement, maintained by
+    /// another cluster from the same inputs, that exists only to serve peeks.
+    fn create_index_replicas(
+        &mut self,
+        name: &str,
+        id: GlobalId,
+        index: &catalog::Index,
+        replicas: Vec<(String, GlobalId)>,
+    ) {
+        let on_type = self
+            .catalog
+            .get_by_id(&index.on)
+            .desc()
+            .unwrap()
+            .typ()
+            .clone();
+        for (cluster, replica_id) in replicas {
+            let replica = catalog::Index {
+                cluster: Some(cluster.clone()),
+                replicas: vec![],
+                ..index.clone()
+            };
+            let mut dataflow = DataflowDesc::new(format!("{}-replica-{}", name, cluster));
+            dataflow.cluster = replica.cluster.clone();
+            self.export_arrangement(&replica_id, &replica, on_type.clone(), dataflow);
+            let mut replica_state = IndexState::new(
+                self.cluster_workers(&replica.cluster),
+                replica.cluster.clone(),
+            );
+            replica_state.replica_of = Some(id);
+            self.indexes.insert(replica_id, replica_state);
+            if let Some(index_state) = self.indexes.get_mut(&id) {
+                index_state.replicas.push(replica_id);
+            }
+        }
+    }
+
     fn create_sink_dataflow(&mut self, name: String, id: GlobalId, sink: catalog::Sink) {
         let mut dataflow = DataflowDesc::new(name);
+        dataflow.cluster = sink.cluster.clone();
         self.import_source_or_view(&id, &sink.from, &mut dataflow);
         let from_type = self.catalog.get_by_id(&sink.from).desc().unwrap().clone();
         dataflow.add_sink_export(id, sink.from, from_type, sink.connector);
+        dataflow.push_down_source_operators();
+        dataflow.fuse_source_unions();
+        let mut frontier = MutableAntichain::new();
+        frontier.update_iter(Some((0, self.cluster_workers(&sink.cluster) as i64)));
+        if self.log {
+            for time in frontier.frontier().iter() {
+                broadcast(
+                    &mut self.broadcast_tx,
+                    SequencedCommand::AppendLog(MaterializedEvent::SinkFrontier(id, *time, 1)),
+                );
+            }
+        }
+        self.sink_frontiers.insert(id, frontier);
         broadcast(
-            &mut self.broadcast_tx,
+            self.cluster_tx(&sink.cluster),
             SequencedCommand::CreateDataflows(vec![dataflow]),
         );
     }
 
     pub fn drop_sinks(&mut self, dataflow_names: Vec<GlobalId>) {
-        broadcast(
-            &mut self.broadcast_tx,
-            SequencedCommand::DropSinks(dataflow_names),
-        )
+        for id in &dataflow_names {
+            if let Some(frontier) = self.sink_frontiers.remove(id) {
+                if self.log {
+                    for time in frontier.frontier().iter() {
+                        broadcast(
+                            &mut self.broadcast_tx,
+                            SequencedCommand::AppendLog(MaterializedEvent::SinkFrontier(
+                                *id, *time, -1,
+                            )),
+                        );
+                    }
+                }
+            }
+        }
+        self.broadcast_all(SequencedCommand::DropSinks(dataflow_names));
+        self.advance_flushes();
+    }
+
+    /// Waits for each of the sinks in `ids` to emit its output for all of the
+    /// input that is currently available, and then reports the frontier of
+    /// each sink, as `SHOW SINK PROGRESS` does.
+    ///
+    /// The input of each sink must be materialized, as the coordinator
+    /// otherwise cannot tell how far the input has advanced.
+    fn sequence_flush_sinks(
+        &mut self,
+        ids: Vec<(String, GlobalId)>,
+    ) -> Result<ExecuteResponse, failure::Error> {
+        let mut sinks = vec![];
+        for (name, id) in ids {
+            let from = match self.catalog.get_by_id(&id).item() {
+                CatalogItem::Sink(sink) => sink.from,
+                _ => unreachable!("flushed item is not a sink"),
+            };
+            let queryable = self
+                .views
+                .get(&from)
+                .map_or(false, |view_state| view_state.queryable);
+            if !queryable {
+                bail!(
+                    "cannot flush sink {}: it depends on non-materialized sources",
+                    name
+                );
+            }
+            let mut upper = Antichain::new();
+            for index_id in self.find_dependent_indexes(&from) {
+                upper.extend(self.upper_of(&index_id).unwrap().iter().cloned());
+            }
+            sinks.push((name, id, upper));
+        }
+        let (tx, rx) = futures::channel::oneshot::channel();
+        self.pending_flushes.push(PendingFlush { sinks, tx });
+        self.advance_flushes();
+        Ok(ExecuteResponse::SendRows(Box::pin(rx.err_into())))
+    }
+
+    /// Responds to each pending flush whose sinks have all caught up with
+    /// their inputs. A sink that has been dropped has nothing left to emit,
+    /// and is left out of the response.
+    fn advance_flushes(&mut self) {
+        let sink_frontiers = &self.sink_frontiers;
+        let caught_up = |id: &GlobalId, upper: &Antichain<Timestamp>| match sink_frontiers.get(id) {
+            Some(frontier) => frontier.frontier().iter().all(|t| upper.less_equal(t)),
+            None => true,
+        };
+        let (ready, pending): (Vec<_>, Vec<_>) =
+            self.pending_flushes.drain(..).partition(|flush| {
+                flush
+                    .sinks
+                    .iter()
+                    .all(|(_name, id, upper)| caught_up(id, upper))
+            });
+        self.pending_flushes = pending;
+        for flush in ready {
+            let mut rows = flush
+                .sinks
+                .into_iter()
+                .filter_map(|(name, id, _upper)| {
+                    let frontier = sink_frontiers.get(&id)?;
+                    let time = frontier.frontier().get(0).copied();
+                    Some(Row::pack(&[
+                        Datum::from(name.as_str()),
+                        Datum::from(time.map(|time| time as i64)),
+                    ]))
+                })
+                .collect::<Vec<_>>();
+            rows.sort_unstable_by(move |a, b| a.unpack_first().cmp(&b.unpack_first()));
+            // The client may have gone away in the meantime.
+            let _ = flush.tx.send((PeekResponse::Rows(rows), None));
+        }
     }
 
     pub fn drop_indexes(&mut self, indexes: Vec<(GlobalId, &catalog::Index)>) {
-        let mut trace_keys = Vec::new();
+        let mut trace_keys: HashMap<_, Vec<_>> = HashMap::new();
         for (id, idx) in indexes {
             if let Some(index_state) = self.indexes.remove(&id) {
+                let _ = ARRANGEMENT_RECORDS.remove_label_values(&[&id.to_string()]);
+                let _ = ARRANGEMENT_BYTES.remove_label_values(&[&id.to_string()]);
+                if let Some(backfill) = &index_state.backfill {
+                    self.retract_backfill(id, backfill);
+                }
                 if self.log {
                     for time in index_state.upper.frontier().iter() {
                         broadcast(
@@ -1405,12 +2911,22 @@ where
                         self.propagate_queryability(&idx.on);
                     }
                 }
-                trace_keys.push(id);
+                for replica_id in index_state.replicas {
+                    if let Some(replica_state) = self.indexes.remove(&replica_id) {
+                        let _ = ARRANGEMENT_RECORDS.remove_label_values(&[&replica_id.to_string()]);
+                        let _ = ARRANGEMENT_BYTES.remove_label_values(&[&replica_id.to_string()]);
+                        trace_keys
+                            .entry(replica_state.cluster)
+                            .or_default()
+                            .push(replica_id);
+                    }
+                }
+                trace_keys.entry(index_state.cluster).or_default().push(id);
             }
         }
-        if !trace_keys.is_empty() {
+        for (cluster, trace_keys) in trace_keys {
             broadcast(
-                &mut self.broadcast_tx,
+                self.cluster_tx(&cluster),
                 SequencedCommand::DropIndexes(trace_keys),
             )
         }
@@ -1423,7 +2939,22 @@ where
     }
 
     pub fn shutdown(&mut self) {
-        broadcast(&mut self.broadcast_tx, SequencedCommand::Shutdown)
+        self.broadcast_all(SequencedCommand::Shutdown)
+    }
+
+    /// Delivers a notification with `payload` to each session that listens
+    /// on `channel`.
+    fn notify(&mut self, channel: &str, payload: String) {
+        for listener in self.listeners.values() {
+            if listener.channels.contains(channel) {
+                // The session may have ended without telling us, in which
+                // case the notification is simply dropped.
+                let _ = listener.tx.unbounded_send(Notification {
+                    channel: channel.to_owned(),
+                    payload: payload.clone(),
+                });
+            }
+        }
     }
 
     pub fn report_catalog_update(&mut self, id: GlobalId, name: String, insert: bool) {
@@ -1445,12 +2976,451 @@ where
         self.since_updates
             .retain(|(_, frontier)| !frontier.is_empty());
         if !self.since_updates.is_empty() {
+            let since_updates = std::mem::replace(&mut self.since_updates, Vec::new());
+            self.broadcast_all(SequencedCommand::AllowCompaction(since_updates));
+        }
+
+        // Periodically remove the history that the catalog no longer needs,
+        // so that it does not grow without bound.
+        if let Some(interval) = self.catalog_compaction_interval {
+            if self.last_catalog_compaction.elapsed() >= interval {
+                self.last_catalog_compaction = Instant::now();
+                match self.compact_catalog() {
+                    Ok(stats) => info!(
+                        "compacted catalog: removed {} superseded timestamp bindings and {} orphaned rows",
+                        stats.superseded_bindings, stats.orphaned_rows
+                    ),
+                    Err(e) => error!("failed to compact catalog: {}", e),
+                }
+            }
+        }
+
+        if let Some(interval) = self.consistency_check_interval {
+            if self.last_consistency_check.elapsed() >= interval {
+                self.last_consistency_check = Instant::now();
+                self.check_consistency();
+            }
+        }
+
+        self.check_freshness();
+        self.check_backfills();
+        self.check_source_skew();
+    }
+
+    /// Returns an expression that evaluates the definition of the view `id`
+    /// from scratch and compares the result with the contents of the view's
+    /// index. The expression produces the view's columns followed by a column
+    /// that describes the divergence: `missing` for a row that the
+    /// definition produces but the index lacks, and `unexpected` for a row
+    /// that the index holds but the definition does not produce. Duplicate
+    /// rows are reported once for each surplus copy.
+    ///
+    /// The inputs of the view are read from their own indexes, if they have
+    /// them, so the check covers only the view's own dataflow.
+    fn consistency_check(&self, id: GlobalId) -> Result<RelationExpr, failure::Error> {
+        let name = self.catalog.humanize_id(Id::Global(id)).unwrap();
+        let view = match self.catalog.get_by_id(&id).item() {
+            CatalogItem::View(view) => view,
+            _ => bail!("{} is not a view", name),
+        };
+        match self.views.get(&id) {
+            Some(ViewState {
+                default_idx: Some(_),
+                ..
+            }) => (),
+            _ => bail!("{} is not materialized", name),
+        }
+        if view.refresh_interval.is_some() || view.emit_final_after.is_some() {
+            bail!(
+                "{} deliberately lags behind its definition, so it cannot be checked",
+                name
+            );
+        }
+        let label = |s| ScalarExpr::literal(Datum::String(s), ColumnType::new(ScalarType::String));
+        let maintained = RelationExpr::global_get(id, view.desc.typ().clone());
+        let reference = view.expr.as_ref().clone();
+        let unexpected = maintained
+            .clone()
+            .union(reference.clone().negate())
+            .threshold()
+            .map(vec![label("unexpected")]);
+        let missing = reference
+            .union(maintained.negate())
+            .threshold()
+            .map(vec![label("missing")]);
+        Ok(unexpected.union(missing))
+    }
+
+    /// Compares each materialized view with a reference evaluation of its
+    /// definition, and reports any divergence to the log and to
+    /// `mz_view_divergences_total`.
+    fn check_consistency(&mut self) {
+        let ids: Vec<_> = self
+            .views
+            .iter()
+            .filter(|(id, view_state)| {
+                if let GlobalId::User(_) = id {
+                    view_state.default_idx.is_some()
+                } else {
+                    false
+                }
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            match self.catalog.get_by_id(&id).item() {
+                CatalogItem::View(view)
+                    if view.refresh_interval.is_none() && view.emit_final_after.is_none() => {}
+                _ => continue,
+            }
+            let name = self.catalog.humanize_id(Id::Global(id)).unwrap();
+            let mut session = Session::default();
+            // Like peeks at changes, consistency checks are never canceled,
+            // so they need not belong to a real connection.
+            let rows = match self.sequence_plan(&mut session, Plan::CheckView { id }, 0) {
+                Ok(ExecuteResponse::SendRows(rows)) => rows,
+                Ok(ExecuteResponse::StreamRows(stream)) => collect_rows(stream),
+                Ok(_) => unreachable!(),
+                Err(e) => {
+                    warn!("failed to check the consistency of {}: {}", name, e);
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                match rows.await {
+                    Ok((PeekResponse::Rows(rows), _)) if !rows.is_empty() => {
+                        error!(
+                            "view {} diverges from its definition in {} rows, e.g. {:?}",
+                            name,
+                            rows.len(),
+                            rows[0]
+                        );
+                        VIEW_DIVERGENCES
+                            .with_label_values(&[&id.to_string()])
+                            .inc_by(rows.len() as i64);
+                    }
+                    Ok(_) => (),
+                    Err(e) => warn!("failed to check the consistency of {}: {}", name, e),
+                }
+            });
+        }
+    }
+
+    /// Advances the local inputs to the current wall-clock time, if tables
+    /// are assigned wall-clock timestamps and no write has already advanced
+    /// them that far.
+    fn advance_local_inputs(&mut self) {
+        if self.quiesced.is_some() {
+            return;
+        }
+        if let LocalInputTimestamps::WallClock { granularity } = self.local_input_timestamps {
+            let now = wall_clock_tick(granularity);
+            if now > self.local_input_time {
+                self.local_input_time = now;
+                broadcast(
+                    &mut self.broadcast_tx,
+                    SequencedCommand::AdvanceLocalInputs { advance_to: now },
+                );
+            }
+        }
+    }
+
+    /// Returns the timestamp at which to write the next batch of updates to
+    /// the local inputs, and moves `local_input_time` past it.
+    fn next_local_input_time(&mut self) -> Timestamp {
+        match self.local_input_timestamps {
+            LocalInputTimestamps::Counter => {
+                let timestamp = self.local_input_time;
+                self.local_input_time += 1;
+                timestamp
+            }
+            LocalInputTimestamps::WallClock { granularity } => {
+                let timestamp = std::cmp::max(self.local_input_time, wall_clock_tick(granularity));
+                self.local_input_time = timestamp + granularity_millis(granularity);
+                timestamp
+            }
+        }
+    }
+
+    /// Compares the frontier of each view that has a freshness objective with
+    /// the wall clock, and records any change in whether the view meets its
+    /// objective to the log, to the metrics, and to `mz_view_freshness`.
+    fn check_freshness(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time seems to be before 1970.")
+            .as_millis() as Timestamp;
+        let mut changes = vec![];
+        for (id, view_state) in self.views.iter_mut() {
+            let freshness = match &mut view_state.freshness {
+                Some(freshness) => freshness,
+                None => continue,
+            };
+            // A view's lag is measured at its default index. Views without
+            // an index have no frontier, so their lag is unknown.
+            let index_state = match &view_state.default_idx {
+                Some((index_id, _)) => match self.indexes.get(index_id) {
+                    Some(index_state) => index_state,
+                    None => continue,
+                },
+                None => continue,
+            };
+            // An empty frontier means that the view is complete and cannot
+            // fall behind. An index that has not yet reported any progress
+            // has not yet computed its first results.
+            let lag = match index_state.upper.frontier().iter().min() {
+                None => 0,
+                Some(0) => continue,
+                Some(time) => now.saturating_sub(*time),
+            };
+            let label = id.to_string();
+            VIEW_LAG_MS.with_label_values(&[&label]).set(lag as i64);
+
+            let violating = lag > freshness.max_lag.as_millis() as Timestamp;
+            if violating == freshness.violating {
+                continue;
+            }
+            let old = freshness.clone();
+            freshness.violating = violating;
+            let name = self
+                .catalog
+                .humanize_id(Id::Global(*id))
+                .unwrap_or_else(|| label.clone());
+            if violating {
+                freshness.violations += 1;
+                VIEW_FRESHNESS_VIOLATIONS.with_label_values(&[&label]).inc();
+                warn!(
+                    "view {} is {}ms behind the wall clock, exceeding its max_lag of {}ms",
+                    name,
+                    lag,
+                    freshness.max_lag.as_millis()
+                );
+            } else {
+                info!("view {} is once again within its max_lag", name);
+            }
+            changes.push((*id, old, freshness.clone()));
+        }
+        for (id, old, new) in changes {
+            self.report_freshness(id, &old, false);
+            self.report_freshness(id, &new, true);
+        }
+    }
+
+    /// Inserts or retracts the state of a view's freshness objective in
+    /// `mz_view_freshness`.
+    fn report_freshness(&mut self, id: GlobalId, freshness: &Freshness, insert: bool) {
+        if self.log {
             broadcast(
                 &mut self.broadcast_tx,
-                SequencedCommand::AllowCompaction(std::mem::replace(
-                    &mut self.since_updates,
-                    Vec::new(),
-                )),
+                SequencedCommand::AppendLog(MaterializedEvent::Freshness {
+                    id,
+                    max_lag_ms: freshness.max_lag.as_millis() as u64,
+                    violating: freshness.violating,
+                    violations: freshness.violations,
+                    insert,
+                }),
+            );
+        }
+    }
+
+    /// Reports the progress of each index that is catching up with its inputs
+    /// to `mz_view_backfills`, at most once per `BACKFILL_REPORT_INTERVAL`,
+    /// and notifies the sessions that listen on `mz_backfills` of each index
+    /// that has caught up.
+    fn check_backfills(&mut self) {
+        let mut progress = vec![];
+        let mut caught_up = vec![];
+        for (id, index_state) in &self.indexes {
+            let backfill = match &index_state.backfill {
+                Some(backfill) => backfill,
+                None => continue,
+            };
+            let frontier = index_state.upper.frontier();
+            if frontier.iter().all(|time| *time >= backfill.target) {
+                caught_up.push(*id);
+                continue;
+            }
+            if let Some((_, reported_at)) = &backfill.reported {
+                if reported_at.elapsed() < BACKFILL_REPORT_INTERVAL {
+                    continue;
+                }
+            }
+            progress.push((
+                *id,
+                BackfillProgress {
+                    frontier: frontier.iter().min().copied().unwrap_or(0),
+                    records: index_state.total_records(),
+                    estimated_records: backfill
+                        .inputs
+                        .iter()
+                        .filter_map(|input| self.indexes.get(input))
+                        .map(|input| input.total_records())
+                        .sum(),
+                    elapsed_ms: backfill.started.elapsed().as_millis() as u64,
+                },
+            ));
+        }
+
+        for (id, progress) in progress {
+            let mut backfill = self.indexes.get_mut(&id).unwrap().backfill.take().unwrap();
+            self.retract_backfill(id, &backfill);
+            self.report_backfill(id, &backfill, &progress, true);
+            backfill.reported = Some((progress, Instant::now()));
+            self.indexes.get_mut(&id).unwrap().backfill = Some(backfill);
+        }
+
+        for id in caught_up {
+            let backfill = self.indexes.get_mut(&id).unwrap().backfill.take().unwrap();
+            self.retract_backfill(id, &backfill);
+            let name = self
+                .catalog
+                .humanize_id(Id::Global(id))
+                .unwrap_or_else(|| id.to_string());
+            info!(
+                "index {} caught up with its inputs after {}ms",
+                name,
+                backfill.started.elapsed().as_millis()
+            );
+            self.notify("mz_backfills", format!("complete {}", name));
+        }
+    }
+
+    /// Retracts the progress of `backfill` last reported to
+    /// `mz_view_backfills`, if any.
+    fn retract_backfill(&mut self, id: GlobalId, backfill: &Backfill) {
+        if let Some((progress, _)) = &backfill.reported {
+            self.report_backfill(id, backfill, progress, false);
+        }
+    }
+
+    /// Inserts or retracts the progress of the index `id` towards catching up
+    /// with its inputs in `mz_view_backfills`.
+    fn report_backfill(
+        &mut self,
+        id: GlobalId,
+        backfill: &Backfill,
+        progress: &BackfillProgress,
+        insert: bool,
+    ) {
+        if self.log {
+            broadcast(
+                &mut self.broadcast_tx,
+                SequencedCommand::AppendLog(MaterializedEvent::Backfill {
+                    id,
+                    target: backfill.target,
+                    frontier: progress.frontier,
+                    records: progress.records,
+                    estimated_records: progress.estimated_records,
+                    elapsed_ms: progress.elapsed_ms,
+                    insert,
+                }),
+            );
+        }
+    }
+
+    /// Records that the records of the source instance `id` up to some offset
+    /// have been assigned `timestamp`, if the source has event-time
+    /// timestamps.
+    fn observe_source_timestamp(&mut self, id: SourceInstanceId, timestamp: Timestamp) {
+        if let Some(latest) = self.event_time_sources.get_mut(&id) {
+            *latest = Some(cmp::max(latest.unwrap_or(0), timestamp));
+        }
+    }
+
+    /// Compares the event times of each source with event-time timestamps
+    /// with the wall clock and with the event times of the most advanced such
+    /// source, and reports the skew to the metrics and to `mz_source_skew`, at
+    /// most once per `SKEW_REPORT_INTERVAL`. A warning is logged when a
+    /// source's skew begins to exceed `MAX_SOURCE_SKEW_MS`, as a source that
+    /// runs behind holds back the frontiers of every view that joins it with
+    /// other sources.
+    fn check_source_skew(&mut self) {
+        if self.last_skew_report.elapsed() < SKEW_REPORT_INTERVAL {
+            return;
+        }
+        self.last_skew_report = Instant::now();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time seems to be before 1970.")
+            .as_millis() as i64;
+        // Instances of the same source share their timestamps, but an
+        // instance that has just been created may not have caught up yet.
+        let mut timestamps = HashMap::new();
+        for (id, timestamp) in &self.event_time_sources {
+            if let Some(timestamp) = timestamp {
+                let latest = timestamps.entry(id.sid).or_insert(0);
+                *latest = cmp::max(*latest, *timestamp);
+            }
+        }
+        let max = timestamps.values().max().copied().unwrap_or(0) as i64;
+        let skews: HashMap<_, _> = timestamps
+            .into_iter()
+            .map(|(id, timestamp)| {
+                let skew = SourceSkew {
+                    timestamp,
+                    wall_clock_skew_ms: timestamp as i64 - now,
+                    source_skew_ms: timestamp as i64 - max,
+                };
+                (id, skew)
+            })
+            .collect();
+
+        let mut changes = vec![];
+        for (id, old) in &self.source_skews {
+            if skews.get(id) != Some(old) {
+                changes.push((*id, old.clone(), false));
+            }
+            if !skews.contains_key(id) {
+                let _ = SOURCE_SKEW_MS.remove_label_values(&[&id.to_string()]);
+            }
+        }
+        for (id, new) in &skews {
+            SOURCE_SKEW_MS
+                .with_label_values(&[&id.to_string()])
+                .set(new.wall_clock_skew_ms);
+            let old = self.source_skews.get(id);
+            if old == Some(new) {
+                continue;
+            }
+            let was_skewed = old.map_or(false, SourceSkew::is_excessive);
+            if new.is_excessive() != was_skewed {
+                let name = self
+                    .catalog
+                    .humanize_id(Id::Global(*id))
+                    .unwrap_or_else(|| id.to_string());
+                if was_skewed {
+                    info!(
+                        "source {} is once again within {}ms of the wall clock and other sources",
+                        name, MAX_SOURCE_SKEW_MS
+                    );
+                } else {
+                    warn!(
+                        "source {} has event times {}ms from the wall clock and {}ms behind the most advanced source",
+                        name, new.wall_clock_skew_ms, -new.source_skew_ms
+                    );
+                }
+            }
+            changes.push((*id, new.clone(), true));
+        }
+        for (id, skew, insert) in changes {
+            self.report_source_skew(id, &skew, insert);
+        }
+        self.source_skews = skews;
+    }
+
+    /// Inserts or retracts the skew of the source `id` in `mz_source_skew`.
+    fn report_source_skew(&mut self, id: GlobalId, skew: &SourceSkew, insert: bool) {
+        if self.log {
+            broadcast(
+                &mut self.broadcast_tx,
+                SequencedCommand::AppendLog(MaterializedEvent::SourceSkew {
+                    id,
+                    timestamp: skew.timestamp,
+                    wall_clock_skew_ms: skew.wall_clock_skew_ms,
+                    source_skew_ms: skew.source_skew_ms,
+                    insert,
+                }),
             );
         }
     }
@@ -1563,6 +3533,15 @@ where
         let mut uses_ids = Vec::new();
         source.global_uses(&mut uses_ids);
 
+        // A peek that reads a view or source directly can be served by a
+        // replica of its index, so its timestamp may be determined by the
+        // freshest replica.
+        let direct = if let RelationExpr::Get { .. } = source {
+            true
+        } else {
+            false
+        };
+
         uses_ids.sort();
         uses_ids.dedup();
         if uses_ids.iter().any(|id| {
@@ -1585,6 +3564,14 @@ where
             // Explicitly requested timestamps should be respected.
             PeekWhen::AtTimestamp(timestamp) => timestamp,
 
+            // While the instance is quiesced, user views are read at the
+            // quiesced time, which they are all sure to reach.
+            PeekWhen::Immediately
+                if self.quiesced.is_some() && uses_ids.iter().all(|id| id.is_user()) =>
+            {
+                self.quiesced.unwrap()
+            }
+
             // These two strategies vary in terms of which traces drive the
             // timestamp determination process: either the trace itself or the
             // original sources on which they depend.
@@ -1592,8 +3579,13 @@ where
                 // Form lower bound on available times
                 let mut upper = Antichain::new();
                 for id in uses_ids.iter() {
+                    let id = if direct {
+                        self.freshest_replica(*id)
+                    } else {
+                        *id
+                    };
                     // To track the meet of `upper` we just extend with the upper frontier.
-                    upper.extend(self.upper_of(id).unwrap().iter().cloned());
+                    upper.extend(self.upper_of(&id).unwrap().iter().cloned());
                 }
 
                 // We peek at the largest element not in advance of `upper`, which
@@ -1646,10 +3638,15 @@ where
 
     /// Updates the upper frontier of a named view.
     fn update_upper(&mut self, name: &GlobalId, mut changes: ChangeBatch<Timestamp>) {
+        let mut completed = false;
+        let mut sink_advanced = false;
+        let quiesced = self.quiesced;
         if let Some(index_state) = self.indexes.get_mut(name) {
             let changes: Vec<_> = index_state.upper.update_iter(changes.drain()).collect();
             if !changes.is_empty() {
-                if self.log {
+                // Replicas are not catalog items, so their frontiers are not
+                // logged.
+                if self.log && index_state.replica_of.is_none() {
                     for (time, change) in changes {
                         broadcast(
                             &mut self.broadcast_tx,
@@ -1667,16 +3664,504 @@ where
                 // the case of a constant collection, this compaction is actively
                 // harmful. We should reconsider compaction policy with an eye
                 // towards minimizing unexpected screw-ups.
+                // While the instance is quiesced, the quiesced time must
+                // remain readable.
                 if let Some(compaction_latency_ms) = index_state.compaction_latency_ms {
                     let mut since = Antichain::new();
                     for time in index_state.upper.frontier().iter() {
-                        since.insert(time.saturating_sub(compaction_latency_ms));
+                        let time = time.saturating_sub(compaction_latency_ms);
+                        since.insert(match quiesced {
+                            Some(until) => cmp::min(time, until),
+                            None => time,
+                        });
+                    }
+                    if index_state.advance_since(&since) {
+                        self.since_updates
+                            .push((name.clone(), since.elements().to_vec()));
+                    }
+                }
+
+                completed = index_state.upper.frontier().is_empty();
+            }
+        } else if let Some(frontier) = self.sink_frontiers.get_mut(name) {
+            let changes: Vec<_> = frontier.update_iter(changes.drain()).collect();
+            completed = !changes.is_empty() && frontier.frontier().is_empty();
+            sink_advanced = !changes.is_empty();
+            if self.log {
+                for (time, change) in changes {
+                    broadcast(
+                        &mut self.broadcast_tx,
+                        SequencedCommand::AppendLog(MaterializedEvent::SinkFrontier(
+                            *name, time, change,
+                        )),
+                    );
+                }
+            }
+        }
+
+        if completed {
+            self.notify_completed(*name);
+        }
+        if sink_advanced {
+            self.advance_flushes();
+        }
+    }
+
+    /// Notifies listeners that the source or sink that `id` implements will
+    /// produce no further updates. A source is complete when its default
+    /// index is; the completion of other indexes is not reported.
+    fn notify_completed(&mut self, id: GlobalId) {
+        let entry = match self.catalog.try_get_by_id(&id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let (channel, name) = match entry.item() {
+            CatalogItem::Sink(_) => ("mz_sinks", entry.name()),
+            CatalogItem::Index(index) => {
+                let is_default = self
+                    .views
+                    .get(&index.on)
+                    .and_then(|view_state| view_state.default_idx.as_ref())
+                    .map_or(false, |(default_id, _)| *default_id == id);
+                match self.catalog.try_get_by_id(&index.on) {
+                    Some(on) if is_default => match on.item() {
+                        CatalogItem::Source(_) => ("mz_sources", on.name()),
+                        _ => return,
+                    },
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        let payload = format!("complete {}", name);
+        self.notify(channel, payload);
+    }
+
+    /// Records the number of updates and bytes held in the arrangements of
+    /// each dataflow on a worker.
+    fn update_arrangement_sizes(&mut self, worker_id: usize, sizes: Vec<(GlobalId, usize, usize)>) {
+        for (id, records, bytes) in sizes {
+            // The dataflow may have been dropped since the worker reported on
+            // it.
+            if let Some(index_state) = self.indexes.get_mut(&id) {
+                index_state.records.insert(worker_id, records);
+                index_state.bytes.insert(worker_id, bytes);
+                ARRANGEMENT_RECORDS
+                    .with_label_values(&[&id.to_string()])
+                    .set(index_state.total_records() as i64);
+                ARRANGEMENT_BYTES
+                    .with_label_values(&[&id.to_string()])
+                    .set(index_state.total_bytes() as i64);
+            }
+        }
+    }
+
+    /// Fails if the arrangements of all dataflows occupy more bytes than the
+    /// configured limit, in which case no new temporary dataflows may be
+    /// created.
+    ///
+    /// Temporary dataflows, like those of peeks that cannot be served from an
+    /// existing index, are refused rather than allowed to exhaust the memory
+    /// of the process. The indexes that users have created are never evicted.
+    fn check_arrangement_budget(&self) -> Result<(), failure::Error> {
+        if let Some(limit) = self.arrangement_memory_limit {
+            let bytes: usize = self.indexes.values().map(|i| i.total_bytes()).sum();
+            if bytes > limit {
+                bail!(
+                    "arrangements occupy {} bytes, more than the limit of {}; \
+                     query an existing index or drop unneeded indexes and retry",
+                    bytes,
+                    limit
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the dataflow objects installed on a worker with those that the
+    /// catalog calls for, and reports any discrepancies, which indicate a bug
+    /// in recovery, to the log and to `mz_catalog_discrepancies`.
+    fn check_inventory(&mut self, worker_id: usize, inventory: Inventory) {
+        let mut expected = Inventory {
+            cluster: inventory.cluster.clone(),
+            ..Inventory::default()
+        };
+        for entry in self.catalog.iter() {
+            // Dataflows that depend on failed items are deliberately not
+            // installed.
+            if self.catalog.failed_dependency(entry.id()).is_some() {
+                continue;
+            }
+            match (entry.id(), entry.item()) {
+                (id, CatalogItem::Index(index)) if index.cluster == inventory.cluster => {
+                    expected.arrangements.push(id);
+                }
+                (id, CatalogItem::Sink(sink)) if sink.cluster == inventory.cluster => {
+                    expected.sinks.push(id);
+                }
+                // User sources without an external connector are tables,
+                // which live on the default cluster.
+                (
+                    id @ GlobalId::User(_),
+                    CatalogItem::Source(catalog::Source {
+                        connector: SourceConnector::Local,
+                        ..
+                    }),
+                ) if inventory.cluster.is_none() => {
+                    expected.local_inputs.push(id);
+                }
+                _ => (),
+            }
+        }
+        for (id, index_state) in &self.indexes {
+            if index_state.replica_of.is_some() && index_state.cluster == inventory.cluster {
+                expected.arrangements.push(*id);
+            }
+        }
+
+        let cluster = inventory.cluster.clone();
+        for (id, object, problem) in find_discrepancies(expected, inventory) {
+            error!(
+                "catalog inconsistent with worker {} of cluster {}: {} for {} is {}",
+                worker_id,
+                cluster.as_deref().unwrap_or("default"),
+                object,
+                self.catalog
+                    .humanize_id(Id::Global(id))
+                    .unwrap_or_else(|| id.to_string()),
+                problem
+            );
+            if self.log {
+                broadcast(
+                    &mut self.broadcast_tx,
+                    SequencedCommand::AppendLog(MaterializedEvent::Discrepancy {
+                        id,
+                        worker: worker_id,
+                        object: object.into(),
+                        problem: problem.into(),
+                    }),
+                );
+            }
+        }
+    }
+
+    /// Returns an error if any of the items identified by `ids` depends upon a
+    /// failed item, as such items cannot be computed.
+    fn check_available(&self, ids: &[GlobalId]) -> Result<(), failure::Error> {
+        for id in ids {
+            if let Some(entry) = self.catalog.failed_dependency(*id) {
+                return Err(dataflow_types::Error::Connector(format!(
+                    "{} '{}' is unavailable: {}",
+                    entry.item().type_string(),
+                    entry.name(),
+                    entry.error().unwrap()
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports whether any of the items identified by `ids` is, or depends
+    /// upon, a table. Tables are timestamped by a logical clock rather than by
+    /// the wall clock.
+    fn depends_on_table(&self, ids: &[GlobalId]) -> bool {
+        ids.iter()
+            .any(|id| match self.catalog.get_by_id(id).item() {
+                CatalogItem::Source(catalog::Source {
+                    connector: SourceConnector::Local,
+                    ..
+                }) => id.is_user(),
+                item => self.depends_on_table(&item.uses()),
+            })
+    }
+
+    /// Returns an input among `ids`, or among their transitive inputs, whose
+    /// timestamps are not milliseconds of wall-clock time, if any.
+    ///
+    /// These are the user tables, unless tables are timestamped by the wall
+    /// clock, and the sources whose timestamps are dictated by a consistency
+    /// topic.
+    fn logical_time_input(&self, ids: &[GlobalId]) -> Option<GlobalId> {
+        ids.iter()
+            .find_map(|id| match self.catalog.get_by_id(id).item() {
+                CatalogItem::Source(catalog::Source {
+                    connector: SourceConnector::Local,
+                    ..
+                }) => match self.local_input_timestamps {
+                    LocalInputTimestamps::Counter if id.is_user() => Some(*id),
+                    _ => None,
+                },
+                CatalogItem::Source(catalog::Source {
+                    connector:
+                        SourceConnector::External {
+                            consistency: Consistency::BringYourOwn(_),
+                            ..
+                        },
+                    ..
+                }) => Some(*id),
+                item => self.logical_time_input(&item.uses()),
+            })
+    }
+
+    /// Returns the command channel of `cluster`, where `None` names the
+    /// default cluster.
+    fn cluster_tx(
+        &mut self,
+        cluster: &Option<String>,
+    ) -> &mut comm::broadcast::Sender<SequencedCommand> {
+        match cluster {
+            None => &mut self.broadcast_tx,
+            Some(name) => {
+                &mut self
+                    .clusters
+                    .get_mut(name)
+                    .expect("unknown cluster")
+                    .broadcast_tx
+            }
+        }
+    }
+
+    /// Returns the number of workers in `cluster`, where `None` names the
+    /// default cluster.
+    fn cluster_workers(&self, cluster: &Option<String>) -> usize {
+        match cluster {
+            None => self.num_timely_workers,
+            Some(name) => self.clusters[name].num_workers,
+        }
+    }
+
+    /// Sends `cmd` to the workers of every cluster.
+    fn broadcast_all(&mut self, cmd: SequencedCommand) {
+        for cluster in self.clusters.values_mut() {
+            broadcast(&mut cluster.broadcast_tx, cmd.clone());
+        }
+        broadcast(&mut self.broadcast_tx, cmd);
+    }
+
+    /// Returns an error if `cluster` does not exist, or cannot compute
+    /// dataflows that read the items identified by `uses`.
+    ///
+    /// Tables and logging sources are only fed to the default cluster, so
+    /// other clusters cannot read them.
+    fn check_cluster(
+        &self,
+        cluster: &Option<String>,
+        uses: &[GlobalId],
+    ) -> Result<(), failure::Error> {
+        let name = match cluster {
+            None => return Ok(()),
+            Some(name) => name,
+        };
+        if !self.clusters.contains_key(name) {
+            bail!("cluster '{}' does not exist", name);
+        }
+        let mut stack = uses.to_vec();
+        while let Some(id) = stack.pop() {
+            let entry = self.catalog.get_by_id(&id);
+            match entry.item() {
+                CatalogItem::Source(catalog::Source {
+                    connector: SourceConnector::Local,
+                    ..
+                }) => bail!(
+                    "cluster '{}' cannot read {}, which is only available on the default cluster",
+                    name,
+                    entry.name()
+                ),
+                item => stack.extend(item.uses()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an index on the view or source identified by `id` that is
+    /// maintained by `cluster`, preferring the default index, if any.
+    fn index_in_cluster(
+        &self,
+        id: &GlobalId,
+        cluster: &Option<String>,
+    ) -> Option<(GlobalId, Vec<ScalarExpr>)> {
+        let view_state = &self.views[id];
+        let in_cluster = |index_id: &GlobalId| &self.indexes[index_id].cluster == cluster;
+        if let Some((index_id, keys)) = &view_state.default_idx {
+            if in_cluster(index_id) {
+                return Some((*index_id, keys.clone()));
+            }
+        }
+        view_state.primary_idxes.iter().find_map(|(keys, ids)| {
+            ids.iter()
+                .find(|index_id| in_cluster(index_id))
+                .map(|index_id| (*index_id, keys.clone()))
+        })
+    }
+
+    /// Returns an index on the view or source identified by `id` whose key
+    /// columns are all pinned to constants by `filter`, along with the key
+    /// that `filter` selects, if there is such an index.
+    fn lookup_key(
+        &self,
+        id: GlobalId,
+        filter: &[ScalarExpr],
+        eval_env: &EvalEnv,
+    ) -> Option<(GlobalId, Row)> {
+        fn collect_pinned(expr: &ScalarExpr, pinned: &mut HashMap<usize, ScalarExpr>) {
+            if let ScalarExpr::CallBinary { func, expr1, expr2 } = expr {
+                match (func, &**expr1, &**expr2) {
+                    (BinaryFunc::And, _, _) => {
+                        collect_pinned(expr1, pinned);
+                        collect_pinned(expr2, pinned);
+                    }
+                    (BinaryFunc::Eq, ScalarExpr::Column(c), lit)
+                    | (BinaryFunc::Eq, lit, ScalarExpr::Column(c))
+                        if lit.is_literal() && !lit.is_literal_null() =>
+                    {
+                        pinned.insert(*c, lit.clone());
                     }
-                    self.since_updates
-                        .push((name.clone(), since.elements().to_vec()));
+                    _ => (),
                 }
             }
         }
+
+        let mut pinned = HashMap::new();
+        for predicate in filter {
+            let mut predicate = predicate.clone();
+            predicate.reduce(eval_env);
+            collect_pinned(&predicate, &mut pinned);
+        }
+
+        let view_state = self.views.get(&id)?;
+        view_state.primary_idxes.iter().find_map(|(keys, ids)| {
+            let columns = keys
+                .iter()
+                .map(|key| match key {
+                    ScalarExpr::Column(c) if pinned.contains_key(c) => Some(*c),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            if columns.is_empty() {
+                return None;
+            }
+            let index_id = ids
+                .iter()
+                .find(|index_id| self.indexes.contains_key(index_id))?;
+            let key = Row::pack(columns.iter().map(|c| pinned[c].as_literal().unwrap()));
+            Some((*index_id, key))
+        })
+    }
+
+    /// Chooses the cluster on which to build a transient dataflow that
+    /// computes `expr`: the cluster that maintains the default indexes of all
+    /// the views and sources that `expr` reads, if they agree, or the default
+    /// cluster otherwise.
+    fn transient_cluster(&self, expr: &RelationExpr) -> Option<String> {
+        let mut uses = vec![];
+        expr.global_uses(&mut uses);
+        let mut clusters = uses.iter().filter_map(|id| {
+            let (index_id, _) = self.views.get(id)?.default_idx.as_ref()?;
+            Some(&self.indexes[index_id].cluster)
+        });
+        let cluster = match clusters.next() {
+            Some(first) if clusters.all(|cluster| cluster == first) => first.clone(),
+            _ => return None,
+        };
+        match self.check_cluster(&cluster, &uses) {
+            Ok(()) => cluster,
+            Err(_) => None,
+        }
+    }
+
+    /// Builds a transient dataflow that computes `source` as of `timestamp`
+    /// into an index with ID `index_id`, for a peek to read and then drop.
+    ///
+    /// If `profile` is set, the workers gather the statistics of the
+    /// dataflow's operators, and report them to the peek in place of the
+    /// index's contents.
+    fn build_transient_index(
+        &mut self,
+        index_id: GlobalId,
+        source: OptimizedRelationExpr,
+        timestamp: Timestamp,
+        eval_env: &EvalEnv,
+        cluster: &Option<String>,
+        profile: bool,
+    ) -> Result<catalog::Index, failure::Error> {
+        self.check_arrangement_budget()?;
+        let typ = source.as_ref().typ();
+        let ncols = typ.column_types.len();
+        // Cheat a little bit here to get a relation description. A
+        // relation description is just a relation type with column
+        // names, but we don't know the column names for `source`
+        // here. Nothing in the dataflow layer cares about column
+        // names, so just set them all to `None`. The column names
+        // will ultimately be correctly transmitted to the client
+        // because they are safely stashed in the connection's
+        // session.
+        let desc = RelationDesc::new(
+            typ.clone(),
+            iter::repeat::<Option<ColumnName>>(None).take(ncols),
+        );
+        let view_id = self.allocate_transient_id()?;
+        let view_name = FullName {
+            database: DatabaseSpecifier::Ambient,
+            schema: "temp".into(),
+            item: format!("temp-view-{}", view_id),
+        };
+        let index_name = format!("temp-index-on-{}", view_id);
+        let mut dataflow = DataflowDesc::new(view_name.to_string());
+        dataflow.as_of(Some(vec![timestamp]));
+        dataflow.cluster = cluster.clone();
+        dataflow.profile = profile;
+        let view = catalog::View {
+            create_sql: "<none>".into(),
+            expr: source,
+            desc,
+            eval_env: eval_env.clone(),
+            refresh_interval: None,
+            changes_only: false,
+            emit_final_after: None,
+            max_lag: None,
+        };
+        self.build_view_collection(&view_id, &view, &mut dataflow);
+        let index = auto_generate_view_idx(index_name, view_name, &view, view_id, cluster.clone());
+        self.build_arrangement(&index_id, index.clone(), typ, dataflow);
+        Ok(index)
+    }
+
+    /// Returns whichever of the index identified by `id` and its replicas has
+    /// the most advanced upper frontier.
+    fn freshest_replica(&self, id: GlobalId) -> GlobalId {
+        let progress = |id: &GlobalId| {
+            self.indexes[id]
+                .upper
+                .frontier()
+                .iter()
+                .next()
+                .cloned()
+                .unwrap_or(Timestamp::max_value())
+        };
+        iter::once(id)
+            .chain(self.indexes[&id].replicas.iter().cloned())
+            .max_by_key(progress)
+            .unwrap()
+    }
+
+    /// Chooses which of the index identified by `id` and its replicas should
+    /// serve a peek at `timestamp`.
+    ///
+    /// Peeks are spread in turn across the copies whose upper frontier is
+    /// beyond `timestamp`, as they can serve the peek immediately. If there are
+    /// none, the freshest copy is chosen, as it is likely to be the first
+    /// able to.
+    fn choose_replica(&mut self, id: GlobalId, timestamp: Timestamp) -> GlobalId {
+        let ready: Vec<_> = iter::once(id)
+            .chain(self.indexes[&id].replicas.iter().cloned())
+            .filter(|id| !self.indexes[id].upper.less_equal(&timestamp))
+            .collect();
+        if ready.is_empty() {
+            return self.freshest_replica(id);
+        }
+        self.replica_rotation = self.replica_rotation.wrapping_add(1);
+        ready[self.replica_rotation % ready.len()]
     }
 
     /// The upper frontier of a maintained index, if it exists.
@@ -1719,6 +4204,15 @@ where
                 uses,
             ),
         );
+        if let Some(max_lag) = view.max_lag {
+            let freshness = Freshness {
+                max_lag,
+                violating: false,
+                violations: 0,
+            };
+            self.report_freshness(view_id, &freshness, true);
+            self.views.get_mut(&view_id).unwrap().freshness = Some(freshness);
+        }
     }
 
     /// Add an index to a view in the coordinator.
@@ -1735,7 +4229,8 @@ where
                 self.propagate_queryability(&index.on);
             }
         } // else the view is temporary
-        let mut index_state = IndexState::new(self.num_timely_workers);
+        let mut index_state =
+            IndexState::new(self.cluster_workers(&index.cluster), index.cluster.clone());
         if latency_ms.is_some() {
             index_state.set_compaction_latency(latency_ms);
         }
@@ -1779,6 +4274,7 @@ where
         &mut self,
         session: Session,
         portal_name: String,
+        conn_id: u32,
         tx: ClientTx,
     ) -> MaybeFuture<'static, (Session, ClientTx, Result<Plan, failure::Error>)> {
         let res = session
@@ -1803,9 +4299,13 @@ where
             }
         };
         match prepared.sql() {
-            Some(stmt) => self
-                .handle_statement(&session, stmt.clone(), &portal.parameters)
-                .map(|res| (session, tx, res)),
+            Some(stmt) => {
+                if let Some(command_log) = &mut self.command_log {
+                    command_log.record(conn_id, stmt, &portal.parameters);
+                }
+                self.handle_statement(&session, stmt.clone(), &portal.parameters)
+                    .map(|res| (session, tx, res))
+            }
             None => (session, tx, Ok(Plan::EmptyQuery)).into(),
         }
     }
@@ -1842,17 +4342,352 @@ where
     }
 }
 
+/// Compares the dataflow objects `installed` on a worker with those that are
+/// `expected` to be installed on it, and returns the ID, the kind of object,
+/// and the problem of each discrepancy, in order of ID.
+fn find_discrepancies(
+    expected: Inventory,
+    installed: Inventory,
+) -> Vec<(GlobalId, &'static str, &'static str)> {
+    let mut discrepancies = vec![];
+    for (object, expected, installed) in vec![
+        ("arrangement", expected.arrangements, installed.arrangements),
+        ("input", expected.local_inputs, installed.local_inputs),
+        ("sink", expected.sinks, installed.sinks),
+    ] {
+        let expected: HashSet<_> = expected.into_iter().collect();
+        let installed: HashSet<_> = installed.into_iter().collect();
+        for id in expected.difference(&installed) {
+            discrepancies.push((*id, object, "missing"));
+        }
+        for id in installed.difference(&expected) {
+            discrepancies.push((*id, object, "orphaned"));
+        }
+    }
+    discrepancies.sort();
+    discrepancies
+}
+
+/// Classifies `err` using `class`, unless it is already classified.
+fn classify(err: failure::Error, class: fn(String) -> dataflow_types::Error) -> failure::Error {
+    if err.downcast_ref::<dataflow_types::Error>().is_some() {
+        err
+    } else {
+        class(err.to_string()).into()
+    }
+}
+
+/// Checks that the schema of the data upstream of `source` is still
+/// compatible with the schema that the source was created with.
+///
+/// Only Avro sources that use a schema registry can be checked. If the
+/// registry cannot be reached within [`SCHEMA_VALIDATION_TIMEOUT`], the source
+/// is assumed to be compatible, so that a temporary outage neither fails it
+/// permanently nor stalls startup. Registries in `unreachable` are not
+/// contacted again, so that an outage costs at most one timeout per registry.
+fn validate_source_schema(
+    source: &catalog::Source,
+    unreachable: &mut HashSet<Url>,
+) -> Result<(), failure::Error> {
+    let (topic, encoding) = match &source.connector {
+        SourceConnector::External {
+            connector: ExternalSourceConnector::Kafka(kafka),
+            encoding: DataEncoding::Avro(encoding),
+            ..
+        } => (&kafka.topic, encoding),
+        _ => return Ok(()),
+    };
+    let url = match &encoding.schema_registry_url {
+        Some(url) if !unreachable.contains(url) => url.clone(),
+        _ => return Ok(()),
+    };
+    let subject = format!("{}-value", topic);
+    let client = ccsr::Client::with_timeout(url.clone(), SCHEMA_VALIDATION_TIMEOUT);
+    let writer_schema = match client.get_schema_by_subject(&subject) {
+        Ok(schema) => schema.raw,
+        Err(e) => {
+            warn!(
+                "unable to validate schema for subject '{}' against registry: {}",
+                subject, e
+            );
+            if let ccsr::GetBySubjectError::Transport(_) = e {
+                unreachable.insert(url);
+            }
+            return Ok(());
+        }
+    };
+    interchange::avro::validate_schema_compatibility(&writer_schema, &source.desc).map_err(|e| {
+        failure::format_err!(
+            "latest schema for subject '{}' is incompatible with the source: {}",
+            subject,
+            e
+        )
+    })
+}
+
+/// Checks that the plugin through which `source` is read, if any, is
+/// registered and accepts the source's options.
+fn validate_source_plugin(source: &catalog::Source) -> Result<(), failure::Error> {
+    let connector = match &source.connector {
+        SourceConnector::External {
+            connector: ExternalSourceConnector::Plugin(connector),
+            ..
+        } => connector,
+        _ => return Ok(()),
+    };
+    match dataflow::source_plugin(&connector.name) {
+        Some(factory) => factory.validate(&connector.options),
+        None => bail!("source plugin '{}' is not registered", connector.name),
+    }
+}
+
+/// Checks that the plugin through which `sink` is written, if any, is
+/// registered and accepts the sink's options.
+fn validate_sink_plugin(sink: &catalog::Sink) -> Result<(), failure::Error> {
+    let connector = match &sink.connector {
+        SinkConnector::Plugin(connector) => connector,
+        _ => return Ok(()),
+    };
+    match dataflow::sink_plugin(&connector.name) {
+        Some(factory) => factory.validate(&connector.options),
+        None => bail!("sink plugin '{}' is not registered", connector.name),
+    }
+}
+
+/// Returns the current wall-clock time in milliseconds since the Unix epoch,
+/// rounded down to a multiple of `granularity`.
+fn wall_clock_tick(granularity: Duration) -> Timestamp {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before Unix epoch")
+        .as_millis() as u64;
+    let granularity = granularity_millis(granularity);
+    now - now % granularity
+}
+
+fn granularity_millis(granularity: Duration) -> u64 {
+    std::cmp::max(granularity.as_millis() as u64, 1)
+}
+
 fn broadcast(tx: &mut comm::broadcast::Sender<SequencedCommand>, cmd: SequencedCommand) {
     // TODO(benesch): avoid flushing after every send.
     block_on(tx.send(cmd)).unwrap();
 }
 
+/// Combines the batches of results with which `num_workers` workers answer a
+/// peek into a single response, using `merge` to fold each batch into the
+/// response so far.
+///
+/// If the stream of results ends before every worker has sent its
+/// end-of-results marker, some results are missing, and the response is an
+/// error.
+fn fold_peek_results<F>(
+    rx: comm::mpsc::Receiver<PeekMessage>,
+    num_workers: usize,
+    init: PeekResponse,
+    merge: F,
+) -> impl Future<Output = Result<(PeekResponse, Option<PeekMetadata>), comm::Error>>
+where
+    F: Fn(PeekResponse, PeekResponse) -> PeekResponse,
+{
+    rx.try_fold((init, None, 0), move |(memo, metadata, done), message| {
+        future::ok(match message {
+            PeekMessage::Batch(resp) => (merge(memo, resp), metadata, done),
+            PeekMessage::Run(_) => unreachable!("peek streams merge the workers' runs"),
+            PeekMessage::Done(worker_metadata) => {
+                let metadata = match metadata {
+                    None => worker_metadata,
+                    Some(metadata) => PeekMetadata::merge(metadata, worker_metadata),
+                };
+                (memo, Some(metadata), done + 1)
+            }
+        })
+    })
+    .map_ok(move |(resp, metadata, done)| {
+        if done < num_workers {
+            let err = dataflow_types::Error::Internal(format!(
+                "results from {} of {} workers ended unexpectedly",
+                num_workers - done,
+                num_workers
+            ));
+            (PeekResponse::Error(err), None)
+        } else {
+            (resp, metadata)
+        }
+    })
+}
+
+/// Forwards the rows with which the workers answer a peek, whose `finishing`
+/// must be trivial, as they arrive, and then yields the combined metadata of
+/// the workers. Each worker answers through the receiver in `rxs` at its
+/// index.
+///
+/// Each worker sends its rows in runs, ordered by their key in the arrangement
+/// that the worker reads, which are merged into a single sequence of rows in
+/// the same order. The merged order depends only on the contents of the
+/// arrangement, so whether a client fetches the rows all at once or a few at a
+/// time never changes it. A worker's next run is read only once the rows of
+/// its previous runs have all been merged, so at most one run per worker is
+/// held here. The rest wait in the workers' delivery tasks.
+///
+/// As in [`fold_peek_results`], if the stream of results ends before every
+/// worker has sent its end-of-results marker, the last batch is an error.
+fn stream_peek_results(
+    rxs: Vec<comm::mpsc::Receiver<PeekMessage>>,
+    finishing: RowSetFinishing,
+) -> impl Stream<Item = Result<PeekMessage, comm::Error>> {
+    let num_workers = rxs.len();
+    let state = (rxs, RunMerge::new(num_workers), None);
+    stream::unfold(Some(state), move |state| {
+        let finishing = finishing.clone();
+        async move {
+            let (mut rxs, mut runs, mut metadata) = state?;
+            loop {
+                let mut rows = runs.pop(PEEK_BATCH_SIZE);
+                if !rows.is_empty() {
+                    // The rows are already in order, so they are only
+                    // projected.
+                    for row in &mut rows {
+                        let datums = row.unpack();
+                        let projected = Row::pack(finishing.project.iter().map(|i| datums[*i]));
+                        *row = projected;
+                    }
+                    let message = PeekMessage::Batch(PeekResponse::Rows(rows));
+                    return Some((Ok(message), Some((rxs, runs, metadata))));
+                }
+                // Each worker sends its end-of-results marker after its last
+                // run, so once no worker is waited on, every row has been
+                // forwarded.
+                let worker = match runs.waiting() {
+                    Some(worker) => worker,
+                    None => return Some((Ok(PeekMessage::Done(metadata.unwrap())), None)),
+                };
+                match rxs[worker].next().await {
+                    Some(Ok(PeekMessage::Run(run))) => runs.push(worker, run),
+                    Some(Ok(PeekMessage::Batch(resp))) => {
+                        // Rows only arrive in runs, so any other batch is an
+                        // error or a cancellation, which ends the response.
+                        return Some((Ok(PeekMessage::Batch(resp)), None));
+                    }
+                    Some(Ok(PeekMessage::Done(worker_metadata))) => {
+                        metadata = Some(match metadata {
+                            None => worker_metadata,
+                            Some(metadata) => PeekMetadata::merge(metadata, worker_metadata),
+                        });
+                        runs.finish(worker);
+                    }
+                    Some(Err(err)) => return Some((Err(err), None)),
+                    None => {
+                        let err = dataflow_types::Error::Internal(format!(
+                            "results from worker {} of {} ended unexpectedly",
+                            worker, num_workers
+                        ));
+                        let message = PeekMessage::Batch(PeekResponse::Error(err));
+                        return Some((Ok(message), None));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Merges the runs of rows that each worker sends into a single sequence of
+/// rows, ordered by the key with which each group of rows arrived, and then by
+/// the index of the worker that sent it.
+struct RunMerge {
+    /// The groups of rows of each worker that have arrived but not yet been
+    /// merged.
+    runs: Vec<VecDeque<(Row, Vec<Row>)>>,
+    /// Whether each worker has sent its last run.
+    finished: Vec<bool>,
+}
+
+impl RunMerge {
+    fn new(num_workers: usize) -> RunMerge {
+        RunMerge {
+            runs: vec![VecDeque::new(); num_workers],
+            finished: vec![false; num_workers],
+        }
+    }
+
+    /// Adds the next run of `worker`.
+    fn push(&mut self, worker: usize, run: Vec<(Row, Vec<Row>)>) {
+        self.runs[worker].extend(run);
+    }
+
+    /// Records that `worker` has sent its last run.
+    fn finish(&mut self, worker: usize) {
+        self.finished[worker] = true;
+    }
+
+    /// Returns a worker whose next run must arrive before any more rows can
+    /// be merged, if there is one.
+    fn waiting(&self) -> Option<usize> {
+        (0..self.runs.len()).find(|worker| self.runs[*worker].is_empty() && !self.finished[*worker])
+    }
+
+    /// Removes and returns, in order, the groups of rows that precede every
+    /// group yet to arrive, until at least `max` rows are returned.
+    ///
+    /// A worker whose rows have all been merged may still send a group that
+    /// precedes the groups of the other workers, unless it has sent its last
+    /// run, so no rows are merged while any worker is waited on.
+    fn pop(&mut self, max: usize) -> Vec<Row> {
+        let mut rows = vec![];
+        while rows.len() < max && self.waiting().is_none() {
+            let mut next: Option<(usize, &Row)> = None;
+            for (worker, run) in self.runs.iter().enumerate() {
+                if let Some((key, _)) = run.front() {
+                    if next.map_or(true, |(_, next)| key < next) {
+                        next = Some((worker, key));
+                    }
+                }
+            }
+            match next {
+                Some((worker, _)) => rows.extend(self.runs[worker].pop_front().unwrap().1),
+                None => break,
+            }
+        }
+        rows
+    }
+}
+
+/// Combines the profiles that the workers report for the dataflow of an
+/// `EXPLAIN ANALYZE`, each of which holds one row per operator, into one row
+/// per operator whose records and elapsed time are summed across workers.
+fn combine_profiles(rows: Vec<Row>) -> Vec<Row> {
+    let mut operators = BTreeMap::new();
+    for row in rows {
+        let datums = row.unpack();
+        let id = datums[0].unwrap_int64();
+        let name = datums[1].unwrap_str().to_owned();
+        let (records, elapsed_ns) = operators.entry((id, name)).or_insert((0, 0));
+        *records += datums[2].unwrap_int64();
+        *elapsed_ns += datums[3].unwrap_int64();
+    }
+    operators
+        .into_iter()
+        .map(|((id, name), (records, elapsed_ns))| {
+            let elapsed = Interval {
+                duration: Duration::from_nanos(elapsed_ns as u64),
+                ..Default::default()
+            };
+            Row::pack(&[
+                Datum::Int64(id),
+                Datum::String(&name),
+                Datum::Int64(records),
+                Datum::from(elapsed),
+            ])
+        })
+        .collect()
+}
+
 /// Constructs an [`ExecuteResponse`] that that will send some rows to the
 /// client immediately, as opposed to asking the dataflow layer to send along
 /// the rows after some computation.
 fn send_immediate_rows(rows: Vec<Row>) -> ExecuteResponse {
     let (tx, rx) = futures::channel::oneshot::channel();
-    tx.send(PeekResponse::Rows(rows)).unwrap();
+    tx.send((PeekResponse::Rows(rows), None)).unwrap();
     ExecuteResponse::SendRows(Box::pin(rx.err_into()))
 }
 
@@ -1863,24 +4698,49 @@ pub struct IndexState {
     /// The compaction frontier.
     /// All peeks in advance of this frontier will be correct,
     /// but peeks not in advance of this frontier may not be.
-    #[allow(dead_code)]
+    ///
+    /// This is the frontier up to which the workers have been allowed to
+    /// compact the index. It only ever advances.
     since: Antichain<Timestamp>,
     /// Compaction delay.
     ///
     /// This timestamp drives the advancement of the since frontier as a
     /// function of the upper frontier, trailing it by exactly this much.
     compaction_latency_ms: Option<Timestamp>,
+    /// The compute cluster that maintains the index, or `None` for the
+    /// default cluster.
+    cluster: Option<String>,
+    /// The arrangements that replicate this index on other clusters.
+    replicas: Vec<GlobalId>,
+    /// The index that this arrangement replicates, if it is a replica.
+    replica_of: Option<GlobalId>,
+    /// The number of updates held in the arrangement, as last reported by
+    /// each worker that maintains it.
+    records: HashMap<usize, usize>,
+    /// The approximate number of bytes held in the arrangements of the
+    /// index's dataflow, as last reported by each worker that maintains it.
+    bytes: HashMap<usize, usize>,
+    /// The progress of the index towards catching up with its inputs, if it
+    /// has not yet caught up.
+    backfill: Option<Backfill>,
 }
 
 impl IndexState {
-    /// Creates an empty index state from a number of workers.
-    pub fn new(workers: usize) -> Self {
+    /// Creates an empty index state from the cluster that maintains the
+    /// index and its number of workers.
+    pub fn new(workers: usize, cluster: Option<String>) -> Self {
         let mut upper = MutableAntichain::new();
         upper.update_iter(Some((0, workers as i64)));
         Self {
             upper,
             since: Antichain::from_elem(0),
             compaction_latency_ms: Some(60_000),
+            cluster,
+            replicas: vec![],
+            replica_of: None,
+            records: HashMap::new(),
+            bytes: HashMap::new(),
+            backfill: None,
         }
     }
 
@@ -1888,6 +4748,87 @@ impl IndexState {
     pub fn set_compaction_latency(&mut self, latency_ms: Option<Timestamp>) {
         self.compaction_latency_ms = latency_ms;
     }
+
+    /// Advances the compaction frontier to `since`, and reports whether it
+    /// changed.
+    ///
+    /// The frontier does not move backwards, e.g. when the compaction latency
+    /// grows, as the history that has been compacted cannot be recovered. Nor
+    /// does it advance to the empty frontier, at which the index could not be
+    /// read at all.
+    fn advance_since(&mut self, since: &Antichain<Timestamp>) -> bool {
+        if since.elements().is_empty()
+            || since.elements() == self.since.elements()
+            || !since.elements().iter().all(|t| self.since.less_equal(t))
+        {
+            return false;
+        }
+        self.since = since.clone();
+        true
+    }
+
+    /// Returns the number of updates held in the arrangement across all
+    /// workers.
+    fn total_records(&self) -> usize {
+        self.records.values().sum()
+    }
+
+    /// Returns the approximate number of bytes held in the arrangements of
+    /// the index's dataflow across all workers.
+    fn total_bytes(&self) -> usize {
+        self.bytes.values().sum()
+    }
+
+    /// Returns the time to which the index is being compacted, or zero if
+    /// it is not being compacted.
+    fn compaction_since(&self) -> Timestamp {
+        self.since.elements().get(0).copied().unwrap_or(0)
+    }
+}
+
+/// The progress of an index towards catching up with its inputs after it
+/// was created.
+struct Backfill {
+    /// When the index was created.
+    started: Instant,
+    /// The frontier that the index must reach to have caught up.
+    target: Timestamp,
+    /// The indexes that the index reads. The updates that they hold estimate
+    /// the number that the index must process.
+    inputs: Vec<GlobalId>,
+    /// The progress last reported to `mz_view_backfills`, if any, and when it
+    /// was reported.
+    reported: Option<(BackfillProgress, Instant)>,
+}
+
+#[derive(Clone)]
+struct BackfillProgress {
+    frontier: Timestamp,
+    records: usize,
+    estimated_records: usize,
+    elapsed_ms: u64,
+}
+
+/// The skew of a source with event-time timestamps.
+#[derive(Clone, PartialEq)]
+struct SourceSkew {
+    /// The latest timestamp assigned to the source's records.
+    timestamp: Timestamp,
+    /// How far `timestamp` runs ahead of the wall clock, in milliseconds.
+    /// Negative if it runs behind.
+    wall_clock_skew_ms: i64,
+    /// How far `timestamp` runs behind the latest timestamp of the most
+    /// advanced source with event-time timestamps, in milliseconds. Never
+    /// positive.
+    source_skew_ms: i64,
+}
+
+impl SourceSkew {
+    /// Whether the skew exceeds `MAX_SOURCE_SKEW_MS`.
+    fn is_excessive(&self) -> bool {
+        self.wall_clock_skew_ms.abs() > MAX_SOURCE_SKEW_MS
+            || -self.source_skew_ms > MAX_SOURCE_SKEW_MS
+    }
 }
 
 /// Per-view state.
@@ -1902,6 +4843,20 @@ pub struct ViewState {
     primary_idxes: BTreeMap<Vec<ScalarExpr>, Vec<GlobalId>>,
     // TODO(andiwang): materialize#220 Implement seconary indexes
     // secondary_idxes: BTreeMap<Vec<ScalarExpr>, Vec<GlobalId>>,
+    /// The view's freshness objective, if it has one.
+    freshness: Option<Freshness>,
+}
+
+/// A view's freshness objective, and whether the view meets it.
+#[derive(Clone)]
+struct Freshness {
+    /// How far the view's results may trail the wall clock.
+    max_lag: Duration,
+    /// Whether the view currently trails the wall clock by more than
+    /// `max_lag`.
+    violating: bool,
+    /// The number of times the view has begun to violate its objective.
+    violations: u64,
 }
 
 impl ViewState {
@@ -1912,6 +4867,7 @@ impl ViewState {
             default_idx: None,
             primary_idxes: BTreeMap::new(),
             //secondary_idxes: BTreeMap::new(),
+            freshness: None,
         }
     }
 
@@ -1958,6 +4914,7 @@ pub fn auto_generate_src_idx(
         source_name,
         source_id,
         &source.desc,
+        None,
     )
 }
 
@@ -1966,6 +4923,7 @@ pub fn auto_generate_view_idx(
     view_name: FullName,
     view: &catalog::View,
     view_id: GlobalId,
+    cluster: Option<String>,
 ) -> catalog::Index {
     auto_generate_primary_idx(
         index_name,
@@ -1973,6 +4931,7 @@ pub fn auto_generate_view_idx(
         view_name,
         view_id,
         &view.desc,
+        cluster,
     )
 }
 
@@ -1982,6 +4941,7 @@ pub fn auto_generate_primary_idx(
     on_name: FullName,
     on_id: GlobalId,
     on_desc: &RelationDesc,
+    cluster: Option<String>,
 ) -> catalog::Index {
     let keys = if let Some(keys) = keys.first() {
         keys.clone()
@@ -1989,10 +4949,12 @@ pub fn auto_generate_primary_idx(
         (0..on_desc.typ().column_types.len()).collect()
     };
     catalog::Index {
-        create_sql: index_sql(index_name, on_name, &on_desc, &keys),
+        create_sql: index_sql(index_name, on_name, &on_desc, &keys, cluster.as_deref()),
         on: on_id,
         keys: keys.into_iter().map(ScalarExpr::Column).collect(),
         eval_env: EvalEnv::default(),
+        cluster,
+        replicas: vec![],
     }
 }
 
@@ -2003,6 +4965,7 @@ fn index_sql(
     view_name: FullName,
     view_desc: &RelationDesc,
     keys: &[usize],
+    cluster: Option<&str>,
 ) -> String {
     use sql_parser::ast::{Expr, Ident, Statement, Value};
 
@@ -2011,6 +4974,11 @@ fn index_sql(
             value: index_name,
             quote_style: Some('"'),
         },
+        in_cluster: cluster.map(|cluster| Ident {
+            value: cluster.to_owned(),
+            quote_style: Some('"'),
+        }),
+        replicas: vec![],
         on_name: sql::normalize::unresolve(view_name),
         key_parts: keys
             .iter()
@@ -2032,11 +5000,82 @@ pub fn dump_catalog(data_directory: &Path) -> Result<String, failure::Error> {
     let coord = Coordinator::new(Config {
         switchboard,
         num_timely_workers: 1,
+        clusters: &[],
         data_directory: Some(data_directory),
         symbiosis_url: None,
         logging: Some(&LoggingConfig::new(Duration::from_secs(0))),
         executor: runtime.handle(),
         timestamp: None,
+        catalog_compaction_interval: None,
+        peek_timeout: None,
+        arrangement_memory_limit: None,
+        consistency_check_interval: None,
+        local_input_timestamps: LocalInputTimestamps::Counter,
+        command_log: None,
     })?;
     Ok(coord.catalog.dump())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_discrepancies() {
+        let expected = Inventory {
+            cluster: None,
+            arrangements: vec![GlobalId::System(1), GlobalId::User(1), GlobalId::User(2)],
+            local_inputs: vec![GlobalId::User(3)],
+            sinks: vec![GlobalId::User(4)],
+        };
+        assert_eq!(
+            find_discrepancies(expected.clone(), expected.clone()),
+            vec![]
+        );
+
+        // A worker that lost an arrangement during recovery, and that is
+        // writing to a sink that no longer exists.
+        let mut installed = expected.clone();
+        installed.arrangements.retain(|id| *id != GlobalId::User(2));
+        installed.sinks.push(GlobalId::User(5));
+        assert_eq!(
+            find_discrepancies(expected, installed),
+            vec![
+                (GlobalId::User(2), "arrangement", "missing"),
+                (GlobalId::User(5), "sink", "orphaned"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_merge() {
+        let row = |i| Row::pack(&[Datum::Int64(i)]);
+        let rows = |is: &[i64]| is.iter().map(|i| row(*i)).collect::<Vec<_>>();
+        let group = |key, is: &[i64]| (row(key), rows(is));
+
+        let mut runs = RunMerge::new(3);
+        runs.push(0, vec![group(1, &[10, 11]), group(4, &[40])]);
+        runs.push(1, vec![group(2, &[20])]);
+        // Worker 2 has yet to send anything, so it may still send key 1.
+        assert_eq!(runs.waiting(), Some(2));
+        assert_eq!(runs.pop(10), vec![]);
+        runs.push(2, vec![group(3, &[30]), group(9, &[90])]);
+        // Once worker 1 runs out, its next group may precede key 3.
+        assert_eq!(runs.pop(10), rows(&[10, 11, 20]));
+        assert_eq!(runs.waiting(), Some(1));
+        // A group may be continued by the next run.
+        runs.push(1, vec![group(2, &[21]), group(5, &[50])]);
+        assert_eq!(runs.pop(2), rows(&[21, 30]));
+        assert_eq!(runs.pop(10), rows(&[40]));
+        assert_eq!(runs.waiting(), Some(0));
+        runs.finish(0);
+        // Worker 0 has sent its last run, so it is no longer waited on.
+        assert_eq!(runs.pop(10), rows(&[50]));
+        runs.finish(1);
+        assert_eq!(runs.pop(10), rows(&[90]));
+        assert_eq!(runs.waiting(), Some(2));
+        runs.finish(2);
+        assert_eq!(runs.waiting(), None);
+        assert_eq!(runs.pop(10), vec![]);
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The built-in demos that `INSTALL DEMO` installs.
+//!
+//! A demo is a script of SQL statements that creates a schema named after the
+//! demo, a source that reads from one of the built-in load generators, and a
+//! set of views over that source.
+
+/// The names of the available demos.
+pub const DEMOS: &[&str] = &["auction"];
+
+/// Returns the statements that install the demo named `name`, or `None` if
+/// there is no such demo.
+pub fn script(name: &str) -> Option<&'static str> {
+    match name {
+        "auction" => Some(AUCTION),
+        _ => None,
+    }
+}
+
+const AUCTION: &str = "
+CREATE SCHEMA auction;
+
+CREATE MATERIALIZED SOURCE auction.events
+    FROM PLUGIN 'auction' WITH (rate = 10)
+    FORMAT CSV WITH 5 COLUMNS;
+
+CREATE VIEW auction.auctions AS
+    SELECT column3::bigint AS id, column4 AS seller, column5 AS item
+    FROM auction.events
+    WHERE column1 = 'auction';
+
+CREATE VIEW auction.bids AS
+    SELECT column2::bigint AS id, column3::bigint AS auction_id, column4 AS bidder,
+        column5::int AS amount
+    FROM auction.events
+    WHERE column1 = 'bid';
+
+CREATE MATERIALIZED VIEW auction.highest_bids AS
+    SELECT auction_id, max(amount) AS amount
+    FROM auction.bids
+    GROUP BY auction_id;
+
+CREATE MATERIALIZED VIEW auction.winning_bids AS
+    SELECT a.id AS auction_id, a.item, a.seller, b.bidder, b.amount
+    FROM auction.auctions a
+    JOIN auction.highest_bids h ON a.id = h.auction_id
+    JOIN auction.bids b ON b.auction_id = h.auction_id AND b.amount = h.amount;
+
+CREATE MATERIALIZED VIEW auction.seller_revenue AS
+    SELECT seller, count(*) AS sales, sum(amount) AS revenue
+    FROM auction.winning_bids
+    GROUP BY seller;
+";
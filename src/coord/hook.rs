@@ -0,0 +1,180 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Rewriting of query plans by integrators.
+//!
+//! A program that embeds `materialized` can enforce its own policies on the
+//! queries it serves, like restricting each tenant to its own rows or
+//! refusing cross joins, by implementing [`PlanHook`] and calling
+//! [`register_plan_hook`] before starting the server. The plan of every
+//! `SELECT` and of every new view is then passed through each registered
+//! hook, in order of registration, before it is optimized and installed on
+//! the dataflow workers. A hook may rewrite the plan, or reject the
+//! statement by returning an error, which is reported to the client.
+//!
+//! `TAIL`, `CREATE SINK`, `EXPORT TRACE`, and reads of changes over HTTP read
+//! the dataflow or index of an existing collection directly, rather than
+//! planning a query of their own. Hooks are passed a plan that reads the
+//! entire collection for each such statement, which they may reject, but not
+//! rewrite, as there is no query to which a rewritten plan could apply.
+//!
+//! The catalog stores the SQL definitions of views, which are planned again
+//! whenever the catalog is loaded, so hooks must be registered before the
+//! server starts, and are applied to each view both when it is created and
+//! when it is loaded. A view that a hook rejects on load fails to load, like
+//! a view whose definition is no longer valid. `SHOW CREATE VIEW` shows the
+//! view's original definition.
+
+use std::sync::{Arc, RwLock};
+
+use failure::bail;
+use lazy_static::lazy_static;
+
+use catalog::names::FullName;
+use catalog::Catalog;
+use expr::{GlobalId, Id, RelationExpr};
+use sql::Session;
+
+lazy_static! {
+    static ref PLAN_HOOKS: RwLock<Vec<(String, Arc<dyn PlanHook>)>> = RwLock::new(Vec::new());
+}
+
+/// Registers `hook` to rewrite the plans of subsequent statements, replacing
+/// any hook previously registered under `name`.
+///
+/// A hook that replaces another takes its place in the order in which hooks
+/// are applied.
+pub fn register_plan_hook<H>(name: &str, hook: H)
+where
+    H: PlanHook + 'static,
+{
+    let mut hooks = PLAN_HOOKS.write().unwrap();
+    let hook: Arc<dyn PlanHook> = Arc::new(hook);
+    match hooks.iter_mut().find(|(n, _)| n == name) {
+        Some((_, existing)) => *existing = hook,
+        None => hooks.push((name.to_owned(), hook)),
+    }
+}
+
+/// Rewrites or rejects the plans of queries and views.
+pub trait PlanHook: Send + Sync {
+    /// Rewrites `expr`, the plan of the statement described by `context`, or
+    /// returns an error to reject the statement.
+    ///
+    /// The rewritten plan must produce columns of the same types as the
+    /// original plan. It may read catalog items that the original plan did
+    /// not.
+    fn rewrite(&self, context: &PlanContext, expr: &mut RelationExpr)
+        -> Result<(), failure::Error>;
+}
+
+/// What a plan passed to a [`PlanHook`] computes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanKind {
+    /// The results of a `SELECT`, or of another statement that reads the
+    /// contents of a collection once, like `CHECK VIEW`.
+    Peek,
+    /// The contents of a new view.
+    View,
+    /// The changes to a collection that a `TAIL` reports.
+    Tail,
+    /// The changes to a collection that a new sink emits.
+    Sink,
+    /// The contents or history of a collection that `EXPORT TRACE` writes.
+    ExportTrace,
+    /// The changes to a collection that a client reads over HTTP.
+    Changes,
+}
+
+/// The statement whose plan is passed to a [`PlanHook`].
+pub struct PlanContext<'a> {
+    /// What the plan computes.
+    pub kind: PlanKind,
+    /// The ID of the connection that issued the statement, or 0 for
+    /// statements that the coordinator issues itself.
+    pub conn_id: u32,
+    /// The session of the connection that issued the statement, or `None`
+    /// if the plan is that of a view being loaded from the catalog or of a
+    /// read of changes over HTTP.
+    pub session: Option<&'a Session>,
+    catalog: &'a Catalog,
+}
+
+impl<'a> PlanContext<'a> {
+    pub(crate) fn new(
+        kind: PlanKind,
+        conn_id: u32,
+        session: Option<&'a Session>,
+        catalog: &'a Catalog,
+    ) -> PlanContext<'a> {
+        PlanContext {
+            kind,
+            conn_id,
+            session,
+            catalog,
+        }
+    }
+
+    /// Returns the name of the catalog item `id`, which a plan reads via
+    /// `RelationExpr::Get`, if it exists.
+    pub fn name(&self, id: GlobalId) -> Option<&FullName> {
+        self.catalog.try_get_by_id(&id).map(|entry| entry.name())
+    }
+}
+
+/// Passes `expr` through each registered hook.
+pub(crate) fn apply_plan_hooks(
+    context: &PlanContext,
+    expr: &mut RelationExpr,
+) -> Result<(), failure::Error> {
+    // Hooks are not called with the registry locked, so that a hook may
+    // register other hooks.
+    let hooks = PLAN_HOOKS.read().unwrap().clone();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let scalar_types = |expr: &RelationExpr| {
+        expr.typ()
+            .column_types
+            .into_iter()
+            .map(|typ| typ.scalar_type)
+            .collect::<Vec<_>>()
+    };
+    let expected = scalar_types(expr);
+    for (name, hook) in hooks {
+        hook.rewrite(context, expr)?;
+        if scalar_types(expr) != expected {
+            bail!("plan hook {} changed the type of the plan", name);
+        }
+    }
+    Ok(())
+}
+
+/// Passes a plan that reads the entire collection `id` through each registered
+/// hook, for a statement that reads the collection directly.
+///
+/// Such a statement cannot apply a rewritten plan, so it is rejected if any
+/// hook rewrites the plan.
+pub(crate) fn check_direct_read(context: &PlanContext, id: GlobalId) -> Result<(), failure::Error> {
+    let entry = context.catalog.get_by_id(&id);
+    let get = RelationExpr::Get {
+        id: Id::Global(id),
+        typ: entry.desc()?.typ().clone(),
+    };
+    let mut expr = get.clone();
+    apply_plan_hooks(context, &mut expr)?;
+    if expr != get {
+        bail!(
+            "plan hooks cannot restrict {:?} statements, which read {} directly",
+            context.kind,
+            entry.name()
+        );
+    }
+    Ok(())
+}
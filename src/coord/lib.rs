@@ -21,10 +21,18 @@
 //! provided by something other than a pgwire server.
 
 mod command;
+mod command_log;
 mod coord;
+mod demo;
+mod hook;
 mod persistence;
 mod timestamp;
+mod trace;
 
 pub use self::coord::{dump_catalog, Config, Coordinator};
-pub use self::timestamp::TimestampConfig;
-pub use command::{Command, ExecuteResponse, Response, RowsFuture, StartupMessage};
+pub use self::hook::{register_plan_hook, PlanContext, PlanHook, PlanKind};
+pub use self::timestamp::{LocalInputTimestamps, TimestampConfig};
+pub use command::{
+    collect_rows, ChangesResponse, Command, ExecuteResponse, Notification, PeekFuture, PeekStream,
+    Response, RowsFuture, StartupMessage,
+};
@@ -15,7 +15,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use catalog::{Catalog, CatalogItemSerializer, Index, Sink, Source, View};
+use catalog::{Catalog, CatalogItemSerializer, Index, Sink, Source, Type, View};
 use expr::transform::Optimizer;
 use failure::bail;
 use ore::collections::CollectionExt;
@@ -23,6 +23,8 @@ use ore::future::MaybeFuture;
 use repr::Row;
 use sql::{Params, Plan};
 
+use crate::hook::{self, PlanContext, PlanKind};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 enum CatalogItem {
     V1 {
@@ -76,6 +78,10 @@ impl CatalogItemSerializer for SqlSerializer {
                 create_sql: sink.create_sql.clone(),
                 eval_env: None,
             },
+            catalog::CatalogItem::Type(typ) => CatalogItem::V1 {
+                create_sql: typ.create_sql.clone(),
+                eval_env: None,
+            },
         };
         serde_json::to_vec(&item).expect("catalog serialization cannot fail")
     }
@@ -119,7 +125,9 @@ impl CatalogItemSerializer for SqlSerializer {
                 connector: source.connector,
                 desc: source.desc,
             }),
-            Plan::CreateView { view, .. } => {
+            Plan::CreateView { mut view, .. } => {
+                let context = PlanContext::new(PlanKind::View, 0, None, catalog);
+                hook::apply_plan_hooks(&context, &mut view.expr)?;
                 let mut optimizer = Optimizer::default();
                 let eval_env = match eval_env {
                     None => bail!("view missing eval env"),
@@ -130,12 +138,18 @@ impl CatalogItemSerializer for SqlSerializer {
                     expr: optimizer.optimize(view.expr, catalog.indexes(), &eval_env),
                     eval_env,
                     desc: view.desc,
+                    refresh_interval: view.refresh_interval,
+                    changes_only: view.changes_only,
+                    emit_final_after: view.emit_final_after,
+                    max_lag: view.max_lag,
                 })
             }
             Plan::CreateIndex { index, .. } => catalog::CatalogItem::Index(Index {
                 create_sql: index.create_sql,
                 on: index.on,
                 keys: index.keys,
+                cluster: index.cluster,
+                replicas: index.replicas,
                 eval_env: match eval_env {
                     None => bail!("index missing eval env"),
                     Some(eval_env) => eval_env.into(),
@@ -145,6 +159,11 @@ impl CatalogItemSerializer for SqlSerializer {
                 create_sql: sink.create_sql,
                 from: sink.from,
                 connector: sink.connector,
+                cluster: sink.cluster,
+            }),
+            Plan::CreateType { typ, .. } => catalog::CatalogItem::Type(Type {
+                create_sql: typ.create_sql,
+                labels: typ.labels,
             }),
             _ => bail!("catalog entry generated inappropriate plan"),
         })
@@ -31,11 +31,18 @@ fn no_block() {
     let mut coord = coord::Coordinator::new(coord::Config {
         switchboard: switchboard.clone(),
         num_timely_workers: 1,
+        clusters: &[],
         symbiosis_url: None,
         logging: logging_config.as_ref(),
         data_directory: None,
         executor: &executor,
         timestamp: None,
+        catalog_compaction_interval: None,
+        peek_timeout: None,
+        arrangement_memory_limit: None,
+        consistency_check_interval: None,
+        local_input_timestamps: coord::LocalInputTimestamps::Counter,
+        command_log: None,
     })
     .unwrap();
 
@@ -53,6 +60,9 @@ fn no_block() {
         runtime.handle().clone(),
         false,
         logging_config,
+        None,
+        vec![],
+        None,
     )
     .unwrap();
 
@@ -134,7 +144,7 @@ fn no_block() {
 
         let Response { result, session: _ } = oneshot_rx.await.unwrap();
         let response: ExecuteResponse = result.unwrap();
-        let rows = match response {
+        let (rows, _metadata) = match response {
             ExecuteResponse::SendRows(rows) => rows,
             _ => panic!(),
         }
@@ -10,7 +10,6 @@
 use rusqlite::{params, NO_PARAMS};
 
 use std::collections::HashMap;
-use std::path::PathBuf;
 use std::str;
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
@@ -23,22 +22,37 @@ use rdkafka::consumer::{BaseConsumer, Consumer};
 use rdkafka::message::Message;
 use rdkafka::ClientConfig;
 
-use dataflow_types::Consistency;
+use dataflow_types::{Consistency, KafkaSourceConnector};
 
 use log::{error, info};
-use url::Url;
 
 pub struct TimestampConfig {
     pub frequency: Duration,
     pub max_size: i64,
 }
 
+/// How the coordinator assigns timestamps to the rows written to tables.
+#[derive(Clone, Copy, Debug)]
+pub enum LocalInputTimestamps {
+    /// Each write is assigned the next integer timestamp, without regard for
+    /// the wall clock.
+    Counter,
+    /// Writes are assigned the wall-clock time in milliseconds since the Unix
+    /// epoch, rounded down to a multiple of `granularity`, and tables advance
+    /// with the wall clock while they are idle. Writes that arrive faster than
+    /// `granularity` are assigned successive multiples of `granularity`.
+    WallClock { granularity: Duration },
+}
+
 #[derive(Debug)]
 pub enum TimestampMessage {
-    Add(SourceInstanceId, Url, String, Option<PathBuf>, Consistency),
+    Add(SourceInstanceId, KafkaSourceConnector, Consistency),
     DropInstance(SourceInstanceId),
-    BatchedUpdate(u64, Vec<(SourceInstanceId, i64)>),
-    Update(SourceInstanceId, u64, i64),
+    /// Bindings of a timestamp to an offset in each of several partitions of
+    /// several sources.
+    BatchedUpdate(u64, Vec<(SourceInstanceId, i32, i64)>),
+    /// A binding of a timestamp to an offset in one partition of a source.
+    Update(SourceInstanceId, i32, u64, i64),
     Shutdown,
 }
 
@@ -52,7 +66,8 @@ pub struct TimestampChannel {
 struct RtTimestampConsumer {
     consumer: BaseConsumer,
     topic: String,
-    last_offset: i64,
+    /// The last offset bound to a timestamp in each partition of the topic.
+    last_offsets: HashMap<i32, i64>,
 }
 
 struct ByoTimestampConsumer {
@@ -76,21 +91,37 @@ fn byo_query_source(consumer: &mut ByoTimestampConsumer, max_increment_size: i64
     messages
 }
 
+/// Extracts the bindings for the consumer's topic from `messages`. Each
+/// message is either `topic,timestamp,offset`, which binds an offset in
+/// partition zero, or `topic,partition,timestamp,offset`.
 fn byo_extract_ts_update(
     consumer: &ByoTimestampConsumer,
     messages: Vec<Vec<u8>>,
-) -> Vec<(u64, i64)> {
+) -> Vec<(i32, u64, i64)> {
     let mut updates = vec![];
     for payload in messages {
         let st = str::from_utf8(&payload);
         match st {
             Ok(timestamp) => {
                 // Extract timestamp from payload
-                let split: Vec<&str> = timestamp.split(',').collect();
-                if split.len() != 3 {
-                    error!("incorrect payload format. Expected: SourceName/TS/Offset");
-                    continue;
-                }
+                let mut split: Vec<&str> = timestamp.split(',').collect();
+                let partition = match split.len() {
+                    3 => 0,
+                    4 => match split.remove(1).parse::<i32>() {
+                        Ok(i) => i,
+                        Err(err) => {
+                            error!("incorrect partition format {}", err);
+                            continue;
+                        }
+                    },
+                    _ => {
+                        error!(
+                            "incorrect payload format. Expected: SourceName/TS/Offset \
+                             or SourceName/Partition/TS/Offset"
+                        );
+                        continue;
+                    }
+                };
                 let topic_name = String::from(split[0]);
                 let ts = match split[1].parse::<u64>() {
                     Ok(i) => i,
@@ -107,7 +138,7 @@ fn byo_extract_ts_update(
                     }
                 };
                 if topic_name == consumer.topic {
-                    updates.push((ts, offset))
+                    updates.push((partition, ts, offset))
                 }
             }
             Err(err) => error!("incorrect payload format: {}", err),
@@ -118,13 +149,13 @@ fn byo_extract_ts_update(
 
 fn byo_notify_coordinator(
     id: SourceInstanceId,
-    updates: Vec<(u64, i64)>,
+    updates: Vec<(i32, u64, i64)>,
     coord_channel: &TimestampChannel,
 ) {
-    for (ts, offset) in updates {
+    for (partition, ts, offset) in updates {
         coord_channel
             .sender
-            .send(TimestampMessage::Update(id, ts, offset))
+            .send(TimestampMessage::Update(id, partition, ts, offset))
             .expect("Failed to send update to coordinator");
     }
 }
@@ -249,31 +280,21 @@ impl Timestamper {
         // start checking
         while let Ok(update) = self.coord_channel.receiver.try_recv() {
             match update {
-                TimestampMessage::Add(id, url, topic, ssl_certificate_file, consistency) => {
+                TimestampMessage::Add(id, connector, consistency) => {
                     if !self.rt_sources.contains_key(&id) && !self.byo_sources.contains_key(&id) {
                         // Did not know about source, must update
                         match consistency {
                             Consistency::RealTime => {
                                 info!("Timestamping Source {} with Real Time Consistency", id);
-                                let last_offset = self.rt_recover_source(id);
-                                let connector = self.create_rt_connector(
-                                    id,
-                                    url,
-                                    topic,
-                                    ssl_certificate_file,
-                                    last_offset,
-                                );
+                                let last_offsets = self.rt_recover_source(id);
+                                let connector =
+                                    self.create_rt_connector(id, connector, last_offsets);
                                 self.rt_sources.insert(id, connector);
                             }
                             Consistency::BringYourOwn(consistency_topic) => {
-                                info!("Timestamping Source {} with BYO Consistency. Topic: {}, Consistency Topic: {}", id, topic, consistency_topic);
-                                let consumer = self.create_byo_connector(
-                                    url,
-                                    topic,
-                                    id,
-                                    ssl_certificate_file,
-                                    consistency_topic,
-                                );
+                                info!("Timestamping Source {} with BYO Consistency. Topic: {}, Consistency Topic: {}", id, connector.topic, consistency_topic);
+                                let consumer =
+                                    self.create_byo_connector(id, connector, consistency_topic);
                                 self.byo_sources.insert(id, consumer);
                             }
                         }
@@ -314,11 +335,16 @@ impl Timestamper {
     fn create_rt_connector(
         &self,
         id: SourceInstanceId,
-        url: Url,
-        topic: String,
-        ssl_certificate_file: Option<PathBuf>,
-        last_offset: i64,
+        connector: KafkaSourceConnector,
+        last_offsets: HashMap<i32, i64>,
     ) -> RtTimestampConsumer {
+        let KafkaSourceConnector {
+            url,
+            topic,
+            ssl_certificate_file,
+            config_options,
+            ..
+        } = connector;
         let mut config = ClientConfig::new();
         config
             .set("auto.offset.reset", "smallest")
@@ -340,24 +366,32 @@ impl Timestamper {
                     .expect("Converting ssl certificate file path failed"),
             );
         }
+        for (name, value) in &config_options {
+            config.set(name, value);
+        }
 
         let k_consumer: BaseConsumer = config.create().expect("Failed to create Kakfa consumer");
         RtTimestampConsumer {
             consumer: k_consumer,
             topic,
-            last_offset,
+            last_offsets,
         }
     }
 
     /// Creates a RT Kafka connector
     fn create_byo_connector(
         &self,
-        url: Url,
-        topic: String,
         id: SourceInstanceId,
-        ssl_certificate_file: Option<PathBuf>,
+        connector: KafkaSourceConnector,
         timestamp_topic: String,
     ) -> ByoTimestampConsumer {
+        let KafkaSourceConnector {
+            url,
+            topic,
+            ssl_certificate_file,
+            config_options,
+            ..
+        } = connector;
         let mut config = ClientConfig::new();
         config
             .set("auto.offset.reset", "smallest")
@@ -382,6 +416,9 @@ impl Timestamper {
                     .expect("Converting ssl certificate file path failed"),
             );
         }
+        for (name, value) in &config_options {
+            config.set(name, value);
+        }
 
         let k_consumer: BaseConsumer = config.create().expect("Failed to create Kakfa consumer");
         let consumer = ByoTimestampConsumer {
@@ -397,85 +434,108 @@ impl Timestamper {
     }
 
     /// Recovers any existing timestamp updates for that (SourceId,ViewId) pair from the underlying
-    /// SQL database. Notifies the coordinator of these updates
-    fn rt_recover_source(&mut self, id: SourceInstanceId) -> i64 {
-        let ts_updates: Vec<_> = self
+    /// SQL database. Notifies the coordinator of these updates, and returns the greatest offset
+    /// bound in each partition.
+    ///
+    /// The bindings of instances in transient dataflows are never persisted,
+    /// as their identifiers are reused after a restart, so there is nothing
+    /// to recover for them.
+    fn rt_recover_source(&mut self, id: SourceInstanceId) -> HashMap<i32, i64> {
+        let mut max_offsets = HashMap::new();
+        if id.vid.is_transient() {
+            return max_offsets;
+        }
+        let ts_updates = self
             .storage()
-            .prepare("SELECT timestamp, offset FROM timestamps WHERE sid = ? AND vid = ? ORDER BY timestamp")
-            .expect("Failed to execute select statement")
-            .query_and_then(params![SqlVal(&id.sid), SqlVal(&id.vid)], |row| -> Result<_, failure::Error> {
-                let timestamp: SqlVal<u64> = row.get(0)?;
-                let offset: SqlVal<i64> = row.get(1)?;
-                Ok((timestamp.0, offset.0))
-            })
-            .expect("Failed to parse SQL result")
-            .collect();
-
-        let mut max_offset = 0;
-        for row in ts_updates {
-            let (ts, offset) = row.expect("Failed to parse SQL result");
-            max_offset = if offset > max_offset {
-                offset
-            } else {
-                max_offset
-            };
+            .load_timestamp_bindings(id)
+            .expect("Failed to load timestamp bindings");
+
+        for (partition, ts, offset) in ts_updates {
+            let max_offset = max_offsets.entry(partition).or_insert(offset);
+            if offset > *max_offset {
+                *max_offset = offset;
+            }
             self.coord_channel
                 .sender
-                .send(TimestampMessage::Update(id, ts, offset))
+                .send(TimestampMessage::Update(id, partition, ts, offset))
                 .expect("Failed to send timestamp update to coordinator");
         }
-        max_offset
+        max_offsets
     }
 
-    /// Query real-time sources for the current max offset that has been generated for that source
-    /// Set the new timestamped offset to min(max_offset, last_offset + increment_size): this ensures
-    /// that we never create an overly large batch of messages for the same timestamp (which would
-    /// prevent views from becoming visible in a timely fashion)
-    fn rt_query_sources(&mut self) -> Vec<(SourceInstanceId, i64)> {
+    /// Query each partition of the real-time sources for the current max offset that has been
+    /// generated for that partition. Set the new timestamped offset to
+    /// min(max_offset, last_offset + increment_size): this ensures that we never create an overly
+    /// large batch of messages for the same timestamp (which would prevent views from becoming
+    /// visible in a timely fashion)
+    ///
+    /// Every partition is bound at every timestamp, even if it has no new messages, as the workers
+    /// that read a partition cannot otherwise tell that the partition has no messages at that
+    /// timestamp. Partitions that are added to the topic are bound from the next timestamp on.
+    fn rt_query_sources(&mut self) -> Vec<(SourceInstanceId, i32, i64)> {
         let mut result = vec![];
         for (id, cons) in self.rt_sources.iter_mut() {
-            let watermark = cons
+            let partitions = match cons
                 .consumer
-                .fetch_watermarks(&cons.topic, 0, Duration::from_secs(1));
-            match watermark {
-                Ok(watermark) => {
-                    let high = watermark.1 - 1;
-                    // Bound the next timestamp to be no more than max_increment_size in the future
-                    let next_ts = if (high - cons.last_offset) > self.max_increment_size {
-                        cons.last_offset + self.max_increment_size
-                    } else {
-                        high
-                    };
-                    cons.last_offset = next_ts;
-                    result.push((*id, next_ts))
-                }
+                .fetch_metadata(Some(&cons.topic), Duration::from_secs(1))
+            {
+                Ok(metadata) => metadata
+                    .topics()
+                    .iter()
+                    .flat_map(|topic| topic.partitions())
+                    .map(|partition| partition.id())
+                    .collect::<Vec<_>>(),
                 Err(e) => {
-                    error!("Failed to obtain Kafka Watermark Information: {} {}", id, e);
+                    error!("Failed to obtain Kafka Metadata Information: {} {}", id, e);
+                    continue;
+                }
+            };
+            // A partition is only bound if all of the source's partitions
+            // are, so that the bindings at a timestamp are never partial.
+            let mut bindings = vec![];
+            for partition in partitions {
+                let watermark =
+                    cons.consumer
+                        .fetch_watermarks(&cons.topic, partition, Duration::from_secs(1));
+                match watermark {
+                    Ok(watermark) => {
+                        let high = watermark.1 - 1;
+                        let last_offset = *cons.last_offsets.get(&partition).unwrap_or(&-1);
+                        // Bound the next timestamp to be no more than
+                        // max_increment_size in the future
+                        let next_offset = if (high - last_offset) > self.max_increment_size {
+                            last_offset + self.max_increment_size
+                        } else {
+                            high
+                        };
+                        bindings.push((partition, next_offset));
+                    }
+                    Err(e) => {
+                        error!("Failed to obtain Kafka Watermark Information: {} {}", id, e);
+                        bindings.clear();
+                        break;
+                    }
                 }
             }
+            for (partition, offset) in bindings {
+                cons.last_offsets.insert(partition, offset);
+                result.push((*id, partition, offset));
+            }
         }
         result
     }
 
     /// Persist timestamp updates to the underlying storage when using the
     /// real-time timestamping logic.
-    fn rt_persist_timestamp(&self, ts_updates: &[(SourceInstanceId, i64)]) {
+    fn rt_persist_timestamp(&self, ts_updates: &[(SourceInstanceId, i32, i64)]) {
         let storage = self.storage();
-        for (id, offset) in ts_updates {
-            let mut stmt = storage
-                .prepare_cached(
-                    "INSERT INTO timestamps (sid, vid, timestamp, offset) VALUES (?, ?, ?, ?)",
-                )
-                .expect(
-                    "Failed to prepare insert statement into persistent store. \
-                     Hint: increase the system file descriptor limit.",
-                );
-            while let Err(e) = stmt.execute(params![
-                SqlVal(&id.sid),
-                SqlVal(&id.vid),
-                SqlVal(&self.current_timestamp),
-                SqlVal(&offset)
-            ]) {
+        for (id, partition, offset) in ts_updates
+            .iter()
+            .filter(|(id, _, _)| !id.vid.is_transient())
+        {
+            while let Err(e) =
+                storage.insert_timestamp_binding(*id, *partition, self.current_timestamp, *offset)
+            {
                 error!(
                     "Failed to insert statement into persistent store: {}. \
                      Hint: increase the system file descriptor limit.",
@@ -489,7 +549,7 @@ impl Timestamper {
     /// Notify coordinator of a batch of timestamp updates, all with the same timestamp
     /// Used in real-time timestamping logic, where a set of sources get assigned the same
     /// timestamp
-    fn rt_notify_coordinator(&self, ts_updates: Vec<(SourceInstanceId, i64)>) {
+    fn rt_notify_coordinator(&self, ts_updates: Vec<(SourceInstanceId, i32, i64)>) {
         self.coord_channel
             .sender
             .send(TimestampMessage::BatchedUpdate(
@@ -0,0 +1,210 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The file format of the traces that `EXPORT TRACE` writes and
+//! `IMPORT TRACE` reads.
+//!
+//! A trace file is a sequence of JSON documents, one per line. The first line
+//! is a [`Header`] that describes the exported view or source. Each
+//! subsequent line is one update to its contents, of the form
+//!
+//! ```json
+//! {"row": [1, "foo", null], "time": 1587000000000, "diff": 1}
+//! ```
+//!
+//! The values of the row are encoded as in JSON sinks: booleans, integers,
+//! floats, strings, and `jsonb` values as their JSON counterparts, and other
+//! values as strings in the text format of pgwire.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use failure::{bail, format_err, ResultExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use dataflow_types::{Diff, Timestamp};
+use interchange::json::Encoder;
+use repr::jsonb::Jsonb;
+use repr::{strconv, ColumnType, Datum, RelationDesc, Row, RowPacker, ScalarType};
+
+/// The version of the trace file format that this module reads and writes.
+pub const VERSION: u64 = 1;
+
+/// The first line of a trace file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Header {
+    /// The version of the file format.
+    pub version: u64,
+    /// The name of the exported view or source.
+    pub name: String,
+    /// The columns of the exported view or source.
+    pub desc: RelationDesc,
+    /// The time of the latest updates in the file.
+    pub timestamp: Timestamp,
+    /// Whether the file holds every update that the index retained, rather
+    /// than only the contents of the view at `timestamp`. In either case,
+    /// updates at times before the index's compaction frontier are reported
+    /// at that frontier.
+    pub history: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Update {
+    row: Vec<Value>,
+    time: Timestamp,
+    diff: Diff,
+}
+
+/// Writes `updates` to a new trace file at `path`, overwriting any existing
+/// file.
+pub fn write(
+    path: &Path,
+    header: &Header,
+    updates: &[(Row, Timestamp, Diff)],
+) -> Result<(), failure::Error> {
+    let encoder = Encoder::new(&header.desc)?;
+    let mut file = BufWriter::new(
+        File::create(path).with_context(|e| format!("creating {}: {}", path.display(), e))?,
+    );
+    serde_json::to_writer(&mut file, header)?;
+    writeln!(file)?;
+    for (row, time, diff) in updates {
+        let update = Update {
+            row: row
+                .iter()
+                .enumerate()
+                .map(|(i, datum)| encoder.encode_datum(i, datum))
+                .collect(),
+            time: *time,
+            diff: *diff,
+        };
+        serde_json::to_writer(&mut file, &update)?;
+        writeln!(file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Reads the trace file at `path`.
+pub fn read(path: &Path) -> Result<(Header, Vec<(Row, Timestamp, Diff)>), failure::Error> {
+    let file = File::open(path).with_context(|e| format!("opening {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header: Header = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)
+            .map_err(|e| format_err!("{} is not a trace file: {}", path.display(), e))?,
+        None => bail!("{} is empty", path.display()),
+    };
+    if header.version != VERSION {
+        bail!(
+            "{} has trace format version {}, but only version {} is supported",
+            path.display(),
+            header.version,
+            VERSION
+        );
+    }
+    let types = &header.desc.typ().column_types;
+    let mut updates = vec![];
+    for (i, line) in lines.enumerate() {
+        let lineno = i + 2;
+        let update: Update = serde_json::from_str(&line?)
+            .map_err(|e| format_err!("{}:{}: {}", path.display(), lineno, e))?;
+        if update.row.len() != types.len() {
+            bail!(
+                "{}:{}: expected {} values, but found {}",
+                path.display(),
+                lineno,
+                types.len(),
+                update.row.len()
+            );
+        }
+        let mut packer = RowPacker::new();
+        for (typ, value) in types.iter().zip(&update.row) {
+            decode_datum(&mut packer, typ, value)
+                .map_err(|e| format_err!("{}:{}: {}", path.display(), lineno, e))?;
+        }
+        updates.push((packer.finish(), update.time, update.diff));
+    }
+    Ok((header, updates))
+}
+
+/// Decodes `value`, which must belong to a column of type `typ`, and pushes
+/// the resulting datum onto `packer`.
+fn decode_datum(
+    packer: &mut RowPacker,
+    typ: &ColumnType,
+    value: &Value,
+) -> Result<(), failure::Error> {
+    if value.is_null() {
+        if !typ.nullable {
+            bail!("null value in non-nullable column");
+        }
+        packer.push(Datum::Null);
+        return Ok(());
+    }
+    let string = || {
+        value
+            .as_str()
+            .ok_or_else(|| format_err!("expected a string, but found {}", value))
+    };
+    let float = || match value {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| format_err!("expected a float, but found {}", value)),
+        Value::String(s) => strconv::parse_float64(s),
+        _ => bail!("expected a float, but found {}", value),
+    };
+    let int = || {
+        value
+            .as_i64()
+            .ok_or_else(|| format_err!("expected an integer, but found {}", value))
+    };
+    match &typ.scalar_type {
+        ScalarType::Unknown => packer.push(Datum::Null),
+        ScalarType::Bool => match value.as_bool() {
+            Some(b) => packer.push(Datum::from(b)),
+            None => bail!("expected a boolean, but found {}", value),
+        },
+        ScalarType::Int32 | ScalarType::Enum(_) => {
+            let i = int()?;
+            if i < i64::from(i32::min_value()) || i > i64::from(i32::max_value()) {
+                bail!("{} is out of range for a 32-bit integer", i);
+            }
+            packer.push(Datum::Int32(i as i32))
+        }
+        ScalarType::Int64 => packer.push(Datum::Int64(int()?)),
+        ScalarType::Float32 => packer.push(Datum::from(float()? as f32)),
+        ScalarType::Float64 => packer.push(Datum::from(float()?)),
+        ScalarType::String => packer.push(Datum::String(string()?)),
+        ScalarType::Jsonb => packer.extend_by_row(&Jsonb::new(value.clone())?.into_row()),
+        ScalarType::Decimal(_, scale) => {
+            let d = strconv::parse_decimal(string()?)?;
+            if d.scale() != *scale {
+                bail!("expected a decimal with scale {}, but found {}", scale, d);
+            }
+            packer.push(Datum::from(d.significand()))
+        }
+        ScalarType::Date => packer.push(Datum::Date(strconv::parse_date(string()?)?)),
+        ScalarType::Time => packer.push(Datum::Time(strconv::parse_time(string()?)?)),
+        ScalarType::Timestamp => {
+            packer.push(Datum::Timestamp(strconv::parse_timestamp(string()?)?))
+        }
+        ScalarType::TimestampTz => {
+            packer.push(Datum::TimestampTz(strconv::parse_timestamptz(string()?)?))
+        }
+        ScalarType::Interval => packer.push(Datum::Interval(strconv::parse_interval(string()?)?)),
+        ScalarType::Point => packer.push(Datum::Point(strconv::parse_point(string()?)?)),
+        ScalarType::Inet => packer.push(Datum::Inet(strconv::parse_inet(string()?)?)),
+        ScalarType::Cidr => packer.push(Datum::Inet(strconv::parse_cidr(string()?)?)),
+        ScalarType::Bytes => packer.push(Datum::Bytes(&strconv::parse_bytes(string()?)?)),
+        ScalarType::List => bail!("columns of type {} are not supported", typ.scalar_type),
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Classified errors.
+
+use std::error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// An error that occurred while processing a client request, classified by
+/// its cause.
+///
+/// Errors of this type are passed between the dataflow workers, the
+/// coordinator, and the SQL front end intact, so that the front end can
+/// report the class of an error to the client without inspecting its message.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Error {
+    /// The request is invalid, e.g., because it is malformed or refers to an
+    /// object that does not exist.
+    Plan(String),
+    /// Evaluating an expression over the data failed.
+    Eval(String),
+    /// The system lacks the resources to complete the request.
+    Resource(String),
+    /// An external system, like a Kafka broker or a schema registry, could not
+    /// be reached or returned an error.
+    Connector(String),
+    /// An unexpected condition, which indicates a bug.
+    Internal(String),
+    /// A row written to a table violated one of the table's `CHECK`
+    /// constraints, and was rejected.
+    CheckViolation {
+        /// The name of the violated constraint.
+        constraint: String,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Returns a code that identifies the class of the error. The codes are
+    /// stable, so clients may match on them.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Plan(_) => "plan",
+            Error::Eval(_) => "eval",
+            Error::Resource(_) => "resource",
+            Error::Connector(_) => "connector",
+            Error::Internal(_) => "internal",
+            Error::CheckViolation { .. } => "check_violation",
+        }
+    }
+
+    /// Returns the human-readable description of the error.
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Plan(message)
+            | Error::Eval(message)
+            | Error::Resource(message)
+            | Error::Connector(message)
+            | Error::Internal(message)
+            | Error::CheckViolation { message, .. } => message,
+        }
+    }
+
+    /// Returns the name of the constraint that the error reports a violation
+    /// of, if any.
+    pub fn constraint(&self) -> Option<&str> {
+        match self {
+            Error::CheckViolation { constraint, .. } => Some(constraint),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl error::Error for Error {}
@@ -15,6 +15,8 @@
 
 pub mod logging;
 
+mod errors;
 mod types;
 
+pub use errors::Error;
 pub use types::*;
@@ -88,6 +88,11 @@ pub enum MaterializedLog {
     PrimaryKeys,
     ForeignKeys,
     Catalog,
+    SinkFrontierCurrent,
+    CatalogDiscrepancies,
+    ViewFreshness,
+    ViewBackfills,
+    SourceSkew,
 }
 
 impl LogVariant {
@@ -109,6 +114,11 @@ impl LogVariant {
             LogVariant::Materialized(MaterializedLog::PrimaryKeys),
             LogVariant::Materialized(MaterializedLog::ForeignKeys),
             LogVariant::Materialized(MaterializedLog::Catalog),
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent),
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies),
+            LogVariant::Materialized(MaterializedLog::ViewFreshness),
+            LogVariant::Materialized(MaterializedLog::ViewBackfills),
+            LogVariant::Materialized(MaterializedLog::SourceSkew),
         ]
     }
 
@@ -135,6 +145,13 @@ impl LogVariant {
             LogVariant::Materialized(MaterializedLog::PrimaryKeys) => "mz_view_keys",
             LogVariant::Materialized(MaterializedLog::ForeignKeys) => "mz_view_foreign_keys",
             LogVariant::Materialized(MaterializedLog::Catalog) => "mz_catalog_names",
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent) => "mz_sink_frontiers",
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies) => {
+                "mz_catalog_discrepancies"
+            }
+            LogVariant::Materialized(MaterializedLog::ViewFreshness) => "mz_view_freshness",
+            LogVariant::Materialized(MaterializedLog::ViewBackfills) => "mz_view_backfills",
+            LogVariant::Materialized(MaterializedLog::SourceSkew) => "mz_source_skew",
         }
     }
 
@@ -157,6 +174,11 @@ impl LogVariant {
             LogVariant::Materialized(MaterializedLog::PrimaryKeys) => GlobalId::system(27),
             LogVariant::Materialized(MaterializedLog::ForeignKeys) => GlobalId::system(29),
             LogVariant::Materialized(MaterializedLog::Catalog) => GlobalId::system(31),
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent) => GlobalId::system(56),
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies) => GlobalId::system(58),
+            LogVariant::Materialized(MaterializedLog::ViewFreshness) => GlobalId::system(60),
+            LogVariant::Materialized(MaterializedLog::ViewBackfills) => GlobalId::system(62),
+            LogVariant::Materialized(MaterializedLog::SourceSkew) => GlobalId::system(64),
         }
     }
 
@@ -179,6 +201,11 @@ impl LogVariant {
             LogVariant::Materialized(MaterializedLog::PrimaryKeys) => GlobalId::system(28),
             LogVariant::Materialized(MaterializedLog::ForeignKeys) => GlobalId::system(30),
             LogVariant::Materialized(MaterializedLog::Catalog) => GlobalId::system(32),
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent) => GlobalId::system(57),
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies) => GlobalId::system(59),
+            LogVariant::Materialized(MaterializedLog::ViewFreshness) => GlobalId::system(61),
+            LogVariant::Materialized(MaterializedLog::ViewBackfills) => GlobalId::system(63),
+            LogVariant::Materialized(MaterializedLog::SourceSkew) => GlobalId::system(65),
         }
     }
 
@@ -296,6 +323,41 @@ impl LogVariant {
                 .add_column("global_id", ScalarType::String)
                 .add_column("name", ScalarType::String)
                 .add_keys(vec![0]),
+
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent) => RelationDesc::empty()
+                .add_column("global_id", ScalarType::String)
+                .add_column("time", ScalarType::Int64),
+
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies) => {
+                RelationDesc::empty()
+                    .add_column("global_id", ScalarType::String)
+                    .add_column("worker", ScalarType::Int64)
+                    .add_column("object", ScalarType::String)
+                    .add_column("problem", ScalarType::String)
+            }
+
+            LogVariant::Materialized(MaterializedLog::ViewFreshness) => RelationDesc::empty()
+                .add_column("global_id", ScalarType::String)
+                .add_column("max_lag_ms", ScalarType::Int64)
+                .add_column("violating", ScalarType::Bool)
+                .add_column("violations", ScalarType::Int64)
+                .add_keys(vec![0]),
+
+            LogVariant::Materialized(MaterializedLog::ViewBackfills) => RelationDesc::empty()
+                .add_column("global_id", ScalarType::String)
+                .add_column("target", ScalarType::Int64)
+                .add_column("frontier", ScalarType::Int64)
+                .add_column("records", ScalarType::Int64)
+                .add_column("estimated_records", ScalarType::Int64)
+                .add_column("elapsed_ms", ScalarType::Int64)
+                .add_keys(vec![0]),
+
+            LogVariant::Materialized(MaterializedLog::SourceSkew) => RelationDesc::empty()
+                .add_column("global_id", ScalarType::String)
+                .add_column("timestamp", ScalarType::Int64)
+                .add_column("wall_clock_skew_ms", ScalarType::Int64)
+                .add_column("source_skew_ms", ScalarType::Int64)
+                .add_keys(vec![0]),
         }
     }
 
@@ -349,6 +411,21 @@ impl LogVariant {
                 ),
             ],
             LogVariant::Materialized(MaterializedLog::Catalog) => vec![],
+            // Sinks that service `TAIL`s are not in the catalog.
+            LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent) => vec![],
+            LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies) => vec![],
+            LogVariant::Materialized(MaterializedLog::ViewFreshness) => vec![(
+                LogVariant::Materialized(MaterializedLog::Catalog).id(),
+                vec![(0, 0)],
+            )],
+            LogVariant::Materialized(MaterializedLog::ViewBackfills) => vec![(
+                LogVariant::Materialized(MaterializedLog::Catalog).id(),
+                vec![(0, 0)],
+            )],
+            LogVariant::Materialized(MaterializedLog::SourceSkew) => vec![(
+                LogVariant::Materialized(MaterializedLog::Catalog).id(),
+                vec![(0, 0)],
+            )],
         }
     }
 }
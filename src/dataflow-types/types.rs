@@ -14,18 +14,21 @@
 //! avoid the dependency, as the dataflow crate is very slow to compile.
 
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use url::Url;
 
 use expr::{
-    ColumnOrder, EvalEnv, GlobalId, OptimizedRelationExpr, RelationExpr, ScalarExpr,
+    ColumnOrder, EvalEnv, GlobalId, Id, OptimizedRelationExpr, RelationExpr, ScalarExpr,
     SourceInstanceId,
 };
 use regex::Regex;
-use repr::{Datum, RelationDesc, RelationType, Row};
+use repr::{Datum, RelationDesc, RelationType, Row, ScalarType};
+
+use crate::Error;
 
 /// System-wide update type.
 pub type Diff = isize;
@@ -47,15 +50,74 @@ pub enum PeekWhen {
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum PeekResponse {
     Rows(Vec<Row>),
-    Error(String),
+    /// The net change to each row that changed, in response to a peek with a
+    /// `since`.
+    Changes(Vec<(Row, Diff)>),
+    /// Each update to the collection with its time, in response to a peek
+    /// with a `history`.
+    Updates(Vec<(Row, Timestamp, Diff)>),
+    Error(Error),
     Canceled,
 }
 
+/// A message in the stream of results with which each worker answers a
+/// `Peek`.
+///
+/// A worker sends its response as one or more batches, each of which holds at
+/// most [`PEEK_BATCH_SIZE`] rows, so that no single message need hold the
+/// entire result. The batches are followed by `Done`, which distinguishes a
+/// worker that has finished sending its results from one that has gone away,
+/// and describes how the worker computed its results.
+///
+/// The rows of a peek whose recipient merges the responses of the workers are
+/// sent as runs instead, so that the recipient can merge the rows of all
+/// workers into a deterministic order as they arrive, rather than waiting for
+/// every row.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PeekMessage {
+    Batch(PeekResponse),
+    /// A run of rows, grouped by their key in the arrangement that the worker
+    /// read. The groups are in ascending order of key, and follow the groups
+    /// of the worker's previous runs. The last group of a run may be continued
+    /// by the first group of the next run.
+    Run(Vec<(Row, Vec<Row>)>),
+    Done(PeekMetadata),
+}
+
+/// A description of how the response to a `Peek` was computed.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PeekMetadata {
+    /// The logical time at which the peeked collection was read.
+    pub timestamp: Timestamp,
+    /// The number of rows in the response.
+    pub rows: usize,
+    /// The time between the arrival of the peek at the workers and the
+    /// moment the last of them produced its response.
+    pub duration: Duration,
+}
+
+impl PeekMetadata {
+    /// Combines the metadata that two workers reported for the same peek.
+    pub fn merge(self, other: PeekMetadata) -> PeekMetadata {
+        PeekMetadata {
+            timestamp: std::cmp::max(self.timestamp, other.timestamp),
+            rows: self.rows + other.rows,
+            duration: std::cmp::max(self.duration, other.duration),
+        }
+    }
+}
+
+/// The maximum number of rows in a [`PeekMessage::Batch`] or [`PeekMessage::Run`].
+pub const PEEK_BATCH_SIZE: usize = 10_000;
+
 impl PeekResponse {
     pub fn unwrap_rows(self) -> Vec<Row> {
         match self {
             PeekResponse::Rows(rows) => rows,
-            PeekResponse::Error(_) | PeekResponse::Canceled => {
+            PeekResponse::Changes(_)
+            | PeekResponse::Updates(_)
+            | PeekResponse::Error(_)
+            | PeekResponse::Canceled => {
                 panic!("PeekResponse::unwrap_rows called on an invalid response")
             }
         }
@@ -101,6 +163,14 @@ where
 /// keywords), whereas much of the rest of SQL is defined in terms of unordered
 /// multisets. But as it turns out, the same idea can be used to optimize
 /// trivial peeks.
+///
+/// Finishing always produces rows in a deterministic order, even when
+/// `order_by` is empty. Rows are first ordered by `order_by`, and any ties are
+/// broken by comparing the rows in their entirety. The order in which workers
+/// happen to return their results therefore never leaks through to clients:
+/// the same query against the same data always produces the same sequence of
+/// rows. Only the order imposed by `order_by` is meaningful, however; the
+/// order among rows that `order_by` considers equal is unspecified.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RowSetFinishing {
     /// Order rows by the given columns.
@@ -114,28 +184,41 @@ pub struct RowSetFinishing {
 }
 
 impl RowSetFinishing {
-    /// True if the finishing does nothing to any result set.
+    /// True if the finishing does nothing to any result set, other than
+    /// putting it into its deterministic order.
     pub fn is_trivial(&self) -> bool {
         (self.limit == None) && self.order_by.is_empty() && self.offset == 0
     }
+    /// Discards the rows that cannot appear in the finished row set, because
+    /// at least `offset + limit` rows precede them, without ordering or
+    /// projecting the remaining rows.
+    ///
+    /// Applying `truncate` any number of times before `finish` does not change
+    /// the finished row set, so partial results may be truncated as they are
+    /// combined.
+    pub fn truncate(&self, rows: &mut Vec<Row>) {
+        if let Some(limit) = self.limit {
+            let offset_plus_limit = self.offset + limit;
+            if rows.len() > offset_plus_limit {
+                pdqselect::select_by(rows, offset_plus_limit, |left, right| {
+                    self.compare(left, right)
+                });
+                rows.truncate(offset_plus_limit);
+            }
+        }
+    }
+
     /// Applies finishing actions to a row set.
+    ///
+    /// The resulting rows are in a deterministic order, as described in the
+    /// documentation for `RowSetFinishing`.
     pub fn finish(&self, rows: &mut Vec<Row>) {
-        let mut sort_by = |left: &Row, right: &Row| {
-            compare_columns(&self.order_by, &left.unpack(), &right.unpack(), || {
-                left.cmp(right)
-            })
-        };
+        let mut sort_by = |left: &Row, right: &Row| self.compare(left, right);
         let offset = self.offset;
         if offset > rows.len() {
             *rows = Vec::new();
         } else {
-            if let Some(limit) = self.limit {
-                let offset_plus_limit = offset + limit;
-                if rows.len() > offset_plus_limit {
-                    pdqselect::select_by(rows, offset_plus_limit, &mut sort_by);
-                    rows.truncate(offset_plus_limit);
-                }
-            }
+            self.truncate(rows);
             if offset > 0 {
                 pdqselect::select_by(rows, offset, &mut sort_by);
                 rows.drain(..offset);
@@ -148,6 +231,14 @@ impl RowSetFinishing {
             }
         }
     }
+
+    fn compare(&self, left: &Row, right: &Row) -> Ordering {
+        // Breaking ties by comparing entire rows makes this a total order,
+        // which is what guarantees deterministic output.
+        compare_columns(&self.order_by, &left.unpack(), &right.unpack(), || {
+            left.cmp(right)
+        })
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -157,6 +248,18 @@ pub struct BuildDesc {
     pub eval_env: EvalEnv,
     /// is_some if building a view, none otherwise
     pub typ: Option<RelationType>,
+    /// If set, the inputs to the view are only advanced at multiples of this
+    /// interval, batching together all updates that occur in between.
+    pub refresh_interval: Option<Duration>,
+    /// If set, the view's updates are consolidated before they are presented
+    /// to downstream consumers, so that updates which do not change the
+    /// view's contents are never observed.
+    pub changes_only: bool,
+    /// If set, the index of a timestamp column that indicates when each row
+    /// of the view becomes final, when interpreted as milliseconds since the
+    /// Unix epoch. Updates to a row are delayed until that time, and updates
+    /// that occur after that time are discarded.
+    pub emit_final_after: Option<usize>,
 }
 
 /// A description of a dataflow to construct and results to surface.
@@ -178,6 +281,18 @@ pub struct DataflowDesc {
     pub as_of: Option<Vec<Timestamp>>,
     /// Human readable name
     pub debug_name: String,
+    /// The compute cluster that runs the dataflow, or `None` for the default
+    /// cluster. A dataflow can only import arrangements that are maintained
+    /// by the same cluster.
+    pub cluster: Option<String>,
+    /// Groups of sources that are only ever read as their union, and so are
+    /// decoded in a single input stage, as negotiated by
+    /// `DataflowDesc::fuse_source_unions`.
+    pub fused_sources: Vec<Vec<SourceInstanceId>>,
+    /// Whether the workers gather per-operator statistics for the dataflow,
+    /// which the first peek at its first exported index reports in place of
+    /// the index's contents.
+    pub profile: bool,
 }
 
 impl DataflowDesc {
@@ -211,8 +326,14 @@ impl DataflowDesc {
         connector: SourceConnector,
         desc: RelationDesc,
     ) {
-        self.source_imports
-            .insert(id, SourceDesc { connector, desc });
+        self.source_imports.insert(
+            id,
+            SourceDesc {
+                connector,
+                desc,
+                operators: None,
+            },
+        );
     }
 
     pub fn add_view_to_build(
@@ -221,12 +342,18 @@ impl DataflowDesc {
         expr: OptimizedRelationExpr,
         eval_env: EvalEnv,
         typ: RelationType,
+        refresh_interval: Option<Duration>,
+        changes_only: bool,
+        emit_final_after: Option<usize>,
     ) {
         self.objects_to_build.push(BuildDesc {
             id,
             relation_expr: expr,
             eval_env,
             typ: Some(typ),
+            refresh_interval,
+            changes_only,
+            emit_final_after,
         });
     }
 
@@ -246,6 +373,9 @@ impl DataflowDesc {
             }),
             eval_env,
             typ: None,
+            refresh_interval: None,
+            changes_only: false,
+            emit_final_after: None,
         });
     }
 
@@ -309,6 +439,220 @@ impl DataflowDesc {
         }
         result
     }
+
+    /// Determines the operators that each imported source applies to its
+    /// records as soon as they are decoded, before shipping them to the rest
+    /// of the dataflow.
+    ///
+    /// A predicate is pushed down to a source only if every use of the source
+    /// is filtered by it. The uses themselves are left unchanged, as the
+    /// pushed-down operators only discard records that every use discards
+    /// anyway. Records that pass keep all of their columns.
+    pub fn push_down_source_operators(&mut self) {
+        let objects_to_build = &self.objects_to_build;
+        let sink_exports = &self.sink_exports;
+        for (src_id, src) in self.source_imports.iter_mut() {
+            // Sinks observe their inputs in full.
+            if sink_exports
+                .iter()
+                .any(|(_, sink)| sink.from.0 == src_id.sid)
+            {
+                continue;
+            }
+            let mut uses = Vec::new();
+            for object in objects_to_build {
+                source_uses(object.relation_expr.as_ref(), src_id.sid, &mut uses);
+            }
+            if uses.is_empty() {
+                continue;
+            }
+
+            // Predicates that depend on the evaluation environment cannot be
+            // evaluated by the source, which has no environment of its own.
+            let mut predicates = uses[0].clone();
+            predicates.retain(|predicate| {
+                let mut nullary = false;
+                predicate.visit(&mut |e| {
+                    if let ScalarExpr::CallNullary(_) = e {
+                        nullary = true;
+                    }
+                });
+                !nullary && uses.iter().all(|predicates| predicates.contains(predicate))
+            });
+
+            if !predicates.is_empty() {
+                src.operators = Some(LinearOperator { predicates });
+            }
+        }
+    }
+
+    /// Finds the groups of sources that are only ever read as their union,
+    /// as when a topic is sharded across several Kafka topics, so that each
+    /// group can be decoded in a single input stage rather than one stage per
+    /// source.
+    ///
+    /// The sources in a group must be Kafka sources with the same encoding,
+    /// envelope, and type, and, as this must be called after
+    /// `push_down_source_operators`, the same pushed-down operators.
+    pub fn fuse_source_unions(&mut self) {
+        let mut unions = Vec::new();
+        let mut gets = HashMap::new();
+        for object in &self.objects_to_build {
+            let expr = object.relation_expr.as_ref();
+            collect_unions_of_gets(expr, &mut unions);
+            expr.visit(&mut |e| {
+                if let RelationExpr::Get {
+                    id: Id::Global(id), ..
+                } = e
+                {
+                    *gets.entry(*id).or_insert(0) += 1;
+                }
+            });
+        }
+
+        // Each use of a fused source must be in one of its group's unions, and
+        // a source can belong to only one group.
+        let mut uses_in_unions = HashMap::new();
+        for ids in &unions {
+            for id in ids {
+                *uses_in_unions.entry(*id).or_insert(0) += 1;
+            }
+        }
+        unions.sort();
+        unions.dedup();
+        let mut groups_per_source = HashMap::new();
+        for ids in &unions {
+            for id in ids {
+                *groups_per_source.entry(*id).or_insert(0) += 1;
+            }
+        }
+
+        for ids in unions {
+            let sources = ids
+                .iter()
+                .map(|id| {
+                    self.source_imports
+                        .iter()
+                        .find(|(src_id, _)| src_id.sid == *id)
+                })
+                .collect::<Option<Vec<_>>>();
+            let sources = match sources {
+                Some(sources) => sources,
+                None => continue,
+            };
+            let exclusive = ids.iter().all(|id| {
+                gets.get(id) == uses_in_unions.get(id)
+                    && groups_per_source[id] == 1
+                    && !self.sink_exports.iter().any(|(_, sink)| sink.from.0 == *id)
+            });
+            let (_, first) = sources[0];
+            let homogeneous = sources.iter().all(|(_, src)| can_fuse(first, src));
+            if exclusive && homogeneous {
+                self.fused_sources
+                    .push(sources.into_iter().map(|(src_id, _)| *src_id).collect());
+            }
+        }
+    }
+}
+
+/// Returns the IDs of the global collections that `expr` unions together, in
+/// sorted order, if `expr` is a union of two or more distinct global
+/// collections, possibly nested.
+pub fn union_of_gets(expr: &RelationExpr) -> Option<Vec<GlobalId>> {
+    fn leaves<'a>(expr: &'a RelationExpr, out: &mut Vec<&'a RelationExpr>) {
+        match expr {
+            RelationExpr::Union { left, right } => {
+                leaves(left, out);
+                leaves(right, out);
+            }
+            _ => out.push(expr),
+        }
+    }
+
+    if let RelationExpr::Union { .. } = expr {
+        let mut exprs = Vec::new();
+        leaves(expr, &mut exprs);
+        let mut ids = exprs
+            .into_iter()
+            .map(|e| match e {
+                RelationExpr::Get {
+                    id: Id::Global(id), ..
+                } => Some(*id),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        ids.sort();
+        let len = ids.len();
+        ids.dedup();
+        if ids.len() == len {
+            return Some(ids);
+        }
+    }
+    None
+}
+
+/// Reports whether `b` can be decoded in the same input stage as `a`.
+fn can_fuse(a: &SourceDesc, b: &SourceDesc) -> bool {
+    match (&a.connector, &b.connector) {
+        (
+            SourceConnector::External {
+                connector: ExternalSourceConnector::Kafka(_),
+                encoding: a_encoding,
+                envelope: a_envelope,
+                metadata: a_metadata,
+                deduplication: a_deduplication,
+                ..
+            },
+            SourceConnector::External {
+                connector: ExternalSourceConnector::Kafka(_),
+                encoding: b_encoding,
+                envelope: b_envelope,
+                metadata: b_metadata,
+                deduplication: b_deduplication,
+                ..
+            },
+        ) => {
+            a_encoding == b_encoding
+                && a_envelope == b_envelope
+                && a_metadata == b_metadata
+                && a_deduplication == b_deduplication
+                && a.desc.typ() == b.desc.typ()
+                && a.operators == b.operators
+        }
+        _ => false,
+    }
+}
+
+/// Records the outermost unions of distinct global collections in `expr`.
+fn collect_unions_of_gets(expr: &RelationExpr, unions: &mut Vec<Vec<GlobalId>>) {
+    match union_of_gets(expr) {
+        Some(ids) => unions.push(ids),
+        None => expr.visit1(|e| collect_unions_of_gets(e, unions)),
+    }
+}
+
+/// Records the predicates applied directly to each use of the global
+/// collection `id` in `expr`.
+fn source_uses(expr: &RelationExpr, id: GlobalId, uses: &mut Vec<Vec<ScalarExpr>>) {
+    let is_use = |e: &RelationExpr| match e {
+        RelationExpr::Get {
+            id: Id::Global(get_id),
+            ..
+        } => *get_id == id,
+        _ => false,
+    };
+    match expr {
+        RelationExpr::Filter { input, predicates } if is_use(input) => {
+            uses.push(predicates.clone());
+            return;
+        }
+        expr if is_use(expr) => {
+            uses.push(Vec::new());
+            return;
+        }
+        _ => (),
+    }
+    expr.visit1(|e| source_uses(e, id, uses));
 }
 
 /// A description of how each row should be decoded, from a string of bytes to a sequence of
@@ -325,26 +669,65 @@ pub enum DataEncoding {
     Protobuf(ProtobufEncoding),
     Bytes,
     Text,
+    /// Each record is a JSON document.
+    Json(JsonEncoding),
+    /// Each record is a row of a table in a database, like PostgreSQL or
+    /// MySQL, which the source decodes itself.
+    Row,
+}
+
+// `Regex` does not implement `PartialEq`, so regexes are compared by their
+// source text instead.
+impl PartialEq for DataEncoding {
+    fn eq(&self, other: &DataEncoding) -> bool {
+        match (self, other) {
+            (DataEncoding::Avro(a), DataEncoding::Avro(b)) => a == b,
+            (DataEncoding::Csv(a), DataEncoding::Csv(b)) => a == b,
+            (DataEncoding::Regex { regex: a }, DataEncoding::Regex { regex: b }) => {
+                a.as_str() == b.as_str()
+            }
+            (DataEncoding::Protobuf(a), DataEncoding::Protobuf(b)) => a == b,
+            (DataEncoding::Json(a), DataEncoding::Json(b)) => a == b,
+            (DataEncoding::Bytes, DataEncoding::Bytes)
+            | (DataEncoding::Text, DataEncoding::Text)
+            | (DataEncoding::Row, DataEncoding::Row) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Encoding in Avro format.
 ///
 /// Assumes Debezium-style `before: ..., after: ...` structure.
 #[serde(rename_all = "snake_case")]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AvroEncoding {
     pub raw_schema: String,
     pub schema_registry_url: Option<Url>,
 }
 
-/// Encoding in CSV format, with no headers, and `n_cols` columns per row.
+/// Encoding in CSV format, with one column per entry in `column_types`.
 #[serde(rename_all = "snake_case")]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CsvEncoding {
-    pub n_cols: usize,
+    /// The type of each column. Fields are parsed as the text representation
+    /// of a value of their column's type.
+    pub column_types: Vec<ScalarType>,
+    /// Whether the first line of the input is a header, which is skipped.
+    pub header: bool,
     pub delimiter: u8,
 }
 
+/// Encoding in JSON format.
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonEncoding {
+    /// The names and types of the columns into which the top-level fields of
+    /// each document are decoded, or empty if each document is decoded into
+    /// a single `jsonb` value.
+    pub columns: Vec<(String, ScalarType)>,
+}
+
 /// Encoding in Protobuf format.
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -363,6 +746,18 @@ pub struct ProtobufEncoding {
 pub struct SourceDesc {
     pub connector: SourceConnector,
     pub desc: RelationDesc,
+    /// Operators to apply to the source's records as soon as they are
+    /// decoded, as negotiated by `DataflowDesc::push_down_source_operators`.
+    pub operators: Option<LinearOperator>,
+}
+
+/// Filters that a source applies to its records before shipping them to the
+/// dataflows that use it.
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LinearOperator {
+    /// Records that do not satisfy every predicate are discarded.
+    pub predicates: Vec<ScalarExpr>,
 }
 
 /// A sink for updates to a relational collection.
@@ -386,15 +781,50 @@ pub enum SourceConnector {
         encoding: DataEncoding,
         envelope: Envelope,
         consistency: Consistency,
+        /// The metadata columns to append to each decoded record, in order.
+        metadata: Vec<SourceMetadata>,
+        /// How to discard the records that repeat a recent record, if at all.
+        deduplication: Option<Deduplication>,
     },
     Local,
 }
 
+/// Discards the records of a source whose key matches that of a record
+/// ingested shortly before, as upstream producers that deliver records at
+/// least once may send the same record more than once.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Deduplication {
+    /// The columns of the decoded record that identify it.
+    pub key: Vec<usize>,
+    /// How long after a record's timestamp records with the same key are
+    /// considered duplicates of it.
+    pub window: Duration,
+}
+
+/// A column of metadata about each record that an external source can append
+/// to the record's contents.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SourceMetadata {
+    /// The record's position in the source, if the source reports one.
+    Offset,
+    /// The partition from which the record was read, if the source is
+    /// partitioned.
+    Partition,
+    /// The time at which the record was ingested.
+    Timestamp,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ExternalSourceConnector {
     Kafka(KafkaSourceConnector),
     Kinesis(KinesisSourceConnector),
     File(FileSourceConnector),
+    Mqtt(MqttSourceConnector),
+    Nats(NatsSourceConnector),
+    Plugin(PluginSourceConnector),
+    Postgres(PostgresSourceConnector),
+    Mysql(MysqlSourceConnector),
+    S3(S3SourceConnector),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -408,6 +838,15 @@ pub struct KafkaSourceConnector {
     pub url: Url,
     pub topic: String,
     pub ssl_certificate_file: Option<PathBuf>,
+    /// The offset at which to begin reading the topic, or `None` to begin at
+    /// the earliest offset that the broker retains.
+    pub start_offset: Option<i64>,
+    /// The consumer group in which to read the topic, or `None` for a group
+    /// named after the source.
+    pub group_id: Option<String>,
+    /// Additional librdkafka configuration options, which take precedence
+    /// over the defaults.
+    pub config_options: BTreeMap<String, String>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -418,15 +857,122 @@ pub struct KinesisSourceConnector {
     pub region: String,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MqttSourceConnector {
+    pub host: String,
+    pub port: u16,
+    /// The topic filter to subscribe to, which may contain the `+` and `#`
+    /// wildcards.
+    pub topic: String,
+    pub qos: MqttQos,
+    /// The client identifier to present to the broker. If absent, one is
+    /// derived from the source's ID.
+    pub client_id: Option<String>,
+}
+
+/// The quality of service with which an MQTT source subscribes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MqttQos {
+    AtMostOnce,
+    AtLeastOnce,
+    ExactlyOnce,
+}
+
+/// A source that reads a NATS JetStream stream through a durable consumer.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NatsSourceConnector {
+    pub url: Url,
+    /// The subject filter, which may contain the `*` and `>` wildcards.
+    pub subject: String,
+    /// The JetStream stream that stores the messages.
+    pub stream: String,
+    /// The name of the durable consumer through which the stream is read,
+    /// which records how far the source has read.
+    pub durable: String,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FileSourceConnector {
     pub path: PathBuf,
     pub tail: bool,
+    pub compression: Compression,
+}
+
+/// The compression applied to the contents of a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    None,
+}
+
+/// A source connector that was registered with the dataflow layer, rather
+/// than built in.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PluginSourceConnector {
+    /// The name under which the plugin was registered.
+    pub name: String,
+    /// The options from the source's `WITH` clause, which the plugin
+    /// interprets.
+    pub options: BTreeMap<String, String>,
+}
+
+/// A source that reads a PostgreSQL table, first as of a snapshot and then
+/// through the changes that a logical replication slot reports.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PostgresSourceConnector {
+    /// The libpq connection string of the database.
+    pub conn: String,
+    /// The name of the table, which may be qualified with its schema.
+    pub table: String,
+}
+
+/// A source that reads a MySQL table, first as of a snapshot and then through
+/// the changes that the server's binary log records.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MysqlSourceConnector {
+    /// The URL of the server, including the credentials of the user to
+    /// connect as.
+    pub url: Url,
+    /// The database that contains the table.
+    pub database: String,
+    /// The name of the table.
+    pub table: String,
+    /// Whether the server's public key may be retrieved over the unencrypted
+    /// connection, to encrypt the password with if the server asks for it in
+    /// full.
+    pub get_server_public_key: bool,
+}
+
+/// A source that reads the lines of the objects in an S3 bucket.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct S3SourceConnector {
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_access_key: String,
+    /// Only objects whose keys begin with this prefix are read.
+    pub prefix: String,
+    /// A glob pattern, in which `*` matches any sequence of characters and `?`
+    /// matches any one character, that the keys of the objects to read must
+    /// match in their entirety.
+    pub pattern: Option<String>,
+    /// The URL of an SQS queue that receives the bucket's notifications of
+    /// created objects. If present, objects that are created after the
+    /// bucket is first listed are read as their notifications arrive.
+    pub sqs_queue: Option<Url>,
+    /// The endpoint of an S3-compatible service to use in place of AWS, for
+    /// which objects are addressed in path style.
+    pub endpoint: Option<Url>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum SinkConnector {
+    Elasticsearch(ElasticsearchSinkConnector),
     Kafka(KafkaSinkConnector),
+    Nats(NatsSinkConnector),
+    Plugin(PluginSinkConnector),
+    Redis(RedisSinkConnector),
     Tail(TailSinkConnector),
 }
 
@@ -437,10 +983,63 @@ pub struct KafkaSinkConnector {
     pub schema_registry_url: Url,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ElasticsearchSinkConnector {
+    pub url: Url,
+    pub index: String,
+    /// The indices of the columns that form each row's document ID.
+    pub key_indices: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NatsSinkConnector {
+    pub url: Url,
+    /// The subject to publish to, which must be stored by a JetStream
+    /// stream.
+    pub subject: String,
+}
+
+/// A sink connector that was registered with the dataflow layer, rather than
+/// built in.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PluginSinkConnector {
+    /// The name under which the plugin was registered.
+    pub name: String,
+    /// The options from the sink's `WITH` clause, which the plugin
+    /// interprets.
+    pub options: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RedisSinkConnector {
+    pub url: Url,
+    /// The indices of the columns that form each row's Redis key.
+    pub key_indices: Vec<usize>,
+    /// A prefix for every Redis key written by the sink.
+    pub key_prefix: String,
+    pub value_format: RedisValueFormat,
+}
+
+/// How a Redis sink stores the non-key columns of each row.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RedisValueFormat {
+    /// As a string containing a JSON object.
+    Json,
+    /// As a hash with one field per column.
+    Hash,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TailSinkConnector {
     pub tx: comm::mpsc::Sender<Vec<Update>>,
     pub since: Timestamp,
+    /// The predicates that rows must satisfy to be sent.
+    pub filter: Vec<ScalarExpr>,
+    /// The columns to which rows are projected before they are sent, or
+    /// `None` to send all columns.
+    pub project: Option<Vec<usize>>,
+    /// The environment in which `filter` is evaluated.
+    pub eval_env: EvalEnv,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
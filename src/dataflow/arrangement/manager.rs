@@ -11,10 +11,11 @@
 
 use differential_dataflow::operators::arrange::TraceAgent;
 use std::collections::HashMap;
+use std::mem;
 
 use dataflow_types::{Diff, Timestamp};
-use expr::GlobalId;
-use repr::Row;
+use expr::{GlobalId, ScalarExpr};
+use repr::{ColumnType, Row};
 
 use differential_dataflow::trace::implementations::ord::OrdValBatch;
 use differential_dataflow::trace::implementations::spine_fueled_neu::Spine;
@@ -34,12 +35,121 @@ pub type KeysValsHandle = TraceValHandle<Row, Row, Timestamp, Diff>;
 pub struct TraceManager {
     /// A map from global identifiers to maintained traces.
     pub traces: HashMap<GlobalId, WithDrop<KeysValsHandle>>,
+    /// Arrangements that dataflows build of the collections they read, by
+    /// the identifier of the collection and the keys of the arrangement, so
+    /// that later dataflows can import them rather than arrange the same
+    /// collection again.
+    shared: HashMap<(GlobalId, Vec<ScalarExpr>), SharedTrace>,
+    /// The updates that have arrived in each of `traces` since it was last
+    /// released for merging.
+    unreleased: HashMap<GlobalId, UnreleasedCount>,
+    /// The position, among the managed traces, of the first trace whose
+    /// release for merging the last call to [`TraceManager::maintenance`]
+    /// deferred, so that the next call releases it first.
+    maintenance_cursor: usize,
+}
+
+/// An arrangement that a dataflow offers to other dataflows.
+struct SharedTrace {
+    /// The index whose dataflow built the arrangement. The arrangement is
+    /// compacted along with the index, and forgotten when the index is
+    /// dropped.
+    owner: GlobalId,
+    /// The types of the keys by which the collection is arranged.
+    key_types: Vec<ColumnType>,
+    trace: WithDrop<KeysValsHandle>,
+    unreleased: UnreleasedCount,
+}
+
+/// The updates in the batches of a trace that have arrived since the trace was
+/// last released for merging.
+///
+/// Each batch is counted once, when it is first observed, rather than by every
+/// call to [`TraceManager::maintenance`].
+#[derive(Debug)]
+struct UnreleasedCount {
+    /// The upper frontier of the batches that have been counted.
+    upper: Vec<Timestamp>,
+    /// The number of updates in the counted batches that have not been
+    /// released.
+    records: usize,
+}
+
+impl UnreleasedCount {
+    /// Starts counting the batches of `handle` that have not been released.
+    fn new(handle: &mut WithDrop<KeysValsHandle>) -> UnreleasedCount {
+        use differential_dataflow::trace::TraceReader;
+        UnreleasedCount {
+            upper: handle.distinguish_frontier().to_vec(),
+            records: 0,
+        }
+    }
+
+    /// Counts the batches of `handle` that have arrived since the last call.
+    fn observe(&mut self, handle: &mut WithDrop<KeysValsHandle>) {
+        use differential_dataflow::trace::{BatchReader, TraceReader};
+        handle.map_batches(|batch| {
+            let description = batch.description();
+            self.observe_batch(description.lower(), description.upper(), batch.len());
+        });
+    }
+
+    /// Counts the `len` updates of the batch from `lower` to `upper`, unless
+    /// it has already been counted.
+    fn observe_batch(&mut self, lower: &[Timestamp], upper: &[Timestamp], len: usize) {
+        // Batches that have already been counted, and the batches that they
+        // have been merged into, begin before the counted upper frontier.
+        if lower.iter().all(|t| self.upper.iter().any(|u| u <= t)) {
+            self.records += len;
+            self.upper = upper.to_vec();
+        }
+    }
+
+    /// Releases the counted batches of `handle` for merging.
+    fn release(&mut self, handle: &mut WithDrop<KeysValsHandle>) {
+        use differential_dataflow::trace::TraceReader;
+        handle.distinguish_since(&self.upper);
+        self.records = 0;
+    }
+}
+
+/// A description of an arrangement that a [`TraceManager`] holds, for
+/// introspection.
+#[derive(Debug)]
+pub struct ArrangementStats {
+    /// The index that the arrangement implements or, if the arrangement is
+    /// shared, the index whose dataflow built it.
+    pub index_id: GlobalId,
+    /// If the arrangement is shared, the collection that it arranges and the
+    /// types of its keys.
+    pub shared: Option<(GlobalId, Vec<ColumnType>)>,
+    /// The number of batches in the arrangement.
+    pub batches: usize,
+    /// The number of updates in the arrangement, as in
+    /// [`TraceManager::dataflow_sizes`].
+    pub records: usize,
+    /// The approximate number of bytes that the batches of the arrangement
+    /// occupy.
+    pub bytes: usize,
+}
+
+/// The merging work that a call to [`TraceManager::maintenance`] deferred.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct MergeDebt {
+    /// The number of traces that were not released for merging.
+    pub traces: usize,
+    /// The number of updates in the batches of those traces that arrived
+    /// since the traces were last released for merging.
+    pub records: usize,
 }
 
 impl Default for TraceManager {
     fn default() -> Self {
         TraceManager {
             traces: HashMap::new(),
+            shared: HashMap::new(),
+            unreleased: HashMap::new(),
+            maintenance_cursor: 0,
         }
     }
 }
@@ -52,13 +162,46 @@ impl TraceManager {
     /// will not be physically merged until the method is called again. This is mostly due to limitations
     /// of differential dataflow, which requires users to perform this explicitly; if that changes we may
     /// be able to remove this code.
-    pub fn maintenance(&mut self) {
-        let mut antichain = timely::progress::frontier::Antichain::new();
-        for handle in self.traces.values_mut() {
-            use differential_dataflow::trace::TraceReader;
-            handle.read_upper(&mut antichain);
-            handle.distinguish_since(antichain.elements());
+    ///
+    /// Releasing a trace commits the worker to merging the batches that have
+    /// arrived since its last release, which after a bulk load can be a great
+    /// deal of work. Traces are therefore released only until the updates in
+    /// those batches exceed `effort`; the remaining traces are released first
+    /// by the next call, and are described by the returned [`MergeDebt`]. At
+    /// least one trace is released by every call, so that a trace whose new
+    /// batches alone exceed `effort` is not deferred indefinitely.
+    pub fn maintenance(&mut self, effort: usize) -> MergeDebt {
+        // The traces are visited in the same order by both passes, as neither
+        // map is modified in between.
+        let mut unreleased = vec![];
+        for (id, handle) in self.traces.iter_mut() {
+            let count = self
+                .unreleased
+                .entry(*id)
+                .or_insert_with(|| UnreleasedCount::new(handle));
+            count.observe(handle);
+            unreleased.push(count.records);
+        }
+        for shared in self.shared.values_mut() {
+            shared.unreleased.observe(&mut shared.trace);
+            unreleased.push(shared.unreleased.records);
         }
+
+        let (released, debt, cursor) =
+            plan_maintenance(&unreleased, self.maintenance_cursor, effort);
+        self.maintenance_cursor = cursor;
+        let mut released = released.into_iter();
+        for (id, handle) in self.traces.iter_mut() {
+            if released.next() == Some(true) {
+                self.unreleased.get_mut(id).unwrap().release(handle);
+            }
+        }
+        for shared in self.shared.values_mut() {
+            if released.next() == Some(true) {
+                shared.unreleased.release(&mut shared.trace);
+            }
+        }
+        debt
     }
 
     /// Enables compaction of traces associated with the identifier.
@@ -67,11 +210,64 @@ impl TraceManager {
     /// associated traces may not accumulate to the correct quantities for times
     /// not in advance of `frontier`. Users should take care to only rely on
     /// accumulations at times in advance of `frontier`.
+    ///
+    /// This only releases the manager's own hold on the traces. Every clone of
+    /// a trace handle, like those of pending peeks and of the dataflows that
+    /// import the trace, holds back compaction to its own frontier, so reads
+    /// that are in progress are unaffected.
     pub fn allow_compaction(&mut self, id: GlobalId, frontier: &[Timestamp]) {
         use differential_dataflow::trace::TraceReader;
         if let Some(val) = self.traces.get_mut(&id) {
             val.advance_by(frontier);
         }
+        for shared in self.shared.values_mut() {
+            if shared.owner == id {
+                shared.trace.advance_by(frontier);
+            }
+        }
+    }
+
+    /// Returns the number of updates and the approximate number of bytes that
+    /// the arrangements of each dataflow hold, keyed by the index that the
+    /// dataflow exports, as in [`TraceManager::stats`].
+    ///
+    /// The counts include updates that have not yet been consolidated by
+    /// merging, so they are a measure of the memory the arrangements occupy
+    /// rather than of the size of the collections they represent.
+    pub fn dataflow_sizes(&mut self) -> Vec<(GlobalId, usize, usize)> {
+        let mut sizes: HashMap<GlobalId, (usize, usize)> = HashMap::new();
+        for stats in self.stats() {
+            let size = sizes.entry(stats.index_id).or_default();
+            size.0 += stats.records;
+            size.1 += stats.bytes;
+        }
+        sizes
+            .into_iter()
+            .map(|(id, (records, bytes))| (id, records, bytes))
+            .collect()
+    }
+
+    /// Describes every managed trace, including the arrangements that
+    /// dataflows share.
+    ///
+    /// Determining the size of an arrangement requires visiting each of its
+    /// keys and values, so this is as expensive as reading every arrangement
+    /// in full.
+    pub fn stats(&mut self) -> Vec<ArrangementStats> {
+        let mut stats: Vec<_> = self
+            .traces
+            .iter_mut()
+            .map(|(id, handle)| trace_stats(*id, None, handle))
+            .collect();
+        for ((on_id, _keys), shared) in self.shared.iter_mut() {
+            let key_types = shared.key_types.clone();
+            stats.push(trace_stats(
+                shared.owner,
+                Some((*on_id, key_types)),
+                &mut shared.trace,
+            ));
+        }
+        stats
     }
 
     /// Returns a copy of a by_key arrangement, should it exist.
@@ -94,15 +290,119 @@ impl TraceManager {
         self.traces.insert(id, trace);
     }
 
-    /// Removes a trace
+    /// Offers the arrangement of the collection `on_id` by `keys`, which the
+    /// dataflow of the index `owner` has built, to other dataflows.
+    /// `key_types` are the types of the keys, for introspection.
+    pub fn share(
+        &mut self,
+        owner: GlobalId,
+        on_id: GlobalId,
+        keys: Vec<ScalarExpr>,
+        key_types: Vec<ColumnType>,
+        mut trace: WithDrop<KeysValsHandle>,
+    ) {
+        self.shared
+            .entry((on_id, keys))
+            .or_insert_with(|| SharedTrace {
+                owner,
+                key_types,
+                unreleased: UnreleasedCount::new(&mut trace),
+                trace,
+            });
+    }
+
+    /// Returns an arrangement of the collection `on_id` by `keys` that
+    /// another dataflow has offered, should one exist.
+    pub fn get_shared_mut(
+        &mut self,
+        on_id: GlobalId,
+        keys: &[ScalarExpr],
+    ) -> Option<&mut WithDrop<KeysValsHandle>> {
+        self.shared
+            .get_mut(&(on_id, keys.to_vec()))
+            .map(|shared| &mut shared.trace)
+    }
+
+    /// Removes a trace, along with the arrangements that its dataflow offered
+    /// to other dataflows.
     pub fn del_trace(&mut self, id: &GlobalId) -> bool {
+        self.shared.retain(|_, shared| shared.owner != *id);
+        self.unreleased.remove(&id);
         self.traces.remove(&id).is_some()
     }
 
     /// Removes all remnants of all named traces.
     pub fn del_all_traces(&mut self) {
         self.traces.clear();
+        self.shared.clear();
+        self.unreleased.clear();
+    }
+}
+
+/// Decides which traces a call to [`TraceManager::maintenance`] releases for
+/// merging, given the number of unreleased updates in each trace and the
+/// position of the trace to consider first. Returns whether each trace is
+/// released, the deferred work, and the position of the trace to consider
+/// first in the next call.
+fn plan_maintenance(
+    unreleased: &[usize],
+    cursor: usize,
+    effort: usize,
+) -> (Vec<bool>, MergeDebt, usize) {
+    let len = unreleased.len();
+    let mut released = vec![true; len];
+    let mut debt = MergeDebt::default();
+    if len == 0 {
+        return (released, debt, 0);
     }
+    let start = cursor % len;
+    let mut next_cursor = start;
+    let mut spent = 0;
+    for i in (start..len).chain(0..start) {
+        let records = unreleased[i];
+        if records > 0 && spent > 0 && spent + records > effort {
+            if debt.traces == 0 {
+                next_cursor = i;
+            }
+            released[i] = false;
+            debt.traces += 1;
+            debt.records += records;
+        } else {
+            spent += records;
+        }
+    }
+    (released, debt, next_cursor)
+}
+
+/// Computes the [`ArrangementStats`] of the trace `handle`.
+fn trace_stats(
+    index_id: GlobalId,
+    shared: Option<(GlobalId, Vec<ColumnType>)>,
+    handle: &mut WithDrop<KeysValsHandle>,
+) -> ArrangementStats {
+    use differential_dataflow::trace::{BatchReader, Cursor, TraceReader};
+    let mut stats = ArrangementStats {
+        index_id,
+        shared,
+        batches: 0,
+        records: 0,
+        bytes: 0,
+    };
+    handle.map_batches(|batch| {
+        stats.batches += 1;
+        stats.records += batch.len();
+        stats.bytes += batch.len() * mem::size_of::<(Timestamp, Diff)>();
+        let mut cursor = batch.cursor();
+        while cursor.key_valid(batch) {
+            stats.bytes += mem::size_of::<Row>() + cursor.key(batch).data().len();
+            while cursor.val_valid(batch) {
+                stats.bytes += mem::size_of::<Row>() + cursor.val(batch).data().len();
+                cursor.step_val(batch);
+            }
+            cursor.step_key(batch);
+        }
+    });
+    stats
 }
 
 /// A thin wrapper containing an associated item to drop.
@@ -152,3 +452,76 @@ impl<T> std::ops::DerefMut for WithDrop<T> {
         &mut self.element
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_maintenance, MergeDebt, UnreleasedCount};
+
+    fn debt(traces: usize, records: usize) -> MergeDebt {
+        MergeDebt { traces, records }
+    }
+
+    #[test]
+    fn test_plan_maintenance() {
+        assert_eq!(plan_maintenance(&[], 3, 10), (vec![], debt(0, 0), 0));
+
+        // Traces are released until their updates exceed the effort, and the
+        // first trace that is deferred is considered first by the next call.
+        assert_eq!(
+            plan_maintenance(&[4, 4, 4, 4], 0, 10),
+            (vec![true, true, false, false], debt(2, 8), 2)
+        );
+        assert_eq!(
+            plan_maintenance(&[0, 0, 4, 4], 2, 10),
+            (vec![true, true, true, true], debt(0, 0), 2)
+        );
+
+        // Consideration wraps around from the cursor, which may point past
+        // traces that have since been removed.
+        assert_eq!(
+            plan_maintenance(&[4, 4, 4, 4], 3, 10),
+            (vec![true, false, false, true], debt(2, 8), 1)
+        );
+        assert_eq!(
+            plan_maintenance(&[4, 4, 4], 7, 10),
+            (vec![false, true, true], debt(1, 4), 0)
+        );
+
+        // The first trace with updates is released however many it has, and
+        // traces without updates are always released.
+        assert_eq!(
+            plan_maintenance(&[0, 100, 1, 0], 0, 10),
+            (vec![true, true, false, true], debt(1, 1), 2)
+        );
+    }
+
+    #[test]
+    fn test_unreleased_count() {
+        let mut count = UnreleasedCount {
+            upper: vec![0],
+            records: 0,
+        };
+        count.observe_batch(&[0], &[5], 3);
+        count.observe_batch(&[5], &[8], 2);
+        assert_eq!((count.upper.clone(), count.records), (vec![8], 5));
+
+        // Batches are not counted again when they are observed again, or
+        // once they have been merged.
+        count.observe_batch(&[0], &[5], 3);
+        count.observe_batch(&[5], &[8], 2);
+        count.observe_batch(&[0], &[8], 5);
+        assert_eq!(count.records, 5);
+
+        // After a release, only batches that arrive later are counted.
+        count.records = 0;
+        count.observe_batch(&[0], &[8], 5);
+        count.observe_batch(&[8], &[9], 1);
+        count.observe_batch(&[9], &[12], 0);
+        assert_eq!((count.upper.clone(), count.records), (vec![12], 1));
+
+        // A trace whose upper frontier is empty receives no more batches.
+        count.observe_batch(&[12], &[], 4);
+        count.observe_batch(&[12], &[13], 4);
+        assert_eq!((count.upper.clone(), count.records), (vec![], 5));
+    }
+}
@@ -14,35 +14,40 @@ use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
 use url::Url;
 
-use super::EVENTS_COUNTER;
-use dataflow_types::{Diff, Timestamp};
+use super::{append_metadata, EVENTS_COUNTER};
+use dataflow_types::{Diff, SourceMetadata, Timestamp};
 use repr::Row;
 
 pub fn avro<G>(
-    stream: &Stream<G, (Vec<u8>, Option<i64>)>,
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
     raw_schema: &str,
     schema_registry: Option<Url>,
+    metadata: Vec<SourceMetadata>,
 ) -> Stream<G, (Row, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
 {
     stream.unary(
-        Exchange::new(|x: &(Vec<u8>, _)| x.0.hashed()),
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
         "AvroDecode",
         move |_, _| {
             let mut decoder = interchange::avro::Decoder::new(raw_schema, schema_registry);
             move |input, output| {
                 input.for_each(|cap, data| {
                     let mut session = output.session(&cap);
-                    for (payload, _) in data.iter() {
+                    for (payload, position, partition) in data.iter() {
                         match decoder.decode(payload) {
                             Ok(diff_pair) => {
                                 EVENTS_COUNTER.avro.success.inc();
+                                let time = *cap.time();
+                                let append = |row| {
+                                    append_metadata(row, &metadata, *position, *partition, time)
+                                };
                                 if let Some(before) = diff_pair.before {
-                                    session.give((before, *cap.time(), 1));
+                                    session.give((append(before), time, 1));
                                 }
                                 if let Some(after) = diff_pair.after {
-                                    session.give((after, *cap.time(), 1));
+                                    session.give((append(after), time, 1));
                                 }
                             }
                             Err(err) => {
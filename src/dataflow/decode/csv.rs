@@ -7,7 +7,12 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::iter;
+//! Decoding of CSV records into rows.
+//!
+//! Each field is parsed as the text representation of a value of its
+//! column's type. Records that cannot be decoded, because they are malformed,
+//! have the wrong number of fields, or hold a field that is not a valid value
+//! of its column's type, are logged and skipped.
 
 use differential_dataflow::Hashable;
 use log::error;
@@ -15,20 +20,25 @@ use timely::dataflow::channels::pact::Exchange;
 use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
 
-use super::EVENTS_COUNTER;
-use dataflow_types::{Diff, Timestamp};
-use repr::{Datum, Row};
+use super::{pack_field, push_metadata, EVENTS_COUNTER};
+use dataflow_types::{CsvEncoding, Diff, SourceMetadata, Timestamp};
+use repr::{Datum, Row, RowPacker};
 
 pub fn csv<G>(
-    stream: &Stream<G, (Vec<u8>, Option<i64>)>,
-    n_cols: usize,
-    delimiter: u8,
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    encoding: CsvEncoding,
+    metadata: Vec<SourceMetadata>,
 ) -> Stream<G, (Row, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
 {
+    let CsvEncoding {
+        column_types,
+        header,
+        delimiter,
+    } = encoding;
     stream.unary(
-        Exchange::new(|x: &(Vec<u8>, _)| x.0.hashed()),
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
         "CsvDecode",
         |_, _| {
             move |input, output| {
@@ -39,33 +49,62 @@ where
                     // but the CsvReader *itself* searches for line breaks.
                     // This is mainly an aesthetic/performance-golfing
                     // issue as I doubt it will ever be a bottleneck.
-                    for (line, line_no) in &*lines {
+                    for (line, line_no, partition) in &*lines {
+                        // Only file sources have headers, and their lines are
+                        // numbered from one.
+                        if header && *line_no == Some(1) {
+                            continue;
+                        }
                         let mut csv_reader = csv::ReaderBuilder::new()
                             .has_headers(false)
                             .delimiter(delimiter)
                             .from_reader(line.as_slice());
                         for result in csv_reader.records() {
-                            let record = result.unwrap();
-                            if record.len() != n_cols {
+                            let record = match result {
+                                Ok(record) => record,
+                                Err(err) => {
+                                    EVENTS_COUNTER.csv.error.inc();
+                                    error!("CSV error: {}. Ignoring row.", err);
+                                    continue;
+                                }
+                            };
+                            if record.len() != column_types.len() {
                                 EVENTS_COUNTER.csv.error.inc();
                                 error!(
                                     "CSV error: expected {} columns, got {}. Ignoring row.",
-                                    n_cols,
+                                    column_types.len(),
                                     record.len()
                                 );
                                 continue;
                             }
-                            EVENTS_COUNTER.csv.success.inc();
-                            session.give((
-                                Row::pack(
-                                    record
-                                        .iter()
-                                        .map(|s| Datum::String(s))
-                                        .chain(iter::once(line_no.map(Datum::Int64).into())),
-                                ),
+                            let mut packer = RowPacker::new();
+                            let mut decoded = Ok(());
+                            for (i, (field, typ)) in record.iter().zip(&column_types).enumerate() {
+                                if let Err(err) = pack_field(&mut packer, field, *typ) {
+                                    decoded = Err((i, field, err));
+                                    break;
+                                }
+                            }
+                            if let Err((i, field, err)) = decoded {
+                                EVENTS_COUNTER.csv.error.inc();
+                                error!(
+                                    "CSV error: invalid value {:?} in column {}: {}. Ignoring row.",
+                                    field,
+                                    i + 1,
+                                    err
+                                );
+                                continue;
+                            }
+                            packer.push(Datum::from(*line_no));
+                            push_metadata(
+                                &mut packer,
+                                &metadata,
+                                *line_no,
+                                *partition,
                                 *cap.time(),
-                                1,
-                            ));
+                            );
+                            EVENTS_COUNTER.csv.success.inc();
+                            session.give((packer.finish(), *cap.time(), 1));
                         }
                     }
                 });
@@ -0,0 +1,104 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Decoding of JSON documents into rows whose columns are the documents'
+//! top-level fields.
+//!
+//! `jsonb` columns hold their field's JSON value as is. Other columns parse
+//! the text of their field, which is the contents of a string or the JSON
+//! representation of any other value, just as CSV sources parse their
+//! fields. Missing and `null` fields are NULL. Documents that are not JSON
+//! objects, or that hold a field that is not a valid value of its column's
+//! type, are logged and skipped.
+
+use differential_dataflow::Hashable;
+use failure::{bail, format_err};
+use log::error;
+use serde_json::Value;
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::Operator;
+use timely::dataflow::{Scope, Stream};
+
+use super::{pack_field, push_metadata, EVENTS_COUNTER};
+use dataflow_types::{Diff, SourceMetadata, Timestamp};
+use repr::jsonb::Jsonb;
+use repr::{Datum, Row, RowPacker, ScalarType};
+
+pub fn json<G>(
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    columns: Vec<(String, ScalarType)>,
+    metadata: Vec<SourceMetadata>,
+) -> Stream<G, (Row, Timestamp, Diff)>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    stream.unary(
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
+        "JsonDecode",
+        |_, _| {
+            move |input, output| {
+                input.for_each(|cap, records| {
+                    let mut session = output.session(&cap);
+                    for (record, position, partition) in &*records {
+                        match decode_document(record, &columns) {
+                            Ok(mut packer) => {
+                                packer.push(Datum::from(*position));
+                                push_metadata(
+                                    &mut packer,
+                                    &metadata,
+                                    *position,
+                                    *partition,
+                                    *cap.time(),
+                                );
+                                EVENTS_COUNTER.json.success.inc();
+                                session.give((packer.finish(), *cap.time(), 1));
+                            }
+                            Err(err) => {
+                                EVENTS_COUNTER.json.error.inc();
+                                error!("JSON error: {}. Ignoring record.", err);
+                            }
+                        }
+                    }
+                });
+            }
+        },
+    )
+}
+
+/// Decodes the fields of the JSON object in `record` that `columns` names
+/// into a row with one column per entry in `columns`.
+fn decode_document(
+    record: &[u8],
+    columns: &[(String, ScalarType)],
+) -> Result<RowPacker, failure::Error> {
+    let fields = match serde_json::from_slice::<Value>(record)? {
+        Value::Object(fields) => fields,
+        document => bail!("expected a JSON object, got {}", document),
+    };
+    let mut packer = RowPacker::new();
+    for (name, typ) in columns {
+        let value = match fields.get(name) {
+            None | Some(Value::Null) => {
+                packer.push(Datum::Null);
+                continue;
+            }
+            Some(value) => value,
+        };
+        let decoded = match (typ, value) {
+            (ScalarType::Jsonb, _) => {
+                Jsonb::new(value.clone()).map(|jsonb| packer.extend_by_row(&jsonb.into_row()))
+            }
+            (_, Value::String(s)) => pack_field(&mut packer, s, *typ),
+            _ => pack_field(&mut packer, &value.to_string(), *typ),
+        };
+        decoded
+            .map_err(|err| format_err!("invalid value {} in field {:?}: {}", value, name, err))?;
+    }
+    Ok(packer)
+}
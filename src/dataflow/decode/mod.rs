@@ -7,9 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cmp::Ordering;
+
 use lazy_static::lazy_static;
 
+use chrono::{DateTime, NaiveDateTime, Utc};
 use differential_dataflow::hashable::Hashable;
+use failure::bail;
 use prometheus::{register_int_counter_vec, IntCounterVec};
 use prometheus_static_metric::make_static_metric;
 use timely::dataflow::{
@@ -18,16 +22,19 @@ use timely::dataflow::{
     Scope, Stream,
 };
 
-use dataflow_types::{DataEncoding, Diff, Timestamp};
-use repr::Datum;
-use repr::Row;
+use dataflow_types::{DataEncoding, Diff, SourceMetadata, Timestamp};
+use repr::jsonb::Jsonb;
+use repr::{strconv, Datum, ScalarType};
+use repr::{Row, RowPacker};
 
 mod avro;
 mod csv;
+mod json;
 mod protobuf;
 mod regex;
 
 use self::csv::csv;
+use self::json::json;
 use self::regex::regex as regex_fn;
 use avro::avro;
 use protobuf::protobuf;
@@ -35,7 +42,7 @@ use std::iter;
 
 make_static_metric! {
     struct EventsRead: IntCounter {
-        "format" => { avro, csv, protobuf },
+        "format" => { avro, csv, json, protobuf, regex },
         "status" => { success, error }
     }
 }
@@ -50,26 +57,69 @@ lazy_static! {
     static ref EVENTS_COUNTER: EventsRead = EventsRead::from(&EVENTS_COUNTER_INTERNAL);
 }
 
-fn raw<G>(stream: &Stream<G, (Vec<u8>, Option<i64>)>) -> Stream<G, (Row, Timestamp, Diff)>
+/// Pushes the metadata columns that `metadata` requests onto `packer`, for a
+/// record read from `partition` at `position` and ingested at `time`.
+fn push_metadata(
+    packer: &mut RowPacker,
+    metadata: &[SourceMetadata],
+    position: Option<i64>,
+    partition: Option<i32>,
+    time: Timestamp,
+) {
+    for m in metadata {
+        packer.push(match m {
+            SourceMetadata::Offset => Datum::from(position),
+            SourceMetadata::Partition => Datum::from(partition),
+            SourceMetadata::Timestamp => {
+                let secs = (time / 1000) as i64;
+                let nanos = (time % 1000) as u32 * 1_000_000;
+                Datum::TimestampTz(DateTime::from_utc(
+                    NaiveDateTime::from_timestamp(secs, nanos),
+                    Utc,
+                ))
+            }
+        });
+    }
+}
+
+/// Appends the metadata columns that `metadata` requests to `row`, as
+/// [`push_metadata`] does.
+fn append_metadata(
+    row: Row,
+    metadata: &[SourceMetadata],
+    position: Option<i64>,
+    partition: Option<i32>,
+    time: Timestamp,
+) -> Row {
+    if metadata.is_empty() {
+        return row;
+    }
+    let mut packer = RowPacker::new();
+    packer.extend_by_row(&row);
+    push_metadata(&mut packer, metadata, position, partition, time);
+    packer.finish()
+}
+
+fn raw<G>(
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    metadata: Vec<SourceMetadata>,
+) -> Stream<G, (Row, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
 {
     stream.unary(
-        Exchange::new(|x: &(Vec<u8>, _)| x.0.hashed()),
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
         "RawBytes",
         move |_, _| {
             move |input, output| {
                 input.for_each(|cap, data| {
                     let mut session = output.session(&cap);
-                    for (payload, line_no) in data.iter() {
-                        session.give((
-                            Row::pack(
-                                iter::once(Datum::from(payload.as_slice()))
-                                    .chain(line_no.map(Datum::from)),
-                            ),
-                            *cap.time(),
-                            1,
-                        ));
+                    for (payload, position, partition) in data.iter() {
+                        let mut packer = RowPacker::new();
+                        packer.push(Datum::from(payload.as_slice()));
+                        packer.push(Datum::from(*position));
+                        push_metadata(&mut packer, &metadata, *position, *partition, *cap.time());
+                        session.give((packer.finish(), *cap.time(), 1));
                     }
                 });
             }
@@ -77,32 +127,112 @@ where
     )
 }
 
+/// Decodes the records in `stream`, which are each accompanied by their
+/// position in the source and the partition from which they were read, and
+/// appends the metadata columns that `metadata` requests to the rows decoded
+/// from each record.
 pub fn decode<G>(
-    stream: &Stream<G, (Vec<u8>, Option<i64>)>,
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
     encoding: DataEncoding,
+    metadata: Vec<SourceMetadata>,
     name: &str,
 ) -> Stream<G, (Row, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
 {
     match encoding {
-        DataEncoding::Csv(enc) => csv(stream, enc.n_cols, enc.delimiter),
-        DataEncoding::Avro(enc) => avro(stream, &enc.raw_schema, enc.schema_registry_url),
-        DataEncoding::Regex { regex } => regex_fn(stream, regex, name),
-        DataEncoding::Protobuf(enc) => protobuf(stream, &enc.descriptors, &enc.message_name),
-        DataEncoding::Bytes => raw(stream),
-        DataEncoding::Text => raw(stream).map(|(row, r, d)| {
+        DataEncoding::Csv(enc) => csv(stream, enc, metadata),
+        DataEncoding::Avro(enc) => avro(stream, &enc.raw_schema, enc.schema_registry_url, metadata),
+        DataEncoding::Regex { regex } => regex_fn(stream, regex, metadata, name),
+        DataEncoding::Protobuf(enc) => {
+            protobuf(stream, &enc.descriptors, &enc.message_name, metadata)
+        }
+        DataEncoding::Bytes => raw(stream, metadata),
+        DataEncoding::Text => raw(stream, metadata).map(|(row, r, d)| {
             let datums = row.unpack();
             (
                 Row::pack(
                     iter::once(Datum::from(
                         std::str::from_utf8(datums[0].unwrap_bytes()).ok(),
                     ))
-                    .chain(iter::once(datums[1])),
+                    .chain(datums[1..].iter().cloned()),
                 ),
                 r,
                 d,
             )
         }),
+        DataEncoding::Json(enc) if !enc.columns.is_empty() => json(stream, enc.columns, metadata),
+        DataEncoding::Json(_) => raw(stream, metadata).map(|(row, r, d)| {
+            let datums = row.unpack();
+            let mut packer = pack_json(RowPacker::new(), datums[0].unwrap_bytes());
+            packer.extend(&datums[1..]);
+            (packer.finish(), r, d)
+        }),
+        DataEncoding::Row => unreachable!("database sources decode their own records"),
+    }
+}
+
+/// Pushes `payload`, which should be a JSON document, onto `packer` as a
+/// `jsonb` value. Payloads that are not valid JSON are pushed as NULL, just
+/// as text payloads that are not valid UTF-8 are.
+pub fn pack_json(mut packer: RowPacker, payload: &[u8]) -> RowPacker {
+    match std::str::from_utf8(payload).map(|s| s.parse::<Jsonb>()) {
+        Ok(Ok(jsonb)) => jsonb.pack_into(packer),
+        _ => {
+            packer.push(Datum::Null);
+            packer
+        }
+    }
+}
+
+/// Pushes `field`, parsed as the text representation of a value of type
+/// `typ`, onto `packer`.
+///
+/// An empty field is NULL, unless its column holds text.
+pub(crate) fn pack_field(
+    packer: &mut RowPacker,
+    field: &str,
+    typ: ScalarType,
+) -> Result<(), failure::Error> {
+    let is_text = if let ScalarType::String = typ {
+        true
+    } else {
+        false
+    };
+    if field.is_empty() && !is_text {
+        packer.push(Datum::Null);
+        return Ok(());
+    }
+    match typ {
+        ScalarType::String => packer.push(Datum::String(field)),
+        ScalarType::Bool => packer.push(Datum::from(strconv::parse_bool(field)?)),
+        ScalarType::Int32 => packer.push(Datum::Int32(strconv::parse_int32(field)?)),
+        ScalarType::Int64 => packer.push(Datum::Int64(strconv::parse_int64(field)?)),
+        ScalarType::Float32 => packer.push(Datum::from(strconv::parse_float32(field)?)),
+        ScalarType::Float64 => packer.push(Datum::from(strconv::parse_float64(field)?)),
+        ScalarType::Decimal(_, scale) => {
+            let d = strconv::parse_decimal(field)?;
+            packer.push(Datum::from(match d.scale().cmp(&scale) {
+                Ordering::Less => d.significand() * 10_i128.pow(u32::from(scale - d.scale())),
+                Ordering::Equal => d.significand(),
+                Ordering::Greater => d.significand() / 10_i128.pow(u32::from(d.scale() - scale)),
+            }))
+        }
+        ScalarType::Date => packer.push(Datum::Date(strconv::parse_date(field)?)),
+        ScalarType::Time => packer.push(Datum::Time(strconv::parse_time(field)?)),
+        ScalarType::Timestamp => packer.push(Datum::Timestamp(strconv::parse_timestamp(field)?)),
+        ScalarType::TimestampTz => {
+            packer.push(Datum::TimestampTz(strconv::parse_timestamptz(field)?))
+        }
+        ScalarType::Interval => packer.push(Datum::Interval(strconv::parse_interval(field)?)),
+        ScalarType::Bytes => packer.push(Datum::Bytes(&strconv::parse_bytes(field)?)),
+        ScalarType::Jsonb => packer.extend_by_row(&strconv::parse_jsonb(field)?.into_row()),
+        ScalarType::Point => packer.push(Datum::Point(strconv::parse_point(field)?)),
+        ScalarType::Inet => packer.push(Datum::Inet(strconv::parse_inet(field)?)),
+        ScalarType::Cidr => packer.push(Datum::Inet(strconv::parse_cidr(field)?)),
+        ScalarType::Unknown | ScalarType::Enum(_) | ScalarType::List => {
+            bail!("columns of type {:?} are not supported", typ)
+        }
     }
+    Ok(())
 }
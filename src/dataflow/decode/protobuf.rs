@@ -13,16 +13,17 @@ use timely::dataflow::channels::pact::Exchange;
 use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
 
-use dataflow_types::{Diff, Timestamp};
+use dataflow_types::{Diff, SourceMetadata, Timestamp};
 use interchange::protobuf::{self, Decoder};
 use repr::Row;
 
-use super::EVENTS_COUNTER;
+use super::{append_metadata, EVENTS_COUNTER};
 
 pub fn protobuf<G>(
-    stream: &Stream<G, (Vec<u8>, Option<i64>)>,
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
     descriptors: &[u8],
     message_name: &str,
+    metadata: Vec<SourceMetadata>,
 ) -> Stream<G, (Row, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
@@ -33,17 +34,24 @@ where
     let mut decoder = Decoder::new(descriptors, &message_name);
 
     stream.unary(
-        Exchange::new(|x: &(Vec<u8>, _)| x.0.hashed()),
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
         "ProtobufDecode",
         move |_, _| {
             move |input, output| {
                 input.for_each(|cap, data| {
                     let mut session = output.session(&cap);
-                    for (payload, _) in data.iter() {
+                    for (payload, position, partition) in data.iter() {
                         match decoder.decode(payload) {
                             Ok(row) => {
                                 EVENTS_COUNTER.protobuf.success.inc();
                                 if let Some(row) = row {
+                                    let row = append_metadata(
+                                        row,
+                                        &metadata,
+                                        *position,
+                                        *partition,
+                                        *cap.time(),
+                                    );
                                     session.give((row, *cap.time(), 1));
                                 } else {
                                     EVENTS_COUNTER.protobuf.error.inc();
@@ -7,12 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use dataflow_types::{Diff, Timestamp};
+use super::{append_metadata, EVENTS_COUNTER};
+use dataflow_types::{Diff, SourceMetadata, Timestamp};
 use differential_dataflow::Hashable;
 use log::warn;
 use regex::Regex;
 use repr::{Datum, Row};
-use std::cmp::max;
+use std::cmp::min;
 use std::iter;
 use std::str;
 use timely::dataflow::channels::pact::Exchange;
@@ -20,8 +21,9 @@ use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
 
 pub fn regex<G>(
-    stream: &Stream<G, (Vec<u8>, Option<i64>)>,
+    stream: &Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
     regex: Regex,
+    metadata: Vec<SourceMetadata>,
     name: &str,
 ) -> Stream<G, (Row, Timestamp, Diff)>
 where
@@ -29,39 +31,51 @@ where
 {
     let name = String::from(name);
     stream.unary(
-        Exchange::new(|x: &(Vec<u8>, _)| x.0.hashed()),
+        Exchange::new(|x: &(Vec<u8>, _, _)| x.0.hashed()),
         "RegexDecode",
         |_, _| {
             move |input, output| {
                 input.for_each(|cap, lines| {
                     let mut session = output.session(&cap);
-                    for (line, line_no) in &*lines {
+                    for (line, line_no, partition) in &*lines {
                         let line = match str::from_utf8(&line) {
                             Ok(line) => line,
                             _ => {
-                                let line_len = max(line.len(), 1024);
+                                EVENTS_COUNTER.regex.error.inc();
+                                let line_len = min(line.len(), 1024);
                                 warn!(
                                     "Line {}{} from source {} cannot be decoded as utf8. Discarding line.",
-                                    if line_len == line.len() { "" } else {"starting with: "},
+                                    if line_len == line.len() { "" } else { "starting with: " },
                                     String::from_utf8_lossy(&line[0..line_len]),
                                     name
                                 );
                                 continue;
                             }
                         };
+                        // Records from message-based sources, unlike lines
+                        // read from files, may retain their line terminator.
+                        let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
                         let captures = match regex.captures(line) {
                             Some(captures) => captures,
-                            None => continue,
+                            None => {
+                                // Records that do not match are discarded,
+                                // but counted, so that a regex that matches
+                                // nothing does not go unnoticed.
+                                EVENTS_COUNTER.regex.error.inc();
+                                continue;
+                            }
                         };
-                        session.give((
-                            Row::pack(captures.iter().skip(1).map(
-                                |m| Datum::from( m.map(
-                                    |m| m.as_str())
-                                )
-                            ).chain(iter::once(line_no.map(Datum::Int64).into()))),
-                            *cap.time(),
-                            1,
-                        ));
+                        EVENTS_COUNTER.regex.success.inc();
+                        let row = Row::pack(
+                            captures
+                                .iter()
+                                .skip(1)
+                                .map(|m| Datum::from(m.map(|m| m.as_str())))
+                                .chain(iter::once(Datum::from(*line_no))),
+                        );
+                        let row =
+                            append_metadata(row, &metadata, *line_no, *partition, *cap.time());
+                        session.give((row, *cap.time(), 1));
                     }
                 });
             }
@@ -18,4 +18,15 @@ mod source;
 pub mod logging;
 pub mod server;
 
-pub use server::{serve, BroadcastToken, SequencedCommand, WorkerFeedback, WorkerFeedbackWithMeta};
+pub use server::{
+    crash_context, crash_contexts, serve, BroadcastToken, ClusterBroadcastToken, CrashContext,
+    Inventory, SequencedCommand, WorkerFeedback, WorkerFeedbackWithMeta,
+};
+pub use sink::{
+    check_sink_plugin, register_sink_plugin, sink_plugin, DeliveryGuarantee, SinkPlugin,
+    SinkPluginFactory, SinkRecord,
+};
+pub use source::{
+    register_source_plugin, source_plugin, SourcePlugin, SourcePluginFactory, SourceRecord,
+    SourceWaker,
+};
@@ -42,6 +42,66 @@ pub enum MaterializedEvent {
     Peek(Peek, bool),
     /// Available frontier information for views.
     Frontier(GlobalId, Timestamp, i64),
+    /// Frontier up to which sinks have emitted their output.
+    SinkFrontier(GlobalId, Timestamp, i64),
+    /// A discrepancy between the catalog and the dataflow objects installed
+    /// on a worker, as found by the consistency check after recovery.
+    Discrepancy {
+        /// The identifier of the catalog item.
+        id: GlobalId,
+        /// The worker with the discrepancy.
+        worker: usize,
+        /// The kind of dataflow object: "arrangement", "input", or "sink".
+        object: String,
+        /// Either "missing" or "orphaned".
+        problem: String,
+    },
+    /// The state of a view's freshness objective.
+    Freshness {
+        /// The identifier of the view.
+        id: GlobalId,
+        /// How far, in milliseconds, the view may trail the wall clock.
+        max_lag_ms: u64,
+        /// Whether the view currently trails the wall clock by more than
+        /// `max_lag_ms`.
+        violating: bool,
+        /// The number of times the view has begun to violate its objective.
+        violations: u64,
+        /// Whether the state is being inserted or retracted.
+        insert: bool,
+    },
+    /// The progress of an index that is catching up with its inputs.
+    Backfill {
+        /// The identifier of the index.
+        id: GlobalId,
+        /// The frontier that the index must reach to catch up.
+        target: Timestamp,
+        /// The least element of the index's frontier.
+        frontier: Timestamp,
+        /// The number of updates in the index's arrangement.
+        records: usize,
+        /// The number of updates in the arrangements that the index reads.
+        estimated_records: usize,
+        /// How long ago, in milliseconds, the index was created.
+        elapsed_ms: u64,
+        /// Whether the state is being inserted or retracted.
+        insert: bool,
+    },
+    /// The skew of a source whose records carry event-time timestamps.
+    SourceSkew {
+        /// The identifier of the source.
+        id: GlobalId,
+        /// The latest timestamp assigned to the source's records.
+        timestamp: Timestamp,
+        /// How far, in milliseconds, the timestamp runs ahead of the wall
+        /// clock. Negative if it runs behind.
+        wall_clock_skew_ms: i64,
+        /// How far, in milliseconds, the timestamp runs behind that of the
+        /// most advanced source with event-time timestamps.
+        source_skew_ms: i64,
+        /// Whether the state is being inserted or retracted.
+        insert: bool,
+    },
     /// Primary key.
     PrimaryKey(GlobalId, Vec<usize>, usize),
     /// Foreign key relationship: child, parent, then pairs of child and parent columns.
@@ -100,6 +160,11 @@ pub fn construct<A: Allocate>(
         let (mut primary_out, primary) = demux.new_output();
         let (mut foreign_out, foreign) = demux.new_output();
         let (mut catalog_out, catalog) = demux.new_output();
+        let (mut sink_frontier_out, sink_frontier) = demux.new_output();
+        let (mut discrepancy_out, discrepancy) = demux.new_output();
+        let (mut freshness_out, freshness) = demux.new_output();
+        let (mut backfill_out, backfill) = demux.new_output();
+        let (mut skew_out, skew) = demux.new_output();
 
         let mut demux_buffer = Vec::new();
         demux.build(move |_capability| {
@@ -116,6 +181,11 @@ pub fn construct<A: Allocate>(
                 let mut primary = primary_out.activate();
                 let mut foreign = foreign_out.activate();
                 let mut catalog = catalog_out.activate();
+                let mut sink_frontier = sink_frontier_out.activate();
+                let mut discrepancy = discrepancy_out.activate();
+                let mut freshness = freshness_out.activate();
+                let mut backfill = backfill_out.activate();
+                let mut skew = skew_out.activate();
 
                 input.for_each(|time, data| {
                     data.swap(&mut demux_buffer);
@@ -127,6 +197,11 @@ pub fn construct<A: Allocate>(
                     let mut primary_session = primary.session(&time);
                     let mut foreign_session = foreign.session(&time);
                     let mut catalog_session = catalog.session(&time);
+                    let mut sink_frontier_session = sink_frontier.session(&time);
+                    let mut discrepancy_session = discrepancy.session(&time);
+                    let mut freshness_session = freshness.session(&time);
+                    let mut backfill_session = backfill.session(&time);
+                    let mut skew_session = skew.session(&time);
 
                     for (time, worker, datum) in demux_buffer.drain(..) {
                         let time_ns = time.as_nanos() as Timestamp;
@@ -230,6 +305,91 @@ pub fn construct<A: Allocate>(
                                     delta as isize,
                                 ));
                             }
+                            MaterializedEvent::SinkFrontier(name, logical, delta) => {
+                                sink_frontier_session.give((
+                                    Row::pack(&[
+                                        Datum::String(&name.to_string()),
+                                        Datum::Int64(logical as i64),
+                                    ]),
+                                    time_ms,
+                                    delta as isize,
+                                ));
+                            }
+                            MaterializedEvent::Discrepancy {
+                                id,
+                                worker,
+                                object,
+                                problem,
+                            } => {
+                                discrepancy_session.give((
+                                    Row::pack(&[
+                                        Datum::String(&id.to_string()),
+                                        Datum::Int64(worker as i64),
+                                        Datum::String(&object),
+                                        Datum::String(&problem),
+                                    ]),
+                                    time_ms,
+                                    1,
+                                ));
+                            }
+                            MaterializedEvent::Freshness {
+                                id,
+                                max_lag_ms,
+                                violating,
+                                violations,
+                                insert,
+                            } => {
+                                freshness_session.give((
+                                    Row::pack(&[
+                                        Datum::String(&id.to_string()),
+                                        Datum::Int64(max_lag_ms as i64),
+                                        Datum::from(violating),
+                                        Datum::Int64(violations as i64),
+                                    ]),
+                                    time_ms,
+                                    if insert { 1 } else { -1 },
+                                ));
+                            }
+                            MaterializedEvent::Backfill {
+                                id,
+                                target,
+                                frontier,
+                                records,
+                                estimated_records,
+                                elapsed_ms,
+                                insert,
+                            } => {
+                                backfill_session.give((
+                                    Row::pack(&[
+                                        Datum::String(&id.to_string()),
+                                        Datum::Int64(target as i64),
+                                        Datum::Int64(frontier as i64),
+                                        Datum::Int64(records as i64),
+                                        Datum::Int64(estimated_records as i64),
+                                        Datum::Int64(elapsed_ms as i64),
+                                    ]),
+                                    time_ms,
+                                    if insert { 1 } else { -1 },
+                                ));
+                            }
+                            MaterializedEvent::SourceSkew {
+                                id,
+                                timestamp,
+                                wall_clock_skew_ms,
+                                source_skew_ms,
+                                insert,
+                            } => {
+                                skew_session.give((
+                                    Row::pack(&[
+                                        Datum::String(&id.to_string()),
+                                        Datum::Int64(timestamp as i64),
+                                        Datum::Int64(wall_clock_skew_ms),
+                                        Datum::Int64(source_skew_ms),
+                                    ]),
+                                    time_ms,
+                                    if insert { 1 } else { -1 },
+                                ));
+                            }
                             MaterializedEvent::PrimaryKey(dataflow_id, key, index) => {
                                 for k in key.iter() {
                                     primary_session.give((
@@ -328,6 +488,11 @@ pub fn construct<A: Allocate>(
             });
 
         let frontier_current = frontier.as_collection();
+        let sink_frontier_current = sink_frontier.as_collection();
+        let discrepancies = discrepancy.as_collection();
+        let freshness = freshness.as_collection();
+        let backfill = backfill.as_collection();
+        let skew = skew.as_collection();
         let primary_key = primary.as_collection();
         let foreign_key = foreign.as_collection();
         let catalog = catalog.as_collection().map({
@@ -428,6 +593,23 @@ pub fn construct<A: Allocate>(
                 foreign_key,
             ),
             (LogVariant::Materialized(MaterializedLog::Catalog), catalog),
+            (
+                LogVariant::Materialized(MaterializedLog::SinkFrontierCurrent),
+                sink_frontier_current,
+            ),
+            (
+                LogVariant::Materialized(MaterializedLog::CatalogDiscrepancies),
+                discrepancies,
+            ),
+            (
+                LogVariant::Materialized(MaterializedLog::ViewFreshness),
+                freshness,
+            ),
+            (
+                LogVariant::Materialized(MaterializedLog::ViewBackfills),
+                backfill,
+            ),
+            (LogVariant::Materialized(MaterializedLog::SourceSkew), skew),
         ];
 
         use differential_dataflow::operators::arrange::arrangement::ArrangeByKey;
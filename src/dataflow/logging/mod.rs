@@ -9,6 +9,7 @@
 
 pub mod differential;
 pub mod materialized;
+pub mod profile;
 pub mod timely;
 
 use ::timely::dataflow::operators::capture::{Event, EventPusher};
@@ -0,0 +1,171 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Per-operator statistics for the dataflows of `EXPLAIN ANALYZE`.
+//!
+//! The logging dataflows maintain statistics for every operator, but only at
+//! the granularity of the logging interval, and they retract the statistics
+//! of a dataflow as soon as it shuts down. Transient dataflows are often
+//! gone by the time either happens, so the profiler instead observes the
+//! timely events as they are logged, and tallies them for the few dataflows
+//! that have asked to be profiled until the peek at their index is answered.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::time::Duration;
+
+use timely::logging::{StartStop, TimelyEvent, WorkerIdentifier};
+
+use expr::GlobalId;
+use repr::{Datum, Row};
+
+/// A worker's handle on the profiles of its dataflows.
+#[derive(Clone, Default)]
+pub struct Profiler(Rc<RefCell<ProfilerState>>);
+
+#[derive(Default)]
+struct ProfilerState {
+    /// The index of each profiled dataflow, by the ID of the index that it
+    /// exports.
+    exports: HashMap<GlobalId, usize>,
+    /// The profiles of the profiled dataflows, by dataflow index.
+    dataflows: HashMap<usize, DataflowProfile>,
+    /// The dataflow index of each operator of a profiled dataflow.
+    operators: HashMap<usize, usize>,
+    /// The dataflow index of each channel of a profiled dataflow.
+    channels: HashMap<usize, usize>,
+}
+
+#[derive(Default)]
+struct DataflowProfile {
+    /// The profile of each operator, by operator ID.
+    operators: BTreeMap<usize, OperatorProfile>,
+    /// The ID of each operator, by address.
+    addrs: HashMap<Vec<usize>, usize>,
+    /// The address of the operator that feeds each channel, and the number
+    /// of records sent on the channel.
+    channels: HashMap<usize, (Vec<usize>, u64)>,
+}
+
+#[derive(Default)]
+struct OperatorProfile {
+    name: String,
+    /// The time at which the operator was last scheduled, if it is running.
+    started: Option<Duration>,
+    elapsed: Duration,
+}
+
+impl Profiler {
+    /// Starts profiling the dataflow with index `dataflow`, which exports the
+    /// index `id`.
+    pub fn register(&self, id: GlobalId, dataflow: usize) {
+        let mut state = self.0.borrow_mut();
+        state.exports.insert(id, dataflow);
+        state.dataflows.insert(dataflow, DataflowProfile::default());
+    }
+
+    /// Reports whether the dataflow that exports the index `id` is profiled.
+    pub fn is_profiled(&self, id: GlobalId) -> bool {
+        self.0.borrow().exports.contains_key(&id)
+    }
+
+    /// Stops profiling the dataflow that exports the index `id`, and returns
+    /// its profile, as one row per operator with the operator's ID, name,
+    /// number of records produced, and elapsed time in nanoseconds.
+    pub fn take(&self, id: GlobalId) -> Option<Vec<Row>> {
+        let mut state = self.0.borrow_mut();
+        let dataflow = state.exports.remove(&id)?;
+        state.operators.retain(|_, d| *d != dataflow);
+        state.channels.retain(|_, d| *d != dataflow);
+        let profile = state.dataflows.remove(&dataflow)?;
+
+        let mut records = HashMap::new();
+        for (addr, count) in profile.channels.values() {
+            if let Some(id) = profile.addrs.get(addr) {
+                *records.entry(*id).or_insert(0) += count;
+            }
+        }
+        let rows = profile
+            .operators
+            .iter()
+            .map(|(id, operator)| {
+                Row::pack(&[
+                    Datum::Int64(*id as i64),
+                    Datum::String(&operator.name),
+                    Datum::Int64(records.get(id).cloned().unwrap_or(0) as i64),
+                    Datum::Int64(operator.elapsed.as_nanos() as i64),
+                ])
+            })
+            .collect();
+        Some(rows)
+    }
+
+    /// Tallies the timely events in `data` that concern profiled dataflows.
+    pub fn observe(&self, data: &[(Duration, WorkerIdentifier, TimelyEvent)]) {
+        let mut state = self.0.borrow_mut();
+        if state.dataflows.is_empty() {
+            return;
+        }
+        let state = &mut *state;
+        for (time, _worker, event) in data {
+            match event {
+                // The dataflow itself, whose address has length one, is not
+                // reported, as it is scheduled for as long as any of its
+                // operators.
+                TimelyEvent::Operates(event) if event.addr.len() > 1 => {
+                    if let Some(profile) = state.dataflows.get_mut(&event.addr[0]) {
+                        state.operators.insert(event.id, event.addr[0]);
+                        profile.addrs.insert(event.addr.clone(), event.id);
+                        profile.operators.insert(
+                            event.id,
+                            OperatorProfile {
+                                name: event.name.clone(),
+                                ..Default::default()
+                            },
+                        );
+                    }
+                }
+                // Channels from port zero carry records into a scope from
+                // its parent, and so are not fed by any of its operators.
+                TimelyEvent::Channels(event) if event.source.0 != 0 => {
+                    if let Some(profile) = state.dataflows.get_mut(&event.scope_addr[0]) {
+                        state.channels.insert(event.id, event.scope_addr[0]);
+                        let mut addr = event.scope_addr.clone();
+                        addr.push(event.source.0);
+                        profile.channels.insert(event.id, (addr, 0));
+                    }
+                }
+                TimelyEvent::Messages(event) if event.is_send => {
+                    if let Some(dataflow) = state.channels.get(&event.channel) {
+                        let profile = state.dataflows.get_mut(dataflow).unwrap();
+                        if let Some((_, count)) = profile.channels.get_mut(&event.channel) {
+                            *count += event.length as u64;
+                        }
+                    }
+                }
+                TimelyEvent::Schedule(event) => {
+                    if let Some(dataflow) = state.operators.get(&event.id) {
+                        let profile = state.dataflows.get_mut(dataflow).unwrap();
+                        let operator = profile.operators.get_mut(&event.id).unwrap();
+                        match event.start_stop {
+                            StartStop::Start => operator.started = Some(*time),
+                            StartStop::Stop => {
+                                if let Some(started) = operator.started.take() {
+                                    operator.elapsed += *time - started;
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
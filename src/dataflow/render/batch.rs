@@ -0,0 +1,366 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Batch-at-a-time rendering of linear operators.
+//!
+//! Maps, filters, and projections transform each row independently, but
+//! rendering them with differential's per-record `map` and `filter` pays
+//! for a fresh vector of unpacked datums and a fresh [`RowArena`] for every
+//! row. The operators here instead process each batch of updates that timely
+//! delivers in one go, reusing the datum buffer and the arena across the rows
+//! of the batch, and only decoding the columns that they read. Chains of these
+//! operators, as produced by SQL lowering, are fused into a single [`linear`]
+//! operator.
+//!
+//! All of the operators discard their input once their dataflow is shutting
+//! down, as indicated by a [`ShutdownToken`].
+
+use std::cmp;
+use std::mem;
+
+use differential_dataflow::{AsCollection, Collection};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::Operator;
+use timely::dataflow::Scope;
+
+use dataflow_types::{Diff, Timestamp};
+use expr::{CompiledExpr, CompiledPredicates, EvalEnv};
+use repr::{Row, RowArena};
+
+use super::context::ShutdownToken;
+
+type Updates = Vec<(Row, Timestamp, Diff)>;
+
+/// Applies `logic` to `collection` a batch of updates at a time.
+///
+/// `logic` consumes the updates in its first argument and pushes the updates
+/// it produces onto its second. Once `shutdown_token` indicates shutdown, the
+/// updates are discarded without applying `logic`.
+fn map_batches<G, L>(
+    collection: &Collection<G, Row>,
+    name: &str,
+    shutdown_token: &ShutdownToken,
+    mut logic: L,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+    L: FnMut(&mut Updates, &mut Updates) + 'static,
+{
+    let shutdown_token = shutdown_token.clone();
+    collection
+        .inner
+        .unary(Pipeline, name, move |_, _| {
+            let mut input_buffer = Vec::new();
+            let mut output_buffer = Vec::new();
+            move |input, output| {
+                input.for_each(|cap, data| {
+                    if shutdown_token.in_shutdown() {
+                        return;
+                    }
+                    data.swap(&mut input_buffer);
+                    logic(&mut input_buffer, &mut output_buffer);
+                    input_buffer.clear();
+                    output.session(&cap).give_vec(&mut output_buffer);
+                });
+            }
+        })
+        .as_collection()
+}
+
+/// One step of a chain of linear operators fused by [`linear`].
+pub enum LinearOp {
+    /// Appends the result of each scalar to the row. Each scalar may refer to
+    /// the results of the scalars before it.
+    Map(Vec<CompiledExpr>),
+    /// Retains the rows that satisfy all of the predicates.
+    Filter(CompiledPredicates),
+    /// Projects the row onto the listed columns.
+    Project(Vec<usize>),
+}
+
+/// Applies the chain of `ops` to each row of `collection`, in order, as a
+/// single operator.
+///
+/// Rather than packing a new row after each step, the datums of a row are
+/// carried from one step to the next, and a row is packed only once it has
+/// made it through the whole chain. A row that fails a filter is dropped
+/// without evaluating the steps after it. `arity` is the arity of
+/// `collection`, which determines how many of its columns must be decoded.
+pub fn linear<G>(
+    collection: &Collection<G, Row>,
+    arity: usize,
+    ops: Vec<LinearOp>,
+    env: EvalEnv,
+    shutdown_token: &ShutdownToken,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let arity_read = linear_arity_read(arity, &ops);
+    map_batches(
+        collection,
+        "Linear",
+        shutdown_token,
+        move |updates, output| apply_linear(&ops, arity_read, &env, updates, output),
+    )
+}
+
+/// Applies the chain of `ops` to each of `updates`, decoding the first
+/// `arity_read` columns of each row, and pushes the results onto `output`.
+fn apply_linear(
+    ops: &[LinearOp],
+    arity_read: usize,
+    env: &EvalEnv,
+    updates: &Updates,
+    output: &mut Updates,
+) {
+    let temp_storage = RowArena::new();
+    let mut datums = Vec::new();
+    let mut projected = Vec::new();
+    'rows: for (row, time, diff) in updates.iter() {
+        datums.clear();
+        datums.extend(row.iter().take(arity_read));
+        for op in ops {
+            match op {
+                LinearOp::Map(scalars) => {
+                    for scalar in scalars {
+                        let datum = scalar.eval(&datums, env, &temp_storage);
+                        datums.push(datum);
+                    }
+                }
+                LinearOp::Filter(predicates) => {
+                    if !predicates.eval(&datums, env, &temp_storage) {
+                        continue 'rows;
+                    }
+                }
+                LinearOp::Project(outputs) => {
+                    projected.clear();
+                    projected.extend(outputs.iter().map(|i| datums[*i]));
+                    mem::swap(&mut datums, &mut projected);
+                }
+            }
+        }
+        output.push((Row::pack(&*datums), *time, *diff));
+    }
+}
+
+/// Returns the number of leading columns of a row of arity `arity` that the
+/// chain of `ops` reads.
+///
+/// Columns past the last one read are only needed if no projection discards
+/// them, so the chain is walked from its end, tracking the columns needed by
+/// the steps that follow.
+fn linear_arity_read(arity: usize, ops: &[LinearOp]) -> usize {
+    // The arity of the row before each step.
+    let mut arities = Vec::with_capacity(ops.len());
+    let mut current = arity;
+    for op in ops {
+        arities.push(current);
+        current = match op {
+            LinearOp::Map(scalars) => current + scalars.len(),
+            LinearOp::Filter(_) => current,
+            LinearOp::Project(outputs) => outputs.len(),
+        };
+    }
+    let mut needed = current;
+    for (op, arity) in ops.iter().zip(arities).rev() {
+        needed = match op {
+            LinearOp::Map(scalars) => {
+                let read = scalars.iter().map(|s| s.arity_read()).max().unwrap_or(0);
+                cmp::min(arity, cmp::max(needed, read))
+            }
+            LinearOp::Filter(predicates) => cmp::max(needed, predicates.arity_read()),
+            LinearOp::Project(outputs) => outputs.iter().max().map_or(0, |c| c + 1),
+        };
+    }
+    needed
+}
+
+/// Retains the rows of `collection` that satisfy all of `predicates`.
+pub fn filter<G>(
+    collection: &Collection<G, Row>,
+    predicates: CompiledPredicates,
+    env: EvalEnv,
+    shutdown_token: &ShutdownToken,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let arity_read = predicates.arity_read();
+    let mut keep = Vec::new();
+    map_batches(
+        collection,
+        "Filter",
+        shutdown_token,
+        move |updates, output| {
+            {
+                let temp_storage = RowArena::new();
+                let mut datums = Vec::new();
+                for (row, _time, _diff) in updates.iter() {
+                    datums.clear();
+                    datums.extend(row.iter().take(arity_read));
+                    keep.push(predicates.eval(&datums, &env, &temp_storage));
+                }
+            }
+            output.extend(
+                updates
+                    .drain(..)
+                    .zip(keep.drain(..))
+                    .filter(|(_update, keep)| *keep)
+                    .map(|(update, _keep)| update),
+            );
+        },
+    )
+}
+
+/// Projects each row of `collection` onto the columns in `outputs`.
+pub fn project<G>(
+    collection: &Collection<G, Row>,
+    outputs: Vec<usize>,
+    shutdown_token: &ShutdownToken,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let arity_read = outputs.iter().max().map_or(0, |c| c + 1);
+    map_batches(
+        collection,
+        "Project",
+        shutdown_token,
+        move |updates, output| {
+            let mut datums = Vec::new();
+            for (row, time, diff) in updates.iter() {
+                datums.clear();
+                datums.extend(row.iter().take(arity_read));
+                output.push((Row::pack(outputs.iter().map(|i| datums[*i])), *time, *diff));
+            }
+        },
+    )
+}
+
+/// Passes `collection` through unchanged until `shutdown_token` indicates
+/// shutdown, and discards it afterwards.
+pub fn discard_on_shutdown<G>(
+    collection: &Collection<G, Row>,
+    shutdown_token: &ShutdownToken,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    map_batches(
+        collection,
+        "ShutdownProbe",
+        shutdown_token,
+        |updates, output| output.append(updates),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::{BinaryFunc, ScalarExpr};
+    use repr::{ColumnType, Datum, ScalarType};
+
+    use super::*;
+
+    fn int64(n: i64) -> ScalarExpr {
+        ScalarExpr::literal(Datum::Int64(n), ColumnType::new(ScalarType::Int64))
+    }
+
+    /// Evaluates `map`, then `filter`, then `project` on `row` one expression
+    /// at a time, as a reference for the fused operator.
+    fn reference(
+        row: &Row,
+        map: &[ScalarExpr],
+        filter: &[ScalarExpr],
+        project: &[usize],
+    ) -> Option<Row> {
+        let env = EvalEnv::default();
+        let temp_storage = RowArena::new();
+        let mut datums = row.unpack();
+        for scalar in map {
+            let datum = scalar.eval(&datums, &env, &temp_storage);
+            datums.push(datum);
+        }
+        for predicate in filter {
+            if predicate.eval(&datums, &env, &temp_storage) != Datum::True {
+                return None;
+            }
+        }
+        Some(Row::pack(project.iter().map(|i| datums[*i])))
+    }
+
+    #[test]
+    fn test_linear() {
+        // SELECT a + b, c FROM t WHERE a + b > 5
+        let map =
+            vec![ScalarExpr::Column(0).call_binary(ScalarExpr::Column(1), BinaryFunc::AddInt64)];
+        let filter = vec![ScalarExpr::Column(4).call_binary(int64(5), BinaryFunc::Gt)];
+        let project = vec![4, 2];
+        let ops = vec![
+            LinearOp::Map(map.iter().map(CompiledExpr::compile).collect()),
+            LinearOp::Filter(CompiledPredicates::compile(&filter)),
+            LinearOp::Project(project.clone()),
+        ];
+        let arity_read = linear_arity_read(4, &ops);
+
+        let updates: Updates = (0..10)
+            .map(|i| {
+                let datums = [
+                    Datum::Int64(i),
+                    Datum::Int64(i % 3),
+                    Datum::Int64(-i),
+                    Datum::Null,
+                ];
+                (Row::pack(&datums), i as Timestamp, 1 - 2 * (i % 2) as Diff)
+            })
+            .collect();
+        let mut output = Vec::new();
+        apply_linear(&ops, arity_read, &EvalEnv::default(), &updates, &mut output);
+
+        let expected: Updates = updates
+            .iter()
+            .filter_map(|(row, time, diff)| {
+                reference(row, &map, &filter, &project).map(|row| (row, *time, *diff))
+            })
+            .collect();
+        assert!(!expected.is_empty() && expected.len() < updates.len());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_linear_arity_read() {
+        let filter = |col| {
+            LinearOp::Filter(CompiledPredicates::compile(&[
+                ScalarExpr::Column(col).call_binary(int64(0), BinaryFunc::Gt)
+            ]))
+        };
+
+        // A chain without a projection passes every column through.
+        assert_eq!(linear_arity_read(5, &[filter(1)]), 5);
+        // A projection limits the columns needed to those it retains.
+        assert_eq!(linear_arity_read(5, &[LinearOp::Project(vec![1, 0])]), 2);
+        // ...unless a step before it reads more of them.
+        assert_eq!(
+            linear_arity_read(5, &[filter(3), LinearOp::Project(vec![0])]),
+            4
+        );
+        // A projection that comes first determines the columns read by the
+        // steps after it.
+        assert_eq!(
+            linear_arity_read(5, &[LinearOp::Project(vec![2]), filter(0)]),
+            3
+        );
+        // A map only needs the columns that its scalars read, if its results
+        // are projected away.
+        let map = LinearOp::Map(vec![CompiledExpr::compile(
+            &ScalarExpr::Column(0).call_binary(ScalarExpr::Column(1), BinaryFunc::AddInt64),
+        )]);
+        assert_eq!(linear_arity_read(5, &[map, LinearOp::Project(vec![1])]), 2);
+    }
+}
@@ -10,6 +10,7 @@
 //! Management of arrangements while building a dataflow.
 
 use std::collections::{BTreeMap, HashMap};
+use std::rc::{Rc, Weak};
 
 use timely::dataflow::{Scope, ScopeParent};
 use timely::progress::{timestamp::Refines, Timestamp};
@@ -22,7 +23,7 @@ use differential_dataflow::trace::wrappers::frontier::TraceFrontier;
 use differential_dataflow::Collection;
 use differential_dataflow::Data;
 
-use expr::ScalarExpr;
+use expr::{GlobalId, ScalarExpr};
 
 /// A trace handle for key-value data.
 pub type TraceValHandle<K, V, T, R> = TraceAgent<OrdValSpine<K, V, T, R>>;
@@ -36,6 +37,42 @@ type ArrangementImport<S, V, T> = Arranged<
     TraceEnter<TraceFrontier<TraceValHandle<V, V, T, Diff>>, <S as ScopeParent>::Timestamp>,
 >;
 
+/// A probe that indicates whether a dataflow is shutting down.
+///
+/// A dataflow is shutting down once every export of the dataflow has been
+/// dropped, at which point nothing can observe its output. The operators that
+/// can do a lot of work with their input, like joins and reductions, consult
+/// the token to discard their input and produce no output instead, so that a
+/// dataflow that is dropped while it is still hydrating stops promptly rather
+/// than working through all the data that is already in flight.
+///
+/// Each sink additionally has a token of its own, which indicates that the
+/// sink has been dropped. Sink operators consult it to close their connections
+/// to external systems as soon as the sink is dropped.
+#[derive(Clone)]
+pub struct ShutdownToken(Option<Weak<()>>);
+
+impl ShutdownToken {
+    /// Returns a token that indicates shutdown once `token` and all of its
+    /// clones have been dropped.
+    pub fn new(token: &Rc<()>) -> Self {
+        ShutdownToken(Some(Rc::downgrade(token)))
+    }
+
+    /// Returns a token that never indicates shutdown.
+    pub fn without_shutdown() -> Self {
+        ShutdownToken(None)
+    }
+
+    /// Reports whether the dataflow is shutting down.
+    pub fn in_shutdown(&self) -> bool {
+        match &self.0 {
+            Some(token) => token.upgrade().is_none(),
+            None => false,
+        }
+    }
+}
+
 /// Dataflow-local collections and arrangements.
 ///
 /// A context means to wrap available data assets and present them in an easy-to-use manner.
@@ -59,6 +96,11 @@ where
     /// Imported arrangements.
     #[allow(clippy::type_complexity)] // TODO(fms): fix or ignore lint globally.
     pub trace: HashMap<P, BTreeMap<Vec<ScalarExpr>, ArrangementImport<S, V, T>>>,
+    /// Indicates whether the dataflow is shutting down.
+    pub shutdown_token: ShutdownToken,
+    /// The collections produced by fused source input stages, keyed by the
+    /// sorted IDs of the sources whose union they contain.
+    pub fused_sources: HashMap<Vec<GlobalId>, Collection<S, V, Diff>>,
 }
 
 impl<S: Scope, P, V: Data, T> Context<S, P, V, T>
@@ -73,6 +115,8 @@ where
             collections: HashMap::new(),
             local: HashMap::new(),
             trace: HashMap::new(),
+            shutdown_token: ShutdownToken::without_shutdown(),
+            fused_sources: HashMap::new(),
         }
     }
 
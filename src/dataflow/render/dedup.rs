@@ -0,0 +1,95 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Discarding the duplicate records of sources.
+//!
+//! Records are routed to workers by the hash of their key. Each worker
+//! remembers, for every key it has seen, the latest timestamp at which a
+//! record with that key was passed along, and discards the records whose
+//! timestamp falls within the window that follows it. Records are considered
+//! in timestamp order once the input frontier passes them, so which of a set
+//! of duplicates survives does not depend on the order in which they arrive.
+//! A key is forgotten once the input frontier passes the end of its window,
+//! which bounds the state to the keys seen within the last window.
+
+use std::collections::HashMap;
+
+use differential_dataflow::hashable::Hashable;
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::{Capability, Operator};
+use timely::dataflow::{Scope, Stream};
+
+use dataflow_types::{Deduplication, Diff, Timestamp};
+use repr::Row;
+
+/// Discards the records of `stream` that repeat the key of an earlier record
+/// within `deduplication`'s window.
+pub(crate) fn deduplicate<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    deduplication: Deduplication,
+) -> Stream<G, (Row, Timestamp, Diff)>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let key_columns = deduplication.key.clone();
+    let window = deduplication.window.as_millis() as Timestamp;
+    stream.unary_frontier(
+        Exchange::new(move |(row, _, _): &(Row, Timestamp, Diff)| {
+            key_of(row, &key_columns).hashed()
+        }),
+        "Deduplicate",
+        move |_cap, _info| {
+            let key_columns = deduplication.key;
+            // The time at which each key's window began.
+            let mut seen: HashMap<Row, Timestamp> = HashMap::new();
+            let mut stash: Vec<(Capability<Timestamp>, Vec<(Row, Timestamp, Diff)>)> = Vec::new();
+            move |input, output| {
+                input.for_each(|cap, data| {
+                    let mut updates = Vec::new();
+                    data.swap(&mut updates);
+                    stash.push((cap.retain(), updates));
+                });
+
+                let mut ready = Vec::new();
+                for (cap, updates) in &mut stash {
+                    let (done, pending): (Vec<_>, Vec<_>) = updates
+                        .drain(..)
+                        .partition(|(_, time, _)| !input.frontier().less_equal(time));
+                    *updates = pending;
+                    ready.extend(done.into_iter().map(|update| (cap.clone(), update)));
+                }
+                stash.retain(|(_, updates)| !updates.is_empty());
+
+                ready.sort_by_key(|(_, (_, time, _))| *time);
+                for (cap, (row, time, diff)) in ready {
+                    let key = key_of(&row, &key_columns);
+                    match seen.get(&key) {
+                        Some(start) if time < start + window => (),
+                        _ => {
+                            seen.insert(key, time);
+                            output.session(&cap).give((row, time, diff));
+                        }
+                    }
+                }
+
+                // Keys whose window has closed can no longer suppress a
+                // record, as every record yet to arrive is at a later time.
+                match input.frontier().frontier().iter().min() {
+                    Some(lower) => seen.retain(|_, start| *start + window > *lower),
+                    None => seen.clear(),
+                }
+            }
+        },
+    )
+}
+
+fn key_of(row: &Row, key_columns: &[usize]) -> Row {
+    let datums = row.unpack();
+    Row::pack(key_columns.iter().map(|i| datums[*i]))
+}
@@ -16,7 +16,7 @@ use differential_dataflow::lattice::Lattice;
 use dogsdogsdogs::altneu::AltNeu;
 
 use dataflow_types::Timestamp;
-use expr::{EvalEnv, RelationExpr, ScalarExpr};
+use expr::{CompiledPredicates, EvalEnv, RelationExpr, ScalarExpr};
 use repr::{Datum, Row};
 
 use super::context::{ArrangementFlavor, Context};
@@ -93,11 +93,14 @@ where
                             // concurrent updates from relations prior to `relation`.
                             let delta_query = inner.clone().region(|region| {
                                 // Ensure this input is rendered, and extract its update stream.
-                                let mut update_stream = self
-                                    .collection(&inputs[relation])
-                                    .expect("Failed to render update stream")
-                                    .enter(inner)
-                                    .enter(region);
+                                let mut update_stream = super::batch::discard_on_shutdown(
+                                    &self
+                                        .collection(&inputs[relation])
+                                        .expect("Failed to render update stream"),
+                                    &self.shutdown_token,
+                                )
+                                .enter(inner)
+                                .enter(region);
 
                                 // We track the sources of each column in our update stream.
                                 let mut update_column_sources = (0..arities[relation])
@@ -337,15 +340,7 @@ where
     } else {
         let env = env.clone();
         let temp_storage = repr::RowArena::new();
-        updates.filter(move |input_row| {
-            let datums = input_row.unpack();
-            ready_to_go.iter().all(
-                |predicate| match predicate.eval(&datums, &env, &temp_storage) {
-                    Datum::True => true,
-                    Datum::False | Datum::Null => false,
-                    _ => unreachable!(),
-                },
-            )
-        })
+        let predicates = CompiledPredicates::compile(&ready_to_go);
+        updates.filter(move |input_row| predicates.eval_row(input_row, &env, &temp_storage))
     }
 }
@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::rc::Weak;
@@ -20,16 +21,21 @@ use differential_dataflow::trace::implementations::ord::OrdValSpine;
 use differential_dataflow::{AsCollection, Collection};
 use timely::communication::Allocate;
 use timely::dataflow::operators::unordered_input::UnorderedInput;
-use timely::dataflow::Scope;
+use timely::dataflow::{Scope, Stream};
+use timely::progress::frontier::Antichain;
 use timely::worker::Worker as TimelyWorker;
 
 use dataflow_types::Timestamp;
 use dataflow_types::*;
-use expr::{EvalEnv, GlobalId, Id, RelationExpr, ScalarExpr, SourceInstanceId};
+use expr::{
+    CompiledExpr, CompiledPredicates, EvalEnv, GlobalId, Id, RelationExpr, ScalarExpr,
+    SourceInstanceId,
+};
 use repr::{Datum, RelationType, Row, RowArena};
 
 use self::context::{ArrangementFlavor, Context};
 use super::sink;
+use super::sink::SinkFrontier;
 use super::source;
 use super::source::FileReadStyle;
 use super::source::SourceToken;
@@ -39,10 +45,16 @@ use crate::logging::materialized::{Logger, MaterializedEvent};
 use crate::server::LocalInput;
 use crate::server::{TimestampChanges, TimestampHistories};
 
+mod batch;
 mod context;
+mod dedup;
 mod delta_join;
+mod quiesce;
 mod reduce;
 
+pub(crate) use self::context::ShutdownToken;
+pub(crate) use self::quiesce::Quiesce;
+
 pub(crate) fn build_local_input<A: Allocate>(
     manager: &mut TraceManager,
     worker: &mut TimelyWorker<A>,
@@ -94,12 +106,14 @@ pub(crate) fn build_dataflow<A: Allocate>(
     manager: &mut TraceManager,
     worker: &mut TimelyWorker<A>,
     dataflow_drops: &mut HashMap<GlobalId, Box<dyn Any>>,
+    sink_frontiers: &mut HashMap<GlobalId, SinkFrontier>,
     advance_timestamp: bool,
     global_source_mappings: &mut HashMap<SourceInstanceId, Weak<Option<SourceToken>>>,
     timestamp_histories: TimestampHistories,
     timestamp_channel: TimestampChanges,
     logger: &mut Option<Logger>,
     executor: &tokio::runtime::Handle,
+    quiesce: &Quiesce,
 ) {
     let worker_index = worker.index();
     let worker_peers = worker.peers();
@@ -114,7 +128,15 @@ pub(crate) fn build_dataflow<A: Allocate>(
         scope.clone().region(|region| {
             let mut context = Context::<_, _, _, Timestamp>::new();
 
+            // The dataflow shuts down once all of its exports, each of which
+            // holds a clone of this token, have been dropped.
+            let shutdown_token = Rc::new(());
+            context.shutdown_token = ShutdownToken::new(&shutdown_token);
+
             let mut source_tokens = HashMap::new();
+            // The raw streams of the sources in each group of fused sources,
+            // by the group's position in `dataflow.fused_sources`.
+            let mut fused_streams = HashMap::new();
             // this is stopgap measure so dropping an index and recreating one with the same name
             // does not result in timestamp/reading from source errors.
             // use an export id to distinguish between different dataflows
@@ -135,6 +157,8 @@ pub(crate) fn build_dataflow<A: Allocate>(
                     encoding,
                     envelope,
                     consistency,
+                    metadata,
+                    deduplication,
                 } = src.connector
                 {
                     // This uid must be unique across all different instantiations of a source
@@ -142,60 +166,157 @@ pub(crate) fn build_dataflow<A: Allocate>(
                         sid: src_id.sid,
                         vid: first_export_id,
                     };
-                    let (source, capability) = match connector {
-                        ExternalSourceConnector::Kafka(c) => {
-                            // Distribute read responsibility among workers.
-                            use differential_dataflow::hashable::Hashable;
-                            let hash = src_id.hashed() as usize;
-                            let read_from_kafka = hash % worker_peers == worker_index;
-                            source::kafka(
+                    // MQTT sources decode their own messages, as their rows
+                    // include each message's topic alongside its payload.
+                    // PostgreSQL sources receive rows rather than messages.
+                    let (stream, capability) = if let ExternalSourceConnector::Mqtt(c) = connector {
+                        // Distribute read responsibility among workers.
+                        use differential_dataflow::hashable::Hashable;
+                        let hash = src_id.hashed() as usize;
+                        let read_from_mqtt = hash % worker_peers == worker_index;
+                        source::mqtt(
+                            region,
+                            format!("mqtt-{}-{}", first_export_id, source_number),
+                            c,
+                            encoding,
+                            uid,
+                            read_from_mqtt,
+                        )
+                    } else if let ExternalSourceConnector::Postgres(c) = connector {
+                        // Distribute read responsibility among workers.
+                        use differential_dataflow::hashable::Hashable;
+                        let hash = src_id.hashed() as usize;
+                        let read_from_postgres = hash % worker_peers == worker_index;
+                        source::postgres(
+                            region,
+                            format!("postgres-{}-{}", first_export_id, source_number),
+                            c,
+                            src.desc.clone(),
+                            uid,
+                            read_from_postgres,
+                        )
+                    } else if let ExternalSourceConnector::Mysql(c) = connector {
+                        // Distribute read responsibility among workers.
+                        use differential_dataflow::hashable::Hashable;
+                        let hash = src_id.hashed() as usize;
+                        let read_from_mysql = hash % worker_peers == worker_index;
+                        source::mysql(
+                            region,
+                            format!("mysql-{}-{}", first_export_id, source_number),
+                            c,
+                            src.desc.clone(),
+                            uid,
+                            read_from_mysql,
+                        )
+                    } else {
+                        let (source, capability) = match connector {
+                            ExternalSourceConnector::Kafka(c) => {
+                                // The source distributes the topic's partitions
+                                // among the workers itself.
+                                let (source, capability) = source::kafka(
+                                    region,
+                                    format!("kafka-{}-{}", first_export_id, source_number),
+                                    c,
+                                    uid,
+                                    advance_timestamp,
+                                    timestamp_histories.clone(),
+                                    timestamp_channel.clone(),
+                                    consistency,
+                                    worker_index,
+                                    worker_peers,
+                                );
+                                // A fused source is decoded together with the
+                                // rest of its group, below.
+                                let group = dataflow
+                                    .fused_sources
+                                    .iter()
+                                    .position(|group| group.contains(&src_id));
+                                if let Some(group) = group {
+                                    fused_streams
+                                        .entry(group)
+                                        .or_insert_with(Vec::new)
+                                        .push(source);
+                                    let token = Rc::new(capability);
+                                    source_tokens.insert(src_id.sid, token.clone());
+                                    let prev =
+                                        global_source_mappings.insert(uid, Rc::downgrade(&token));
+                                    assert!(prev.is_none());
+                                    continue;
+                                }
+                                (source, capability)
+                            }
+                            ExternalSourceConnector::Kinesis(_c) => unreachable!(),
+                            ExternalSourceConnector::Mqtt(_)
+                            | ExternalSourceConnector::Postgres(_)
+                            | ExternalSourceConnector::Mysql(_) => unreachable!(),
+                            ExternalSourceConnector::Nats(c) => {
+                                // Distribute read responsibility among workers.
+                                use differential_dataflow::hashable::Hashable;
+                                let hash = src_id.hashed() as usize;
+                                let read_from_nats = hash % worker_peers == worker_index;
+                                source::nats(
+                                    region,
+                                    format!("nats-{}-{}", first_export_id, source_number),
+                                    c,
+                                    uid,
+                                    read_from_nats,
+                                )
+                            }
+                            ExternalSourceConnector::S3(c) => {
+                                // Distribute read responsibility among workers.
+                                use differential_dataflow::hashable::Hashable;
+                                let hash = src_id.hashed() as usize;
+                                let read_from_s3 = hash % worker_peers == worker_index;
+                                source::s3(
+                                    region,
+                                    format!("s3-{}-{}", first_export_id, source_number),
+                                    c,
+                                    uid,
+                                    read_from_s3,
+                                )
+                            }
+                            ExternalSourceConnector::Plugin(c) => source::plugin(
                                 region,
-                                format!("kafka-{}-{}", first_export_id, source_number),
+                                format!("plugin-{}-{}", first_export_id, source_number),
                                 c,
                                 uid,
-                                advance_timestamp,
-                                timestamp_histories.clone(),
-                                timestamp_channel.clone(),
-                                consistency,
-                                read_from_kafka,
-                            )
-                        }
-                        ExternalSourceConnector::Kinesis(_c) => unreachable!(),
-                        ExternalSourceConnector::File(c) => {
-                            let read_style = if worker_index != 0 {
-                                FileReadStyle::None
-                            } else if c.tail {
-                                FileReadStyle::TailFollowFd
-                            } else {
-                                FileReadStyle::ReadOnce
-                            };
-                            source::file(
-                                src_id,
-                                region,
-                                format!("csv-{}", src_id),
-                                c.path,
-                                executor,
-                                read_style,
-                            )
-                        }
+                                worker_index,
+                                worker_peers,
+                            ),
+                            ExternalSourceConnector::File(c) => {
+                                let read_style = if worker_index != 0 {
+                                    FileReadStyle::None
+                                } else if c.tail {
+                                    FileReadStyle::TailFollowFd
+                                } else {
+                                    FileReadStyle::ReadOnce
+                                };
+                                source::file(
+                                    src_id,
+                                    region,
+                                    format!("csv-{}", src_id),
+                                    c.path,
+                                    executor,
+                                    read_style,
+                                    c.compression,
+                                )
+                            }
+                        };
+
+                        // TODO(brennan) -- this should just be a RelationExpr::FlatMap using regexp_extract, csv_extract,
+                        // a hypothetical future avro_extract, protobuf_extract, etc.
+                        (
+                            decode(&source, encoding, metadata, &dataflow.debug_name),
+                            capability,
+                        )
                     };
-
-                    // TODO(brennan) -- this should just be a RelationExpr::FlatMap using regexp_extract, csv_extract,
-                    // a hypothetical future avro_extract, protobuf_extract, etc.
-                    let stream = decode(&source, encoding, &dataflow.debug_name);
-
-                    let collection = match envelope {
-                        Envelope::None => stream.as_collection(),
-                        Envelope::Debezium => {
-                            // TODO(btv) -- this should just be a RelationExpr::Explode (name TBD)
-                            stream.as_collection().explode(|row| {
-                                let mut datums = row.unpack();
-                                let diff = datums.pop().unwrap().unwrap_int64() as isize;
-                                Some((Row::pack(datums.into_iter()), diff))
-                            })
-                        }
+                    let stream = match deduplication {
+                        Some(deduplication) => dedup::deduplicate(&stream, deduplication),
+                        None => stream,
                     };
 
+                    let collection = source_collection(&stream, &envelope, src.operators, quiesce);
+
                     // Introduce the stream by name, as an unarranged collection.
                     context.collections.insert(
                         RelationExpr::global_get(src_id.sid, src.desc.typ().clone()),
@@ -211,6 +332,39 @@ pub(crate) fn build_dataflow<A: Allocate>(
                 }
             }
 
+            // Decode each group of fused sources in a single stage, and make
+            // the result available to the unions of the group.
+            for (group, streams) in fused_streams {
+                let src_ids = &dataflow.fused_sources[group];
+                let src = &dataflow.source_imports[&src_ids[0]];
+                if let SourceConnector::External {
+                    encoding,
+                    envelope,
+                    metadata,
+                    deduplication,
+                    ..
+                } = &src.connector
+                {
+                    use timely::dataflow::operators::Concatenate;
+                    let source = region.concatenate(streams);
+                    let stream = decode(
+                        &source,
+                        encoding.clone(),
+                        metadata.clone(),
+                        &dataflow.debug_name,
+                    );
+                    let stream = match deduplication {
+                        Some(deduplication) => dedup::deduplicate(&stream, deduplication.clone()),
+                        None => stream,
+                    };
+                    let collection =
+                        source_collection(&stream, envelope, src.operators.clone(), quiesce);
+                    let mut ids = src_ids.iter().map(|src_id| src_id.sid).collect::<Vec<_>>();
+                    ids.sort();
+                    context.fused_sources.insert(ids, collection);
+                }
+            }
+
             let as_of = dataflow
                 .as_of
                 .as_ref()
@@ -236,18 +390,123 @@ pub(crate) fn build_dataflow<A: Allocate>(
                 }
             }
 
+            // The tokens of the arrangements that this dataflow imports from
+            // other dataflows, rather than arranging the same collections
+            // again itself.
+            let mut shared_tokens = Vec::new();
+
             for object in dataflow.objects_to_build.clone() {
                 if let Some(typ) = object.typ {
+                    // Import any arrangement of an input of the view that
+                    // another dataflow has already built. A view with a
+                    // refresh interval must arrange its delayed inputs itself.
+                    if object.refresh_interval.is_none() {
+                        object.relation_expr.as_ref().visit(&mut |e| {
+                            if let RelationExpr::ArrangeBy { input, keys } = e {
+                                if let RelationExpr::Get {
+                                    id: Id::Global(on_id),
+                                    typ: _,
+                                } = &**input
+                                {
+                                    for key_set in keys {
+                                        if context.arrangement(input, key_set).is_some() {
+                                            continue;
+                                        }
+                                        if let Some(trace) = manager.get_shared_mut(*on_id, key_set)
+                                        {
+                                            let token = trace.to_drop().clone();
+                                            let (arranged, button) = trace.import_frontier_core(
+                                                scope,
+                                                &format!("Shared({}, {:?})", on_id, key_set),
+                                                as_of.clone(),
+                                            );
+                                            let arranged = arranged.enter(region);
+                                            context.set_trace(input, key_set, arranged);
+                                            shared_tokens
+                                                .push(Rc::new((button.press_on_drop(), token)));
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    // A view with a refresh interval observes its inputs only
+                    // at multiples of that interval. Shadow each of its global
+                    // inputs with a delayed collection while it is rendered,
+                    // and restore the original assets afterwards.
+                    let mut shadowed = Vec::new();
+                    if let Some(interval) = object.refresh_interval {
+                        let interval = interval.as_millis() as Timestamp;
+                        let mut inputs = Vec::new();
+                        object.relation_expr.as_ref().visit(&mut |e| {
+                            if let RelationExpr::Get {
+                                id: Id::Global(_),
+                                typ: _,
+                            } = e
+                            {
+                                if !inputs.contains(e) {
+                                    inputs.push(e.clone());
+                                }
+                            }
+                        });
+                        for input in inputs {
+                            if let Some(collection) = context.collection(&input) {
+                                shadowed.push((
+                                    input.clone(),
+                                    context.collections.remove(&input),
+                                    context.local.remove(&input),
+                                    context.trace.remove(&input),
+                                ));
+                                context.collections.insert(
+                                    input,
+                                    collection.delay(move |time| round_up(*time, interval)),
+                                );
+                            }
+                        }
+                    }
                     context.ensure_rendered(
                         object.relation_expr.as_ref(),
                         &object.eval_env,
                         region,
                         worker_index,
                     );
-                    context.clone_from_to(
-                        &object.relation_expr.as_ref(),
-                        &RelationExpr::global_get(object.id, typ.clone()),
-                    );
+                    if object.changes_only || object.emit_final_after.is_some() {
+                        let mut collection = context
+                            .collection(object.relation_expr.as_ref())
+                            .expect("view alarmingly absent");
+                        if let Some(column) = object.emit_final_after {
+                            collection = emit_final_after(&collection, column);
+                        }
+                        // Consolidating the view's updates cancels out any
+                        // retraction and reinsertion of the same row at the
+                        // same time, which is exactly the set of updates that
+                        // do not change the view's contents. It also collapses
+                        // the intermediate results that `emit_final_after`
+                        // gathered at the same time into the final result.
+                        context.collections.insert(
+                            RelationExpr::global_get(object.id, typ.clone()),
+                            collection.consolidate(),
+                        );
+                    } else {
+                        context.clone_from_to(
+                            &object.relation_expr.as_ref(),
+                            &RelationExpr::global_get(object.id, typ.clone()),
+                        );
+                    }
+                    for (input, collection, local, trace) in shadowed {
+                        context.collections.remove(&input);
+                        context.local.remove(&input);
+                        context.trace.remove(&input);
+                        if let Some(collection) = collection {
+                            context.collections.insert(input.clone(), collection);
+                        }
+                        if let Some(local) = local {
+                            context.local.insert(input.clone(), local);
+                        }
+                        if let Some(trace) = trace {
+                            context.trace.insert(input, trace);
+                        }
+                    }
                 } else {
                     context.render_arranged(
                         &object.relation_expr.as_ref(),
@@ -292,6 +551,55 @@ pub(crate) fn build_dataflow<A: Allocate>(
                 // We do not install in `context.trace`, and can skip deleting things from it.
             }
 
+            // Offer the arrangements that the dataflow has built of the
+            // collections it reads to later dataflows. Each holds the tokens
+            // of the whole dataflow, which lives on for as long as anything
+            // imports one of them, and is forgotten along with the first index
+            // that the dataflow exports.
+            //
+            // A source that applies operators negotiated for this dataflow
+            // only produces the records that this dataflow uses, so its
+            // arrangements are not offered.
+            if let Some((owner, _, _)) = dataflow.index_exports.first() {
+                let tokens = Rc::new((
+                    source_tokens.values().cloned().collect::<Vec<_>>(),
+                    index_tokens.values().cloned().collect::<Vec<_>>(),
+                    shared_tokens.clone(),
+                    shutdown_token.clone(),
+                ));
+                for (expr, arrangements) in &context.local {
+                    if let RelationExpr::Get {
+                        id: Id::Global(on_id),
+                        typ,
+                    } = expr
+                    {
+                        let restricted = dataflow
+                            .source_imports
+                            .iter()
+                            .any(|(id, src)| id.sid == *on_id && src.operators.is_some());
+                        if restricted {
+                            continue;
+                        }
+                        for (keys, arranged) in arrangements {
+                            // Indexes are imported by the coordinator instead.
+                            let exported = dataflow
+                                .index_exports
+                                .iter()
+                                .any(|(_, desc, _)| desc.on_id == *on_id && &desc.keys == keys);
+                            if !exported {
+                                manager.share(
+                                    *owner,
+                                    *on_id,
+                                    keys.clone(),
+                                    keys.iter().map(|key| key.typ(typ)).collect(),
+                                    WithDrop::new(arranged.trace.clone(), tokens.clone()),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+
             for (export_id, index_desc, typ) in &dataflow.index_exports {
                 // put together tokens that belong to the export
                 let mut needed_source_tokens = Vec::new();
@@ -310,7 +618,12 @@ pub(crate) fn build_dataflow<A: Allocate>(
                         needed_source_tokens.push(source_token.clone());
                     }
                 }
-                let tokens = Rc::new((needed_source_tokens, needed_index_tokens));
+                needed_index_tokens.extend(shared_tokens.iter().cloned());
+                let tokens = Rc::new((
+                    needed_source_tokens,
+                    needed_index_tokens,
+                    shutdown_token.clone(),
+                ));
                 let get_expr = RelationExpr::global_get(index_desc.on_id, typ.clone());
                 match context.arrangement(&get_expr, &index_desc.keys) {
                     Some(ArrangementFlavor::Local(local)) => {
@@ -351,7 +664,7 @@ pub(crate) fn build_dataflow<A: Allocate>(
                         needed_source_tokens.push(source_token.clone());
                     }
                 }
-                let tokens = Rc::new((needed_source_tokens, needed_index_tokens));
+                needed_index_tokens.extend(shared_tokens.iter().cloned());
                 let collection = context
                     .collection(&RelationExpr::global_get(
                         sink.from.0,
@@ -359,18 +672,171 @@ pub(crate) fn build_dataflow<A: Allocate>(
                     ))
                     .expect("No arrangements");
 
-                match sink.connector {
+                let frontier = Rc::new(RefCell::new(Antichain::from_elem(0)));
+                sink_frontiers.insert(sink_id, frontier.clone());
+                // The sink operator holds on to its connection to the external
+                // system until its token is dropped along with the rest of the
+                // sink's tokens, even if the dataflow lives on.
+                let sink_token = match sink.connector {
+                    SinkConnector::Elasticsearch(c) => {
+                        sink::elasticsearch(&collection.inner, sink_id, c, sink.from.1, frontier)
+                    }
                     SinkConnector::Kafka(c) => {
-                        sink::kafka(&collection.inner, sink_id, c, sink.from.1)
+                        sink::kafka(&collection.inner, sink_id, c, sink.from.1, frontier)
                     }
-                    SinkConnector::Tail(c) => sink::tail(&collection.inner, sink_id, c),
-                }
+                    SinkConnector::Nats(c) => {
+                        sink::nats(&collection.inner, sink_id, c, sink.from.1, frontier)
+                    }
+                    SinkConnector::Plugin(c) => {
+                        sink::plugin(&collection.inner, sink_id, c, sink.from.1, frontier)
+                    }
+                    SinkConnector::Redis(c) => {
+                        sink::redis(&collection.inner, sink_id, c, sink.from.1, frontier)
+                    }
+                    SinkConnector::Tail(c) => {
+                        // Only send the rows and columns that the client
+                        // asked for.
+                        let mut collection = collection;
+                        if !c.filter.is_empty() {
+                            collection = batch::filter(
+                                &collection,
+                                CompiledPredicates::compile(&c.filter),
+                                c.eval_env.clone(),
+                                &context.shutdown_token,
+                            );
+                        }
+                        if let Some(project) = &c.project {
+                            collection = batch::project(
+                                &collection,
+                                project.clone(),
+                                &context.shutdown_token,
+                            );
+                        }
+                        sink::tail(&collection.inner, sink_id, c, frontier)
+                    }
+                };
+                let tokens = Rc::new((
+                    needed_source_tokens,
+                    needed_index_tokens,
+                    shutdown_token.clone(),
+                    sink_token,
+                ));
                 dataflow_drops.insert(sink_id, Box::new(tokens));
             }
         });
     })
 }
 
+/// Delays each update in `collection` until the time in the row's `column`,
+/// and discards updates that occur after that time.
+///
+/// Rows whose `column` is null are passed through unchanged.
+fn emit_final_after<G>(collection: &Collection<G, Row>, column: usize) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    use timely::dataflow::operators::map::Map;
+
+    collection
+        .inner
+        .flat_map(move |(row, time, diff)| {
+            let final_time = match row.unpack()[column] {
+                Datum::Timestamp(ts) => Some(ts.timestamp_millis()),
+                Datum::TimestampTz(ts) => Some(ts.timestamp_millis()),
+                _ => None,
+            };
+            match final_time {
+                None => Some((row, time, diff)),
+                Some(final_time) => {
+                    let final_time = std::cmp::max(final_time, 0) as Timestamp;
+                    if time <= final_time {
+                        Some((row, final_time, diff))
+                    } else {
+                        None
+                    }
+                }
+            }
+        })
+        .as_collection()
+}
+
+/// Turns the decoded records of a source into the source's collection,
+/// applying the source's envelope and the operators negotiated for it, and
+/// gating the result on the quiescence of the instance.
+fn source_collection<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    envelope: &Envelope,
+    operators: Option<LinearOperator>,
+    quiesce: &Quiesce,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let collection = match envelope {
+        Envelope::None => stream.as_collection(),
+        Envelope::Debezium => {
+            // TODO(btv) -- this should just be a RelationExpr::Explode (name TBD)
+            stream.as_collection().explode(|row| {
+                let mut datums = row.unpack();
+                let diff = datums.pop().unwrap().unwrap_int64() as isize;
+                Some((Row::pack(datums.into_iter()), diff))
+            })
+        }
+    };
+
+    // Discard the records that no use of the source needs, before they leave
+    // this worker.
+    let collection = match operators {
+        Some(operators) => apply_source_operators(&collection, operators),
+        None => collection,
+    };
+    quiesce::gate(&collection, quiesce)
+}
+
+/// Applies the operators pushed down to a source to its decoded records,
+/// discarding the records that fail a predicate.
+fn apply_source_operators<G>(
+    collection: &Collection<G, Row>,
+    operators: LinearOperator,
+) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let predicates = CompiledPredicates::compile(&operators.predicates);
+    let env = EvalEnv::default();
+    collection.filter(move |row| {
+        let temp_storage = RowArena::new();
+        predicates.eval(&row.unpack(), &env, &temp_storage)
+    })
+}
+
+/// Rounds `time` up to the nearest multiple of `interval`.
+fn round_up(time: Timestamp, interval: Timestamp) -> Timestamp {
+    match time % interval {
+        0 => time,
+        rem => time.saturating_add(interval - rem),
+    }
+}
+
+/// The largest constant join input that is broadcast to all workers.
+const BROADCAST_CONSTANT_ROWS: usize = 1024;
+
+/// Reports whether `expr` is known to contain so few records that it is
+/// cheaper to replicate it to every worker than to exchange its join partner.
+///
+/// This is the case for small constant collections, and for any collection
+/// whose type has an empty key, as such a collection has at most one record.
+fn is_known_small(expr: &RelationExpr) -> bool {
+    match expr {
+        RelationExpr::ArrangeBy { input, .. }
+        | RelationExpr::Filter { input, .. }
+        | RelationExpr::Project { input, .. }
+        | RelationExpr::Map { input, .. } => is_known_small(input),
+        RelationExpr::Constant { rows, .. } => rows.len() <= BROADCAST_CONSTANT_ROWS,
+        _ => expr.typ().keys.iter().any(|key| key.is_empty()),
+    }
+}
+
 impl<G> Context<G, RelationExpr, Row, Timestamp>
 where
     G: Scope<Timestamp = Timestamp>,
@@ -437,35 +903,8 @@ where
                     }
                 }
 
-                RelationExpr::Project { input, outputs } => {
-                    self.ensure_rendered(input, env, scope, worker_index);
-                    let outputs = outputs.clone();
-                    let collection = self.collection(input).unwrap().map(move |row| {
-                        let datums = row.unpack();
-                        Row::pack(outputs.iter().map(|i| datums[*i]))
-                    });
-
-                    self.collections.insert(relation_expr.clone(), collection);
-                }
-
-                RelationExpr::Map { input, scalars } => {
-                    self.ensure_rendered(input, env, scope, worker_index);
-                    let env = env.clone();
-                    let scalars = scalars.clone();
-                    let collection = self.collection(input).unwrap().map(move |input_row| {
-                        let mut datums = input_row.unpack();
-                        let temp_storage = RowArena::new();
-                        for scalar in &scalars {
-                            let datum = scalar.eval(&datums, &env, &temp_storage);
-                            // Scalar is allowed to see the outputs of previous scalars.
-                            // To avoid repeatedly unpacking input_row, we just push the outputs into datums so later scalars can see them.
-                            // Note that this doesn't mutate input_row.
-                            datums.push(datum);
-                        }
-                        Row::pack(&*datums)
-                    });
-
-                    self.collections.insert(relation_expr.clone(), collection);
+                RelationExpr::Project { .. } | RelationExpr::Map { .. } => {
+                    self.render_linear(relation_expr, env, scope, worker_index);
                 }
 
                 RelationExpr::FlatMapUnary {
@@ -478,6 +917,7 @@ where
                     let env = env.clone();
                     let func = func.clone();
                     let expr = expr.clone();
+                    let shutdown_token = self.shutdown_token.clone();
 
                     // Determine for each output column if it should be replaced by a
                     // small default value. This information comes from the "demand"
@@ -503,6 +943,9 @@ where
                         .collect::<Vec<_>>();
 
                     let collection = self.collection(input).unwrap().flat_map(move |input_row| {
+                        if shutdown_token.in_shutdown() {
+                            return vec![];
+                        }
                         let datums = input_row.unpack();
                         let replace = replace.clone();
                         let temp_storage = RowArena::new();
@@ -537,8 +980,8 @@ where
                 }
 
                 RelationExpr::Filter { input, predicates } => {
-                    let collection = if let RelationExpr::Join { implementation, .. } = &**input {
-                        match implementation {
+                    if let RelationExpr::Join { implementation, .. } = &**input {
+                        let collection = match implementation {
                             expr::JoinImplementation::Differential(_start, _order) => {
                                 self.render_join(input, predicates, env, scope, worker_index)
                             }
@@ -554,24 +997,11 @@ where
                             expr::JoinImplementation::Unimplemented => {
                                 panic!("Attempt to render unimplemented join");
                             }
-                        }
+                        };
+                        self.collections.insert(relation_expr.clone(), collection);
                     } else {
-                        self.ensure_rendered(input, env, scope, worker_index);
-                        let env = env.clone();
-                        let temp_storage = RowArena::new();
-                        let predicates = predicates.clone();
-                        self.collection(input).unwrap().filter(move |input_row| {
-                            let datums = input_row.unpack();
-                            predicates.iter().all(|predicate| {
-                                match predicate.eval(&datums, &env, &temp_storage) {
-                                    Datum::True => true,
-                                    Datum::False | Datum::Null => false,
-                                    _ => unreachable!(),
-                                }
-                            })
-                        })
-                    };
-                    self.collections.insert(relation_expr.clone(), collection);
+                        self.render_linear(relation_expr, env, scope, worker_index);
+                    }
                 }
 
                 RelationExpr::Join { implementation, .. } => match implementation {
@@ -615,6 +1045,17 @@ where
                 }
 
                 RelationExpr::Union { left, right } => {
+                    // A union of fused sources was rendered when the sources
+                    // were loaded, and its sources have no collections of
+                    // their own.
+                    let fused = union_of_gets(relation_expr)
+                        .and_then(|ids| self.fused_sources.get(&ids))
+                        .cloned();
+                    if let Some(collection) = fused {
+                        self.collections.insert(relation_expr.clone(), collection);
+                        return;
+                    }
+
                     self.ensure_rendered(left, env, scope, worker_index);
                     self.ensure_rendered(right, env, scope, worker_index);
 
@@ -632,6 +1073,61 @@ where
         }
     }
 
+    /// Renders a chain of maps, filters, and projections as a single operator.
+    ///
+    /// SQL lowering produces deep stacks of these operators, and rendering
+    /// each of them as its own operator pays for scheduling it and for packing
+    /// every row at every step. Instead, this walks down the chain to the
+    /// first input that is not one of these operators, or that is already
+    /// rendered, and applies the whole chain to that input at once. Filters
+    /// directly atop joins are left to the join, which applies them itself.
+    fn render_linear(
+        &mut self,
+        relation_expr: &RelationExpr,
+        env: &EvalEnv,
+        scope: &mut G,
+        worker_index: usize,
+    ) {
+        let mut ops = Vec::new();
+        let mut input = relation_expr;
+        loop {
+            if !ops.is_empty() && self.has_collection(input) {
+                break;
+            }
+            input = match input {
+                RelationExpr::Map { input, scalars } => {
+                    let scalars = scalars.iter().map(CompiledExpr::compile).collect();
+                    ops.push(batch::LinearOp::Map(scalars));
+                    input
+                }
+                RelationExpr::Filter { input, predicates } => {
+                    if let RelationExpr::Join { .. } = &**input {
+                        break;
+                    }
+                    let predicates = CompiledPredicates::compile(predicates);
+                    ops.push(batch::LinearOp::Filter(predicates));
+                    input
+                }
+                RelationExpr::Project { input, outputs } => {
+                    ops.push(batch::LinearOp::Project(outputs.clone()));
+                    input
+                }
+                _ => break,
+            };
+        }
+        ops.reverse();
+
+        self.ensure_rendered(input, env, scope, worker_index);
+        let collection = batch::linear(
+            &self.collection(input).unwrap(),
+            input.arity(),
+            ops,
+            env.clone(),
+            &self.shutdown_token,
+        );
+        self.collections.insert(relation_expr.clone(), collection);
+    }
+
     fn render_arranged(
         &mut self,
         relation_expr: &RelationExpr,
@@ -747,7 +1243,10 @@ where
             };
 
             // This collection will evolve as we join in more inputs.
-            let mut joined = self.collection(&inputs[*start]).unwrap();
+            let mut joined = batch::discard_on_shutdown(
+                &self.collection(&inputs[*start]).unwrap(),
+                &self.shutdown_token,
+            );
 
             // Maintain sources of each in-progress column.
             let mut columns = (0..arities[*start])
@@ -836,45 +1335,101 @@ where
                     .collect();
 
                 // We exploit the demand information to restrict `prev` to its demanded columns.
-                let prev_keyed = joined
-                    .map({
-                        move |row| {
-                            let datums = row.unpack();
-                            let key_row = Row::pack(prev_keys.iter().map(|i| datums[*i]));
-                            (key_row, Row::pack(prev_outputs.iter().map(|i| datums[*i])))
-                        }
-                    })
-                    .arrange_named::<OrdValSpine<_, _, _, _>>(&format!("JoinStage: {}", input));
-
-                joined = match self.arrangement(&inputs[*input], &next_keys[..]) {
-                    Some(ArrangementFlavor::Local(local)) => {
-                        prev_keyed.join_core(&local, move |_keys, old, new| {
-                            let prev_datums = old.unpack();
-                            let next_datums = new.unpack();
-                            // TODO: We could in principle apply some predicates here, and avoid
-                            // constructing output rows that will be filtered out soon.
-                            Some(Row::pack(
-                                prev_datums
-                                    .iter()
-                                    .chain(next_outputs.iter().map(|i| &next_datums[*i])),
-                            ))
-                        })
+                let prev_mapped = joined.map({
+                    move |row| {
+                        let datums = row.unpack();
+                        let key_row = Row::pack(prev_keys.iter().map(|i| datums[*i]));
+                        (key_row, Row::pack(prev_outputs.iter().map(|i| datums[*i])))
                     }
-                    Some(ArrangementFlavor::Trace(trace)) => {
-                        prev_keyed.join_core(&trace, move |_keys, old, new| {
-                            let prev_datums = old.unpack();
-                            let next_datums = new.unpack();
-                            // TODO: We could in principle apply some predicates here, and avoid
-                            // constructing output rows that will be filtered out soon.
-                            Some(Row::pack(
-                                prev_datums
-                                    .iter()
-                                    .chain(next_outputs.iter().map(|i| &next_datums[*i])),
-                            ))
+                });
+
+                let shutdown_token = self.shutdown_token.clone();
+                joined = if is_known_small(&inputs[*input]) {
+                    // The input is small enough that it is cheaper to replicate it
+                    // to every worker than to exchange `prev` by key. Each update
+                    // in `prev` then finds all of its matches on its own worker.
+                    use timely::dataflow::channels::pact::Pipeline;
+                    use timely::dataflow::operators::broadcast::Broadcast;
+
+                    let next_keyed = self
+                        .collection(&inputs[*input])
+                        .unwrap()
+                        .inner
+                        .broadcast()
+                        .as_collection()
+                        .map({
+                            let env = env.clone();
+                            let next_keys = next_keys.clone();
+                            move |row| {
+                                let temp_storage = RowArena::new();
+                                let datums = row.unpack();
+                                let key_row = Row::pack(
+                                    next_keys
+                                        .iter()
+                                        .map(|k| k.eval(&datums, &env, &temp_storage)),
+                                );
+                                (key_row, row)
+                            }
                         })
-                    }
-                    None => {
-                        panic!("Arrangement alarmingly absent!");
+                        .arrange_core::<_, OrdValSpine<_, _, _, _>>(
+                            Pipeline,
+                            &format!("JoinBroadcast: {}", input),
+                        );
+                    let prev_keyed = prev_mapped.arrange_core::<_, OrdValSpine<_, _, _, _>>(
+                        Pipeline,
+                        &format!("JoinStage: {}", input),
+                    );
+                    prev_keyed.join_core(&next_keyed, move |_keys, old, new| {
+                        if shutdown_token.in_shutdown() {
+                            return None;
+                        }
+                        let prev_datums = old.unpack();
+                        let next_datums = new.unpack();
+                        Some(Row::pack(
+                            prev_datums
+                                .iter()
+                                .chain(next_outputs.iter().map(|i| &next_datums[*i])),
+                        ))
+                    })
+                } else {
+                    let prev_keyed = prev_mapped
+                        .arrange_named::<OrdValSpine<_, _, _, _>>(&format!("JoinStage: {}", input));
+                    match self.arrangement(&inputs[*input], &next_keys[..]) {
+                        Some(ArrangementFlavor::Local(local)) => {
+                            prev_keyed.join_core(&local, move |_keys, old, new| {
+                                if shutdown_token.in_shutdown() {
+                                    return None;
+                                }
+                                let prev_datums = old.unpack();
+                                let next_datums = new.unpack();
+                                // TODO: We could in principle apply some predicates here, and avoid
+                                // constructing output rows that will be filtered out soon.
+                                Some(Row::pack(
+                                    prev_datums
+                                        .iter()
+                                        .chain(next_outputs.iter().map(|i| &next_datums[*i])),
+                                ))
+                            })
+                        }
+                        Some(ArrangementFlavor::Trace(trace)) => {
+                            prev_keyed.join_core(&trace, move |_keys, old, new| {
+                                if shutdown_token.in_shutdown() {
+                                    return None;
+                                }
+                                let prev_datums = old.unpack();
+                                let next_datums = new.unpack();
+                                // TODO: We could in principle apply some predicates here, and avoid
+                                // constructing output rows that will be filtered out soon.
+                                Some(Row::pack(
+                                    prev_datums
+                                        .iter()
+                                        .chain(next_outputs.iter().map(|i| &next_datums[*i])),
+                                ))
+                            })
+                        }
+                        None => {
+                            panic!("Arrangement alarmingly absent!");
+                        }
                     }
                 };
 
@@ -942,7 +1497,8 @@ where
             use differential_dataflow::operators::reduce::Reduce;
 
             self.ensure_rendered(input, env, scope, worker_index);
-            let input = self.collection(input).unwrap();
+            let input =
+                batch::discard_on_shutdown(&self.collection(input).unwrap(), &self.shutdown_token);
 
             // To provide a robust incremental orderby-limit experience, we want to avoid grouping
             // *all* records (or even large groups) and then applying the ordering and limit. Instead,
@@ -1085,7 +1641,10 @@ where
             // TODO: easier idioms for detecting, re-using, and stashing.
             if self.arrangement_columns(&input, &keys[..]).is_none() {
                 self.ensure_rendered(input, env, scope, worker_index);
-                let built = self.collection(input).unwrap();
+                let built = batch::discard_on_shutdown(
+                    &self.collection(input).unwrap(),
+                    &self.shutdown_token,
+                );
                 let keys2 = keys.clone();
                 let keyed = built
                     .map(move |row| {
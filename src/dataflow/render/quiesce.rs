@@ -0,0 +1,126 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Holding back the frontiers of sources while the instance is quiesced.
+//!
+//! Every source collection passes through a gate. While the instance is
+//! quiesced at some time `t`, each gate passes along the updates at times up
+//! to and including `t`, stashes the updates at later times, and holds its
+//! output frontier at `t + 1`. The contents of every view are then complete
+//! at `t` and stay there: peeks at `t` can be answered, but sinks emit
+//! nothing after `t`. Resuming releases the stashed updates and lets the
+//! frontiers advance again.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+
+use differential_dataflow::{AsCollection, Collection};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Capability, Operator};
+use timely::dataflow::Scope;
+use timely::scheduling::activate::Activator;
+
+use dataflow_types::{Diff, Timestamp};
+use repr::Row;
+
+/// A worker's handle on the quiescence of the instance, shared by the gates
+/// of all of the worker's sources.
+#[derive(Clone, Default)]
+pub(crate) struct Quiesce(Rc<RefCell<QuiesceState>>);
+
+#[derive(Default)]
+struct QuiesceState {
+    /// The last time at which sources may complete, or `None` if the
+    /// instance is not quiesced.
+    until: Option<Timestamp>,
+    /// The activators of the gates, which must reconsider their frontiers
+    /// whenever `until` changes. The activators of gates whose dataflows
+    /// have been dropped are harmless, so they are never removed.
+    activators: Vec<Activator>,
+}
+
+impl Quiesce {
+    /// Quiesces the instance at `until`, or resumes it if `until` is `None`.
+    pub fn set(&self, until: Option<Timestamp>) {
+        let mut state = self.0.borrow_mut();
+        state.until = until;
+        for activator in &state.activators {
+            activator.activate();
+        }
+    }
+}
+
+/// Routes `collection` through a gate that holds it back while the instance
+/// is quiesced.
+pub(crate) fn gate<G>(collection: &Collection<G, Row>, quiesce: &Quiesce) -> Collection<G, Row>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let scope = collection.scope();
+    let quiesce = quiesce.clone();
+    collection
+        .inner
+        .unary_frontier(Pipeline, "QuiesceGate", move |cap, info| {
+            quiesce
+                .0
+                .borrow_mut()
+                .activators
+                .push(scope.activator_for(&info.address[..]));
+            // Holding a capability lets the gate hold back its output
+            // frontier even when no updates are stashed.
+            let mut held = Some(cap);
+            let mut stash: Vec<(Capability<Timestamp>, Vec<(Row, Timestamp, Diff)>)> = Vec::new();
+            let mut buffer = Vec::new();
+            move |input, output| {
+                let until = quiesce.0.borrow().until;
+                input.for_each(|cap, data| {
+                    data.swap(&mut buffer);
+                    match until {
+                        None => output.session(&cap).give_vec(&mut buffer),
+                        Some(until) => {
+                            let (ready, later): (Vec<_>, Vec<_>) =
+                                buffer.drain(..).partition(|(_, time, _)| *time <= until);
+                            if !ready.is_empty() {
+                                output.session(&cap).give_iter(ready);
+                            }
+                            if !later.is_empty() {
+                                let time = cmp::max(*cap.time(), until + 1);
+                                stash.push((cap.delayed(&time), later));
+                            }
+                        }
+                    }
+                });
+
+                if until.is_none() {
+                    for (cap, updates) in stash.drain(..) {
+                        output.session(&cap).give_iter(updates);
+                    }
+                }
+
+                let frontier = input.frontier().frontier().iter().min().cloned();
+                let target = match (frontier, until) {
+                    (Some(time), Some(until)) => Some(cmp::min(time, until + 1)),
+                    (None, Some(until)) => Some(until + 1),
+                    (time, None) => time,
+                };
+                match target {
+                    Some(target) => {
+                        if let Some(cap) = &mut held {
+                            if *cap.time() < target {
+                                cap.downgrade(&target);
+                            }
+                        }
+                    }
+                    None => held = None,
+                }
+            }
+        })
+        .as_collection()
+}
@@ -66,7 +66,10 @@ where
             let keys_clone = group_key.clone();
 
             self.ensure_rendered(input, env, scope, worker_index);
-            let input = self.collection(input).unwrap();
+            let input = super::batch::discard_on_shutdown(
+                &self.collection(input).unwrap(),
+                &self.shutdown_token,
+            );
 
             // Distinct is a special case, as there are no aggregates to aggregate.
             // In this case, we use a special implementation that does not rely on
@@ -456,5 +459,6 @@ fn accumulable_hierarchical(func: &AggregateFunc) -> (bool, bool) {
         | AggregateFunc::MinTimestamp
         | AggregateFunc::MinTimestampTz
         | AggregateFunc::MinNull => (false, true),
+        AggregateFunc::MatchPattern { .. } => (false, false),
     }
 }
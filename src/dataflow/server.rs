@@ -9,14 +9,19 @@
 
 //! An interactive dataflow server.
 
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::net::TcpStream;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::rc::Weak;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use differential_dataflow::trace::cursor::Cursor;
 use differential_dataflow::trace::TraceReader;
@@ -36,25 +41,28 @@ use futures::channel::mpsc::UnboundedReceiver;
 use futures::executor::block_on;
 use futures::future::TryFutureExt;
 use futures::sink::{Sink, SinkExt};
+use futures::stream::StreamExt;
 use prometheus::{register_int_gauge_vec, IntGauge, IntGaugeVec};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::render;
 use crate::arrangement::{
-    manager::{KeysValsHandle, WithDrop},
+    manager::{KeysValsHandle, MergeDebt, WithDrop},
     TraceManager,
 };
 use dataflow_types::logging::LoggingConfig;
 use dataflow_types::{
-    compare_columns, Consistency, DataflowDesc, Diff, IndexDesc, KafkaSourceConnector,
-    PeekResponse, RowSetFinishing, Timestamp, Update,
+    compare_columns, Consistency, DataflowDesc, Diff, IndexDesc, KafkaSourceConnector, PeekMessage,
+    PeekMetadata, PeekResponse, RowSetFinishing, Timestamp, Update, PEEK_BATCH_SIZE,
 };
-use expr::{EvalEnv, GlobalId, SourceInstanceId};
+use expr::{ColumnOrder, EvalEnv, GlobalId, SourceInstanceId};
 use ore::future::channel::mpsc::ReceiverExt;
 use repr::{Datum, RelationType, Row, RowArena};
 
 use crate::logging;
 use crate::logging::materialized::MaterializedEvent;
+use crate::sink::SinkFrontier;
 
 use crate::source::SourceToken;
 
@@ -71,6 +79,18 @@ lazy_static! {
         &["worker"]
     )
     .unwrap();
+    static ref MERGE_DEBT_TRACES_RAW: IntGaugeVec = register_int_gauge_vec!(
+        "mz_worker_arrangement_merge_debt_traces",
+        "the number of arrangements whose merging the worker has deferred",
+        &["worker"]
+    )
+    .unwrap();
+    static ref MERGE_DEBT_RECORDS_RAW: IntGaugeVec = register_int_gauge_vec!(
+        "mz_worker_arrangement_merge_debt_records",
+        "the number of updates in arrangements whose merging the worker has deferred",
+        &["worker"]
+    )
+    .unwrap();
 }
 
 /// A [`comm::broadcast::Token`] that permits broadcasting commands to the
@@ -90,6 +110,32 @@ impl comm::broadcast::Token for BroadcastToken {
     }
 }
 
+/// A [`comm::broadcast::Token`] that permits broadcasting commands to the
+/// Timely workers of the named compute cluster.
+///
+/// The default cluster instead receives commands via [`BroadcastToken`].
+pub struct ClusterBroadcastToken(pub String);
+
+impl comm::broadcast::Token for ClusterBroadcastToken {
+    type Item = SequencedCommand;
+
+    /// Returns true, to enable loopback, as with [`BroadcastToken`].
+    fn loopback(&self) -> bool {
+        true
+    }
+
+    /// Derives the channel UUID from the cluster name, so that each cluster
+    /// receives only its own commands.
+    fn uuid(&self) -> Uuid {
+        let mut hasher = DefaultHasher::new();
+        TypeId::of::<Self>().hash(&mut hasher);
+        self.0.hash(&mut hasher);
+        let mut buf = [0; 16];
+        (&mut buf[8..]).copy_from_slice(&hasher.finish().to_be_bytes());
+        Uuid::from_bytes(buf)
+    }
+}
+
 /// Explicit instructions for timely dataflow workers.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SequencedCommand {
@@ -105,14 +151,56 @@ pub enum SequencedCommand {
     Peek {
         id: GlobalId,
         conn_id: u32,
-        tx: comm::mpsc::Sender<PeekResponse>,
+        tx: comm::mpsc::Sender<PeekMessage>,
         timestamp: Timestamp,
         finishing: RowSetFinishing,
         project: Option<Vec<usize>>,
         filter: Vec<expr::ScalarExpr>,
         eval_env: EvalEnv,
+        /// If set, only the rows of the arrangement with this key are read,
+        /// rather than the entire arrangement.
+        key: Option<Row>,
+        /// If set, the peek reports the net changes to the collection after
+        /// this time and up to `timestamp`, as [`PeekResponse::Changes`],
+        /// rather than the contents of the collection at `timestamp`.
+        since: Option<Timestamp>,
+        /// If set, the peek reports each update to the collection at times up
+        /// to `timestamp`, as [`PeekResponse::Updates`]. Updates at times
+        /// before this time, which should be the index's compaction frontier,
+        /// are reported at this time.
+        history: Option<Timestamp>,
+        /// If set, the peek is retired with an error if it cannot be fulfilled
+        /// within this long, e.g., because the collection never advances
+        /// past `timestamp`.
+        timeout: Option<Duration>,
+        /// If set, the recipient reads the response only as its client
+        /// fetches the rows, so delivering the response may be suspended for
+        /// arbitrarily long.
+        suspendable: bool,
+        /// If not empty, the worker with index `i` sends its entire response
+        /// through `worker_txs[i]` rather than `tx`, with its rows in runs,
+        /// so that the recipient can merge the runs of the workers as fast
+        /// as it forwards the merged rows, and no faster.
+        worker_txs: Vec<comm::mpsc::Sender<PeekMessage>>,
     },
-    /// Cancel the peek associated with the given `conn_id`.
+    /// Subscribe to the changes to the rows of an index with a given key.
+    ///
+    /// Unlike a TAIL, which renders a new dataflow, a subscription is served
+    /// directly from the index's arrangement, so it is cheap to maintain many
+    /// narrow subscriptions at once.
+    Subscribe {
+        id: GlobalId,
+        conn_id: u32,
+        /// The key of the rows to report.
+        key: Row,
+        tx: comm::mpsc::Sender<Vec<Update>>,
+        /// Changes at times after this time are reported.
+        since: Timestamp,
+        project: Option<Vec<usize>>,
+        filter: Vec<expr::ScalarExpr>,
+        eval_env: EvalEnv,
+    },
+    /// Cancel the peek or subscription associated with the given `conn_id`.
     CancelPeek { conn_id: u32 },
     /// Create a local input named `id`
     CreateLocalInput {
@@ -128,6 +216,11 @@ pub enum SequencedCommand {
         updates: Vec<Update>,
         advance_to: Timestamp,
     },
+    /// Advance all local inputs to `advance_to` without inserting updates.
+    AdvanceLocalInputs { advance_to: Timestamp },
+    /// Hold the frontiers of all sources at `until + 1`, or release them if
+    /// `until` is `None`.
+    Quiesce { until: Option<Timestamp> },
     /// Enable compaction in views.
     ///
     /// Each entry in the vector names a view and provides a frontier after which
@@ -136,18 +229,196 @@ pub enum SequencedCommand {
     AllowCompaction(Vec<(GlobalId, Vec<Timestamp>)>),
     /// Append a new event to the log stream.
     AppendLog(MaterializedEvent),
-    /// Advance worker timestamp
+    /// Advance worker timestamp: bind `timestamp` to the messages of
+    /// `partition` up to and including `offset`.
     AdvanceSourceTimestamp {
         id: SourceInstanceId,
+        partition: i32,
         timestamp: Timestamp,
         offset: i64,
     },
     /// Request that feedback is streamed to the provided channel.
     EnableFeedback(comm::mpsc::Sender<WorkerFeedbackWithMeta>),
+    /// Request that each worker report the dataflow objects it has installed,
+    /// via [`WorkerFeedback::Inventory`].
+    ReportInventory,
+    /// Request that each worker describe the arrangements it maintains, as
+    /// the response to a peek.
+    ///
+    /// Each row names the worker's cluster and index, the index that the
+    /// arrangement implements or whose dataflow built it, the arranged
+    /// collection if the arrangement is shared, whether it is shared, the
+    /// types of its keys if it is shared, and the number of batches, updates,
+    /// and approximate bytes that it holds.
+    ReportArrangements { tx: comm::mpsc::Sender<PeekMessage> },
     /// Disconnect inputs, drain dataflows, and shut down timely workers.
     Shutdown,
 }
 
+impl SequencedCommand {
+    /// Returns a brief description of the command, for diagnostics. Unlike
+    /// the command's `Debug` representation, the description omits bulky
+    /// payloads like dataflow plans and inserted rows.
+    pub fn summarize(&self) -> String {
+        match self {
+            SequencedCommand::CreateDataflows(dataflows) => format!(
+                "CreateDataflows({})",
+                dataflows
+                    .iter()
+                    .map(|d| d.debug_name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SequencedCommand::DropSources(ids) => format!("DropSources({:?})", ids),
+            SequencedCommand::DropSinks(ids) => format!("DropSinks({:?})", ids),
+            SequencedCommand::DropIndexes(ids) => format!("DropIndexes({:?})", ids),
+            SequencedCommand::Peek {
+                id,
+                conn_id,
+                timestamp,
+                ..
+            } => format!(
+                "Peek {{ id: {}, conn_id: {}, timestamp: {} }}",
+                id, conn_id, timestamp
+            ),
+            SequencedCommand::Subscribe { id, conn_id, .. } => {
+                format!("Subscribe {{ id: {}, conn_id: {} }}", id, conn_id)
+            }
+            SequencedCommand::CancelPeek { conn_id } => {
+                format!("CancelPeek {{ conn_id: {} }}", conn_id)
+            }
+            SequencedCommand::CreateLocalInput { name, index_id, .. } => format!(
+                "CreateLocalInput {{ name: {}, index_id: {} }}",
+                name, index_id
+            ),
+            SequencedCommand::Insert { id, updates, .. } => {
+                format!("Insert {{ id: {}, updates: {} }}", id, updates.len())
+            }
+            SequencedCommand::AdvanceLocalInputs { advance_to } => {
+                format!("AdvanceLocalInputs {{ advance_to: {} }}", advance_to)
+            }
+            SequencedCommand::Quiesce { until } => format!("Quiesce {{ until: {:?} }}", until),
+            SequencedCommand::AllowCompaction(frontiers) => {
+                format!("AllowCompaction({} indexes)", frontiers.len())
+            }
+            SequencedCommand::AppendLog(_) => "AppendLog".into(),
+            SequencedCommand::AdvanceSourceTimestamp {
+                id,
+                partition,
+                timestamp,
+                offset,
+            } => format!(
+                "AdvanceSourceTimestamp {{ id: {}, partition: {}, timestamp: {}, offset: {} }}",
+                id, partition, timestamp, offset
+            ),
+            SequencedCommand::EnableFeedback(_) => "EnableFeedback".into(),
+            SequencedCommand::ReportInventory => "ReportInventory".into(),
+            SequencedCommand::ReportArrangements { .. } => "ReportArrangements".into(),
+            SequencedCommand::Shutdown => "Shutdown".into(),
+        }
+    }
+}
+
+/// What a dataflow worker was doing, for inclusion in crash reports.
+#[derive(Clone, Debug, Default)]
+pub struct CrashContext {
+    /// The index of the worker.
+    pub worker_id: usize,
+    /// A summary of the last command the worker received.
+    pub last_command: Option<String>,
+    /// The name of the dataflow the worker was constructing or peeking at,
+    /// if any. This is unset while the worker is executing its dataflows,
+    /// as the worker does not track which dataflow each operator belongs to.
+    pub active_dataflow: Option<String>,
+    /// Summaries of the peeks that the worker has yet to fulfill.
+    pub pending_peeks: Vec<String>,
+    /// The names of the dataflows that export each index and sink that the
+    /// worker maintains.
+    pub dataflows: BTreeMap<GlobalId, String>,
+    /// The last frontier that the worker reported for each index and sink.
+    pub frontiers: BTreeMap<GlobalId, Vec<Timestamp>>,
+}
+
+lazy_static! {
+    /// The crash contexts of every dataflow worker in the process, so that a
+    /// crash report can describe all workers, not only the one that panicked.
+    static ref CRASH_CONTEXTS: Mutex<Vec<Arc<Mutex<CrashContext>>>> = Mutex::new(Vec::new());
+}
+
+thread_local! {
+    static CRASH_CONTEXT: RefCell<Option<Arc<Mutex<CrashContext>>>> = RefCell::new(None);
+}
+
+/// How long a worker waits for its dataflows to complete when it shuts down.
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many updates a worker releases for merging in a single step.
+const MAINTENANCE_EFFORT: usize = 1_000_000;
+
+/// How many updates a worker releases for merging in a single step while
+/// peeks are pending on it.
+const MAINTENANCE_EFFORT_WITH_PEEKS: usize = 10_000;
+
+/// How many steps a worker takes while peeks are pending on it before it
+/// returns to its maintenance work. The peeks are retried after every step.
+const PEEK_PRIORITY_STEPS: usize = 16;
+
+/// How often a worker reports the sizes of its arrangements.
+const ARRANGEMENT_SIZE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Returns the crash context of the dataflow worker running on the current
+/// thread, or `None` if the current thread is not a dataflow worker.
+///
+/// This is intended to be called from a panic hook, which runs on the
+/// panicking thread.
+pub fn crash_context() -> Option<CrashContext> {
+    CRASH_CONTEXT.with(|context| {
+        let context = context.borrow();
+        let context = context.as_ref()?.try_lock().ok()?;
+        Some(context.clone())
+    })
+}
+
+/// Returns the crash contexts of all dataflow workers in the process, ordered
+/// by worker index.
+///
+/// Workers whose context is locked, because they were updating it when the
+/// panic occurred, are omitted rather than waited for.
+pub fn crash_contexts() -> Vec<CrashContext> {
+    let registry = match CRASH_CONTEXTS.try_lock() {
+        Ok(registry) => registry,
+        Err(_) => return Vec::new(),
+    };
+    let mut contexts: Vec<_> = registry
+        .iter()
+        .filter_map(|context| context.try_lock().ok().map(|context| context.clone()))
+        .collect();
+    contexts.sort_by_key(|context| context.worker_id);
+    contexts
+}
+
+fn update_crash_context<F>(f: F)
+where
+    F: FnOnce(&mut CrashContext),
+{
+    CRASH_CONTEXT.with(|context| {
+        if let Some(context) = context.borrow().as_ref() {
+            if let Ok(mut context) = context.lock() {
+                f(&mut context)
+            }
+        }
+    })
+}
+
+/// Removes the index or sink `id` from the crash context of the current
+/// worker.
+fn forget_crash_context_export(id: GlobalId) {
+    update_crash_context(|context| {
+        context.dataflows.remove(&id);
+        context.frontiers.remove(&id);
+    })
+}
+
 /// Information from timely dataflow workers.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WorkerFeedbackWithMeta {
@@ -157,16 +428,42 @@ pub struct WorkerFeedbackWithMeta {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum WorkerFeedback {
-    /// A list of identifiers of traces, with prior and new upper frontiers.
+    /// A list of identifiers of traces and sinks, with prior and new upper
+    /// frontiers.
     FrontierUppers(Vec<(GlobalId, ChangeBatch<Timestamp>)>),
     /// The id of a source whose source connector has been dropped
     DroppedSource(SourceInstanceId),
     /// The id of a source whose source connector has been created
     CreateSource(SourceInstanceId, KafkaSourceConnector, Consistency),
+    /// The dataflow objects installed on the worker.
+    Inventory(Inventory),
+    /// The number of updates and the approximate number of bytes that the
+    /// arrangements of each of the worker's dataflows hold, keyed by the index
+    /// that the dataflow exports.
+    ArrangementSizes(Vec<(GlobalId, usize, usize)>),
+}
+
+/// The dataflow objects installed on a worker, identified by the IDs of the
+/// catalog items they implement.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    /// The compute cluster to which the worker belongs, or `None` for the
+    /// default cluster.
+    pub cluster: Option<String>,
+    /// The indexes whose arrangements the worker maintains.
+    pub arrangements: Vec<GlobalId>,
+    /// The tables into which the worker accepts inserts.
+    pub local_inputs: Vec<GlobalId>,
+    /// The sinks the worker is writing to.
+    pub sinks: Vec<GlobalId>,
 }
 
 /// Initiates a timely dataflow computation, processing materialized commands.
 ///
+/// The workers form the compute cluster named `cluster`, or the default
+/// cluster if `cluster` is `None`. If `cores` is not empty, each worker thread
+/// is pinned to the core at its index in `cores`.
+///
 /// TODO(benesch): pass a config struct here, or find some other way to cut
 /// down on the number of arguments.
 #[allow(clippy::too_many_arguments)]
@@ -178,6 +475,9 @@ pub fn serve<C>(
     executor: tokio::runtime::Handle,
     advance_timestamp: bool,
     logging_config: Option<dataflow_types::logging::LoggingConfig>,
+    cluster: Option<String>,
+    cores: Vec<usize>,
+    peek_results_addr: Option<comm::protocol::Addr>,
 ) -> Result<WorkerGuards<()>, String>
 where
     C: comm::Connection,
@@ -189,7 +489,11 @@ where
     // to the N timely threads that will be spawned. The Mutex<Vec<Option<T>>>
     // is hard to read through.
     let command_rxs = {
-        let mut rx = switchboard.broadcast_rx(BroadcastToken).fanout();
+        let rx = match &cluster {
+            None => switchboard.broadcast_rx(BroadcastToken),
+            Some(name) => switchboard.broadcast_rx(ClusterBroadcastToken(name.clone())),
+        };
+        let mut rx = rx.fanout();
         let command_rxs = Mutex::new((0..threads).map(|_| Some(rx.attach())).collect::<Vec<_>>());
         executor.spawn(
             rx.shuttle()
@@ -207,23 +511,43 @@ where
         .collect();
 
     timely::execute::execute_from(builders, Box::new(guard), move |timely_worker| {
+        // Pin each worker thread to its own core, if cores were assigned.
+        if !cores.is_empty() {
+            let core = cores[timely_worker.index() % threads];
+            if let Err(err) = ore::thread::pin_current_thread(&[core]) {
+                log::warn!("unable to pin worker thread to core {}: {}", core, err);
+            }
+        }
         executor.enter(|| {
             let command_rx = command_rxs.lock().unwrap()[timely_worker.index() % threads]
                 .take()
                 .unwrap()
                 .request_unparks(&executor);
             let worker_idx = timely_worker.index();
+            let (peek_delivery_tx, peek_delivery_rx) =
+                futures::channel::mpsc::channel(PEEK_DELIVERY_QUEUE);
+            executor.spawn(deliver_peek_responses(
+                peek_delivery_rx,
+                peek_results_addr.clone(),
+            ));
             Worker {
                 inner: timely_worker,
                 pending_peeks: Vec::new(),
+                peek_delivery_tx,
+                subscriptions: Vec::new(),
                 traces: TraceManager::default(),
                 logging_config: logging_config.clone(),
+                cluster: cluster.clone(),
                 feedback_tx: None,
                 command_rx,
                 materialized_logger: None,
                 sink_tokens: HashMap::new(),
+                sink_frontiers: HashMap::new(),
                 local_inputs: HashMap::new(),
                 reported_frontiers: HashMap::new(),
+                last_size_report: Instant::now(),
+                quiesce: render::Quiesce::default(),
+                profiler: logging::profile::Profiler::default(),
                 executor: executor.clone(),
                 metrics: Metrics::for_worker_id(worker_idx),
                 advance_timestamp,
@@ -236,7 +560,10 @@ where
     })
 }
 
-pub type TimestampHistories = Rc<RefCell<HashMap<SourceInstanceId, Vec<(Timestamp, i64)>>>>;
+/// The timestamp bindings of each partition of each Kafka source instance, in
+/// timestamp order, that the instance has yet to retire.
+pub type TimestampHistories =
+    Rc<RefCell<HashMap<SourceInstanceId, HashMap<i32, Vec<(Timestamp, i64)>>>>>;
 pub type TimestampChanges = Rc<
     RefCell<
         Vec<(
@@ -252,18 +579,31 @@ where
 {
     inner: &'w mut TimelyWorker<A>,
     pending_peeks: Vec<PendingPeek>,
+    /// Queues peek responses for delivery by a background task.
+    peek_delivery_tx: PeekDeliverySender,
+    subscriptions: Vec<Subscription>,
     traces: TraceManager,
     logging_config: Option<LoggingConfig>,
+    cluster: Option<String>,
     feedback_tx: Option<Pin<Box<dyn Sink<WorkerFeedbackWithMeta, Error = ()>>>>,
     command_rx: UnboundedReceiver<SequencedCommand>,
     materialized_logger: Option<logging::materialized::Logger>,
     sink_tokens: HashMap<GlobalId, Box<dyn Any>>,
+    sink_frontiers: HashMap<GlobalId, SinkFrontier>,
     local_inputs: HashMap<GlobalId, LocalInput>,
     advance_timestamp: bool,
     ts_source_mapping: HashMap<SourceInstanceId, Weak<Option<SourceToken>>>,
     ts_histories: TimestampHistories,
     ts_source_drops: TimestampChanges,
     reported_frontiers: HashMap<GlobalId, Antichain<Timestamp>>,
+    /// When the worker last reported the sizes of its arrangements.
+    last_size_report: Instant,
+    /// The gates on the worker's sources, which hold back their frontiers
+    /// while the instance is quiesced.
+    quiesce: render::Quiesce,
+    /// The per-operator statistics of the dataflows that `EXPLAIN ANALYZE`
+    /// profiles.
+    profiler: logging::profile::Profiler,
     executor: tokio::runtime::Handle,
     metrics: Metrics,
 }
@@ -278,6 +618,12 @@ struct Metrics {
     ///
     /// Updated every time we successfully fulfill a peek
     pending_peeks: IntGauge,
+    /// The number of arrangements whose merging was deferred
+    ///
+    /// Updated every time we perform trace maintenance
+    merge_debt_traces: IntGauge,
+    /// The number of updates in arrangements whose merging was deferred
+    merge_debt_records: IntGauge,
 }
 
 impl Metrics {
@@ -287,6 +633,8 @@ impl Metrics {
         Metrics {
             command_queue: COMMAND_QUEUE_RAW.with_label_values(&[&worker_id]),
             pending_peeks: PENDING_PEEKS_RAW.with_label_values(&[&worker_id]),
+            merge_debt_traces: MERGE_DEBT_TRACES_RAW.with_label_values(&[&worker_id]),
+            merge_debt_records: MERGE_DEBT_RECORDS_RAW.with_label_values(&[&worker_id]),
         }
     }
 
@@ -297,6 +645,11 @@ impl Metrics {
     fn observe_pending_peeks(&self, pending_peeks: &[PendingPeek]) {
         self.pending_peeks.set(pending_peeks.len() as i64);
     }
+
+    fn observe_merge_debt(&self, debt: &MergeDebt) {
+        self.merge_debt_traces.set(debt.traces as i64);
+        self.merge_debt_records.set(debt.records as i64);
+    }
 }
 
 impl<'w, A> Worker<'w, A>
@@ -329,10 +682,13 @@ where
             let d_traces = logging::differential::construct(&mut self.inner, logging, d_linked);
             let m_traces = logging::materialized::construct(&mut self.inner, logging, m_linked);
 
-            // Register each logger endpoint.
+            // Register each logger endpoint. The profiler observes the
+            // timely events before they are batched.
+            let profiler = self.profiler.clone();
             self.inner
                 .log_register()
                 .insert::<timely::logging::TimelyEvent, _>("timely", move |time, data| {
+                    profiler.observe(data);
                     t_logger.publish_batch(time, data)
                 });
 
@@ -381,8 +737,108 @@ where
         self.inner.log_register().remove("materialized");
     }
 
+    /// Winds down the worker in an orderly fashion.
+    ///
+    /// Pending peeks and subscriptions are aborted with an error. Then every
+    /// input and source is closed, so that each dataflow processes the data
+    /// already inside it. The sinks keep their dataflows alive until they have
+    /// delivered all of their output, i.e., until their frontiers are empty,
+    /// and only then are the sinks and indexes dropped. The worker steps until
+    /// all of its dataflows have completed, or until `SHUTDOWN_DRAIN_TIMEOUT`
+    /// elapses.
+    fn shutdown(&mut self) {
+        for peek in self.pending_peeks.drain(..) {
+            let err =
+                dataflow_types::Error::Internal("the dataflow workers are shutting down".into());
+            send_peek_response(&mut self.peek_delivery_tx, &peek, PeekResponse::Error(err));
+            if let Some(logger) = self.materialized_logger.as_mut() {
+                logger.log(MaterializedEvent::Peek(peek.as_log_event(), false));
+            }
+        }
+        self.metrics.pending_peeks.set(0);
+        self.subscriptions.clear();
+
+        self.local_inputs.clear();
+        for token in self.ts_source_mapping.values() {
+            if let Some(token) = token.upgrade() {
+                if let Some(token) = &*token {
+                    token.close();
+                }
+            }
+        }
+        self.shutdown_logging();
+
+        // Dropping a sink's tokens shuts down its dataflow, discarding any
+        // output that the sink has yet to emit, so the tokens are only dropped
+        // once the sink's frontier is empty.
+        let deadline = Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+        let drained = self.step_until(deadline, |worker| {
+            worker
+                .sink_frontiers
+                .values()
+                .all(|frontier| frontier.borrow().elements().is_empty())
+        });
+        self.sink_tokens.clear();
+        self.sink_frontiers.clear();
+        self.traces.del_all_traces();
+        if !drained || !self.step_until(deadline, |_| false) {
+            log::warn!(
+                "worker {}: dataflows did not complete within {:?} of shutdown",
+                self.inner.index(),
+                SHUTDOWN_DRAIN_TIMEOUT
+            );
+        }
+    }
+
+    /// Steps the worker until `done` returns true or the worker has no
+    /// dataflows left. Returns false if `deadline` passes first.
+    fn step_until<F>(&mut self, deadline: Instant, mut done: F) -> bool
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        while !done(self) {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            if !self.inner.step_or_park(Some(deadline - now)) {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Steps the dataflows on behalf of the pending peeks, retrying the peeks
+    /// after every step.
+    ///
+    /// A backfill keeps its dataflows busy for many steps, and between two
+    /// iterations of the worker loop the worker also merges traces, reports
+    /// its progress, and handles commands. A peek that the first of those
+    /// steps could fulfill would otherwise wait for all of that work, so the
+    /// worker takes up to `PEEK_PRIORITY_STEPS` steps, retrying the peeks
+    /// after each, before it returns to the rest of its loop. Only the first
+    /// step may park the thread.
+    fn step_with_peeks(&mut self, park_duration: Option<Duration>) {
+        self.inner.step_or_park(park_duration);
+        for _ in 1..PEEK_PRIORITY_STEPS {
+            self.process_peeks();
+            if self.pending_peeks.is_empty() {
+                break;
+            }
+            self.inner.step_or_park(Some(Duration::from_millis(0)));
+        }
+    }
+
     /// Draws from `dataflow_command_receiver` until shutdown.
     fn run(&mut self) {
+        let worker_id = self.inner.index();
+        let crash_context = Arc::new(Mutex::new(CrashContext {
+            worker_id,
+            ..Default::default()
+        }));
+        CRASH_CONTEXTS.lock().unwrap().push(crash_context.clone());
+        CRASH_CONTEXT.with(|context| *context.borrow_mut() = Some(crash_context.clone()));
+
         // Logging can be initialized with a "granularity" in nanoseconds, so that events are only
         // produced at logical times that are multiples of this many nanoseconds, which can reduce
         // the churn of the underlying computation.
@@ -391,17 +847,44 @@ where
 
         let mut shutdown = false;
         while !shutdown {
-            // Enable trace compaction.
-            self.traces.maintenance();
+            // Enable trace compaction. While peeks are pending, release less
+            // merging work per step, so that the merges that follow a bulk
+            // load are spread across steps between which the peeks are
+            // retried, rather than delaying the peeks until they complete.
+            let effort = if self.pending_peeks.is_empty() {
+                MAINTENANCE_EFFORT
+            } else {
+                MAINTENANCE_EFFORT_WITH_PEEKS
+            };
+            let debt = self.traces.maintenance(effort);
+            self.metrics.observe_merge_debt(&debt);
 
             // Ask Timely to execute a unit of work. If Timely decides there's
             // nothing to do, it will park the thread. We rely on another thread
             // unparking us when there's new work to be done, e.g., when sending
             // a command or when new Kafka messages have arrived.
-            self.inner.step_or_park(None);
+            //
+            // A pending peek with a timeout must not wait on such a wakeup, so
+            // park for no longer than it takes the earliest peek to expire.
+            // Deferred merging must not wait on one either.
+            let park_duration = if debt.traces > 0 {
+                Some(Duration::from_millis(0))
+            } else {
+                self.pending_peeks
+                    .iter()
+                    .filter_map(|peek| peek.deadline)
+                    .min()
+                    .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            };
+            if self.pending_peeks.is_empty() {
+                self.inner.step_or_park(park_duration);
+            } else {
+                self.step_with_peeks(park_duration);
+            }
 
             // Report frontier information back the coordinator.
             self.report_frontiers();
+            self.report_arrangement_sizes();
 
             self.report_source_drops();
 
@@ -420,7 +903,20 @@ where
 
             self.metrics.observe_pending_peeks(&self.pending_peeks);
             self.process_peeks();
+            self.process_subscriptions();
+            let pending_peeks = &self.pending_peeks;
+            update_crash_context(|context| {
+                if !pending_peeks.is_empty() || !context.pending_peeks.is_empty() {
+                    context.pending_peeks = pending_peeks.iter().map(|p| p.summarize()).collect();
+                }
+            });
         }
+
+        CRASH_CONTEXT.with(|context| *context.borrow_mut() = None);
+        CRASH_CONTEXTS
+            .lock()
+            .unwrap()
+            .retain(|context| !Arc::ptr_eq(context, &crash_context));
     }
 
     /// Send source drop notifications to the coordinator
@@ -468,23 +964,26 @@ where
                         .reported_frontiers
                         .get_mut(&id)
                         .expect("Frontier missing!");
-                    if lower != &upper {
-                        let mut changes = ChangeBatch::new();
-                        for time in lower.elements().iter() {
-                            changes.update(time.clone(), -1);
-                        }
-                        for time in upper.elements().iter() {
-                            changes.update(time.clone(), 1);
-                        }
-                        let lower = self.reported_frontiers.get_mut(&id).unwrap();
-                        changes.compact();
-                        if !changes.is_empty() {
-                            progress.push((id, changes));
-                        }
-                        lower.clone_from(&upper);
-                    }
+                    report_frontier_change(&mut progress, id, lower, &upper);
                 }
             }
+            for (id, frontier) in self.sink_frontiers.iter() {
+                let lower = self
+                    .reported_frontiers
+                    .get_mut(id)
+                    .expect("Frontier missing!");
+                report_frontier_change(&mut progress, *id, lower, &frontier.borrow());
+            }
+            if !progress.is_empty() {
+                let reported = &self.reported_frontiers;
+                update_crash_context(|context| {
+                    for (id, _) in &progress {
+                        context
+                            .frontiers
+                            .insert(*id, reported[id].elements().to_vec());
+                    }
+                });
+            }
             block_on(feedback_tx.send(WorkerFeedbackWithMeta {
                 worker_id: self.inner.index(),
                 message: WorkerFeedback::FrontierUppers(progress),
@@ -493,29 +992,71 @@ where
         }
     }
 
+    /// Send the sizes of the worker's arrangements to the coordinator, at most
+    /// once per `ARRANGEMENT_SIZE_INTERVAL`.
+    fn report_arrangement_sizes(&mut self) {
+        if self.last_size_report.elapsed() < ARRANGEMENT_SIZE_INTERVAL {
+            return;
+        }
+        self.last_size_report = Instant::now();
+        if let Some(feedback_tx) = &mut self.feedback_tx {
+            let sizes = self.traces.dataflow_sizes();
+            block_on(feedback_tx.send(WorkerFeedbackWithMeta {
+                worker_id: self.inner.index(),
+                message: WorkerFeedback::ArrangementSizes(sizes),
+            }))
+            .unwrap();
+        }
+    }
+
     fn handle_command(&mut self, cmd: SequencedCommand) {
+        update_crash_context(|context| context.last_command = Some(cmd.summarize()));
         match cmd {
             SequencedCommand::CreateDataflows(dataflows) => {
                 for dataflow in dataflows.into_iter() {
+                    let debug_name = dataflow.debug_name.clone();
+                    let export_ids = dataflow
+                        .index_exports
+                        .iter()
+                        .map(|(id, _, _)| *id)
+                        .chain(dataflow.sink_exports.iter().map(|(id, _)| *id));
+                    update_crash_context(|context| {
+                        for id in export_ids {
+                            context.dataflows.insert(id, debug_name.clone());
+                        }
+                        context.active_dataflow = Some(debug_name);
+                    });
                     for (id, _, _) in dataflow.index_exports.iter() {
                         self.reported_frontiers.insert(*id, Antichain::from_elem(0));
                         if let Some(logger) = self.materialized_logger.as_mut() {
                             logger.log(MaterializedEvent::Dataflow(*id, true));
                         }
                     }
+                    for (id, _) in dataflow.sink_exports.iter() {
+                        self.reported_frontiers.insert(*id, Antichain::from_elem(0));
+                    }
+                    if dataflow.profile {
+                        if let Some((id, _, _)) = dataflow.index_exports.first() {
+                            self.profiler
+                                .register(*id, self.inner.next_dataflow_index());
+                        }
+                    }
 
                     render::build_dataflow(
                         dataflow,
                         &mut self.traces,
                         self.inner,
                         &mut self.sink_tokens,
+                        &mut self.sink_frontiers,
                         self.advance_timestamp,
                         &mut self.ts_source_mapping,
                         self.ts_histories.clone(),
                         self.ts_source_drops.clone(),
                         &mut self.materialized_logger,
                         &self.executor,
+                        &self.quiesce,
                     );
+                    update_crash_context(|context| context.active_dataflow = None);
                 }
             }
 
@@ -526,18 +1067,29 @@ where
             }
             SequencedCommand::DropSinks(ids) => {
                 for id in ids {
+                    // Dropping the sink's tokens disconnects the sink operator
+                    // from its external system, and shuts down the dataflow
+                    // if nothing else depends on it.
                     self.sink_tokens.remove(&id);
+                    self.sink_frontiers.remove(&id);
+                    self.reported_frontiers.remove(&id);
+                    forget_crash_context_export(id);
                 }
             }
             SequencedCommand::DropIndexes(ids) => {
                 for id in ids {
                     self.traces.del_trace(&id);
+                    // Dropping a subscription closes its channel, which ends
+                    // the subscription for the client.
+                    self.subscriptions
+                        .retain(|subscription| subscription.id != id);
                     if let Some(logger) = self.materialized_logger.as_mut() {
                         logger.log(MaterializedEvent::Dataflow(id, false));
                     }
                     self.reported_frontiers
                         .remove(&id)
                         .expect("Dropped index with no frontier");
+                    forget_crash_context_export(id);
                 }
             }
 
@@ -550,29 +1102,56 @@ where
                 project,
                 filter,
                 eval_env,
+                key,
+                since,
+                history,
+                timeout,
+                suspendable,
+                worker_txs,
             } => {
+                let merged = !worker_txs.is_empty();
+                let tx = if merged {
+                    worker_txs.into_iter().nth(self.inner.index()).unwrap()
+                } else {
+                    tx
+                };
                 // Acquire a copy of the trace suitable for fulfilling the peek.
+                // A peek at changes or history must see the updates after
+                // `since` or `history` distinctly from those before it.
                 let mut trace = self.traces.get(&id).unwrap().clone();
-                trace.advance_by(&[timestamp]);
+                trace.advance_by(&[since.or(history).unwrap_or(timestamp)]);
                 trace.distinguish_since(&[]);
                 // Prepare a description of the peek work to do.
                 let mut peek = PendingPeek {
                     id,
                     conn_id,
                     tx,
+                    merged,
                     timestamp,
                     finishing,
                     trace,
                     project,
                     filter,
                     eval_env,
+                    key,
+                    since,
+                    history,
+                    deadline: timeout.map(|timeout| Instant::now() + timeout),
+                    received: Instant::now(),
+                    suspendable,
+                    profiler: if self.profiler.is_profiled(id) {
+                        Some(self.profiler.clone())
+                    } else {
+                        None
+                    },
                 };
                 // Log the receipt of the peek.
                 if let Some(logger) = self.materialized_logger.as_mut() {
                     logger.log(MaterializedEvent::Peek(peek.as_log_event(), true));
                 }
                 // Attempt to fulfill the peek.
-                let fulfilled = peek.seek_fulfillment(&mut Antichain::new());
+                let fulfilled =
+                    peek.seek_fulfillment(&mut Antichain::new(), &mut self.peek_delivery_tx);
                 if !fulfilled {
                     self.pending_peeks.push(peek);
                 } else {
@@ -588,10 +1167,10 @@ where
 
             SequencedCommand::CancelPeek { conn_id } => {
                 let logger = &mut self.materialized_logger;
+                let peek_delivery_tx = &mut self.peek_delivery_tx;
                 self.pending_peeks.retain(|peek| {
                     if peek.conn_id == conn_id {
-                        let mut tx = block_on(peek.tx.connect()).unwrap();
-                        block_on(tx.send(PeekResponse::Canceled)).unwrap();
+                        send_peek_response(peek_delivery_tx, peek, PeekResponse::Canceled);
 
                         if let Some(logger) = logger {
                             logger.log(MaterializedEvent::Peek(peek.as_log_event(), false));
@@ -601,7 +1180,36 @@ where
                     } else {
                         true // retain
                     }
-                })
+                });
+                self.subscriptions
+                    .retain(|subscription| subscription.conn_id != conn_id);
+            }
+
+            SequencedCommand::Subscribe {
+                id,
+                conn_id,
+                key,
+                tx,
+                since,
+                project,
+                filter,
+                eval_env,
+            } => {
+                let mut trace = self.traces.get(&id).unwrap().clone();
+                trace.advance_by(&[since]);
+                trace.distinguish_since(&[since]);
+                let tx = block_on(tx.connect()).expect("subscription transmitter failed");
+                self.subscriptions.push(Subscription {
+                    id,
+                    conn_id,
+                    key,
+                    tx,
+                    since,
+                    project,
+                    filter,
+                    eval_env,
+                    trace,
+                });
             }
 
             SequencedCommand::CreateLocalInput {
@@ -645,6 +1253,14 @@ where
                 }
             }
 
+            SequencedCommand::AdvanceLocalInputs { advance_to } => {
+                for (_, local_input) in self.local_inputs.iter_mut() {
+                    local_input.capability.downgrade(&advance_to);
+                }
+            }
+
+            SequencedCommand::Quiesce { until } => self.quiesce.set(until),
+
             SequencedCommand::AllowCompaction(list) => {
                 for (id, frontier) in list {
                     self.traces.allow_compaction(id, &frontier[..]);
@@ -665,35 +1281,81 @@ where
                         |err| panic!("error sending worker feedback: {}", err),
                     )));
             }
-            SequencedCommand::Shutdown => {
-                // this should lead timely to wind down eventually
-                self.traces.del_all_traces();
-                self.shutdown_logging();
+            SequencedCommand::ReportInventory => {
+                let inventory = Inventory {
+                    cluster: self.cluster.clone(),
+                    arrangements: self.traces.traces.keys().cloned().collect(),
+                    local_inputs: self.local_inputs.keys().cloned().collect(),
+                    sinks: self.sink_tokens.keys().cloned().collect(),
+                };
+                if let Some(feedback_tx) = &mut self.feedback_tx {
+                    block_on(feedback_tx.send(WorkerFeedbackWithMeta {
+                        worker_id: self.inner.index(),
+                        message: WorkerFeedback::Inventory(inventory),
+                    }))
+                    .unwrap();
+                }
+            }
+            SequencedCommand::ReportArrangements { tx } => {
+                let received = Instant::now();
+                let cluster = Datum::from(self.cluster.as_deref());
+                let worker_id = Datum::Int64(self.inner.index() as i64);
+                let rows: Vec<_> = self
+                    .traces
+                    .stats()
+                    .into_iter()
+                    .map(|stats| {
+                        let (on_id, key_types) = match stats.shared {
+                            Some((on_id, key_types)) => {
+                                let key_types = key_types
+                                    .iter()
+                                    .map(|typ| typ.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                (Some(on_id.to_string()), Some(key_types))
+                            }
+                            None => (None, None),
+                        };
+                        Row::pack(&[
+                            cluster,
+                            worker_id,
+                            Datum::String(&stats.index_id.to_string()),
+                            Datum::from(on_id.as_deref()),
+                            Datum::from(on_id.is_some()),
+                            Datum::from(key_types.as_deref()),
+                            Datum::Int64(stats.batches as i64),
+                            Datum::Int64(stats.records as i64),
+                            Datum::Int64(stats.bytes as i64),
+                        ])
+                    })
+                    .collect();
+                let mut responder = PeekResponder::open(&mut self.peek_delivery_tx, tx, false);
+                responder.send(PeekResponse::Rows(rows));
+                responder.finish(0, received);
             }
+            SequencedCommand::Shutdown => self.shutdown(),
             SequencedCommand::AdvanceSourceTimestamp {
                 id,
+                partition,
                 timestamp,
                 offset,
             } => {
                 let mut timestamps = self.ts_histories.borrow_mut();
-                if let Some(entries) = timestamps.get_mut(&id) {
-                    entries.push((timestamp, offset));
-                    let last_offset = if let Some(offs) = timestamps.get(&id).unwrap().last() {
-                        offs.1
-                    } else {
-                        -1
-                    };
-                    if last_offset == offset {
-                        // We only activate the Kakfa source if the offset is the same as the last
-                        // offset as new data already triggers the Kafka source's activation
-                        let source = self
-                            .ts_source_mapping
-                            .get(&id)
-                            .expect("Id should be present");
-                        if let Some(source) = source.upgrade() {
-                            if let Some(token) = &*source {
-                                token.activate();
-                            }
+                if let Some(partitions) = timestamps.get_mut(&id) {
+                    partitions
+                        .entry(partition)
+                        .or_insert_with(Vec::new)
+                        .push((timestamp, offset));
+                    // The source does not poll for new timestamp bindings, so
+                    // wake it: it may be able to downgrade its capability, or
+                    // it may be holding a message that awaited this binding.
+                    let source = self
+                        .ts_source_mapping
+                        .get(&id)
+                        .expect("Id should be present");
+                    if let Some(source) = source.upgrade() {
+                        if let Some(token) = &*source {
+                            token.activate();
                         }
                     }
                 }
@@ -701,8 +1363,10 @@ where
         }
     }
 
-    /// Scan pending peeks and attempt to retire each.
+    /// Scan pending peeks and attempt to retire each. Peeks that remain
+    /// unfulfilled past their deadline are retired with an error.
     fn process_peeks(&mut self) {
+        let now = Instant::now();
         let mut upper = Antichain::new();
         let pending_peeks_len = self.pending_peeks.len();
         let mut pending_peeks = std::mem::replace(
@@ -710,8 +1374,22 @@ where
             Vec::with_capacity(pending_peeks_len),
         );
         for mut peek in pending_peeks.drain(..) {
-            let success = peek.seek_fulfillment(&mut upper);
-            if !success {
+            let id = peek.id;
+            update_crash_context(|context| {
+                context.active_dataflow = Some(format!("peek of {}", id))
+            });
+            let success = peek.seek_fulfillment(&mut upper, &mut self.peek_delivery_tx);
+            update_crash_context(|context| context.active_dataflow = None);
+            if !success && peek.deadline.map_or(false, |deadline| deadline <= now) {
+                let err = dataflow_types::Error::Resource(format!(
+                    "timed out waiting for {} to advance past timestamp {}",
+                    id, peek.timestamp
+                ));
+                send_peek_response(&mut self.peek_delivery_tx, &peek, PeekResponse::Error(err));
+                if let Some(logger) = self.materialized_logger.as_mut() {
+                    logger.log(MaterializedEvent::Peek(peek.as_log_event(), false));
+                }
+            } else if !success {
                 self.pending_peeks.push(peek);
             } else {
                 // Log the fulfillment of the peek.
@@ -721,6 +1399,246 @@ where
             }
         }
     }
+
+    /// Reports the newly complete changes of each subscription. Subscriptions
+    /// whose client has gone away are dropped.
+    fn process_subscriptions(&mut self) {
+        let mut upper = Antichain::new();
+        let subscriptions_len = self.subscriptions.len();
+        let mut subscriptions = std::mem::replace(
+            &mut self.subscriptions,
+            Vec::with_capacity(subscriptions_len),
+        );
+        for mut subscription in subscriptions.drain(..) {
+            if subscription.report_changes(&mut upper) {
+                self.subscriptions.push(subscription);
+            }
+        }
+    }
+}
+
+/// Records in `progress` the change from the reported frontier `lower` to the
+/// current frontier `upper` of `id`, if they differ, and updates `lower`.
+fn report_frontier_change(
+    progress: &mut Vec<(GlobalId, ChangeBatch<Timestamp>)>,
+    id: GlobalId,
+    lower: &mut Antichain<Timestamp>,
+    upper: &Antichain<Timestamp>,
+) {
+    if lower != upper {
+        let mut changes = ChangeBatch::new();
+        for time in lower.elements().iter() {
+            changes.update(time.clone(), -1);
+        }
+        for time in upper.elements().iter() {
+            changes.update(time.clone(), 1);
+        }
+        changes.compact();
+        if !changes.is_empty() {
+            progress.push((id, changes));
+        }
+        lower.clone_from(upper);
+    }
+}
+
+/// The number of peek responses that may await delivery before a worker
+/// waits for their delivery before it can queue another.
+const PEEK_DELIVERY_QUEUE: usize = 64;
+
+/// The number of peek responses that a worker delivers concurrently.
+const PEEK_DELIVERY_CONCURRENCY: usize = 16;
+
+/// How many times to try to connect to the recipient of a peek response
+/// before giving up.
+const PEEK_DELIVERY_ATTEMPTS: usize = 5;
+
+/// How long to wait before the first retry of a failed connection to the
+/// recipient of a peek response. Each further retry waits twice as long.
+const PEEK_DELIVERY_BACKOFF: Duration = Duration::from_millis(100);
+
+type PeekDelivery = (
+    comm::mpsc::Sender<PeekMessage>,
+    UnboundedReceiver<PeekMessage>,
+    bool,
+);
+type PeekDeliverySender = futures::channel::mpsc::Sender<PeekDelivery>;
+
+/// The stream of messages with which a worker answers one peek.
+///
+/// Opening a responder queues the stream for delivery by the worker's
+/// delivery task, so that the worker does not stall on the network. The task
+/// forwards each batch to the recipient as soon as the worker sends it, so the
+/// worker need not assemble the entire response before any of it is sent.
+///
+/// The queue of streams is bounded, so a worker whose responses cannot be
+/// delivered as fast as it produces them eventually waits to open another
+/// responder. The batches within a stream are not: the recipient of a
+/// suspendable peek reads them only as its client fetches the rows, and the
+/// worker must never wait on a client.
+struct PeekResponder {
+    tx: futures::channel::mpsc::UnboundedSender<PeekMessage>,
+    rows: usize,
+    /// The groups of the run that has yet to be sent, and the number of rows
+    /// that they hold.
+    run: Vec<(Row, Vec<Row>)>,
+    run_len: usize,
+}
+
+impl PeekResponder {
+    /// Queues the response for delivery to the recipient at the other end of
+    /// `tx`.
+    fn open(
+        queue: &mut PeekDeliverySender,
+        tx: comm::mpsc::Sender<PeekMessage>,
+        suspendable: bool,
+    ) -> PeekResponder {
+        let (messages_tx, messages_rx) = futures::channel::mpsc::unbounded();
+        block_on(queue.send((tx, messages_rx, suspendable))).expect("peek delivery task failed");
+        PeekResponder {
+            tx: messages_tx,
+            rows: 0,
+            run: Vec::new(),
+            run_len: 0,
+        }
+    }
+
+    /// Sends `response` as the next batch of the response.
+    fn send(&mut self, response: PeekResponse) {
+        self.rows += match &response {
+            PeekResponse::Rows(rows) => rows.len(),
+            PeekResponse::Changes(changes) => changes.len(),
+            PeekResponse::Updates(updates) => updates.len(),
+            PeekResponse::Error(_) | PeekResponse::Canceled => 0,
+        };
+        // The delivery task only stops reading early if the recipient has
+        // gone away, in which case there is no one left to answer.
+        let _ = self.tx.unbounded_send(PeekMessage::Batch(response));
+    }
+
+    /// Sends the items in `batch`, wrapped by `wrap`, as the next batch of the
+    /// response if there are at least `PEEK_BATCH_SIZE` of them, leaving
+    /// `batch` empty.
+    fn send_full<T>(&mut self, batch: &mut Vec<T>, wrap: fn(Vec<T>) -> PeekResponse) {
+        if batch.len() >= PEEK_BATCH_SIZE {
+            self.send(wrap(std::mem::replace(batch, Vec::new())));
+        }
+    }
+
+    /// Adds `row`, whose key in the arrangement is `key`, to the next run of
+    /// the response, which is sent once it holds `PEEK_BATCH_SIZE` rows.
+    ///
+    /// The rows must be added in ascending order of key.
+    fn push_run(&mut self, key: &Row, row: Row) {
+        match self.run.last_mut() {
+            Some((last, rows)) if last == key => rows.push(row),
+            _ => self.run.push((key.clone(), vec![row])),
+        }
+        self.run_len += 1;
+        if self.run_len >= PEEK_BATCH_SIZE {
+            self.send_run();
+        }
+    }
+
+    /// Sends the run of rows added so far, if there are any.
+    fn send_run(&mut self) {
+        if !self.run.is_empty() {
+            self.rows += self.run_len;
+            self.run_len = 0;
+            let run = std::mem::replace(&mut self.run, Vec::new());
+            let _ = self.tx.unbounded_send(PeekMessage::Run(run));
+        }
+    }
+
+    /// Sends the end-of-results marker, which describes the response to a
+    /// peek at `timestamp` that arrived at the worker at `received`.
+    fn finish(mut self, timestamp: Timestamp, received: Instant) {
+        self.send_run();
+        let metadata = PeekMetadata {
+            timestamp,
+            rows: self.rows,
+            duration: received.elapsed(),
+        };
+        let _ = self.tx.unbounded_send(PeekMessage::Done(metadata));
+    }
+}
+
+/// Answers `peek` with `response`, in a single batch.
+fn send_peek_response(queue: &mut PeekDeliverySender, peek: &PendingPeek, response: PeekResponse) {
+    let mut responder = PeekResponder::open(queue, peek.tx.clone(), peek.suspendable);
+    responder.send(response);
+    responder.finish(peek.timestamp, peek.received);
+}
+
+/// Delivers the peek responses that a worker queues until the worker goes
+/// away.
+///
+/// If `addr` is set, responses are delivered to the recipient at `addr`
+/// rather than at the address that the recipient advertised in the peek.
+///
+/// The recipient of a suspendable peek reads the response only as its client
+/// fetches the rows, so the rest of the response is held here until then.
+/// Such responses are delivered by tasks of their own, so that they do not
+/// occupy the slots in which the responses to other peeks are delivered.
+async fn deliver_peek_responses(
+    rx: futures::channel::mpsc::Receiver<PeekDelivery>,
+    addr: Option<comm::protocol::Addr>,
+) {
+    rx.for_each_concurrent(PEEK_DELIVERY_CONCURRENCY, |(tx, messages, suspendable)| {
+        let tx = match &addr {
+            Some(addr) => tx.with_addr(addr.clone()),
+            None => tx,
+        };
+        let delivery = deliver_peek_response(tx, messages);
+        async move {
+            if suspendable {
+                tokio::spawn(delivery);
+            } else {
+                delivery.await
+            }
+        }
+    })
+    .await
+}
+
+/// Forwards the messages with which a worker answers a peek to the peek's
+/// recipient as the worker sends them: batches of at most `PEEK_BATCH_SIZE`
+/// rows, followed by the end-of-results marker.
+///
+/// Connecting to the recipient is retried with exponential backoff. Errors
+/// are logged rather than propagated: the recipient notices that the
+/// end-of-results marker is missing and reports the failure to the client.
+async fn deliver_peek_response(
+    tx: comm::mpsc::Sender<PeekMessage>,
+    mut messages: UnboundedReceiver<PeekMessage>,
+) {
+    let mut backoff = PEEK_DELIVERY_BACKOFF;
+    let mut attempt = 1;
+    let mut tx = loop {
+        match tx.connect().await {
+            Ok(tx) => break tx,
+            Err(err) if attempt < PEEK_DELIVERY_ATTEMPTS => {
+                log::warn!(
+                    "connecting to peek recipient failed (attempt {} of {}): {}",
+                    attempt,
+                    PEEK_DELIVERY_ATTEMPTS,
+                    err
+                );
+                tokio::time::delay_for(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(err) => {
+                log::error!("unable to deliver peek results: {}", err);
+                return;
+            }
+        }
+    };
+    while let Some(message) = messages.next().await {
+        if let Err(err) = tx.send(message).await {
+            log::error!("delivering peek results failed: {}", err);
+            return;
+        }
+    }
 }
 
 pub(crate) struct LocalInput {
@@ -736,7 +1654,10 @@ struct PendingPeek {
     /// The ID of the connection that submitted the peek. For logging only.
     conn_id: u32,
     /// A transmitter connected to the intended recipient of the peek.
-    tx: comm::mpsc::Sender<PeekResponse>,
+    tx: comm::mpsc::Sender<PeekMessage>,
+    /// Whether the rows are sent in runs, for the recipient to merge with the
+    /// runs of the other workers.
+    merged: bool,
     /// Time at which the collection should be materialized.
     timestamp: Timestamp,
     /// Finishing operations to perform on the peek, like an ordering and a
@@ -745,11 +1666,48 @@ struct PendingPeek {
     project: Option<Vec<usize>>,
     filter: Vec<expr::ScalarExpr>,
     eval_env: EvalEnv,
+    /// If set, the key of the only rows to read.
+    key: Option<Row>,
+    /// If set, the time after which changes are reported.
+    since: Option<Timestamp>,
+    /// If set, the time before which updates are reported as if they had
+    /// occurred at this time.
+    history: Option<Timestamp>,
+    /// If set, the time at which the peek is retired with an error if it has
+    /// not yet been fulfilled.
+    deadline: Option<Instant>,
+    /// The time at which the peek arrived at the worker.
+    received: Instant,
+    /// Whether the recipient reads the response only as its client fetches
+    /// the rows.
+    suspendable: bool,
     /// The data from which the trace derives.
     trace: WithDrop<KeysValsHandle>,
+    /// If set, the peek responds with the profile of the dataflow that
+    /// computes the peeked index, rather than with its contents.
+    profiler: Option<logging::profile::Profiler>,
+}
+
+impl Drop for PendingPeek {
+    fn drop(&mut self) {
+        // A peek that is retired without being fulfilled must still stop
+        // the profiling of its dataflow.
+        if let Some(profiler) = &self.profiler {
+            profiler.take(self.id);
+        }
+    }
 }
 
 impl PendingPeek {
+    /// Describes the peek for a crash report, without its filters or
+    /// finishing, which may contain user data.
+    fn summarize(&self) -> String {
+        format!(
+            "peek of {} at {} for connection {}",
+            self.id, self.timestamp, self.conn_id
+        )
+    }
+
     /// Produces a corresponding log event.
     pub fn as_log_event(&self) -> crate::logging::materialized::Peek {
         crate::logging::materialized::Peek::new(self.id, self.timestamp, self.conn_id)
@@ -767,16 +1725,38 @@ impl PendingPeek {
     /// then for any time `t` less or equal to `peek.timestamp` it is
     /// not the case that `upper` is less or equal to that timestamp,
     /// and so the result cannot further evolve.
-    fn seek_fulfillment(&mut self, upper: &mut Antichain<Timestamp>) -> bool {
+    fn seek_fulfillment(
+        &mut self,
+        upper: &mut Antichain<Timestamp>,
+        peek_delivery_tx: &mut PeekDeliverySender,
+    ) -> bool {
         self.trace.read_upper(upper);
         if !upper.less_equal(&self.timestamp) {
-            let response = match self.collect_finished_data() {
-                Ok(rows) => PeekResponse::Rows(rows),
-                Err(text) => PeekResponse::Error(text),
+            let mut responder =
+                PeekResponder::open(peek_delivery_tx, self.tx.clone(), self.suspendable);
+            let result = match (self.since, self.history) {
+                _ if self.profiler.is_some() => {
+                    let profiler = self.profiler.take().unwrap();
+                    let rows = profiler.take(self.id).unwrap_or_default();
+                    responder.send(PeekResponse::Rows(rows));
+                    Ok(())
+                }
+                (Some(since), _) => {
+                    self.collect_changes(since, &mut responder);
+                    Ok(())
+                }
+                (None, Some(since)) => {
+                    self.collect_history(since, &mut responder);
+                    Ok(())
+                }
+                (None, None) => self.collect_finished_data(&mut responder),
             };
-
-            let mut tx = block_on(self.tx.connect()).unwrap();
-            block_on(tx.send(response)).unwrap();
+            // Any batches already sent are followed by the error, which the
+            // recipient reports in place of the results.
+            if let Err(err) = result {
+                responder.send(PeekResponse::Error(err));
+            }
+            responder.finish(self.timestamp, self.received);
 
             true
         } else {
@@ -784,10 +1764,91 @@ impl PendingPeek {
         }
     }
 
-    /// Collects data for a known-complete peek.
-    fn collect_finished_data(&mut self) -> Result<Vec<Row>, String> {
+    /// Collects the net changes after `since` for a known-complete peek,
+    /// sending them through `responder` in batches as they are found.
+    ///
+    /// Peeks at changes always read an arrangement directly, so there is no
+    /// filter, projection, or finishing to apply.
+    fn collect_changes(&mut self, since: Timestamp, responder: &mut PeekResponder) {
+        use timely::order::PartialOrder;
+
         let (mut cursor, storage) = self.trace.cursor();
-        let mut results = Vec::new();
+        let mut changes = Vec::new();
+        while cursor.key_valid(&storage) {
+            while cursor.val_valid(&storage) {
+                let mut diff = 0;
+                cursor.map_times(&storage, |time, d| {
+                    if !time.less_equal(&since) && time.less_equal(&self.timestamp) {
+                        diff += d;
+                    }
+                });
+                if diff != 0 {
+                    changes.push((cursor.val(&storage).clone(), diff));
+                    responder.send_full(&mut changes, PeekResponse::Changes);
+                }
+                cursor.step_val(&storage);
+            }
+            cursor.step_key(&storage);
+        }
+        if !changes.is_empty() {
+            responder.send(PeekResponse::Changes(changes));
+        }
+    }
+
+    /// Collects each update at or before `self.timestamp` for a known-complete
+    /// peek, with times before `since` advanced to `since`, sending them
+    /// through `responder` in batches as they are found.
+    ///
+    /// Like peeks at changes, peeks at history always read an arrangement
+    /// directly.
+    fn collect_history(&mut self, since: Timestamp, responder: &mut PeekResponder) {
+        use timely::order::PartialOrder;
+
+        let (mut cursor, storage) = self.trace.cursor();
+        let mut updates = Vec::new();
+        // The updates to a row are all visited together, so they can be
+        // consolidated before moving on to the next row.
+        let mut row_updates = Vec::new();
+        while cursor.key_valid(&storage) {
+            while cursor.val_valid(&storage) {
+                let row = cursor.val(&storage);
+                cursor.map_times(&storage, |time, diff| {
+                    if time.less_equal(&self.timestamp) {
+                        row_updates.push((std::cmp::max(*time, since), *diff));
+                    }
+                });
+                differential_dataflow::consolidation::consolidate(&mut row_updates);
+                for (time, diff) in row_updates.drain(..) {
+                    updates.push((row.clone(), time, diff));
+                    responder.send_full(&mut updates, PeekResponse::Updates);
+                }
+                cursor.step_val(&storage);
+            }
+            cursor.step_key(&storage);
+        }
+        if !updates.is_empty() {
+            responder.send(PeekResponse::Updates(updates));
+        }
+    }
+
+    /// Collects data for a known-complete peek, sending it through
+    /// `responder` in batches as the cursor produces it.
+    ///
+    /// The rows of a peek with an ordering and a limit are sent only once the
+    /// whole arrangement has been read, as only then is it known which rows
+    /// survive the limit.
+    ///
+    /// The rows of a merged peek are sent in runs, in the order in which the
+    /// cursor produces them, which is by key and then by row. The recipient
+    /// merges the runs of the workers by key, so no worker need sort or
+    /// hold on to its rows.
+    fn collect_finished_data(
+        &mut self,
+        responder: &mut PeekResponder,
+    ) -> Result<(), dataflow_types::Error> {
+        let (mut cursor, storage) = self.trace.cursor();
+        let mut batch = Vec::new();
+        let mut produced = 0;
 
         // We can limit the record enumeration if i. there is a limit set,
         // and ii. if the specified ordering is empty (specifies no order).
@@ -797,16 +1858,31 @@ impl PendingPeek {
             None
         };
 
-        while cursor.key_valid(&storage) && limit.map(|l| results.len() < l).unwrap_or(true) {
-            while cursor.val_valid(&storage) && limit.map(|l| results.len() < l).unwrap_or(true) {
+        // Otherwise, if there is a limit, only the first `offset + limit`
+        // rows in the specified order can survive finishing, so we keep just
+        // those in a heap whose greatest row is the first to be evicted.
+        let top_k = if self.finishing.order_by.is_empty() {
+            None
+        } else {
+            self.finishing.limit.map(|l| l + self.finishing.offset)
+        };
+        let mut heap = BinaryHeap::new();
+
+        // A lookup visits only the rows with the requested key.
+        let key = self.key.as_ref();
+        if let Some(key) = key {
+            cursor.seek_key(&storage, key);
+        }
+        while cursor.key_valid(&storage)
+            && key.map_or(true, |key| cursor.key(&storage) == key)
+            && limit.map(|l| produced < l).unwrap_or(true)
+        {
+            while cursor.val_valid(&storage) && limit.map(|l| produced < l).unwrap_or(true) {
                 let row = cursor.val(&storage);
                 let datums = row.unpack();
                 // Before (expensively) determining how many copies of a row
                 // we have, let's eliminate rows that we don't care about.
-                if self.filter.iter().all(|predicate| {
-                    let temp_storage = RowArena::new();
-                    predicate.eval(&datums, &self.eval_env, &temp_storage) == Datum::True
-                }) {
+                if eval_filter(&self.filter, &datums, &self.eval_env)? {
                     // Differential dataflow represents collections with binary counts,
                     // but our output representation is unary (as many rows as reported
                     // by the count). We should determine this count, and especially if
@@ -819,62 +1895,251 @@ impl PendingPeek {
                         }
                     });
                     if copies < 0 {
-                        return Result::Err(format!(
+                        return Result::Err(dataflow_types::Error::Internal(format!(
                             "Negative multiplicity: {} for {:?}",
                             copies,
                             row.unpack(),
-                        ));
+                        )));
                     }
-
-                    // TODO: We could push a count here, as we create owned output later.
-                    for _ in 0..copies {
-                        results.push(row);
+                    let copies = copies as usize;
+
+                    if let Some(k) = top_k {
+                        // The finishing's ordering, ties included, applies to
+                        // the projected rows, so the heap must compare the
+                        // rows as projected to keep the same rows that the
+                        // coordinator would.
+                        let row = TopKRow {
+                            row: match self.project.as_deref() {
+                                Some(columns) => Cow::Owned(project_row(Some(columns), row)),
+                                None => Cow::Borrowed(row),
+                            },
+                            order_by: &self.finishing.order_by,
+                        };
+                        for _ in 0..copies {
+                            if heap.len() < k {
+                                heap.push(row.clone());
+                            } else if heap.peek().map(|max| row < *max).unwrap_or(false) {
+                                heap.pop();
+                                heap.push(row.clone());
+                            } else {
+                                // Further copies of the row would be evicted
+                                // too.
+                                break;
+                            }
+                        }
+                    } else {
+                        // Copies beyond the limit cannot survive finishing.
+                        let copies = limit.map_or(copies, |l| std::cmp::min(copies, l - produced));
+                        for _ in 0..copies {
+                            let projected = project_row(self.project.as_deref(), row);
+                            if self.merged {
+                                responder.push_run(cursor.key(&storage), projected);
+                            } else {
+                                batch.push(projected);
+                                responder.send_full(&mut batch, PeekResponse::Rows);
+                            }
+                            produced += 1;
+                        }
                     }
                 }
                 cursor.step_val(&storage);
             }
             cursor.step_key(&storage)
         }
+        for row in heap {
+            batch.push(row.row.into_owned());
+            responder.send_full(&mut batch, PeekResponse::Rows);
+        }
+        if !batch.is_empty() {
+            responder.send(PeekResponse::Rows(batch));
+        }
+        Ok(())
+    }
+}
 
-        // If we have extracted a projection, we should re-write the order_by columns.
-        if let Some(columns) = &self.project {
-            for key in self.finishing.order_by.iter_mut() {
-                key.column = columns[key.column];
-            }
+/// Applies the projection of a peek, if it has one, to `row`.
+fn project_row(project: Option<&[usize]>, row: &Row) -> Row {
+    match project {
+        Some(columns) => {
+            let datums = row.unpack();
+            Row::pack(columns.iter().map(|i| datums[*i]))
+        }
+        None => row.clone(),
+    }
+}
+
+/// Reports whether the row with `datums` satisfies every predicate in
+/// `filter`, or the error that evaluating a predicate produced.
+fn eval_filter(
+    filter: &[expr::ScalarExpr],
+    datums: &[Datum],
+    env: &EvalEnv,
+) -> Result<bool, dataflow_types::Error> {
+    for predicate in filter {
+        let temp_storage = RowArena::new();
+        match predicate.try_eval(datums, env, &temp_storage) {
+            Ok(Datum::True) => (),
+            Ok(_) => return Ok(false),
+            Err(err) => return Err(dataflow_types::Error::Eval(err.to_string())),
         }
+    }
+    Ok(true)
+}
+
+/// A projected row in the heap of a peek with an ordering and a limit, ordered
+/// as the peek's finishing orders it.
+#[derive(Clone)]
+struct TopKRow<'a> {
+    row: Cow<'a, Row>,
+    order_by: &'a [ColumnOrder],
+}
+
+impl Ord for TopKRow<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_columns(
+            self.order_by,
+            &self.row.unpack(),
+            &other.row.unpack(),
+            || self.row.cmp(&other.row),
+        )
+    }
+}
 
-        // TODO: We could sort here in any case, as it allows a merge sort at the coordinator.
-        if let Some(limit) = self.finishing.limit {
-            let offset_plus_limit = limit + self.finishing.offset;
-            if results.len() > offset_plus_limit {
-                // The `results` should be sorted by `Row`, which means we only
-                // need to re-order `results` when there is a non-empty order_by.
-                if !self.finishing.order_by.is_empty() {
-                    pdqselect::select_by(&mut results, offset_plus_limit, |left, right| {
-                        compare_columns(
-                            &self.finishing.order_by,
-                            &left.unpack(),
-                            &right.unpack(),
-                            || left.cmp(right),
-                        )
+impl PartialOrd for TopKRow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopKRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TopKRow<'_> {}
+
+/// A subscription to the changes to the rows of an index with a given key.
+struct Subscription {
+    /// The identifier of the index from which the subscription is served.
+    id: GlobalId,
+    /// The ID of the connection that holds the subscription.
+    conn_id: u32,
+    /// The key of the rows to report.
+    key: Row,
+    /// The channel to the client.
+    tx: Pin<Box<dyn Sink<Vec<Update>, Error = comm::Error> + Send>>,
+    /// The time through which changes have been reported.
+    since: Timestamp,
+    project: Option<Vec<usize>>,
+    filter: Vec<expr::ScalarExpr>,
+    eval_env: EvalEnv,
+    /// The data from which the trace derives.
+    trace: WithDrop<KeysValsHandle>,
+}
+
+impl Subscription {
+    /// Sends the changes to the subscribed rows at the times that have become
+    /// complete since the last report, and reports whether the client is still
+    /// listening.
+    ///
+    /// Each worker reads only its own shard of the arrangement, and at most one
+    /// shard contains the key. The cursor seeks directly to the key, so the
+    /// cost of a report does not depend on the size of the index.
+    fn report_changes(&mut self, upper: &mut Antichain<Timestamp>) -> bool {
+        self.trace.read_upper(upper);
+        // Times before the least element of `upper` are complete. Timestamps
+        // are totally ordered, so `upper` has at most one element.
+        let complete = match upper.elements().get(0) {
+            Some(upper) => upper.saturating_sub(1),
+            None => Timestamp::max_value(),
+        };
+        if complete <= self.since {
+            return true;
+        }
+
+        let mut results = Vec::new();
+        let (mut cursor, storage) = self.trace.cursor();
+        cursor.seek_key(&storage, &self.key);
+        if cursor.key_valid(&storage) && *cursor.key(&storage) == self.key {
+            while cursor.val_valid(&storage) {
+                let row = cursor.val(&storage);
+                let datums = row.unpack();
+                if self.filter.iter().all(|predicate| {
+                    let temp_storage = RowArena::new();
+                    predicate.eval(&datums, &self.eval_env, &temp_storage) == Datum::True
+                }) {
+                    let mut diffs = BTreeMap::new();
+                    cursor.map_times(&storage, |time, diff| {
+                        if self.since < *time && *time <= complete {
+                            *diffs.entry(*time).or_insert(0) += diff;
+                        }
                     });
+                    let row = match &self.project {
+                        Some(columns) => Row::pack(columns.iter().map(|i| datums[*i])),
+                        None => row.clone(),
+                    };
+                    for (timestamp, diff) in diffs {
+                        if diff != 0 {
+                            results.push(Update {
+                                row: row.clone(),
+                                timestamp,
+                                diff,
+                            });
+                        }
+                    }
                 }
-                results.truncate(offset_plus_limit);
+                cursor.step_val(&storage);
             }
         }
 
-        Ok(if let Some(columns) = &self.project {
-            results
-                .iter()
-                .map({
-                    move |row| {
-                        let datums = row.unpack();
-                        Row::pack(columns.iter().map(|i| datums[*i]))
-                    }
-                })
-                .collect()
-        } else {
-            results.iter().map(|row| (*row).clone()).collect()
-        })
+        self.since = complete;
+        self.trace.advance_by(&[complete]);
+        self.trace.distinguish_since(&[complete]);
+
+        if results.is_empty() {
+            return true;
+        }
+        results.sort_by_key(|update| update.timestamp);
+        block_on(self.tx.send(results)).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_peek_delivery_backoff() -> Result<(), Box<dyn Error>> {
+        let (switchboard, mut runtime) = comm::Switchboard::local()?;
+        let (tx, _rx) = switchboard.mpsc::<PeekMessage>();
+
+        // Nothing listens at the address of a listener that has been dropped,
+        // so every attempt to connect to it fails.
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let (messages_tx, messages_rx) = futures::channel::mpsc::unbounded();
+        messages_tx.unbounded_send(PeekMessage::Batch(PeekResponse::Rows(vec![])))?;
+        drop(messages_tx);
+
+        let start = Instant::now();
+        runtime.block_on(deliver_peek_response(tx.with_addr(addr), messages_rx));
+        let elapsed = start.elapsed();
+
+        // Each failed attempt but the last is followed by a wait twice as long
+        // as the one before. Another attempt would have waited longer still.
+        let waits: Vec<_> = (0..PEEK_DELIVERY_ATTEMPTS as u32)
+            .map(|i| PEEK_DELIVERY_BACKOFF * 2u32.pow(i))
+            .collect();
+        let backoff: Duration = waits[..PEEK_DELIVERY_ATTEMPTS - 1].iter().sum();
+        assert!(elapsed >= backoff, "gave up after {:?}", elapsed);
+        assert!(
+            elapsed < backoff + waits[PEEK_DELIVERY_ATTEMPTS - 1],
+            "gave up after {:?}",
+            elapsed
+        );
+        Ok(())
     }
 }
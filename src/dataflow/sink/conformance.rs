@@ -0,0 +1,168 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A conformance harness for sink plugins.
+
+use std::collections::BTreeMap;
+
+use failure::bail;
+use serde_json::{Map, Value};
+
+use dataflow_types::Diff;
+use interchange::json::Encoder;
+use repr::{Datum, RelationDesc, Row, ScalarType};
+
+use super::plugin::{DeliveryGuarantee, SinkPlugin, SinkPluginFactory, SinkRecord};
+
+/// Checks that the plugin constructed by `factory` with `options` honors the
+/// contract described on [`SinkPlugin`], including the delivery guarantee
+/// that the factory claims.
+///
+/// The harness drives a single instance of the plugin through the same
+/// sequence of calls that a sink makes, including the retries that follow a
+/// failure, writing rows with columns `a` (an `int8`) and `b` (a `text`).
+/// After each step, it calls `contents` to read back the destination, which
+/// must return the net updates that the plugin has applied since the harness
+/// began, in any order. The destination must be empty when the harness
+/// starts.
+///
+/// Returns an error describing the first step after which the destination
+/// did not contain what it should have.
+pub fn check_sink_plugin<F>(
+    factory: &dyn SinkPluginFactory,
+    options: &BTreeMap<String, String>,
+    mut contents: F,
+) -> Result<(), failure::Error>
+where
+    F: FnMut() -> Result<Vec<SinkRecord>, failure::Error>,
+{
+    let desc = RelationDesc::empty()
+        .add_column("a", ScalarType::Int64)
+        .add_column("b", ScalarType::String);
+    let encoder = Encoder::new(&desc)?;
+    let record = |a: i64, b: &str, diff: Diff| SinkRecord {
+        value: encoder.encode_columns(&Row::pack(&[Datum::Int64(a), Datum::String(b)]), &[0, 1]),
+        diff,
+    };
+    let guarantee = factory.guarantee();
+
+    factory.validate(options)?;
+    let mut plugin = factory.create(options, 0, 1)?;
+    let mut check = |step: &str, expected: &[SinkRecord], retried: &[SinkRecord]| {
+        check_contents(step, guarantee, contents()?, expected, retried)
+    };
+
+    // Progress without any updates must not change the destination.
+    plugin.commit(1)?;
+    check("committing an empty timestamp", &[], &[])?;
+
+    // The updates at a timestamp may be split across several writes.
+    plugin.begin(1)?;
+    plugin.write(&[record(1, "one", 1)])?;
+    plugin.write(&[record(2, "two", 1), record(3, "three", 2)])?;
+    plugin.commit(2)?;
+    let mut expected = vec![
+        record(1, "one", 1),
+        record(2, "two", 1),
+        record(3, "three", 2),
+    ];
+    check("delivering the first timestamp", &expected, &[])?;
+
+    // A failed delivery is retried from the beginning. An exactly-once
+    // plugin must discard the first attempt.
+    let updates = [record(1, "one", -1), record(4, "four", 1)];
+    plugin.begin(2)?;
+    plugin.write(&updates)?;
+    plugin.begin(2)?;
+    plugin.write(&updates)?;
+    plugin.commit(4)?;
+    expected = vec![
+        record(2, "two", 1),
+        record(3, "three", 2),
+        record(4, "four", 1),
+    ];
+    check("retrying the delivery of a timestamp", &expected, &updates)?;
+
+    // A timestamp with only deletions empties the destination.
+    plugin.begin(5)?;
+    plugin.write(&[
+        record(2, "two", -1),
+        record(3, "three", -2),
+        record(4, "four", -1),
+    ])?;
+    plugin.commit(6)?;
+    check("deleting every row", &[], &updates)?;
+
+    Ok(())
+}
+
+/// Checks that `actual` contains exactly the updates in `expected`, or, for
+/// at-least-once plugins, those updates plus a second copy of some of the
+/// updates in `retried`.
+fn check_contents(
+    step: &str,
+    guarantee: DeliveryGuarantee,
+    actual: Vec<SinkRecord>,
+    expected: &[SinkRecord],
+    retried: &[SinkRecord],
+) -> Result<(), failure::Error> {
+    let actual = consolidate(actual.iter());
+    let expected = consolidate(expected.iter());
+    let duplicated = consolidate(retried.iter());
+
+    let mut values: Vec<&Map<String, Value>> = vec![];
+    for &(value, _) in actual.iter().chain(&expected) {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    for value in values {
+        let actual_diff = diff_of(&actual, value);
+        let expected_diff = diff_of(&expected, value);
+        let ok = match guarantee {
+            DeliveryGuarantee::ExactlyOnce => actual_diff == expected_diff,
+            DeliveryGuarantee::AtLeastOnce => {
+                actual_diff == expected_diff
+                    || actual_diff == expected_diff + diff_of(&duplicated, value)
+            }
+        };
+        if !ok {
+            bail!(
+                "after {}, expected {} copies of {} in the destination, but found {}",
+                step,
+                expected_diff,
+                Value::Object(value.clone()),
+                actual_diff
+            );
+        }
+    }
+    Ok(())
+}
+
+fn consolidate<'a, I>(records: I) -> Vec<(&'a Map<String, Value>, Diff)>
+where
+    I: IntoIterator<Item = &'a SinkRecord>,
+{
+    let mut out: Vec<(&Map<String, Value>, Diff)> = vec![];
+    for record in records {
+        match out.iter_mut().find(|(value, _)| *value == &record.value) {
+            Some((_, diff)) => *diff += record.diff,
+            None => out.push((&record.value, record.diff)),
+        }
+    }
+    out
+}
+
+fn diff_of(records: &[(&Map<String, Value>, Diff)], value: &Map<String, Value>) -> Diff {
+    records
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, diff)| *diff)
+        .unwrap_or(0)
+}
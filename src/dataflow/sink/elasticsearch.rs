@@ -0,0 +1,154 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use log::error;
+use serde_json::json;
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::{Scope, Stream};
+
+use dataflow_types::{Diff, ElasticsearchSinkConnector, Timestamp};
+use expr::GlobalId;
+use interchange::json::Encoder;
+use repr::{RelationDesc, Row};
+
+use super::upsert::{self, Change, UpsertBuffer};
+use super::writer::SinkWriter;
+use super::{SinkFrontier, SinkToken};
+
+/// Maintains one Elasticsearch document per key of the input.
+///
+/// Each time the input frontier passes a timestamp, the net changes at that
+/// timestamp are sent in a single bulk request: upserted rows are indexed,
+/// replacing any existing document with the same ID, and deleted rows have
+/// their documents deleted. Requests are sent from a background thread, in
+/// order of timestamp. Requests that fail outright, e.g. because the cluster
+/// is unavailable, are retried until they succeed.
+/// Failures of individual documents, e.g. due to mapping conflicts, are
+/// logged and skipped, since retrying them cannot succeed.
+pub fn elasticsearch<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    id: GlobalId,
+    connector: ElasticsearchSinkConnector,
+    relation_desc: RelationDesc,
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let encoder = match Encoder::new(&relation_desc) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            error!("unable to encode rows for elasticsearch sink {}: {}", id, e);
+            return None;
+        }
+    };
+    let all_indices: Vec<_> = (0..relation_desc.typ().column_types.len()).collect();
+    let bulk_url = match connector.url.join("_bulk") {
+        Ok(url) => url,
+        Err(e) => {
+            error!("invalid url for elasticsearch sink {}: {}", id, e);
+            return None;
+        }
+    };
+
+    let key_indices = connector.key_indices.clone();
+    let exchange = Exchange::new(move |(row, _time, _diff): &(Row, Timestamp, Diff)| {
+        upsert::key_hash(row, &key_indices)
+    });
+    let mut buffer = UpsertBuffer::new(connector.key_indices.clone());
+    let name = format!("elasticsearch-{}", id);
+    let writer_name = name.clone();
+
+    let token = super::activatable_sink(stream, exchange, name, move |activator, shutdown| {
+        let client = reqwest::blocking::Client::new();
+        let mut writer = Some(SinkWriter::spawn(
+            writer_name,
+            activator,
+            move |body: &mut String| {
+                let res = client
+                    .post(bulk_url.clone())
+                    .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                    .body(body.clone())
+                    .send()?
+                    .error_for_status()?
+                    .json::<serde_json::Value>()?;
+                if res["errors"].as_bool() == Some(true) {
+                    log_item_errors(id, &res);
+                }
+                Ok(())
+            },
+        ));
+
+        move |input| {
+            let writer = match &mut writer {
+                Some(writer) if !shutdown.in_shutdown() => writer,
+                _ => {
+                    // The sink has been dropped. Stop the background thread,
+                    // which closes the client's connections, and discard the
+                    // input until the rest of the dataflow shuts down.
+                    writer = None;
+                    buffer = UpsertBuffer::new(connector.key_indices.clone());
+                    input.for_each(|_, _| ());
+                    return;
+                }
+            };
+            input.for_each(|_, rows| {
+                for (row, time, diff) in rows.iter() {
+                    buffer.push(row.clone(), *time, *diff);
+                }
+            });
+
+            while let Some((time, changes)) = buffer.peek(input.frontier()) {
+                let mut body = String::new();
+                for change in &changes {
+                    let (action, row) = match change {
+                        Change::Upsert(row) => ("index", row),
+                        Change::Delete(row) => ("delete", row),
+                    };
+                    let doc_id = upsert::key_string(&encoder, &connector.key_indices, row);
+                    let metadata = json!({ action: { "_index": connector.index, "_id": doc_id } });
+                    body.push_str(&metadata.to_string());
+                    body.push('\n');
+                    if let Change::Upsert(row) = change {
+                        let doc = encoder.encode_columns(row, &all_indices);
+                        body.push_str(&serde_json::Value::Object(doc).to_string());
+                        body.push('\n');
+                    }
+                }
+                writer.send(time, body);
+                buffer.pop(time);
+            }
+
+            let mut applied = buffer.applied_frontier(input.frontier());
+            if let Some(time) = writer.earliest_in_flight() {
+                applied.insert(time);
+            }
+            *frontier.borrow_mut() = applied;
+        }
+    });
+    Some(token)
+}
+
+fn log_item_errors(id: GlobalId, res: &serde_json::Value) {
+    let items = match res["items"].as_array() {
+        Some(items) => items,
+        None => return,
+    };
+    for item in items {
+        // Each item is an object with a single key, the action.
+        if let Some((_action, result)) = item.as_object().and_then(|o| o.iter().next()) {
+            if let Some(err) = result.get("error") {
+                error!(
+                    "elasticsearch sink {} failed to write document {}: {}",
+                    id, result["_id"], err
+                );
+            }
+        }
+    }
+}
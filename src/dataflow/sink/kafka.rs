@@ -12,7 +12,6 @@ use rdkafka::config::ClientConfig;
 use rdkafka::producer::FutureProducer;
 use rdkafka::producer::FutureRecord;
 use timely::dataflow::channels::pact::Pipeline;
-use timely::dataflow::operators::generic::Operator;
 use timely::dataflow::{Scope, Stream};
 
 use dataflow_types::{Diff, KafkaSinkConnector, Timestamp};
@@ -20,6 +19,8 @@ use expr::GlobalId;
 use interchange::avro::Encoder;
 use repr::{RelationDesc, Row};
 
+use super::{SinkFrontier, SinkToken};
+
 // TODO@jldlaughlin: What guarantess does this sink support? #1728
 
 // TODO@jldlaughlin: Progress tracking for kafka sinks #1442
@@ -52,7 +53,9 @@ pub fn kafka<G>(
     id: GlobalId,
     connector: KafkaSinkConnector,
     relation_desc: RelationDesc,
-) where
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
     G: Scope<Timestamp = Timestamp>,
 {
     let schema = interchange::avro::encode_schema(&relation_desc).expect("");
@@ -64,20 +67,46 @@ pub fn kafka<G>(
         Ok(schema_id) => {
             let mut config = ClientConfig::new();
             config.set("bootstrap.servers", &connector.url.to_string());
-            let producer: FutureProducer = config.create().unwrap();
+            let mut producer: Option<FutureProducer> = Some(config.create().unwrap());
+
+            let name = format!("kafka-{}", id);
+            let token =
+                super::activatable_sink(stream, Pipeline, name, move |_activator, shutdown| {
+                    move |input| {
+                        let producer = match &producer {
+                            Some(producer) if !shutdown.in_shutdown() => producer,
+                            _ => {
+                                // The sink has been dropped. Disconnect from Kafka,
+                                // abandoning any records still in flight, and discard
+                                // the input until the rest of the dataflow shuts down.
+                                producer = None;
+                                input.for_each(|_, _| ());
+                                return;
+                            }
+                        };
+                        let encoder = Encoder::new(&schema.to_string());
+                        input.for_each(|_, rows| {
+                            for (row, _time, _diff) in rows.iter() {
+                                let buf = encoder.encode(schema_id, row);
+                                let record: FutureRecord<&Vec<u8>, _> =
+                                    FutureRecord::to(&connector.topic).payload(&buf);
+                                producer.send(record, 1000 /* block_ms */);
+                            }
+                        });
 
-            stream.sink(Pipeline, &format!("kafka-{}", id), move |input| {
-                let encoder = Encoder::new(&schema.to_string());
-                input.for_each(|_, rows| {
-                    for (row, _time, _diff) in rows.iter() {
-                        let buf = encoder.encode(schema_id, row);
-                        let record: FutureRecord<&Vec<u8>, _> =
-                            FutureRecord::to(&connector.topic).payload(&buf);
-                        producer.send(record, 1000 /* block_ms */);
+                        // Records are only known to be written once the producer has
+                        // no more records in flight. Until then, the frontier stays
+                        // put, so it may lag the input while the producer is busy.
+                        if producer.in_flight_count() == 0 {
+                            super::advance_to_input(&frontier, input.frontier());
+                        }
                     }
-                })
-            })
+                });
+            Some(token)
+        }
+        Err(e) => {
+            error!("unable to publish schema to registry in kafka sink: {}", e);
+            None
         }
-        Err(e) => error!("unable to publish schema to registry in kafka sink: {}", e),
     }
 }
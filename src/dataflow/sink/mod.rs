@@ -7,8 +7,128 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+use futures::task::ArcWake;
+
+use timely::dataflow::channels::pact::ParallelizationContract;
+use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+use timely::dataflow::operators::generic::FrontieredInputHandle;
+use timely::dataflow::{Scope, Stream};
+use timely::progress::frontier::{Antichain, MutableAntichain};
+use timely::scheduling::{Activator, SyncActivator};
+use timely::Data;
+
+use dataflow_types::Timestamp;
+
+use crate::render::ShutdownToken;
+
+mod conformance;
+mod elasticsearch;
 mod kafka;
+mod nats;
+mod plugin;
+mod redis;
 mod tail;
+mod upsert;
+mod writer;
 
+pub use self::redis::redis;
+pub use conformance::check_sink_plugin;
+pub use elasticsearch::elasticsearch;
 pub use kafka::kafka;
+pub use nats::nats;
+pub use plugin::{
+    plugin, register_sink_plugin, sink_plugin, DeliveryGuarantee, SinkPlugin, SinkPluginFactory,
+    SinkRecord,
+};
 pub use tail::tail;
+
+/// The frontier up to which a sink has emitted its input to the external
+/// system. All updates at times not in advance of this frontier have been
+/// durably written.
+///
+/// The sink operator advances the frontier, and the worker reports its
+/// changes to the coordinator.
+pub type SinkFrontier = Rc<RefCell<Antichain<Timestamp>>>;
+
+/// Keeps a sink connected to its external system. Dropping the token
+/// disconnects the sink, even if the rest of its dataflow lives on.
+pub struct SinkToken {
+    _token: Rc<()>,
+    activator: Activator,
+}
+
+impl Drop for SinkToken {
+    fn drop(&mut self) {
+        // Schedule the sink operator, so that it notices that the token is gone
+        // right away, rather than once more input arrives.
+        self.activator.activate();
+    }
+}
+
+/// Sets `frontier` to the frontier of `input`, i.e., marks every update that
+/// the sink has received as emitted.
+fn advance_to_input(frontier: &SinkFrontier, input: &MutableAntichain<Timestamp>) {
+    let mut frontier = frontier.borrow_mut();
+    *frontier = Antichain::new();
+    for time in input.frontier().iter() {
+        frontier.insert(*time);
+    }
+}
+
+/// Returns a waker that schedules the operator that `activator` activates, so
+/// that a sink can poll an asynchronous connection, return if it is not
+/// ready, and run again once it is.
+fn activator_waker(activator: SyncActivator) -> Waker {
+    struct ActivatorWaker(Mutex<SyncActivator>);
+
+    impl ArcWake for ActivatorWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            // The operator may since have been dropped, in which case there
+            // is nothing left to schedule.
+            let _ = arc_self.0.lock().unwrap().activate();
+        }
+    }
+
+    futures::task::waker(Arc::new(ActivatorWaker(Mutex::new(activator))))
+}
+
+/// Like timely's `Operator::sink`, but hands `constructor` an activator
+/// through which other threads can schedule the operator, and which it can
+/// move into the returned logic, along with a shutdown token that indicates
+/// that the returned `SinkToken` has been dropped.
+fn activatable_sink<G, D, P, B, L>(
+    stream: &Stream<G, D>,
+    pact: P,
+    name: String,
+    constructor: B,
+) -> SinkToken
+where
+    G: Scope,
+    D: Data,
+    P: ParallelizationContract<G::Timestamp, D>,
+    B: FnOnce(SyncActivator, ShutdownToken) -> L,
+    L: FnMut(&mut FrontieredInputHandle<G::Timestamp, D, P::Puller>) + 'static,
+{
+    let scope = stream.scope();
+    let mut builder = OperatorBuilder::new(name, scope.clone());
+    let address = builder.operator_info().address;
+    let token = Rc::new(());
+    let shutdown_token = ShutdownToken::new(&token);
+    let mut input = builder.new_input(stream, pact);
+    let mut logic = constructor(scope.sync_activator_for(&address[..]), shutdown_token);
+    builder.build(|_capabilities| {
+        move |frontiers| {
+            let mut input = FrontieredInputHandle::new(&mut input, &frontiers[0]);
+            logic(&mut input);
+        }
+    });
+    SinkToken {
+        _token: token,
+        activator: scope.activator_for(&address[..]),
+    }
+}
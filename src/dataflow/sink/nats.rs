@@ -0,0 +1,161 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use differential_dataflow::consolidation::consolidate;
+use failure::bail;
+use log::error;
+use serde_json::{json, Value};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::{Scope, Stream};
+use timely::progress::frontier::Antichain;
+
+use dataflow_types::{Diff, NatsSinkConnector, Timestamp};
+use expr::GlobalId;
+use interchange::json::Encoder;
+use repr::{RelationDesc, Row};
+
+use super::writer::SinkWriter;
+use super::{SinkFrontier, SinkToken};
+
+/// How long to wait for JetStream to acknowledge a message before assuming
+/// that it was lost.
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Publishes each update in `stream` as a JSON message to the subject that
+/// `connector` names.
+///
+/// Updates are published one timestamp at a time, in order of timestamp,
+/// once every update at that timestamp is known. Messages are published from
+/// a background thread, and each must be acknowledged by the JetStream stream
+/// that captures the subject before the next is published. If a message is
+/// not acknowledged, the connection is dropped and the message is published
+/// again, so every update is delivered at least once. The sink's frontier
+/// advances past a timestamp only once all of its updates are acknowledged.
+pub fn nats<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    id: GlobalId,
+    connector: NatsSinkConnector,
+    relation_desc: RelationDesc,
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let encoder = match Encoder::new(&relation_desc) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            error!("unable to encode rows for nats sink {}: {}", id, e);
+            return None;
+        }
+    };
+    let all_indices: Vec<_> = (0..relation_desc.typ().column_types.len()).collect();
+
+    let mut pending: BTreeMap<Timestamp, Vec<(Row, Diff)>> = BTreeMap::new();
+    let name = format!("nats-{}", id);
+    let writer_name = name.clone();
+
+    let token = super::activatable_sink(stream, Pipeline, name, move |activator, shutdown| {
+        let url = connector.url.clone();
+        let subject = connector.subject.clone();
+        let mut conn: Option<nats::Connection> = None;
+        let mut writer = Some(SinkWriter::spawn(
+            writer_name,
+            activator,
+            move |messages: &mut VecDeque<String>| {
+                if conn.is_none() {
+                    conn = Some(nats::connect(url.as_str())?);
+                }
+                // Messages are removed once they are acknowledged, so that a
+                // retry resumes with the first unacknowledged message.
+                while let Some(message) = messages.front() {
+                    if let Err(e) = publish(conn.as_ref().unwrap(), &subject, message) {
+                        conn = None;
+                        return Err(e);
+                    }
+                    messages.pop_front();
+                }
+                Ok(())
+            },
+        ));
+
+        move |input| {
+            let writer = match &mut writer {
+                Some(writer) if !shutdown.in_shutdown() => writer,
+                _ => {
+                    // The sink has been dropped. Stop the background thread,
+                    // which disconnects from NATS, and discard the input until
+                    // the rest of the dataflow shuts down.
+                    writer = None;
+                    pending.clear();
+                    input.for_each(|_, _| ());
+                    return;
+                }
+            };
+
+            input.for_each(|_, rows| {
+                for (row, time, diff) in rows.iter() {
+                    pending.entry(*time).or_default().push((row.clone(), *diff));
+                }
+            });
+
+            loop {
+                let time = match pending.keys().next() {
+                    Some(&time) if !input.frontier().less_equal(&time) => time,
+                    _ => break,
+                };
+                let mut updates = pending.remove(&time).unwrap();
+                consolidate(&mut updates);
+                let messages = updates
+                    .into_iter()
+                    .map(|(row, diff)| {
+                        json!({
+                            "row": Value::Object(encoder.encode_columns(&row, &all_indices)),
+                            "timestamp": time,
+                            "diff": diff,
+                        })
+                        .to_string()
+                    })
+                    .collect();
+                writer.send(time, messages);
+            }
+
+            let mut lower = Antichain::new();
+            for time in input
+                .frontier()
+                .frontier()
+                .iter()
+                .chain(pending.keys().next())
+            {
+                lower.insert(*time);
+            }
+            if let Some(time) = writer.earliest_in_flight() {
+                lower.insert(time);
+            }
+            *frontier.borrow_mut() = lower;
+        }
+    });
+    Some(token)
+}
+
+/// Publishes `message` to `subject` and waits for JetStream to acknowledge
+/// it.
+fn publish(conn: &nats::Connection, subject: &str, message: &str) -> Result<(), failure::Error> {
+    let res = conn.request_timeout(subject, message, ACK_TIMEOUT)?;
+    let res: Value = match serde_json::from_slice(&res.data) {
+        Ok(res) => res,
+        Err(_) => bail!("subject {} is not captured by a JetStream stream", subject),
+    };
+    if let Some(err) = res.get("error") {
+        bail!("{}", err["description"].as_str().unwrap_or("unknown error"));
+    }
+    Ok(())
+}
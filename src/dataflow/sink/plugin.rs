@@ -0,0 +1,296 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Sink connectors supplied by integrators.
+//!
+//! A program that embeds `materialized` can compile in connectors to
+//! destinations that Materialize does not support natively by implementing
+//! [`SinkPluginFactory`] and calling [`register_sink_plugin`] before starting
+//! the server. Sinks that name the plugin, as in
+//! `CREATE SINK ... INTO PLUGIN 'name'`, then write through it.
+//!
+//! The contract between a sink and its plugin is described on [`SinkPlugin`].
+//! Plugins can be checked against that contract with
+//! [`check_sink_plugin`](super::check_sink_plugin).
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use differential_dataflow::consolidation::consolidate;
+use lazy_static::lazy_static;
+use log::error;
+use serde_json::{Map, Value};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::{Scope, Stream};
+use timely::progress::frontier::Antichain;
+
+use dataflow_types::{Diff, PluginSinkConnector, Timestamp};
+use expr::GlobalId;
+use interchange::json::Encoder;
+use repr::{RelationDesc, Row};
+
+use super::{SinkFrontier, SinkToken};
+
+/// The maximum number of records passed to a single call to
+/// [`SinkPlugin::write`].
+const WRITE_BATCH_SIZE: usize = 1024;
+
+lazy_static! {
+    static ref SINK_PLUGINS: RwLock<HashMap<String, Arc<dyn SinkPluginFactory>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Makes `factory` available to sinks created with `INTO PLUGIN 'name'`,
+/// replacing any plugin previously registered under `name`.
+///
+/// Plugins must be registered before the server starts, as sinks that name an
+/// unregistered plugin fail when the catalog is loaded.
+pub fn register_sink_plugin<F>(name: &str, factory: F)
+where
+    F: SinkPluginFactory + 'static,
+{
+    SINK_PLUGINS
+        .write()
+        .unwrap()
+        .insert(name.to_owned(), Arc::new(factory));
+}
+
+/// Returns the plugin registered under `name`, if any.
+pub fn sink_plugin(name: &str) -> Option<Arc<dyn SinkPluginFactory>> {
+    SINK_PLUGINS.read().unwrap().get(name).cloned()
+}
+
+/// The delivery guarantee that a [`SinkPlugin`] provides.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeliveryGuarantee {
+    /// Every update is applied to the destination at least once. Updates
+    /// written since the last successful commit may be applied again when
+    /// delivery is retried.
+    AtLeastOnce,
+    /// Every update is applied to the destination exactly once. Updates are
+    /// applied atomically when they are committed, and updates written since
+    /// the last successful commit are discarded when delivery is retried.
+    ExactlyOnce,
+}
+
+/// Constructs the [`SinkPlugin`]s that write a sink.
+pub trait SinkPluginFactory: Send + Sync {
+    /// Checks that `options`, which come from the `WITH` clause of a
+    /// `CREATE SINK` statement, are valid for this plugin.
+    ///
+    /// This is called when a sink is created and each time the catalog is
+    /// loaded, so that invalid sinks are rejected before any worker attempts
+    /// to write them.
+    fn validate(&self, options: &BTreeMap<String, String>) -> Result<(), failure::Error>;
+
+    /// Returns the delivery guarantee that this plugin's sinks provide.
+    fn guarantee(&self) -> DeliveryGuarantee;
+
+    /// Constructs the instance of the plugin that runs on worker
+    /// `worker_index` of `worker_count`.
+    ///
+    /// Each worker's instance writes a disjoint share of the sink's updates.
+    fn create(
+        &self,
+        options: &BTreeMap<String, String>,
+        worker_index: usize,
+        worker_count: usize,
+    ) -> Result<Box<dyn SinkPlugin>, failure::Error>;
+}
+
+/// An update written by a [`SinkPlugin`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SinkRecord {
+    /// The updated row, as a JSON object keyed by column name.
+    pub value: Map<String, Value>,
+    /// The change in the number of copies of the row: positive for
+    /// insertions, negative for deletions.
+    pub diff: Diff,
+}
+
+/// One worker's writer for a sink.
+///
+/// A plugin is driven by a Timely operator, so its methods should not block
+/// for long. The operator delivers updates one timestamp at a time, in
+/// increasing order of timestamp, once every update at that timestamp is
+/// known. Delivering the updates at a timestamp consists of a call to
+/// [`SinkPlugin::begin`], any number of calls to [`SinkPlugin::write`], and a
+/// call to [`SinkPlugin::commit`]. The updates at a timestamp are
+/// consolidated before delivery, so a row is written at most once per
+/// timestamp.
+///
+/// If any of these calls returns an error, the error is logged and the
+/// delivery of the timestamp is retried, from the call to `begin`, the next
+/// time the operator runs. Updates are never redelivered once their commit
+/// has succeeded. What a retry means for the destination depends on the
+/// plugin's [`DeliveryGuarantee`]:
+///
+///   * An at-least-once plugin may apply writes immediately. Updates written
+///     before a failure are then applied again when they are redelivered.
+///
+///   * An exactly-once plugin must apply the writes at a timestamp
+///     atomically when they are committed, and discard any uncommitted writes
+///     when `begin` is called. A plugin whose commits can fail after taking
+///     effect, e.g. due to a timeout, must additionally recognize and ignore
+///     redeliveries of timestamps that it has already committed, e.g. by
+///     storing the upper bound of each commit alongside the data.
+///
+/// Materialize does not yet persist sink progress, so these guarantees hold
+/// only for the lifetime of the server. After a restart, a sink's plugin is
+/// created anew, and it receives the full contents of the sink's input,
+/// as insertions, before any further changes.
+pub trait SinkPlugin {
+    /// Begins delivering the updates at `time`.
+    fn begin(&mut self, time: Timestamp) -> Result<(), failure::Error>;
+
+    /// Writes some of the updates at the time passed to the last call to
+    /// `begin`.
+    fn write(&mut self, records: &[SinkRecord]) -> Result<(), failure::Error>;
+
+    /// Commits all updates at times earlier than `upper`.
+    ///
+    /// `upper` is later than any time passed to `begin` so far. `commit` is
+    /// also called without a preceding call to `begin` when the sink's input
+    /// advances without any updates, so that the destination can record the
+    /// sink's progress.
+    fn commit(&mut self, upper: Timestamp) -> Result<(), failure::Error>;
+}
+
+/// Writes the updates in `stream` through the plugin that `connector` names.
+pub fn plugin<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    id: GlobalId,
+    connector: PluginSinkConnector,
+    relation_desc: RelationDesc,
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let encoder = match Encoder::new(&relation_desc) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            error!("unable to encode rows for sink {}: {}", id, e);
+            return None;
+        }
+    };
+    let all_indices: Vec<_> = (0..relation_desc.typ().column_types.len()).collect();
+
+    let scope = stream.scope();
+    let plugin = match sink_plugin(&connector.name) {
+        Some(factory) => factory.create(&connector.options, scope.index(), scope.peers()),
+        None => Err(failure::format_err!(
+            "sink plugin '{}' is not registered",
+            connector.name
+        )),
+    };
+    let mut plugin = match plugin {
+        Ok(plugin) => Some(plugin),
+        Err(e) => {
+            error!("unable to start sink {}: {}", id, e);
+            return None;
+        }
+    };
+
+    let mut pending: BTreeMap<Timestamp, Vec<(Row, Diff)>> = BTreeMap::new();
+    let mut committed: Timestamp = 0;
+
+    let name = format!("plugin-{}", id);
+    let token = super::activatable_sink(stream, Pipeline, name, move |_activator, shutdown| {
+        move |input| {
+            let plugin = match &mut plugin {
+                Some(plugin) if !shutdown.in_shutdown() => plugin,
+                _ => {
+                    // The sink has been dropped. Drop the plugin instance, which
+                    // releases its connection to the destination, and discard the
+                    // input until the rest of the dataflow shuts down.
+                    plugin = None;
+                    pending.clear();
+                    input.for_each(|_, _| ());
+                    return;
+                }
+            };
+
+            input.for_each(|_, rows| {
+                for (row, time, diff) in rows.iter() {
+                    pending.entry(*time).or_default().push((row.clone(), *diff));
+                }
+            });
+
+            let input_lower = input.frontier().frontier().iter().next().copied();
+
+            // Deliver each complete timestamp in its own transaction.
+            loop {
+                let time = match pending.keys().next() {
+                    Some(&time) if !input.frontier().less_equal(&time) => time,
+                    _ => break,
+                };
+                let updates = pending.get_mut(&time).unwrap();
+                consolidate(updates);
+                let records: Vec<_> = updates
+                    .iter()
+                    .map(|(row, diff)| SinkRecord {
+                        value: encoder.encode_columns(row, &all_indices),
+                        diff: *diff,
+                    })
+                    .collect();
+                let upper = pending
+                    .keys()
+                    .nth(1)
+                    .copied()
+                    .into_iter()
+                    .chain(input_lower)
+                    .min()
+                    .unwrap_or(time + 1);
+                match deliver(&mut **plugin, time, &records, upper) {
+                    Ok(()) => {
+                        pending.remove(&time);
+                        committed = upper;
+                    }
+                    Err(e) => {
+                        error!("sink {} failed to deliver updates: {}", id, e);
+                        break;
+                    }
+                }
+            }
+
+            // Record the sink's progress, even if it has no updates to deliver.
+            if pending.is_empty() {
+                if let Some(lower) = input_lower {
+                    if lower > committed {
+                        match plugin.commit(lower) {
+                            Ok(()) => committed = lower,
+                            Err(e) => error!("sink {} failed to commit: {}", id, e),
+                        }
+                    }
+                }
+            }
+
+            *frontier.borrow_mut() = if pending.is_empty() && input_lower.is_none() {
+                Antichain::new()
+            } else {
+                Antichain::from_elem(committed)
+            };
+        }
+    });
+    Some(token)
+}
+
+fn deliver(
+    plugin: &mut dyn SinkPlugin,
+    time: Timestamp,
+    records: &[SinkRecord],
+    upper: Timestamp,
+) -> Result<(), failure::Error> {
+    plugin.begin(time)?;
+    for chunk in records.chunks(WRITE_BATCH_SIZE) {
+        plugin.write(chunk)?;
+    }
+    plugin.commit(upper)
+}
@@ -0,0 +1,165 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use log::error;
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::{Scope, Stream};
+
+use dataflow_types::{Diff, RedisSinkConnector, RedisValueFormat, Timestamp};
+use expr::GlobalId;
+use interchange::json::Encoder;
+use repr::{RelationDesc, Row};
+
+use super::upsert::{self, Change, UpsertBuffer};
+use super::writer::SinkWriter;
+use super::{SinkFrontier, SinkToken};
+
+/// Maintains one Redis key per key of the input.
+///
+/// Each time the input frontier passes a timestamp, the net changes at that
+/// timestamp are applied in a single `MULTI`/`EXEC` transaction, so readers
+/// never observe a partially applied timestamp. Transactions are run on a
+/// background thread, in order of timestamp; if Redis is unavailable, the
+/// pending transaction is retried until it succeeds.
+pub fn redis<G>(
+    stream: &Stream<G, (Row, Timestamp, Diff)>,
+    id: GlobalId,
+    connector: RedisSinkConnector,
+    relation_desc: RelationDesc,
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    let encoder = match Encoder::new(&relation_desc) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            error!("unable to encode rows for redis sink {}: {}", id, e);
+            return None;
+        }
+    };
+    let value_indices: Vec<_> = (0..relation_desc.typ().column_types.len())
+        .filter(|i| !connector.key_indices.contains(i))
+        .collect();
+
+    let key_indices = connector.key_indices.clone();
+    let exchange = Exchange::new(move |(row, _time, _diff): &(Row, Timestamp, Diff)| {
+        upsert::key_hash(row, &key_indices)
+    });
+    let mut buffer = UpsertBuffer::new(connector.key_indices.clone());
+    let name = format!("redis-{}", id);
+    let writer_name = name.clone();
+
+    let token = super::activatable_sink(stream, exchange, name, move |activator, shutdown| {
+        let url = connector.url.clone();
+        let mut conn: Option<redis::Connection> = None;
+        let mut writer = Some(SinkWriter::spawn(
+            writer_name,
+            activator,
+            move |pipe: &mut redis::Pipeline| {
+                if conn.is_none() {
+                    let client = redis::Client::open(url.as_str())?;
+                    conn = Some(client.get_connection()?);
+                }
+                if let Err(e) = pipe.query::<()>(conn.as_mut().unwrap()) {
+                    conn = None;
+                    return Err(e.into());
+                }
+                Ok(())
+            },
+        ));
+
+        move |input| {
+            let writer = match &mut writer {
+                Some(writer) if !shutdown.in_shutdown() => writer,
+                _ => {
+                    // The sink has been dropped. Stop the background thread,
+                    // which disconnects from Redis, and discard the input
+                    // until the rest of the dataflow shuts down.
+                    writer = None;
+                    buffer = UpsertBuffer::new(connector.key_indices.clone());
+                    input.for_each(|_, _| ());
+                    return;
+                }
+            };
+
+            input.for_each(|_, rows| {
+                for (row, time, diff) in rows.iter() {
+                    buffer.push(row.clone(), *time, *diff);
+                }
+            });
+
+            while let Some((time, changes)) = buffer.peek(input.frontier()) {
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+                for change in &changes {
+                    add_change(&mut pipe, &connector, &encoder, &value_indices, change);
+                }
+                writer.send(time, pipe);
+                buffer.pop(time);
+            }
+
+            let mut applied = buffer.applied_frontier(input.frontier());
+            if let Some(time) = writer.earliest_in_flight() {
+                applied.insert(time);
+            }
+            *frontier.borrow_mut() = applied;
+        }
+    });
+    Some(token)
+}
+
+fn add_change(
+    pipe: &mut redis::Pipeline,
+    connector: &RedisSinkConnector,
+    encoder: &Encoder,
+    value_indices: &[usize],
+    change: &Change,
+) {
+    let row = match change {
+        Change::Upsert(row) | Change::Delete(row) => row,
+    };
+    let key = format!(
+        "{}{}",
+        connector.key_prefix,
+        upsert::key_string(encoder, &connector.key_indices, row)
+    );
+
+    pipe.cmd("DEL").arg(&key).ignore();
+    if let Change::Upsert(row) = change {
+        match connector.value_format {
+            RedisValueFormat::Json => {
+                let value = encoder.encode_columns(row, value_indices);
+                pipe.cmd("SET")
+                    .arg(&key)
+                    .arg(serde_json::Value::Object(value).to_string())
+                    .ignore();
+            }
+            RedisValueFormat::Hash => {
+                // Redis has no representation for a NULL field, so NULL
+                // values are omitted from the hash. A row whose values are
+                // all NULL leaves no hash behind at all.
+                let mut cmd = redis::cmd("HSET");
+                cmd.arg(&key);
+                let mut nfields = 0;
+                for (name, value) in encoder.encode_columns(row, value_indices) {
+                    match value {
+                        serde_json::Value::Null => continue,
+                        serde_json::Value::String(s) => cmd.arg(name).arg(s),
+                        value => cmd.arg(name).arg(value.to_string()),
+                    };
+                    nfields += 1;
+                }
+                if nfields > 0 {
+                    pipe.add_command(cmd).ignore();
+                }
+            }
+        }
+    }
+}
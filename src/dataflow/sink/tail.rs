@@ -7,44 +7,114 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::VecDeque;
+use std::task::{Context, Poll};
+
 use timely::dataflow::channels::pact::Pipeline;
-use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
 use timely::order::PartialOrder;
 
 use futures::executor::block_on;
-use futures::sink::SinkExt;
+use futures::sink::Sink;
 
 use dataflow_types::{Diff, TailSinkConnector, Timestamp, Update};
 use expr::GlobalId;
 use repr::Row;
 
+use super::{SinkFrontier, SinkToken};
+
+/// Sends the updates to a view to the client that tails it.
+///
+/// The sink never waits on the client. Batches of updates that the client is
+/// not yet ready to accept are held in the operator, which is rescheduled once
+/// the client is ready for more. The sink's frontier does not advance past the
+/// updates that it holds.
 pub fn tail<G>(
     stream: &Stream<G, (Row, Timestamp, Diff)>,
     id: GlobalId,
     connector: TailSinkConnector,
-) where
+    frontier: SinkFrontier,
+) -> Option<SinkToken>
+where
     G: Scope<Timestamp = Timestamp>,
 {
-    let mut tx = block_on(connector.tx.connect()).expect("tail transmitter failed");
-    stream.sink(Pipeline, &format!("tail-{}", id), move |input| {
-        input.for_each(|_, rows| {
-            let mut results: Vec<Update> = Vec::new();
-            for (row, time, diff) in rows.iter() {
-                if connector.since.less_than(time) {
-                    results.push(Update {
-                        row: row.clone(),
-                        timestamp: *time,
-                        diff: *diff,
-                    });
+    let mut tx = Some(block_on(connector.tx.connect()).expect("tail transmitter failed"));
+    let mut client_listening = true;
+    let mut pending: VecDeque<Vec<Update>> = VecDeque::new();
+    let name = format!("tail-{}", id);
+    let token = super::activatable_sink(stream, Pipeline, name, move |activator, shutdown| {
+        let waker = super::activator_waker(activator);
+        move |input| {
+            let tx = match &mut tx {
+                Some(tx) if !shutdown.in_shutdown() => tx,
+                _ => {
+                    // The sink has been dropped. Hang up on the client, so
+                    // that it observes the end of the stream, and discard the
+                    // input until the rest of the dataflow shuts down.
+                    tx = None;
+                    pending.clear();
+                    input.for_each(|_, _| ());
+                    return;
+                }
+            };
+            input.for_each(|_, rows| {
+                if !client_listening {
+                    // The client has gone away, or has stopped reading
+                    // because it fell too far behind. The dataflow will be
+                    // dropped shortly; until then, discard the updates.
+                    return;
+                }
+                let mut results: Vec<Update> = Vec::new();
+                for (row, time, diff) in rows.iter() {
+                    if connector.since.less_than(time) {
+                        results.push(Update {
+                            row: row.clone(),
+                            timestamp: *time,
+                            diff: *diff,
+                        });
+                    }
+                }
+                pending.push_back(results);
+            });
+
+            // Send as many batches as the client is ready to accept. If it is
+            // not ready for the rest, the waker reschedules the operator once
+            // it is.
+            let mut cx = Context::from_waker(&waker);
+            let mut result = Ok(());
+            while client_listening && !pending.is_empty() {
+                match tx.as_mut().poll_ready(&mut cx) {
+                    Poll::Ready(Ok(())) => {
+                        let results = pending.pop_front().unwrap();
+                        result = tx.as_mut().start_send(results);
+                    }
+                    Poll::Ready(Err(err)) => result = Err(err),
+                    Poll::Pending => break,
+                }
+                if result.is_err() {
+                    break;
+                }
+            }
+            if result.is_ok() && client_listening {
+                if let Poll::Ready(Err(err)) = tx.as_mut().poll_flush(&mut cx) {
+                    result = Err(err);
                 }
             }
+            if let Err(err) = result {
+                log::warn!("tail {}: client stopped listening: {}", id, err);
+                client_listening = false;
+                pending.clear();
+            }
 
-            // TODO(benesch): this blocks the Timely thread until the send
-            // completes. Hopefully it's just a quick write to a kernel buffer,
-            // but perhaps not if the batch gets too large? We may need to do
-            // something smarter, like offloading to a networking thread.
-            block_on(tx.send(results)).expect("tail send failed");
-        });
-    })
+            super::advance_to_input(&frontier, input.frontier());
+            let earliest_pending = pending
+                .iter()
+                .flat_map(|results| results.iter().map(|update| update.timestamp))
+                .min();
+            if let Some(time) = earliest_pending {
+                frontier.borrow_mut().insert(time);
+            }
+        }
+    });
+    Some(token)
 }
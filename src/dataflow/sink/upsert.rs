@@ -0,0 +1,143 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Support for sinks that maintain keyed state in an external system.
+//!
+//! Such sinks cannot apply a stream of differential updates directly, since
+//! a retraction and an insertion of a row with the same key are only
+//! meaningful together. Instead, they buffer updates until their timestamp is
+//! complete, then apply the net change to each key: an upsert of the key's new
+//! row, or a delete if the key no longer has a row.
+
+use std::collections::{BTreeMap, HashMap};
+
+use differential_dataflow::Hashable;
+use log::warn;
+use timely::progress::frontier::{Antichain, MutableAntichain};
+
+use dataflow_types::{Diff, Timestamp};
+use interchange::json::Encoder;
+use repr::{Datum, Row};
+
+/// The net change to one key at one timestamp.
+#[derive(Debug)]
+pub enum Change {
+    /// The key's row is now the specified row.
+    Upsert(Row),
+    /// The key no longer has a row. The specified row is the retracted row,
+    /// from which the key can be recovered.
+    Delete(Row),
+}
+
+/// Hashes the key columns of `row`, for use in exchanging updates so that all
+/// updates for a key are applied by the same worker.
+pub fn key_hash(row: &Row, key_indices: &[usize]) -> u64 {
+    let datums = row.unpack();
+    key_indices
+        .iter()
+        .map(|&i| datums[i])
+        .collect::<Vec<Datum>>()
+        .hashed()
+}
+
+/// Renders the key columns of `row` as a string, separating the values of
+/// multiple key columns with `:`.
+pub fn key_string(encoder: &Encoder, key_indices: &[usize], row: &Row) -> String {
+    let datums = row.unpack();
+    let mut key = String::new();
+    for (n, &i) in key_indices.iter().enumerate() {
+        if n > 0 {
+            key.push(':');
+        }
+        match encoder.encode_datum(i, datums[i]) {
+            serde_json::Value::String(s) => key.push_str(&s),
+            value => key.push_str(&value.to_string()),
+        }
+    }
+    key
+}
+
+/// Buffers updates until their timestamps are complete.
+#[derive(Debug)]
+pub struct UpsertBuffer {
+    key_indices: Vec<usize>,
+    pending: BTreeMap<Timestamp, Vec<(Row, Diff)>>,
+}
+
+impl UpsertBuffer {
+    pub fn new(key_indices: Vec<usize>) -> UpsertBuffer {
+        UpsertBuffer {
+            key_indices,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Row, time: Timestamp, diff: Diff) {
+        self.pending.entry(time).or_default().push((row, diff));
+    }
+
+    /// Returns the earliest buffered timestamp that `frontier` has passed, if
+    /// any, along with the net changes at that timestamp.
+    ///
+    /// The changes remain buffered until [`UpsertBuffer::pop`] is called, so
+    /// that sinks can retry applying them if the external system is
+    /// unavailable.
+    pub fn peek(&self, frontier: &MutableAntichain<Timestamp>) -> Option<(Timestamp, Vec<Change>)> {
+        let (time, updates) = self.pending.iter().next()?;
+        if frontier.less_equal(time) {
+            return None;
+        }
+        Some((*time, self.changes(updates)))
+    }
+
+    /// Discards the buffered updates at `time`.
+    pub fn pop(&mut self, time: Timestamp) {
+        self.pending.remove(&time);
+    }
+
+    /// Returns the frontier up to which all updates have been applied, given
+    /// the frontier of the sink's input: the earlier of the earliest buffered
+    /// timestamp and the input frontier.
+    pub fn applied_frontier(&self, frontier: &MutableAntichain<Timestamp>) -> Antichain<Timestamp> {
+        let mut applied = Antichain::new();
+        for time in frontier.frontier().iter() {
+            applied.insert(*time);
+        }
+        if let Some(time) = self.pending.keys().next() {
+            applied.insert(*time);
+        }
+        applied
+    }
+
+    fn changes(&self, updates: &[(Row, Diff)]) -> Vec<Change> {
+        let mut consolidated = HashMap::new();
+        for (row, diff) in updates {
+            *consolidated.entry(row).or_insert(0) += diff;
+        }
+        let mut by_key: HashMap<Vec<Datum>, Change> = HashMap::new();
+        for (row, diff) in consolidated {
+            if diff == 0 {
+                continue;
+            }
+            let datums = row.unpack();
+            let key = self.key_indices.iter().map(|&i| datums[i]).collect();
+            if diff > 0 {
+                if let Some(Change::Upsert(_)) = by_key.get(&key) {
+                    warn!("multiple rows for one key in keyed sink; keeping one arbitrarily");
+                }
+                by_key.insert(key, Change::Upsert(row.clone()));
+            } else {
+                by_key
+                    .entry(key)
+                    .or_insert_with(|| Change::Delete(row.clone()));
+            }
+        }
+        by_key.into_iter().map(|(_key, change)| change).collect()
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::cmp;
+use std::collections::BTreeSet;
+use std::sync::mpsc;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+use log::error;
+use timely::scheduling::SyncActivator;
+
+use dataflow_types::Timestamp;
+
+/// The delay before the first retry of a batch that failed to write. The delay
+/// doubles with each consecutive failure, up to `MAX_BACKOFF`.
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Writes the batches of changes that a sink operator hands it to an external
+/// system on a background thread, so that the operator, and every other
+/// dataflow on its worker, never waits on the external system.
+///
+/// Batches are written one at a time, in the order in which they are sent. A
+/// batch that fails to write is retried, with exponential backoff, until it is
+/// written or the writer is dropped. Each time a batch is written, the sink
+/// operator is activated, so that it can advance its frontier.
+pub struct SinkWriter<B> {
+    tx: mpsc::Sender<(Timestamp, B)>,
+    written_rx: mpsc::Receiver<Timestamp>,
+    /// The timestamps of the batches that have been sent but not yet written.
+    in_flight: BTreeSet<Timestamp>,
+    /// The background thread exits once it notices that this token, which
+    /// lives as long as the writer, has been dropped.
+    _alive: Arc<()>,
+}
+
+impl<B> SinkWriter<B>
+where
+    B: Send + 'static,
+{
+    /// Spawns the background thread for the sink named `name`, which writes
+    /// each batch with `write` and then activates the sink operator through
+    /// `activator`.
+    ///
+    /// If `write` fails after writing part of a batch, it may remove that part
+    /// from the batch, so that only the remainder is retried.
+    pub fn spawn<W>(name: String, activator: SyncActivator, write: W) -> SinkWriter<B>
+    where
+        W: FnMut(&mut B) -> Result<(), failure::Error> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let (written_tx, written_rx) = mpsc::channel();
+        let alive = Arc::new(());
+        let weak = Arc::downgrade(&alive);
+        thread::spawn(move || run(name, rx, written_tx, activator, weak, write));
+        SinkWriter {
+            tx,
+            written_rx,
+            in_flight: BTreeSet::new(),
+            _alive: alive,
+        }
+    }
+
+    /// Queues `batch`, the changes at `time`, to be written.
+    pub fn send(&mut self, time: Timestamp, batch: B) {
+        self.in_flight.insert(time);
+        // The background thread only exits before the writer is dropped if
+        // `write` panics, in which case the batch stays in flight forever and
+        // the sink stops advancing.
+        let _ = self.tx.send((time, batch));
+    }
+
+    /// Returns the earliest timestamp whose batch has been sent but not yet
+    /// written, if any.
+    pub fn earliest_in_flight(&mut self) -> Option<Timestamp> {
+        while let Ok(time) = self.written_rx.try_recv() {
+            self.in_flight.remove(&time);
+        }
+        self.in_flight.iter().next().copied()
+    }
+}
+
+fn run<B, W>(
+    name: String,
+    rx: mpsc::Receiver<(Timestamp, B)>,
+    written_tx: mpsc::Sender<Timestamp>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+    mut write: W,
+) where
+    W: FnMut(&mut B) -> Result<(), failure::Error>,
+{
+    for (time, mut batch) in rx {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            if alive.upgrade().is_none() {
+                return;
+            }
+            match write(&mut batch) {
+                Ok(()) => break,
+                Err(e) => {
+                    error!(
+                        "{} failed to write changes at {}: {}; retrying in {:?}",
+                        name, time, e, backoff
+                    );
+                    thread::sleep(backoff);
+                    backoff = cmp::min(backoff * 2, MAX_BACKOFF);
+                }
+            }
+        }
+        if written_tx.send(time).is_err() || activator.activate().is_err() {
+            // The sink has been dropped.
+            return;
+        }
+    }
+}
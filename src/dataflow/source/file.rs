@@ -19,6 +19,7 @@ use {
     tokio::task,
 };
 
+use async_compression::tokio_02::bufread::{BzDecoder, GzipDecoder};
 use expr::SourceInstanceId;
 use futures::ready;
 use futures::sink::SinkExt;
@@ -27,10 +28,10 @@ use log::{error, warn};
 use timely::dataflow::Scope;
 use timely::scheduling::SyncActivator;
 use tokio::fs::File;
-use tokio::io::{self, AsyncRead};
+use tokio::io::{self, AsyncRead, BufReader};
 use tokio_util::codec::{FramedRead, LinesCodec};
 
-use dataflow_types::Timestamp;
+use dataflow_types::{Compression, Timestamp};
 
 use crate::source::util::source;
 use crate::source::{SourceStatus, SourceToken};
@@ -126,11 +127,38 @@ async fn send_lines<R>(
     }
 }
 
+/// Sends the lines of `reader`, after undoing `compression`.
+async fn send_decompressed_lines<R>(
+    reader: R,
+    compression: Compression,
+    tx: futures::channel::mpsc::Sender<String>,
+    activator: Arc<Mutex<SyncActivator>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    match compression {
+        Compression::Gzip => {
+            let mut decoder = GzipDecoder::new(BufReader::new(reader));
+            // Files that were appended to with `gzip` are a series of gzip
+            // members, which should be read as one.
+            decoder.multiple_members(true);
+            send_lines(decoder, tx, activator).await
+        }
+        Compression::Bzip2 => {
+            let mut decoder = BzDecoder::new(BufReader::new(reader));
+            decoder.multiple_members(true);
+            send_lines(decoder, tx, activator).await
+        }
+        Compression::None => send_lines(reader, tx, activator).await,
+    }
+}
+
 async fn read_file_task(
     path: PathBuf,
     tx: futures::channel::mpsc::Sender<String>,
     activator: Arc<Mutex<SyncActivator>>,
     read_style: FileReadStyle,
+    compression: Compression,
 ) {
     let file = match File::open(&path).await {
         Ok(file) => file,
@@ -145,7 +173,7 @@ async fn read_file_task(
     };
     match read_style {
         FileReadStyle::None => unreachable!(),
-        FileReadStyle::ReadOnce => send_lines(file, tx, activator).await,
+        FileReadStyle::ReadOnce => send_decompressed_lines(file, compression, tx, activator).await,
         FileReadStyle::TailFollowFd => {
             // FSEvents doesn't raise events until you close the file, making it
             // useless for tailing log files that are kept open by the daemon
@@ -196,7 +224,7 @@ async fn read_file_task(
                 inner: file,
                 _w: watcher,
             };
-            send_lines(file, tx, activator).await
+            send_decompressed_lines(file, compression, tx, activator).await
         }
     }
 }
@@ -208,8 +236,9 @@ pub fn file<G>(
     path: PathBuf,
     executor: &tokio::runtime::Handle,
     read_style: FileReadStyle,
+    compression: Compression,
 ) -> (
-    timely::dataflow::Stream<G, (Vec<u8>, Option<i64>)>,
+    timely::dataflow::Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
     Option<SourceToken>,
 )
 where
@@ -224,7 +253,7 @@ where
         let (tx, mut rx) = futures::channel::mpsc::channel(MAX_LINES_PER_INVOCATION);
         if read_file {
             let activator = Arc::new(Mutex::new(region.sync_activator_for(&info.address[..])));
-            executor.spawn(read_file_task(path, tx, activator, read_style));
+            executor.spawn(read_file_task(path, tx, activator, read_style, compression));
         }
         let mut total_lines_read = 0;
         move |cap, output| {
@@ -281,7 +310,9 @@ where
                     lines_read += 1;
                     total_lines_read += 1;
                     match line {
-                        Some(line) => session.give((line.into_bytes(), Some(total_lines_read))),
+                        Some(line) => {
+                            session.give((line.into_bytes(), Some(total_lines_read), None))
+                        }
                         None => return SourceStatus::Done,
                     }
                 } else {
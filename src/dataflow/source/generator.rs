@@ -0,0 +1,156 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Source plugins that generate their records, so that Materialize can be
+//! tried out without an external system to read from.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::bail;
+
+use dataflow_types::Timestamp;
+
+use super::plugin::{SourcePlugin, SourcePluginFactory, SourceRecord, SourceWaker};
+
+/// The number of events per second that the auction generator produces if
+/// the source does not specify a `rate`.
+const DEFAULT_RATE: u64 = 10;
+
+/// One in this many events opens an auction; the others are bids.
+const EVENTS_PER_AUCTION: u64 = 5;
+
+/// The number of users that sell and bid.
+const USERS: u64 = 100;
+
+const ITEMS: &[&str] = &[
+    "Best Pizza in Town",
+    "City Bar Crawl",
+    "Custom Art",
+    "Gift Basket",
+    "Signed Jersey",
+];
+
+/// Generates the events of a fictional auction house.
+///
+/// Each record is a line of CSV with five columns: the kind of event, either
+/// `auction` or `bid`; the ID of the event; the ID of the auction; the user
+/// that sells the item or places the bid; and the item up for auction or the
+/// amount bid.
+///
+/// The events are determined by their position in the sequence, but their
+/// times are the times at which they are generated, `rate` per second.
+#[derive(Debug)]
+pub struct AuctionFactory;
+
+impl SourcePluginFactory for AuctionFactory {
+    fn validate(&self, options: &BTreeMap<String, String>) -> Result<(), failure::Error> {
+        for (name, value) in options {
+            match name.as_str() {
+                "rate" => match value.parse::<u64>() {
+                    Ok(rate) if rate > 0 => (),
+                    _ => bail!("rate must be a positive integer"),
+                },
+                _ => bail!("unexpected parameter for auction generator: {}", name),
+            }
+        }
+        Ok(())
+    }
+
+    fn create(
+        &self,
+        options: &BTreeMap<String, String>,
+        worker_index: usize,
+        _worker_count: usize,
+    ) -> Result<Option<Box<dyn SourcePlugin>>, failure::Error> {
+        // The events form a single sequence, so one worker generates them all.
+        if worker_index != 0 {
+            return Ok(None);
+        }
+        let rate = match options.get("rate") {
+            Some(rate) => rate.parse()?,
+            None => DEFAULT_RATE,
+        };
+        Ok(Some(Box::new(AuctionGenerator {
+            rate,
+            start: now_ms(),
+            next: 0,
+        })))
+    }
+}
+
+struct AuctionGenerator {
+    rate: u64,
+    /// The time at which the first event occurred.
+    start: Timestamp,
+    /// The position of the next event in the sequence.
+    next: u64,
+}
+
+impl AuctionGenerator {
+    fn time_of(&self, n: u64) -> Timestamp {
+        self.start + n * 1000 / self.rate
+    }
+}
+
+impl SourcePlugin for AuctionGenerator {
+    fn start(&mut self, waker: SourceWaker) -> Result<(), failure::Error> {
+        waker.wake();
+        Ok(())
+    }
+
+    fn poll_batch(&mut self) -> Result<Option<Vec<SourceRecord>>, failure::Error> {
+        let now = now_ms();
+        let mut records = vec![];
+        while self.time_of(self.next) <= now {
+            records.push(SourceRecord {
+                data: auction_event(self.next).into_bytes(),
+                position: Some(self.next as i64),
+                time: self.time_of(self.next),
+            });
+            self.next += 1;
+        }
+        Ok(Some(records))
+    }
+
+    fn watermark(&self) -> Timestamp {
+        self.time_of(self.next)
+    }
+}
+
+/// Renders the `n`th event of the auction house.
+fn auction_event(n: u64) -> String {
+    let r = mix(n);
+    let user = r % USERS;
+    let auction = n / EVENTS_PER_AUCTION;
+    if n % EVENTS_PER_AUCTION == 0 {
+        let item = ITEMS[(r >> 32) as usize % ITEMS.len()];
+        format!("auction,{},{},user{},{}", auction, auction, user, item)
+    } else {
+        // Bid on the newest auction or one of the two before it.
+        let auction = auction.saturating_sub((r >> 32) % 3);
+        let amount = 1 + (r >> 40) % 1000;
+        format!("bid,{},{},user{},{}", n, auction, user, amount)
+    }
+}
+
+/// Scrambles `n`, using the finalizer of SplitMix64.
+fn mix(n: u64) -> u64 {
+    let mut z = n.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn now_ms() -> Timestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before Unix epoch")
+        .as_millis() as Timestamp
+}
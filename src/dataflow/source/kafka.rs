@@ -7,15 +7,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cmp;
+use std::collections::HashMap;
 use std::sync::Mutex;
 use std::time::Duration;
 
 use crate::server::{TimestampChanges, TimestampHistories};
 use dataflow_types::{Consistency, KafkaSourceConnector, Timestamp};
+use differential_dataflow::hashable::Hashable;
 use lazy_static::lazy_static;
 use log::{error, warn};
 use prometheus::{register_int_counter, IntCounter};
 use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerContext};
+use rdkafka::error::KafkaResult;
+use rdkafka::topic_partition_list::{Offset, TopicPartitionList};
 use rdkafka::{ClientConfig, ClientContext};
 use rdkafka::{Message, Timestamp as KafkaTimestamp};
 use timely::dataflow::operators::Capability;
@@ -35,6 +40,20 @@ lazy_static! {
     .unwrap();
 }
 
+/// Constructs a source that reads the Kafka topic that `connector` describes.
+///
+/// The partitions of the topic are distributed across the workers: partition
+/// `p` is read by worker `(h + p) % worker_peers`, where `h` is derived from
+/// the source's ID, so that the first partitions of different sources are
+/// read by different workers. Workers that read no partitions are done at
+/// once. The partitions are discovered when the source is first scheduled;
+/// partitions added to the topic later are not read.
+///
+/// If `advance_timestamp` is set, each message is assigned the timestamp that
+/// the timestamper bound to its offset in its partition, and each worker
+/// advances its capability once it has read all the messages of each of its
+/// partitions at a timestamp. Otherwise, messages are assigned their Kafka
+/// timestamps.
 #[allow(clippy::too_many_arguments)]
 pub fn kafka<G>(
     scope: &G,
@@ -45,8 +64,12 @@ pub fn kafka<G>(
     timestamp_histories: TimestampHistories,
     timestamp_tx: TimestampChanges,
     consistency: Consistency,
-    read_kafka: bool,
-) -> (Stream<G, (Vec<u8>, Option<i64>)>, Option<SourceToken>)
+    worker_index: usize,
+    worker_peers: usize,
+) -> (
+    Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    Option<SourceToken>,
+)
 where
     G: Scope<Timestamp = Timestamp>,
 {
@@ -54,27 +77,33 @@ where
         url,
         topic,
         ssl_certificate_file,
+        start_offset,
+        group_id,
+        config_options,
     } = connector.clone();
 
-    let ts = if read_kafka {
-        let prev = timestamp_histories.borrow_mut().insert(id.clone(), vec![]);
-        assert!(prev.is_none());
-        timestamp_tx
-            .as_ref()
-            .borrow_mut()
-            .push((id, Some((connector, consistency))));
-        Some(timestamp_tx)
-    } else {
-        None
-    };
-
-    let (stream, capability) = source(id, ts, scope, &name.clone(), move |info| {
+    // Every worker may read some of the topic's partitions, so every worker
+    // tracks the timestamp bindings of the source.
+    let prev = timestamp_histories
+        .borrow_mut()
+        .insert(id.clone(), HashMap::new());
+    assert!(prev.is_none());
+    timestamp_tx
+        .as_ref()
+        .borrow_mut()
+        .push((id, Some((connector, consistency))));
+    let first_worker = (id.hashed() as usize) % worker_peers;
+
+    let (stream, capability) = source(id, Some(timestamp_tx), scope, &name.clone(), move |info| {
         let activator = scope.activator_for(&info.address[..]);
 
         let mut config = ClientConfig::new();
         config
             .set("auto.offset.reset", "smallest")
-            .set("group.id", &format!("materialize-{}", name))
+            .set(
+                "group.id",
+                &group_id.unwrap_or_else(|| format!("materialize-{}", name)),
+            )
             .set("enable.auto.commit", "false")
             .set("enable.partition.eof", "false")
             .set("auto.offset.reset", "earliest")
@@ -94,195 +123,286 @@ where
                     .expect("Converting ssl certificate file path failed"),
             );
         }
-
-        let mut consumer: Option<BaseConsumer<GlueConsumerContext>> = if read_kafka {
-            let cx = GlueConsumerContext(Mutex::new(scope.sync_activator_for(&info.address[..])));
-            Some(
-                config
-                    .create_with_context(cx)
-                    .expect("Failed to create Kafka Consumer"),
-            )
-        } else {
-            None
-        };
-
-        if let Some(consumer) = consumer.as_mut() {
-            consumer.subscribe(&[&topic]).unwrap();
+        for (name, value) in &config_options {
+            config.set(name, value);
         }
 
+        let cx = GlueConsumerContext(Mutex::new(scope.sync_activator_for(&info.address[..])));
+        let consumer: BaseConsumer<GlueConsumerContext> = config
+            .create_with_context(cx)
+            .expect("Failed to create Kafka Consumer");
+
         // Buffer place older for buffering messages for which we did not have a timestamp
         let mut buffer: Option<OwnedMessage> = None;
-        // Index of the last offset that we have already processed
-        let mut last_processed_offset: i64 = -1;
+        // The progress of this worker through each of the partitions that it
+        // reads, which is empty until the partitions have been assigned.
+        let mut progress: HashMap<i32, PartitionProgress> = HashMap::new();
 
         move |cap, output| {
+            if progress.is_empty() {
+                let assigned = assign_partitions(
+                    &consumer,
+                    &topic,
+                    start_offset,
+                    first_worker,
+                    worker_index,
+                    worker_peers,
+                );
+                match assigned {
+                    Ok(Some(partitions)) if partitions.is_empty() => {
+                        // Another worker reads every partition, so this
+                        // worker will never produce data, nor need the
+                        // source's timestamp bindings.
+                        timestamp_histories.borrow_mut().remove(&id);
+                        return SourceStatus::Done;
+                    }
+                    Ok(Some(partitions)) => {
+                        for partition in partitions {
+                            progress.insert(
+                                partition,
+                                PartitionProgress {
+                                    last_offset: start_offset.map_or(-1, |offset| offset - 1),
+                                    closed: None,
+                                },
+                            );
+                        }
+                    }
+                    Ok(None) => {
+                        // The topic does not exist yet.
+                        activator.activate_after(Duration::from_secs(1));
+                        return SourceStatus::Alive;
+                    }
+                    Err(err) => {
+                        error!(
+                            "kafka error: {}: unable to assign partitions: {}",
+                            name, err
+                        );
+                        activator.activate_after(Duration::from_secs(1));
+                        return SourceStatus::Alive;
+                    }
+                }
+            }
+
+            // Repeatedly interrogate Kafka for messages. Cease when
+            // Kafka stops returning new data, or after 10 milliseconds.
+            let timer = std::time::Instant::now();
+
             if advance_timestamp {
-                if let Some(consumer) = consumer.as_mut() {
-                    // Repeatedly interrogate Kafka for messages. Cease when
-                    // Kafka stops returning new data, or after 10 milliseconds.
-                    let timer = std::time::Instant::now();
-
-                    // Check if the capability can be downgraded (this is independent of whether
-                    // there are new messages that can be processed)
-                    downgrade_capability(&id, cap, last_processed_offset, &timestamp_histories);
-
-                    // Check if there was a message buffered and if we can now process it
-                    // If we can now process it, clear the buffer and proceed to poll from
-                    // consumer. Else, exit the function
-                    let mut next_message = if let Some(message) = buffer.take() {
-                        Some(message)
-                    } else {
-                        // No currently buffered message, poll from stream
-                        match consumer.poll(Duration::from_millis(0)) {
-                            Some(Ok(msg)) => Some(msg.detach()),
-                            Some(Err(err)) => {
-                                error!("kafka error: {}: {}", name, err);
-                                None
-                            }
-                            _ => None,
+                // Check if the capability can be downgraded (this is independent of whether
+                // there are new messages that can be processed)
+                downgrade_capability(&id, cap, &mut progress, &timestamp_histories);
+
+                // Check if there was a message buffered and if we can now process it
+                // If we can now process it, clear the buffer and proceed to poll from
+                // consumer. Else, exit the function
+                let mut next_message = match buffer.take() {
+                    Some(message) => Some(message),
+                    // No currently buffered message, poll from stream
+                    None => poll(&consumer, &name),
+                };
+
+                while let Some(message) = next_message {
+                    let partition = message.partition();
+                    let offset = message.offset();
+                    let last_offset = match progress.get(&partition) {
+                        Some(progress) => progress.last_offset,
+                        None => {
+                            error!("{}: message from unassigned partition {}", name, partition);
+                            next_message = poll(&consumer, &name);
+                            continue;
                         }
                     };
 
-                    while let Some(message) = next_message {
-                        let payload = message.payload();
-
-                        let offset = message.offset();
-                        let ts = find_matching_timestamp(&id, offset, &timestamp_histories);
+                    if offset <= last_offset {
+                        error!("duplicate Kakfa message received");
+                        activator.activate();
+                        return SourceStatus::Alive;
+                    }
 
-                        if offset <= last_processed_offset {
-                            error!("duplicate Kakfa message received");
-                            activator.activate();
+                    match find_matching_timestamp(&id, partition, offset, &timestamp_histories) {
+                        None => {
+                            // We have not yet decided on a timestamp for this message,
+                            // we need to buffer the message. There is no need to
+                            // reschedule ourselves: the worker activates the source
+                            // when it receives the next timestamp binding.
+                            buffer = Some(message);
                             return SourceStatus::Alive;
                         }
-
-                        match ts {
-                            None => {
-                                // We have not yet decided on a timestamp for this message,
-                                // we need to buffer the message
-                                buffer = Some(message);
-                                activator.activate();
-                                return SourceStatus::Alive;
+                        Some(ts) => {
+                            if let Some(progress) = progress.get_mut(&partition) {
+                                progress.last_offset = offset;
                             }
-                            Some(_) => {
-                                last_processed_offset = offset;
 
-                                if let Some(payload) = payload {
-                                    let out = payload.to_vec();
-                                    BYTES_READ_COUNTER.inc_by(out.len() as i64);
-                                    output.session(&cap).give((out, Some(message.offset())));
-                                }
-
-                                downgrade_capability(
-                                    &id,
-                                    cap,
-                                    last_processed_offset,
-                                    &timestamp_histories,
-                                );
+                            if let Some(payload) = message.payload() {
+                                let out = payload.to_vec();
+                                BYTES_READ_COUNTER.inc_by(out.len() as i64);
+                                // The capability may already be past the
+                                // timestamp if the bindings were replayed
+                                // from before a restart.
+                                let ts = cmp::max(ts, *cap.time());
+                                output.session(&cap.delayed(&ts)).give((
+                                    out,
+                                    Some(offset),
+                                    Some(partition),
+                                ));
                             }
-                        }
 
-                        if timer.elapsed().as_millis() > 10 {
-                            // We didn't drain the entire queue, so indicate that we
-                            // should run again. We suppress the activation when the
-                            // queue is drained, as in that case librdkafka is
-                            // configured to unpark our thread when a new message
-                            // arrives.
-                            activator.activate();
-                            return SourceStatus::Alive;
+                            downgrade_capability(&id, cap, &mut progress, &timestamp_histories);
                         }
+                    }
 
-                        // Try and poll for next message
-                        next_message = match consumer.poll(Duration::from_millis(0)) {
-                            Some(Ok(msg)) => Some(msg.detach()),
-                            Some(Err(err)) => {
-                                error!("kafka error: {}: {}", name, err);
-                                None
-                            }
-                            _ => None,
-                        };
+                    if timer.elapsed().as_millis() > 10 {
+                        // We didn't drain the entire queue, so indicate that we
+                        // should run again. We suppress the activation when the
+                        // queue is drained, as in that case librdkafka is
+                        // configured to unpark our thread when a new message
+                        // arrives.
+                        activator.activate();
+                        return SourceStatus::Alive;
                     }
+
+                    // Try and poll for next message
+                    next_message = poll(&consumer, &name);
                 }
-                // Ensure that we poll kafka more often than the eviction timeout
-                activator.activate_after(Duration::from_secs(60));
-                SourceStatus::Alive
             } else {
-                if let Some(consumer) = consumer.as_mut() {
-                    // Repeatedly interrogate Kafka for messages. Cease when
-                    // Kafka stops returning new data, or after 10 milliseconds.
-                    let timer = std::time::Instant::now();
-
-                    while let Some(result) = consumer.poll(Duration::from_millis(0)) {
-                        match result {
-                            Ok(message) => {
-                                let payload = match message.payload() {
-                                    Some(p) => p,
-                                    // Null payloads are expected from Debezium.
-                                    // See https://github.com/MaterializeInc/materialize/issues/439#issuecomment-534236276
-                                    None => continue,
-                                };
-
-                                let ms = match message.timestamp() {
-                                    KafkaTimestamp::NotAvailable => {
-                                        // TODO(benesch): do we need to do something
-                                        // else?
-                                        error!("dropped kafka message with no timestamp");
-                                        continue;
-                                    }
-                                    KafkaTimestamp::CreateTime(ms)
-                                    | KafkaTimestamp::LogAppendTime(ms) => ms as u64,
-                                };
-                                let cur = *cap.time();
-                                if ms >= *cap.time() {
-                                    cap.downgrade(&ms)
-                                } else {
-                                    warn!(
-                                        "{}: fast-forwarding out-of-order Kafka timestamp {}ms ({} -> {})",
-                                        name,
-                                        cur - ms,
-                                        ms,
-                                        cur,
-                                    );
-                                };
-
-                                let out = payload.to_vec();
-                                BYTES_READ_COUNTER.inc_by(out.len() as i64);
-                                output.session(&cap).give((out, Some(message.offset())));
-                            }
-                            Err(err) => error!("kafka error: {}: {}", name, err),
+                while let Some(result) = consumer.poll(Duration::from_millis(0)) {
+                    match result {
+                        Ok(message) => {
+                            let payload = match message.payload() {
+                                Some(p) => p,
+                                // Null payloads are expected from Debezium.
+                                // See https://github.com/MaterializeInc/materialize/issues/439#issuecomment-534236276
+                                None => continue,
+                            };
+
+                            let ms = match message.timestamp() {
+                                KafkaTimestamp::NotAvailable => {
+                                    // TODO(benesch): do we need to do something
+                                    // else?
+                                    error!("dropped kafka message with no timestamp");
+                                    continue;
+                                }
+                                KafkaTimestamp::CreateTime(ms)
+                                | KafkaTimestamp::LogAppendTime(ms) => ms as u64,
+                            };
+                            let cur = *cap.time();
+                            if ms >= *cap.time() {
+                                cap.downgrade(&ms)
+                            } else {
+                                warn!(
+                                    "{}: fast-forwarding out-of-order Kafka timestamp {}ms ({} -> {})",
+                                    name,
+                                    cur - ms,
+                                    ms,
+                                    cur,
+                                );
+                            };
+
+                            let out = payload.to_vec();
+                            BYTES_READ_COUNTER.inc_by(out.len() as i64);
+                            output.session(&cap).give((
+                                out,
+                                Some(message.offset()),
+                                Some(message.partition()),
+                            ));
                         }
+                        Err(err) => error!("kafka error: {}: {}", name, err),
+                    }
 
-                        if timer.elapsed().as_millis() > 10 {
-                            // We didn't drain the entire queue, so indicate that we
-                            // should run again. We suppress the activation when the
-                            // queue is drained, as in that case librdkafka is
-                            // configured to unpark our thread when a new message
-                            // arrives.
-                            activator.activate();
-                            return SourceStatus::Alive;
-                        }
+                    if timer.elapsed().as_millis() > 10 {
+                        // We didn't drain the entire queue, so indicate that we
+                        // should run again. We suppress the activation when the
+                        // queue is drained, as in that case librdkafka is
+                        // configured to unpark our thread when a new message
+                        // arrives.
+                        activator.activate();
+                        return SourceStatus::Alive;
                     }
                 }
-                // Ensure that we poll kafka more often than the eviction timeout
-                activator.activate_after(Duration::from_secs(60));
-                SourceStatus::Alive
             }
+            // Ensure that we poll kafka more often than the eviction timeout
+            activator.activate_after(Duration::from_secs(60));
+            SourceStatus::Alive
         }
     });
 
-    if read_kafka {
-        (stream, Some(capability))
-    } else {
-        (stream, None)
+    (stream, Some(capability))
+}
+
+/// A worker's progress through one partition of a topic.
+struct PartitionProgress {
+    /// The offset of the last message that the worker processed.
+    last_offset: i64,
+    /// The latest timestamp whose messages the worker has all processed, if
+    /// any.
+    closed: Option<Timestamp>,
+}
+
+/// Assigns to `consumer` the partitions of `topic` that this worker reads,
+/// starting each at `start_offset`, if specified, or else at the offset
+/// stored for the consumer group. Returns the assigned partitions, or `None`
+/// if the topic has no partitions, as it does not exist yet.
+fn assign_partitions(
+    consumer: &BaseConsumer<GlueConsumerContext>,
+    topic: &str,
+    start_offset: Option<i64>,
+    first_worker: usize,
+    worker_index: usize,
+    worker_peers: usize,
+) -> KafkaResult<Option<Vec<i32>>> {
+    let metadata = consumer.fetch_metadata(Some(topic), Duration::from_secs(1))?;
+    let all: Vec<i32> = metadata
+        .topics()
+        .iter()
+        .flat_map(|topic| topic.partitions())
+        .map(|partition| partition.id())
+        .collect();
+    if all.is_empty() {
+        return Ok(None);
+    }
+    let mut partitions = vec![];
+    let mut list = TopicPartitionList::new();
+    for partition in all {
+        if (first_worker + partition as usize) % worker_peers == worker_index {
+            let offset = match start_offset {
+                Some(offset) => Offset::Offset(offset),
+                None => Offset::Stored,
+            };
+            list.add_partition_offset(topic, partition, offset);
+            partitions.push(partition);
+        }
+    }
+    if !partitions.is_empty() {
+        consumer.assign(&list)?;
+    }
+    Ok(Some(partitions))
+}
+
+/// Polls `consumer` for its next message, if one is ready.
+fn poll(consumer: &BaseConsumer<GlueConsumerContext>, name: &str) -> Option<OwnedMessage> {
+    match consumer.poll(Duration::from_millis(0)) {
+        Some(Ok(msg)) => Some(msg.detach()),
+        Some(Err(err)) => {
+            error!("kafka error: {}: {}", name, err);
+            None
+        }
+        _ => None,
     }
 }
 
-/// For a given offset, returns an option type returning the matching timestamp or None
+/// For a given offset in a partition, returns an option type returning the matching timestamp or
+/// None
 fn find_matching_timestamp(
     id: &SourceInstanceId,
+    partition: i32,
     offset: i64,
     timestamp_histories: &TimestampHistories,
 ) -> Option<Timestamp> {
-    match timestamp_histories.borrow().get(id) {
+    match timestamp_histories
+        .borrow()
+        .get(id)
+        .and_then(|partitions| partitions.get(&partition))
+    {
         None => None,
         Some(entries) => {
             for (ts, max_offset) in entries {
@@ -295,35 +415,53 @@ fn find_matching_timestamp(
     }
 }
 
-/// Timestamp history map is of format [(ts1, offset1), (ts2, offset2)].
+/// The timestamp history of a partition is of format [(ts1, offset1), (ts2, offset2)].
 /// All messages in interval [0,offset1] get assigned ts1, all messages in interval [offset1+1,offset2]
 /// get assigned ts2, etc.
-/// When receive message with offset1, it is safe to downgrade the capability to the next
-/// timestamp, which is either
-/// 1) the timestamp associated with the next highest offset if it exists
-/// 2) max(timestamp, offset1) + 1. The timestamp_history map can contain multiple timestamps for
-/// the same offset. We pick the greatest one + 1
-/// (the next message we generate will necessarily have timestamp timestamp + 1)
+/// Once the message at offset1 has been processed, no more messages of the partition can be
+/// assigned ts1, so the binding is retired, and the partition's next message will be assigned
+/// either
+/// 1) the timestamp of the partition's next binding, if it exists
+/// 2) a timestamp greater than ts1. The timestamper binds every partition at every timestamp, so
+/// the next binding will be for a later timestamp.
+/// The capability is downgraded to the earliest timestamp that the next message of any of the
+/// worker's partitions may be assigned.
 fn downgrade_capability(
     id: &SourceInstanceId,
     cap: &mut Capability<Timestamp>,
-    last_processed_offset: i64,
+    progress: &mut HashMap<i32, PartitionProgress>,
     timestamp_histories: &TimestampHistories,
 ) {
-    match timestamp_histories.borrow_mut().get_mut(id) {
-        None => {}
-        Some(entries) => {
-            while let Some((ts, offset)) = entries.first() {
-                let next_ts = ts + 1;
-                if last_processed_offset == *offset {
-                    entries.remove(0);
-                    cap.downgrade(&next_ts);
-                } else {
-                    // Offset isn't at a timestamp boundary, we take no action
-                    break;
-                }
+    let mut timestamp_histories = timestamp_histories.borrow_mut();
+    let partitions = match timestamp_histories.get_mut(id) {
+        None => return,
+        Some(partitions) => partitions,
+    };
+    // The bindings of the partitions that other workers read are of no use
+    // to this worker.
+    partitions.retain(|partition, _| progress.contains_key(partition));
+    let mut frontier = None;
+    for (partition, progress) in progress.iter_mut() {
+        let entries = partitions.entry(*partition).or_insert_with(Vec::new);
+        while let Some((ts, offset)) = entries.first() {
+            if progress.last_offset >= *offset {
+                progress.closed = Some(*ts);
+                entries.remove(0);
+            } else {
+                // Offset isn't at a timestamp boundary, we take no action
+                break;
             }
         }
+        let next_ts = match entries.first() {
+            Some((ts, _)) => *ts,
+            None => progress.closed.map_or(0, |ts| ts + 1),
+        };
+        frontier = Some(frontier.map_or(next_ts, |frontier| cmp::min(frontier, next_ts)));
+    }
+    if let Some(frontier) = frontier {
+        if frontier > *cap.time() {
+            cap.downgrade(&frontier);
+        }
     }
 }
 
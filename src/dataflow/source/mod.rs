@@ -16,12 +16,30 @@ use timely::scheduling::Activator;
 use crate::server::TimestampChanges;
 
 mod file;
+mod generator;
 mod kafka;
+mod mqtt;
+mod mysql;
+mod nats;
+mod plugin;
+mod postgres;
+mod s3;
 mod util;
 
 use expr::SourceInstanceId;
 pub use file::{file, FileReadStyle};
 pub use kafka::kafka;
+pub use mqtt::mqtt;
+pub use mysql::mysql;
+pub use s3::s3;
+// The `nats` and `postgres` modules share their names with the crates that
+// they use, so they must be named explicitly.
+pub use self::nats::nats;
+pub use self::postgres::postgres;
+pub use plugin::{
+    plugin, register_source_plugin, source_plugin, SourcePlugin, SourcePluginFactory, SourceRecord,
+    SourceWaker,
+};
 
 // A `SourceToken` indicates interest in a source. When the `SourceToken` is
 // dropped, its associated source will be stopped.
@@ -36,6 +54,13 @@ impl SourceToken {
     pub fn activate(&self) {
         self.activator.activate();
     }
+
+    /// Stops the source without dropping the token, so that the dataflows that
+    /// read from the source can process the data already inside them.
+    pub fn close(&self) {
+        *self.capability.borrow_mut() = None;
+        self.activator.activate();
+    }
 }
 
 impl Drop for SourceToken {
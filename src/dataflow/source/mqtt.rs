@@ -0,0 +1,209 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{error, warn};
+use rumqtt::{MqttClient, MqttOptions, Notification, QoS, ReconnectOptions};
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+
+use dataflow_types::{DataEncoding, Diff, MqttQos, MqttSourceConnector, Timestamp};
+use expr::SourceInstanceId;
+use repr::{Datum, Row, RowPacker};
+
+use super::util::source;
+use super::{SourceStatus, SourceToken};
+use crate::decode::pack_json;
+
+/// A message received from an MQTT broker.
+struct Message {
+    topic: String,
+    payload: Arc<Vec<u8>>,
+    retained: bool,
+}
+
+/// Reads the messages published to the topics that `connector` subscribes
+/// to.
+///
+/// Unlike other sources, MQTT sources decode their own messages, since each
+/// row includes the topic that its message was published to, in addition to
+/// the message's payload. Messages are timestamped with the time at which
+/// they are received.
+pub fn mqtt<G>(
+    scope: &G,
+    name: String,
+    connector: MqttSourceConnector,
+    encoding: DataEncoding,
+    id: SourceInstanceId,
+    read_mqtt: bool,
+) -> (Stream<G, (Row, Timestamp, Diff)>, Option<SourceToken>)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    // Update the capability every second if there are no new messages.
+    const HEARTBEAT: Duration = Duration::from_secs(1);
+    const MAX_MESSAGES_PER_INVOCATION: usize = 1024;
+
+    let n2 = name.clone();
+    let (stream, capability) = source(id, None, scope, &name, move |info| {
+        let activator = scope.activator_for(&info.address[..]);
+        let (tx, rx) = mpsc::channel();
+        // The subscriber thread exits once it notices that this token, which
+        // lives as long as the operator, has been dropped.
+        let alive = Arc::new(());
+        if read_mqtt {
+            let client_id = connector
+                .client_id
+                .clone()
+                .unwrap_or_else(|| format!("materialize-{}", id));
+            let activator = scope.sync_activator_for(&info.address[..]);
+            let name = n2.clone();
+            let weak = Arc::downgrade(&alive);
+            thread::spawn(move || subscribe(name, connector, client_id, tx, activator, weak));
+        }
+
+        move |cap, output| {
+            let _alive = &alive;
+            let cap_time = *cap.time();
+            let sys_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time seems to be before 1970.")
+                .as_millis() as u64;
+            let mut next_activation_duration = HEARTBEAT;
+            let next_time = if cap_time > sys_time {
+                if cap_time != sys_time + 1 {
+                    warn!(
+                        "{}: fast-forwarding out-of-order Unix timestamp {}ms ({} -> {})",
+                        n2,
+                        cap_time - sys_time,
+                        sys_time,
+                        cap_time,
+                    );
+                }
+                next_activation_duration = Duration::from_millis(cap_time - sys_time);
+                cap_time
+            } else {
+                cap.downgrade(&sys_time);
+                sys_time + 1
+            };
+
+            let mut messages_read = 0;
+            {
+                let time = *cap.time();
+                let mut session = output.session(cap);
+                while messages_read < MAX_MESSAGES_PER_INVOCATION {
+                    match rx.try_recv() {
+                        Ok(message) => {
+                            messages_read += 1;
+                            session.give((decode_message(&encoding, &message), time, 1));
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return SourceStatus::Done,
+                    }
+                }
+            }
+            if messages_read == MAX_MESSAGES_PER_INVOCATION {
+                next_activation_duration = Default::default();
+            }
+            cap.downgrade(&next_time);
+            activator.activate_after(next_activation_duration);
+            SourceStatus::Alive
+        }
+    });
+
+    if read_mqtt {
+        (stream, Some(capability))
+    } else {
+        (stream, None)
+    }
+}
+
+/// Subscribes to the broker and forwards the messages it publishes to `tx`,
+/// until `alive` can no longer be upgraded.
+///
+/// The client reconnects by itself if the connection is lost after it is
+/// established. If the initial connection or the subscription fails, the
+/// subscriber starts over after a delay.
+fn subscribe(
+    name: String,
+    connector: MqttSourceConnector,
+    client_id: String,
+    tx: mpsc::Sender<Message>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+) {
+    const RETRY: Duration = Duration::from_secs(5);
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    let qos = match connector.qos {
+        MqttQos::AtMostOnce => QoS::AtMostOnce,
+        MqttQos::AtLeastOnce => QoS::AtLeastOnce,
+        MqttQos::ExactlyOnce => QoS::ExactlyOnce,
+    };
+
+    while alive.upgrade().is_some() {
+        let options = MqttOptions::new(client_id.as_str(), connector.host.as_str(), connector.port)
+            .set_reconnect_opts(ReconnectOptions::Always(RETRY.as_secs()));
+        let (mut client, notifications) = match MqttClient::start(options) {
+            Ok(client) => client,
+            Err(e) => {
+                error!("{}: unable to connect to MQTT broker: {}", name, e);
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        if let Err(e) = client.subscribe(connector.topic.as_str(), qos) {
+            error!("{}: unable to subscribe to MQTT topic: {}", name, e);
+            thread::sleep(RETRY);
+            continue;
+        }
+
+        loop {
+            match notifications.recv_timeout(POLL_INTERVAL) {
+                Ok(Notification::Publish(publish)) => {
+                    let message = Message {
+                        topic: publish.topic_name,
+                        payload: publish.payload,
+                        retained: publish.retain,
+                    };
+                    if tx.send(message).is_err() || activator.activate().is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => (),
+                Err(e) if e.is_timeout() => {
+                    if alive.upgrade().is_none() {
+                        return;
+                    }
+                }
+                Err(_) => {
+                    error!("{}: MQTT client shut down unexpectedly", name);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn decode_message(encoding: &DataEncoding, message: &Message) -> Row {
+    let mut packer = RowPacker::new();
+    match encoding {
+        DataEncoding::Bytes => packer.push(Datum::from(&message.payload[..])),
+        DataEncoding::Text => packer.push(Datum::from(std::str::from_utf8(&message.payload).ok())),
+        DataEncoding::Json(_) => packer = pack_json(packer, &message.payload),
+        _ => unreachable!("MQTT sources support only bytes, text, and JSON payloads"),
+    }
+    packer.push(Datum::String(&message.topic));
+    packer.push(Datum::from(message.retained));
+    packer.finish()
+}
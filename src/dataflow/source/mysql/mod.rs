@@ -0,0 +1,802 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::str;
+use std::sync::mpsc;
+use std::sync::Weak;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use failure::{bail, format_err};
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+use uuid::Uuid;
+
+use dataflow_types::{Diff, MysqlSourceConnector, Timestamp};
+use expr::SourceInstanceId;
+use repr::{Datum, RelationDesc, Row, RowPacker, ScalarType};
+
+use self::protocol::{Conn, Reader};
+use super::util::{batch_source, Batch};
+use super::SourceToken;
+use crate::decode::pack_field;
+
+mod protocol;
+
+/// Reads the rows of the MySQL table that `connector` names, and then follows
+/// the changes to the table through the server's binary log.
+///
+/// The source reads the table in a transaction with a consistent snapshot,
+/// which it starts while holding a global read lock, so that it can note the
+/// position in the binary log that the snapshot corresponds to. It then asks
+/// the server to send the binary log from that position, as it would to a
+/// replica. The contents of the table, and the changes of each transaction,
+/// are timestamped together.
+///
+/// The binary log only records the changes to each row if `binlog_format` is
+/// `ROW` and `binlog_row_image` is `FULL`. Rows in the binary log do not name
+/// their columns, so the source notes the positions of its columns in the table
+/// when it starts, and stops if the table's columns change afterwards.
+///
+/// The source does not note how far it has read, so it cannot resume after it
+/// loses its connection. It instead reports an error and stops advancing.
+pub fn mysql<G>(
+    scope: &G,
+    name: String,
+    connector: MysqlSourceConnector,
+    desc: RelationDesc,
+    id: SourceInstanceId,
+    read_mysql: bool,
+) -> (Stream<G, (Row, Timestamp, Diff)>, Option<SourceToken>)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    batch_source(scope, name, id, read_mysql, move |tx, activator, alive| {
+        replicate(&connector, &desc, tx, activator, alive)
+    })
+}
+
+/// Sends the contents of the table and then the changes to it to `tx`, until
+/// `alive` can no longer be upgraded.
+fn replicate(
+    connector: &MysqlSourceConnector,
+    desc: &RelationDesc,
+    tx: mpsc::Sender<Batch>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+) -> Result<(), failure::Error> {
+    let columns = desc
+        .iter()
+        .map(|(name, typ)| match name {
+            Some(name) => Ok((name.as_str().to_owned(), typ.scalar_type.clone())),
+            None => bail!("MySQL source has an unnamed column"),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let url = &connector.url;
+    let mut conn = Conn::connect(
+        url.host_str().unwrap_or("localhost"),
+        url.port().unwrap_or(3306),
+        url.username(),
+        url.password().unwrap_or(""),
+        connector.get_server_public_key,
+    )?;
+
+    let settings = conn.query("SELECT @@binlog_format, @@binlog_row_image")?;
+    match settings.first().map(|row| (&row[0], &row[1])) {
+        Some((Some(format), Some(image))) if format == b"ROW" && image == b"FULL" => (),
+        _ => bail!("MySQL sources require binlog_format = ROW and binlog_row_image = FULL"),
+    }
+
+    // Rows in the binary log list every column of the table, in order.
+    let names = conn.query(&format!(
+        "SELECT COLUMN_NAME FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = {} AND TABLE_NAME = {} ORDER BY ORDINAL_POSITION",
+        quote_str(&connector.database),
+        quote_str(&connector.table)
+    ))?;
+    if names.is_empty() {
+        bail!(
+            "table {}.{} does not exist",
+            connector.database,
+            connector.table
+        );
+    }
+    let names: Vec<_> = names
+        .into_iter()
+        .map(|row| String::from_utf8_lossy(row[0].as_deref().unwrap_or(&[])).to_lowercase())
+        .collect();
+    let positions = columns
+        .iter()
+        .map(|(name, _)| {
+            names
+                .iter()
+                .position(|n| *n == name.to_lowercase())
+                .ok_or_else(|| format_err!("table {} has no column {}", connector.table, name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Timestamps are reported in UTC, as in the binary log.
+    conn.query("SET time_zone = '+00:00'")?;
+    conn.query("FLUSH TABLES WITH READ LOCK")?;
+    conn.query("START TRANSACTION WITH CONSISTENT SNAPSHOT")?;
+    let status = conn.query("SHOW MASTER STATUS")?;
+    conn.query("UNLOCK TABLES")?;
+    let (file, position) = match status.first().map(|row| (&row[0], &row[1])) {
+        Some((Some(file), Some(position))) => (
+            String::from_utf8_lossy(file).into_owned(),
+            str::from_utf8(position)?.parse::<u32>()?,
+        ),
+        _ => bail!("binary logging is not enabled"),
+    };
+    let select = format!(
+        "SELECT {} FROM {}.{}",
+        columns
+            .iter()
+            .map(|(name, _)| quote_ident(name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        quote_ident(&connector.database),
+        quote_ident(&connector.table)
+    );
+    let mut batch = vec![];
+    for row in conn.query(&select)? {
+        let values = row
+            .iter()
+            .zip(&columns)
+            .map(|(value, (_, typ))| match value {
+                Some(value) => parse_text(typ, value),
+                None => Ok(Value::Null),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.push((pack_row(&columns, values)?, 1));
+    }
+    conn.query("COMMIT")?;
+    if tx.send(batch).is_err() || activator.activate().is_err() {
+        return Ok(());
+    }
+
+    // Events carry a checksum if the server is configured to compute one, and
+    // the client says that it can handle it.
+    let checksum = conn.query("SELECT @@global.binlog_checksum")?;
+    let checksum = match checksum.first().and_then(|row| row[0].as_ref()) {
+        Some(alg) if alg == b"NONE" => false,
+        Some(alg) if alg == b"CRC32" => true,
+        _ => bail!("unsupported binlog_checksum"),
+    };
+    conn.query("SET @master_binlog_checksum = @@global.binlog_checksum")?;
+    // The server sends a heartbeat when it has no events to send, so that the
+    // source notices when it is dropped.
+    conn.query("SET @master_heartbeat_period = 1000000000")?;
+    // Each replica must have a distinct ID.
+    let server_id = Uuid::new_v4().as_fields().0.max(1);
+    conn.binlog_dump(&file, position, server_id)?;
+
+    let mut table = None;
+    let mut batch = vec![];
+    while alive.upgrade().is_some() {
+        let event = match conn.read_event()? {
+            Some(event) => event,
+            None => bail!("server stopped sending the binary log"),
+        };
+        let event = if checksum {
+            &event[..event.len().saturating_sub(4)]
+        } else {
+            &event[..]
+        };
+        if event.len() < EVENT_HEADER_LEN {
+            bail!("truncated binary log event");
+        }
+        let mut body = Reader::new(&event[EVENT_HEADER_LEN..]);
+        match event[4] {
+            QUERY_EVENT => {
+                body.bytes(8)?; // thread ID and execution time
+                let schema_len = body.u8()?;
+                body.u16()?; // error code
+                let status_len = body.u16()?;
+                body.bytes(usize::from(status_len) + usize::from(schema_len) + 1)?;
+                // Changes to non-transactional tables, and statements that
+                // are not logged as rows, are not wrapped in a transaction.
+                if body.rest() != b"BEGIN" && !batch.is_empty() {
+                    if tx.send(batch).is_err() || activator.activate().is_err() {
+                        return Ok(());
+                    }
+                    batch = vec![];
+                }
+            }
+            XID_EVENT => {
+                if !batch.is_empty() {
+                    if tx.send(batch).is_err() || activator.activate().is_err() {
+                        return Ok(());
+                    }
+                    batch = vec![];
+                }
+            }
+            TABLE_MAP_EVENT => {
+                let map = TableMap::parse(&mut body)?;
+                if map.database == connector.database && map.table == connector.table {
+                    if map.columns.len() != names.len() {
+                        bail!("the columns of table {} changed", connector.table);
+                    }
+                    table = Some(map);
+                }
+            }
+            typ @ WRITE_ROWS_EVENTV1..=DELETE_ROWS_EVENTV1
+            | typ @ WRITE_ROWS_EVENTV2..=DELETE_ROWS_EVENTV2 => {
+                let table_id = body.uint(6)?;
+                body.u16()?; // flags
+                if typ >= WRITE_ROWS_EVENTV2 {
+                    let extra_len = body.u16()?;
+                    body.bytes(usize::from(extra_len).saturating_sub(2))?;
+                }
+                let table = match &table {
+                    Some(table) if table.id == table_id => table,
+                    _ => continue,
+                };
+                let kind = match typ {
+                    WRITE_ROWS_EVENTV1 | WRITE_ROWS_EVENTV2 => RowsKind::Write,
+                    UPDATE_ROWS_EVENTV1 | UPDATE_ROWS_EVENTV2 => RowsKind::Update,
+                    _ => RowsKind::Delete,
+                };
+                decode_rows(&mut body, kind, table, &columns, &positions, &mut batch)
+                    .map_err(|e| format_err!("decoding rows of table {}: {}", table.table, e))?;
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+const EVENT_HEADER_LEN: usize = 19;
+
+const QUERY_EVENT: u8 = 2;
+const XID_EVENT: u8 = 16;
+const TABLE_MAP_EVENT: u8 = 19;
+const WRITE_ROWS_EVENTV1: u8 = 23;
+const UPDATE_ROWS_EVENTV1: u8 = 24;
+const DELETE_ROWS_EVENTV1: u8 = 25;
+const WRITE_ROWS_EVENTV2: u8 = 30;
+const UPDATE_ROWS_EVENTV2: u8 = 31;
+const DELETE_ROWS_EVENTV2: u8 = 32;
+
+const TYPE_TINY: u8 = 1;
+const TYPE_SHORT: u8 = 2;
+const TYPE_LONG: u8 = 3;
+const TYPE_FLOAT: u8 = 4;
+const TYPE_DOUBLE: u8 = 5;
+const TYPE_LONGLONG: u8 = 8;
+const TYPE_INT24: u8 = 9;
+const TYPE_DATE: u8 = 10;
+const TYPE_YEAR: u8 = 13;
+const TYPE_VARCHAR: u8 = 15;
+const TYPE_BIT: u8 = 16;
+const TYPE_TIMESTAMP2: u8 = 17;
+const TYPE_DATETIME2: u8 = 18;
+const TYPE_TIME2: u8 = 19;
+const TYPE_JSON: u8 = 245;
+const TYPE_NEWDECIMAL: u8 = 246;
+const TYPE_ENUM: u8 = 247;
+const TYPE_SET: u8 = 248;
+const TYPE_BLOB: u8 = 252;
+const TYPE_VAR_STRING: u8 = 253;
+const TYPE_STRING: u8 = 254;
+const TYPE_GEOMETRY: u8 = 255;
+
+/// The table that a `TABLE_MAP_EVENT` describes, which the rows events that
+/// follow it refer to by ID.
+struct TableMap {
+    id: u64,
+    database: String,
+    table: String,
+    /// The type of each column, and the metadata that describes its size.
+    columns: Vec<(u8, u16)>,
+}
+
+impl TableMap {
+    fn parse(r: &mut Reader) -> Result<TableMap, failure::Error> {
+        let id = r.uint(6)?;
+        r.u16()?; // flags
+        let len = r.u8()?;
+        let database = String::from_utf8_lossy(r.bytes(usize::from(len))?).into_owned();
+        r.u8()?;
+        let len = r.u8()?;
+        let table = String::from_utf8_lossy(r.bytes(usize::from(len))?).into_owned();
+        r.u8()?;
+        let count = r.lenenc_int()? as usize;
+        let types = r.bytes(count)?;
+        let mut meta = Reader::new(r.lenenc_bytes()?);
+        let columns = types
+            .iter()
+            .map(|typ| {
+                let m = match *typ {
+                    TYPE_FLOAT | TYPE_DOUBLE | TYPE_BLOB | TYPE_GEOMETRY | TYPE_JSON
+                    | TYPE_TIMESTAMP2 | TYPE_DATETIME2 | TYPE_TIME2 => meta.uint(1)?,
+                    TYPE_VARCHAR | TYPE_VAR_STRING | TYPE_BIT => meta.uint(2)?,
+                    TYPE_NEWDECIMAL | TYPE_STRING | TYPE_ENUM | TYPE_SET => meta.uint_be(2)?,
+                    _ => 0,
+                };
+                Ok((*typ, m as u16))
+            })
+            .collect::<Result<Vec<_>, failure::Error>>()?;
+        Ok(TableMap {
+            id,
+            database,
+            table,
+            columns,
+        })
+    }
+}
+
+enum RowsKind {
+    Write,
+    Update,
+    Delete,
+}
+
+/// Decodes the rows of a rows event into `batch`.
+fn decode_rows(
+    r: &mut Reader,
+    kind: RowsKind,
+    table: &TableMap,
+    columns: &[(String, ScalarType)],
+    positions: &[usize],
+    batch: &mut Batch,
+) -> Result<(), failure::Error> {
+    let count = r.lenenc_int()? as usize;
+    if count != table.columns.len() {
+        bail!("expected {} columns, found {}", table.columns.len(), count);
+    }
+    let bitmap_len = (count + 7) / 8;
+    let mut present = r.bytes(bitmap_len)?.to_vec();
+    if let RowsKind::Update = kind {
+        present.extend(r.bytes(bitmap_len)?);
+    }
+    if present
+        .iter()
+        .zip(full_bitmap(count).iter().cycle())
+        .any(|(p, f)| p != f)
+    {
+        bail!("the row omits some columns; binlog_row_image must be FULL");
+    }
+    let read_row = |r: &mut Reader| -> Result<Row, failure::Error> {
+        let nulls = r.bytes(bitmap_len)?;
+        let mut values = Vec::with_capacity(count);
+        for (i, (typ, meta)) in table.columns.iter().enumerate() {
+            if nulls[i / 8] & (1 << (i % 8)) != 0 {
+                values.push(Value::Null);
+            } else {
+                values.push(decode_value(r, *typ, *meta)?);
+            }
+        }
+        let values = positions.iter().map(|i| values[*i].clone());
+        pack_row(columns, values)
+    };
+    while r.peek().is_some() {
+        match kind {
+            RowsKind::Write => batch.push((read_row(r)?, 1)),
+            RowsKind::Delete => batch.push((read_row(r)?, -1)),
+            RowsKind::Update => {
+                batch.push((read_row(r)?, -1));
+                batch.push((read_row(r)?, 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns a bitmap that marks each of `count` columns as present.
+fn full_bitmap(count: usize) -> Vec<u8> {
+    let mut bitmap = vec![0xff; (count + 7) / 8];
+    if count % 8 != 0 {
+        bitmap[count / 8] = (1 << (count % 8)) - 1;
+    }
+    bitmap
+}
+
+/// The value of a column, as read from the table or the binary log.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    /// The text representation of an exact decimal number.
+    Decimal(String),
+    Bytes(Vec<u8>),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+/// Decodes a value of type `typ`, whose size is described by `meta`, from the
+/// binary log.
+///
+/// Integers are assumed to be signed. Dates that are zero, or otherwise
+/// invalid, are read as NULL.
+fn decode_value(r: &mut Reader, typ: u8, meta: u16) -> Result<Value, failure::Error> {
+    Ok(match typ {
+        TYPE_TINY => Value::Int(i64::from(r.u8()? as i8)),
+        TYPE_SHORT => Value::Int(i64::from(r.u16()? as i16)),
+        TYPE_INT24 => Value::Int(i64::from((r.uint(3)? as i32) << 8 >> 8)),
+        TYPE_LONG => Value::Int(i64::from(r.u32()? as i32)),
+        TYPE_LONGLONG => Value::Int(r.uint(8)? as i64),
+        TYPE_FLOAT => Value::Float(f64::from(f32::from_bits(r.u32()?))),
+        TYPE_DOUBLE => Value::Float(f64::from_bits(r.uint(8)?)),
+        TYPE_YEAR => match r.u8()? {
+            0 => Value::Int(0),
+            y => Value::Int(1900 + i64::from(y)),
+        },
+        TYPE_NEWDECIMAL => Value::Decimal(decode_decimal(
+            r,
+            usize::from(meta >> 8),
+            usize::from(meta & 0xff),
+        )?),
+        TYPE_VARCHAR | TYPE_VAR_STRING => {
+            let len = r.uint(if meta < 256 { 1 } else { 2 })?;
+            Value::Bytes(r.bytes(len as usize)?.to_vec())
+        }
+        TYPE_STRING => {
+            // The metadata holds the real type of the column, and its maximum
+            // length, whose high bits are tucked into the type.
+            let (real_type, len) = (meta >> 8, meta & 0xff);
+            let (real_type, len) = if real_type & 0x30 != 0x30 {
+                (real_type | 0x30, len | (((real_type & 0x30) ^ 0x30) << 4))
+            } else {
+                (real_type, len)
+            };
+            if real_type == u16::from(TYPE_ENUM) || real_type == u16::from(TYPE_SET) {
+                bail!("column type {} is not supported", real_type);
+            }
+            let len = r.uint(if len < 256 { 1 } else { 2 })?;
+            Value::Bytes(r.bytes(len as usize)?.to_vec())
+        }
+        TYPE_BLOB => {
+            let len = r.uint(usize::from(meta))?;
+            Value::Bytes(r.bytes(len as usize)?.to_vec())
+        }
+        TYPE_DATE => {
+            let v = r.uint(3)? as u32;
+            match NaiveDate::from_ymd_opt((v >> 9) as i32, (v >> 5) & 0xf, v & 0x1f) {
+                Some(date) => Value::Date(date),
+                None => Value::Null,
+            }
+        }
+        TYPE_DATETIME2 => {
+            let v = r.uint_be(5)? as i64 - 0x80_0000_0000;
+            let micros = decode_fraction(r, meta)?;
+            let (ymd, hms) = (v >> 17, v % (1 << 17));
+            let (ym, day) = (ymd >> 5, ymd % 32);
+            let datetime = NaiveDate::from_ymd_opt((ym / 13) as i32, (ym % 13) as u32, day as u32)
+                .and_then(|date| {
+                    date.and_hms_micro_opt(
+                        (hms >> 12) as u32,
+                        ((hms >> 6) % 64) as u32,
+                        (hms % 64) as u32,
+                        micros,
+                    )
+                });
+            match datetime {
+                Some(datetime) => Value::DateTime(datetime),
+                None => Value::Null,
+            }
+        }
+        TYPE_TIMESTAMP2 => {
+            let secs = r.uint_be(4)?;
+            let micros = decode_fraction(r, meta)?;
+            match NaiveDateTime::from_timestamp_opt(secs as i64, micros * 1000) {
+                Some(_) if secs == 0 && micros == 0 => Value::Null,
+                Some(datetime) => Value::DateTime(datetime),
+                None => Value::Null,
+            }
+        }
+        _ => bail!("column type {} is not supported", typ),
+    })
+}
+
+/// Decodes the fractional seconds of a temporal value with `fsp` digits of
+/// precision, in microseconds.
+fn decode_fraction(r: &mut Reader, fsp: u16) -> Result<u32, failure::Error> {
+    let len = (usize::from(fsp) + 1) / 2;
+    if len == 0 {
+        return Ok(0);
+    }
+    Ok(r.uint_be(len)? as u32 * 100_u32.pow(3 - len as u32))
+}
+
+/// Decodes a decimal number with `precision` digits, `scale` of which follow
+/// the decimal point, into its text representation.
+///
+/// The digits are stored in big-endian groups of nine, each in four bytes,
+/// with the leftover digits on either end stored in as few bytes as they need.
+/// The sign is the inverse of the first bit, and the other bits are inverted if
+/// the number is negative.
+fn decode_decimal(
+    r: &mut Reader,
+    precision: usize,
+    scale: usize,
+) -> Result<String, failure::Error> {
+    const DIGITS_TO_BYTES: [usize; 10] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4];
+    if scale > precision {
+        bail!("invalid decimal scale {}", scale);
+    }
+    let (int_groups, int_digits) = ((precision - scale) / 9, (precision - scale) % 9);
+    let (frac_groups, frac_digits) = (scale / 9, scale % 9);
+    let len = int_groups * 4
+        + DIGITS_TO_BYTES[int_digits]
+        + frac_groups * 4
+        + DIGITS_TO_BYTES[frac_digits];
+    let mut buf = r.bytes(len)?.to_vec();
+    if buf.is_empty() {
+        bail!("invalid decimal precision {}", precision);
+    }
+    let negative = buf[0] & 0x80 == 0;
+    buf[0] ^= 0x80;
+    if negative {
+        for b in &mut buf {
+            *b = !*b;
+        }
+    }
+    let mut d = Reader::new(&buf);
+    let mut int = String::new();
+    if int_digits > 0 {
+        write!(int, "{}", d.uint_be(DIGITS_TO_BYTES[int_digits])?)?;
+    }
+    for _ in 0..int_groups {
+        write!(int, "{:09}", d.uint_be(4)?)?;
+    }
+    let int = int.trim_start_matches('0');
+    let mut s = String::new();
+    if negative {
+        s.push('-');
+    }
+    s.push_str(if int.is_empty() { "0" } else { int });
+    if scale > 0 {
+        s.push('.');
+        for _ in 0..frac_groups {
+            write!(s, "{:09}", d.uint_be(4)?)?;
+        }
+        if frac_digits > 0 {
+            let group = d.uint_be(DIGITS_TO_BYTES[frac_digits])?;
+            write!(s, "{:0width$}", group, width = frac_digits)?;
+        }
+    }
+    Ok(s)
+}
+
+/// Parses the text representation of a value of type `typ`, as read from the
+/// table.
+fn parse_text(typ: &ScalarType, value: &[u8]) -> Result<Value, failure::Error> {
+    if let ScalarType::String | ScalarType::Bytes = typ {
+        return Ok(Value::Bytes(value.to_vec()));
+    }
+    let value = str::from_utf8(value)?;
+    Ok(match typ {
+        ScalarType::Bool | ScalarType::Int32 | ScalarType::Int64 => Value::Int(value.parse()?),
+        ScalarType::Float64 => Value::Float(value.parse()?),
+        ScalarType::Decimal(..) => Value::Decimal(value.to_owned()),
+        ScalarType::Date if value.starts_with("0000-00-00") => Value::Null,
+        ScalarType::Date => Value::Date(NaiveDate::parse_from_str(value, "%Y-%m-%d")?),
+        ScalarType::Timestamp | ScalarType::TimestampTz if value.starts_with("0000-00-00") => {
+            Value::Null
+        }
+        ScalarType::Timestamp | ScalarType::TimestampTz => Value::DateTime(
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")?,
+        ),
+        _ => bail!("unsupported type {}", typ),
+    })
+}
+
+fn pack_row<I>(columns: &[(String, ScalarType)], values: I) -> Result<Row, failure::Error>
+where
+    I: IntoIterator<Item = Value>,
+{
+    let mut packer = RowPacker::new();
+    for ((name, typ), value) in columns.iter().zip(values) {
+        pack_value(&mut packer, typ, value).map_err(|e| format_err!("column {}: {}", name, e))?;
+    }
+    Ok(packer.finish())
+}
+
+fn pack_value(
+    packer: &mut RowPacker,
+    typ: &ScalarType,
+    value: Value,
+) -> Result<(), failure::Error> {
+    match (typ, value) {
+        (_, Value::Null) => packer.push(Datum::Null),
+        (ScalarType::Bool, Value::Int(i)) => packer.push(Datum::from(i != 0)),
+        (ScalarType::Int32, Value::Int(i)) => packer.push(Datum::Int32(i32::try_from(i)?)),
+        (ScalarType::Int64, Value::Int(i)) => packer.push(Datum::Int64(i)),
+        (ScalarType::Float64, Value::Int(i)) => packer.push(Datum::from(i as f64)),
+        (ScalarType::Float64, Value::Float(f)) => packer.push(Datum::from(f)),
+        (ScalarType::Decimal(..), Value::Int(i)) => {
+            pack_field(packer, &i.to_string(), typ.clone())?
+        }
+        (ScalarType::Decimal(..), Value::Decimal(d)) => pack_field(packer, &d, typ.clone())?,
+        (ScalarType::String, Value::Bytes(b)) => packer.push(Datum::String(str::from_utf8(&b)?)),
+        (ScalarType::Bytes, Value::Bytes(b)) => packer.push(Datum::Bytes(&b)),
+        (ScalarType::Date, Value::Date(d)) => packer.push(Datum::Date(d)),
+        (ScalarType::Timestamp, Value::Date(d)) => {
+            packer.push(Datum::Timestamp(d.and_hms(0, 0, 0)))
+        }
+        (ScalarType::Timestamp, Value::DateTime(dt)) => packer.push(Datum::Timestamp(dt)),
+        (ScalarType::TimestampTz, Value::DateTime(dt)) => {
+            packer.push(Datum::TimestampTz(DateTime::from_utc(dt, Utc)))
+        }
+        _ => bail!("value does not match type {}", typ),
+    }
+    Ok(())
+}
+
+fn quote_ident(s: &str) -> String {
+    format!("`{}`", s.replace('`', "``"))
+}
+
+fn quote_str(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use repr::{Datum, Row, ScalarType};
+
+    use super::protocol::Reader;
+    use super::{
+        decode_decimal, decode_rows, decode_value, Batch, RowsKind, TableMap, Value, TYPE_DATE,
+        TYPE_INT24, TYPE_TIMESTAMP2, TYPE_TINY, TYPE_YEAR,
+    };
+
+    /// The body of a `TABLE_MAP_EVENT` for the table `shop.customers`, whose
+    /// columns are `id BIGINT`, `name VARCHAR(255)`, `joined DATETIME`, and
+    /// `balance DECIMAL(10, 2)`.
+    const TABLE_MAP: &[u8] = b"\
+        \x20\x00\x00\x00\x00\x00\x01\x00\
+        \x04shop\x00\x09customers\x00\
+        \x04\x08\x0f\x12\xf6\
+        \x05\xff\x00\x00\x0a\x02\
+        \x0e";
+
+    /// The rows of a `WRITE_ROWS_EVENT` for `TABLE_MAP`, after the table ID,
+    /// flags, and extra data: `(1, 'Ann', '2020-01-02 03:04:05', 12.34)` and
+    /// `(-1, NULL, NULL, -12.34)`.
+    const ROWS: &[u8] = b"\
+        \x00\x01\x00\x00\x00\x00\x00\x00\x00\x03Ann\
+        \x99\xa5\x44\x31\x05\x80\x00\x00\x0c\x22\
+        \x06\xff\xff\xff\xff\xff\xff\xff\xff\
+        \x7f\xff\xff\xf3\xdd";
+
+    fn table_map() -> TableMap {
+        TableMap::parse(&mut Reader::new(TABLE_MAP)).unwrap()
+    }
+
+    fn decode(event: &[u8], kind: RowsKind) -> Result<Batch, failure::Error> {
+        let columns = vec![
+            ("name".into(), ScalarType::String),
+            ("id".into(), ScalarType::Int64),
+            ("joined".into(), ScalarType::Timestamp),
+        ];
+        let mut batch = vec![];
+        decode_rows(
+            &mut Reader::new(event),
+            kind,
+            &table_map(),
+            &columns,
+            &[1, 0, 2],
+            &mut batch,
+        )?;
+        Ok(batch)
+    }
+
+    #[test]
+    fn test_table_map() {
+        let table = table_map();
+        assert_eq!(table.id, 0x20);
+        assert_eq!(table.database, "shop");
+        assert_eq!(table.table, "customers");
+        assert_eq!(
+            table.columns,
+            vec![(8, 0), (15, 255), (18, 0), (246, 0x0a02)]
+        );
+    }
+
+    #[test]
+    fn test_decode_rows() -> Result<(), failure::Error> {
+        let ann = Row::pack(&[
+            Datum::String("Ann"),
+            Datum::Int64(1),
+            Datum::Timestamp(NaiveDate::from_ymd(2020, 1, 2).and_hms(3, 4, 5)),
+        ]);
+        let null = Row::pack(&[Datum::Null, Datum::Int64(-1), Datum::Null]);
+
+        let mut event = b"\x04\x0f".to_vec();
+        event.extend(ROWS);
+        assert_eq!(
+            decode(&event, RowsKind::Write)?,
+            vec![(ann.clone(), 1), (null.clone(), 1)]
+        );
+        assert_eq!(
+            decode(&event, RowsKind::Delete)?,
+            vec![(ann.clone(), -1), (null.clone(), -1)]
+        );
+
+        // An update lists which columns each image includes, and then each row
+        // before and after the update.
+        let mut event = b"\x04\x0f\x0f".to_vec();
+        event.extend(ROWS);
+        assert_eq!(
+            decode(&event, RowsKind::Update)?,
+            vec![(ann, -1), (null, 1)]
+        );
+
+        let mut event = b"\x04\x0b".to_vec();
+        event.extend(ROWS);
+        let err = decode(&event, RowsKind::Write).unwrap_err();
+        assert!(err.to_string().contains("binlog_row_image"), "{}", err);
+
+        let mut event = b"\x03\x07".to_vec();
+        event.extend(ROWS);
+        assert!(decode(&event, RowsKind::Write).is_err());
+
+        let mut event = b"\x04\x0f".to_vec();
+        event.extend(&ROWS[..ROWS.len() - 1]);
+        assert!(decode(&event, RowsKind::Write).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_value() -> Result<(), failure::Error> {
+        let datetime = NaiveDate::from_ymd(2020, 1, 2).and_hms_micro(3, 4, 5, 123_000);
+        let cases: &[(u8, u16, &[u8], Value)] = &[
+            (TYPE_TINY, 0, b"\xff", Value::Int(-1)),
+            (TYPE_INT24, 0, b"\xff\xff\xff", Value::Int(-1)),
+            (TYPE_INT24, 0, b"\xff\xff\x7f", Value::Int(8_388_607)),
+            (TYPE_YEAR, 0, b"\x78", Value::Int(2020)),
+            (
+                TYPE_DATE,
+                0,
+                b"\x22\xc8\x0f",
+                Value::Date(NaiveDate::from_ymd(2020, 1, 2)),
+            ),
+            (TYPE_DATE, 0, b"\x00\x00\x00", Value::Null),
+            (
+                TYPE_TIMESTAMP2,
+                3,
+                b"\x5e\x0d\x5d\xa5\x04\xce",
+                Value::DateTime(datetime),
+            ),
+            (TYPE_TIMESTAMP2, 0, b"\x00\x00\x00\x00", Value::Null),
+        ];
+        for (typ, meta, bytes, expected) in cases {
+            let mut r = Reader::new(bytes);
+            assert_eq!(decode_value(&mut r, *typ, *meta)?, *expected);
+            assert_eq!(r.peek(), None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_decimal() -> Result<(), failure::Error> {
+        // The first two cases are the examples in MySQL's `strings/decimal.c`.
+        let cases: &[(usize, usize, &[u8], &str)] = &[
+            (14, 4, b"\x81\x0d\xfb\x38\xd2\x04\xd2", "1234567890.1234"),
+            (14, 4, b"\x7e\xf2\x04\xc7\x2d\xfb\x2d", "-1234567890.1234"),
+            (10, 2, b"\x80\x00\x00\x0c\x22", "12.34"),
+            (10, 2, b"\x7f\xff\xff\xf3\xdd", "-12.34"),
+            (5, 2, b"\x80\x00\x00", "0.00"),
+            (4, 0, b"\x84\xd2", "1234"),
+        ];
+        for (precision, scale, bytes, expected) in cases {
+            let mut r = Reader::new(bytes);
+            assert_eq!(decode_decimal(&mut r, *precision, *scale)?, *expected);
+            assert_eq!(r.peek(), None);
+        }
+        assert!(decode_decimal(&mut Reader::new(b""), 2, 3).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,746 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A minimal client for the MySQL protocol, which can run text queries and
+//! follow a server's binary log as a replica.
+//!
+//! See the [MySQL internals manual][manual] for a description of the
+//! protocol.
+//!
+//! [manual]: https://dev.mysql.com/doc/internals/en/client-server-protocol.html
+
+use std::io::{BufReader, Read, Write};
+use std::net::TcpStream;
+
+use failure::{bail, format_err};
+use openssl::rsa::{Padding, Rsa};
+use openssl::sha::{sha1, sha256};
+
+const CLIENT_LONG_PASSWORD: u32 = 0x0000_0001;
+const CLIENT_LONG_FLAG: u32 = 0x0000_0004;
+const CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+const CLIENT_TRANSACTIONS: u32 = 0x0000_2000;
+const CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x0008_0000;
+
+const COM_QUERY: u8 = 0x03;
+const COM_BINLOG_DUMP: u8 = 0x12;
+
+/// The `utf8mb4_general_ci` collation.
+const UTF8MB4: u8 = 45;
+
+/// The largest payload that fits in a single packet. Longer payloads are
+/// split across several packets.
+const MAX_PAYLOAD: usize = 0xff_ffff;
+
+/// A connection to a MySQL server.
+pub struct Conn<S = TcpStream> {
+    /// Writes bypass the buffer, which only holds what has been read.
+    stream: BufReader<S>,
+    /// The sequence number of the next packet.
+    seq: u8,
+}
+
+impl Conn {
+    /// Connects to the server at `host`:`port`, and authenticates as `user`
+    /// with `password`.
+    ///
+    /// The connection is not encrypted, so if the server asks for the password
+    /// in full, it can only be sent encrypted with the server's public key.
+    /// The key is only retrieved from the server if `get_server_public_key` is
+    /// set, as an attacker that intercepts the connection could substitute
+    /// their own.
+    pub fn connect(
+        host: &str,
+        port: u16,
+        user: &str,
+        password: &str,
+        get_server_public_key: bool,
+    ) -> Result<Conn, failure::Error> {
+        let stream = TcpStream::connect((host, port))?;
+        stream.set_nodelay(true)?;
+        let mut conn = Conn::new(stream);
+        conn.handshake(user, password, get_server_public_key)?;
+        Ok(conn)
+    }
+}
+
+impl<S> Conn<S>
+where
+    S: Read + Write,
+{
+    fn new(stream: S) -> Conn<S> {
+        Conn {
+            stream: BufReader::new(stream),
+            seq: 0,
+        }
+    }
+
+    fn handshake(
+        &mut self,
+        user: &str,
+        password: &str,
+        get_server_public_key: bool,
+    ) -> Result<(), failure::Error> {
+        let packet = self.read_packet()?;
+        if packet.first() == Some(&0xff) {
+            return Err(parse_err(&packet));
+        }
+        let mut r = Reader::new(&packet);
+        let version = r.u8()?;
+        if version != 10 {
+            bail!("unsupported protocol version {}", version);
+        }
+        r.nul_str()?; // server version
+        r.u32()?; // connection ID
+        let mut scramble = r.bytes(8)?.to_vec();
+        r.u8()?; // filler
+        let mut capabilities = u32::from(r.u16()?);
+        r.u8()?; // character set
+        r.u16()?; // status flags
+        capabilities |= u32::from(r.u16()?) << 16;
+        let scramble_len = r.u8()?;
+        r.bytes(10)?; // reserved
+        if capabilities & CLIENT_PROTOCOL_41 == 0 || capabilities & CLIENT_SECURE_CONNECTION == 0 {
+            bail!("server does not support the 4.1 protocol");
+        }
+        // The rest of the scramble is padded to at least 13 bytes, including
+        // a trailing NUL.
+        let len = std::cmp::max(13, i32::from(scramble_len) - 8) as usize;
+        scramble.extend(r.bytes(len)?.iter().take(12));
+        let mut plugin = if capabilities & CLIENT_PLUGIN_AUTH != 0 {
+            String::from_utf8_lossy(r.nul_str()?).into_owned()
+        } else {
+            "mysql_native_password".into()
+        };
+
+        let auth = scramble_password(&plugin, password, &scramble)?;
+        let mut response = vec![];
+        let flags = CLIENT_LONG_PASSWORD
+            | CLIENT_LONG_FLAG
+            | CLIENT_PROTOCOL_41
+            | CLIENT_TRANSACTIONS
+            | CLIENT_SECURE_CONNECTION
+            | CLIENT_PLUGIN_AUTH;
+        response.extend(&flags.to_le_bytes());
+        response.extend(&(MAX_PAYLOAD as u32).to_le_bytes());
+        response.push(UTF8MB4);
+        response.extend(&[0; 23]);
+        response.extend(user.as_bytes());
+        response.push(0);
+        response.push(auth.len() as u8);
+        response.extend(&auth);
+        response.extend(plugin.as_bytes());
+        response.push(0);
+        self.write_packet(&response)?;
+
+        loop {
+            let packet = self.read_packet()?;
+            match packet.first() {
+                Some(0x00) => return Ok(()),
+                Some(0xff) => return Err(parse_err(&packet)),
+                // An authentication switch request, which names the plugin
+                // to authenticate with instead, and a new scramble.
+                Some(0xfe) => {
+                    let mut r = Reader::new(&packet[1..]);
+                    plugin = String::from_utf8_lossy(r.nul_str()?).into_owned();
+                    scramble = r.rest().iter().take(20).cloned().collect();
+                    let auth = scramble_password(&plugin, password, &scramble)?;
+                    self.write_packet(&auth)?;
+                }
+                // More data from `caching_sha2_password`, which reports
+                // whether the password was found in the server's cache.
+                Some(0x01) if plugin == "caching_sha2_password" => match packet.get(1) {
+                    // Fast authentication succeeded. An OK packet follows.
+                    Some(0x03) => (),
+                    // The password must be sent in full. Without TLS, it is
+                    // encrypted with the server's public key.
+                    Some(0x04) => {
+                        if !get_server_public_key {
+                            bail!(
+                                "the server requires the password to be encrypted with its \
+                                 public key, which is only retrieved over the unencrypted \
+                                 connection if the source URL sets get-server-public-key=true"
+                            );
+                        }
+                        self.write_packet(&[0x02])?;
+                        let key = self.read_packet()?;
+                        if key.first() != Some(&0x01) {
+                            bail!("unable to retrieve the server's public key");
+                        }
+                        let rsa = Rsa::public_key_from_pem(&key[1..])?;
+                        let mut plain: Vec<u8> = password.bytes().chain(Some(0)).collect();
+                        for (i, b) in plain.iter_mut().enumerate() {
+                            *b ^= scramble[i % scramble.len()];
+                        }
+                        let mut encrypted = vec![0; rsa.size() as usize];
+                        let len =
+                            rsa.public_encrypt(&plain, &mut encrypted, Padding::PKCS1_OAEP)?;
+                        self.write_packet(&encrypted[..len])?;
+                    }
+                    _ => bail!("unexpected caching_sha2_password response"),
+                },
+                _ => bail!("unexpected response to authentication"),
+            }
+        }
+    }
+
+    /// Runs `query`, and returns the rows of its result, if any. Each value is
+    /// the text representation of a column's value, or `None` if it is NULL.
+    pub fn query(&mut self, query: &str) -> Result<Vec<Vec<Option<Vec<u8>>>>, failure::Error> {
+        self.seq = 0;
+        let mut command = vec![COM_QUERY];
+        command.extend(query.as_bytes());
+        self.write_packet(&command)?;
+
+        let packet = self.read_packet()?;
+        match packet.first() {
+            Some(0x00) => return Ok(vec![]),
+            Some(0xff) => return Err(parse_err(&packet)),
+            _ => (),
+        }
+        let columns = Reader::new(&packet).lenenc_int()?;
+        for _ in 0..columns {
+            self.read_packet()?;
+        }
+        let packet = self.read_packet()?;
+        if !is_eof(&packet) {
+            bail!("expected EOF after column definitions");
+        }
+
+        let mut rows = vec![];
+        loop {
+            let packet = self.read_packet()?;
+            if is_eof(&packet) {
+                return Ok(rows);
+            } else if packet.first() == Some(&0xff) {
+                return Err(parse_err(&packet));
+            }
+            let mut r = Reader::new(&packet);
+            let mut row = Vec::with_capacity(columns as usize);
+            for _ in 0..columns {
+                if r.peek() == Some(0xfb) {
+                    r.u8()?;
+                    row.push(None);
+                } else {
+                    row.push(Some(r.lenenc_bytes()?.to_vec()));
+                }
+            }
+            rows.push(row);
+        }
+    }
+
+    /// Asks the server to send the events of its binary log, as it would to
+    /// a replica with ID `server_id`, starting at `position` in `file`.
+    ///
+    /// The connection can only be used to read events afterwards.
+    pub fn binlog_dump(
+        &mut self,
+        file: &str,
+        position: u32,
+        server_id: u32,
+    ) -> Result<(), failure::Error> {
+        self.seq = 0;
+        let mut command = vec![COM_BINLOG_DUMP];
+        command.extend(&position.to_le_bytes());
+        command.extend(&0_u16.to_le_bytes()); // flags
+        command.extend(&server_id.to_le_bytes());
+        command.extend(file.as_bytes());
+        self.write_packet(&command)
+    }
+
+    /// Reads the next event of the binary log, after `binlog_dump` has been
+    /// called. Returns `None` if the server has no more events to send.
+    pub fn read_event(&mut self) -> Result<Option<Vec<u8>>, failure::Error> {
+        let mut packet = self.read_packet()?;
+        match packet.first() {
+            Some(0x00) => {
+                packet.remove(0);
+                Ok(Some(packet))
+            }
+            Some(0xff) => Err(parse_err(&packet)),
+            _ if is_eof(&packet) => Ok(None),
+            _ => bail!("unexpected packet in binary log"),
+        }
+    }
+
+    fn read_packet(&mut self) -> Result<Vec<u8>, failure::Error> {
+        let mut payload = vec![];
+        loop {
+            let mut header = [0; 4];
+            self.stream.read_exact(&mut header)?;
+            let len =
+                usize::from(header[0]) | usize::from(header[1]) << 8 | usize::from(header[2]) << 16;
+            self.seq = header[3].wrapping_add(1);
+            let start = payload.len();
+            payload.resize(start + len, 0);
+            self.stream.read_exact(&mut payload[start..])?;
+            if len < MAX_PAYLOAD {
+                return Ok(payload);
+            }
+        }
+    }
+
+    fn write_packet(&mut self, payload: &[u8]) -> Result<(), failure::Error> {
+        let mut chunks = payload.chunks(MAX_PAYLOAD);
+        let mut buf = vec![];
+        loop {
+            let chunk = chunks.next().unwrap_or(&[]);
+            buf.extend(&(chunk.len() as u32).to_le_bytes()[..3]);
+            buf.push(self.seq);
+            buf.extend(chunk);
+            self.seq = self.seq.wrapping_add(1);
+            // A payload whose length is a multiple of the maximum is followed
+            // by an empty packet.
+            if chunk.len() < MAX_PAYLOAD {
+                break;
+            }
+        }
+        self.stream.get_mut().write_all(&buf)?;
+        Ok(())
+    }
+}
+
+/// Computes the response to the authentication challenge `scramble` from
+/// `plugin`.
+fn scramble_password(
+    plugin: &str,
+    password: &str,
+    scramble: &[u8],
+) -> Result<Vec<u8>, failure::Error> {
+    if password.is_empty() {
+        return Ok(vec![]);
+    }
+    let password = password.as_bytes();
+    match plugin {
+        // SHA1(password) XOR SHA1(scramble + SHA1(SHA1(password)))
+        "mysql_native_password" => {
+            let hash = sha1(password);
+            let mut buf = scramble.to_vec();
+            buf.extend(&sha1(&hash));
+            let mask = sha1(&buf);
+            Ok(hash.iter().zip(mask.iter()).map(|(a, b)| a ^ b).collect())
+        }
+        // SHA256(password) XOR SHA256(SHA256(SHA256(password)) + scramble)
+        "caching_sha2_password" => {
+            let hash = sha256(password);
+            let mut buf = sha256(&hash).to_vec();
+            buf.extend(scramble);
+            let mask = sha256(&buf);
+            Ok(hash.iter().zip(mask.iter()).map(|(a, b)| a ^ b).collect())
+        }
+        _ => bail!("unsupported authentication plugin {}", plugin),
+    }
+}
+
+fn is_eof(packet: &[u8]) -> bool {
+    packet.first() == Some(&0xfe) && packet.len() < 9
+}
+
+fn parse_err(packet: &[u8]) -> failure::Error {
+    let mut r = Reader::new(&packet[1..]);
+    let code = r.u16().unwrap_or(0);
+    let mut message = r.rest();
+    // The SQL state, if present, is a `#` followed by five characters.
+    if message.first() == Some(&b'#') && message.len() >= 6 {
+        message = &message[6..];
+    }
+    format_err!("MySQL error {}: {}", code, String::from_utf8_lossy(message))
+}
+
+/// Reads the fields of a packet or event, which are little-endian unless
+/// stated otherwise.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Reader<'a> {
+        Reader { buf }
+    }
+
+    pub fn peek(&self) -> Option<u8> {
+        self.buf.first().cloned()
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], failure::Error> {
+        if self.buf.len() < n {
+            bail!("truncated packet");
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = self.buf;
+        self.buf = &[];
+        rest
+    }
+
+    /// Reads an unsigned integer of `n` bytes.
+    pub fn uint(&mut self, n: usize) -> Result<u64, failure::Error> {
+        Ok(self
+            .bytes(n)?
+            .iter()
+            .rev()
+            .fold(0, |acc, b| acc << 8 | u64::from(*b)))
+    }
+
+    /// Reads a big-endian unsigned integer of `n` bytes.
+    pub fn uint_be(&mut self, n: usize) -> Result<u64, failure::Error> {
+        Ok(self
+            .bytes(n)?
+            .iter()
+            .fold(0, |acc, b| acc << 8 | u64::from(*b)))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, failure::Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, failure::Error> {
+        Ok(self.uint(2)? as u16)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, failure::Error> {
+        Ok(self.uint(4)? as u32)
+    }
+
+    /// Reads a length-encoded integer.
+    pub fn lenenc_int(&mut self) -> Result<u64, failure::Error> {
+        match self.u8()? {
+            0xfc => self.uint(2),
+            0xfd => self.uint(3),
+            0xfe => self.uint(8),
+            b @ 0..=0xfa => Ok(u64::from(b)),
+            b => bail!("invalid length-encoded integer prefix {:#x}", b),
+        }
+    }
+
+    /// Reads a string prefixed with its length, as a length-encoded integer.
+    pub fn lenenc_bytes(&mut self) -> Result<&'a [u8], failure::Error> {
+        let len = self.lenenc_int()?;
+        self.bytes(len as usize)
+    }
+
+    /// Reads a NUL-terminated string.
+    pub fn nul_str(&mut self) -> Result<&'a [u8], failure::Error> {
+        match self.buf.iter().position(|b| *b == 0) {
+            Some(end) => {
+                let s = &self.buf[..end];
+                self.buf = &self.buf[end + 1..];
+                Ok(s)
+            }
+            None => bail!("unterminated string"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Write};
+
+    use openssl::rsa::{Padding, Rsa};
+
+    use super::{scramble_password, Conn, Reader, MAX_PAYLOAD};
+
+    /// A stream that reads what a server would send from a buffer, and records
+    /// what the client sends.
+    struct FakeStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for FakeStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for FakeStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn fake_conn(input: Vec<u8>) -> Conn<FakeStream> {
+        Conn::new(FakeStream {
+            input: Cursor::new(input),
+            output: vec![],
+        })
+    }
+
+    fn packet(seq: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+        packet.push(seq);
+        packet.extend(payload);
+        packet
+    }
+
+    /// Splits what the client sent into the sequence numbers and payloads of
+    /// its packets.
+    fn sent(conn: &Conn<FakeStream>) -> Vec<(u8, Vec<u8>)> {
+        let mut r = Reader::new(&conn.stream.get_ref().output);
+        let mut packets = vec![];
+        while r.peek().is_some() {
+            let len = r.uint(3).unwrap() as usize;
+            let seq = r.u8().unwrap();
+            packets.push((seq, r.bytes(len).unwrap().to_vec()));
+        }
+        packets
+    }
+
+    /// The initial handshake of a MySQL 5.5 server, which authenticates with
+    /// `mysql_native_password`, from the MySQL internals manual.
+    const HANDSHAKE_V10: &[u8] = b"\
+        \x36\x00\x00\x00\x0a\x35\x2e\x35\x2e\x32\x2d\x6d\x32\x00\x0b\x00\
+        \x00\x00\x64\x76\x48\x40\x49\x2d\x43\x4a\x00\xff\xf7\x08\x02\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x2a\x34\x64\
+        \x7c\x63\x5a\x77\x6b\x34\x5e\x5d\x3a\x00";
+
+    /// The scramble of `HANDSHAKE_V10`.
+    const SCRAMBLE_V10: &[u8] = b"dvH@I-CJ*4d|cZwk4^]:";
+
+    /// An OK packet, as sent after authentication succeeds.
+    const OK: &[u8] = b"\x00\x00\x00\x02\x00\x00\x00";
+
+    /// The initial handshake of a MySQL 8.0 server, which authenticates with
+    /// `caching_sha2_password`, and whose scramble is the bytes 1 through 20.
+    fn handshake_caching_sha2() -> Vec<u8> {
+        let mut payload = b"\x0a8.0.19\x00\x08\x00\x00\x00".to_vec();
+        payload.extend(1..=8);
+        payload.extend(b"\x00\xff\xff\xff\x02\x00\xff\xc3\x15");
+        payload.extend(&[0; 10]);
+        payload.extend(9..=20);
+        payload.extend(b"\x00caching_sha2_password\x00");
+        packet(0, &payload)
+    }
+
+    /// Returns the authentication response of a handshake response packet
+    /// from user `root`.
+    fn auth_response(payload: &[u8]) -> &[u8] {
+        let mut r = Reader::new(&payload[32..]);
+        assert_eq!(r.nul_str().unwrap(), b"root");
+        let len = r.u8().unwrap();
+        r.bytes(usize::from(len)).unwrap()
+    }
+
+    #[test]
+    fn test_lenenc_int() -> Result<(), failure::Error> {
+        let cases: &[(&[u8], u64)] = &[
+            (b"\x00", 0),
+            (b"\xfa", 250),
+            (b"\xfc\xfb\x00", 251),
+            (b"\xfc\xff\xff", 0xffff),
+            (b"\xfd\x00\x00\x01", 0x01_0000),
+            (
+                b"\xfe\x01\x02\x03\x04\x05\x06\x07\x08",
+                0x0807_0605_0403_0201,
+            ),
+        ];
+        for (bytes, expected) in cases {
+            let mut r = Reader::new(bytes);
+            assert_eq!(r.lenenc_int()?, *expected);
+            assert_eq!(r.peek(), None);
+        }
+        // 0xfb stands for NULL in a row, and 0xff begins an error packet.
+        for bytes in &[&b"\xfb"[..], b"\xff", b"\xfc\x01", b""] {
+            assert!(Reader::new(bytes).lenenc_int().is_err());
+        }
+        assert_eq!(Reader::new(b"\x03def\x00").lenenc_bytes()?, &b"def"[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_packets() -> Result<(), failure::Error> {
+        // A payload of the maximum length is followed by another packet,
+        // which is empty if the payload ends there.
+        let payload: Vec<u8> = (0..MAX_PAYLOAD + 3).map(|i| i as u8).collect();
+        let mut input = packet(0, &payload[..MAX_PAYLOAD]);
+        input.extend(packet(1, &payload[MAX_PAYLOAD..]));
+        input.extend(packet(2, &payload[..MAX_PAYLOAD]));
+        input.extend(packet(3, &[]));
+        let mut conn = fake_conn(input);
+        assert_eq!(conn.read_packet()?, payload);
+        assert_eq!(conn.read_packet()?, &payload[..MAX_PAYLOAD]);
+        assert_eq!(conn.seq, 4);
+
+        conn.write_packet(&payload[..MAX_PAYLOAD])?;
+        conn.write_packet(b"abc")?;
+        let sent = sent(&conn);
+        assert_eq!(sent.len(), 3);
+        assert_eq!((sent[0].0, sent[0].1.len()), (4, MAX_PAYLOAD));
+        assert_eq!(sent[1], (5, vec![]));
+        assert_eq!(sent[2], (6, b"abc".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_scramble_password() -> Result<(), failure::Error> {
+        assert_eq!(
+            scramble_password("mysql_native_password", "secret", SCRAMBLE_V10)?,
+            b"\xc5\xd5\x3a\xfe\xd8\x96\x4d\x85\xfe\x3e\xc5\x78\x42\xb3\xcd\xb6\xb8\x3b\xb2\xcb"
+        );
+        let scramble: Vec<u8> = (1..=20).collect();
+        assert_eq!(
+            scramble_password("caching_sha2_password", "secret", &scramble)?,
+            &b"\x74\x6e\xbe\x20\x5d\x56\xa0\x70\x7a\xcb\x3e\x79\x6e\x83\x4e\x0d\
+               \xd7\xb1\xd6\x17\x43\xb2\x6b\xd5\x20\x2c\x7a\x62\x32\x30\xc7\xc9"[..]
+        );
+        assert!(scramble_password("mysql_native_password", "", SCRAMBLE_V10)?.is_empty());
+        assert!(scramble_password("sha256_password", "secret", SCRAMBLE_V10).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_native_password() -> Result<(), failure::Error> {
+        let mut input = HANDSHAKE_V10.to_vec();
+        input.extend(packet(2, OK));
+        let mut conn = fake_conn(input);
+        conn.handshake("root", "secret", false)?;
+        let sent = sent(&conn);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, 1);
+        assert_eq!(
+            auth_response(&sent[0].1),
+            &scramble_password("mysql_native_password", "secret", SCRAMBLE_V10)?[..]
+        );
+        assert!(sent[0].1.ends_with(b"mysql_native_password\x00"));
+
+        let mut input = HANDSHAKE_V10.to_vec();
+        input.extend(packet(
+            2,
+            b"\xff\x15\x04#28000Access denied for user 'root'",
+        ));
+        let err = fake_conn(input)
+            .handshake("root", "wrong", false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "MySQL error 1045: Access denied for user 'root'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_caching_sha2_password() -> Result<(), failure::Error> {
+        let scramble: Vec<u8> = (1..=20).collect();
+
+        // The server finds the password in its cache.
+        let mut input = handshake_caching_sha2();
+        input.extend(packet(2, b"\x01\x03"));
+        input.extend(packet(3, OK));
+        let mut conn = fake_conn(input);
+        conn.handshake("root", "secret", false)?;
+        assert_eq!(
+            auth_response(&sent(&conn)[0].1),
+            &scramble_password("caching_sha2_password", "secret", &scramble)?[..]
+        );
+
+        // The server asks for the password in full, which is refused unless
+        // the public key may be retrieved.
+        let key = Rsa::generate(2048)?;
+        let mut input = handshake_caching_sha2();
+        input.extend(packet(2, b"\x01\x04"));
+        let mut public_key = b"\x01".to_vec();
+        public_key.extend(key.public_key_to_pem()?);
+        input.extend(packet(4, &public_key));
+        input.extend(packet(6, OK));
+        let mut refused = fake_conn(input.clone());
+        let err = refused.handshake("root", "secret", false).unwrap_err();
+        assert!(
+            err.to_string().contains("get-server-public-key=true"),
+            "{}",
+            err
+        );
+        // The public key is not even requested.
+        assert_eq!(sent(&refused).len(), 1);
+
+        let mut conn = fake_conn(input);
+        conn.handshake("root", "secret", true)?;
+        let sent = sent(&conn);
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[1], (3, vec![0x02]));
+        assert_eq!(sent[2].0, 5);
+        let mut plain = vec![0; key.size() as usize];
+        let len = key.private_decrypt(&sent[2].1, &mut plain, Padding::PKCS1_OAEP)?;
+        let expected: Vec<u8> = b"secret\x00"
+            .iter()
+            .zip(scramble.iter().cycle())
+            .map(|(a, b)| a ^ b)
+            .collect();
+        assert_eq!(&plain[..len], &expected[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_query() -> Result<(), failure::Error> {
+        // The result of `SELECT @@version_comment LIMIT 1`, from the MySQL
+        // internals manual.
+        let mut input = b"\
+            \x01\x00\x00\x01\x01\
+            \x27\x00\x00\x02\x03\x64\x65\x66\x00\x00\x00\x11\x40\x40\x76\x65\x72\x73\x69\x6f\
+            \x6e\x5f\x63\x6f\x6d\x6d\x65\x6e\x74\x00\x0c\x08\x00\x1c\x00\x00\x00\xfd\x00\x00\
+            \x1f\x00\x00\
+            \x05\x00\x00\x03\xfe\x00\x00\x02\x00\
+            \x1d\x00\x00\x04\x1c\x4d\x79\x53\x51\x4c\x20\x43\x6f\x6d\x6d\x75\x6e\x69\x74\x79\
+            \x20\x53\x65\x72\x76\x65\x72\x20\x28\x47\x50\x4c\x29\
+            \x05\x00\x00\x05\xfe\x00\x00\x02\x00"
+            .to_vec();
+        // A result with two columns, the first of which is NULL.
+        input.extend(packet(1, b"\x02"));
+        input.extend(packet(2, b"\x03def"));
+        input.extend(packet(3, b"\x03def"));
+        input.extend(packet(4, b"\xfe\x00\x00\x02\x00"));
+        input.extend(packet(5, b"\xfb\x011"));
+        input.extend(packet(6, b"\xfe\x00\x00\x02\x00"));
+        // A statement that returns no rows.
+        input.extend(packet(1, OK));
+        // An error.
+        input.extend(packet(1, b"\xff\x48\x04#HY000No tables used"));
+
+        let mut conn = fake_conn(input);
+        assert_eq!(
+            conn.query("SELECT @@version_comment LIMIT 1")?,
+            vec![vec![Some(b"MySQL Community Server (GPL)".to_vec())]]
+        );
+        assert_eq!(
+            conn.query("SELECT NULL, 1")?,
+            vec![vec![None, Some(b"1".to_vec())]]
+        );
+        assert!(conn.query("COMMIT")?.is_empty());
+        let err = conn.query("SELECT *").unwrap_err();
+        assert_eq!(err.to_string(), "MySQL error 1096: No tables used");
+
+        let sent = sent(&conn);
+        assert_eq!(
+            sent[0],
+            (0, b"\x03SELECT @@version_comment LIMIT 1".to_vec())
+        );
+        assert_eq!(sent[3], (0, b"\x03SELECT *".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_event() -> Result<(), failure::Error> {
+        let mut input = packet(1, b"\x00event");
+        input.extend(packet(2, b"\xfe\x00\x00\x02\x00"));
+        input.extend(packet(3, b"\xff\x29\x05#HY000binlog truncated"));
+        let mut conn = fake_conn(input);
+        assert_eq!(conn.read_event()?, Some(b"event".to_vec()));
+        assert_eq!(conn.read_event()?, None);
+        assert!(conn.read_event().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::io;
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use failure::{bail, format_err};
+use log::{error, warn};
+use serde_json::json;
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+
+use dataflow_types::{NatsSourceConnector, Timestamp};
+use expr::SourceInstanceId;
+
+use super::util::source;
+use super::{SourceStatus, SourceToken};
+
+/// A message delivered by a JetStream consumer.
+struct Message {
+    data: Vec<u8>,
+    /// The subject to which the message's acknowledgement must be sent.
+    ack_subject: String,
+    /// The message's sequence number in the stream.
+    sequence: i64,
+}
+
+/// Reads the messages in a JetStream stream through a durable push consumer.
+///
+/// Each message is acknowledged once it has been passed to the dataflow, so
+/// the consumer records how far the source has read, and a source that is
+/// recreated, e.g. when the server restarts, resumes where it left off.
+/// Messages are timestamped with the time at which they are received, and
+/// their stream sequence number is reported as their position.
+pub fn nats<G>(
+    scope: &G,
+    name: String,
+    connector: NatsSourceConnector,
+    id: SourceInstanceId,
+    read_nats: bool,
+) -> (
+    Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    Option<SourceToken>,
+)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    // Update the capability every second if there are no new messages.
+    const HEARTBEAT: Duration = Duration::from_secs(1);
+    const MAX_MESSAGES_PER_INVOCATION: usize = 1024;
+
+    let n2 = name.clone();
+    let (stream, capability) = source(id, None, scope, &name, move |info| {
+        let activator = scope.activator_for(&info.address[..]);
+        let (tx, rx) = mpsc::channel();
+        let (ack_tx, ack_rx) = mpsc::channel();
+        // The consumer thread exits once it notices that this token, which
+        // lives as long as the operator, has been dropped.
+        let alive = Arc::new(());
+        if read_nats {
+            let activator = scope.sync_activator_for(&info.address[..]);
+            let name = n2.clone();
+            let weak = Arc::downgrade(&alive);
+            thread::spawn(move || consume(name, connector, tx, ack_rx, activator, weak));
+        }
+        // JetStream redelivers messages whose acknowledgements are lost, so
+        // skip any message that has already been passed to the dataflow.
+        let mut last_sequence = 0;
+
+        move |cap, output| {
+            let _alive = &alive;
+            let cap_time = *cap.time();
+            let sys_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time seems to be before 1970.")
+                .as_millis() as u64;
+            let mut next_activation_duration = HEARTBEAT;
+            let next_time = if cap_time > sys_time {
+                if cap_time != sys_time + 1 {
+                    warn!(
+                        "{}: fast-forwarding out-of-order Unix timestamp {}ms ({} -> {})",
+                        n2,
+                        cap_time - sys_time,
+                        sys_time,
+                        cap_time,
+                    );
+                }
+                next_activation_duration = Duration::from_millis(cap_time - sys_time);
+                cap_time
+            } else {
+                cap.downgrade(&sys_time);
+                sys_time + 1
+            };
+
+            let mut messages_read = 0;
+            let mut acks = vec![];
+            {
+                let mut session = output.session(cap);
+                while messages_read < MAX_MESSAGES_PER_INVOCATION {
+                    match rx.try_recv() {
+                        Ok(message) => {
+                            messages_read += 1;
+                            if message.sequence > last_sequence {
+                                last_sequence = message.sequence;
+                                session.give((message.data, Some(message.sequence), None));
+                            }
+                            acks.push(message.ack_subject);
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return SourceStatus::Done,
+                    }
+                }
+            }
+            // The messages are now in the dataflow, so the consumer may
+            // forget them.
+            for ack in acks {
+                let _ = ack_tx.send(ack);
+            }
+            if messages_read == MAX_MESSAGES_PER_INVOCATION {
+                next_activation_duration = Default::default();
+            }
+            cap.downgrade(&next_time);
+            activator.activate_after(next_activation_duration);
+            SourceStatus::Alive
+        }
+    });
+
+    if read_nats {
+        (stream, Some(capability))
+    } else {
+        (stream, None)
+    }
+}
+
+/// Binds to the durable consumer and forwards the messages it delivers to
+/// `tx`, acknowledging the messages whose acknowledgement subjects arrive on
+/// `acks`, until `alive` can no longer be upgraded.
+///
+/// If the connection or the consumer cannot be established, or the
+/// connection is lost, the consumer starts over after a delay.
+fn consume(
+    name: String,
+    connector: NatsSourceConnector,
+    tx: mpsc::Sender<Message>,
+    acks: mpsc::Receiver<String>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+) {
+    const RETRY: Duration = Duration::from_secs(5);
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    // The consumer's configuration is stored by the server, so its delivery
+    // subject must not change from one run to the next.
+    let deliver_subject = format!(
+        "_INBOX.materialize.{}.{}",
+        connector.stream, connector.durable
+    );
+
+    while alive.upgrade().is_some() {
+        let conn = match nats::connect(connector.url.as_str()) {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("{}: unable to connect to NATS: {}", name, e);
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        // Subscribe before binding the consumer, so that no deliveries are
+        // missed.
+        let sub = match conn.subscribe(&deliver_subject) {
+            Ok(sub) => sub,
+            Err(e) => {
+                error!("{}: unable to subscribe to NATS: {}", name, e);
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        if let Err(e) = create_consumer(&conn, &connector, &deliver_subject) {
+            error!("{}: unable to create JetStream consumer: {}", name, e);
+            thread::sleep(RETRY);
+            continue;
+        }
+
+        loop {
+            while let Ok(ack) = acks.try_recv() {
+                if let Err(e) = conn.publish(&ack, "+ACK") {
+                    error!("{}: unable to acknowledge NATS message: {}", name, e);
+                }
+            }
+            match sub.next_timeout(POLL_INTERVAL) {
+                Ok(msg) => {
+                    let ack_subject = match msg.reply {
+                        Some(reply) => reply,
+                        None => continue,
+                    };
+                    let sequence = match stream_sequence(&ack_subject) {
+                        Some(sequence) => sequence,
+                        None => {
+                            warn!("{}: ignoring NATS message without a stream sequence", name);
+                            continue;
+                        }
+                    };
+                    let message = Message {
+                        data: msg.data,
+                        ack_subject,
+                        sequence,
+                    };
+                    if tx.send(message).is_err() || activator.activate().is_err() {
+                        return;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    if alive.upgrade().is_none() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    error!("{}: lost connection to NATS: {}", name, e);
+                    thread::sleep(RETRY);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Creates the durable consumer that `connector` names, or binds to it if it
+/// already exists with the same configuration.
+fn create_consumer(
+    conn: &nats::Connection,
+    connector: &NatsSourceConnector,
+    deliver_subject: &str,
+) -> Result<(), failure::Error> {
+    let request = json!({
+        "stream_name": connector.stream,
+        "config": {
+            "durable_name": connector.durable,
+            "deliver_subject": deliver_subject,
+            "deliver_policy": "all",
+            "ack_policy": "explicit",
+            "filter_subject": connector.subject,
+        },
+    });
+    let res = conn.request(
+        &format!(
+            "$JS.API.CONSUMER.DURABLE.CREATE.{}.{}",
+            connector.stream, connector.durable
+        ),
+        request.to_string(),
+    )?;
+    let res: serde_json::Value = serde_json::from_slice(&res.data)?;
+    if let Some(err) = res.get("error") {
+        match err["description"].as_str() {
+            Some(description) => bail!("{}", description),
+            None => return Err(format_err!("{}", err)),
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the stream sequence number from the acknowledgement subject of a
+/// JetStream message, which has the form
+/// `$JS.ACK.<stream>.<consumer>.<delivered>.<stream seq>.<consumer seq>...`.
+fn stream_sequence(ack_subject: &str) -> Option<i64> {
+    ack_subject.split('.').nth(5)?.parse().ok()
+}
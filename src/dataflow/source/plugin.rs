@@ -0,0 +1,267 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Source connectors supplied by integrators.
+//!
+//! A program that embeds `materialized` can compile in connectors to systems
+//! that Materialize does not support natively by implementing
+//! [`SourcePluginFactory`] and calling [`register_source_plugin`] before
+//! starting the server. Sources that name the plugin, as in
+//! `CREATE SOURCE ... FROM PLUGIN 'name'`, are then read through it.
+//!
+//! Plugins produce raw records, which are decoded according to the source's
+//! `FORMAT` like the records of any other source.
+
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use log::{error, warn};
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+
+use dataflow_types::{PluginSourceConnector, Timestamp};
+use expr::SourceInstanceId;
+
+use super::generator::AuctionFactory;
+use super::util::source;
+use super::{SourceStatus, SourceToken};
+
+lazy_static! {
+    static ref SOURCE_PLUGINS: RwLock<HashMap<String, Arc<dyn SourcePluginFactory>>> = {
+        // The load generators are always available.
+        let mut plugins: HashMap<String, Arc<dyn SourcePluginFactory>> = HashMap::new();
+        plugins.insert("auction".into(), Arc::new(AuctionFactory));
+        RwLock::new(plugins)
+    };
+}
+
+/// Makes `factory` available to sources created with
+/// `FROM PLUGIN 'name'`, replacing any plugin previously registered under
+/// `name`.
+///
+/// Plugins must be registered before the server starts, as sources that name
+/// an unregistered plugin fail when the catalog is loaded.
+pub fn register_source_plugin<F>(name: &str, factory: F)
+where
+    F: SourcePluginFactory + 'static,
+{
+    SOURCE_PLUGINS
+        .write()
+        .unwrap()
+        .insert(name.to_owned(), Arc::new(factory));
+}
+
+/// Returns the plugin registered under `name`, if any.
+pub fn source_plugin(name: &str) -> Option<Arc<dyn SourcePluginFactory>> {
+    SOURCE_PLUGINS.read().unwrap().get(name).cloned()
+}
+
+/// Constructs the [`SourcePlugin`]s that read a source.
+pub trait SourcePluginFactory: Send + Sync {
+    /// Checks that `options`, which come from the `WITH` clause of a
+    /// `CREATE SOURCE` statement, are valid for this plugin.
+    ///
+    /// This is called when a source is created and each time the catalog is
+    /// loaded, so that invalid sources are rejected before any worker
+    /// attempts to read them.
+    fn validate(&self, options: &BTreeMap<String, String>) -> Result<(), failure::Error>;
+
+    /// Constructs the instance of the plugin that runs on worker
+    /// `worker_index` of `worker_count`.
+    ///
+    /// Each worker's instance must read a disjoint share of the source's
+    /// records. A plugin that cannot divide its records among workers should
+    /// return `None` for all workers but one.
+    fn create(
+        &self,
+        options: &BTreeMap<String, String>,
+        worker_index: usize,
+        worker_count: usize,
+    ) -> Result<Option<Box<dyn SourcePlugin>>, failure::Error>;
+}
+
+/// A record read by a [`SourcePlugin`].
+#[derive(Clone, Debug)]
+pub struct SourceRecord {
+    /// The undecoded contents of the record.
+    pub data: Vec<u8>,
+    /// The position of the record in the source, like a Kafka offset, which
+    /// is exposed as the source's `mz_offset` column.
+    pub position: Option<i64>,
+    /// The time at which the record occurred, in milliseconds since the Unix
+    /// epoch.
+    pub time: Timestamp,
+}
+
+/// One worker's reader for a source.
+///
+/// A plugin is driven by a Timely operator, so none of its methods may block.
+/// The operator polls the plugin when it is woken via the [`SourceWaker`]
+/// passed to [`SourcePlugin::start`], and at least once a second otherwise.
+///
+/// Timestamps are the plugin's responsibility. Every record it returns must
+/// have a time no earlier than the last watermark it reported; records that
+/// violate this are emitted at the watermark instead. If a source is read by
+/// more than one dataflow, e.g. because it is used by views on several
+/// clusters, each dataflow reads it through a separate instance of the
+/// plugin, so plugins whose timestamps are not deterministic will present
+/// each dataflow with slightly different data.
+pub trait SourcePlugin {
+    /// Begins reading from the external system.
+    ///
+    /// `waker` may be stored and used, from any thread, to request that the
+    /// plugin be polled, e.g. when new records arrive.
+    fn start(&mut self, waker: SourceWaker) -> Result<(), failure::Error>;
+
+    /// Returns the records that are available without blocking, or `None` if
+    /// the source is exhausted and will never produce records again.
+    ///
+    /// An error is logged and the plugin polled again later, so errors should
+    /// be reported only once the plugin is able to retry.
+    fn poll_batch(&mut self) -> Result<Option<Vec<SourceRecord>>, failure::Error>;
+
+    /// Returns a time no later than the time of any record that the plugin
+    /// will return in the future.
+    ///
+    /// Advancing the watermark is what allows queries to observe the
+    /// plugin's records, so a plugin whose source is idle should still
+    /// advance its watermark, e.g. to the current time.
+    fn watermark(&self) -> Timestamp;
+
+    /// Informs the plugin that all records with times earlier than `upper`
+    /// have been passed to the dataflow.
+    ///
+    /// Materialize does not yet persist the data it ingests, so a plugin
+    /// must not discard records from the external system on the basis of a
+    /// checkpoint if it expects to be able to read them again after a
+    /// restart.
+    fn checkpoint(&mut self, _upper: Timestamp) {}
+}
+
+/// Requests that a [`SourcePlugin`] be polled.
+#[derive(Clone)]
+pub struct SourceWaker(Arc<Mutex<SyncActivator>>);
+
+impl SourceWaker {
+    /// Schedules the plugin to be polled. Has no effect if the source has
+    /// been dropped.
+    pub fn wake(&self) {
+        let _ = self.0.lock().unwrap().activate();
+    }
+}
+
+/// Reads a source through the plugin that its connector names.
+pub fn plugin<G>(
+    scope: &G,
+    name: String,
+    connector: PluginSourceConnector,
+    id: SourceInstanceId,
+    worker_index: usize,
+    worker_count: usize,
+) -> (
+    Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    Option<SourceToken>,
+)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    const HEARTBEAT: Duration = Duration::from_secs(1);
+
+    let plugin = match source_plugin(&connector.name) {
+        Some(factory) => factory.create(&connector.options, worker_index, worker_count),
+        None => Err(failure::format_err!(
+            "source plugin '{}' is not registered",
+            connector.name
+        )),
+    };
+    let mut plugin = match plugin {
+        Ok(plugin) => plugin,
+        Err(e) => {
+            error!("unable to start source {}: {}", name, e);
+            None
+        }
+    };
+    let read = plugin.is_some();
+
+    let (stream, capability) = source(id, None, scope, &name.clone(), move |info| {
+        let activator = scope.activator_for(&info.address[..]);
+        if let Some(p) = plugin.as_mut() {
+            let waker = SourceWaker(Arc::new(Mutex::new(
+                scope.sync_activator_for(&info.address[..]),
+            )));
+            if let Err(e) = p.start(waker) {
+                error!("unable to start source {}: {}", name, e);
+                plugin = None;
+            }
+        }
+
+        move |cap, output| {
+            let plugin = match plugin.as_mut() {
+                Some(plugin) => plugin,
+                None => return SourceStatus::Done,
+            };
+
+            // Drain the available records, ceasing after 10 milliseconds so
+            // that other operators get a chance to run.
+            let timer = Instant::now();
+            loop {
+                match plugin.poll_batch() {
+                    Ok(Some(records)) if records.is_empty() => break,
+                    Ok(Some(records)) => {
+                        for record in records {
+                            let time = if record.time < *cap.time() {
+                                warn!(
+                                    "{}: fast-forwarding out-of-order timestamp {}ms ({} -> {})",
+                                    name,
+                                    cap.time() - record.time,
+                                    record.time,
+                                    cap.time(),
+                                );
+                                *cap.time()
+                            } else {
+                                record.time
+                            };
+                            output.session(&cap.delayed(&time)).give((
+                                record.data,
+                                record.position,
+                                None,
+                            ));
+                        }
+                    }
+                    Ok(None) => return SourceStatus::Done,
+                    Err(e) => {
+                        error!("source {} failed to read: {}", name, e);
+                        break;
+                    }
+                }
+                if timer.elapsed().as_millis() > 10 {
+                    activator.activate();
+                    break;
+                }
+            }
+
+            let watermark = cmp::max(plugin.watermark(), *cap.time());
+            if watermark > *cap.time() {
+                cap.downgrade(&watermark);
+                plugin.checkpoint(watermark);
+            }
+            activator.activate_after(HEARTBEAT);
+            SourceStatus::Alive
+        }
+    });
+
+    if read {
+        (stream, Some(capability))
+    } else {
+        (stream, None)
+    }
+}
@@ -0,0 +1,472 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::mpsc;
+use std::sync::Weak;
+use std::thread;
+use std::time::Duration;
+
+use failure::{bail, format_err};
+use postgres::{Client, IsolationLevel, NoTls, SimpleQueryMessage};
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+use uuid::Uuid;
+
+use dataflow_types::{Diff, PostgresSourceConnector, Timestamp};
+use expr::SourceInstanceId;
+use repr::{Datum, RelationDesc, Row, RowPacker, ScalarType};
+
+use super::util::{batch_source, Batch};
+use super::SourceToken;
+use crate::decode::pack_field;
+
+/// Reads the rows of the PostgreSQL table that `connector` names, and then
+/// follows the changes to the table.
+///
+/// The source creates a temporary logical replication slot, which reports
+/// every change that is committed after the slot is created, and then reads
+/// the table as of a snapshot taken after the slot is created. Transactions
+/// that the snapshot already reflects are skipped when the slot reports them,
+/// so that every change is applied exactly once. The contents of the table,
+/// and the changes of each transaction, are timestamped together.
+///
+/// Like MQTT sources, PostgreSQL sources decode their own records, as the
+/// changes that the slot reports describe rows rather than byte strings.
+///
+/// The slot disappears when the connection that created it closes, so the
+/// source cannot resume after it loses its connection. It instead reports an
+/// error and stops advancing.
+pub fn postgres<G>(
+    scope: &G,
+    name: String,
+    connector: PostgresSourceConnector,
+    desc: RelationDesc,
+    id: SourceInstanceId,
+    read_postgres: bool,
+) -> (Stream<G, (Row, Timestamp, Diff)>, Option<SourceToken>)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    batch_source(
+        scope,
+        name,
+        id,
+        read_postgres,
+        move |tx, activator, alive| replicate(&connector, &desc, tx, activator, alive),
+    )
+}
+
+/// Sends the contents of the table and then the changes to it to `tx`, until
+/// `alive` can no longer be upgraded.
+fn replicate(
+    connector: &PostgresSourceConnector,
+    desc: &RelationDesc,
+    tx: mpsc::Sender<Batch>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+) -> Result<(), failure::Error> {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    let columns = desc
+        .iter()
+        .map(|(name, typ)| match name {
+            Some(name) => Ok((name.as_str().to_owned(), typ.scalar_type.clone())),
+            None => bail!("PostgreSQL source has an unnamed column"),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut client = Client::connect(&connector.conn, NoTls)?;
+
+    // The slot reports changes under the schema-qualified name of the table,
+    // quoted only where necessary. The snapshot query quotes the schema and
+    // table names itself.
+    let row = client.query_one(
+        "SELECT n.nspname, c.relname, quote_ident(n.nspname) || '.' || quote_ident(c.relname) \
+         FROM pg_catalog.pg_class c \
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.oid = $1::text::regclass",
+        &[&connector.table],
+    )?;
+    let (schema, relname, table): (String, String, String) = (row.get(0), row.get(1), row.get(2));
+
+    // The slot must exist before the snapshot is taken, so that every change
+    // that the snapshot misses is reported by the slot.
+    let slot = format!("materialize_{}", Uuid::new_v4().to_simple());
+    client.execute(
+        "SELECT pg_create_logical_replication_slot($1, 'test_decoding', true)",
+        &[&slot],
+    )?;
+
+    let mut txn = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()?;
+    let snapshot: String = txn
+        .query_one("SELECT txid_current_snapshot()::text", &[])?
+        .get(0);
+    let snapshot = Snapshot::parse(&snapshot)?;
+    let select = format!(
+        "SELECT {} FROM {}.{}",
+        columns
+            .iter()
+            .map(|(name, _)| quote_ident(name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        quote_ident(&schema),
+        quote_ident(&relname),
+    );
+    let mut batch = vec![];
+    for message in txn.simple_query(&select)? {
+        if let SimpleQueryMessage::Row(row) = message {
+            let values = (0..columns.len()).map(|i| Value::from(row.get(i)));
+            batch.push((pack_row(&columns, values)?, 1));
+        }
+    }
+    txn.commit()?;
+    if tx.send(batch).is_err() || activator.activate().is_err() {
+        return Ok(());
+    }
+
+    let prefix = format!("table {}: ", table);
+    // Whether the transaction whose changes are being reported is already
+    // reflected in the snapshot, and the changes that it made.
+    let mut skip = false;
+    let mut batch = vec![];
+    while alive.upgrade().is_some() {
+        let changes = client.query(
+            "SELECT data FROM pg_logical_slot_get_changes($1, NULL, NULL, \
+             'include-xids', '1', 'skip-empty-xacts', '1')",
+            &[&slot],
+        )?;
+        if changes.is_empty() {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        for change in changes {
+            let data: String = change.get(0);
+            if data.starts_with("BEGIN ") {
+                skip = snapshot.is_visible(parse_xid(&data["BEGIN ".len()..])?);
+            } else if data.starts_with("COMMIT ") {
+                if !batch.is_empty() {
+                    if tx.send(batch).is_err() || activator.activate().is_err() {
+                        return Ok(());
+                    }
+                    batch = vec![];
+                }
+            } else if data.starts_with(&prefix) && !skip {
+                let change = &data[prefix.len()..];
+                decode_change(&columns, change, &mut batch)
+                    .map_err(|e| format_err!("decoding change {:?}: {}", change, e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quotes `name` for use as an identifier in a query.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The transactions whose changes a snapshot reflects, as reported by
+/// `txid_current_snapshot`.
+struct Snapshot {
+    /// The first transaction ID that the snapshot does not reflect.
+    xmax: u32,
+    /// The transactions below `xmax` that were in progress when the snapshot
+    /// was taken, and so are not reflected.
+    in_progress: HashSet<u32>,
+}
+
+impl Snapshot {
+    /// Parses a snapshot of the form `xmin:xmax:xip,...`.
+    ///
+    /// The slot reports 32-bit transaction IDs, without the epoch that
+    /// `txid_current_snapshot` includes, so only the low 32 bits of each ID
+    /// are kept.
+    fn parse(s: &str) -> Result<Snapshot, failure::Error> {
+        let fields: Vec<_> = s.split(':').collect();
+        if fields.len() != 3 {
+            bail!("invalid snapshot {:?}", s);
+        }
+        let xmax = parse_xid(fields[1])?;
+        let mut in_progress = HashSet::new();
+        for xid in fields[2].split(',').filter(|xid| !xid.is_empty()) {
+            in_progress.insert(parse_xid(xid)?);
+        }
+        Ok(Snapshot { xmax, in_progress })
+    }
+
+    fn is_visible(&self, xid: u32) -> bool {
+        // Transaction IDs wrap around, and are compared modulo 2^32.
+        (xid.wrapping_sub(self.xmax) as i32) < 0 && !self.in_progress.contains(&xid)
+    }
+}
+
+fn parse_xid(s: &str) -> Result<u32, failure::Error> {
+    let xid: u64 = s
+        .trim()
+        .parse()
+        .map_err(|e| format_err!("invalid transaction ID {:?}: {}", s, e))?;
+    Ok(xid as u32)
+}
+
+/// The value of a column in a change that the slot reports.
+#[derive(Clone)]
+enum Value<'a> {
+    Null,
+    Text(Cow<'a, str>),
+    /// A TOASTed value that an update left unchanged, which the slot omits.
+    UnchangedToast,
+}
+
+impl<'a> From<Option<&'a str>> for Value<'a> {
+    fn from(value: Option<&'a str>) -> Value<'a> {
+        match value {
+            Some(value) => Value::Text(Cow::Borrowed(value)),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Decodes one change of the form `ACTION: tuple`, as reported by the
+/// `test_decoding` plugin, into `batch`.
+fn decode_change(
+    columns: &[(String, ScalarType)],
+    change: &str,
+    batch: &mut Batch,
+) -> Result<(), failure::Error> {
+    let colon = change
+        .find(": ")
+        .ok_or_else(|| format_err!("missing action"))?;
+    let (action, rest) = (&change[..colon], &change[colon + 2..]);
+    match action {
+        "INSERT" => {
+            let new = parse_tuple(columns, rest)?;
+            batch.push((pack_row(columns, new)?, 1));
+        }
+        "DELETE" => {
+            if rest == "(no-tuple-data)" {
+                bail!("the table must have REPLICA IDENTITY FULL");
+            }
+            let old = parse_tuple(columns, rest)?;
+            batch.push((pack_row(columns, old)?, -1));
+        }
+        "UPDATE" => {
+            const OLD: &str = "old-key: ";
+            const NEW: &str = " new-tuple: ";
+            if !rest.starts_with(OLD) {
+                bail!("the table must have REPLICA IDENTITY FULL");
+            }
+            let split = rest
+                .find(NEW)
+                .ok_or_else(|| format_err!("missing new tuple"))?;
+            let old = parse_tuple(columns, &rest[OLD.len()..split])?;
+            let mut new = parse_tuple(columns, &rest[split + NEW.len()..])?;
+            for (new, old) in new.iter_mut().zip(&old) {
+                if let Value::UnchangedToast = new {
+                    *new = old.clone();
+                }
+            }
+            batch.push((pack_row(columns, old)?, -1));
+            batch.push((pack_row(columns, new)?, 1));
+        }
+        "TRUNCATE" => bail!("the table was truncated, which PostgreSQL sources do not support"),
+        _ => bail!("unknown action {}", action),
+    }
+    Ok(())
+}
+
+/// Parses a tuple of the form `name[type]:value ...` into the values of
+/// `columns`. Columns of the table that the source does not know about are
+/// ignored.
+///
+/// Names are quoted where necessary, and values are quoted unless they are
+/// numbers, booleans, or null.
+fn parse_tuple<'a>(
+    columns: &[(String, ScalarType)],
+    mut s: &'a str,
+) -> Result<Vec<Value<'a>>, failure::Error> {
+    let mut values = vec![None; columns.len()];
+    while !s.is_empty() {
+        let (name, rest) = if s.starts_with('"') {
+            parse_quoted(&s[1..], '"')?
+        } else {
+            let end = s
+                .find('[')
+                .ok_or_else(|| format_err!("missing column type"))?;
+            (s[..end].to_owned(), &s[end..])
+        };
+        // Array types end in `[]`, so the type ends at the first `]:`.
+        if !rest.starts_with('[') {
+            bail!("missing column type");
+        }
+        let end = rest
+            .find("]:")
+            .ok_or_else(|| format_err!("unterminated column type"))?;
+        let rest = &rest[end + 2..];
+        let (value, rest) = if rest.starts_with('\'') {
+            let (value, rest) = parse_quoted(&rest[1..], '\'')?;
+            (Value::Text(Cow::Owned(value)), rest)
+        } else {
+            let end = rest.find(' ').unwrap_or(rest.len());
+            let value = match &rest[..end] {
+                "null" => Value::Null,
+                "unchanged-toast-datum" => Value::UnchangedToast,
+                value => Value::Text(Cow::Borrowed(value)),
+            };
+            (value, &rest[end..])
+        };
+        if let Some(i) = columns.iter().position(|(n, _)| *n == name) {
+            values[i] = Some(value);
+        }
+        s = rest.trim_start_matches(' ');
+    }
+    values
+        .into_iter()
+        .zip(columns)
+        .map(|(value, (name, _))| value.ok_or_else(|| format_err!("missing column {}", name)))
+        .collect()
+}
+
+/// Parses the quoted string that begins `s`, after its opening quote, and
+/// returns its contents along with the rest of `s`. A doubled quote stands
+/// for a single quote within the string.
+fn parse_quoted(s: &str, quote: char) -> Result<(String, &str), failure::Error> {
+    let mut out = String::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            match chars.peek() {
+                Some((_, c)) if *c == quote => {
+                    chars.next();
+                }
+                _ => return Ok((out, &s[i + 1..])),
+            }
+        }
+        out.push(c);
+    }
+    bail!("unterminated quoted string")
+}
+
+fn pack_row<'a, I>(columns: &[(String, ScalarType)], values: I) -> Result<Row, failure::Error>
+where
+    I: IntoIterator<Item = Value<'a>>,
+{
+    let mut packer = RowPacker::new();
+    for ((name, typ), value) in columns.iter().zip(values) {
+        match value {
+            Value::Null => packer.push(Datum::Null),
+            // The old value is only absent if the old tuple was not logged
+            // in full.
+            Value::UnchangedToast => bail!("column {} has an unknown value", name),
+            Value::Text(text) => pack_field(&mut packer, &text, typ.clone())
+                .map_err(|e| format_err!("column {}: {}", name, e))?,
+        }
+    }
+    Ok(packer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use repr::{Datum, Row, ScalarType};
+
+    use super::{decode_change, quote_ident, Batch, Snapshot};
+
+    fn columns() -> Vec<(String, ScalarType)> {
+        vec![
+            ("a".into(), ScalarType::Int32),
+            ("b c".into(), ScalarType::String),
+        ]
+    }
+
+    fn row(a: i32, b: Option<&str>) -> Row {
+        Row::pack(&[Datum::Int32(a), b.map(Datum::String).unwrap_or(Datum::Null)])
+    }
+
+    fn decode(change: &str) -> Result<Batch, failure::Error> {
+        let mut batch = vec![];
+        decode_change(&columns(), change, &mut batch)?;
+        Ok(batch)
+    }
+
+    #[test]
+    fn test_decode_change() -> Result<(), failure::Error> {
+        assert_eq!(
+            decode(r#"INSERT: a[integer]:1 "b c"[text]:'it''s'"#)?,
+            vec![(row(1, Some("it's")), 1)]
+        );
+        // Columns that the source does not know about are ignored.
+        assert_eq!(
+            decode(r#"INSERT: a[integer]:2 d[integer[]]:'{1,2}' "b c"[text]:null"#)?,
+            vec![(row(2, None), 1)]
+        );
+        assert_eq!(
+            decode(r#"DELETE: a[integer]:1 "b c"[text]:'x y'"#)?,
+            vec![(row(1, Some("x y")), -1)]
+        );
+        assert_eq!(
+            decode(
+                r#"UPDATE: old-key: a[integer]:1 "b c"[text]:'x' new-tuple: a[integer]:2 "b c"[text]:'y'"#
+            )?,
+            vec![(row(1, Some("x")), -1), (row(2, Some("y")), 1)]
+        );
+        // An unchanged TOASTed value keeps its old value.
+        assert_eq!(
+            decode(
+                r#"UPDATE: old-key: a[integer]:1 "b c"[text]:'x' new-tuple: a[integer]:2 "b c"[text]:unchanged-toast-datum"#
+            )?,
+            vec![(row(1, Some("x")), -1), (row(2, Some("x")), 1)]
+        );
+
+        // Without REPLICA IDENTITY FULL, the old tuple is incomplete.
+        for change in &[
+            "DELETE: (no-tuple-data)",
+            r#"UPDATE: a[integer]:2 "b c"[text]:'y'"#,
+        ] {
+            let err = decode(change).unwrap_err().to_string();
+            assert!(err.contains("REPLICA IDENTITY FULL"), "{}", err);
+        }
+        assert!(decode("INSERT: a[integer]:1").is_err());
+        assert!(decode(r#"INSERT: a[integer]:1 "b c"[text]:'x"#).is_err());
+        assert!(decode("TRUNCATE: (no-flags)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot() -> Result<(), failure::Error> {
+        let snapshot = Snapshot::parse("10:20:12,15")?;
+        assert!(snapshot.is_visible(11));
+        assert!(!snapshot.is_visible(12));
+        assert!(!snapshot.is_visible(15));
+        assert!(snapshot.is_visible(19));
+        assert!(!snapshot.is_visible(20));
+        assert!(!snapshot.is_visible(25));
+
+        // Only the low 32 bits of each ID are kept, and IDs are compared
+        // modulo 2^32, so transactions just before the wraparound are visible
+        // to a snapshot taken just after it.
+        let snapshot = Snapshot::parse("4294967290:4294967310:")?;
+        assert!(snapshot.is_visible(u32::max_value()));
+        assert!(snapshot.is_visible(13));
+        assert!(!snapshot.is_visible(14));
+
+        assert!(Snapshot::parse("10:20").is_err());
+        assert!(Snapshot::parse("10:x:").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("t"), r#""t""#);
+        assert_eq!(quote_ident(r#"my "t""#), r#""my ""t""""#);
+    }
+}
@@ -0,0 +1,720 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::Utc;
+use failure::{bail, format_err};
+use log::{error, warn};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sha;
+use openssl::sign::Signer;
+use regex::Regex;
+use reqwest::blocking::{Client, Response};
+use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
+use url::Url;
+
+use dataflow_types::{S3SourceConnector, Timestamp};
+use expr::SourceInstanceId;
+
+use super::util::source;
+use super::{SourceStatus, SourceToken};
+
+/// Reads the lines of the objects in an S3 bucket.
+///
+/// The bucket is listed once, when the source is created, and each object
+/// that the connector selects is read in its entirety. If the connector names
+/// an SQS queue, the source then reads each object that the bucket's
+/// notifications report as created, and otherwise it completes once the
+/// listed objects have been read. Lines are timestamped with the time at
+/// which they are received, and numbered in the order in which they are read.
+pub fn s3<G>(
+    scope: &G,
+    name: String,
+    connector: S3SourceConnector,
+    id: SourceInstanceId,
+    read_s3: bool,
+) -> (
+    Stream<G, (Vec<u8>, Option<i64>, Option<i32>)>,
+    Option<SourceToken>,
+)
+where
+    G: Scope<Timestamp = Timestamp>,
+{
+    // Update the capability every second if there are no new lines.
+    const HEARTBEAT: Duration = Duration::from_secs(1);
+    const MAX_LINES_PER_INVOCATION: usize = 1024;
+
+    let n2 = name.clone();
+    let (stream, capability) = source(id, None, scope, &name, move |info| {
+        let activator = scope.activator_for(&info.address[..]);
+        let (tx, rx) = mpsc::channel();
+        // The reader thread exits once it notices that this token, which
+        // lives as long as the operator, has been dropped.
+        let alive = Arc::new(());
+        if read_s3 {
+            let activator = scope.sync_activator_for(&info.address[..]);
+            let name = n2.clone();
+            let weak = Arc::downgrade(&alive);
+            thread::spawn(move || ingest(name, connector, tx, activator, weak));
+        }
+        let mut line_no = 0;
+
+        move |cap, output| {
+            let _alive = &alive;
+            let cap_time = *cap.time();
+            let sys_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time seems to be before 1970.")
+                .as_millis() as u64;
+            let mut next_activation_duration = HEARTBEAT;
+            let next_time = if cap_time > sys_time {
+                if cap_time != sys_time + 1 {
+                    warn!(
+                        "{}: fast-forwarding out-of-order Unix timestamp {}ms ({} -> {})",
+                        n2,
+                        cap_time - sys_time,
+                        sys_time,
+                        cap_time,
+                    );
+                }
+                next_activation_duration = Duration::from_millis(cap_time - sys_time);
+                cap_time
+            } else {
+                cap.downgrade(&sys_time);
+                sys_time + 1
+            };
+
+            let mut lines_read = 0;
+            {
+                let mut session = output.session(cap);
+                while lines_read < MAX_LINES_PER_INVOCATION {
+                    match rx.try_recv() {
+                        Ok(line) => {
+                            lines_read += 1;
+                            line_no += 1;
+                            session.give((line, Some(line_no), None));
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return SourceStatus::Done,
+                    }
+                }
+            }
+            if lines_read == MAX_LINES_PER_INVOCATION {
+                next_activation_duration = Default::default();
+            }
+            cap.downgrade(&next_time);
+            activator.activate_after(next_activation_duration);
+            SourceStatus::Alive
+        }
+    });
+
+    if read_s3 {
+        (stream, Some(capability))
+    } else {
+        (stream, None)
+    }
+}
+
+/// Reads the objects that `connector` selects and forwards their lines to
+/// `tx`, until `alive` can no longer be upgraded.
+///
+/// Requests that fail are retried after a delay. Each object is read at most
+/// once, even if it is both listed and reported by a notification.
+fn ingest(
+    name: String,
+    connector: S3SourceConnector,
+    tx: mpsc::Sender<Vec<u8>>,
+    activator: SyncActivator,
+    alive: Weak<()>,
+) {
+    const RETRY: Duration = Duration::from_secs(5);
+
+    let client = match AwsClient::new(&connector) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("{}: unable to create S3 client: {}", name, e);
+            return;
+        }
+    };
+    let pattern = connector.pattern.as_deref().map(glob_regex);
+    let selects = |key: &str| {
+        key.starts_with(&connector.prefix) && pattern.as_ref().map_or(true, |p| p.is_match(key))
+    };
+    let mut read = HashSet::new();
+    let mut read_object = |key: String| -> Result<bool, failure::Error> {
+        if read.contains(&key) {
+            return Ok(true);
+        }
+        let body = client.get_object(&key)?;
+        for line in lines(&body) {
+            if tx.send(line.to_vec()).is_err() {
+                return Ok(false);
+            }
+        }
+        if activator.activate().is_err() {
+            return Ok(false);
+        }
+        read.insert(key);
+        Ok(true)
+    };
+
+    // Read the objects that are already in the bucket. If listing fails
+    // partway, the objects that were read are skipped when it is retried.
+    let mut listed = false;
+    while !listed {
+        if alive.upgrade().is_none() {
+            return;
+        }
+        let keys = match client.list_objects(&connector.prefix) {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("{}: unable to list S3 bucket: {}", name, e);
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        listed = true;
+        for key in keys.into_iter().filter(|key| selects(key)) {
+            match read_object(key) {
+                Ok(true) => (),
+                Ok(false) => return,
+                Err(e) => {
+                    error!("{}: unable to read S3 object: {}", name, e);
+                    thread::sleep(RETRY);
+                    listed = false;
+                    break;
+                }
+            }
+        }
+    }
+
+    let queue = match &connector.sqs_queue {
+        Some(queue) => queue,
+        None => return,
+    };
+    while alive.upgrade().is_some() {
+        let messages = match client.receive_messages(queue) {
+            Ok(messages) => messages,
+            Err(e) => {
+                error!("{}: unable to receive SQS messages: {}", name, e);
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        for message in messages {
+            let keys = match created_objects(&message.body, &connector.bucket) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    // The message is deleted regardless, as it would
+                    // otherwise be redelivered forever.
+                    warn!("{}: ignoring unrecognized SQS message: {}", name, e);
+                    vec![]
+                }
+            };
+            let mut done = true;
+            for key in keys.into_iter().filter(|key| selects(key)) {
+                match read_object(key) {
+                    Ok(true) => (),
+                    Ok(false) => return,
+                    Err(e) => {
+                        // The message is left in the queue, which redelivers
+                        // it once its visibility timeout expires.
+                        error!("{}: unable to read S3 object: {}", name, e);
+                        done = false;
+                        break;
+                    }
+                }
+            }
+            if done {
+                if let Err(e) = client.delete_message(queue, &message.receipt_handle) {
+                    error!("{}: unable to delete SQS message: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+/// Splits `body` into lines, which may end with either `\n` or `\r\n`.
+fn lines(body: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let body = match body.last() {
+        Some(b'\n') => &body[..body.len() - 1],
+        _ => body,
+    };
+    let empty = body.is_empty();
+    body.split(|b| *b == b'\n')
+        .filter(move |_| !empty)
+        .map(|line| match line.last() {
+            Some(b'\r') => &line[..line.len() - 1],
+            _ => line,
+        })
+}
+
+/// Translates a glob pattern, in which `*` matches any sequence of characters
+/// and `?` matches any one character, into an anchored regex.
+fn glob_regex(pattern: &str) -> Regex {
+    let mut re = String::from("(?s)^");
+    for c in pattern.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).expect("escaped glob pattern is a valid regex")
+}
+
+/// Extracts the keys of the objects in `bucket` that an S3 event
+/// notification reports as created.
+///
+/// The notification may arrive directly from S3, or wrapped in an SNS
+/// notification by a topic that relays the bucket's notifications to the
+/// queue. Test events, which S3 sends when notifications are configured,
+/// report no objects.
+fn created_objects(body: &str, bucket: &str) -> Result<Vec<String>, failure::Error> {
+    let mut event: serde_json::Value = serde_json::from_str(body)?;
+    if event["Type"] == "Notification" {
+        match event["Message"].as_str() {
+            Some(message) => event = serde_json::from_str(message)?,
+            None => bail!("SNS notification without a message"),
+        }
+    }
+    if event["Event"] == "s3:TestEvent" {
+        return Ok(vec![]);
+    }
+    let records = match event["Records"].as_array() {
+        Some(records) => records,
+        None => bail!("not an S3 event notification"),
+    };
+    let mut keys = vec![];
+    for record in records {
+        let created = record["eventName"]
+            .as_str()
+            .map_or(false, |name| name.starts_with("ObjectCreated:"));
+        if !created || record["s3"]["bucket"]["name"] != bucket {
+            continue;
+        }
+        match record["s3"]["object"]["key"].as_str() {
+            // Keys in notifications are URL-encoded, with spaces as `+`.
+            Some(key) => keys.push(url_decode(&key.replace('+', " "))?),
+            None => bail!("S3 event notification without an object key"),
+        }
+    }
+    Ok(keys)
+}
+
+/// Decodes the percent-encoded octets in `s`.
+fn url_decode(s: &str) -> Result<String, failure::Error> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+            let hex = String::from_utf8(hex)?;
+            match u8::from_str_radix(&hex, 16) {
+                Ok(b) => out.push(b),
+                Err(_) => bail!("invalid percent-encoding in {}", s),
+            }
+        } else {
+            out.push(b);
+        }
+    }
+    Ok(String::from_utf8(out)?)
+}
+
+/// A message received from an SQS queue.
+struct SqsMessage {
+    body: String,
+    /// The handle with which to delete the message from the queue.
+    receipt_handle: String,
+}
+
+/// A client for the S3 and SQS APIs that signs its requests with AWS
+/// Signature Version 4.
+struct AwsClient {
+    http: Client,
+    /// The URL of the bucket, addressed in path style.
+    bucket_url: Url,
+    region: String,
+    access_key: String,
+    secret_access_key: String,
+}
+
+impl AwsClient {
+    fn new(connector: &S3SourceConnector) -> Result<AwsClient, failure::Error> {
+        let endpoint = match &connector.endpoint {
+            Some(endpoint) => endpoint.as_str().trim_end_matches('/').to_owned(),
+            None => format!("https://s3.{}.amazonaws.com", connector.region),
+        };
+        let bucket_url = format!("{}/{}", endpoint, uri_encode(&connector.bucket, true));
+        Ok(AwsClient {
+            // Receiving SQS messages waits up to 20 seconds for a message to
+            // arrive.
+            http: Client::builder().timeout(Duration::from_secs(60)).build()?,
+            bucket_url: bucket_url.parse()?,
+            region: connector.region.clone(),
+            access_key: connector.access_key.clone(),
+            secret_access_key: connector.secret_access_key.clone(),
+        })
+    }
+
+    /// Lists the keys of the objects in the bucket that begin with `prefix`.
+    fn list_objects(&self, prefix: &str) -> Result<Vec<String>, failure::Error> {
+        let mut keys = vec![];
+        let mut continuation_token = None;
+        loop {
+            let mut params = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &continuation_token {
+                params.push(("continuation-token", token));
+            }
+            let res = self.get("s3", self.bucket_url.clone(), &params)?.text()?;
+            keys.extend(xml_elements(&res, "Key").into_iter().map(xml_unescape));
+            if xml_elements(&res, "IsTruncated") != ["true"] {
+                return Ok(keys);
+            }
+            match xml_elements(&res, "NextContinuationToken").first() {
+                Some(token) => continuation_token = Some(xml_unescape(token)),
+                None => bail!("truncated S3 listing without a continuation token"),
+            }
+        }
+    }
+
+    /// Returns the contents of the object with `key`.
+    fn get_object(&self, key: &str) -> Result<Vec<u8>, failure::Error> {
+        let mut url = self.bucket_url.clone();
+        let path = format!("{}/{}", url.path(), uri_encode(key, false));
+        url.set_path(&path);
+        let mut res = self.get("s3", url, &[])?;
+        let mut body = vec![];
+        res.copy_to(&mut body)?;
+        Ok(body)
+    }
+
+    /// Receives the messages in the SQS queue at `queue`, waiting for some to
+    /// arrive if there are none.
+    fn receive_messages(&self, queue: &Url) -> Result<Vec<SqsMessage>, failure::Error> {
+        let params = [
+            ("Action", "ReceiveMessage"),
+            ("MaxNumberOfMessages", "10"),
+            ("Version", "2012-11-05"),
+            ("WaitTimeSeconds", "20"),
+        ];
+        let res = self.get("sqs", queue.clone(), &params)?.text()?;
+        let mut messages = vec![];
+        for message in xml_elements(&res, "Message") {
+            let field = |name| match xml_elements(message, name).first() {
+                Some(value) => Ok(xml_unescape(value)),
+                None => Err(format_err!("SQS message without a {}", name)),
+            };
+            messages.push(SqsMessage {
+                body: field("Body")?,
+                receipt_handle: field("ReceiptHandle")?,
+            });
+        }
+        Ok(messages)
+    }
+
+    /// Deletes the message with `receipt_handle` from the SQS queue at
+    /// `queue`.
+    fn delete_message(&self, queue: &Url, receipt_handle: &str) -> Result<(), failure::Error> {
+        let params = [
+            ("Action", "DeleteMessage"),
+            ("ReceiptHandle", receipt_handle),
+            ("Version", "2012-11-05"),
+        ];
+        self.get("sqs", queue.clone(), &params)?;
+        Ok(())
+    }
+
+    /// Sends a signed GET request to `service` for `url`, with the query
+    /// parameters in `params`.
+    fn get(
+        &self,
+        service: &str,
+        mut url: Url,
+        params: &[(&str, &str)],
+    ) -> Result<Response, failure::Error> {
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        // The query parameters must be signed in sorted order, so send them
+        // in that order too.
+        let mut params: Vec<_> = params
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        params.sort();
+        let query: Vec<_> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        let query = query.join("&");
+        url.set_query(if query.is_empty() { None } else { Some(&query) });
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => bail!("URL has no host: {}", url),
+        };
+
+        let canonical_request = canonical_request(&host, url.path(), &query, &amz_date);
+        let signature = sign(
+            &self.secret_access_key,
+            &self.region,
+            service,
+            &amz_date,
+            &canonical_request,
+        )?;
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            credential_scope(&amz_date, &self.region, service),
+            SIGNED_HEADERS,
+            signature
+        );
+
+        let res = self
+            .http
+            .get(url)
+            .header("x-amz-content-sha256", EMPTY_PAYLOAD_HASH)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+            .send()?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().unwrap_or_default();
+            match xml_elements(&body, "Message").first() {
+                Some(message) => bail!("{}: {}", status, xml_unescape(message)),
+                None => bail!("{}", status),
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// The headers that each request signs.
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// The hex-encoded SHA-256 hash of an empty request body.
+const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Returns the canonical form of a GET request to `host` for `path`, with the
+/// canonical query string `query`, made at `amz_date`, which is what AWS
+/// Signature Version 4 signs.
+fn canonical_request(host: &str, path: &str, query: &str, amz_date: &str) -> String {
+    format!(
+        "GET\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        path, query, host, EMPTY_PAYLOAD_HASH, amz_date, SIGNED_HEADERS, EMPTY_PAYLOAD_HASH
+    )
+}
+
+/// Returns the scope of the credentials that sign a request to `service` in
+/// `region` made at `amz_date`.
+fn credential_scope(amz_date: &str, region: &str, service: &str) -> String {
+    format!("{}/{}/{}/aws4_request", &amz_date[..8], region, service)
+}
+
+/// Derives the key that signs requests to `service` in `region` on `date`
+/// from the secret access key.
+fn signing_key(
+    secret_access_key: &str,
+    date: &str,
+    region: &str,
+    service: &str,
+) -> Result<Vec<u8>, failure::Error> {
+    let mut key = hmac(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        date.as_bytes(),
+    )?;
+    for part in &[region, service, "aws4_request"] {
+        key = hmac(&key, part.as_bytes())?;
+    }
+    Ok(key)
+}
+
+/// Returns the hex-encoded signature of `canonical_request`, a request to
+/// `service` in `region` made at `amz_date`.
+fn sign(
+    secret_access_key: &str,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+    canonical_request: &str,
+) -> Result<String, failure::Error> {
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        credential_scope(amz_date, region, service),
+        hex::encode(sha::sha256(canonical_request.as_bytes()))
+    );
+    let key = signing_key(secret_access_key, &amz_date[..8], region, service)?;
+    Ok(hex::encode(hmac(&key, string_to_sign.as_bytes())?))
+}
+
+/// Computes the HMAC-SHA256 of `data` with `key`.
+fn hmac(key: &[u8], data: &[u8]) -> Result<Vec<u8>, failure::Error> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// Percent-encodes `s` as AWS signatures require, leaving only unreserved
+/// characters, and `/` unless `encode_slash` is set, unencoded.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Returns the contents of each `tag` element in `xml`.
+///
+/// This is only suited to the simple documents that the S3 and SQS APIs
+/// return, in which `tag` elements have no attributes and do not nest.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                elements.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+/// Replaces the entity and character references in the XML text `s` with the
+/// characters that they stand for.
+fn xml_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let c = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            entity if entity.starts_with('#') => {
+                entity[1..].parse().ok().and_then(std::char::from_u32)
+            }
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::sha;
+
+    use super::{canonical_request, sign, signing_key, uri_encode, EMPTY_PAYLOAD_HASH};
+
+    // The examples in the AWS documentation of Signature Version 4, and of its
+    // use to authenticate S3 requests.
+
+    #[test]
+    fn test_signing_key() -> Result<(), failure::Error> {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        )?;
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign() -> Result<(), failure::Error> {
+        assert_eq!(EMPTY_PAYLOAD_HASH, hex::encode(sha::sha256(b"")));
+
+        // GET Bucket Lifecycle, and GET Bucket (List Objects).
+        for (query, canonical_request_hash, signature) in &[
+            (
+                "lifecycle=",
+                "9766c798316ff2757b517bc739a67f6213b4ab36dd5da2f94eaebf79c77395ca",
+                "fea454ca298b7da1c68078a5d1bdbfbbe0d65c699e0f91ac7a200a0136783543",
+            ),
+            (
+                "max-keys=2&prefix=J",
+                "df57d21db20da04d7fa30298dd4488ba3a2b47ca3a489c74750e0f1e7df1b9b7",
+                "34b48302e7b5fa45bde8084f4b7868a86f0a534bc59db6670ed5711ef69dc6f7",
+            ),
+        ] {
+            let request = canonical_request(
+                "examplebucket.s3.amazonaws.com",
+                "/",
+                query,
+                "20130524T000000Z",
+            );
+            assert_eq!(
+                hex::encode(sha::sha256(request.as_bytes())),
+                *canonical_request_hash
+            );
+            assert_eq!(
+                sign(
+                    "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+                    "us-east-1",
+                    "s3",
+                    "20130524T000000Z",
+                    &request,
+                )?,
+                *signature
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("a b/c~d_e.f-g", false), "a%20b/c~d_e.f-g");
+        assert_eq!(uri_encode("a b/c", true), "a%20b%2Fc");
+        assert_eq!(uri_encode("\u{e9}=&+", true), "%C3%A9%3D%26%2B");
+    }
+}
@@ -9,18 +9,25 @@
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use expr::SourceInstanceId;
+use log::{error, warn};
+use repr::Row;
 
 use timely::dataflow::channels::pushers::Tee;
 use timely::dataflow::operators::generic::source as timely_source;
 use timely::dataflow::operators::generic::{OperatorInfo, OutputHandle};
 use timely::dataflow::operators::Capability;
 use timely::dataflow::{Scope, Stream};
+use timely::scheduling::SyncActivator;
 use timely::Data;
 
 use crate::server::TimestampChanges;
-use dataflow_types::Timestamp;
+use dataflow_types::{Diff, Timestamp};
 
 use super::{SourceStatus, SourceToken};
 
@@ -96,3 +103,117 @@ where
     // so we are guaranteed that `token` is non-None.
     (stream, token.unwrap())
 }
+
+/// The updates that one transaction in an external system made, which are
+/// timestamped together.
+pub type Batch = Vec<(Row, Diff)>;
+
+/// Constructs a source whose updates are produced in batches by `replicate`,
+/// which runs on its own thread.
+///
+/// `replicate` sends each batch on the provided channel, and activates the
+/// operator through the provided activator. It should return once the
+/// provided token can no longer be upgraded, which happens when the source is
+/// dropped. Each batch is timestamped with the time at which the source
+/// receives it, so the updates of a batch are never split across timestamps.
+///
+/// Only the worker for which `read` is true runs `replicate`. If `replicate`
+/// fails, its error is logged, and the source stops advancing rather than
+/// claiming that it is complete.
+pub fn batch_source<G, R>(
+    scope: &G,
+    name: String,
+    id: SourceInstanceId,
+    read: bool,
+    replicate: R,
+) -> (Stream<G, (Row, Timestamp, Diff)>, Option<SourceToken>)
+where
+    G: Scope<Timestamp = Timestamp>,
+    R: FnOnce(mpsc::Sender<Batch>, SyncActivator, Weak<()>) -> Result<(), failure::Error>
+        + Send
+        + 'static,
+{
+    // Update the capability every second if there are no new batches.
+    const HEARTBEAT: Duration = Duration::from_secs(1);
+    const MAX_BATCHES_PER_INVOCATION: usize = 1024;
+
+    let n2 = name.clone();
+    let (stream, capability) = source(id, None, scope, &name, move |info| {
+        let activator = scope.activator_for(&info.address[..]);
+        let (tx, rx) = mpsc::channel();
+        // The replication thread exits once it notices that this token, which
+        // lives as long as the operator, has been dropped.
+        let alive = Arc::new(());
+        if read {
+            let activator = scope.sync_activator_for(&info.address[..]);
+            let name = n2.clone();
+            let weak = Arc::downgrade(&alive);
+            thread::spawn(move || {
+                if let Err(e) = replicate(tx, activator, weak) {
+                    error!("{}: source stopped: {}", name, e);
+                }
+            });
+        }
+
+        move |cap, output| {
+            let _alive = &alive;
+            let cap_time = *cap.time();
+            let sys_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time seems to be before 1970.")
+                .as_millis() as u64;
+            let mut next_activation_duration = HEARTBEAT;
+            let next_time = if cap_time > sys_time {
+                if cap_time != sys_time + 1 {
+                    warn!(
+                        "{}: fast-forwarding out-of-order Unix timestamp {}ms ({} -> {})",
+                        n2,
+                        cap_time - sys_time,
+                        sys_time,
+                        cap_time,
+                    );
+                }
+                next_activation_duration = Duration::from_millis(cap_time - sys_time);
+                cap_time
+            } else {
+                cap.downgrade(&sys_time);
+                sys_time + 1
+            };
+
+            let mut batches_read = 0;
+            {
+                let time = *cap.time();
+                let mut session = output.session(cap);
+                while batches_read < MAX_BATCHES_PER_INVOCATION {
+                    match rx.try_recv() {
+                        Ok(batch) => {
+                            batches_read += 1;
+                            for (row, diff) in batch {
+                                session.give((row, time, diff));
+                            }
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        // Workers that do not read are done at once. If the
+                        // replication thread stops, because of an error or
+                        // because the source was dropped, the source stops
+                        // advancing.
+                        Err(TryRecvError::Disconnected) if read => return SourceStatus::Alive,
+                        Err(TryRecvError::Disconnected) => return SourceStatus::Done,
+                    }
+                }
+            }
+            if batches_read == MAX_BATCHES_PER_INVOCATION {
+                next_activation_duration = Default::default();
+            }
+            cap.downgrade(&next_time);
+            activator.activate_after(next_activation_duration);
+            SourceStatus::Alive
+        }
+    });
+
+    if read {
+        (stream, Some(capability))
+    } else {
+        (stream, None)
+    }
+}
@@ -61,6 +61,10 @@ pub enum GlobalId {
     System(u64),
     /// User namespace.
     User(u64),
+    /// Transient namespace, for the dataflows of individual queries, like
+    /// peeks and `TAIL`s. Transient identifiers are never stored in the
+    /// catalog, and are reused after a restart.
+    Transient(u64),
 }
 
 impl GlobalId {
@@ -80,17 +84,25 @@ impl GlobalId {
     pub fn is_system(&self) -> bool {
         match self {
             GlobalId::System(_) => true,
-            GlobalId::User(_) => false,
+            GlobalId::User(_) | GlobalId::Transient(_) => false,
         }
     }
 
     /// Reports whether this ID is in the user namespace.
     pub fn is_user(&self) -> bool {
         match self {
-            GlobalId::System(_) => false,
+            GlobalId::System(_) | GlobalId::Transient(_) => false,
             GlobalId::User(_) => true,
         }
     }
+
+    /// Reports whether this ID is in the transient namespace.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            GlobalId::Transient(_) => true,
+            GlobalId::System(_) | GlobalId::User(_) => false,
+        }
+    }
 }
 
 impl fmt::Display for GlobalId {
@@ -98,6 +110,7 @@ impl fmt::Display for GlobalId {
         match self {
             GlobalId::System(id) => write!(f, "s{}", id),
             GlobalId::User(id) => write!(f, "u{}", id),
+            GlobalId::Transient(id) => write!(f, "t{}", id),
         }
     }
 }
@@ -17,12 +17,14 @@ mod scalar;
 
 pub mod like;
 pub mod pretty;
+pub mod text_search;
 pub mod transform;
 
 pub use id::{DummyHumanizer, GlobalId, Id, IdHumanizer, LocalId, SourceInstanceId};
 pub use relation::func::{AggregateFunc, UnaryTableFunc};
 pub use relation::func::{AnalyzedRegex, CaptureGroupDesc};
 pub use relation::{AggregateExpr, ColumnOrder, IdGen, JoinImplementation, RelationExpr};
+pub use scalar::compiled::{Comparison, CompiledExpr, CompiledPredicates};
 pub use scalar::func::{BinaryFunc, DateTruncTo, NullaryFunc, UnaryFunc, VariadicFunc};
-pub use scalar::{EvalEnv, ScalarExpr};
+pub use scalar::{EvalEnv, EvalError, ScalarExpr};
 pub use transform::OptimizedRelationExpr;
@@ -16,7 +16,7 @@ use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
 use repr::decimal::Significand;
-use repr::{ColumnType, Datum, RelationType, Row, RowArena, ScalarType};
+use repr::{ColumnType, Datum, Interval, RelationType, Row, RowArena, ScalarType};
 
 use crate::EvalEnv;
 
@@ -389,6 +389,81 @@ where
         .fold(Datum::True, |a, b| crate::scalar::func::and(a, b))
 }
 
+/// Counts the occurrences of `pattern` in a collection of `(time, event)`
+/// lists.
+///
+/// The events are considered in time order. An occurrence is a subsequence of
+/// events whose labels match `pattern`, in order, where the last event happens
+/// no later than `within` after the first. Unrelated events may appear between
+/// the matching events. Occurrences do not overlap: once an occurrence is
+/// complete, matching restarts with the next event. A partial occurrence that
+/// can no longer complete in time is abandoned; of the partial occurrences that
+/// have matched the same prefix of `pattern`, only the most recently started
+/// one is tracked, as it has the most time left to complete.
+fn match_pattern<'a, I>(datums: I, pattern: &[String], within: Interval) -> Datum<'a>
+where
+    I: IntoIterator<Item = Datum<'a>>,
+{
+    let within = chrono::Duration::from_std(within.duration)
+        .unwrap_or_else(|_| chrono::Duration::max_value());
+    let mut events: Vec<(NaiveDateTime, &str)> = datums
+        .into_iter()
+        .filter_map(|d| match d {
+            Datum::List(list) => {
+                let mut fields = list.iter();
+                let time = match fields.next()? {
+                    Datum::Timestamp(t) => t,
+                    Datum::TimestampTz(t) => t.naive_utc(),
+                    _ => return None,
+                };
+                match fields.next()? {
+                    Datum::String(event) => Some((time, event)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    events.sort();
+
+    let mut matches = 0;
+    // The start of the latest partial occurrence that has matched the first
+    // `k` labels of `pattern`, at index `k`. Index 0 is unused.
+    let mut starts: Vec<Option<NaiveDateTime>> = vec![None; pattern.len()];
+    for (time, event) in events {
+        for start in &mut starts {
+            if let Some(start_time) = *start {
+                if time - start_time > within {
+                    *start = None;
+                }
+            }
+        }
+        // Extend the longest partial occurrences first, so that an event
+        // extends a partial occurrence by at most one label.
+        let mut complete = false;
+        for k in (0..pattern.len()).rev() {
+            if event != pattern[k] {
+                continue;
+            }
+            let start = if k == 0 { Some(time) } else { starts[k] };
+            if start.is_some() {
+                if k + 1 == pattern.len() {
+                    complete = true;
+                    break;
+                }
+                starts[k + 1] = std::cmp::max(starts[k + 1], start);
+            }
+        }
+        if complete {
+            matches += 1;
+            for start in &mut starts {
+                *start = None;
+            }
+        }
+    }
+    Datum::Int64(matches)
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub enum AggregateFunc {
     MaxInt32,
@@ -423,6 +498,10 @@ pub enum AggregateFunc {
     CountAll, // COUNT(*) counts nulls too
     Any,
     All,
+    MatchPattern {
+        pattern: Vec<String>,
+        within: Interval,
+    },
 }
 
 impl AggregateFunc {
@@ -468,12 +547,16 @@ impl AggregateFunc {
             AggregateFunc::CountAll => count_all(datums),
             AggregateFunc::Any => any(datums),
             AggregateFunc::All => all(datums),
+            AggregateFunc::MatchPattern { pattern, within } => {
+                match_pattern(datums, pattern, *within)
+            }
         }
     }
 
     pub fn default(&self) -> Datum<'static> {
         match self {
             AggregateFunc::Count | AggregateFunc::CountAll => Datum::Int64(0),
+            AggregateFunc::MatchPattern { .. } => Datum::Int64(0),
             AggregateFunc::Any => Datum::False,
             AggregateFunc::All => Datum::True,
             _ => Datum::Null,
@@ -491,10 +574,13 @@ impl AggregateFunc {
             AggregateFunc::CountAll => ScalarType::Int64,
             AggregateFunc::Any => ScalarType::Bool,
             AggregateFunc::All => ScalarType::Bool,
+            AggregateFunc::MatchPattern { .. } => ScalarType::Int64,
             _ => input_type.scalar_type,
         };
         let nullable = match self {
-            AggregateFunc::Count | AggregateFunc::CountAll => false,
+            AggregateFunc::Count | AggregateFunc::CountAll | AggregateFunc::MatchPattern { .. } => {
+                false
+            }
             // max/min/sum return null on empty sets
             _ => true,
         };
@@ -529,6 +615,15 @@ fn jsonb_array_elements<'a>(a: Datum<'a>) -> Vec<Row> {
     }
 }
 
+fn text_tokens(a: Datum) -> Vec<Row> {
+    match a {
+        Datum::String(s) => crate::text_search::tokenize(s)
+            .map(|token| Row::pack(&[Datum::String(&token)]))
+            .collect(),
+        _ => vec![],
+    }
+}
+
 fn regexp_extract(a: Datum, r: &AnalyzedRegex) -> Option<Row> {
     match a {
         Datum::String(s) => {
@@ -579,6 +674,7 @@ impl fmt::Display for AggregateFunc {
             AggregateFunc::CountAll => f.write_str("countall"),
             AggregateFunc::Any => f.write_str("any"),
             AggregateFunc::All => f.write_str("all"),
+            AggregateFunc::MatchPattern { .. } => f.write_str("mz_match_pattern"),
         }
     }
 }
@@ -654,6 +750,7 @@ pub enum UnaryTableFunc {
     JsonbArrayElements,
     RegexpExtract(AnalyzedRegex),
     CsvExtract(usize),
+    TextTokens,
 }
 
 impl UnaryTableFunc {
@@ -669,6 +766,7 @@ impl UnaryTableFunc {
             UnaryTableFunc::JsonbArrayElements => jsonb_array_elements(datum),
             UnaryTableFunc::RegexpExtract(a) => regexp_extract(datum, a).into_iter().collect(),
             UnaryTableFunc::CsvExtract(n_cols) => csv_extract(datum, *n_cols).into_iter().collect(),
+            UnaryTableFunc::TextTokens => text_tokens(datum),
         }
     }
 
@@ -687,6 +785,7 @@ impl UnaryTableFunc {
             UnaryTableFunc::CsvExtract(n_cols) => iter::repeat(ColumnType::new(ScalarType::String))
                 .take(*n_cols)
                 .collect(),
+            UnaryTableFunc::TextTokens => vec![ColumnType::new(ScalarType::String)],
         })
     }
 
@@ -697,6 +796,7 @@ impl UnaryTableFunc {
             UnaryTableFunc::JsonbArrayElements => 1,
             UnaryTableFunc::RegexpExtract(a) => a.capture_groups_len(),
             UnaryTableFunc::CsvExtract(n_cols) => *n_cols,
+            UnaryTableFunc::TextTokens => 1,
         }
     }
 }
@@ -713,6 +813,74 @@ impl fmt::Display for UnaryTableFunc {
             UnaryTableFunc::CsvExtract(n_cols) => {
                 f.write_fmt(format_args!("csv_extract({}, _)", n_cols))
             }
+            UnaryTableFunc::TextTokens => f.write_str("text_tokens"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(events: &[(u64, &str)], pattern: &[&str], within_minutes: u64) -> i64 {
+        let epoch = NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let arena = RowArena::new();
+        let datums: Vec<_> = events
+            .iter()
+            .map(|(minute, event)| {
+                let time = epoch + chrono::Duration::minutes(*minute as i64);
+                arena.make_datum(|packer| {
+                    packer.push_list(&[Datum::Timestamp(time), Datum::String(event)])
+                })
+            })
+            .collect();
+        let pattern: Vec<_> = pattern.iter().map(|label| label.to_string()).collect();
+        let within = Interval {
+            duration: std::time::Duration::from_secs(within_minutes * 60),
+            ..Default::default()
+        };
+        match match_pattern(datums, &pattern, within) {
+            Datum::Int64(n) => n,
+            d => panic!("unexpected datum {:?}", d),
+        }
+    }
+
+    #[test]
+    fn test_match_pattern() {
+        let pattern = &["login", "purchase"];
+        assert_eq!(count(&[(0, "login"), (3, "purchase")], pattern, 5), 1);
+        assert_eq!(count(&[(0, "login"), (10, "purchase")], pattern, 5), 0);
+        assert_eq!(count(&[(3, "purchase"), (0, "login")], pattern, 5), 1);
+        assert_eq!(count(&[(0, "purchase"), (1, "login")], pattern, 5), 0);
+        // A repeated first label restarts the partial occurrence, which
+        // would otherwise expire before the purchase.
+        assert_eq!(
+            count(&[(0, "login"), (4, "login"), (6, "purchase")], pattern, 5),
+            1,
+        );
+        // Occurrences do not overlap.
+        assert_eq!(
+            count(&[(0, "login"), (1, "login"), (2, "purchase")], pattern, 5),
+            1,
+        );
+        assert_eq!(
+            count(
+                &[(0, "login"), (1, "purchase"), (2, "login"), (4, "purchase")],
+                pattern,
+                5,
+            ),
+            2,
+        );
+        // An earlier partial occurrence that has matched more of the pattern
+        // is kept alongside a later one that has matched less.
+        assert_eq!(
+            count(
+                &[(0, "a"), (1, "b"), (2, "a"), (3, "c")],
+                &["a", "b", "c"],
+                5,
+            ),
+            1,
+        );
+        assert_eq!(count(&[(0, "login"), (1, "login")], &["login"], 5), 2);
+    }
+}
@@ -0,0 +1,434 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Scalar expressions compiled for repeated evaluation.
+//!
+//! [`ScalarExpr::eval`] walks the expression tree afresh for every row,
+//! dispatching on the function at every node. Dataflows evaluate the same
+//! filter predicates and map expressions against every row that flows through
+//! them, so they compile those expressions once, when the dataflow is
+//! rendered. Compilation:
+//!
+//!   * specializes the most common shapes of predicate, comparisons between
+//!     columns and literals and tests for nullness, so that each is evaluated
+//!     with a single dispatch and no intermediate function calls;
+//!   * short-circuits conjunctions and disjunctions;
+//!   * records the columns each expression reads, so that filters need only
+//!     decode rows up to the last column that any predicate reads.
+//!
+//! Any other expression is evaluated by the interpreter. A compiled expression
+//! always produces the same result as the expression it was compiled from.
+
+use std::cmp;
+
+use repr::{Datum, Row, RowArena};
+
+use crate::scalar::func::{BinaryFunc, UnaryFunc};
+use crate::scalar::{EvalEnv, ScalarExpr};
+
+/// A scalar expression compiled for repeated evaluation.
+#[derive(Clone, Debug)]
+pub enum CompiledExpr {
+    /// A column of the input row.
+    Column(usize),
+    /// A literal value.
+    Literal(Row),
+    /// A comparison between a column and a literal.
+    CompareColumnLiteral {
+        column: usize,
+        op: Comparison,
+        literal: Row,
+    },
+    /// A comparison between two columns.
+    CompareColumns {
+        left: usize,
+        op: Comparison,
+        right: usize,
+    },
+    /// Whether a column is null.
+    IsNull(usize),
+    /// The logical negation of an expression.
+    Not(Box<CompiledExpr>),
+    /// The conjunction of several expressions, evaluated until one is false.
+    And(Vec<CompiledExpr>),
+    /// The disjunction of several expressions, evaluated until one is true.
+    Or(Vec<CompiledExpr>),
+    /// An expression without a specialized form, which is interpreted.
+    Interpreted(ScalarExpr),
+}
+
+/// A comparison operator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Comparison {
+    fn from_func(func: &BinaryFunc) -> Option<Comparison> {
+        match func {
+            BinaryFunc::Eq => Some(Comparison::Eq),
+            BinaryFunc::NotEq => Some(Comparison::NotEq),
+            BinaryFunc::Lt => Some(Comparison::Lt),
+            BinaryFunc::Lte => Some(Comparison::Lte),
+            BinaryFunc::Gt => Some(Comparison::Gt),
+            BinaryFunc::Gte => Some(Comparison::Gte),
+            _ => None,
+        }
+    }
+
+    /// Returns the operator that gives the same result when its operands are
+    /// swapped, e.g. `>` for `<`.
+    fn flip(self) -> Comparison {
+        match self {
+            Comparison::Eq => Comparison::Eq,
+            Comparison::NotEq => Comparison::NotEq,
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Lte => Comparison::Gte,
+            Comparison::Gt => Comparison::Lt,
+            Comparison::Gte => Comparison::Lte,
+        }
+    }
+
+    /// Compares two datums, either of which may be null.
+    fn apply<'a>(self, a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+        if a.is_null() || b.is_null() {
+            return Datum::Null;
+        }
+        Datum::from(match self {
+            Comparison::Eq => a == b,
+            Comparison::NotEq => a != b,
+            Comparison::Lt => a < b,
+            Comparison::Lte => a <= b,
+            Comparison::Gt => a > b,
+            Comparison::Gte => a >= b,
+        })
+    }
+}
+
+impl CompiledExpr {
+    /// Compiles `expr`.
+    pub fn compile(expr: &ScalarExpr) -> CompiledExpr {
+        match expr {
+            ScalarExpr::Column(column) => CompiledExpr::Column(*column),
+            ScalarExpr::Literal(row, _typ) => CompiledExpr::Literal(row.clone()),
+            ScalarExpr::CallUnary {
+                func: UnaryFunc::IsNull,
+                expr: input,
+            } => match &**input {
+                ScalarExpr::Column(column) => CompiledExpr::IsNull(*column),
+                _ => CompiledExpr::Interpreted(expr.clone()),
+            },
+            ScalarExpr::CallUnary {
+                func: UnaryFunc::Not,
+                expr,
+            } => CompiledExpr::Not(Box::new(CompiledExpr::compile(expr))),
+            ScalarExpr::CallBinary {
+                func: BinaryFunc::And,
+                ..
+            } => {
+                let mut exprs = vec![];
+                flatten(expr, &BinaryFunc::And, &mut exprs);
+                CompiledExpr::And(exprs)
+            }
+            ScalarExpr::CallBinary {
+                func: BinaryFunc::Or,
+                ..
+            } => {
+                let mut exprs = vec![];
+                flatten(expr, &BinaryFunc::Or, &mut exprs);
+                CompiledExpr::Or(exprs)
+            }
+            ScalarExpr::CallBinary { func, expr1, expr2 } => {
+                match (Comparison::from_func(func), &**expr1, &**expr2) {
+                    (Some(op), ScalarExpr::Column(left), ScalarExpr::Column(right)) => {
+                        CompiledExpr::CompareColumns {
+                            left: *left,
+                            op,
+                            right: *right,
+                        }
+                    }
+                    (Some(op), ScalarExpr::Column(column), ScalarExpr::Literal(literal, _)) => {
+                        CompiledExpr::CompareColumnLiteral {
+                            column: *column,
+                            op,
+                            literal: literal.clone(),
+                        }
+                    }
+                    (Some(op), ScalarExpr::Literal(literal, _), ScalarExpr::Column(column)) => {
+                        CompiledExpr::CompareColumnLiteral {
+                            column: *column,
+                            op: op.flip(),
+                            literal: literal.clone(),
+                        }
+                    }
+                    _ => CompiledExpr::Interpreted(expr.clone()),
+                }
+            }
+            _ => CompiledExpr::Interpreted(expr.clone()),
+        }
+    }
+
+    /// Evaluates the expression against `datums`, as [`ScalarExpr::eval`]
+    /// would evaluate the expression it was compiled from.
+    pub fn eval<'a>(
+        &'a self,
+        datums: &[Datum<'a>],
+        env: &'a EvalEnv,
+        temp_storage: &'a RowArena,
+    ) -> Datum<'a> {
+        match self {
+            CompiledExpr::Column(column) => datums[*column],
+            CompiledExpr::Literal(row) => row.unpack_first(),
+            CompiledExpr::CompareColumnLiteral {
+                column,
+                op,
+                literal,
+            } => op.apply(datums[*column], literal.unpack_first()),
+            CompiledExpr::CompareColumns { left, op, right } => {
+                op.apply(datums[*left], datums[*right])
+            }
+            CompiledExpr::IsNull(column) => Datum::from(datums[*column].is_null()),
+            CompiledExpr::Not(expr) => match expr.eval(datums, env, temp_storage) {
+                Datum::Null => Datum::Null,
+                datum => Datum::from(!datum.unwrap_bool()),
+            },
+            CompiledExpr::And(exprs) => {
+                let mut result = Datum::True;
+                for expr in exprs {
+                    match expr.eval(datums, env, temp_storage) {
+                        Datum::False => return Datum::False,
+                        Datum::Null => result = Datum::Null,
+                        Datum::True => (),
+                        _ => unreachable!(),
+                    }
+                }
+                result
+            }
+            CompiledExpr::Or(exprs) => {
+                let mut result = Datum::False;
+                for expr in exprs {
+                    match expr.eval(datums, env, temp_storage) {
+                        Datum::True => return Datum::True,
+                        Datum::Null => result = Datum::Null,
+                        Datum::False => (),
+                        _ => unreachable!(),
+                    }
+                }
+                result
+            }
+            CompiledExpr::Interpreted(expr) => expr.eval(datums, env, temp_storage),
+        }
+    }
+
+    /// Returns the number of leading columns of the input row that evaluation
+    /// may read.
+    pub fn arity_read(&self) -> usize {
+        match self {
+            CompiledExpr::Column(column) | CompiledExpr::IsNull(column) => column + 1,
+            CompiledExpr::CompareColumnLiteral { column, .. } => column + 1,
+            CompiledExpr::CompareColumns { left, right, .. } => cmp::max(*left, *right) + 1,
+            CompiledExpr::Literal(_) => 0,
+            CompiledExpr::Not(expr) => expr.arity_read(),
+            CompiledExpr::And(exprs) | CompiledExpr::Or(exprs) => {
+                exprs.iter().map(|e| e.arity_read()).max().unwrap_or(0)
+            }
+            CompiledExpr::Interpreted(expr) => {
+                expr.support().into_iter().max().map_or(0, |c| c + 1)
+            }
+        }
+    }
+
+    /// Reports whether the expression is evaluated by the interpreter, in
+    /// whole or in part.
+    fn is_interpreted(&self) -> bool {
+        match self {
+            CompiledExpr::Interpreted(_) => true,
+            CompiledExpr::Not(expr) => expr.is_interpreted(),
+            CompiledExpr::And(exprs) | CompiledExpr::Or(exprs) => {
+                exprs.iter().any(|e| e.is_interpreted())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compiles the operands of a tree of calls to `func`, which must be
+/// associative, into `exprs`.
+fn flatten(expr: &ScalarExpr, func: &BinaryFunc, exprs: &mut Vec<CompiledExpr>) {
+    match expr {
+        ScalarExpr::CallBinary {
+            func: f,
+            expr1,
+            expr2,
+        } if f == func => {
+            flatten(expr1, func, exprs);
+            flatten(expr2, func, exprs);
+        }
+        _ => exprs.push(CompiledExpr::compile(expr)),
+    }
+}
+
+/// A conjunction of predicates compiled for repeated evaluation, as in a
+/// filter.
+#[derive(Clone, Debug)]
+pub struct CompiledPredicates {
+    predicates: Vec<CompiledExpr>,
+    arity_read: usize,
+}
+
+impl CompiledPredicates {
+    /// Compiles `predicates`.
+    ///
+    /// Predicates that are evaluated without the interpreter are evaluated
+    /// first, as they are cheap and may spare the evaluation of the rest.
+    pub fn compile(predicates: &[ScalarExpr]) -> CompiledPredicates {
+        let mut compiled = vec![];
+        for predicate in predicates {
+            if predicate.is_literal_true() {
+                continue;
+            }
+            match CompiledExpr::compile(predicate) {
+                CompiledExpr::And(exprs) => compiled.extend(exprs),
+                expr => compiled.push(expr),
+            }
+        }
+        compiled.sort_by_key(|p| p.is_interpreted());
+        let arity_read = compiled.iter().map(|p| p.arity_read()).max().unwrap_or(0);
+        CompiledPredicates {
+            predicates: compiled,
+            arity_read,
+        }
+    }
+
+    /// Reports whether there are no predicates, i.e., whether every row
+    /// satisfies the conjunction.
+    pub fn is_empty(&self) -> bool {
+        self.predicates.is_empty()
+    }
+
+    /// Returns the number of leading columns of the input row that the
+    /// predicates may read.
+    pub fn arity_read(&self) -> usize {
+        self.arity_read
+    }
+
+    /// Reports whether `row` satisfies every predicate. Only the columns of
+    /// `row` that the predicates read are decoded.
+    pub fn eval_row(&self, row: &Row, env: &EvalEnv, temp_storage: &RowArena) -> bool {
+        if self.predicates.is_empty() {
+            return true;
+        }
+        let datums: Vec<_> = row.iter().take(self.arity_read).collect();
+        self.eval(&datums, env, temp_storage)
+    }
+
+    /// Reports whether `datums` satisfies every predicate.
+    pub fn eval<'a>(
+        &'a self,
+        datums: &[Datum<'a>],
+        env: &'a EvalEnv,
+        temp_storage: &'a RowArena,
+    ) -> bool {
+        self.predicates.iter().all(
+            |predicate| match predicate.eval(datums, env, temp_storage) {
+                Datum::True => true,
+                Datum::False | Datum::Null => false,
+                _ => unreachable!(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use repr::{ColumnType, ScalarType};
+
+    use super::*;
+
+    #[test]
+    fn test_compiled_matches_interpreted() {
+        let int64_lit =
+            |n| ScalarExpr::literal(Datum::Int64(n), ColumnType::new(ScalarType::Int64));
+        let null_lit = ScalarExpr::literal(Datum::Null, ColumnType::new(ScalarType::Int64));
+        let col = ScalarExpr::column;
+
+        let exprs = vec![
+            col(0).call_binary(int64_lit(2), BinaryFunc::Lt),
+            int64_lit(2).call_binary(col(0), BinaryFunc::Lt),
+            col(0).call_binary(col(1), BinaryFunc::Gte),
+            col(1).call_binary(null_lit.clone(), BinaryFunc::Eq),
+            col(1).call_unary(UnaryFunc::IsNull),
+            col(0)
+                .call_binary(int64_lit(1), BinaryFunc::Eq)
+                .call_binary(col(1).call_unary(UnaryFunc::IsNull), BinaryFunc::Or)
+                .call_unary(UnaryFunc::Not),
+            col(0)
+                .call_binary(col(1), BinaryFunc::AddInt64)
+                .call_binary(int64_lit(3), BinaryFunc::Gt)
+                .call_binary(
+                    col(0).call_binary(int64_lit(3), BinaryFunc::NotEq),
+                    BinaryFunc::And,
+                )
+                .call_binary(col(1).call_binary(col(0), BinaryFunc::Lte), BinaryFunc::And),
+        ];
+        let rows = vec![
+            Row::pack(&[Datum::Int64(1), Datum::Int64(2)]),
+            Row::pack(&[Datum::Int64(3), Datum::Int64(1)]),
+            Row::pack(&[Datum::Int64(2), Datum::Null]),
+            Row::pack(&[Datum::Null, Datum::Null]),
+        ];
+
+        let env = EvalEnv::default();
+        let temp_storage = RowArena::new();
+        for expr in &exprs {
+            let compiled = CompiledExpr::compile(expr);
+            for row in &rows {
+                let datums = row.unpack();
+                assert_eq!(
+                    compiled.eval(&datums, &env, &temp_storage),
+                    expr.eval(&datums, &env, &temp_storage),
+                    "expr: {:?}, row: {:?}",
+                    expr,
+                    datums
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_compiled_predicates() {
+        let int64_lit =
+            |n| ScalarExpr::literal(Datum::Int64(n), ColumnType::new(ScalarType::Int64));
+        let col = ScalarExpr::column;
+
+        let predicates = CompiledPredicates::compile(&[
+            ScalarExpr::literal(Datum::True, ColumnType::new(ScalarType::Bool)),
+            col(0)
+                .call_binary(int64_lit(1), BinaryFunc::Gt)
+                .call_binary(col(1).call_unary(UnaryFunc::IsNull), BinaryFunc::And),
+        ]);
+        assert_eq!(predicates.predicates.len(), 2);
+        assert_eq!(predicates.arity_read, 2);
+
+        let env = EvalEnv::default();
+        let temp_storage = RowArena::new();
+        let eval = |datums: &[Datum]| predicates.eval_row(&Row::pack(datums), &env, &temp_storage);
+        assert!(eval(&[Datum::Int64(2), Datum::Null, Datum::Int64(0)]));
+        assert!(!eval(&[Datum::Int64(1), Datum::Null, Datum::Int64(0)]));
+        assert!(!eval(&[Datum::Int64(2), Datum::Int64(0), Datum::Int64(0)]));
+        assert!(!eval(&[Datum::Null, Datum::Null, Datum::Int64(0)]));
+
+        assert!(CompiledPredicates::compile(&[]).is_empty());
+    }
+}
@@ -13,6 +13,7 @@ use std::fmt;
 use std::str::FromStr;
 
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use differential_dataflow::hashable::Hashable;
 use encoding::label::encoding_from_whatwg_label;
 use encoding::DecoderTrap;
 use serde::{Deserialize, Serialize};
@@ -20,11 +21,11 @@ use serde::{Deserialize, Serialize};
 use repr::decimal::MAX_DECIMAL_PRECISION;
 use repr::jsonb::Jsonb;
 use repr::regex::Regex;
-use repr::{strconv, ColumnType, Datum, RowArena, RowPacker, ScalarType};
+use repr::{strconv, ColumnType, Datum, Point, RowArena, RowPacker, ScalarType};
 
 use self::format::DateTimeFormat;
 pub use crate::like::build_like_regex_from_string;
-use crate::EvalEnv;
+use crate::{EvalEnv, EvalError};
 
 mod format;
 
@@ -410,6 +411,73 @@ fn cast_interval_to_string<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datu
     Datum::String(temp_storage.push_string(buf))
 }
 
+fn cast_string_to_point<'a>(a: Datum<'a>) -> Datum<'a> {
+    match strconv::parse_point(a.unwrap_str()) {
+        Ok(p) => Datum::Point(p),
+        Err(_) => Datum::Null,
+    }
+}
+
+fn cast_point_to_string<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datum<'a> {
+    let mut buf = String::new();
+    strconv::format_point(&mut buf, a.unwrap_point());
+    Datum::String(temp_storage.push_string(buf))
+}
+
+fn cast_string_to_inet<'a>(a: Datum<'a>) -> Datum<'a> {
+    match strconv::parse_inet(a.unwrap_str()) {
+        Ok(inet) => Datum::Inet(inet),
+        Err(_) => Datum::Null,
+    }
+}
+
+fn cast_inet_to_string<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datum<'a> {
+    let mut buf = String::new();
+    strconv::format_inet(&mut buf, a.unwrap_inet());
+    Datum::String(temp_storage.push_string(buf))
+}
+
+fn cast_string_to_cidr<'a>(a: Datum<'a>) -> Datum<'a> {
+    match strconv::parse_cidr(a.unwrap_str()) {
+        Ok(inet) => Datum::Inet(inet),
+        Err(_) => Datum::Null,
+    }
+}
+
+fn cast_cidr_to_string<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datum<'a> {
+    let mut buf = String::new();
+    strconv::format_cidr(&mut buf, a.unwrap_inet());
+    Datum::String(temp_storage.push_string(buf))
+}
+
+fn cast_inet_to_cidr<'a>(a: Datum<'a>) -> Datum<'a> {
+    Datum::Inet(a.unwrap_inet().network())
+}
+
+/// Converts a label of an enum type with the given labels to its position.
+///
+/// An unknown label evaluates to `NULL`, but is reported as an error by
+/// [`UnaryFunc::input_error`].
+fn cast_string_to_enum<'a>(a: Datum<'a>, labels: &[String]) -> Datum<'a> {
+    let s = a.unwrap_str();
+    match labels.iter().position(|label| label == s) {
+        Some(i) => Datum::Int32(i as i32),
+        None => Datum::Null,
+    }
+}
+
+fn cast_enum_to_string<'a>(a: Datum<'a>, labels: &'a [String]) -> Datum<'a> {
+    Datum::String(&labels[a.unwrap_int32() as usize])
+}
+
+fn inet_host<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datum<'a> {
+    Datum::String(temp_storage.push_string(a.unwrap_inet().addr.to_string()))
+}
+
+fn inet_masklen<'a>(a: Datum<'a>) -> Datum<'a> {
+    Datum::Int32(i32::from(a.unwrap_inet().prefix_len))
+}
+
 fn cast_bytes_to_string<'a>(a: Datum<'a>, temp_storage: &'a RowArena) -> Datum<'a> {
     let mut buf = String::new();
     strconv::format_bytes(&mut buf, a.unwrap_bytes());
@@ -1089,6 +1157,27 @@ fn match_regex<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
     }
 }
 
+fn make_point<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+    Datum::Point(Point::new(a.unwrap_float64(), b.unwrap_float64()))
+}
+
+fn point_distance<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+    Datum::from(a.unwrap_point().distance(&b.unwrap_point()))
+}
+
+fn inet_contained_by<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+    let (a, b) = (a.unwrap_inet(), b.unwrap_inet());
+    Datum::from(b.prefix_len < a.prefix_len && b.contains(&a))
+}
+
+fn inet_contained_by_or_eq<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+    Datum::from(b.unwrap_inet().contains(&a.unwrap_inet()))
+}
+
+fn text_matches<'a>(a: Datum<'a>, b: Datum<'a>) -> Datum<'a> {
+    Datum::from(crate::text_search::matches(a.unwrap_str(), b.unwrap_str()))
+}
+
 fn match_cached_regex<'a>(a: Datum<'a>, needle: &regex::Regex) -> Datum<'a> {
     let haystack = a.unwrap_str();
     Datum::from(needle.is_match(haystack))
@@ -1527,6 +1616,11 @@ pub enum BinaryFunc {
     JsonbContainsJsonb,
     JsonbDeleteInt64,
     JsonbDeleteString,
+    TextMatches,
+    MakePoint,
+    PointDistance,
+    InetContainedBy,
+    InetContainedByOrEq,
 }
 
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -1650,6 +1744,11 @@ impl BinaryFunc {
             BinaryFunc::JsonbContainsJsonb => jsonb_contains_jsonb(a, b),
             BinaryFunc::JsonbDeleteInt64 => jsonb_delete_int64(a, b, temp_storage),
             BinaryFunc::JsonbDeleteString => jsonb_delete_string(a, b, temp_storage),
+            BinaryFunc::TextMatches => text_matches(a, b),
+            BinaryFunc::MakePoint => make_point(a, b),
+            BinaryFunc::PointDistance => point_distance(a, b),
+            BinaryFunc::InetContainedBy => inet_contained_by(a, b),
+            BinaryFunc::InetContainedByOrEq => inet_contained_by_or_eq(a, b),
         }
     }
 
@@ -1758,7 +1857,15 @@ impl BinaryFunc {
                 ColumnType::new(ScalarType::Jsonb).nullable(true)
             }
 
-            JsonbContainsString | JsonbContainsJsonb => {
+            JsonbContainsString | JsonbContainsJsonb | TextMatches => {
+                ColumnType::new(ScalarType::Bool).nullable(in_nullable)
+            }
+
+            MakePoint => ColumnType::new(ScalarType::Point).nullable(in_nullable),
+
+            PointDistance => ColumnType::new(ScalarType::Float64).nullable(in_nullable),
+
+            InetContainedBy | InetContainedByOrEq => {
                 ColumnType::new(ScalarType::Bool).nullable(in_nullable)
             }
         }
@@ -1771,6 +1878,18 @@ impl BinaryFunc {
             _ => true,
         }
     }
+
+    /// Whether applying the function to the divisor `b` divides by zero.
+    pub fn divides_by_zero(&self, b: Datum) -> bool {
+        match self {
+            BinaryFunc::DivInt32 | BinaryFunc::ModInt32 => b.unwrap_int32() == 0,
+            BinaryFunc::DivInt64 | BinaryFunc::ModInt64 => b.unwrap_int64() == 0,
+            BinaryFunc::DivFloat32 | BinaryFunc::ModFloat32 => b.unwrap_float32() == 0.0,
+            BinaryFunc::DivFloat64 | BinaryFunc::ModFloat64 => b.unwrap_float64() == 0.0,
+            BinaryFunc::DivDecimal | BinaryFunc::ModDecimal => b.unwrap_decimal() == 0,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for BinaryFunc {
@@ -1835,6 +1954,11 @@ impl fmt::Display for BinaryFunc {
             BinaryFunc::JsonbContainsJsonb => f.write_str("b<@"),
             BinaryFunc::JsonbDeleteInt64 => f.write_str("b-int64"),
             BinaryFunc::JsonbDeleteString => f.write_str("b-string"),
+            BinaryFunc::TextMatches => f.write_str("text_matches"),
+            BinaryFunc::MakePoint => f.write_str("point"),
+            BinaryFunc::PointDistance => f.write_str("<->"),
+            BinaryFunc::InetContainedBy => f.write_str("<<"),
+            BinaryFunc::InetContainedByOrEq => f.write_str("<<="),
         }
     }
 }
@@ -1904,6 +2028,19 @@ pub enum UnaryFunc {
     CastTimestampTzToTimestamp,
     CastTimestampTzToString,
     CastIntervalToString,
+    CastStringToPoint,
+    CastPointToString,
+    CastStringToInet,
+    CastInetToString,
+    CastStringToCidr,
+    CastCidrToString,
+    CastInetToCidr,
+    CastCidrToInet,
+    /// Converts a label to a value of the enum type with the specified ID,
+    /// whose labels are given.
+    CastStringToEnum(u64, Vec<String>),
+    /// Converts a value of an enum type with the given labels to its label.
+    CastEnumToString(Vec<String>),
     CastBytesToString,
     CastStringToJsonb,
     JsonbStringify,
@@ -1955,6 +2092,8 @@ pub enum UnaryFunc {
     JsonbTypeof,
     JsonbStripNulls,
     JsonbPretty,
+    InetHost,
+    InetMasklen,
 }
 
 impl UnaryFunc {
@@ -2024,6 +2163,16 @@ impl UnaryFunc {
             UnaryFunc::CastTimestampTzToTimestamp => cast_timestamptz_to_timestamp(a),
             UnaryFunc::CastTimestampTzToString => cast_timestamptz_to_string(a, temp_storage),
             UnaryFunc::CastIntervalToString => cast_interval_to_string(a, temp_storage),
+            UnaryFunc::CastStringToPoint => cast_string_to_point(a),
+            UnaryFunc::CastPointToString => cast_point_to_string(a, temp_storage),
+            UnaryFunc::CastStringToInet => cast_string_to_inet(a),
+            UnaryFunc::CastInetToString => cast_inet_to_string(a, temp_storage),
+            UnaryFunc::CastStringToCidr => cast_string_to_cidr(a),
+            UnaryFunc::CastCidrToString => cast_cidr_to_string(a, temp_storage),
+            UnaryFunc::CastInetToCidr => cast_inet_to_cidr(a),
+            UnaryFunc::CastCidrToInet => a,
+            UnaryFunc::CastStringToEnum(_, labels) => cast_string_to_enum(a, labels),
+            UnaryFunc::CastEnumToString(labels) => cast_enum_to_string(a, labels),
             UnaryFunc::CastBytesToString => cast_bytes_to_string(a, temp_storage),
             UnaryFunc::CastStringToJsonb => cast_string_to_jsonb(a, temp_storage),
             UnaryFunc::JsonbStringify => jsonb_stringify(a, temp_storage),
@@ -2091,6 +2240,8 @@ impl UnaryFunc {
             UnaryFunc::JsonbTypeof => jsonb_typeof(a),
             UnaryFunc::JsonbStripNulls => jsonb_strip_nulls(a, temp_storage),
             UnaryFunc::JsonbPretty => jsonb_pretty(a, temp_storage),
+            UnaryFunc::InetHost => inet_host(a, temp_storage),
+            UnaryFunc::InetMasklen => inet_masklen(a),
         }
     }
 
@@ -2135,6 +2286,10 @@ impl UnaryFunc {
             CastStringToTimestamp => ColumnType::new(ScalarType::Timestamp).nullable(true),
             CastStringToTimestampTz => ColumnType::new(ScalarType::TimestampTz).nullable(true),
             CastStringToInterval => ColumnType::new(ScalarType::Interval).nullable(true),
+            CastStringToPoint => ColumnType::new(ScalarType::Point).nullable(true),
+            CastStringToInet => ColumnType::new(ScalarType::Inet).nullable(true),
+            CastStringToCidr => ColumnType::new(ScalarType::Cidr).nullable(true),
+            CastStringToEnum(id, _) => ColumnType::new(ScalarType::Enum(*id)).nullable(true),
 
             CastBoolToString
             | CastInt32ToString
@@ -2146,8 +2301,15 @@ impl UnaryFunc {
             | CastTimestampToString
             | CastTimestampTzToString
             | CastIntervalToString
+            | CastPointToString
+            | CastInetToString
+            | CastCidrToString
+            | CastEnumToString(_)
             | CastBytesToString => ColumnType::new(ScalarType::String).nullable(in_nullable),
 
+            CastCidrToInet => ColumnType::new(ScalarType::Inet).nullable(in_nullable),
+            CastInetToCidr => ColumnType::new(ScalarType::Cidr).nullable(in_nullable),
+
             CastInt32ToFloat32 | CastInt64ToFloat32 | CastSignificandToFloat32 => {
                 ColumnType::new(ScalarType::Float32).nullable(in_nullable)
             }
@@ -2259,6 +2421,9 @@ impl UnaryFunc {
             JsonbTypeof => ColumnType::new(ScalarType::String).nullable(in_nullable),
             JsonbStripNulls => ColumnType::new(ScalarType::Jsonb).nullable(true),
             JsonbPretty => ColumnType::new(ScalarType::String).nullable(in_nullable),
+
+            InetHost => ColumnType::new(ScalarType::String).nullable(in_nullable),
+            InetMasklen => ColumnType::new(ScalarType::Int32).nullable(in_nullable),
         }
     }
 
@@ -2270,6 +2435,22 @@ impl UnaryFunc {
         }
     }
 
+    /// The error, if any, that applying the function to the non-null input
+    /// `a` produces.
+    pub fn input_error(&self, a: Datum) -> Option<EvalError> {
+        match self {
+            UnaryFunc::CastStringToEnum(_, labels) => {
+                let label = a.unwrap_str();
+                if labels.iter().any(|l| l == label) {
+                    None
+                } else {
+                    Some(EvalError::InvalidEnumLabel(label.to_owned()))
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// True iff for x != y, we are assured f(x) != f(y).
     ///
     /// This is most often the case for methods that promote to types that
@@ -2358,6 +2539,16 @@ impl fmt::Display for UnaryFunc {
             UnaryFunc::CastTimestampTzToTimestamp => f.write_str("tstztots"),
             UnaryFunc::CastTimestampTzToString => f.write_str("tstztostr"),
             UnaryFunc::CastIntervalToString => f.write_str("ivtostr"),
+            UnaryFunc::CastStringToPoint => f.write_str("strtopoint"),
+            UnaryFunc::CastPointToString => f.write_str("pointtostr"),
+            UnaryFunc::CastStringToInet => f.write_str("strtoinet"),
+            UnaryFunc::CastInetToString => f.write_str("inettostr"),
+            UnaryFunc::CastStringToCidr => f.write_str("strtocidr"),
+            UnaryFunc::CastCidrToString => f.write_str("cidrtostr"),
+            UnaryFunc::CastInetToCidr => f.write_str("inettocidr"),
+            UnaryFunc::CastCidrToInet => f.write_str("cidrtoinet"),
+            UnaryFunc::CastStringToEnum(..) => f.write_str("strtoenum"),
+            UnaryFunc::CastEnumToString(_) => f.write_str("enumtostr"),
             UnaryFunc::CastBytesToString => f.write_str("bytestostr"),
             UnaryFunc::CastStringToJsonb => f.write_str("strtojsonb"),
             UnaryFunc::JsonbStringify => f.write_str("jsonbtostr"),
@@ -2414,6 +2605,8 @@ impl fmt::Display for UnaryFunc {
             UnaryFunc::JsonbTypeof => f.write_str("jsonb_typeof"),
             UnaryFunc::JsonbStripNulls => f.write_str("jsonb_strip_nulls"),
             UnaryFunc::JsonbPretty => f.write_str("jsonb_pretty"),
+            UnaryFunc::InetHost => f.write_str("host"),
+            UnaryFunc::InetMasklen => f.write_str("masklen"),
         }
     }
 }
@@ -2529,6 +2722,11 @@ fn jsonb_build_array<'a>(datums: &[Datum<'a>], temp_storage: &'a RowArena) -> Da
     }
 }
 
+/// Bundles `datums`, which may be of any types, including null, into a list.
+fn list_create<'a>(datums: &[Datum<'a>], temp_storage: &'a RowArena) -> Datum<'a> {
+    temp_storage.make_datum(|packer| packer.push_list(datums))
+}
+
 fn jsonb_build_object<'a>(datums: &[Datum<'a>], temp_storage: &'a RowArena) -> Datum<'a> {
     if datums.iter().any(|datum| datum.is_null()) {
         // the inputs should all be valid jsonb types, but a casting error might produce a Datum::Null that needs to be propagated
@@ -2578,6 +2776,28 @@ fn make_timestamp<'a>(datums: &[Datum<'a>]) -> Datum<'a> {
     Datum::Timestamp(timestamp)
 }
 
+fn point_in_box<'a>(datums: &[Datum<'a>]) -> Datum<'a> {
+    let point = datums[0].unwrap_point();
+    Datum::from(point.is_within_box(&datums[1].unwrap_point(), &datums[2].unwrap_point()))
+}
+
+/// Derives a pseudorandom number in the range [0, 1) from the values of
+/// `datums`.
+///
+/// The result depends on nothing but those values, so it is the same on every
+/// worker and every time a view is maintained. NULL is a value like any other.
+fn seeded_random<'a>(datums: &[Datum<'a>]) -> Datum<'a> {
+    // The upper bits of the hash are poorly mixed, so mix them with the
+    // finalizer of MurmurHash3 before keeping the top 53 bits.
+    let mut h = datums.hashed();
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    Datum::from((h >> 11) as f64 / (1u64 << 53) as f64)
+}
+
 #[derive(Ord, PartialOrd, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub enum VariadicFunc {
     Coalesce,
@@ -2588,6 +2808,9 @@ pub enum VariadicFunc {
     Replace,
     JsonbBuildArray,
     JsonbBuildObject,
+    PointInBox,
+    SeededRandom,
+    ListCreate,
 }
 
 impl VariadicFunc {
@@ -2606,6 +2829,9 @@ impl VariadicFunc {
             VariadicFunc::Replace => replace(datums, temp_storage),
             VariadicFunc::JsonbBuildArray => jsonb_build_array(datums, temp_storage),
             VariadicFunc::JsonbBuildObject => jsonb_build_object(datums, temp_storage),
+            VariadicFunc::PointInBox => point_in_box(datums),
+            VariadicFunc::SeededRandom => seeded_random(datums),
+            VariadicFunc::ListCreate => list_create(datums, temp_storage),
         }
     }
 
@@ -2627,6 +2853,9 @@ impl VariadicFunc {
             LengthString => ColumnType::new(ScalarType::Int32).nullable(true),
             Replace => ColumnType::new(ScalarType::String).nullable(true),
             JsonbBuildArray | JsonbBuildObject => ColumnType::new(ScalarType::Jsonb).nullable(true),
+            PointInBox => ColumnType::new(ScalarType::Bool).nullable(true),
+            SeededRandom => ColumnType::new(ScalarType::Float64),
+            ListCreate => ColumnType::new(ScalarType::List),
         }
     }
 
@@ -2635,6 +2864,7 @@ impl VariadicFunc {
         match self {
             VariadicFunc::Coalesce | VariadicFunc::Concatenate => false,
             VariadicFunc::JsonbBuildArray | VariadicFunc::JsonbBuildObject => false,
+            VariadicFunc::SeededRandom | VariadicFunc::ListCreate => false,
             _ => true,
         }
     }
@@ -2651,6 +2881,9 @@ impl fmt::Display for VariadicFunc {
             VariadicFunc::Replace => f.write_str("replace"),
             VariadicFunc::JsonbBuildArray => f.write_str("jsonb_build_array"),
             VariadicFunc::JsonbBuildObject => f.write_str("jsonb_build_object"),
+            VariadicFunc::PointInBox => f.write_str("point_in_box"),
+            VariadicFunc::SeededRandom => f.write_str("seeded_random"),
+            VariadicFunc::ListCreate => f.write_str("list_create"),
         }
     }
 }
@@ -2698,4 +2931,23 @@ mod test {
     fn ym(year: i32, month: u32) -> NaiveDateTime {
         NaiveDate::from_ymd(year, month, 1).and_hms(9, 9, 9)
     }
+
+    #[test]
+    fn seeded_random_is_deterministic() {
+        let r = |datums: &[Datum]| seeded_random(datums).unwrap_float64();
+
+        assert_eq!(r(&[Datum::Int32(1)]), r(&[Datum::Int32(1)]));
+        assert_ne!(r(&[Datum::Int32(1)]), r(&[Datum::Int32(2)]));
+        assert_ne!(r(&[Datum::Int32(1)]), r(&[Datum::Int64(1)]));
+        assert_ne!(r(&[Datum::Int32(1), Datum::Null]), r(&[Datum::Int32(1)]));
+
+        // Consecutive seeds should be spread evenly over [0, 1).
+        let n = 10_000;
+        let below_half = (0..n)
+            .map(|i| r(&[Datum::Int32(i)]))
+            .inspect(|x| assert!(*x >= 0.0 && *x < 1.0))
+            .filter(|x| *x < 0.5)
+            .count();
+        assert!(below_half > 4_800 && below_half < 5_200, "{}", below_half);
+    }
 }
@@ -8,6 +8,8 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
 use std::mem;
 
 use chrono::{DateTime, Utc};
@@ -19,6 +21,7 @@ use serde::{Deserialize, Serialize};
 use self::func::{BinaryFunc, DateTruncTo, NullaryFunc, UnaryFunc, VariadicFunc};
 use crate::pretty::DocBuilderExt;
 
+pub mod compiled;
 pub mod func;
 
 #[serde(rename_all = "snake_case")]
@@ -423,6 +426,65 @@ impl ScalarExpr {
         }
     }
 
+    /// Like [`ScalarExpr::eval`], but reports a division by zero, or an input
+    /// that a function rejects, as an error rather than evaluating it to
+    /// `NULL`.
+    ///
+    /// Dataflows cannot yet report errors, so only callers that evaluate an
+    /// expression on behalf of a single client request, like peeks, use this
+    /// method.
+    pub fn try_eval<'a>(
+        &'a self,
+        datums: &[Datum<'a>],
+        env: &'a EvalEnv,
+        temp_storage: &'a RowArena,
+    ) -> Result<Datum<'a>, EvalError> {
+        match self {
+            ScalarExpr::Column(_) | ScalarExpr::Literal(_, _) | ScalarExpr::CallNullary(_) => {
+                Ok(self.eval(datums, env, temp_storage))
+            }
+            ScalarExpr::CallUnary { func, expr } => {
+                let datum = expr.try_eval(datums, env, temp_storage)?;
+                if func.propagates_nulls() && datum.is_null() {
+                    Ok(Datum::Null)
+                } else if let Some(err) = func.input_error(datum) {
+                    Err(err)
+                } else {
+                    Ok(func.eval(datum, env, temp_storage))
+                }
+            }
+            ScalarExpr::CallBinary { func, expr1, expr2 } => {
+                let a = expr1.try_eval(datums, env, temp_storage)?;
+                let b = expr2.try_eval(datums, env, temp_storage)?;
+                if func.propagates_nulls() && (a.is_null() || b.is_null()) {
+                    Ok(Datum::Null)
+                } else if func.divides_by_zero(b) {
+                    Err(EvalError::DivisionByZero)
+                } else {
+                    Ok(func.eval(a, b, env, temp_storage))
+                }
+            }
+            ScalarExpr::CallVariadic { func, exprs } => {
+                let datums = exprs
+                    .iter()
+                    .map(|e| e.try_eval(datums, env, temp_storage))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if func.propagates_nulls() && datums.iter().any(|e| e.is_null()) {
+                    Ok(Datum::Null)
+                } else {
+                    Ok(func.eval(&datums, env, temp_storage))
+                }
+            }
+            ScalarExpr::If { cond, then, els } => {
+                match cond.try_eval(datums, env, temp_storage)? {
+                    Datum::True => then.try_eval(datums, env, temp_storage),
+                    Datum::False | Datum::Null => els.try_eval(datums, env, temp_storage),
+                    d => panic!("IF condition evaluated to non-boolean datum {:?}", d),
+                }
+            }
+        }
+    }
+
     /// Converts this [`ScalarExpr`] to a document for pretty printing. See
     /// [`RelationExpr::to_doc`](crate::RelationExpr::to_doc) for details on the
     /// approach.
@@ -491,6 +553,27 @@ impl ScalarExpr {
     }
 }
 
+/// An error that occurred while evaluating a [`ScalarExpr`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EvalError {
+    DivisionByZero,
+    /// A string that is not a label of the enum type to which it was cast.
+    InvalidEnumLabel(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => f.write_str("division by zero"),
+            EvalError::InvalidEnumLabel(label) => {
+                write!(f, "invalid input value for enum: \"{}\"", label)
+            }
+        }
+    }
+}
+
+impl Error for EvalError {}
+
 /// An evaluation environment. Stores state that controls how certain
 /// expressions are evaluated.
 #[derive(Default, Ord, PartialOrd, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
@@ -593,4 +676,65 @@ mod tests {
 !(true && false)"
         );
     }
+
+    #[test]
+    fn test_try_eval_division_by_zero() {
+        let env = EvalEnv::default();
+        let temp_storage = RowArena::new();
+        let int32_lit =
+            |n| ScalarExpr::literal(Datum::Int32(n), ColumnType::new(ScalarType::Int32));
+        let div = ScalarExpr::Column(0).call_binary(ScalarExpr::Column(1), BinaryFunc::DivInt32);
+
+        let datums = [Datum::Int32(6), Datum::Int32(3)];
+        assert_eq!(
+            div.try_eval(&datums, &env, &temp_storage),
+            Ok(Datum::Int32(2))
+        );
+
+        // `eval` evaluates a division by zero to `NULL`, but `try_eval`
+        // reports it, even beneath functions that do not propagate nulls.
+        let datums = [Datum::Int32(6), Datum::Int32(0)];
+        assert_eq!(div.eval(&datums, &env, &temp_storage), Datum::Null);
+        assert_eq!(
+            div.try_eval(&datums, &env, &temp_storage),
+            Err(EvalError::DivisionByZero)
+        );
+        let coalesce = ScalarExpr::CallVariadic {
+            func: VariadicFunc::Coalesce,
+            exprs: vec![div.clone(), int32_lit(1)],
+        };
+        assert_eq!(coalesce.eval(&datums, &env, &temp_storage), Datum::Int32(1));
+        assert_eq!(
+            coalesce.try_eval(&datums, &env, &temp_storage),
+            Err(EvalError::DivisionByZero)
+        );
+
+        // A null divisor is not a division by zero.
+        let datums = [Datum::Int32(6), Datum::Null];
+        assert_eq!(div.try_eval(&datums, &env, &temp_storage), Ok(Datum::Null));
+    }
+
+    #[test]
+    fn test_try_eval_invalid_enum_label() {
+        let env = EvalEnv::default();
+        let temp_storage = RowArena::new();
+        let labels = vec!["pending".to_owned(), "shipped".to_owned()];
+        let cast = ScalarExpr::Column(0).call_unary(UnaryFunc::CastStringToEnum(1, labels));
+
+        let datums = [Datum::String("shipped")];
+        assert_eq!(
+            cast.try_eval(&datums, &env, &temp_storage),
+            Ok(Datum::Int32(1))
+        );
+
+        let datums = [Datum::String("lost")];
+        assert_eq!(cast.eval(&datums, &env, &temp_storage), Datum::Null);
+        assert_eq!(
+            cast.try_eval(&datums, &env, &temp_storage),
+            Err(EvalError::InvalidEnumLabel("lost".into()))
+        );
+
+        let datums = [Datum::Null];
+        assert_eq!(cast.try_eval(&datums, &env, &temp_storage), Ok(Datum::Null));
+    }
 }
@@ -0,0 +1,69 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Basic full-text search.
+//!
+//! Text is split into tokens at every character that is not alphanumeric, and
+//! tokens are compared case insensitively. A query is a whitespace-separated
+//! list of terms. A text matches a query if it contains every token of the
+//! query's terms, except that a term prefixed with a minus sign (`-`) must not
+//! occur in the text at all.
+//!
+//! Unlike PostgreSQL's text search, there is no stemming and no list of stop
+//! words: `running` does not match `run`, and `the` is a token like any other.
+
+use std::collections::HashSet;
+
+/// Splits `text` into lowercase tokens.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+}
+
+/// Reports whether `text` matches `query`.
+///
+/// A query without any terms matches every text.
+pub fn matches(text: &str, query: &str) -> bool {
+    let tokens: HashSet<_> = tokenize(text).collect();
+    query.split_whitespace().all(|term| {
+        if term.starts_with('-') {
+            tokenize(&term[1..]).all(|token| !tokens.contains(&token))
+        } else {
+            tokenize(term).all(|token| tokens.contains(&token))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("GET /api/v1/users?id=42 -- 500 Internal Error").collect::<Vec<_>>(),
+            vec!["get", "api", "v1", "users", "id", "42", "500", "internal", "error"],
+        );
+        assert_eq!(tokenize("  ...  ").count(), 0);
+    }
+
+    #[test]
+    fn test_matches() {
+        let line = "2020-03-01 ERROR connection to kafka:9092 timed out";
+        assert!(matches(line, "error"));
+        assert!(matches(line, "Kafka ERROR"));
+        assert!(matches(line, "kafka:9092"));
+        assert!(!matches(line, "error warning"));
+        assert!(!matches(line, "error -timed"));
+        assert!(matches(line, "error -refused"));
+        assert!(!matches(line, "erro"));
+        assert!(matches(line, ""));
+    }
+}
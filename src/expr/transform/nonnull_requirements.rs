@@ -108,7 +108,8 @@ impl NonNullRequirements {
                     | UnaryTableFunc::JsonbObjectKeys
                     | UnaryTableFunc::JsonbArrayElements
                     | UnaryTableFunc::RegexpExtract(_)
-                    | UnaryTableFunc::CsvExtract(_) => {
+                    | UnaryTableFunc::CsvExtract(_)
+                    | UnaryTableFunc::TextTokens => {
                         expr.non_null_requirements(&mut columns);
                     }
                 }
@@ -23,6 +23,7 @@ use url::Url;
 
 use ore::collections::CollectionExt;
 use repr::decimal::{Significand, MAX_DECIMAL_PRECISION};
+use repr::jsonb::Jsonb;
 use repr::{ColumnType, Datum, RelationDesc, RelationType, Row, RowPacker, ScalarType};
 
 use crate::error::Result;
@@ -56,6 +57,40 @@ pub fn validate_key_schema(key_schema: &str, value_desc: &RelationDesc) -> Resul
     Ok(indices)
 }
 
+/// Validates that records written with the Avro value schema `writer_schema`
+/// can still be decoded into rows described by `reader_desc`, e.g. because
+/// the upstream schema has evolved since a source was created.
+///
+/// Schema resolution discards fields that only the writer knows about, so the
+/// writer schema is compatible iff it has a column with the same name and type
+/// for every column in `reader_desc`, and only introduces nulls into columns
+/// that were already nullable.
+pub fn validate_schema_compatibility(
+    writer_schema: &str,
+    reader_desc: &RelationDesc,
+) -> Result<()> {
+    let writer_desc = validate_value_schema(writer_schema)?;
+    for (name, reader_type) in reader_desc.iter() {
+        let name = match name {
+            Some(name) => name,
+            None => continue,
+        };
+        match writer_desc.get_by_name(name) {
+            Some((_, writer_type))
+                if writer_type.scalar_type == reader_type.scalar_type
+                    && (reader_type.nullable || !writer_type.nullable) => {}
+            Some((_, writer_type)) => bail!(
+                "column {} changed type: expected {:?}, found {:?}",
+                name,
+                reader_type,
+                writer_type,
+            ),
+            None => bail!("column {} no longer exists", name),
+        }
+    }
+    Ok(())
+}
+
 /// Converts an Apache Avro schema into a [`repr::RelationDesc`].
 pub fn validate_value_schema(schema: &str) -> Result<RelationDesc> {
     let schema = parse_schema(schema)?;
@@ -184,9 +219,9 @@ fn validate_schema_2(schema: &Schema) -> Result<ScalarType> {
             }
         }
 
-        Schema::Array(_) | Schema::Map(_) | Schema::Record { .. } => {
-            bail!("Unsupported scalar type in schema: {:?}", schema)
-        }
+        // Nested fields have no counterpart among our scalar types, so they
+        // are decoded as JSON.
+        Schema::Array(_) | Schema::Map(_) | Schema::Record { .. } => ScalarType::Jsonb,
     })
 }
 
@@ -426,17 +461,27 @@ impl Decoder {
                 Value::Decimal { unscaled, .. } => Ok(Datum::Decimal(
                     Significand::from_twos_complement_be(&unscaled)?,
                 )),
-                Value::Bytes(b) => Ok(Datum::Bytes(b)),
-                Value::String(s) => Ok(Datum::String(s)),
+                Value::Bytes(b) | Value::Fixed(_, b) => Ok(Datum::Bytes(b)),
+                Value::String(s) | Value::Enum(_, s) => Ok(Datum::String(s)),
                 Value::Union(v) => value_to_datum(v),
-                other @ Value::Fixed(..)
-                | other @ Value::Enum(..)
-                | other @ Value::Array(_)
-                | other @ Value::Map(_)
-                | other @ Value::Record(_) => bail!("unsupported avro value: {:?}", other),
+                other @ Value::Array(_) | other @ Value::Map(_) | other @ Value::Record(_) => {
+                    bail!("unsupported avro value: {:?}", other)
+                }
             }
         };
 
+        fn push_value(row: &mut RowPacker, v: &Value) -> Result<()> {
+            match v {
+                Value::Union(v) => push_value(row, v)?,
+                Value::Array(_) | Value::Map(_) | Value::Record(_) => {
+                    let jsonb = Jsonb::new(value_to_json(v)?)?;
+                    row.extend_by_row(&jsonb.into_row());
+                }
+                _ => row.push(value_to_datum(v)?),
+            }
+            Ok(())
+        }
+
         fn extract_row<'a, I>(v: Value, extra: I) -> Result<Option<Row>>
         where
             I: IntoIterator<Item = Datum<'a>>,
@@ -449,7 +494,7 @@ impl Decoder {
                 Value::Record(fields) => {
                     let mut row = RowPacker::new();
                     for (_, col) in fields.iter() {
-                        row.push(value_to_datum(col)?);
+                        push_value(&mut row, col)?;
                     }
                     for d in extra {
                         row.push(d);
@@ -495,6 +540,44 @@ impl Decoder {
     }
 }
 
+/// Converts a nested Avro value, which is stored in a `jsonb` column, to JSON.
+fn value_to_json(v: &Value) -> Result<serde_json::Value> {
+    use serde_json::Value as Json;
+    fn float_to_json(f: f64) -> Result<Json> {
+        match serde_json::Number::from_f64(f) {
+            Some(n) => Ok(Json::Number(n)),
+            None => bail!("{} cannot be represented as a jsonb number", f),
+        }
+    }
+    Ok(match v {
+        Value::Null => Json::Null,
+        Value::Boolean(b) => Json::Bool(*b),
+        Value::Int(i) => Json::from(*i),
+        Value::Long(i) => Json::from(*i),
+        Value::Float(f) => float_to_json((*f).into())?,
+        Value::Double(f) => float_to_json(*f)?,
+        Value::Date(d) => Json::String(d.to_string()),
+        Value::Timestamp(ts) => Json::String(ts.to_string()),
+        Value::String(s) | Value::Enum(_, s) => Json::String(s.clone()),
+        Value::Union(v) => value_to_json(v)?,
+        Value::Array(vs) => Json::Array(vs.iter().map(value_to_json).collect::<Result<_>>()?),
+        Value::Map(m) => Json::Object(
+            m.iter()
+                .map(|(k, v)| Ok((k.clone(), value_to_json(v)?)))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Record(fields) => Json::Object(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), value_to_json(v)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other @ Value::Bytes(_) | other @ Value::Fixed(..) | other @ Value::Decimal { .. } => {
+            bail!("unsupported avro value in nested field: {:?}", other)
+        }
+    })
+}
+
 pub fn encode_schema(desc: &RelationDesc) -> Result<serde_json::Value> {
     let mut fields = Vec::new();
     for (name, typ) in desc.iter() {
@@ -763,7 +846,9 @@ mod tests {
     use avro_rs::schema::Schema;
     use avro_rs::types::Value;
     use repr::decimal::Significand;
-    use repr::{Datum, RelationDesc};
+    use repr::jsonb::Jsonb;
+    use repr::{Datum, RelationDesc, RowPacker, ScalarType};
+    use serde_json::json;
 
     #[derive(Deserialize)]
     struct TestCase {
@@ -872,4 +957,171 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    /// Test that nested and enum fields are decoded into `jsonb` and `text`
+    /// columns, respectively.
+    fn test_decode_nested_fields() -> Result<(), failure::Error> {
+        let schema = r#"{
+            "type": "record",
+            "name": "envelope",
+            "fields": [
+                {
+                    "name": "before",
+                    "type": [
+                        "null",
+                        {
+                            "type": "record",
+                            "name": "row",
+                            "fields": [
+                                {"name": "tags", "type": {"type": "array", "items": "string"}},
+                                {
+                                    "name": "point",
+                                    "type": [
+                                        "null",
+                                        {
+                                            "type": "record",
+                                            "name": "point",
+                                            "fields": [{"name": "x", "type": "int"}]
+                                        }
+                                    ]
+                                },
+                                {
+                                    "name": "color",
+                                    "type": {"type": "enum", "name": "color", "symbols": ["red", "blue"]}
+                                }
+                            ]
+                        }
+                    ]
+                },
+                {"name": "after", "type": ["null", "row"]}
+            ]
+        }"#;
+
+        let desc = super::validate_value_schema(schema)?;
+        let types: Vec<_> = desc
+            .iter_types()
+            .map(|typ| (typ.scalar_type.clone(), typ.nullable))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                (ScalarType::Jsonb, false),
+                (ScalarType::Jsonb, true),
+                (ScalarType::String, false),
+            ]
+        );
+
+        let value = Value::Record(vec![
+            ("before".into(), Value::Union(Box::new(Value::Null))),
+            (
+                "after".into(),
+                Value::Union(Box::new(Value::Record(vec![
+                    (
+                        "tags".into(),
+                        Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+                    ),
+                    (
+                        "point".into(),
+                        Value::Union(Box::new(Value::Record(vec![("x".into(), Value::Int(2))]))),
+                    ),
+                    ("color".into(), Value::Enum(1, "blue".into())),
+                ]))),
+            ),
+        ]);
+        // Frame the datum as the Confluent serializers do: a zero magic byte
+        // and a four-byte schema ID.
+        let mut bytes = vec![0, 0, 0, 0, 1];
+        bytes.extend(avro_rs::to_avro_datum(
+            &super::parse_schema(schema)?,
+            value,
+        )?);
+
+        let mut decoder = super::Decoder::new(schema, None);
+        let diff_pair = decoder.decode(&bytes)?;
+        assert_eq!(diff_pair.before, None);
+
+        let mut expected = RowPacker::new();
+        expected.extend_by_row(&Jsonb::new(json!(["a", "b"]))?.into_row());
+        expected.extend_by_row(&Jsonb::new(json!({"x": 2}))?.into_row());
+        expected.push(Datum::String("blue"));
+        expected.push(Datum::Int64(1));
+        assert_eq!(diff_pair.after, Some(expected.finish()));
+
+        Ok(())
+    }
+
+    /// Wraps the fields of a row in a Debezium-style diff envelope.
+    fn envelope(fields: serde_json::Value) -> String {
+        json!({
+            "type": "record",
+            "name": "envelope",
+            "fields": [
+                {
+                    "name": "before",
+                    "type": ["null", {"type": "record", "name": "row", "fields": fields}]
+                },
+                {"name": "after", "type": ["null", "row"]}
+            ]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_schema_compatibility() -> Result<(), failure::Error> {
+        let reader_desc = super::validate_value_schema(&envelope(json!([
+            {"name": "a", "type": "long"},
+            {"name": "b", "type": ["null", "string"]}
+        ])))?;
+        let check = |fields| super::validate_schema_compatibility(&envelope(fields), &reader_desc);
+
+        // An unchanged schema is compatible.
+        check(json!([
+            {"name": "a", "type": "long"},
+            {"name": "b", "type": ["null", "string"]}
+        ]))?;
+
+        // So is one that adds defaults, or a new field with a default.
+        check(json!([
+            {"name": "a", "type": "long", "default": 0},
+            {"name": "b", "type": ["null", "string"], "default": null},
+            {"name": "c", "type": "int", "default": 0}
+        ]))?;
+
+        // So is one that stops producing nulls for a nullable column.
+        check(json!([
+            {"name": "a", "type": "long"},
+            {"name": "b", "type": "string"}
+        ]))?;
+
+        // A removed field is not.
+        let err = check(json!([{"name": "a", "type": "long"}])).unwrap_err();
+        assert_eq!(err.to_string(), "column b no longer exists");
+
+        // Nor is a field that changed type.
+        let err = check(json!([
+            {"name": "a", "type": "string"},
+            {"name": "b", "type": ["null", "string"]}
+        ]))
+        .unwrap_err();
+        assert!(
+            err.to_string().starts_with("column a changed type"),
+            "{}",
+            err
+        );
+
+        // Nor is one that introduces nulls into a column that is not nullable.
+        let err = check(json!([
+            {"name": "a", "type": ["null", "long"]},
+            {"name": "b", "type": ["null", "string"]}
+        ]))
+        .unwrap_err();
+        assert!(
+            err.to_string().starts_with("column a changed type"),
+            "{}",
+            err
+        );
+
+        Ok(())
+    }
 }
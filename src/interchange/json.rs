@@ -0,0 +1,111 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! JSON encoding of rows.
+
+use failure::bail;
+use serde_json::{Map, Value};
+
+use repr::jsonb::Jsonb;
+use repr::{strconv, ColumnType, Datum, RelationDesc, Row, ScalarType};
+
+/// Encodes rows of a relation as JSON objects keyed by column name.
+///
+/// Values whose types have no exact JSON counterpart, like decimals,
+/// timestamps, and intervals, are encoded as strings in the same text format
+/// that pgwire uses.
+#[derive(Debug)]
+pub struct Encoder {
+    names: Vec<String>,
+    types: Vec<ColumnType>,
+}
+
+impl Encoder {
+    /// Constructs an encoder for rows described by `desc`. Every column must
+    /// have a name, and no column may have an enum type, whose labels live in
+    /// the catalog.
+    pub fn new(desc: &RelationDesc) -> Result<Encoder, failure::Error> {
+        let mut names = vec![];
+        for (i, (name, typ)) in desc.iter().enumerate() {
+            let name = match name {
+                Some(name) => name.as_str(),
+                None => bail!("column {} must have a name to be encoded as JSON", i + 1),
+            };
+            if let ScalarType::Enum(_) = typ.scalar_type {
+                bail!(
+                    "column \"{}\" has an enum type, which cannot be encoded as JSON",
+                    name
+                );
+            }
+            names.push(name.to_owned());
+        }
+        Ok(Encoder {
+            names,
+            types: desc.typ().column_types.clone(),
+        })
+    }
+
+    /// Returns the names of the columns, in order.
+    pub fn column_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Encodes the specified columns of `row` as a JSON object.
+    pub fn encode_columns(&self, row: &Row, columns: &[usize]) -> Map<String, Value> {
+        let datums = row.unpack();
+        columns
+            .iter()
+            .map(|&i| (self.names[i].clone(), self.encode_datum(i, datums[i])))
+            .collect()
+    }
+
+    /// Encodes `datum`, which must belong to column `column`, as a JSON value.
+    pub fn encode_datum(&self, column: usize, datum: Datum) -> Value {
+        if datum.is_null() {
+            return Value::Null;
+        }
+        let mut buf = String::new();
+        match self.types[column].scalar_type {
+            // Lists only hold intermediate values, which never reach sinks.
+            ScalarType::Unknown | ScalarType::List => return Value::Null,
+            ScalarType::Bool => return Value::Bool(datum.unwrap_bool()),
+            ScalarType::Enum(_) => unreachable!("encoders reject enum columns"),
+            ScalarType::Int32 => return Value::from(datum.unwrap_int32()),
+            ScalarType::Int64 => return Value::from(datum.unwrap_int64()),
+            ScalarType::Float32 => return float_to_json(f64::from(datum.unwrap_float32())),
+            ScalarType::Float64 => return float_to_json(datum.unwrap_float64()),
+            ScalarType::String => return Value::String(datum.unwrap_str().to_owned()),
+            ScalarType::Jsonb => return Jsonb::from_datum(datum).as_serde_json().clone(),
+            ScalarType::Decimal(_, scale) => {
+                strconv::format_decimal(&mut buf, &datum.unwrap_decimal().with_scale(scale))
+            }
+            ScalarType::Date => strconv::format_date(&mut buf, datum.unwrap_date()),
+            ScalarType::Time => strconv::format_time(&mut buf, datum.unwrap_time()),
+            ScalarType::Timestamp => strconv::format_timestamp(&mut buf, datum.unwrap_timestamp()),
+            ScalarType::TimestampTz => {
+                strconv::format_timestamptz(&mut buf, datum.unwrap_timestamptz())
+            }
+            ScalarType::Interval => strconv::format_interval(&mut buf, datum.unwrap_interval()),
+            ScalarType::Point => strconv::format_point(&mut buf, datum.unwrap_point()),
+            ScalarType::Inet => strconv::format_inet(&mut buf, datum.unwrap_inet()),
+            ScalarType::Cidr => strconv::format_cidr(&mut buf, datum.unwrap_inet()),
+            ScalarType::Bytes => strconv::format_bytes(&mut buf, datum.unwrap_bytes()),
+        }
+        Value::String(buf)
+    }
+}
+
+/// Encodes a float as a JSON number, or, for infinities and NaN, which JSON
+/// numbers cannot represent, as a string.
+fn float_to_json(f: f64) -> Value {
+    match serde_json::Number::from_f64(f) {
+        Some(n) => Value::Number(n),
+        None => Value::String(f.to_string()),
+    }
+}
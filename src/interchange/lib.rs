@@ -13,4 +13,5 @@
 
 pub mod avro;
 mod error;
+pub mod json;
 pub mod protobuf;
@@ -18,15 +18,18 @@
 //! [0]: https://paper.dropbox.com/doc/Materialize-architecture-plans--AYSu6vvUu7ZDoOEZl7DNi8UQAg-sZj5rhJmISdZSfK0WBxAl
 
 use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
 use std::panic;
 use std::panic::PanicInfo;
 use std::path::PathBuf;
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use backtrace::Backtrace;
 use failure::{bail, format_err, ResultExt};
@@ -66,12 +69,66 @@ fn run() -> Result<(), failure::Error> {
         "maximum number of messages with same timestamp (default 5000) ",
         "SIZE",
     );
+    opts.optopt(
+        "",
+        "catalog-compaction-interval",
+        "how often to remove unneeded history from the catalog (default 1h)",
+        "DURATION/\"off\"",
+    );
+    opts.optopt(
+        "",
+        "peek-timeout",
+        "how long a query may wait for its data to become available (default off)",
+        "DURATION/\"off\"",
+    );
+    opts.optopt(
+        "",
+        "arrangement-memory-limit",
+        "refuse temporary dataflows once arrangements hold this many bytes (default off)",
+        "BYTES/\"off\"",
+    );
+    opts.optopt(
+        "",
+        "consistency-check-interval",
+        "compare materialized views with their definitions this often, for debugging (default off)",
+        "DURATION/\"off\"",
+    );
+    opts.optopt(
+        "",
+        "table-timestamp-granularity",
+        "assign table writes wall-clock timestamps at this granularity (default off)",
+        "DURATION/\"off\"",
+    );
+    opts.optopt(
+        "",
+        "merge-effort",
+        "updates' worth of merge work every arrangement does when idle (default 1000)",
+        "N/\"off\"",
+    );
     opts.optopt(
         "w",
         "threads",
         "number of per-process worker threads (default 1)",
         "N",
     );
+    opts.optmulti(
+        "",
+        "cluster",
+        "run an additional compute cluster with N worker threads (repeatable)",
+        "NAME=N",
+    );
+    opts.optopt(
+        "",
+        "worker-cores",
+        "pin worker threads to these cores, one per thread (e.g. 0-7,16-23 or node0)",
+        "CORES",
+    );
+    opts.optopt(
+        "",
+        "reserved-cores",
+        "run networking and result delivery threads on these cores",
+        "CORES",
+    );
     opts.optopt(
         "p",
         "process",
@@ -90,6 +147,18 @@ fn run() -> Result<(), failure::Error> {
         "text file whose lines are process addresses",
         "FILE",
     );
+    opts.optopt(
+        "",
+        "addresses",
+        "comma-separated process addresses, in order of process ID",
+        "HOST:PORT,...",
+    );
+    opts.optopt(
+        "",
+        "peek-results-addr",
+        "address at which the dataflow workers reach the coordinator to deliver peek results",
+        "HOST:PORT",
+    );
     opts.optopt(
         "D",
         "data-directory",
@@ -98,9 +167,34 @@ fn run() -> Result<(), failure::Error> {
     );
     opts.optopt("", "symbiosis", "(internal use only)", "URL");
     opts.optflag("", "no-prometheus", "Do not gather prometheus metrics");
+    opts.optopt(
+        "",
+        "ingest-token",
+        "enable the HTTP ingest endpoint, authenticated by this bearer token",
+        "TOKEN",
+    );
+    opts.optopt(
+        "",
+        "command-log",
+        "append every executed SQL statement to this file, for replay",
+        "PATH",
+    );
+    opts.optflag(
+        "",
+        "restart-on-crash",
+        "restart from the catalog in the data directory after a crash",
+    );
+    opts.optflag(
+        "",
+        "crash-report-message",
+        "include the panic message, which may contain user data, in crash report files",
+    );
 
-    // Inform the user about what they are using, and how to contact us.
-    beta_splash();
+    // Inform the user about what they are using, and how to contact us. A
+    // supervised process leaves this to its supervisor.
+    if env::var_os(SUPERVISED_VAR).is_none() {
+        beta_splash();
+    }
 
     let popts = opts.parse(&args[1..])?;
     if popts.opt_present("h") {
@@ -114,6 +208,13 @@ fn run() -> Result<(), failure::Error> {
         );
         return Ok(());
     }
+    if popts.opt_present("restart-on-crash") && env::var_os(SUPERVISED_VAR).is_none() {
+        let mut command = process::Command::new(env::current_exe()?);
+        command.args(&args[1..]).env(SUPERVISED_VAR, "1");
+        let status = supervise(command, MIN_RESTART_UPTIME)?;
+        process::exit(status.code().unwrap_or(1));
+    }
+    CRASH_REPORT_MESSAGE.store(popts.opt_present("crash-report-message"), Ordering::SeqCst);
 
     let logging_granularity = match popts
         .opt_str("logging-granularity")
@@ -136,35 +237,142 @@ fn run() -> Result<(), failure::Error> {
     };
 
     let max_increment_ts_size = popts.opt_get_default("batch-size", 10000_i64)?;
+    let catalog_compaction_interval = match popts
+        .opt_str("catalog-compaction-interval")
+        .as_ref()
+        .map(|x| x.as_str())
+    {
+        None => Some(parse_duration::parse("1h")?),
+        Some("off") => None,
+        Some(d) => Some(parse_duration::parse(&d)?),
+    };
+    let peek_timeout = match popts.opt_str("peek-timeout").as_ref().map(|x| x.as_str()) {
+        None | Some("off") => None,
+        Some(d) => Some(parse_duration::parse(&d)?),
+    };
+    let arrangement_memory_limit = match popts
+        .opt_str("arrangement-memory-limit")
+        .as_ref()
+        .map(|x| x.as_str())
+    {
+        None | Some("off") => None,
+        Some(n) => Some(n.parse()?),
+    };
+    let consistency_check_interval = match popts
+        .opt_str("consistency-check-interval")
+        .as_ref()
+        .map(|x| x.as_str())
+    {
+        None | Some("off") => None,
+        Some(d) => Some(parse_duration::parse(&d)?),
+    };
+    let table_timestamp_granularity = match popts
+        .opt_str("table-timestamp-granularity")
+        .as_ref()
+        .map(|x| x.as_str())
+    {
+        None | Some("off") => None,
+        Some(d) => Some(parse_duration::parse(&d)?),
+    };
+    let merge_effort: Option<usize> =
+        match popts.opt_str("merge-effort").as_ref().map(|x| x.as_str()) {
+            None => Some(1000),
+            Some("off") => None,
+            Some(n) => Some(n.parse()?),
+        };
+    // Differential merges the batches of an arrangement in proportion to the
+    // number of new updates, so a burst of updates can leave behind large
+    // merges that stall the arrangement when the next updates arrive. Idle
+    // merging lets each arrangement work through its pending merges whenever
+    // it is otherwise idle, so merge work tracks the arrangement's own update
+    // rate rather than accumulating.
+    //
+    // Differential only reads the effort from the environment, when it builds
+    // each arrangement, so the effort applies to every arrangement in the
+    // process alike, and is set here, before any worker thread starts, rather
+    // than by the server. An explicit setting in the environment takes
+    // precedence.
+    if env::var_os("DIFFERENTIAL_EAGER_MERGE").is_none() {
+        if let Some(effort) = merge_effort {
+            env::set_var("DIFFERENTIAL_EAGER_MERGE", effort.to_string());
+        }
+    }
     let threads = popts.opt_get_default("threads", 1)?;
+    let clusters = popts
+        .opt_strs("cluster")
+        .iter()
+        .map(|spec| parse_cluster(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    let worker_cores = match popts.opt_str("worker-cores") {
+        None => vec![],
+        Some(spec) => parse_cores(&spec)?,
+    };
+    let reserved_cores = match popts.opt_str("reserved-cores") {
+        None => vec![],
+        Some(spec) => parse_cores(&spec)?,
+    };
     let process = popts.opt_get_default("process", 0)?;
-    let processes = popts.opt_get_default("processes", 1)?;
+    let address_list = popts.opt_str("addresses");
     let address_file = popts.opt_str("address-file");
+    if address_list.is_some() && address_file.is_some() {
+        bail!("--addresses and --address-file cannot both be specified");
+    }
+    let processes = match &address_list {
+        Some(list) if !popts.opt_present("processes") => list.split(',').count(),
+        _ => popts.opt_get_default("processes", 1)?,
+    };
     let gather_metrics = !popts.opt_present("no-prometheus");
 
     if process >= processes {
         bail!("process ID {} is not between 0 and {}", process, processes);
     }
 
-    let addresses = match address_file {
-        None => (0..processes)
+    let addresses = match (address_list, address_file) {
+        (Some(list), _) => {
+            let addrs: Vec<_> = list.split(',').map(|addr| addr.trim().to_owned()).collect();
+            if addrs.len() != processes {
+                bail!(
+                    "--addresses lists {} addresses, but there are {} processes",
+                    addrs.len(),
+                    processes
+                );
+            }
+            resolve_addresses(addrs)?
+        }
+        (None, Some(address_file)) => read_address_file(&address_file, processes)?,
+        (None, None) => (0..processes)
             .map(|i| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6875 + i as u16))
             .collect(),
-        Some(address_file) => read_address_file(&address_file, processes)?,
+    };
+    let peek_results_addr = match popts.opt_str("peek-results-addr") {
+        None => None,
+        Some(addr) => resolve_addresses(vec![addr])?.pop(),
     };
 
     let data_directory = popts.opt_get_default("data-directory", PathBuf::from("mzdata"))?;
+    *CRASH_REPORT_DIR.lock().unwrap() = Some(data_directory.join("crash-reports"));
 
     let _server = materialized::serve(materialized::Config {
         logging_granularity,
         timestamp_frequency,
         max_increment_ts_size,
+        catalog_compaction_interval,
+        peek_timeout,
+        arrangement_memory_limit,
+        consistency_check_interval,
+        table_timestamp_granularity,
         threads,
+        clusters,
+        worker_cores,
+        reserved_cores,
         process,
         addresses,
+        peek_results_addr,
         data_directory: Some(data_directory),
         symbiosis_url: popts.opt_str("symbiosis"),
         gather_metrics,
+        ingest_token: popts.opt_str("ingest-token"),
+        command_log: popts.opt_str("command-log").map(PathBuf::from),
     })?;
 
     // Block forever.
@@ -173,6 +381,39 @@ fn run() -> Result<(), failure::Error> {
     }
 }
 
+/// Parses a `NAME=N` cluster specification.
+fn parse_cluster(spec: &str) -> Result<(String, usize), failure::Error> {
+    let mut parts = spec.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(threads)) if !name.is_empty() => {
+            let threads = threads.parse::<usize>().with_context(|err| {
+                format!("invalid thread count for cluster {}: {}", name, err)
+            })?;
+            Ok((name.to_owned(), threads))
+        }
+        _ => bail!("invalid cluster {:?}: expected NAME=N", spec),
+    }
+}
+
+/// Parses a list of CPU cores, like `0-3,8`, in which `nodeN` stands for all
+/// of the cores of NUMA node `N`.
+fn parse_cores(spec: &str) -> Result<Vec<usize>, failure::Error> {
+    let mut cores = vec![];
+    for part in spec.split(',') {
+        if part.starts_with("node") {
+            let node = part["node".len()..]
+                .parse::<usize>()
+                .map_err(|_| format_err!("invalid NUMA node {:?}", part))?;
+            let node_cores = ore::thread::numa_node_cores(node)
+                .with_context(|err| format!("reading cores of NUMA node {}: {}", node, err))?;
+            cores.extend(node_cores);
+        } else {
+            cores.extend(ore::thread::parse_core_list(part).map_err(|err| format_err!("{}", err))?);
+        }
+    }
+    Ok(cores)
+}
+
 fn read_address_file(path: &str, n: usize) -> Result<Vec<SocketAddr>, failure::Error> {
     let file =
         File::open(path).with_context(|err| format!("opening address file {}: {}", path, err))?;
@@ -181,6 +422,10 @@ fn read_address_file(path: &str, n: usize) -> Result<Vec<SocketAddr>, failure::E
     if addrs.len() < n || lines.next().is_some() {
         bail!("address file does not contain exactly {} lines", n);
     }
+    resolve_addresses(addrs)
+}
+
+fn resolve_addresses(addrs: Vec<String>) -> Result<Vec<SocketAddr>, failure::Error> {
     Ok(addrs
         .into_iter()
         .map(|addr| match addr.to_socket_addrs() {
@@ -197,6 +442,44 @@ fn read_address_file(path: &str, n: usize) -> Result<Vec<SocketAddr>, failure::E
 
 lazy_static! {
     static ref PANIC_MUTEX: Mutex<()> = Mutex::new(());
+    static ref CRASH_REPORT_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+static CRASH_REPORT_MESSAGE: AtomicBool = AtomicBool::new(false);
+
+/// The minimum uptime for a crashed process to be restarted. A process that
+/// crashes sooner likely crashed while recovering its catalog, and restarting
+/// it would only crash it again.
+const MIN_RESTART_UPTIME: Duration = Duration::from_secs(60);
+
+/// The environment variable that marks a process as the child of a
+/// supervising process, so that it does not supervise a child of its own.
+const SUPERVISED_VAR: &str = "MATERIALIZED_SUPERVISED";
+
+/// Runs `command`, which starts a materialized process, and runs it again
+/// whenever the process crashes. The new process recovers its state from the
+/// catalog in the data directory.
+///
+/// Returns the exit status of the last process, once a process exits
+/// successfully or crashes within `min_uptime` of starting.
+fn supervise(
+    mut command: process::Command,
+    min_uptime: Duration,
+) -> Result<process::ExitStatus, io::Error> {
+    loop {
+        let start = Instant::now();
+        let status = command.spawn()?.wait()?;
+        if status.success() {
+            return Ok(status);
+        } else if start.elapsed() < min_uptime {
+            eprintln!(
+                "materialized crashed within {}s of starting; not restarting",
+                min_uptime.as_secs()
+            );
+            return Ok(status);
+        }
+        eprintln!("materialized crashed ({}); restarting...", status);
+    }
 }
 
 fn handle_panic(panic_info: &PanicInfo) {
@@ -213,6 +496,20 @@ fn handle_panic(panic_info: &PanicInfo) {
         },
     };
 
+    // If the panic occurred on a dataflow worker, describe what the worker was
+    // doing, as the backtrace alone does not identify the command or dataflow
+    // that triggered the panic.
+    let worker_details = match dataflow::crash_context() {
+        None => String::new(),
+        Some(context) => format!(
+            " worker: {}\ncommand: {}\ndataflow: {}\n",
+            context.worker_id,
+            context.last_command.as_deref().unwrap_or("<none>"),
+            context.active_dataflow.as_deref().unwrap_or("<none>"),
+        ),
+    };
+
+    let workers = describe_workers(&dataflow::crash_contexts());
     let backtrace = Backtrace::new();
 
     eprintln!(
@@ -224,14 +521,101 @@ To protect your privacy, we do not collect crash reports automatically.
 
  thread: {}
 message: {}
-{:?}
+{}{}{:?}
 "#,
-        thr_name, msg, backtrace
+        thr_name, msg, worker_details, workers, backtrace
     );
 
+    // The panic message may quote the data that triggered the panic, so it
+    // is omitted from the persisted report unless explicitly requested. The
+    // location of the panic serves to identify it instead.
+    let location = match panic_info.location() {
+        Some(location) => location.to_string(),
+        None => "<unknown>".into(),
+    };
+    let report_msg = if CRASH_REPORT_MESSAGE.load(Ordering::SeqCst) {
+        msg
+    } else {
+        "<omitted; start materialized with --crash-report-message to include it>"
+    };
+    let report = format!(
+        "materialized v{} ({})\n  thread: {}\nlocation: {}\n message: {}\n{}{}{:?}\n",
+        materialized::VERSION,
+        materialized::BUILD_SHA,
+        thr_name,
+        location,
+        report_msg,
+        worker_details,
+        workers,
+        backtrace
+    );
+    match write_crash_report(&report) {
+        Ok(Some(path)) => eprintln!("crash report written to {}", path.display()),
+        Ok(None) => (),
+        Err(err) => eprintln!("unable to write crash report: {}", err),
+    }
+
     process::exit(1);
 }
 
+/// Describes the state of every dataflow worker in the process, for inclusion
+/// in a crash report. The description names dataflows and reports their
+/// frontiers and pending peeks, but contains no user data.
+fn describe_workers(contexts: &[dataflow::CrashContext]) -> String {
+    let mut out = String::new();
+    if contexts.is_empty() {
+        return out;
+    }
+    out.push_str("workers:\n");
+    for context in contexts {
+        writeln!(
+            out,
+            "  worker {}: command: {}; dataflow: {}",
+            context.worker_id,
+            context.last_command.as_deref().unwrap_or("<none>"),
+            context.active_dataflow.as_deref().unwrap_or("<none>"),
+        )
+        .unwrap();
+        for peek in &context.pending_peeks {
+            writeln!(out, "    pending {}", peek).unwrap();
+        }
+    }
+    // Every worker installs the same dataflows, so list them once, along with
+    // the frontier that each worker last reported for them.
+    out.push_str("dataflows:\n");
+    for (id, name) in &contexts[0].dataflows {
+        write!(out, "  {} ({}): frontiers", id, name).unwrap();
+        for context in contexts {
+            match context.frontiers.get(id) {
+                Some(frontier) => write!(out, " {:?}", frontier).unwrap(),
+                None => out.push_str(" ?"),
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes `report` to a new file in the crash report directory, if one has
+/// been configured, and returns the path of the file.
+fn write_crash_report(report: &str) -> Result<Option<PathBuf>, io::Error> {
+    let dir = match CRASH_REPORT_DIR.try_lock() {
+        Ok(dir) => match &*dir {
+            Some(dir) => dir.clone(),
+            None => return Ok(None),
+        },
+        Err(_) => return Ok(None),
+    };
+    fs::create_dir_all(&dir)?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("crash-{}-{}.txt", secs, process::id()));
+    fs::write(&path, report)?;
+    Ok(Some(path))
+}
+
 /// Print to the screen information about how to contact us.
 fn beta_splash() {
     eprintln!(
@@ -249,3 +633,107 @@ to improve both our software and your queries! Please reach out at:
 "
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+    use std::time::Duration;
+
+    use expr::GlobalId;
+
+    use super::{describe_workers, supervise, write_crash_report, CRASH_REPORT_DIR};
+
+    /// Returns a command that records each time it runs in `log`, and fails
+    /// until it has run `n` times.
+    fn fail_until(log: &std::path::Path, n: usize) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!(
+            "echo run >> {0}; test $(wc -l < {0}) -ge {1}",
+            log.display(),
+            n
+        ));
+        command
+    }
+
+    #[test]
+    fn test_supervise() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let runs = |name| -> Result<usize, std::io::Error> {
+            Ok(fs::read_to_string(dir.path().join(name))?.lines().count())
+        };
+
+        // A process that exits successfully is not restarted.
+        let status = supervise(fail_until(&dir.path().join("a"), 1), Duration::from_secs(0))?;
+        assert!(status.success());
+        assert_eq!(runs("a")?, 1);
+
+        // A process that crashes is restarted until it exits successfully.
+        let status = supervise(fail_until(&dir.path().join("b"), 3), Duration::from_secs(0))?;
+        assert!(status.success());
+        assert_eq!(runs("b")?, 3);
+
+        // A process that crashes soon after starting is not restarted, and its
+        // exit status is passed on.
+        let status = supervise(
+            fail_until(&dir.path().join("c"), 3),
+            Duration::from_secs(60),
+        )?;
+        assert_eq!(status.code(), Some(1));
+        assert_eq!(runs("c")?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_workers() {
+        assert_eq!(describe_workers(&[]), "");
+
+        let mut worker0 = dataflow::CrashContext {
+            worker_id: 0,
+            last_command: Some("CreateDataflows(u1)".into()),
+            active_dataflow: Some("materialize.public.v".into()),
+            pending_peeks: vec!["peek at u1 @ 5".into()],
+            ..Default::default()
+        };
+        worker0
+            .dataflows
+            .insert(GlobalId::User(1), "materialize.public.v".into());
+        worker0.frontiers.insert(GlobalId::User(1), vec![7]);
+        let mut worker1 = worker0.clone();
+        worker1.worker_id = 1;
+        worker1.last_command = None;
+        worker1.active_dataflow = None;
+        worker1.pending_peeks.clear();
+        worker1.frontiers.clear();
+
+        assert_eq!(
+            describe_workers(&[worker0, worker1]),
+            "workers:
+  worker 0: command: CreateDataflows(u1); dataflow: materialize.public.v
+    pending peek at u1 @ 5
+  worker 1: command: <none>; dataflow: <none>
+dataflows:
+  u1 (materialize.public.v): frontiers [7] ?
+"
+        );
+    }
+
+    #[test]
+    fn test_write_crash_report() -> Result<(), Box<dyn std::error::Error>> {
+        // Without a crash report directory, no report is written.
+        assert_eq!(write_crash_report("report")?, None);
+
+        let dir = tempfile::tempdir()?;
+        let reports = dir.path().join("crash-reports");
+        *CRASH_REPORT_DIR.lock().unwrap() = Some(reports.clone());
+        let path = write_crash_report("report");
+        *CRASH_REPORT_DIR.lock().unwrap() = None;
+
+        // The directory is created if necessary.
+        let path = path?.unwrap();
+        assert_eq!(path.parent(), Some(reports.as_path()));
+        assert_eq!(fs::read_to_string(&path)?, "report");
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Incremental reads of materialized views over HTTP.
+//!
+//! `GET /changes/<view>?since=<timestamp>` returns the net changes to the
+//! named source or view after `timestamp`, as a JSON object of the form:
+//!
+//! ```json
+//! {"timestamp": 1234, "changes": [{"row": {"a": 1}, "diff": -1}]}
+//! ```
+//!
+//! The returned `timestamp` is to be supplied as `since` in the client's next
+//! request. If `since` is omitted, the entire contents of the view are
+//! returned as insertions. Changes are computed from the history retained by
+//! the view's index, so a client that falls further behind than the index's
+//! compaction window must read the entire contents again.
+
+use failure::format_err;
+use futures::channel::mpsc::UnboundedSender;
+use futures::sink::SinkExt;
+use hyper::{header, Body, Request, Response, StatusCode};
+use serde_json::json;
+
+use dataflow_types::{PeekResponse, Timestamp};
+use interchange::json::Encoder;
+
+/// The path prefix under which the changes endpoint is served.
+pub const PATH_PREFIX: &str = "/changes/";
+
+pub async fn handle_changes(
+    req: Request<Body>,
+    mut cmd_tx: UnboundedSender<coord::Command>,
+) -> Result<Response<Body>, failure::Error> {
+    let name = req.uri().path()[PATH_PREFIX.len()..].to_owned();
+    let since = match parse_since(req.uri().query()) {
+        Ok(since) => since,
+        Err(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+    };
+
+    let (tx, rx) = futures::channel::oneshot::channel();
+    cmd_tx
+        .send(coord::Command::PeekChanges { name, since, tx })
+        .await?;
+    let res = match rx.await? {
+        Ok(res) => res,
+        Err(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+    };
+    let encoder = match Encoder::new(&res.desc) {
+        Ok(encoder) => encoder,
+        Err(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+    };
+    let changes = match res.changes.await? {
+        PeekResponse::Changes(changes) => changes,
+        PeekResponse::Error(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+        res => return Err(format_err!("unexpected response to peek: {:?}", res)),
+    };
+
+    let columns: Vec<_> = (0..encoder.column_names().len()).collect();
+    let changes: Vec<_> = changes
+        .iter()
+        .map(|(row, diff)| json!({ "row": encoder.encode_columns(row, &columns), "diff": diff }))
+        .collect();
+    let body = json!({ "timestamp": res.timestamp, "changes": changes });
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap())
+}
+
+/// Extracts the `since` parameter from a query string, if present.
+fn parse_since(query: Option<&str>) -> Result<Option<Timestamp>, failure::Error> {
+    for param in query.unwrap_or("").split('&') {
+        let mut parts = param.splitn(2, '=');
+        if parts.next() == Some("since") {
+            let value = parts.next().unwrap_or("");
+            return value
+                .parse()
+                .map(Some)
+                .map_err(|_| format_err!("invalid since timestamp: {:?}", value));
+        }
+    }
+    Ok(None)
+}
+
+fn respond(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(format!("{}\n", message)))
+        .unwrap()
+}
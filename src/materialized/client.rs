@@ -0,0 +1,249 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! An in-process client for an embedded server.
+//!
+//! A [`Client`] issues SQL directly to the coordinator of the [`Server`] that
+//! created it, without going through pgwire, and exchanges rows as [`Row`]s
+//! rather than as text. Each client has its own session, much like a pgwire
+//! connection.
+//!
+//! [`Server`]: crate::Server
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+
+use failure::{bail, format_err};
+use futures::channel::mpsc::UnboundedSender;
+use futures::sink::SinkExt;
+
+use coord::ExecuteResponse;
+use dataflow_types::{PeekResponse, Update};
+use repr::{ColumnType, Datum, Row};
+use sql::Session;
+
+/// The connection ID to assign to the next client. Clients are numbered from
+/// just above the range of IDs that pgwire allocates, so that the
+/// cancellation of one client's `TAIL` can never affect a pgwire connection.
+static NEXT_CONN_ID: AtomicU32 = AtomicU32::new((1 << 16) + 1);
+
+/// Allocates a connection ID for a connection that does not arrive via pgwire,
+/// like an embedded client or an HTTP ingest request.
+pub(crate) fn next_conn_id() -> u32 {
+    NEXT_CONN_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+/// A handle through which to define and query views on an embedded server.
+///
+/// Dropping a client cancels its `TAIL`, if any. A client does not keep its
+/// server running; once the server is dropped, every method fails.
+///
+/// The names of tables and views are used verbatim, as if they were quoted
+/// identifiers, so they are case sensitive.
+pub struct Client {
+    cmd_tx: Weak<UnboundedSender<coord::Command>>,
+    conn_id: u32,
+    /// The client's session, which is lent to the coordinator while a
+    /// command executes. It is absent if a command was interrupted before the
+    /// coordinator returned it.
+    session: Option<Session>,
+}
+
+impl Client {
+    pub(crate) fn new(cmd_tx: &Arc<UnboundedSender<coord::Command>>) -> Client {
+        Client {
+            cmd_tx: Arc::downgrade(cmd_tx),
+            conn_id: next_conn_id(),
+            session: Some(Session::default()),
+        }
+    }
+
+    /// Creates a materialized view named `name` whose contents are the
+    /// results of the SQL query `query`.
+    pub async fn create_view(&mut self, name: &str, query: &str) -> Result<(), failure::Error> {
+        let sql = format!("CREATE MATERIALIZED VIEW {} AS {}", quote(name)?, query);
+        match self.execute(&sql, &[]).await? {
+            ExecuteResponse::CreatedView => Ok(()),
+            res => bail!("unexpected response to CREATE VIEW: {:?}", res),
+        }
+    }
+
+    /// Appends `rows` to the table named `table`, returning the number of
+    /// rows inserted.
+    ///
+    /// The datums of each row must match the types of the table's columns.
+    pub async fn insert(&mut self, table: &str, rows: &[Row]) -> Result<usize, failure::Error> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let mut values = vec![];
+        let mut params = vec![];
+        for row in rows {
+            let mut value = vec![];
+            for datum in row.iter() {
+                params.push(datum);
+                value.push(format!("${}", params.len()));
+            }
+            values.push(format!("({})", value.join(", ")));
+        }
+        let sql = format!("INSERT INTO {} VALUES {}", quote(table)?, values.join(", "));
+        match self.execute(&sql, &params).await? {
+            ExecuteResponse::Inserted(n) => Ok(n),
+            res => bail!("unexpected response to INSERT: {:?}", res),
+        }
+    }
+
+    /// Returns the results of the SQL query `query`.
+    pub async fn peek(&mut self, query: &str) -> Result<Vec<Row>, failure::Error> {
+        let rx = match self.execute(query, &[]).await? {
+            ExecuteResponse::SendRows(rx) => rx,
+            ExecuteResponse::StreamRows(stream) => coord::collect_rows(stream),
+            res => bail!("unexpected response to query: {:?}", res),
+        };
+        match rx.await?.0 {
+            PeekResponse::Rows(rows) => Ok(rows),
+            PeekResponse::Error(err) => Err(err.into()),
+            PeekResponse::Canceled => bail!("query canceled"),
+            res => bail!("unexpected peek response: {:?}", res),
+        }
+    }
+
+    /// Streams the changes to the source or view named `name` that occur
+    /// after the tail begins.
+    ///
+    /// A client may only tail one source or view at a time. The tail ends
+    /// when the client is dropped or tails something else.
+    pub async fn tail(
+        &mut self,
+        name: &str,
+    ) -> Result<comm::mpsc::Receiver<Vec<Update>>, failure::Error> {
+        let sql = format!("TAIL {}", quote(name)?);
+        match self.execute(&sql, &[]).await? {
+            ExecuteResponse::Tailing { rx } => Ok(rx),
+            res => bail!("unexpected response to TAIL: {:?}", res),
+        }
+    }
+
+    /// Executes the SQL statement `sql`, binding the parameters `$1`, `$2`,
+    /// and so on to `params`.
+    ///
+    /// If the returned future is dropped before it completes, or the server
+    /// shuts down while the statement executes, the client's session is lost,
+    /// and every later call fails.
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        params: &[Datum<'_>],
+    ) -> Result<ExecuteResponse, failure::Error> {
+        let mut cmd_tx = self.cmd_tx()?;
+        let session = match self.session.take() {
+            Some(session) => session,
+            None => bail!("client session was lost when a previous command was interrupted"),
+        };
+        let (tx, rx) = futures::channel::oneshot::channel();
+        cmd_tx
+            .send(coord::Command::Parse {
+                name: String::new(),
+                sql: sql.into(),
+                session,
+                tx,
+            })
+            .await?;
+        let coord::Response {
+            result,
+            mut session,
+        } = rx.await?;
+        if let Err(err) = result {
+            self.session = Some(session);
+            return Err(err);
+        }
+        let bound = bind_params(&session, params)
+            .and_then(|params| session.set_portal(String::new(), String::new(), params, vec![]));
+        if let Err(err) = bound {
+            self.session = Some(session);
+            return Err(err);
+        }
+
+        let (tx, rx) = futures::channel::oneshot::channel();
+        cmd_tx
+            .send(coord::Command::Execute {
+                portal_name: String::new(),
+                session,
+                conn_id: self.conn_id,
+                tx,
+            })
+            .await?;
+        let coord::Response { result, session } = rx.await?;
+        self.session = Some(session);
+        result
+    }
+
+    fn cmd_tx(&self) -> Result<UnboundedSender<coord::Command>, failure::Error> {
+        match self.cmd_tx.upgrade() {
+            Some(cmd_tx) => Ok((*cmd_tx).clone()),
+            None => bail!("server has shut down"),
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(cmd_tx) = self.cmd_tx.upgrade() {
+            let _ = cmd_tx.unbounded_send(coord::Command::CancelRequest {
+                conn_id: self.conn_id,
+            });
+        }
+    }
+}
+
+/// Quotes `name` as an identifier. The SQL parser does not support escaped
+/// quotes within identifiers, so names that contain a double quote are
+/// rejected.
+fn quote(name: &str) -> Result<String, failure::Error> {
+    if name.contains('"') {
+        bail!("name {:?} contains a double quote", name);
+    }
+    Ok(format!("\"{}\"", name))
+}
+
+/// Pairs each of `params` with the type of the corresponding parameter of the
+/// unnamed prepared statement in `session`.
+fn bind_params<'a>(
+    session: &Session,
+    params: &[Datum<'a>],
+) -> Result<Vec<(Datum<'a>, repr::ScalarType)>, failure::Error> {
+    let stmt = session
+        .get_prepared_statement("")
+        .expect("unnamed statement to be present after parsing");
+    let types = stmt.param_types();
+    if types.len() != params.len() {
+        bail!(
+            "statement requires {} parameters, but {} were supplied",
+            types.len(),
+            params.len()
+        );
+    }
+    types
+        .iter()
+        .zip(params)
+        .enumerate()
+        .map(|(i, (typ, datum))| {
+            let (_, scalar_type) = pgrepr::null_datum(*typ);
+            if !datum.is_instance_of(&ColumnType::new(scalar_type.clone()).nullable(true)) {
+                return Err(format_err!(
+                    "parameter ${} has type {:?}, but {:?} was supplied",
+                    i + 1,
+                    scalar_type,
+                    datum
+                ));
+            }
+            Ok((*datum, scalar_type))
+        })
+        .collect()
+}
@@ -9,6 +9,7 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Write};
+use std::sync::Arc;
 use std::time::Instant;
 
 use futures::channel::mpsc::UnboundedSender;
@@ -19,6 +20,9 @@ use lazy_static::lazy_static;
 use prometheus::{register_gauge_vec, Encoder, Gauge, GaugeVec};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+use crate::changes;
+use crate::ingest;
+
 lazy_static! {
     static ref SERVER_METADATA_RAW: GaugeVec = register_gauge_vec!(
         "mz_server_metadata_seconds",
@@ -50,10 +54,12 @@ pub async fn handle_connection<A: 'static + AsyncRead + AsyncWrite + Unpin>(
     a: A,
     cmd_tx: UnboundedSender<coord::Command>,
     gather_metrics: bool,
+    ingest_token: Option<Arc<str>>,
     start_time: Instant,
 ) -> Result<(), failure::Error> {
     let svc = service::service_fn(move |req: Request<Body>| {
         let cmd_tx = cmd_tx.clone();
+        let ingest_token = ingest_token.clone();
         async move {
             match (req.method(), req.uri().path()) {
                 (&Method::GET, "/") => handle_home(req).await,
@@ -62,6 +68,13 @@ pub async fn handle_connection<A: 'static + AsyncRead + AsyncWrite + Unpin>(
                 }
                 (&Method::GET, "/status") => handle_status(req, start_time).await,
                 (&Method::GET, "/internal/catalog") => handle_internal_catalog(req, cmd_tx).await,
+                (&Method::GET, "/catalog.sql") => handle_catalog_sql(req, cmd_tx).await,
+                (&Method::GET, path) if path.starts_with(changes::PATH_PREFIX) => {
+                    changes::handle_changes(req, cmd_tx).await
+                }
+                (&Method::POST, path) if path.starts_with(ingest::PATH_PREFIX) => {
+                    ingest::handle_ingest(req, cmd_tx, ingest_token).await
+                }
                 _ => handle_unknown(req).await,
             }
         }
@@ -130,6 +143,7 @@ async fn handle_status(
         s.insert("mz_dataflow_events_read_total");
         s.insert("mz_kafka_bytes_read_total");
         s.insert("mz_worker_command_queue_size");
+        s.insert("mz_worker_arrangement_merge_debt_records");
         s.insert("mz_command_durations");
         s
     };
@@ -327,6 +341,19 @@ async fn handle_internal_catalog(
         .unwrap())
 }
 
+async fn handle_catalog_sql(
+    _: Request<Body>,
+    mut cmd_tx: UnboundedSender<coord::Command>,
+) -> Result<Response<Body>, failure::Error> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    cmd_tx.send(coord::Command::DumpCatalogSql { tx }).await?;
+    let dump = rx.await?;
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/sql")
+        .body(Body::from(dump))
+        .unwrap())
+}
+
 async fn handle_unknown(_: Request<Body>) -> Result<Response<Body>, failure::Error> {
     Ok(Response::builder()
         .status(403)
@@ -0,0 +1,398 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Streaming row ingestion over HTTP.
+//!
+//! `POST /ingest/<table>` appends the rows in the request body to the named
+//! table. The body is either newline-delimited JSON, where each line is an
+//! array of column values, or CSV without a header row. Rows are grouped into
+//! batches of up to [`BATCH_ROWS`] rows, and each batch is executed as a
+//! single `INSERT` statement.
+//!
+//! The response body is streamed back as newline-delimited JSON, with one
+//! acknowledgement per committed batch. Processing stops at the first batch
+//! that fails, whose acknowledgement carries the error instead; batches that
+//! were acknowledged before the failure remain committed. A successful request
+//! ends with a final line that reports the total number of rows ingested.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use failure::{bail, format_err};
+use futures::channel::mpsc::UnboundedSender;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use hyper::{header, Body, Request, Response, StatusCode};
+use serde_json::json;
+
+use coord::ExecuteResponse;
+use sql::Session;
+
+use crate::client;
+
+/// The path prefix under which the ingest endpoint is served.
+pub const PATH_PREFIX: &str = "/ingest/";
+
+/// The maximum number of rows in a single `INSERT` batch.
+const BATCH_ROWS: usize = 1024;
+
+/// The maximum number of ingest requests that may be in progress at once.
+/// Requests beyond this limit are rejected with `429 Too Many Requests`.
+const MAX_ACTIVE_REQUESTS: usize = 16;
+
+static ACTIVE_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Tracks an in-progress ingest request for the purposes of back-pressure.
+struct ActiveRequest;
+
+impl ActiveRequest {
+    fn acquire() -> Option<ActiveRequest> {
+        if ACTIVE_REQUESTS.fetch_add(1, Ordering::SeqCst) >= MAX_ACTIVE_REQUESTS {
+            ACTIVE_REQUESTS.fetch_sub(1, Ordering::SeqCst);
+            None
+        } else {
+            Some(ActiveRequest)
+        }
+    }
+}
+
+impl Drop for ActiveRequest {
+    fn drop(&mut self) {
+        ACTIVE_REQUESTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Ndjson,
+    Csv,
+}
+
+pub async fn handle_ingest(
+    req: Request<Body>,
+    cmd_tx: UnboundedSender<coord::Command>,
+    token: Option<Arc<str>>,
+) -> Result<Response<Body>, failure::Error> {
+    let token = match token {
+        Some(token) => token,
+        None => {
+            return Ok(respond(
+                StatusCode::NOT_FOUND,
+                "ingest endpoint is disabled",
+            ))
+        }
+    };
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| strip_prefix(value, "Bearer "))
+        .map_or(false, |presented| {
+            constant_time_eq(presented.as_bytes(), token.as_bytes())
+        });
+    if !authorized {
+        let mut res = respond(StatusCode::UNAUTHORIZED, "missing or invalid bearer token");
+        res.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            header::HeaderValue::from_static("Bearer"),
+        );
+        return Ok(res);
+    }
+
+    let table = match quote_table_name(&req.uri().path()[PATH_PREFIX.len()..]) {
+        Ok(table) => table,
+        Err(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+    };
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap().trim().to_ascii_lowercase());
+    let format = match content_type.as_ref().map(|s| s.as_str()) {
+        None | Some("application/x-ndjson") | Some("application/json") => Format::Ndjson,
+        Some("text/csv") => Format::Csv,
+        Some(other) => {
+            return Ok(respond(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                &format!("unsupported content type: {}", other),
+            ))
+        }
+    };
+
+    let active = match ActiveRequest::acquire() {
+        Some(active) => active,
+        None => {
+            let mut res = respond(
+                StatusCode::TOO_MANY_REQUESTS,
+                "too many concurrent ingest requests",
+            );
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from_static("1"));
+            return Ok(res);
+        }
+    };
+
+    let (acks, body) = Body::channel();
+    tokio::spawn(async move {
+        let _active = active;
+        let mut ingester = Ingester {
+            cmd_tx,
+            conn_id: client::next_conn_id(),
+            session: Some(Session::default()),
+            table,
+            acks,
+            batch: vec![],
+            batches: 0,
+            rows: 0,
+        };
+        if let Err(err) = ingester.run(req.into_body(), format).await {
+            log::warn!("error handling ingest request: {}", err);
+        }
+    });
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(body)
+        .unwrap())
+}
+
+struct Ingester {
+    cmd_tx: UnboundedSender<coord::Command>,
+    /// The connection ID under which the request's batches are executed,
+    /// which is distinct from that of every other connection.
+    conn_id: u32,
+    session: Option<Session>,
+    table: String,
+    acks: hyper::body::Sender,
+    /// The SQL literals of the rows in the current batch.
+    batch: Vec<String>,
+    batches: usize,
+    rows: usize,
+}
+
+impl Ingester {
+    /// Ingests every row in `body`. An error is only returned if the
+    /// acknowledgement stream breaks; errors in the rows themselves are
+    /// reported to the client.
+    async fn run(&mut self, mut body: Body, format: Format) -> Result<(), failure::Error> {
+        let mut buf = vec![];
+        let mut line_num = 0;
+        let mut eof = false;
+        while !eof {
+            match body.next().await {
+                Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return self.fail(err.into()).await,
+                None => eof = true,
+            }
+            while let Some(line) = next_line(&mut buf, eof) {
+                line_num += 1;
+                match parse_row(format, &line) {
+                    Ok(Some(row)) => self.batch.push(row),
+                    Ok(None) => (),
+                    Err(err) => {
+                        let err = format_err!("line {}: {}", line_num, err);
+                        return self.fail(err).await;
+                    }
+                }
+                if self.batch.len() >= BATCH_ROWS && !self.flush().await? {
+                    return Ok(());
+                }
+            }
+        }
+        if !self.batch.is_empty() && !self.flush().await? {
+            return Ok(());
+        }
+        self.ack(json!({ "done": true, "rows": self.rows })).await
+    }
+
+    /// Inserts the current batch, returning whether the insert succeeded.
+    async fn flush(&mut self) -> Result<bool, failure::Error> {
+        let sql = format!(
+            "INSERT INTO {} VALUES {}",
+            self.table,
+            self.batch.drain(..).collect::<Vec<_>>().join(", ")
+        );
+        match self.execute(sql).await {
+            Ok(ExecuteResponse::Inserted(n)) => {
+                self.ack(json!({ "batch": self.batches, "rows": n }))
+                    .await?;
+                self.batches += 1;
+                self.rows += n;
+                Ok(true)
+            }
+            Ok(res) => {
+                self.fail(format_err!("unexpected response to INSERT: {:?}", res))
+                    .await?;
+                Ok(false)
+            }
+            Err(err) => {
+                self.fail(err).await?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Runs `sql` through the simple query flow in the ingester's session.
+    async fn execute(&mut self, sql: String) -> Result<ExecuteResponse, failure::Error> {
+        let session = self
+            .session
+            .take()
+            .expect("session present between commands");
+        let (tx, rx) = futures::channel::oneshot::channel();
+        self.cmd_tx
+            .send(coord::Command::Parse {
+                name: String::new(),
+                sql,
+                session,
+                tx,
+            })
+            .await?;
+        let coord::Response {
+            result,
+            mut session,
+        } = rx.await?;
+        if let Err(err) = result {
+            self.session = Some(session);
+            return Err(err);
+        }
+        session
+            .set_portal(String::new(), String::new(), vec![], vec![])
+            .expect("unnamed statement to be present after parsing");
+
+        let (tx, rx) = futures::channel::oneshot::channel();
+        self.cmd_tx
+            .send(coord::Command::Execute {
+                portal_name: String::new(),
+                session,
+                conn_id: self.conn_id,
+                tx,
+            })
+            .await?;
+        let coord::Response { result, session } = rx.await?;
+        self.session = Some(session);
+        result
+    }
+
+    async fn fail(&mut self, err: failure::Error) -> Result<(), failure::Error> {
+        self.ack(json!({ "batch": self.batches, "error": err.to_string() }))
+            .await
+    }
+
+    async fn ack(&mut self, ack: serde_json::Value) -> Result<(), failure::Error> {
+        let mut line = ack.to_string();
+        line.push('\n');
+        self.acks
+            .send_data(line.into())
+            .await
+            .map_err(|err| format_err!("sending acknowledgement: {}", err))
+    }
+}
+
+/// Removes the next complete line from `buf`. If `eof` is set, any trailing
+/// data without a newline is returned as the final line.
+fn next_line(buf: &mut Vec<u8>, eof: bool) -> Option<Vec<u8>> {
+    match buf.iter().position(|b| *b == b'\n') {
+        Some(pos) => {
+            let mut line: Vec<u8> = buf.drain(..=pos).collect();
+            line.pop();
+            Some(line)
+        }
+        None if eof && !buf.is_empty() => Some(buf.drain(..).collect()),
+        None => None,
+    }
+}
+
+/// Converts one line of input into the SQL literal for the row it describes,
+/// e.g. `(1, 'a', NULL)`. Blank lines describe no row.
+fn parse_row(format: Format, line: &[u8]) -> Result<Option<String>, failure::Error> {
+    let line = match line.last() {
+        Some(b'\r') => &line[..line.len() - 1],
+        _ => line,
+    };
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+    let values = match format {
+        Format::Ndjson => {
+            let values: Vec<serde_json::Value> = serde_json::from_slice(line)
+                .map_err(|err| format_err!("expected a JSON array of column values: {}", err))?;
+            values
+                .into_iter()
+                .map(|value| match value {
+                    serde_json::Value::Null => "NULL".into(),
+                    serde_json::Value::Bool(true) => "TRUE".into(),
+                    serde_json::Value::Bool(false) => "FALSE".into(),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    serde_json::Value::String(s) => quote_literal(&s),
+                    value => quote_literal(&value.to_string()),
+                })
+                .collect::<Vec<_>>()
+        }
+        Format::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(line);
+            let record = match reader.records().next() {
+                Some(record) => record?,
+                None => return Ok(None),
+            };
+            record
+                .iter()
+                .map(|field| {
+                    if field.is_empty() {
+                        "NULL".into()
+                    } else {
+                        quote_literal(field)
+                    }
+                })
+                .collect()
+        }
+    };
+    if values.is_empty() {
+        bail!("row has no columns");
+    }
+    Ok(Some(format!("({})", values.join(", "))))
+}
+
+/// Quotes each dot-separated component of `name` as a SQL identifier.
+fn quote_table_name(name: &str) -> Result<String, failure::Error> {
+    let parts = name.split('.').collect::<Vec<_>>();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+        bail!("invalid table name: {:?}", name);
+    }
+    Ok(parts
+        .iter()
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn strip_prefix<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    if s.starts_with(prefix) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Compares two byte strings in time that depends only on their lengths, so
+/// that token comparisons do not leak how many leading bytes matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn respond(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(format!("{}\n", message)))
+        .unwrap()
+}
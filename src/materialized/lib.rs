@@ -29,7 +29,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use compile_time_run::run_command_str;
-use failure::format_err;
+use failure::{bail, format_err};
 use futures::channel::mpsc::{self, UnboundedSender};
 use futures::future::TryFutureExt;
 use futures::stream::StreamExt;
@@ -46,7 +46,12 @@ use ore::netio::{SniffedStream, SniffingStream};
 use ore::thread::{JoinHandleExt, JoinOnDropHandle};
 use ore::tokio::net::TcpStreamExt;
 
+mod changes;
+mod client;
 mod http;
+mod ingest;
+
+pub use client::Client;
 
 /// The version of the crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -76,14 +81,46 @@ pub struct Config {
     pub timestamp_frequency: Option<Duration>,
     /// The maximum size of a timestamp batch.
     pub max_increment_ts_size: i64,
+    /// The interval at which to remove history that is no longer needed
+    /// from the catalog, or `None` to remove it only on request.
+    pub catalog_compaction_interval: Option<Duration>,
+    /// How long a query may wait for the data it reads to become available
+    /// before it fails, or `None` to wait indefinitely.
+    pub peek_timeout: Option<Duration>,
+    /// The number of bytes that the arrangements of all dataflows may hold
+    /// before new temporary dataflows are refused, or `None` for no limit.
+    pub arrangement_memory_limit: Option<usize>,
+    /// The interval at which to compare each materialized view with a
+    /// reference evaluation of its definition, or `None` to never do so.
+    pub consistency_check_interval: Option<Duration>,
+    /// The granularity of the wall-clock timestamps assigned to the rows
+    /// written to tables, or `None` to assign each write the next integer
+    /// timestamp.
+    pub table_timestamp_granularity: Option<Duration>,
     /// The number of Timely worker threads that this process should host.
     pub threads: usize,
+    /// The name and number of worker threads of each compute cluster to run
+    /// in addition to the default cluster. Each cluster is a separate set of
+    /// Timely workers, so that the dataflows assigned to one cluster cannot
+    /// slow down those of another. Only supported with a single process.
+    pub clusters: Vec<(String, usize)>,
+    /// The CPU cores to which this process's Timely worker threads are pinned,
+    /// one core per thread: first the default cluster's, then each additional
+    /// cluster's in order. If empty, worker threads are not pinned.
+    pub worker_cores: Vec<usize>,
+    /// The CPU cores on which the threads that serve network connections and
+    /// deliver results run. If empty, these threads are not pinned.
+    pub reserved_cores: Vec<usize>,
     /// The ID of this process in the cluster. IDs must be contiguously
     /// allocated, starting at zero.
     pub process: usize,
     /// The addresses of each process in the cluster, including this node,
     /// in order of process ID.
     pub addresses: Vec<SocketAddr>,
+    /// The address at which this process's dataflow workers deliver peek
+    /// results to the coordinator, or `None` to use the address of the
+    /// coordinator's process in `addresses`.
+    pub peek_results_addr: Option<SocketAddr>,
     /// The directory in which `materialized` should store its own metadata.
     pub data_directory: Option<PathBuf>,
     /// An optional symbiosis endpoint. See the
@@ -92,6 +129,12 @@ pub struct Config {
     /// Whether to collect metrics. If enabled, metrics can be collected by
     /// e.g. Prometheus via the `/metrics` HTTP endpoint.
     pub gather_metrics: bool,
+    /// The bearer token that clients must present to append rows via the
+    /// `/ingest` HTTP endpoint. If unset, the endpoint is disabled.
+    pub ingest_token: Option<String>,
+    /// The file to which to append every SQL statement that is executed, for
+    /// replay with the `replay` tool. If unset, no statements are recorded.
+    pub command_log: Option<PathBuf>,
 }
 
 impl Config {
@@ -107,6 +150,7 @@ async fn handle_connection(
     switchboard: Switchboard<SniffedStream<TcpStream>>,
     cmd_tx: UnboundedSender<coord::Command>,
     gather_metrics: bool,
+    ingest_token: Option<Arc<str>>,
     start_time: Instant,
 ) {
     // Sniff out what protocol we've received. Choosing how many bytes to sniff
@@ -129,7 +173,14 @@ async fn handle_connection(
     let res = if pgwire::match_handshake(buf) {
         pgwire::serve(ss.into_sniffed(), cmd_tx, gather_metrics).await
     } else if http::match_handshake(buf) {
-        http::handle_connection(ss.into_sniffed(), cmd_tx, gather_metrics, start_time).await
+        http::handle_connection(
+            ss.into_sniffed(),
+            cmd_tx,
+            gather_metrics,
+            ingest_token,
+            start_time,
+        )
+        .await
     } else if comm::protocol::match_handshake(buf) {
         switchboard
             .handle_connection(ss.into_sniffed())
@@ -152,6 +203,33 @@ async fn handle_connection(
 pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
     let start_time = Instant::now();
 
+    if !config.clusters.is_empty() && config.addresses.len() > 1 {
+        bail!("compute clusters are not supported with multiple processes");
+    }
+    for (i, (name, threads)) in config.clusters.iter().enumerate() {
+        if *threads == 0 {
+            bail!("cluster '{}' must have at least one thread", name);
+        }
+        if config.clusters[..i].iter().any(|(other, _)| other == name) {
+            bail!("cluster '{}' specified more than once", name);
+        }
+    }
+    let local_threads = config.threads + config.clusters.iter().map(|(_, t)| t).sum::<usize>();
+    if !config.worker_cores.is_empty() && config.worker_cores.len() != local_threads {
+        bail!(
+            "{} worker cores specified, but this process runs {} worker threads",
+            config.worker_cores.len(),
+            local_threads
+        );
+    }
+    if let Some(core) = config
+        .worker_cores
+        .iter()
+        .find(|core| config.reserved_cores.contains(core))
+    {
+        bail!("core {} is both a worker core and a reserved core", core);
+    }
+
     // Construct shared channels for SQL command and result exchange, and
     // dataflow command and result exchange.
     let (cmd_tx, cmd_rx) = mpsc::unbounded::<coord::Command>();
@@ -162,7 +240,12 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
     let num_timely_workers = config.num_timely_workers();
 
     // Start Tokio runtime.
-    let mut runtime = tokio::runtime::Runtime::new()?;
+    let reserved_cores = config.reserved_cores.clone();
+    let mut runtime = tokio::runtime::Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .on_thread_start(move || pin_reserved_thread(&reserved_cores))
+        .build()?;
     let executor = runtime.handle().clone();
 
     // Initialize network listener.
@@ -185,6 +268,7 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
 
     let switchboard = Switchboard::new(config.addresses, config.process, executor.clone());
     let gather_metrics = config.gather_metrics;
+    let ingest_token: Option<Arc<str>> = config.ingest_token.map(Arc::from);
     runtime.spawn({
         let switchboard = switchboard.clone();
         let cmd_tx = Arc::downgrade(&cmd_tx);
@@ -217,6 +301,7 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
                             switchboard.clone(),
                             (*cmd_tx).clone(),
                             gather_metrics,
+                            ingest_token.clone(),
                             start_time,
                         ));
                         continue;
@@ -250,6 +335,7 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
         let mut coord = coord::Coordinator::new(coord::Config {
             switchboard: switchboard.clone(),
             num_timely_workers,
+            clusters: &config.clusters,
             symbiosis_url: config.symbiosis_url.as_deref(),
             logging: logging_config.as_ref(),
             data_directory: config.data_directory.as_deref(),
@@ -260,13 +346,50 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
                 }),
                 None => None,
             },
+            catalog_compaction_interval: config.catalog_compaction_interval,
+            peek_timeout: config.peek_timeout,
+            arrangement_memory_limit: config.arrangement_memory_limit,
+            consistency_check_interval: config.consistency_check_interval,
+            local_input_timestamps: match config.table_timestamp_granularity {
+                Some(granularity) => coord::LocalInputTimestamps::WallClock { granularity },
+                None => coord::LocalInputTimestamps::Counter,
+            },
+            command_log: config.command_log.as_deref(),
             executor: &executor,
         })?;
-        Some(thread::spawn(move || coord.serve(cmd_rx)).join_on_drop())
+        let reserved_cores = config.reserved_cores.clone();
+        Some(
+            thread::spawn(move || {
+                pin_reserved_thread(&reserved_cores);
+                coord.serve(cmd_rx)
+            })
+            .join_on_drop(),
+        )
     } else {
         None
     };
 
+    // Construct a timely dataflow instance for each additional compute
+    // cluster. Logging describes only the default cluster.
+    let mut cluster_guards: Vec<Box<dyn Any>> = vec![];
+    let mut cluster_cores = config.worker_cores.iter().skip(config.threads);
+    for (name, threads) in config.clusters {
+        let guard = dataflow::serve(
+            vec![None],
+            threads,
+            0,
+            switchboard.clone(),
+            executor.clone(),
+            config.timestamp_frequency.is_some(),
+            None,
+            Some(name),
+            cluster_cores.by_ref().take(threads).cloned().collect(),
+            config.peek_results_addr.map(comm::protocol::Addr::from),
+        )
+        .map_err(|s| format_err!("{}", s))?;
+        cluster_guards.push(Box::new(guard));
+    }
+
     // Construct timely dataflow instance.
     let dataflow_guard = dataflow::serve(
         dataflow_conns,
@@ -276,6 +399,14 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
         executor,
         config.timestamp_frequency.is_some(),
         logging_config,
+        None,
+        config
+            .worker_cores
+            .iter()
+            .take(config.threads)
+            .cloned()
+            .collect(),
+        config.peek_results_addr.map(comm::protocol::Addr::from),
     )
     .map_err(|s| format_err!("{}", s))?;
 
@@ -283,6 +414,7 @@ pub fn serve(mut config: Config) -> Result<Server, failure::Error> {
         local_addr,
         _cmd_tx: cmd_tx,
         _dataflow_guard: Box::new(dataflow_guard),
+        _cluster_guards: cluster_guards,
         _coord_thread: coord_thread,
         _runtime: runtime,
     })
@@ -294,6 +426,7 @@ pub struct Server {
     // Drop order matters for these fields.
     _cmd_tx: Arc<mpsc::UnboundedSender<coord::Command>>,
     _dataflow_guard: Box<dyn Any>,
+    _cluster_guards: Vec<Box<dyn Any>>,
     _coord_thread: Option<JoinOnDropHandle<()>>,
     _runtime: Runtime,
 }
@@ -302,4 +435,21 @@ impl Server {
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
+
+    /// Returns a new in-process client for the server.
+    ///
+    /// Only the primary process of a cluster can serve clients. On any other
+    /// process, every request that the client makes fails.
+    pub fn client(&self) -> Client {
+        Client::new(&self._cmd_tx)
+    }
+}
+
+/// Pins the calling thread to the reserved cores, if any are configured.
+fn pin_reserved_thread(reserved_cores: &[usize]) {
+    if !reserved_cores.is_empty() {
+        if let Err(err) = ore::thread::pin_current_thread(reserved_cores) {
+            log::warn!("unable to pin thread to reserved cores: {}", err);
+        }
+    }
 }
@@ -17,6 +17,7 @@ use std::time::Duration;
 
 use futures::stream::{self, StreamExt, TryStreamExt};
 use postgres::error::SqlState;
+use postgres::types::Type;
 use tokio::runtime::Runtime;
 
 pub mod util;
@@ -61,6 +62,49 @@ fn test_bind_params() -> Result<(), Box<dyn Error>> {
         .collect();
     assert_eq!(rows, &[-41]);
 
+    // Parameters in VALUES take their type from the other rows.
+    let stmt = client.prepare("SELECT * FROM (VALUES ($1), (2)) ORDER BY 1")?;
+    assert_eq!(stmt.params(), &[Type::INT4]);
+    let rows: Vec<i32> = client
+        .query(&stmt, &[&1_i32])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, &[1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_null_types() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    // NULLs take their type from the other rows of VALUES, or the other side
+    // of a set operation.
+    let stmt = client.prepare("SELECT * FROM (VALUES (NULL), (1))")?;
+    assert_eq!(stmt.columns()[0].type_(), &Type::INT4);
+    let stmt = client.prepare("SELECT NULL UNION ALL SELECT 1")?;
+    assert_eq!(stmt.columns()[0].type_(), &Type::INT4);
+    let rows: Vec<Option<i32>> = client
+        .query("SELECT 1 UNION ALL SELECT NULL ORDER BY 1", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, &[Some(1), None]);
+
+    // Columns that are NULL everywhere are reported as text.
+    let stmt = client.prepare("SELECT NULL, NULL::int")?;
+    assert_eq!(stmt.columns()[0].type_(), &Type::TEXT);
+    assert_eq!(stmt.columns()[1].type_(), &Type::INT4);
+    let rows: Vec<Option<String>> = client
+        .query("SELECT NULL", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, &[None]);
+
     Ok(())
 }
 
@@ -93,6 +137,135 @@ fn test_partial_read() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_partial_read_streamed() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    // The workers send more rows than this in separate batches, so portals
+    // over the table are suspended partway through a batch.
+    let values: Vec<_> = (1..=25_000).map(|i| format!("({})", i)).collect();
+    client.batch_execute(&format!(
+        "CREATE TABLE t (a int); INSERT INTO t VALUES {}",
+        values.join(", ")
+    ))?;
+
+    let max_rows = 7_000;
+    let mut trans = client.transaction()?;
+    let portal = trans.bind("SELECT a FROM t", &[])?;
+    let mut seen = vec![];
+    loop {
+        let rows = trans.query_portal(&portal, max_rows)?;
+        assert!(rows.len() <= max_rows as usize);
+        seen.extend(rows.iter().map(|row| row.get::<_, i32>(0)));
+        if rows.len() < max_rows as usize {
+            break;
+        }
+    }
+    seen.sort();
+    assert_eq!(seen, (1..=25_000).collect::<Vec<_>>());
+
+    // Abandoning a portal partway through leaves the connection usable.
+    let portal = trans.bind("SELECT a FROM t", &[])?;
+    assert_eq!(trans.query_portal(&portal, 10)?.len(), 10);
+    drop(portal);
+    trans.commit()?;
+    let count: i64 = client.query_one("SELECT count(*) FROM t", &[])?.get(0);
+    assert_eq!(count, 25_000);
+
+    Ok(())
+}
+
+#[test]
+fn test_read_batched_results() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    // The workers send more rows than this in separate batches.
+    let values: Vec<_> = (1..=25_000).map(|i| format!("({})", i)).collect();
+    client.batch_execute(&format!(
+        "CREATE TABLE t (a int); INSERT INTO t VALUES {}",
+        values.join(", ")
+    ))?;
+
+    // Unordered rows are forwarded to the client batch by batch.
+    let mut streamed: Vec<i32> = client
+        .query("SELECT a FROM t", &[])?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    streamed.sort();
+    assert_eq!(streamed, (1..=25_000).collect::<Vec<_>>());
+
+    // Ordered rows are collected from every batch before any are sent.
+    let ordered: Vec<i32> = client
+        .query("SELECT a FROM t ORDER BY a DESC", &[])?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(ordered, (1..=25_000).rev().collect::<Vec<_>>());
+
+    // An error that occurs after some batches have been sent replaces the
+    // results.
+    match client.query("SELECT a FROM t WHERE 1 / (a - 20000) > 0", &[]) {
+        Ok(_) => panic!("division by zero executed successfully"),
+        Err(err) => {
+            assert_eq!(err.code(), Some(&SqlState::DATA_EXCEPTION));
+            assert!(err.to_string().contains("division by zero"), "{}", err);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_streamed_results_order() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    // Each worker holds some of the rows, so the results of every worker
+    // must be merged.
+    let (_server, mut client) = util::start_server(util::Config::default().threads(4))?;
+    let values: Vec<_> = (0..25_000)
+        .map(|i| format!("({}, 'row {}')", (i * 7919) % 25_000, i % 100))
+        .collect();
+    client.batch_execute(&format!(
+        "CREATE TABLE t (a int, b text); INSERT INTO t VALUES {}",
+        values.join(", ")
+    ))?;
+    let read = |rows: &[postgres::Row]| {
+        rows.iter()
+            .map(|row| (row.get::<_, i32>(0), row.get::<_, String>(1)))
+            .collect::<Vec<_>>()
+    };
+
+    // A limit that cannot take effect makes the coordinator finish the entire
+    // result at once, rather than stream it, which orders the rows
+    // differently but must produce the same rows.
+    let mut finished = read(&client.query("SELECT a, b FROM t LIMIT 100000", &[])?);
+    assert_eq!(finished.len(), 25_000);
+    let streamed = read(&client.query("SELECT a, b FROM t", &[])?);
+    let mut sorted = streamed.clone();
+    sorted.sort();
+    finished.sort();
+    assert_eq!(sorted, finished);
+
+    for max_rows in &[1_000, 7_000] {
+        let mut trans = client.transaction()?;
+        let portal = trans.bind("SELECT a, b FROM t", &[])?;
+        let mut fetched = vec![];
+        loop {
+            let rows = trans.query_portal(&portal, *max_rows)?;
+            fetched.extend(read(&rows));
+            if rows.len() < *max_rows as usize {
+                break;
+            }
+        }
+        assert_eq!(fetched, streamed, "fetching {} rows at a time", max_rows);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_read_many_rows() -> Result<(), Box<dyn Error>> {
     ore::log::init();
@@ -110,6 +283,46 @@ fn test_read_many_rows() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_error_codes() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+
+    // Errors are reported with the SQLSTATE class that corresponds to their
+    // cause, whether they occur while parsing, planning, or sequencing.
+    for query in &[
+        "SELEC 1",
+        "SELECT * FROM nonexistent",
+        "CREATE VIEW v AS SELECT 1",
+        "TAIL v",
+    ] {
+        match client.batch_execute(query) {
+            Ok(_) => panic!("invalid query {} executed successfully", query),
+            Err(err) => assert_eq!(
+                err.code(),
+                Some(&SqlState::SYNTAX_ERROR_OR_ACCESS_RULE_VIOLATION),
+                "{}",
+                query
+            ),
+        }
+    }
+
+    // Errors that occur while the workers evaluate a query are data
+    // exceptions.
+    client.batch_execute("CREATE TABLE t (a int, b int); INSERT INTO t VALUES (1, 0)")?;
+    match client.query("SELECT * FROM t WHERE a / b = 1", &[]) {
+        Ok(_) => panic!("division by zero executed successfully"),
+        Err(err) => {
+            assert_eq!(err.code(), Some(&SqlState::DATA_EXCEPTION));
+            assert!(err.to_string().contains("division by zero"), "{}", err);
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_conn_params() -> Result<(), Box<dyn Error>> {
     ore::log::init();
@@ -199,6 +412,137 @@ fn test_conn_params() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_listen() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, _client) = util::start_server(util::Config::default())?;
+
+    Runtime::new()?.block_on(async {
+        let (client, mut conn) = server.pg_config_async().connect(postgres::NoTls).await?;
+        let (message_tx, mut message_rx) = futures::channel::mpsc::unbounded();
+        tokio::spawn(
+            stream::poll_fn(move |cx| conn.poll_message(cx))
+                .map_err(|e| panic!(e))
+                .forward(message_tx),
+        );
+
+        client.batch_execute("LISTEN mz_catalog").await?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1").await?;
+
+        match message_rx.next().await {
+            Some(tokio_postgres::AsyncMessage::Notification(n)) => {
+                assert_eq!(n.channel(), "mz_catalog");
+                assert_eq!(n.payload(), "create view materialize.public.v");
+            }
+            _ => panic!("catalog notification not delivered"),
+        }
+
+        // Once the session stops listening, no further notifications should
+        // be delivered.
+        client.batch_execute("UNLISTEN *").await?;
+        client.batch_execute("DROP VIEW v").await?;
+        client.batch_execute("LISTEN mz_catalog").await?;
+        client.batch_execute("CREATE VIEW w AS SELECT 1").await?;
+
+        match message_rx.next().await {
+            Some(tokio_postgres::AsyncMessage::Notification(n)) => {
+                assert_eq!(n.payload(), "create view materialize.public.w");
+            }
+            _ => panic!("catalog notification not delivered"),
+        }
+
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_backfill_progress() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, _client) = util::start_server(util::Config::default())?;
+
+    Runtime::new()?.block_on(async {
+        let (client, mut conn) = server.pg_config_async().connect(postgres::NoTls).await?;
+        let (message_tx, mut message_rx) = futures::channel::mpsc::unbounded();
+        tokio::spawn(
+            stream::poll_fn(move |cx| conn.poll_message(cx))
+                .map_err(|e| panic!(e))
+                .forward(message_tx),
+        );
+
+        client
+            .batch_execute("CREATE TABLE t (a int); INSERT INTO t VALUES (1), (2), (3)")
+            .await?;
+        client.batch_execute("LISTEN mz_backfills").await?;
+        client
+            .batch_execute("CREATE MATERIALIZED VIEW v AS SELECT sum(a) FROM t")
+            .await?;
+
+        match message_rx.next().await {
+            Some(tokio_postgres::AsyncMessage::Notification(n)) => {
+                assert_eq!(n.channel(), "mz_backfills");
+                assert_eq!(n.payload(), "complete materialize.public.v_primary_idx");
+            }
+            _ => panic!("backfill notification not delivered"),
+        }
+
+        // An index that has caught up no longer reports its progress.
+        let rows = client
+            .query(
+                "SELECT * FROM mz_catalog.mz_view_backfills b \
+                 JOIN mz_catalog.mz_catalog_names n ON b.global_id = n.global_id \
+                 WHERE n.name = 'materialize.public.v_primary_idx'",
+                &[],
+            )
+            .await?;
+        assert!(rows.is_empty());
+
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_peek_metadata() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, _client) = util::start_server(util::Config::default())?;
+
+    Runtime::new()?.block_on(async {
+        let (client, mut conn) = server.pg_config_async().connect(postgres::NoTls).await?;
+        let (message_tx, mut message_rx) = futures::channel::mpsc::unbounded();
+        tokio::spawn(
+            stream::poll_fn(move |cx| conn.poll_message(cx))
+                .map_err(|e| panic!(e))
+                .forward(message_tx),
+        );
+
+        client
+            .batch_execute(
+                "CREATE MATERIALIZED VIEW v AS SELECT * FROM (VALUES (1), (2), (3)) AS t (a)",
+            )
+            .await?;
+        client.batch_execute("SET peek_metadata = true").await?;
+        client.batch_execute("SELECT * FROM v WHERE a > 1").await?;
+
+        match message_rx.next().await {
+            Some(tokio_postgres::AsyncMessage::Notice(n)) => {
+                assert!(n.message().starts_with("query answered at timestamp "));
+                assert!(n.detail().unwrap().starts_with("2 rows, "));
+            }
+            _ => panic!("peek metadata not reported"),
+        }
+
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
 #[test]
 fn test_persistence() -> Result<(), Box<dyn Error>> {
     ore::log::init();
@@ -259,6 +603,14 @@ fn test_persistence() -> Result<(), Box<dyn Error>> {
                 .collect::<Vec<String>>(),
             &["v"]
         );
+        // Recovery should have reinstalled exactly the dataflows that the
+        // catalog calls for.
+        assert_eq!(
+            client
+                .query("SELECT * FROM mz_catalog.mz_catalog_discrepancies", &[])?
+                .len(),
+            0
+        );
     }
 
     {
@@ -275,3 +627,96 @@ fn test_persistence() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_catalog_migration() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path().to_owned());
+    let catalog_path = data_dir.path().join("catalog");
+
+    {
+        let (_server, mut client) = util::start_server(config.clone())?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+    }
+
+    // Roll the catalog back to the format that predates partitioned timestamp
+    // bindings, as written by an older version of materialized, with one
+    // binding in the old format.
+    {
+        let conn = rusqlite::Connection::open(&catalog_path)?;
+        let version: i32 =
+            conn.query_row("PRAGMA user_version", rusqlite::NO_PARAMS, |row| row.get(0))?;
+        assert!(version > 2);
+        conn.execute_batch(
+            "DROP TABLE timestamps;
+             CREATE TABLE timestamps (
+                 sid blob NOT NULL,
+                 vid blob NOT NULL,
+                 timestamp integer NOT NULL,
+                 offset blob NOT NULL,
+                 checksum integer NOT NULL DEFAULT 0,
+                 PRIMARY KEY (sid, vid, timestamp)
+             );
+             DROP TABLE quarantined_timestamps;
+             CREATE TABLE quarantined_timestamps (
+                 sid blob,
+                 vid blob,
+                 timestamp integer,
+                 offset blob,
+                 checksum integer,
+                 reason text NOT NULL
+             );
+             PRAGMA user_version = 2",
+        )?;
+        let gid: Vec<u8> = conn.query_row(
+            "SELECT gid FROM items WHERE name = 'v'",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )?;
+        let checksum = catalog::sql::timestamp_checksum(&gid, &gid, b"1", b"0");
+        conn.execute(
+            "INSERT INTO timestamps VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![gid, gid, "1".as_bytes(), "0".as_bytes(), checksum],
+        )?;
+    }
+
+    // The upgraded catalog keeps the binding, whose checksum is still valid.
+    {
+        let (_server, mut client) = util::start_server(config.clone())?;
+        client.batch_execute("COMMENT ON VIEW v IS 'upgraded'")?;
+        assert_eq!(
+            client
+                .query("SHOW COMMENTS", &[])?
+                .into_iter()
+                .map(|row| row.get(1))
+                .collect::<Vec<String>>(),
+            &["upgraded"]
+        );
+        assert!(client.query("CHECK CATALOG", &[])?.is_empty());
+        let conn = rusqlite::Connection::open(&catalog_path)?;
+        let bindings: i64 = conn.query_row(
+            "SELECT count(*) FROM timestamps WHERE partition_id = 0",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )?;
+        assert_eq!(bindings, 1);
+    }
+
+    // A catalog written by a newer version of materialized must be refused.
+    {
+        let conn = rusqlite::Connection::open(&catalog_path)?;
+        conn.execute_batch("PRAGMA user_version = 1000")?;
+    }
+    match util::start_server(config) {
+        Ok(_) => panic!("server unexpectedly booted with a newer catalog"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "catalog has format version 1000, but this version of materialized only \
+             supports versions up to 3; downgrading is not supported"
+        ),
+    }
+
+    Ok(())
+}
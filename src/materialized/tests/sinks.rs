@@ -0,0 +1,389 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Integration tests for sinks to external systems.
+//!
+//! Each test runs a sink against a minimal fake of the external system that
+//! withholds its responses until the test releases it, so that the tests can
+//! observe the server while the external system is unresponsive.
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub mod util;
+
+/// A fake external system that records the requests it receives.
+#[derive(Clone)]
+struct Fake {
+    port: u16,
+    requests: Arc<Mutex<Vec<String>>>,
+    released: Arc<AtomicBool>,
+    /// The number of connections that have been closed.
+    closed: Arc<AtomicUsize>,
+}
+
+impl Fake {
+    /// Starts a fake that serves each connection with `serve` on its own
+    /// thread.
+    fn start<F>(serve: F) -> Result<Fake, Box<dyn Error>>
+    where
+        F: Fn(Fake, TcpStream) -> Result<(), Box<dyn Error>> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let fake = Fake {
+            port: listener.local_addr()?.port(),
+            requests: Arc::new(Mutex::new(vec![])),
+            released: Arc::new(AtomicBool::new(false)),
+            closed: Arc::new(AtomicUsize::new(0)),
+        };
+        let serve = Arc::new(serve);
+        let f = fake.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (f, serve) = (f.clone(), serve.clone());
+                thread::spawn(move || {
+                    // Connections end with an error when the server hangs up.
+                    let _ = serve(f.clone(), stream.unwrap());
+                    f.closed.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+        Ok(fake)
+    }
+
+    /// Records `request`, then blocks until the test releases the fake.
+    fn record(&self, request: String) {
+        self.requests.lock().unwrap().push(request);
+        while !self.released.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn release(&self) {
+        self.released.store(true, Ordering::SeqCst);
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Waits until the fake has received a request that contains `needle`.
+    fn wait_for(&self, needle: &str) -> Result<(), Box<dyn Error>> {
+        util::poll(|| {
+            Ok(if self.requests().iter().any(|r| r.contains(needle)) {
+                Some(())
+            } else {
+                None
+            })
+        })
+    }
+}
+
+/// Serves the subset of the Redis protocol that a Redis sink uses:
+/// transactions of `DEL`, `SET`, and `HSET` commands. Each transaction is
+/// recorded as one request when its `EXEC` arrives.
+fn serve_redis(fake: Fake, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut queued: Option<Vec<String>> = None;
+    loop {
+        let command = read_resp_array(&mut reader)?;
+        match command[0].as_str() {
+            "MULTI" => {
+                queued = Some(vec![]);
+                writer.write_all(b"+OK\r\n")?;
+            }
+            "EXEC" => {
+                let commands = queued.take().unwrap_or_default();
+                fake.record(commands.join("\n"));
+                write!(writer, "*{}\r\n", commands.len())?;
+                for _ in &commands {
+                    writer.write_all(b"+OK\r\n")?;
+                }
+            }
+            _ => match &mut queued {
+                Some(queued) => {
+                    queued.push(command.join(" "));
+                    writer.write_all(b"+QUEUED\r\n")?;
+                }
+                None => writer.write_all(b"+OK\r\n")?,
+            },
+        }
+    }
+}
+
+/// Reads one command, an array of bulk strings, in the Redis protocol.
+fn read_resp_array(reader: &mut impl BufRead) -> Result<Vec<String>, Box<dyn Error>> {
+    let read_header = |reader: &mut dyn BufRead, prefix: &str| -> Result<usize, Box<dyn Error>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err("connection closed".into());
+        }
+        if !line.starts_with(prefix) {
+            return Err(format!("unexpected line: {:?}", line).into());
+        }
+        Ok(line[1..].trim_end().parse()?)
+    };
+    let n = read_header(reader, "*")?;
+    let mut out = vec![];
+    for _ in 0..n {
+        let len = read_header(reader, "$")?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf)?;
+        buf.truncate(len);
+        out.push(String::from_utf8(buf)?);
+    }
+    Ok(out)
+}
+
+/// Serves Elasticsearch's bulk API over HTTP/1.1, recording the body of each
+/// request and reporting that every action succeeded.
+fn serve_elasticsearch(fake: Fake, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    loop {
+        let mut content_length = 0;
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim_end().to_ascii_lowercase();
+            if header.is_empty() {
+                break;
+            }
+            if header.starts_with("content-length:") {
+                content_length = header["content-length:".len()..].trim().parse()?;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        fake.record(format!("{}{}", request_line, String::from_utf8(body)?));
+        let response = r#"{"took":1,"errors":false,"items":[]}"#;
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        )?;
+    }
+}
+
+/// Serves the core NATS protocol, and acknowledges each published message
+/// with a reply like JetStream's, recording the message.
+fn serve_nats(fake: Fake, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    write!(
+        writer,
+        "INFO {{\"server_id\":\"fake\",\"version\":\"2.1.4\",\"go\":\"go1.13\",\
+         \"host\":\"127.0.0.1\",\"port\":{},\"auth_required\":false,\
+         \"tls_required\":false,\"max_payload\":1048576,\"proto\":1,\
+         \"client_id\":1}}\r\n",
+        fake.port
+    )?;
+    let mut subscriptions: Vec<(String, String)> = vec![];
+    let mut seq = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.get(0).map(|w| w.to_ascii_uppercase()).as_deref() {
+            Some("PING") => writer.write_all(b"PONG\r\n")?,
+            Some("SUB") => {
+                let (subject, sid) = (words[1], words[words.len() - 1]);
+                subscriptions.push((subject.into(), sid.into()));
+            }
+            Some("PUB") => {
+                let len: usize = words[words.len() - 1].parse()?;
+                let mut payload = vec![0; len + 2];
+                reader.read_exact(&mut payload)?;
+                payload.truncate(len);
+                fake.record(String::from_utf8(payload)?);
+                if words.len() == 4 {
+                    let reply = words[2];
+                    let sid = subscriptions.iter().find(|(subject, _)| {
+                        subject == reply
+                            || (subject.ends_with(".*")
+                                && reply.starts_with(&subject[..subject.len() - 1]))
+                    });
+                    if let Some((_, sid)) = sid {
+                        seq += 1;
+                        let ack = format!(r#"{{"stream":"OUT","seq":{}}}"#, seq);
+                        write!(writer, "MSG {} {} {}\r\n{}\r\n", reply, sid, ack.len(), ack)?;
+                    }
+                }
+            }
+            // CONNECT, UNSUB, and PONG need no response.
+            _ => (),
+        }
+    }
+}
+
+/// Creates a sink into `target` and checks that, while the external system
+/// withholds its response, the sink's worker continues to serve other
+/// dataflows, and that the sink delivers every row once the external system
+/// responds.
+fn test_sink(fake: &Fake, target: &str, needles: &[&str]) -> Result<(), Box<dyn Error>> {
+    // Peeks fail, rather than hang, if the worker is blocked.
+    let config = util::Config::default().peek_timeout(Duration::from_secs(5));
+    let (_server, mut client) = util::start_server(config)?;
+    client.batch_execute(&format!(
+        "CREATE TABLE t (id int, name text);
+         CREATE MATERIALIZED VIEW v AS SELECT * FROM t;
+         CREATE SINK s FROM v INTO {} FORMAT JSON;
+         INSERT INTO t VALUES (1, 'one')",
+        target
+    ))?;
+    fake.wait_for(needles[0])?;
+
+    // The external system is now stalled on the first write. Other dataflows
+    // on the same worker keep up with their inputs regardless.
+    client.batch_execute("INSERT INTO t VALUES (2, 'two')")?;
+    let count: i64 = client.query_one("SELECT count(*) FROM v", &[])?.get(0);
+    assert_eq!(count, 2);
+    assert!(!fake.requests().iter().any(|r| r.contains(needles[1])));
+
+    // Once the external system responds, the sink catches up.
+    fake.release();
+    fake.wait_for(needles[1])?;
+    Ok(())
+}
+
+#[test]
+fn test_redis_sink() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let fake = Fake::start(serve_redis)?;
+    test_sink(
+        &fake,
+        &format!("REDIS 'redis://127.0.0.1:{}' WITH (key = 'id')", fake.port),
+        &[r#"SET 1 {"name":"one"}"#, r#"SET 2 {"name":"two"}"#],
+    )?;
+
+    // Each transaction deletes the key before setting it.
+    let requests = fake.requests();
+    assert_eq!(requests[0], "DEL 1\nSET 1 {\"name\":\"one\"}");
+    Ok(())
+}
+
+#[test]
+fn test_elasticsearch_sink() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let fake = Fake::start(serve_elasticsearch)?;
+    test_sink(
+        &fake,
+        &format!(
+            "ELASTICSEARCH 'http://127.0.0.1:{}' INDEX 'things' WITH (key = 'id')",
+            fake.port
+        ),
+        &[r#""name":"one""#, r#""name":"two""#],
+    )?;
+
+    let requests = fake.requests();
+    assert!(requests[0].starts_with("POST /_bulk "), "{}", requests[0]);
+    assert!(
+        requests[0].contains(r#"{"index":{"_id":"1","_index":"things"}}"#),
+        "{}",
+        requests[0]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_nats_sink() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let fake = Fake::start(serve_nats)?;
+    test_sink(
+        &fake,
+        &format!("NATS 'nats://127.0.0.1:{}' SUBJECT 'out'", fake.port),
+        &[r#""name":"one""#, r#""name":"two""#],
+    )?;
+
+    // Each update is published as its own message.
+    let requests = fake.requests();
+    assert_eq!(requests.len(), 2, "{:?}", requests);
+    assert!(requests[0].contains(r#""diff":1"#), "{}", requests[0]);
+    Ok(())
+}
+
+#[test]
+fn test_sink_drains_on_shutdown() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let fake = Fake::start(serve_redis)?;
+    let (server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(&format!(
+        "CREATE TABLE t (id int, name text);
+         CREATE MATERIALIZED VIEW v AS SELECT * FROM t;
+         CREATE SINK s FROM v INTO REDIS 'redis://127.0.0.1:{}' WITH (key = 'id') FORMAT JSON;
+         INSERT INTO t VALUES (1, 'one')",
+        fake.port
+    ))?;
+    fake.wait_for(r#"SET 1 {"name":"one"}"#)?;
+    client.batch_execute("INSERT INTO t VALUES (2, 'two')")?;
+    drop(client);
+
+    // Shut the server down while the sink is stalled with the second row
+    // outstanding. Shutdown waits for the sink to deliver it.
+    let f = fake.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(1));
+        f.release();
+    });
+    drop(server);
+    assert!(
+        fake.requests()
+            .iter()
+            .any(|r| r.contains(r#"SET 2 {"name":"two"}"#)),
+        "{:?}",
+        fake.requests()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sink_disconnects_on_drop() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let fake = Fake::start(serve_redis)?;
+    fake.release();
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(&format!(
+        "CREATE TABLE t (id int, name text);
+         CREATE MATERIALIZED VIEW v AS SELECT * FROM t;
+         CREATE SINK s FROM v INTO REDIS 'redis://127.0.0.1:{}' WITH (key = 'id') FORMAT JSON;
+         INSERT INTO t VALUES (1, 'one')",
+        fake.port
+    ))?;
+    fake.wait_for(r#"SET 1 {"name":"one"}"#)?;
+    assert_eq!(fake.closed.load(Ordering::SeqCst), 0);
+
+    // The sink hangs up as soon as it is dropped, even though no more input
+    // arrives to schedule its operator.
+    client.batch_execute("DROP SINK s")?;
+    util::poll(|| {
+        Ok(if fake.closed.load(Ordering::SeqCst) > 0 {
+            Some(())
+        } else {
+            None
+        })
+    })
+}
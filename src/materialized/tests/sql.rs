@@ -13,14 +13,34 @@
 //! scripts. The tests here are simply too complicated to be easily expressed
 //! in testdrive, e.g., because they depend on the current time.
 
+use std::cmp;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::{self, File};
-use std::io::{BufRead, Write};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
+use failure::{bail, format_err};
+use futures::future::FutureExt;
+use futures::stream::StreamExt;
+use serde_json::json;
+use tokio::runtime::Runtime;
+
+use catalog::sql::timestamp_checksum;
+use coord::{PlanContext, PlanHook};
+use dataflow::{
+    DeliveryGuarantee, SinkPlugin, SinkPluginFactory, SinkRecord, SourcePlugin,
+    SourcePluginFactory, SourceRecord, SourceWaker,
+};
+use dataflow_types::Timestamp;
+use expr::{BinaryFunc, Id, RelationExpr, ScalarExpr};
+use repr::{ColumnType, Datum, Row, ScalarType};
 
 pub mod util;
 
@@ -244,6 +264,45 @@ New York,NY,10004
     assert!(tail_reader.next().is_none());
     drop(tail_reader);
 
+    // A TAIL with a column list and a WHERE clause streams only the matching
+    // rows, and only the requested columns of those rows.
+    let cancel_token = client.cancel_token();
+    let mut tail_reader = client
+        .copy_out("TAIL dynamic_csv (column1, column3) WHERE column2 = 'AZ'")?
+        .split(b'\n');
+
+    append(&mut dynamic_file, b"City 3,ST,00003\n")?;
+    append(&mut dynamic_file, b"Phoenix,AZ,85001\n")?;
+    assert!(tail_reader
+        .next()
+        .unwrap()?
+        .starts_with(&b"Phoenix\t85001\tDiff: 1 at "[..]));
+
+    cancel_token.cancel_query(postgres::NoTls)?;
+
+    assert!(tail_reader.next().is_none());
+    drop(tail_reader);
+
+    // A TAIL that pins the key of an index is served by the index itself.
+    client.batch_execute("CREATE INDEX dynamic_csv_state_idx ON dynamic_csv (column2)")?;
+    let cancel_token = client.cancel_token();
+    let mut tail_reader = client
+        .copy_out("TAIL dynamic_csv (column1) WHERE column2 = 'WA'")?
+        .split(b'\n');
+
+    append(&mut dynamic_file, b"Tucson,AZ,85701\n")?;
+    append(&mut dynamic_file, b"Seattle,WA,98101\n")?;
+    assert!(tail_reader
+        .next()
+        .unwrap()?
+        .starts_with(&b"Seattle\tDiff: 1 at "[..]));
+
+    cancel_token.cancel_query(postgres::NoTls)?;
+
+    assert!(tail_reader.next().is_none());
+    drop(tail_reader);
+    client.batch_execute("DROP INDEX dynamic_csv_state_idx")?;
+
     // Check that writing to the tailed file after the view and source are
     // dropped doesn't cause a crash (#1361).
     client.execute("DROP VIEW dynamic_csv", &[])?;
@@ -254,6 +313,218 @@ New York,NY,10004
     Ok(())
 }
 
+// Tests that compressed files are decompressed, whether the compression is
+// inferred from the file's extension or named explicitly.
+#[test]
+fn test_compressed_file_sources() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let data = b"Rochester,NY,14618\nNew York,NY,10004\n";
+    let gzip = |data: &[u8]| -> Result<_, Box<dyn Error>> {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    };
+    let bzip2 = |data: &[u8]| -> Result<_, Box<dyn Error>> {
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::Default);
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    };
+
+    // A file appended to with gzip holds several gzip members, which are read
+    // in turn.
+    let mut gz = gzip(b"Rochester,NY,14618\n")?;
+    gz.extend(gzip(b"New York,NY,10004\n")?);
+    fs::write(Path::join(temp_dir.path(), "cities.csv.gz"), gz)?;
+    fs::write(Path::join(temp_dir.path(), "cities.csv.bz2"), bzip2(data)?)?;
+    fs::write(Path::join(temp_dir.path(), "cities.1"), gzip(data)?)?;
+
+    for (name, options) in &[
+        ("cities.csv.gz", ""),
+        ("cities.csv.bz2", ""),
+        ("cities.1", "WITH (compression = 'gzip')"),
+    ] {
+        client.batch_execute(&*format!(
+            "CREATE MATERIALIZED SOURCE src FROM FILE '{}' {} FORMAT CSV WITH 3 COLUMNS",
+            Path::join(temp_dir.path(), name).display(),
+            options,
+        ))?;
+        let rows = query_rows(
+            &mut client,
+            "SELECT column1, column3 FROM src ORDER BY mz_line_no",
+            2,
+        )?
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect::<Vec<(String, String)>>();
+        assert_eq!(
+            rows,
+            &[
+                ("Rochester".into(), "14618".into()),
+                ("New York".into(), "10004".into()),
+            ],
+            "reading {}",
+            name,
+        );
+        client.batch_execute("DROP SOURCE src")?;
+    }
+    Ok(())
+}
+
+// Tests that a source with a deduplication key passes along only the first of
+// the records that share a key within the window.
+#[test]
+fn test_deduplicated_file_source() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let path = Path::join(temp_dir.path(), "events.csv");
+    fs::write(
+        &path,
+        "1,created
+2,created
+1,created
+1,updated
+2,created
+",
+    )?;
+
+    client.batch_execute(&*format!(
+        "CREATE MATERIALIZED SOURCE events FROM FILE '{}' \
+         WITH (dedup_key = 'column1, column2', dedup_window = '1h') FORMAT CSV WITH 2 COLUMNS",
+        path.display(),
+    ))?;
+    let rows = query_rows(
+        &mut client,
+        "SELECT column1, column2 FROM events ORDER BY mz_line_no",
+        3,
+    )?
+    .into_iter()
+    .map(|row| (row.get(0), row.get(1)))
+    .collect::<Vec<(String, String)>>();
+    assert_eq!(
+        rows,
+        &[
+            ("1".into(), "created".into()),
+            ("2".into(), "created".into()),
+            ("1".into(), "updated".into()),
+        ]
+    );
+    Ok(())
+}
+
+// Tests that CSV sources with typed columns skip their header, coerce each
+// field to its column's type, and skip the rows that cannot be decoded.
+#[test]
+fn test_typed_csv_sources() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let path = Path::join(temp_dir.path(), "typed.csv");
+    fs::write(
+        &path,
+        "city,zip,population
+Rochester,14618,206284
+New York,not a zip,8336817
+Bad,row
+Glendale,85310,
+",
+    )?;
+
+    client.batch_execute(&*format!(
+        "CREATE SOURCE typed_csv_source FROM FILE '{}' \
+         FORMAT CSV WITH COLUMNS (city text, zip int, population bigint) HEADER",
+        path.display(),
+    ))?;
+    client.batch_execute("CREATE MATERIALIZED VIEW typed_csv AS SELECT * FROM typed_csv_source")?;
+
+    let rows = query_rows(
+        &mut client,
+        "SELECT * FROM typed_csv ORDER BY mz_line_no",
+        2,
+    )?
+    .into_iter()
+    .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+    .collect::<Vec<(String, Option<i32>, Option<i64>, i64)>>();
+    assert_eq!(
+        rows,
+        &[
+            ("Rochester".into(), Some(14618), Some(206284), 2),
+            ("Glendale".into(), Some(85310), None, 5),
+        ]
+    );
+
+    let err = client
+        .batch_execute(&*format!(
+            "CREATE SOURCE bad_csv_source FROM FILE '{}' FORMAT CSV WITH 0 COLUMNS",
+            path.display(),
+        ))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("CSV sources must have at least one column"));
+    Ok(())
+}
+
+#[test]
+fn test_typed_json_sources() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let path = Path::join(temp_dir.path(), "typed.json");
+    fs::write(
+        &path,
+        r#"{"city": "Rochester", "zip": 14618, "tags": ["lilac"]}
+{"city": "New York", "zip": "not a zip"}
+[1, 2, 3]
+{"city": "Glendale", "zip": "85310", "tags": null, "extra": true}
+{"zip": 10001}
+"#,
+    )?;
+
+    client.batch_execute(&*format!(
+        "CREATE SOURCE typed_json_source FROM FILE '{}' \
+         FORMAT JSON WITH COLUMNS (city text, zip int, tags jsonb)",
+        path.display(),
+    ))?;
+    client.batch_execute(
+        "CREATE MATERIALIZED VIEW typed_json AS \
+         SELECT city, zip, tags::text, mz_line_no FROM typed_json_source",
+    )?;
+
+    let rows = query_rows(
+        &mut client,
+        "SELECT * FROM typed_json ORDER BY mz_line_no",
+        3,
+    )?
+    .into_iter()
+    .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+    .collect::<Vec<(Option<String>, Option<i32>, Option<String>, i64)>>();
+    assert_eq!(
+        rows,
+        &[
+            (
+                Some("Rochester".into()),
+                Some(14618),
+                Some(r#"["lilac"]"#.into()),
+                1
+            ),
+            (Some("Glendale".into()), Some(85310), None, 4),
+            (None, Some(10001), None, 5),
+        ]
+    );
+    Ok(())
+}
+
 // Tests that a client that launches a non-terminating TAIL and disconnects
 // does not keep the server alive forever.
 #[test]
@@ -283,3 +554,1533 @@ fn test_tail_shutdown() -> Result<(), Box<dyn Error>> {
     // So if this function exits, things are working correctly.
     Ok(())
 }
+
+// Tests that a client that stops reading the updates of a TAIL does not stall
+// the dataflow worker that sends them.
+#[test]
+fn test_tail_slow_client() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    // Peeks fail, rather than hang, if the worker is blocked.
+    let config = util::Config::default().peek_timeout(Duration::from_secs(5));
+    let (server, mut client) = util::start_server(config)?;
+    client.batch_execute("CREATE TABLE t (a text); CREATE TABLE u (a int)")?;
+    let _tail = server.stalled_query("TAIL t")?;
+
+    // Each insertion reaches the TAIL as a batch of its own. Enough batches
+    // fill the TAIL's buffer and the connections behind it, after which the
+    // client is not ready for more.
+    let text = "x".repeat(32 << 10);
+    for _ in 0..1500 {
+        client.execute("INSERT INTO t VALUES ($1)", &[&text])?;
+    }
+
+    // The worker continues to serve other queries regardless.
+    client.batch_execute("INSERT INTO u VALUES (1)")?;
+    let count: i64 = client.query_one("SELECT count(*) FROM u", &[])?.get(0);
+    assert_eq!(count, 1);
+    let count: i64 = client.query_one("SELECT count(*) FROM t", &[])?.get(0);
+    assert_eq!(count, 1500);
+    Ok(())
+}
+
+// Tests that a view created with `changes_only` does not emit the retraction
+// and reinsertion that a no-op update produces, while a plain view does.
+#[test]
+fn test_changes_only() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(
+        "CREATE TABLE t (a int, b int);
+         INSERT INTO t VALUES (1, 2);
+         CREATE MATERIALIZED VIEW v AS SELECT a, b FROM t;
+         CREATE MATERIALIZED VIEW w WITH (changes_only = true) AS SELECT a, b FROM t",
+    )?;
+
+    let mut tail_client = server.connect()?;
+    let mut tail = |view: &str| -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let cancel_token = tail_client.cancel_token();
+        let mut tail_reader = tail_client
+            .copy_out(&*format!("TAIL {}", view))?
+            .split(b'\n');
+        // The initial snapshot.
+        assert!(tail_reader
+            .next()
+            .unwrap()?
+            .starts_with(&b"1\t2\tDiff: 1 at "[..]));
+        client.batch_execute("UPDATE t SET b = b WHERE a = 1")?;
+        client.batch_execute("INSERT INTO t VALUES (3, 4)")?;
+        let mut lines = vec![];
+        loop {
+            let line = tail_reader.next().unwrap()?;
+            let done = line.starts_with(&b"3\t4\t"[..]);
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+        cancel_token.cancel_query(postgres::NoTls)?;
+        assert!(tail_reader.next().is_none());
+        client.batch_execute("DELETE FROM t WHERE a = 3")?;
+        Ok(lines)
+    };
+
+    // The plain view sees the no-op update as a retraction and a reinsertion
+    // before the inserted row arrives...
+    let lines = tail("v")?;
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert!(lines[..2]
+        .iter()
+        .any(|line| line.starts_with(&b"1\t2\tDiff: -1 at "[..])));
+
+    // ...while the `changes_only` view suppresses both, so the inserted row is
+    // the very next update.
+    let lines = tail("w")?;
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    assert!(lines[0].starts_with(&b"3\t4\tDiff: 1 at "[..]));
+
+    Ok(())
+}
+
+// Tests that a view with `emit_final_after` withholds each row until the wall
+// clock passes the time in the row, and discards rows whose time has passed.
+#[test]
+fn test_emit_final_after() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let config = util::Config::default().table_timestamp_granularity(Duration::from_millis(10));
+    let (_server, mut client) = util::start_server(config)?;
+    client.batch_execute(
+        "CREATE TABLE events (ts timestamp, amount int);
+         CREATE MATERIALIZED VIEW windowed WITH (emit_final_after = 'ts') AS
+         SELECT ts, amount FROM events",
+    )?;
+    let soon = Utc::now() + chrono::Duration::seconds(3);
+    client.execute(
+        &*format!(
+            "INSERT INTO events VALUES
+             ('2000-01-01 00:00:00', 1), ('3000-01-01 00:00:00', 2), (NULL, 3), ('{}', 4)",
+            soon.format("%Y-%m-%d %H:%M:%S%.3f")
+        ),
+        &[],
+    )?;
+
+    let amounts = |client: &mut postgres::Client| -> Result<Vec<i32>, Box<dyn Error>> {
+        Ok(client
+            .query("SELECT amount FROM windowed ORDER BY amount", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+    };
+
+    // Only the row without a time is visible right away.
+    assert_eq!(amounts(&mut client)?, &[3]);
+
+    // The row that becomes final soon appears once the wall clock passes its
+    // time. The row whose time had already passed never appears, and neither
+    // does the row whose time is far in the future.
+    util::poll(|| {
+        let amounts = amounts(&mut client)?;
+        Ok(if amounts.len() > 1 { Some(()) } else { None })
+    })?;
+    assert!(Utc::now() >= soon);
+    assert_eq!(amounts(&mut client)?, &[3, 4]);
+
+    Ok(())
+}
+
+// Tests that a view with a `refresh_interval` observes the changes to its inputs
+// only at multiples of the interval.
+#[test]
+fn test_refresh_interval() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let config = util::Config::default().table_timestamp_granularity(Duration::from_millis(10));
+    let (server, mut client) = util::start_server(config)?;
+    client.batch_execute(
+        "CREATE TABLE t (a int);
+         CREATE MATERIALIZED VIEW v WITH (refresh_interval = '1s') AS SELECT a FROM t",
+    )?;
+
+    let mut tail_client = server.connect()?;
+    let mut tail_reader = tail_client.copy_out("TAIL v")?.split(b'\n');
+    // Each insertion is timestamped separately, a few milliseconds apart.
+    for i in 0..5 {
+        client.execute("INSERT INTO t VALUES ($1)", &[&i])?;
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let mut times = vec![];
+    for _ in 0..5 {
+        let line = String::from_utf8(tail_reader.next().unwrap()?)?;
+        let time: Timestamp = line
+            .rsplit(" at ")
+            .next()
+            .and_then(|time| time.parse().ok())
+            .ok_or_else(|| format!("malformed TAIL output: {}", line))?;
+        times.push(time);
+    }
+    // The insertions took well under a second, so they were batched into at
+    // most two refreshes, each at a multiple of the interval.
+    assert!(times.iter().all(|time| time % 1000 == 0), "{:?}", times);
+    times.dedup();
+    assert!(times.len() <= 2, "{:?}", times);
+
+    Ok(())
+}
+
+// Tests that the dataflows of peeks are assigned transient IDs, rather than
+// consuming IDs from the catalog.
+#[test]
+fn test_transient_ids() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute("CREATE MATERIALIZED VIEW a AS SELECT 1 AS x")?;
+    // Each of these peeks must build a dataflow of its own to compute x + 1.
+    for _ in 0..3 {
+        let row = client.query_one("SELECT x + 1 FROM a", &[])?;
+        assert_eq!(row.get::<_, i32>(0), 2);
+    }
+    client.batch_execute("CREATE VIEW b AS SELECT 2")?;
+
+    let ids = client
+        .query(
+            "SELECT global_id FROM mz_catalog.mz_catalog_names \
+             WHERE name IN ('materialize.public.a', 'materialize.public.b') \
+             ORDER BY name",
+            &[],
+        )?
+        .into_iter()
+        .map(|row| row.get::<_, String>(0)[1..].parse())
+        .collect::<Result<Vec<u64>, _>>()?;
+    // The view's index is the only item created in between.
+    assert_eq!(ids, &[ids[0], ids[0] + 2]);
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute("CREATE TABLE t (a int)")?;
+    client.batch_execute("INSERT INTO t VALUES (1), (2), (3)")?;
+
+    let rows = client.query("EXPLAIN ANALYZE FOR SELECT a + 1 FROM t", &[])?;
+    assert!(!rows.is_empty());
+    let mut ids = vec![];
+    let mut max_records = 0;
+    for row in rows {
+        ids.push(row.get::<_, i64>(0));
+        assert!(!row.get::<_, String>(1).is_empty());
+        max_records = cmp::max(max_records, row.get::<_, i64>(2));
+    }
+    // Operators are reported once each, in order of their IDs.
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    // Some operator must have produced each of the table's rows.
+    assert!(max_records >= 3);
+
+    // A constant query runs no dataflow.
+    let rows = client.query("EXPLAIN ANALYZE FOR SELECT 1", &[])?;
+    assert!(rows.is_empty());
+
+    let (_server, mut client) =
+        util::start_server(util::Config::default().logging_granularity(None))?;
+    let err = client
+        .query("EXPLAIN ANALYZE FOR SELECT 1", &[])
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("EXPLAIN ANALYZE requires logging to be enabled"));
+    Ok(())
+}
+
+#[test]
+fn test_command_log() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("commands.log");
+    let (_server, mut client) = util::start_server(util::Config::default().command_log(&path))?;
+    client.batch_execute("CREATE TABLE t (a int)")?;
+    client.query("SELECT $1 + 1", &[&42_i32])?;
+    client.batch_execute("SELECT 'a\tb\nc'")?;
+
+    let log = fs::read_to_string(&path)?;
+    let lines: Vec<Vec<&str>> = log.lines().map(|l| l.split('\t').collect()).collect();
+    assert_eq!(lines.len(), 3);
+    for line in &lines {
+        assert_eq!(line.len(), 3);
+        line[0].parse::<u64>()?;
+        assert_eq!(line[1], lines[0][1]);
+    }
+    assert_eq!(lines[0][2], "CREATE TABLE t (a int)");
+    // Parameters are inlined, so the statement can be replayed as is.
+    assert_eq!(lines[1][2], "SELECT CAST('42' AS int) + 1");
+    assert_eq!(lines[2][2], r"SELECT 'a\tb\nc'");
+    // The log is readable only by its owner.
+    let mode = fs::metadata(&path)?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    Ok(())
+}
+
+#[test]
+fn test_catalog_sql_dump() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, mut client) = util::start_server(util::Config::default())?;
+
+    // Several statements in one simple query are executed in order.
+    client.batch_execute(
+        "CREATE SCHEMA s; \
+         CREATE VIEW s.v AS SELECT 1 AS a; \
+         CREATE MATERIALIZED VIEW w AS SELECT a + 1 AS b FROM s.v",
+    )?;
+
+    let dump = server.http_get("/catalog.sql")?;
+    assert!(dump.contains("CREATE SCHEMA IF NOT EXISTS \"materialize\".\"s\""));
+
+    // Applying the dump to a fresh server recreates the same objects.
+    let (_server2, mut client2) = util::start_server(util::Config::default())?;
+    client2.batch_execute(&dump)?;
+    let rows: Vec<i32> = client2
+        .query("SELECT b FROM w", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, &[2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_http_ingest() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    // The endpoint is disabled unless a token is configured.
+    let (server, _client) = util::start_server(util::Config::default())?;
+    let (status, _body) = server.http_post("/ingest/t", &[], "[1]\n")?;
+    assert_eq!(status, 404);
+
+    let config = util::Config::default().ingest_token("s3cret");
+    let (server, _client) = util::start_server(config)?;
+
+    let (status, _body) = server.http_post("/ingest/t", &[], "[1]\n")?;
+    assert_eq!(status, 401);
+    let (status, _body) =
+        server.http_post("/ingest/t", &[("Authorization", "Bearer wrong")], "[1]\n")?;
+    assert_eq!(status, 401);
+
+    let auth = ("Authorization", "Bearer s3cret");
+    let (status, _body) =
+        server.http_post("/ingest/t", &[auth, ("Content-Type", "text/xml")], "")?;
+    assert_eq!(status, 415);
+
+    // Malformed rows are reported in the acknowledgement stream, along with
+    // the line on which they occurred.
+    let (status, body) = server.http_post("/ingest/t", &[auth], "\n{\"a\": 1}\n")?;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""batch":0"#), "body: {}", body);
+    assert!(
+        body.contains("line 2: expected a JSON array"),
+        "body: {}",
+        body
+    );
+
+    // So are errors from the insert itself.
+    let (status, body) = server.http_post(
+        "/ingest/nonexistent",
+        &[auth, ("Content-Type", "text/csv")],
+        "1,a\n2,b\n",
+    )?;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""error""#), "body: {}", body);
+    assert!(!body.contains(r#""done""#), "body: {}", body);
+
+    Ok(())
+}
+
+// Tests that rows posted to the ingest endpoint in either format end up in
+// the table, in as many batches as their number requires.
+#[test]
+fn test_http_ingest_rows() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let config = util::Config::default().ingest_token("s3cret");
+    let (server, mut client) = util::start_server(config)?;
+    client.batch_execute("CREATE TABLE t (a int, b text)")?;
+    let auth = ("Authorization", "Bearer s3cret");
+
+    let (status, body) = server.http_post(
+        "/ingest/t",
+        &[auth, ("Content-Type", "application/x-ndjson")],
+        "[1, \"one\"]\n\n[2, null]\n",
+    )?;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#"{"batch":0,"rows":2}"#), "body: {}", body);
+    assert!(body.contains(r#"{"done":true,"rows":2}"#), "body: {}", body);
+
+    let csv: String = (3..2003).map(|i| format!("{},\"n{}\"\n", i, i)).collect();
+    let (status, body) =
+        server.http_post("/ingest/t", &[auth, ("Content-Type", "text/csv")], &csv)?;
+    assert_eq!(status, 200);
+    assert!(
+        body.contains(r#"{"batch":0,"rows":1024}"#),
+        "body: {}",
+        body
+    );
+    assert!(body.contains(r#"{"batch":1,"rows":976}"#), "body: {}", body);
+    assert!(
+        body.contains(r#"{"done":true,"rows":2000}"#),
+        "body: {}",
+        body
+    );
+
+    let rows = client.query("SELECT a, b FROM t WHERE a < 4 ORDER BY a", &[])?;
+    let rows: Vec<(i32, Option<String>)> = rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+    assert_eq!(
+        rows,
+        vec![(1, Some("one".into())), (2, None), (3, Some("n3".into())),]
+    );
+    let count: i64 = client.query_one("SELECT count(*) FROM t", &[])?.get(0);
+    assert_eq!(count, 2002);
+
+    Ok(())
+}
+
+#[test]
+fn test_http_changes() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(
+        "CREATE TABLE t (a int); \
+         CREATE VIEW v AS SELECT a FROM t; \
+         CREATE MATERIALIZED VIEW w AS SELECT a FROM t; \
+         INSERT INTO t VALUES (1), (2)",
+    )?;
+
+    let get_changes = |path: &str| -> Result<serde_json::Value, Box<dyn Error>> {
+        Ok(serde_json::from_str(&server.http_get(path)?)?)
+    };
+    let sorted_changes = |res: &serde_json::Value| {
+        let mut changes = res["changes"].as_array().unwrap().clone();
+        changes.sort_by_key(|change| change["row"]["a"].as_i64());
+        changes
+    };
+
+    // Without `since`, the entire contents of the view are returned.
+    let res = get_changes("/changes/w")?;
+    assert_eq!(
+        sorted_changes(&res),
+        vec![
+            json!({ "row": { "a": 1 }, "diff": 1 }),
+            json!({ "row": { "a": 2 }, "diff": 1 }),
+        ]
+    );
+
+    // With `since`, only the changes after that timestamp are returned.
+    client.batch_execute("DELETE FROM t WHERE a = 1; INSERT INTO t VALUES (3)")?;
+    let since = res["timestamp"].as_u64().unwrap();
+    let res = get_changes(&format!("/changes/materialize.public.w?since={}", since))?;
+    assert!(res["timestamp"].as_u64().unwrap() > since);
+    assert_eq!(
+        sorted_changes(&res),
+        vec![
+            json!({ "row": { "a": 1 }, "diff": -1 }),
+            json!({ "row": { "a": 3 }, "diff": 1 }),
+        ]
+    );
+
+    // Nothing has changed since the last read.
+    let since = res["timestamp"].as_u64().unwrap();
+    let res = get_changes(&format!("/changes/w?since={}", since))?;
+    assert!(res["timestamp"].as_u64().unwrap() >= since);
+    assert_eq!(sorted_changes(&res), Vec::<serde_json::Value>::new());
+
+    let body = server.http_get("/changes/v")?;
+    assert!(body.contains("is not materialized"), "body: {}", body);
+    let body = server.http_get("/changes/w?since=never")?;
+    assert!(body.contains("invalid since timestamp"), "body: {}", body);
+
+    Ok(())
+}
+
+#[test]
+fn test_clusters() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path().to_owned());
+
+    {
+        let (_server, mut client) = util::start_server(config.clone().cluster("analytics", 2))?;
+        client.batch_execute(
+            "CREATE VIEW v AS SELECT 1 AS a; \
+             CREATE MATERIALIZED VIEW w IN CLUSTER analytics AS SELECT a + 1 AS b FROM v; \
+             CREATE INDEX v_idx IN CLUSTER analytics ON v (a)",
+        )?;
+        let rows: Vec<i32> = client
+            .query("SELECT b FROM w", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(rows, &[2]);
+
+        let err = client
+            .batch_execute("CREATE INDEX v_idx2 IN CLUSTER nope ON v (a)")
+            .unwrap_err();
+        assert!(err.to_string().contains("cluster 'nope' does not exist"));
+
+        // Tables are only maintained by the default cluster.
+        client.batch_execute("CREATE TABLE t (a int)")?;
+        let err = client
+            .batch_execute("CREATE MATERIALIZED VIEW u IN CLUSTER analytics AS SELECT a FROM t")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only available on the default cluster"));
+    }
+
+    // Without the cluster, the items assigned to it are unavailable, but the
+    // views they were built on can still be computed on the default cluster.
+    {
+        let (_server, mut client) = util::start_server(config)?;
+        let rows: Vec<i32> = client
+            .query("SELECT b FROM w", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(rows, &[2]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_index_replicas() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let config = util::Config::default()
+        .cluster("replica1", 1)
+        .cluster("replica2", 1);
+    let (_server, mut client) = util::start_server(config)?;
+
+    client.batch_execute(
+        "CREATE VIEW v AS SELECT 1 AS a; \
+         CREATE INDEX v_idx REPLICAS (replica1, replica2) ON v (a)",
+    )?;
+
+    // Peeks are spread across the index and its replicas, all of which must
+    // agree.
+    for _ in 0..6 {
+        let rows: Vec<i32> = client
+            .query("SELECT a FROM v", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(rows, &[1]);
+    }
+
+    let err = client
+        .batch_execute("CREATE INDEX v_idx2 IN CLUSTER replica1 REPLICAS (replica1) ON v (a)")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("index cannot be replicated on its own cluster 'replica1'"));
+
+    let err = client
+        .batch_execute("CREATE INDEX v_idx2 REPLICAS (replica1, replica1) ON v (a)")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("index replicated on cluster 'replica1' more than once"));
+
+    // Dropping the index drops its replicas along with it.
+    client.batch_execute("DROP INDEX v_idx")?;
+    let rows: Vec<i32> = client
+        .query("SELECT a FROM v", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, &[1]);
+
+    Ok(())
+}
+
+/// A source plugin that produces the numbers from zero up to its `count`
+/// option, all at time 1, and is then exhausted.
+struct CounterFactory;
+
+impl SourcePluginFactory for CounterFactory {
+    fn validate(&self, options: &BTreeMap<String, String>) -> Result<(), failure::Error> {
+        match options.get("count").map(|count| count.parse::<i64>()) {
+            Some(Ok(_)) => Ok(()),
+            _ => bail!("count must be an integer"),
+        }
+    }
+
+    fn create(
+        &self,
+        options: &BTreeMap<String, String>,
+        worker_index: usize,
+        _worker_count: usize,
+    ) -> Result<Option<Box<dyn SourcePlugin>>, failure::Error> {
+        if worker_index != 0 {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(Counter {
+            count: options["count"].parse()?,
+            done: false,
+        })))
+    }
+}
+
+struct Counter {
+    count: i64,
+    done: bool,
+}
+
+impl SourcePlugin for Counter {
+    fn start(&mut self, waker: SourceWaker) -> Result<(), failure::Error> {
+        waker.wake();
+        Ok(())
+    }
+
+    fn poll_batch(&mut self) -> Result<Option<Vec<SourceRecord>>, failure::Error> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        Ok(Some(
+            (0..self.count)
+                .map(|i| SourceRecord {
+                    data: i.to_string().into_bytes(),
+                    position: Some(i),
+                    time: 1,
+                })
+                .collect(),
+        ))
+    }
+
+    fn watermark(&self) -> Timestamp {
+        2
+    }
+}
+
+#[test]
+fn test_source_plugin() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    dataflow::register_source_plugin("counter", CounterFactory);
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    client.batch_execute(
+        "CREATE MATERIALIZED SOURCE counter FROM PLUGIN 'counter' WITH (count = 3) FORMAT TEXT",
+    )?;
+    let rows: Vec<(String, i64)> = query_rows(
+        &mut client,
+        "SELECT text, mz_offset FROM counter ORDER BY mz_offset",
+        3,
+    )?
+    .into_iter()
+    .map(|row| (row.get(0), row.get(1)))
+    .collect();
+    assert_eq!(rows, &[("0".into(), 0), ("1".into(), 1), ("2".into(), 2)]);
+
+    let err = client
+        .batch_execute("CREATE SOURCE bad FROM PLUGIN 'counter' WITH (count = 'many') FORMAT TEXT")
+        .unwrap_err();
+    assert!(err.to_string().contains("count must be an integer"));
+
+    let err = client
+        .batch_execute("CREATE SOURCE bad FROM PLUGIN 'nope' FORMAT TEXT")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("source plugin 'nope' is not registered"));
+
+    Ok(())
+}
+
+#[test]
+fn test_install_demo() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    client.batch_execute("INSTALL DEMO auction")?;
+    // The generator produces ten events per second, so there should be a
+    // winning bid within a few seconds.
+    util::poll(|| {
+        let winning_bids: i64 = client
+            .query_one("SELECT count(*) FROM auction.winning_bids", &[])?
+            .get(0);
+        Ok(if winning_bids > 0 { Some(()) } else { None })
+    })?;
+
+    let err = client.batch_execute("INSTALL DEMO auction").unwrap_err();
+    assert!(err.to_string().contains("schema 'auction' already exists"));
+
+    let err = client.batch_execute("INSTALL DEMO tpch").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown demo 'tpch'; the available demos are: auction"));
+
+    let err = client
+        .batch_execute("CREATE SOURCE bad FROM PLUGIN 'auction' WITH (rate = 0) FORMAT TEXT")
+        .unwrap_err();
+    assert!(err.to_string().contains("rate must be a positive integer"));
+
+    Ok(())
+}
+
+/// A sink plugin that writes to a vector in memory.
+///
+/// If `transactional` is set, writes are staged until they are committed, and
+/// discarded when a timestamp is redelivered; otherwise they are applied
+/// immediately.
+#[derive(Clone)]
+struct MemorySinkFactory {
+    store: Arc<Mutex<Vec<SinkRecord>>>,
+    transactional: bool,
+    guarantee: DeliveryGuarantee,
+}
+
+impl MemorySinkFactory {
+    fn new(transactional: bool, guarantee: DeliveryGuarantee) -> MemorySinkFactory {
+        MemorySinkFactory {
+            store: Arc::new(Mutex::new(vec![])),
+            transactional,
+            guarantee,
+        }
+    }
+
+    fn contents(&self) -> Result<Vec<SinkRecord>, failure::Error> {
+        Ok(self.store.lock().unwrap().clone())
+    }
+}
+
+impl SinkPluginFactory for MemorySinkFactory {
+    fn validate(&self, options: &BTreeMap<String, String>) -> Result<(), failure::Error> {
+        if !options.is_empty() {
+            bail!("memory sinks do not accept options");
+        }
+        Ok(())
+    }
+
+    fn guarantee(&self) -> DeliveryGuarantee {
+        self.guarantee
+    }
+
+    fn create(
+        &self,
+        _options: &BTreeMap<String, String>,
+        _worker_index: usize,
+        _worker_count: usize,
+    ) -> Result<Box<dyn SinkPlugin>, failure::Error> {
+        Ok(Box::new(MemorySink {
+            store: self.store.clone(),
+            transactional: self.transactional,
+            staged: vec![],
+        }))
+    }
+}
+
+struct MemorySink {
+    store: Arc<Mutex<Vec<SinkRecord>>>,
+    transactional: bool,
+    staged: Vec<SinkRecord>,
+}
+
+impl SinkPlugin for MemorySink {
+    fn begin(&mut self, _time: Timestamp) -> Result<(), failure::Error> {
+        self.staged.clear();
+        Ok(())
+    }
+
+    fn write(&mut self, records: &[SinkRecord]) -> Result<(), failure::Error> {
+        if self.transactional {
+            self.staged.extend(records.iter().cloned());
+        } else {
+            self.store.lock().unwrap().extend(records.iter().cloned());
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self, _upper: Timestamp) -> Result<(), failure::Error> {
+        self.store.lock().unwrap().extend(self.staged.drain(..));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_sink_plugin_conformance() -> Result<(), Box<dyn Error>> {
+    let options = BTreeMap::new();
+
+    let factory = MemorySinkFactory::new(true, DeliveryGuarantee::ExactlyOnce);
+    dataflow::check_sink_plugin(&factory, &options, || factory.contents()).unwrap();
+
+    let factory = MemorySinkFactory::new(false, DeliveryGuarantee::AtLeastOnce);
+    dataflow::check_sink_plugin(&factory, &options, || factory.contents()).unwrap();
+
+    // A plugin that applies writes immediately cannot deliver exactly once.
+    let factory = MemorySinkFactory::new(false, DeliveryGuarantee::ExactlyOnce);
+    let err = dataflow::check_sink_plugin(&factory, &options, || factory.contents()).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("after retrying the delivery of a timestamp"));
+
+    Ok(())
+}
+
+#[test]
+fn test_sink_plugin() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let factory = MemorySinkFactory::new(true, DeliveryGuarantee::ExactlyOnce);
+    dataflow::register_source_plugin("counter", CounterFactory);
+    dataflow::register_sink_plugin("memory", factory.clone());
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    client.batch_execute(
+        "CREATE SOURCE counter FROM PLUGIN 'counter' WITH (count = 2) FORMAT TEXT;
+         CREATE SINK counter_sink FROM counter INTO PLUGIN 'memory' FORMAT JSON",
+    )?;
+    let records = util::poll(|| {
+        let records = factory.contents().unwrap();
+        Ok(if records.len() >= 2 {
+            Some(records)
+        } else {
+            None
+        })
+    })?;
+    let mut values: Vec<_> = records
+        .into_iter()
+        .map(|record| (serde_json::Value::Object(record.value), record.diff))
+        .collect();
+    values.sort_by_key(|(value, _diff)| value["mz_offset"].as_i64());
+    assert_eq!(
+        values,
+        &[
+            (json!({ "text": "0", "mz_offset": 0 }), 1),
+            (json!({ "text": "1", "mz_offset": 1 }), 1),
+        ]
+    );
+
+    let err = client
+        .batch_execute("CREATE SINK bad FROM counter INTO PLUGIN 'memory' WITH (a = 1) FORMAT JSON")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("memory sinks do not accept options"));
+
+    let err = client
+        .batch_execute("CREATE SINK bad FROM counter INTO PLUGIN 'memory' FORMAT TEXT")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("plugin sinks support only FORMAT JSON"));
+
+    let err = client
+        .batch_execute("CREATE SINK bad FROM counter INTO PLUGIN 'nope' FORMAT JSON")
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("sink plugin 'nope' is not registered"));
+
+    Ok(())
+}
+
+#[test]
+fn test_catalog_compaction() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path().to_owned());
+    let temp_dir = tempfile::tempdir()?;
+    let path = Path::join(temp_dir.path(), "source.txt");
+    File::create(&path)?;
+
+    {
+        let (_server, mut client) = util::start_server(config.clone())?;
+        client.batch_execute(&format!(
+            "CREATE SOURCE src FROM FILE '{}' FORMAT BYTES",
+            path.display(),
+        ))?;
+        client.batch_execute("CREATE MATERIALIZED VIEW v AS SELECT * FROM src")?;
+    }
+
+    // Plant the history that compaction removes: timestamp bindings for the
+    // view's source instance that later bindings supersede, and rows that
+    // refer to an item that no longer exists.
+    {
+        let conn = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        let gid = |name: &str| -> rusqlite::Result<Vec<u8>> {
+            conn.query_row("SELECT gid FROM items WHERE name = ?", &[name], |row| {
+                row.get(0)
+            })
+        };
+        let src = gid("src")?;
+        let idx = gid("v_primary_idx")?;
+        let missing = br#"{"User":1000}"#.to_vec();
+        for (sid, timestamp) in &[(&src, "1"), (&src, "2"), (&src, "3"), (&missing, "1")] {
+            let checksum = timestamp_checksum(sid, &idx, timestamp.as_bytes(), b"0");
+            conn.execute(
+                "INSERT INTO timestamps (sid, vid, timestamp, offset, checksum)
+                 VALUES (?, ?, ?, ?, ?)",
+                rusqlite::params![*sid, idx, timestamp.as_bytes(), "0".as_bytes(), checksum],
+            )?;
+        }
+        conn.execute(
+            "INSERT INTO item_comments VALUES (?, 'gone')",
+            rusqlite::params![missing],
+        )?;
+    }
+
+    let (_server, mut client) = util::start_server(config)?;
+    // Wait for the view's index to report its frontier, which determines how
+    // far its bindings can be compacted.
+    thread::sleep(Duration::from_secs(1));
+
+    let check = |client: &mut postgres::Client| -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(client
+            .query("CHECK CATALOG", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+    };
+    let problems = check(&mut client)?;
+    assert_eq!(problems.len(), 2);
+    assert!(problems.contains(&"comment refers to missing item u1000".to_string()));
+
+    let row = client.query_one("COMPACT CATALOG", &[])?;
+    assert_eq!((row.get(0), row.get(1)), (2_i64, 2_i64));
+    assert_eq!(check(&mut client)?, Vec::<String>::new());
+
+    let row = client.query_one("VACUUM CATALOG", &[])?;
+    assert_eq!((row.get(0), row.get(1)), (0_i64, 0_i64));
+
+    Ok(())
+}
+
+#[test]
+fn test_catalog_quarantine() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path().to_owned());
+
+    {
+        let (_server, mut client) = util::start_server(config.clone())?;
+        client.batch_execute(
+            "CREATE VIEW v AS SELECT 1 AS a; \
+             CREATE VIEW w AS SELECT a + 1 AS b FROM v; \
+             CREATE VIEW unrelated AS SELECT 2 AS c",
+        )?;
+    }
+
+    // Corrupt the definition of v without updating its checksum, as a torn
+    // write or a bad disk might.
+    {
+        let conn = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        let n = conn.execute(
+            "UPDATE items SET definition = definition || x'00' WHERE name = 'v'",
+            rusqlite::params![],
+        )?;
+        assert_eq!(n, 1);
+    }
+
+    // The server starts without v, and without w, which depends on v.
+    let (_server, mut client) = util::start_server(config)?;
+    let err = client.query("SELECT * FROM w", &[]).unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+    let row = client.query_one("SELECT * FROM unrelated", &[])?;
+    assert_eq!(row.get::<_, i32>(0), 2);
+
+    let mut problems: Vec<String> = client
+        .query("CHECK CATALOG", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    problems.sort();
+    assert_eq!(problems.len(), 2);
+    assert_eq!(problems[0], "item v is quarantined: checksum mismatch");
+    assert!(problems[1].starts_with("item w is quarantined: depends on a quarantined item"));
+
+    Ok(())
+}
+
+/// Serves `schema` as the latest schema of every subject, in the manner of a
+/// Confluent Schema Registry, and returns the URL of the registry. The schema
+/// can be changed while the registry runs.
+fn start_schema_registry(schema: Arc<Mutex<String>>) -> Result<String, Box<dyn Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap() > 2 {
+                header.clear();
+            }
+            let (status, body) = if request.contains("-value/versions/latest") {
+                let schema = schema.lock().unwrap().clone();
+                ("200 OK", json!({"id": 1, "schema": schema}))
+            } else {
+                (
+                    "404 Not Found",
+                    json!({"error_code": 40401, "message": "Subject not found."}),
+                )
+            };
+            let body = body.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    Ok(url)
+}
+
+#[test]
+fn test_source_schema_errors() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let envelope = |fields| {
+        json!({
+            "type": "record",
+            "name": "envelope",
+            "fields": [
+                {
+                    "name": "before",
+                    "type": ["null", {"type": "record", "name": "row", "fields": fields}]
+                },
+                {"name": "after", "type": ["null", "row"]}
+            ]
+        })
+        .to_string()
+    };
+    let schema = Arc::new(Mutex::new(envelope(json!([
+        {"name": "a", "type": "long"},
+        {"name": "b", "type": "string"}
+    ]))));
+    let registry_url = start_schema_registry(schema.clone())?;
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path().to_owned());
+
+    {
+        let (_server, mut client) = util::start_server(config.clone())?;
+        client.batch_execute(&format!(
+            "CREATE SOURCE orders FROM KAFKA BROKER '127.0.0.1:1' TOPIC 'orders' \
+             FORMAT AVRO USING CONFLUENT SCHEMA REGISTRY '{}' ENVELOPE DEBEZIUM",
+            registry_url
+        ))?;
+        assert!(client.query("SHOW SOURCE ERRORS", &[])?.is_empty());
+    }
+
+    // Remove a column upstream while the server is down.
+    *schema.lock().unwrap() = envelope(json!([{"name": "a", "type": "long"}]));
+
+    let (_server, mut client) = util::start_server(config)?;
+    let rows = client.query("SHOW SOURCE ERRORS", &[])?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, String>(0), "orders");
+    assert_eq!(
+        rows[0].get::<_, String>(1),
+        "latest schema for subject 'orders-value' is incompatible with the source: \
+         column b no longer exists"
+    );
+    let err = client.query("SELECT * FROM orders", &[]).unwrap_err();
+    assert!(err.to_string().contains("is unavailable"), "{}", err);
+
+    Ok(())
+}
+
+#[test]
+fn test_view_freshness() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let path = Path::join(temp_dir.path(), "file");
+    fs::write(&path, "")?;
+    client.batch_execute(&format!(
+        "CREATE SOURCE s FROM FILE '{}' WITH (tail = true) FORMAT BYTES",
+        path.display()
+    ))?;
+    // The frontier of a tailed file source trails the wall clock by up to a
+    // second, which violates the first objective but not the second.
+    client.batch_execute(
+        "CREATE MATERIALIZED VIEW strict WITH (max_lag = '1ms') AS SELECT * FROM s; \
+         CREATE MATERIALIZED VIEW relaxed WITH (max_lag = '1h') AS SELECT * FROM s",
+    )?;
+
+    let rows = util::poll(|| {
+        let rows = client.query(
+            "SELECT max_lag_ms, violations FROM mz_catalog.mz_view_freshness ORDER BY max_lag_ms",
+            &[],
+        )?;
+        let violated = rows.len() == 2 && rows[0].get::<_, i64>(1) >= 1;
+        Ok(if violated { Some(rows) } else { None })
+    })?;
+    assert_eq!(rows[0].get::<_, i64>(0), 1);
+    assert_eq!(rows[1].get::<_, i64>(0), 3_600_000);
+    assert_eq!(rows[1].get::<_, i64>(1), 0);
+
+    // Dropping a view removes its objective.
+    client.batch_execute("DROP VIEW strict")?;
+    let rows = util::poll(|| {
+        let rows = client.query("SELECT max_lag_ms FROM mz_catalog.mz_view_freshness", &[])?;
+        Ok(if rows.len() == 1 { Some(rows) } else { None })
+    })?;
+    assert_eq!(rows[0].get::<_, i64>(0), 3_600_000);
+
+    Ok(())
+}
+
+#[test]
+fn test_peek_latency_during_backfill() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    const LINES: usize = 4_000_000;
+
+    let temp_dir = tempfile::tempdir()?;
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+
+    let path = Path::join(temp_dir.path(), "backfill");
+    let mut file = io::BufWriter::new(File::create(&path)?);
+    for i in 0..LINES {
+        writeln!(file, "{}", i)?;
+    }
+    file.flush()?;
+    drop(file);
+
+    client.batch_execute("CREATE TABLE t (a int); INSERT INTO t VALUES (1)")?;
+    client.batch_execute(&format!(
+        "CREATE SOURCE s FROM FILE '{}' FORMAT TEXT; \
+         CREATE MATERIALIZED VIEW c AS SELECT count(*) FROM s",
+        path.display()
+    ))?;
+
+    // Peeks of the table must not wait for the backfill of the view.
+    let mut latencies = vec![];
+    for _ in 0..50 {
+        let start = Instant::now();
+        let rows = client.query("SELECT a FROM t", &[])?;
+        latencies.push(start.elapsed());
+        assert_eq!(rows.len(), 1);
+    }
+    latencies.sort();
+    let p99 = latencies[latencies.len() * 99 / 100];
+    assert!(p99 < Duration::from_secs(1), "p99 peek latency {:?}", p99);
+
+    // Check that the backfill was in fact still in progress.
+    let rows = client.query("SELECT * FROM c", &[])?;
+    let complete = rows
+        .first()
+        .map_or(false, |row| row.get::<_, i64>(0) == LINES as i64);
+    assert!(!complete, "backfill completed before the peeks");
+
+    Ok(())
+}
+
+#[test]
+fn test_arrangement_memory_limit() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let config = util::Config::default().arrangement_memory_limit(100);
+    let (_server, mut client) = util::start_server(config)?;
+
+    client.batch_execute("CREATE TABLE t (a int); INSERT INTO t VALUES (1), (2), (3)")?;
+    // Give the workers time to report the size of the table's arrangement.
+    thread::sleep(Duration::from_secs(3));
+
+    // A peek that needs a temporary dataflow is refused...
+    let err = client
+        .query("SELECT count(*) FROM t", &[])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("bytes, more than the limit of 100"), "{}", err);
+
+    // ...but one that reads an existing index is not.
+    let rows = client.query("SELECT a FROM t", &[])?;
+    assert_eq!(rows.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_trace_export() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let temp_dir = tempfile::tempdir()?;
+    let contents_path = temp_dir.path().join("w.trace");
+    let history_path = temp_dir.path().join("w-history.trace");
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(
+        "CREATE TABLE t (a int, b text); \
+         CREATE MATERIALIZED VIEW w AS SELECT a, b FROM t; \
+         INSERT INTO t VALUES (1, 'one'), (2, 'two'), (2, 'two'); \
+         DELETE FROM t WHERE a = 1; \
+         INSERT INTO t VALUES (3, NULL)",
+    )?;
+
+    let export = |client: &mut postgres::Client, sql: String| -> Result<i64, Box<dyn Error>> {
+        Ok(client.query_one(sql.as_str(), &[])?.get(0))
+    };
+    let n = export(
+        &mut client,
+        format!("EXPORT TRACE w TO '{}'", contents_path.display()),
+    )?;
+    assert_eq!(n, 2);
+    export(
+        &mut client,
+        format!(
+            "EXPORT TRACE w TO '{}' WITH HISTORY",
+            history_path.display()
+        ),
+    )?;
+
+    // The first line of the file describes the view, and each subsequent line
+    // is an update.
+    let file = fs::read_to_string(&contents_path)?;
+    let mut lines = file.lines();
+    let header: serde_json::Value = serde_json::from_str(lines.next().unwrap())?;
+    assert_eq!(header["version"], json!(1));
+    assert_eq!(header["name"], json!("materialize.public.w"));
+    assert_eq!(header["history"], json!(false));
+    let mut updates: Vec<serde_json::Value> = lines
+        .map(|line| serde_json::from_str(line))
+        .collect::<Result<_, _>>()?;
+    for update in &mut updates {
+        update.as_object_mut().unwrap().remove("time");
+    }
+    updates.sort_by_key(|update| update["row"][0].as_i64());
+    assert_eq!(
+        updates,
+        vec![
+            json!({ "row": [2, "two"], "diff": 2 }),
+            json!({ "row": [3, null], "diff": 1 }),
+        ]
+    );
+
+    // Importing a trace without history reproduces the view's contents...
+    let query_contents = |client: &mut postgres::Client, sql: &str| {
+        client.query(sql, &[]).map(|rows| {
+            rows.into_iter()
+                .map(|row| (row.get::<_, i32>(0), row.get::<_, Option<String>>(1)))
+                .collect::<Vec<_>>()
+        })
+    };
+    let expected = query_contents(&mut client, "SELECT a, b FROM w ORDER BY a, b")?;
+    client.batch_execute(&format!(
+        "IMPORT TRACE w_contents FROM '{}'",
+        contents_path.display()
+    ))?;
+    assert_eq!(
+        query_contents(&mut client, "SELECT a, b FROM w_contents ORDER BY a, b")?,
+        expected
+    );
+
+    // ...and importing one with history yields each update as a row, from
+    // which the contents can be reconstructed.
+    client.batch_execute(&format!(
+        "IMPORT TRACE w_history FROM '{}'",
+        history_path.display()
+    ))?;
+    let rows = client.query(
+        "SELECT a, b, sum(mz_diff)::bigint FROM w_history \
+         GROUP BY a, b HAVING sum(mz_diff) <> 0 ORDER BY a",
+        &[],
+    )?;
+    let reconstructed: Vec<_> = rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get::<_, i32>(0),
+                row.get::<_, Option<String>>(1),
+                row.get::<_, i64>(2),
+            )
+        })
+        .collect();
+    assert_eq!(
+        reconstructed,
+        vec![(2, Some("two".into()), 2), (3, None, 1)]
+    );
+
+    let err = client
+        .batch_execute("EXPORT TRACE t_primary_idx TO '/dev/null'")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is not a view or source"), "{}", err);
+
+    Ok(())
+}
+
+#[test]
+fn test_show_arrangements() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (_server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(
+        "CREATE TABLE t (a int, b text); \
+         CREATE MATERIALIZED VIEW w AS SELECT a, b FROM t; \
+         INSERT INTO t VALUES (1, 'one'), (2, 'two')",
+    )?;
+    // Reading the view waits for the inserts to reach its arrangement.
+    assert_eq!(client.query("SELECT * FROM w", &[])?.len(), 2);
+
+    let rows: Vec<_> = client
+        .query("SHOW ARRANGEMENTS", &[])?
+        .into_iter()
+        .filter(|row| row.get::<_, String>("Index") == "materialize.public.w_primary_idx")
+        .collect();
+    assert_eq!(rows.len(), 1);
+    let row = &rows[0];
+    assert_eq!(row.get::<_, Option<String>>("Cluster"), None);
+    assert_eq!(row.get::<_, i64>("Worker"), 0);
+    assert_eq!(row.get::<_, String>("Collection"), "materialize.public.w");
+    assert!(!row.get::<_, bool>("Shared"));
+    assert_eq!(row.get::<_, String>("Key_types"), "i32?, string?");
+    assert!(row.get::<_, i64>("Records") >= 2);
+    assert!(row.get::<_, i64>("Bytes") > 0);
+
+    Ok(())
+}
+
+/// Rejects plans that read `hook_secrets`, and restricts plans that read
+/// `hook_tenants` to the rows of tenant 1. Hooks apply to every server in the
+/// process, so the hook leaves the tables of other tests alone.
+struct TenantHook;
+
+impl PlanHook for TenantHook {
+    fn rewrite(
+        &self,
+        context: &PlanContext,
+        expr: &mut RelationExpr,
+    ) -> Result<(), failure::Error> {
+        let mut result = Ok(());
+        expr.visit_mut(&mut |e| {
+            let id = match e {
+                RelationExpr::Get {
+                    id: Id::Global(id), ..
+                } => *id,
+                _ => return,
+            };
+            match context.name(id).map(|name| name.item.as_str()) {
+                Some("hook_secrets") => {
+                    result = Err(format_err!("{:?} of hook_secrets denied", context.kind))
+                }
+                Some("hook_tenants") => {
+                    let tenant =
+                        ScalarExpr::literal(Datum::Int32(1), ColumnType::new(ScalarType::Int32));
+                    let predicate = ScalarExpr::column(0).call_binary(tenant, BinaryFunc::Eq);
+                    *e = e.take_dangerous().filter(vec![predicate]);
+                }
+                _ => (),
+            }
+        });
+        result
+    }
+}
+
+#[test]
+fn test_plan_hooks() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    coord::register_plan_hook("tenants", TenantHook);
+
+    let (server, mut client) = util::start_server(util::Config::default())?;
+    client.batch_execute(
+        "CREATE TABLE hook_tenants (tenant int, v text); \
+         CREATE TABLE hook_secrets (v text); \
+         INSERT INTO hook_tenants VALUES (1, 'mine'), (2, 'theirs')",
+    )?;
+
+    // Queries and views only see the rows of tenant 1.
+    let query = |client: &mut postgres::Client, sql: &str| -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(client
+            .query(sql, &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+    };
+    assert_eq!(
+        query(&mut client, "SELECT v FROM hook_tenants")?,
+        vec!["mine"]
+    );
+    client.batch_execute("CREATE MATERIALIZED VIEW hook_view AS SELECT v FROM hook_tenants")?;
+    assert_eq!(query(&mut client, "SELECT v FROM hook_view")?, vec!["mine"]);
+
+    // Plans that read the secrets are rejected, whether they are queries or
+    // views.
+    let err = client
+        .query("SELECT * FROM hook_secrets", &[])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Peek of hook_secrets denied"), "{}", err);
+    let err = client
+        .batch_execute("CREATE VIEW hook_leak AS SELECT * FROM hook_secrets")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("View of hook_secrets denied"), "{}", err);
+
+    // Statements that read a collection directly may be rejected, but not
+    // restricted.
+    for (sql, kind) in &[
+        ("TAIL hook_secrets", "Tail"),
+        (
+            "CREATE SINK hook_sink FROM hook_secrets \
+             INTO REDIS 'redis://localhost:6379' FORMAT JSON",
+            "Sink",
+        ),
+        ("EXPORT TRACE hook_secrets TO '/dev/null'", "ExportTrace"),
+    ] {
+        let err = client.batch_execute(sql).unwrap_err().to_string();
+        assert!(
+            err.contains(&format!("{} of hook_secrets denied", kind)),
+            "{}",
+            err
+        );
+    }
+    let body = server.http_get("/changes/hook_secrets")?;
+    assert!(body.contains("Changes of hook_secrets denied"), "{}", body);
+    let err = client
+        .batch_execute("TAIL hook_tenants")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("plan hooks cannot restrict Tail statements"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_embedded_client() -> Result<(), Box<dyn Error>> {
+    ore::log::init();
+
+    let (server, mut pg_client) = util::start_server(util::Config::default())?;
+    pg_client.batch_execute("CREATE TABLE embedded (a int, b text)")?;
+
+    Runtime::new()?.block_on(async {
+        let mut client = server.client();
+        let inserted = client
+            .insert(
+                "embedded",
+                &[
+                    Row::pack(&[Datum::Int32(1), Datum::String("one")]),
+                    Row::pack(&[Datum::Int32(2), Datum::Null]),
+                ],
+            )
+            .await?;
+        assert_eq!(inserted, 2);
+
+        // Datums of the wrong type are rejected before the insert is planned.
+        let err = client
+            .insert("embedded", &[Row::pack(&[Datum::Int64(3), Datum::Null])])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("parameter $1"), "{}", err);
+
+        client
+            .create_view("embedded_view", "SELECT a + 1 AS a, b FROM embedded")
+            .await?;
+        let mut rows = client.peek("SELECT * FROM embedded_view").await?;
+        rows.sort_by(|a, b| a.unpack().cmp(&b.unpack()));
+        assert_eq!(
+            rows,
+            vec![
+                Row::pack(&[Datum::Int32(2), Datum::String("one")]),
+                Row::pack(&[Datum::Int32(3), Datum::Null]),
+            ]
+        );
+
+        let err = client.peek("SELECT * FROM nonexistent").await.unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+
+        // The tail reports changes made by other clients.
+        let mut rx = client.tail("embedded_view").await?;
+        let mut other_client = server.client();
+        other_client
+            .insert("embedded", &[Row::pack(&[Datum::Int32(3), Datum::Null])])
+            .await?;
+        other_client
+            .execute("DELETE FROM embedded WHERE a = 1", &[])
+            .await?;
+        let mut tailed = vec![];
+        while tailed.len() < 2 {
+            match rx.next().await {
+                Some(updates) => tailed.extend(updates?.into_iter().map(|u| (u.row, u.diff))),
+                None => panic!("tail ended unexpectedly"),
+            }
+        }
+        tailed.sort_by(|a, b| a.0.unpack().cmp(&b.0.unpack()));
+        assert_eq!(
+            tailed,
+            vec![
+                (Row::pack(&[Datum::Int32(2), Datum::String("one")]), -1),
+                (Row::pack(&[Datum::Int32(4), Datum::Null]), 1),
+            ]
+        );
+
+        // Names are quoted, rather than pasted into the SQL.
+        other_client
+            .execute("CREATE TABLE \"Mixed Case\" (a int)", &[])
+            .await?;
+        other_client
+            .insert("Mixed Case", &[Row::pack(&[Datum::Int32(1)])])
+            .await?;
+        let rows = other_client.peek("SELECT * FROM \"Mixed Case\"").await?;
+        assert_eq!(rows, vec![Row::pack(&[Datum::Int32(1)])]);
+        let err = other_client
+            .insert(
+                "embedded\" VALUES (5, NULL); DROP TABLE embedded; --",
+                &[Row::pack(&[Datum::Int32(1)])],
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("double quote"), "{}", err);
+
+        // A command that is interrupted takes the client's session with it,
+        // after which the client reports an error rather than panicking.
+        let res = other_client.execute("SELECT 1", &[]).now_or_never();
+        if res.is_none() {
+            let err = other_client.peek("SELECT 1").await.unwrap_err();
+            assert!(err.to_string().contains("session was lost"), "{}", err);
+        }
+
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
+/// Runs `query` until it returns at least `n` rows, as a source may take a
+/// moment to ingest its data, and returns the rows.
+fn query_rows(
+    client: &mut postgres::Client,
+    query: &str,
+    n: usize,
+) -> Result<Vec<postgres::Row>, Box<dyn Error>> {
+    util::poll(|| {
+        let rows = client.query(query, &[])?;
+        Ok(if rows.len() >= n { Some(rows) } else { None })
+    })
+}
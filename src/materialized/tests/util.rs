@@ -8,14 +8,23 @@
 // by the Apache License, Version 2.0.
 
 use std::error::Error;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
 use std::path::PathBuf;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct Config {
     data_directory: Option<PathBuf>,
     logging_granularity: Option<Duration>,
+    ingest_token: Option<String>,
+    threads: usize,
+    clusters: Vec<(String, usize)>,
+    peek_timeout: Option<Duration>,
+    arrangement_memory_limit: Option<usize>,
+    command_log: Option<PathBuf>,
+    table_timestamp_granularity: Option<Duration>,
 }
 
 impl Default for Config {
@@ -23,6 +32,13 @@ impl Default for Config {
         Config {
             data_directory: None,
             logging_granularity: Some(Duration::from_millis(10)),
+            ingest_token: None,
+            threads: 1,
+            clusters: vec![],
+            peek_timeout: None,
+            arrangement_memory_limit: None,
+            command_log: None,
+            table_timestamp_granularity: None,
         }
     }
 }
@@ -37,6 +53,41 @@ impl Config {
         self.data_directory = Some(data_directory.into());
         self
     }
+
+    pub fn ingest_token(mut self, ingest_token: impl Into<String>) -> Self {
+        self.ingest_token = Some(ingest_token.into());
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn cluster(mut self, name: impl Into<String>, threads: usize) -> Self {
+        self.clusters.push((name.into(), threads));
+        self
+    }
+
+    pub fn peek_timeout(mut self, timeout: Duration) -> Self {
+        self.peek_timeout = Some(timeout);
+        self
+    }
+
+    pub fn arrangement_memory_limit(mut self, limit: usize) -> Self {
+        self.arrangement_memory_limit = Some(limit);
+        self
+    }
+
+    pub fn command_log(mut self, command_log: impl Into<PathBuf>) -> Self {
+        self.command_log = Some(command_log.into());
+        self
+    }
+
+    pub fn table_timestamp_granularity(mut self, granularity: Duration) -> Self {
+        self.table_timestamp_granularity = Some(granularity);
+        self
+    }
 }
 
 pub fn start_server(config: Config) -> Result<(Server, postgres::Client), Box<dyn Error>> {
@@ -44,17 +95,45 @@ pub fn start_server(config: Config) -> Result<(Server, postgres::Client), Box<dy
         logging_granularity: config.logging_granularity,
         timestamp_frequency: None,
         max_increment_ts_size: 1000,
-        threads: 1,
+        catalog_compaction_interval: None,
+        peek_timeout: config.peek_timeout,
+        arrangement_memory_limit: config.arrangement_memory_limit,
+        consistency_check_interval: None,
+        table_timestamp_granularity: config.table_timestamp_granularity,
+        threads: config.threads,
+        clusters: config.clusters,
+        worker_cores: vec![],
+        reserved_cores: vec![],
         process: 0,
         addresses: vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)],
+        peek_results_addr: None,
         data_directory: config.data_directory,
         symbiosis_url: None,
         gather_metrics: false,
+        ingest_token: config.ingest_token,
+        command_log: config.command_log,
     })?);
     let client = server.connect()?;
     Ok((server, client))
 }
 
+/// Calls `f` until it returns `Some`, and returns that value, or fails if that
+/// takes longer than ten seconds.
+pub fn poll<T>(
+    mut f: impl FnMut() -> Result<Option<T>, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(t) = f()? {
+            return Ok(t);
+        }
+        if Instant::now() > deadline {
+            return Err("timed out waiting for condition".into());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
 pub struct Server(materialized::Server);
 
 impl Server {
@@ -78,6 +157,72 @@ impl Server {
         config
     }
 
+    /// Connects without a client library, issues `query`, and returns the
+    /// connection without reading any of the response, like a client that
+    /// has stopped reading.
+    pub fn stalled_query(&self, query: &str) -> Result<TcpStream, Box<dyn Error>> {
+        let mut stream = TcpStream::connect(self.0.local_addr())?;
+        let params = b"user\0root\0\0";
+        stream.write_all(&(8 + params.len() as i32).to_be_bytes())?;
+        stream.write_all(&196_608i32.to_be_bytes())?;
+        stream.write_all(params)?;
+        stream.write_all(b"Q")?;
+        stream.write_all(&(4 + query.len() as i32 + 1).to_be_bytes())?;
+        stream.write_all(query.as_bytes())?;
+        stream.write_all(b"\0")?;
+        Ok(stream)
+    }
+
+    /// Issues an HTTP GET request for `path` and returns the response body.
+    pub fn http_get(&self, path: &str) -> Result<String, Box<dyn Error>> {
+        let (_status, body) = self.http_request("GET", path, &[], "")?;
+        Ok(body)
+    }
+
+    /// Issues an HTTP POST request for `path` with the specified headers and
+    /// body, and returns the response status code and body.
+    pub fn http_post(
+        &self,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<(u16, String), Box<dyn Error>> {
+        self.http_request("POST", path, headers, body)
+    }
+
+    fn http_request(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<(u16, String), Box<dyn Error>> {
+        let mut stream = TcpStream::connect(self.0.local_addr())?;
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n",
+            method, path
+        )?;
+        for (name, value) in headers {
+            write!(stream, "{}: {}\r\n", name, value)?;
+        }
+        write!(stream, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let status = response
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok());
+        match (status, response.find("\r\n\r\n")) {
+            (Some(status), Some(i)) => Ok((status, response[i + 4..].to_owned())),
+            _ => Err(format!("malformed HTTP response: {}", response).into()),
+        }
+    }
+
+    pub fn client(&self) -> materialized::Client {
+        self.0.client()
+    }
+
     pub fn connect(&self) -> Result<postgres::Client, Box<dyn Error>> {
         Ok(self.pg_config().connect(postgres::NoTls)?)
     }
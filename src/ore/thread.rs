@@ -9,6 +9,8 @@
 
 //! Thread utilities.
 
+use std::fs;
+use std::io;
 use std::thread::JoinHandle;
 
 /// Wraps a [`JoinHandle`] so that the child thread is joined when the handle is
@@ -34,3 +36,116 @@ impl<T> JoinHandleExt<T> for JoinHandle<T> {
         JoinOnDropHandle(Some(self))
     }
 }
+
+/// The number of CPU cores that a thread can be pinned to. Cores are numbered
+/// from zero, so the highest core is one less than this.
+#[cfg(target_os = "linux")]
+pub const MAX_CORES: usize = libc::CPU_SETSIZE as usize;
+/// The number of CPU cores that a thread can be pinned to. Cores are numbered
+/// from zero, so the highest core is one less than this.
+#[cfg(not(target_os = "linux"))]
+pub const MAX_CORES: usize = 1024;
+
+/// Restricts the calling thread to run only on the specified CPU cores.
+///
+/// Thread affinity is only supported on Linux. On other platforms, this
+/// function always returns an error.
+pub fn pin_current_thread(cores: &[usize]) -> Result<(), io::Error> {
+    if let Some(core) = cores.iter().find(|core| **core >= MAX_CORES) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("core {} exceeds the maximum core {}", core, MAX_CORES - 1),
+        ));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // `cpu_set_t` is a plain bitmask, for which all zeros is a
+        // valid (empty) value, and `sched_setaffinity` only reads from it.
+        // Every core is less than `CPU_SETSIZE`, so it lies within the set.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            for core in cores {
+                libc::CPU_SET(*core, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = cores;
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "thread affinity is only supported on Linux",
+        ))
+    }
+}
+
+/// Parses a list of CPU cores in the format that Linux uses in sysfs, e.g.
+/// `0-3,8,10-11`. Cores numbered [`MAX_CORES`] or higher are rejected.
+pub fn parse_core_list(list: &str) -> Result<Vec<usize>, String> {
+    let mut cores = vec![];
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let mut bounds = range.splitn(2, '-');
+        let parse = |bound: Option<&str>| {
+            bound
+                .unwrap()
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid core range {:?}", range))
+        };
+        let start = parse(bounds.next())?;
+        let end = match bounds.next() {
+            Some(end) => parse(Some(end))?,
+            None => start,
+        };
+        if end < start {
+            return Err(format!("invalid core range {:?}", range));
+        }
+        if end >= MAX_CORES {
+            return Err(format!(
+                "core {} exceeds the maximum core {}",
+                end,
+                MAX_CORES - 1
+            ));
+        }
+        cores.extend(start..=end);
+    }
+    Ok(cores)
+}
+
+/// Returns the CPU cores that belong to the specified NUMA node.
+pub fn numa_node_cores(node: usize) -> Result<Vec<usize>, io::Error> {
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    let list = fs::read_to_string(&path)?;
+    parse_core_list(&list).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_core_list, pin_current_thread, MAX_CORES};
+
+    #[test]
+    fn test_parse_core_list() {
+        assert_eq!(
+            parse_core_list("0-3,8,10-11\n"),
+            Ok(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_core_list("5"), Ok(vec![5]));
+        assert_eq!(parse_core_list(""), Ok(vec![]));
+        assert!(parse_core_list("3-1").is_err());
+        assert!(parse_core_list("a-b").is_err());
+    }
+
+    #[test]
+    fn test_core_limit() {
+        let max = MAX_CORES - 1;
+        assert_eq!(parse_core_list(&max.to_string()), Ok(vec![max]));
+        assert!(parse_core_list(&MAX_CORES.to_string()).is_err());
+        assert!(parse_core_list(&format!("0-{}", MAX_CORES)).is_err());
+        assert!(parse_core_list(&format!("0-{}", usize::max_value())).is_err());
+        assert!(pin_current_thread(&[MAX_CORES]).is_err());
+    }
+}
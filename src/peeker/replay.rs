@@ -0,0 +1,258 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Replays a command log against a materialized instance.
+//!
+//! A command log, written by `materialized --command-log`, records every SQL
+//! statement that an instance executed, along with when it was executed and
+//! by which connection. Replaying the log against a fresh instance of another
+//! version runs the same workload, to compare the performance of the two
+//! versions or to reproduce a problem.
+//!
+//! Each connection in the log is replayed on its own connection, which
+//! executes the connection's statements in order. Statements are issued at
+//! the same offsets from the start of the replay as they were from the start
+//! of the log, divided by `--speed`; a connection whose statements take
+//! longer than they originally did falls behind, but never reorders them.
+//! With `--speed 0`, every statement is issued as soon as the previous
+//! statement of its connection completes.
+//!
+//! `TAIL` statements are skipped, as they do not complete on their own.
+//! Statements that fail are reported and counted, but do not stop the replay.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::process;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use env_logger::{Builder as LogBuilder, Env, Target};
+use failure::{bail, format_err};
+use log::{info, warn};
+use postgres::Client;
+
+/// A statement from the command log.
+#[derive(Debug)]
+struct Entry {
+    /// The time at which the statement was executed, in milliseconds since
+    /// the Unix epoch.
+    time_ms: u64,
+    /// The ID of the connection that executed the statement.
+    conn_id: u32,
+    sql: String,
+}
+
+/// The outcome of the statements that have been replayed.
+#[derive(Debug, Default)]
+struct Stats {
+    latencies: Vec<Duration>,
+    errors: usize,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ore::panic::set_abort_on_panic();
+
+    LogBuilder::from_env(Env::new().filter_or("MZ_LOG", "info"))
+        .target(Target::Stdout)
+        .init();
+
+    let args: Vec<_> = std::env::args().collect();
+
+    let mut opts = getopts::Options::new();
+    opts.optflag("h", "help", "show this usage information");
+    opts.optopt(
+        "",
+        "materialized-url",
+        "url of the materialized instance to replay against",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "speed",
+        "how many times faster than the original to replay the log, or 0 to \
+         replay it as fast as possible (default 1)",
+        "N",
+    );
+    let popts = opts.parse(&args[1..])?;
+    if popts.opt_present("h") || popts.free.len() != 1 {
+        print!("{}", opts.usage("usage: replay [options] COMMAND-LOG"));
+        process::exit(if popts.opt_present("h") { 0 } else { 1 });
+    }
+    let url = popts.opt_get_default(
+        "materialized-url",
+        "postgres://ignoreuser@localhost:6875/materialize".to_owned(),
+    )?;
+    let speed: f64 = popts.opt_get_default("speed", 1.0)?;
+    if !speed.is_finite() || speed < 0.0 {
+        bail!("invalid speed: {}", speed);
+    }
+
+    let entries = read_log(&popts.free[0])?;
+    info!("replaying {} statements at speed {}", entries.len(), speed);
+
+    let stats = Arc::new(Mutex::new(Stats::default()));
+    let mut conns = HashMap::new();
+    let mut threads = vec![];
+    let start = Instant::now();
+    let first_ms = entries.first().map(|e| e.time_ms).unwrap_or(0);
+    for entry in entries {
+        if is_tail(&entry.sql) {
+            warn!(
+                "skipping TAIL on connection {}: {}",
+                entry.conn_id, entry.sql
+            );
+            continue;
+        }
+        if speed > 0.0 {
+            let offset =
+                Duration::from_millis(entry.time_ms.saturating_sub(first_ms)).div_f64(speed);
+            let elapsed = start.elapsed();
+            if offset > elapsed {
+                thread::sleep(offset - elapsed);
+            }
+        }
+        if !conns.contains_key(&entry.conn_id) {
+            let (tx, rx) = mpsc::channel();
+            let url = url.clone();
+            let stats = stats.clone();
+            let conn_id = entry.conn_id;
+            threads.push(
+                thread::Builder::new()
+                    .name(format!("conn-{}", conn_id))
+                    .spawn(move || replay_conn(&url, conn_id, rx, &stats))?,
+            );
+            conns.insert(conn_id, tx);
+        }
+        // If the connection's thread has exited, it has already reported why.
+        let _ = conns[&entry.conn_id].send(entry.sql);
+    }
+    drop(conns);
+    for thread in threads {
+        if let Err(err) = thread.join().unwrap() {
+            warn!("{}", err);
+        }
+    }
+
+    let stats = stats.lock().unwrap();
+    report(&stats, start.elapsed());
+    if stats.errors > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Reads the statements of the command log at `path`.
+fn read_log(path: &str) -> Result<Vec<Entry>, failure::Error> {
+    let file = File::open(path).map_err(|e| format_err!("opening {}: {}", path, e))?;
+    let mut entries = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        entries.push(parse_entry(&line).map_err(|e| format_err!("{}:{}: {}", path, i + 1, e))?);
+    }
+    // Statements are written to the log in the order in which they are
+    // executed, but the clock may have stepped backwards in the meantime.
+    entries.sort_by_key(|e| e.time_ms);
+    Ok(entries)
+}
+
+fn parse_entry(line: &str) -> Result<Entry, failure::Error> {
+    let mut fields = line.splitn(3, '\t');
+    let (time_ms, conn_id, sql) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(time_ms), Some(conn_id), Some(sql)) => (time_ms, conn_id, sql),
+        _ => bail!("expected three tab-separated fields"),
+    };
+    Ok(Entry {
+        time_ms: time_ms
+            .parse()
+            .map_err(|e| format_err!("invalid time {:?}: {}", time_ms, e))?,
+        conn_id: conn_id
+            .parse()
+            .map_err(|e| format_err!("invalid connection ID {:?}: {}", conn_id, e))?,
+        sql: unescape(sql)?,
+    })
+}
+
+/// Reverses the escaping of backslashes, tabs, carriage returns, and newlines
+/// in the command log.
+fn unescape(s: &str) -> Result<String, failure::Error> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some(c) => bail!("invalid escape sequence \\{}", c),
+            None => bail!("unterminated escape sequence"),
+        }
+    }
+    Ok(out)
+}
+
+fn is_tail(sql: &str) -> bool {
+    sql.trim_start()
+        .get(..4)
+        .map(|s| s.eq_ignore_ascii_case("TAIL"))
+        .unwrap_or(false)
+}
+
+/// Executes the statements received on `rx` on a new connection, until the
+/// log is exhausted.
+fn replay_conn(
+    url: &str,
+    conn_id: u32,
+    rx: mpsc::Receiver<String>,
+    stats: &Mutex<Stats>,
+) -> Result<(), failure::Error> {
+    let mut client = Client::connect(url, postgres::NoTls)
+        .map_err(|e| format_err!("connection {} unable to connect: {}", conn_id, e))?;
+    for sql in rx {
+        let start = Instant::now();
+        let res = client.simple_query(&sql);
+        let latency = start.elapsed();
+        let mut stats = stats.lock().unwrap();
+        match res {
+            Ok(_) => stats.latencies.push(latency),
+            Err(err) => {
+                warn!("connection {}: {}: {}", conn_id, sql, err);
+                stats.errors += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn report(stats: &Stats, elapsed: Duration) {
+    let mut latencies = stats.latencies.clone();
+    latencies.sort();
+    info!(
+        "replayed {} statements in {:?}, of which {} failed",
+        latencies.len() + stats.errors,
+        elapsed,
+        stats.errors
+    );
+    if latencies.is_empty() {
+        return;
+    }
+    for &(name, quantile) in &[("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)] {
+        let i = ((latencies.len() as f64 * quantile).ceil() as usize).saturating_sub(1);
+        info!("{} latency: {:?}", name, latencies[i]);
+    }
+}
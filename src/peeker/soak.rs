@@ -0,0 +1,512 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A long-running test of materialized.
+//!
+//! The soak test continuously inserts rows into a table, creates and drops
+//! views over that table, and issues random peeks and a TAIL against it, for
+//! as long as it is asked to. Throughout, it checks invariants that should
+//! always hold:
+//!
+//!   * the frontiers of indexes never move backwards;
+//!   * every peek observes a prefix of the inserted rows, and never a
+//!     shorter prefix than an earlier peek;
+//!   * a view agrees with the table it is defined over, when both are read
+//!     at the same time;
+//!   * the TAIL reports each inserted row exactly once;
+//!   * the resident memory of materialized stays within a bound.
+//!
+//! Rows are inserted in batches of consecutive integers, starting at zero, by
+//! a single connection. A batch is inserted at a single timestamp, so at any
+//! time the table contains the integers from zero up to some bound.
+//!
+//! The test must be run against a materialized instance that does not yet
+//! contain a table named `soak_t`. It stops at the first violation, and exits
+//! with a non-zero status if there was one.
+
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Read, Write};
+use std::net::TcpStream;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use env_logger::{Builder as LogBuilder, Env, Target};
+use failure::{bail, format_err};
+use log::{error, info};
+use postgres::Client;
+
+/// The number of rows inserted before the test begins.
+const INITIAL_ROWS: u64 = 100;
+
+/// The largest number of rows inserted at once.
+const MAX_BATCH: u64 = 100;
+
+/// How long the ingest connection waits between inserts.
+const INGEST_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often frontiers and memory usage are checked.
+const MONITOR_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long peeks may observe the same number of rows while rows are being
+/// inserted before the test concludes that the table has stopped advancing.
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+struct Config {
+    materialized_url: String,
+    duration: Duration,
+    seed: u64,
+    /// The largest resident memory of materialized, in bytes, that is not a
+    /// violation.
+    max_memory: Option<u64>,
+}
+
+/// A thread of the test, which runs until the test stops.
+type Task = fn(&Config, &State, &mut Rng) -> Result<(), failure::Error>;
+
+/// The state shared by the threads of the test.
+#[derive(Debug, Default)]
+struct State {
+    /// The number of rows whose insertion has begun. Every row that a query
+    /// observes must be below this bound.
+    attempted: AtomicU64,
+    /// Set when the test should stop, because it has run for long enough or
+    /// because an invariant has been violated.
+    stop: AtomicBool,
+    violated: AtomicBool,
+}
+
+impl State {
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    fn violation(&self, message: String) {
+        error!("invariant violated: {}", message);
+        self.violated.store(true, Ordering::SeqCst);
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ore::panic::set_abort_on_panic();
+
+    LogBuilder::from_env(Env::new().filter_or("MZ_LOG", "info"))
+        .target(Target::Stdout)
+        .init();
+
+    let args: Vec<_> = std::env::args().collect();
+
+    let mut opts = getopts::Options::new();
+    opts.optflag("h", "help", "show this usage information");
+    opts.optopt(
+        "",
+        "materialized-url",
+        "url of the materialized instance to test",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "duration",
+        "how long to run the test, e.g. 30s, 15m, or 4h (default 1h)",
+        "DURATION",
+    );
+    opts.optopt(
+        "",
+        "seed",
+        "seed for the random choices of the test (default random)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "max-memory-mb",
+        "fail if materialized uses more than this much resident memory (default off)",
+        "MB",
+    );
+    let popts = opts.parse(&args[1..])?;
+    if popts.opt_present("h") {
+        print!("{}", opts.usage("usage: soak [options]"));
+        return Ok(());
+    }
+    let config = Config {
+        materialized_url: popts.opt_get_default(
+            "materialized-url",
+            "postgres://ignoreuser@localhost:6875/materialize".to_owned(),
+        )?,
+        duration: parse_duration(&popts.opt_get_default("duration", "1h".to_owned())?)?,
+        seed: match popts.opt_get("seed")? {
+            Some(seed) => seed,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+        },
+        max_memory: popts
+            .opt_get::<u64>("max-memory-mb")?
+            .map(|mb| mb * 1024 * 1024),
+    };
+    info!("starting soak test: {:?}", config);
+
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    client.batch_execute("CREATE TABLE soak_t (a bigint)")?;
+    insert_batch(&mut client, 0, INITIAL_ROWS)?;
+    let state = Arc::new(State {
+        attempted: AtomicU64::new(INITIAL_ROWS),
+        ..Default::default()
+    });
+
+    let tasks: &[(&str, Task)] = &[
+        ("ingest", ingest),
+        ("ddl", ddl),
+        ("peek", peek),
+        ("tail", tail),
+        ("monitor", monitor),
+    ];
+    for (i, &(name, task)) in tasks.iter().enumerate() {
+        let config = config.clone();
+        let state = state.clone();
+        let mut rng = Rng::new(config.seed.wrapping_add(i as u64));
+        thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                if let Err(err) = task(&config, &state, &mut rng) {
+                    state.violation(format!("{} failed: {}", name, err));
+                }
+            })?;
+    }
+
+    let start = Instant::now();
+    while !state.stopped() && start.elapsed() < config.duration {
+        thread::sleep(min(Duration::from_secs(1), config.duration));
+    }
+    state.stop.store(true, Ordering::SeqCst);
+
+    // The threads are not joined, as the TAIL may be blocked waiting for
+    // data that will never arrive now that ingest has stopped.
+    if state.violated.load(Ordering::SeqCst) {
+        error!("soak test failed after {:?}", start.elapsed());
+        process::exit(1);
+    }
+    info!(
+        "soak test passed after inserting {} rows",
+        state.attempted.load(Ordering::SeqCst)
+    );
+    Ok(())
+}
+
+/// Inserts the rows `start..end` into the table.
+fn insert_batch(client: &mut Client, start: u64, end: u64) -> Result<(), failure::Error> {
+    let values: Vec<_> = (start..end).map(|a| format!("({})", a)).collect();
+    client.batch_execute(&format!("INSERT INTO soak_t VALUES {}", values.join(", ")))?;
+    Ok(())
+}
+
+/// Inserts batches of rows into the table until the test stops.
+fn ingest(config: &Config, state: &State, rng: &mut Rng) -> Result<(), failure::Error> {
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    while !state.stopped() {
+        let start = state.attempted.load(Ordering::SeqCst);
+        let end = start + 1 + rng.below(MAX_BATCH);
+        state.attempted.store(end, Ordering::SeqCst);
+        insert_batch(&mut client, start, end)?;
+        thread::sleep(INGEST_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Repeatedly creates a materialized view over the table, checks that it
+/// agrees with the table, and drops it.
+fn ddl(config: &Config, state: &State, rng: &mut Rng) -> Result<(), failure::Error> {
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    let mut i = 0;
+    while !state.stopped() {
+        let name = format!("soak_ddl_{}", i);
+        client.batch_execute(&format!(
+            "CREATE MATERIALIZED VIEW {} AS SELECT count(*) AS n, max(a) AS m FROM soak_t",
+            name
+        ))?;
+        for _ in 0..rng.below(10) {
+            let row = client.query_one(
+                &*format!(
+                    "SELECT v.n, v.m, t.n, t.m FROM {} v, \
+                     (SELECT count(*) AS n, max(a) AS m FROM soak_t) t",
+                    name
+                ),
+                &[],
+            )?;
+            let view: (i64, Option<i64>) = (row.get(0), row.get(1));
+            let table: (i64, Option<i64>) = (row.get(2), row.get(3));
+            if view != table {
+                state.violation(format!(
+                    "view {} reports (count, max) = {:?}, but the table contains {:?}",
+                    name, view, table
+                ));
+            }
+        }
+        client.batch_execute(&format!("DROP VIEW {}", name))?;
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Issues random peeks against the table, and checks that each observes a
+/// prefix of the inserted rows that is no shorter than the last.
+fn peek(config: &Config, state: &State, rng: &mut Rng) -> Result<(), failure::Error> {
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    // The number of rows that the last peek of the whole table observed.
+    let mut last_count = 0;
+    let mut last_advance = Instant::now();
+    while !state.stopped() {
+        let x = rng.below(state.attempted.load(Ordering::SeqCst)) as i64;
+        match rng.below(3) {
+            0 => {
+                let row = client.query_one("SELECT count(*), max(a) FROM soak_t", &[])?;
+                let (count, max): (i64, Option<i64>) = (row.get(0), row.get(1));
+                let attempted = state.attempted.load(Ordering::SeqCst) as i64;
+                if max.map(|m| m + 1).unwrap_or(0) != count {
+                    state.violation(format!(
+                        "table holds {} rows, but its largest row is {:?}",
+                        count, max
+                    ));
+                } else if count < last_count {
+                    state.violation(format!(
+                        "table went from {} rows to {} rows",
+                        last_count, count
+                    ));
+                } else if count > attempted {
+                    state.violation(format!(
+                        "table holds {} rows, but only {} have been inserted",
+                        count, attempted
+                    ));
+                }
+                if count > last_count {
+                    last_count = count;
+                    last_advance = Instant::now();
+                } else if last_advance.elapsed() > STALL_TIMEOUT {
+                    state.violation(format!(
+                        "table has held {} rows for {:?}",
+                        count,
+                        last_advance.elapsed()
+                    ));
+                }
+            }
+            1 => {
+                let rows = client.query("SELECT a FROM soak_t WHERE a = $1", &[&x])?;
+                if rows.len() > 1 || (x < last_count && rows.is_empty()) {
+                    state.violation(format!(
+                        "row {} appears {} times, but the table held {} rows",
+                        x,
+                        rows.len(),
+                        last_count
+                    ));
+                }
+            }
+            _ => {
+                let row = client.query_one("SELECT count(*) FROM soak_t WHERE a < $1", &[&x])?;
+                let count: i64 = row.get(0);
+                if count > x || (x <= last_count && count != x) {
+                    state.violation(format!(
+                        "table holds {} rows below {}, but held {} rows",
+                        count, x, last_count
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tails the table, and checks that each row is reported exactly once.
+fn tail(config: &Config, state: &State, _: &mut Rng) -> Result<(), failure::Error> {
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    let reader = client.copy_out("TAIL soak_t")?;
+    // The TAIL begins at an arbitrary time, and reports only the rows
+    // inserted afterwards. The rows from `start` up to `low` have been
+    // reported, as have those in `seen`, which holds the rows above `low`
+    // and the few from the first batch that may be below `start`.
+    let mut start = None;
+    let mut low = 0;
+    let mut seen = HashSet::new();
+    for line in reader.split(b'\n') {
+        if state.stopped() {
+            break;
+        }
+        let line = String::from_utf8(line?)?;
+        let (a, diff) = parse_tail_update(&line)?;
+        let attempted = state.attempted.load(Ordering::SeqCst);
+        let start = *start.get_or_insert_with(|| {
+            low = a;
+            a
+        });
+        if diff != 1 {
+            state.violation(format!("TAIL reported diff {} for row {}", diff, a));
+        } else if a >= attempted {
+            state.violation(format!(
+                "TAIL reported row {}, but only {} have been inserted",
+                a, attempted
+            ));
+        } else if (start <= a && a < low) || !seen.insert(a) {
+            state.violation(format!("TAIL reported row {} more than once", a));
+        }
+        while seen.remove(&low) {
+            low += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a line of TAIL output of the form `<a>\tDiff: <diff> at <time>`.
+fn parse_tail_update(line: &str) -> Result<(u64, i64), failure::Error> {
+    let mut parts = line.splitn(2, '\t');
+    let a = parts.next().unwrap_or("").parse()?;
+    let diff = match parts.next() {
+        Some(rest) if rest.starts_with("Diff: ") => rest["Diff: ".len()..]
+            .split(' ')
+            .next()
+            .unwrap_or("")
+            .parse()?,
+        _ => bail!("malformed TAIL update: {:?}", line),
+    };
+    Ok((a, diff))
+}
+
+/// Checks that the frontiers of indexes never move backwards, and that the
+/// resident memory of materialized stays within bounds.
+fn monitor(config: &Config, state: &State, _: &mut Rng) -> Result<(), failure::Error> {
+    let mut client = Client::connect(&config.materialized_url, postgres::NoTls)?;
+    let metrics_addr = metrics_addr(&config.materialized_url)
+        .ok_or_else(|| format_err!("unable to determine the address of materialized"))?;
+    let mut frontiers = HashMap::new();
+    while !state.stopped() {
+        let rows = client.query(
+            "SELECT global_id, min(time) FROM mz_catalog.mz_materialization_frontiers \
+             GROUP BY global_id",
+            &[],
+        )?;
+        let mut new_frontiers = HashMap::new();
+        for row in rows {
+            let (id, time): (String, i64) = (row.get(0), row.get(1));
+            if let Some(old_time) = frontiers.get(&id) {
+                if time < *old_time {
+                    state.violation(format!(
+                        "frontier of {} went from {} to {}",
+                        id, old_time, time
+                    ));
+                }
+            }
+            new_frontiers.insert(id, time);
+        }
+        // Forget the indexes that have been dropped.
+        frontiers = new_frontiers;
+
+        let metrics = fetch_metrics(&metrics_addr)?;
+        let memory = metric(&metrics, "process_resident_memory_bytes");
+        let records = metric(&metrics, "mz_arrangement_records");
+        info!(
+            "{} rows inserted; resident memory {:?} bytes; {:?} arrangement records",
+            state.attempted.load(Ordering::SeqCst),
+            memory,
+            records
+        );
+        if let (Some(memory), Some(max_memory)) = (memory, config.max_memory) {
+            if memory > max_memory as f64 {
+                state.violation(format!(
+                    "resident memory is {} bytes, more than the limit of {}",
+                    memory, max_memory
+                ));
+            }
+        }
+        thread::sleep(MONITOR_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Returns the address at which materialized serves HTTP, which is the
+/// address at which it serves SQL, given a URL like
+/// `postgres://user@host:port/database`.
+fn metrics_addr(url: &str) -> Option<String> {
+    let authority = url.splitn(2, "://").nth(1)?.split('/').next()?;
+    let host = authority.rsplit('@').next()?;
+    if host.is_empty() {
+        None
+    } else if host.contains(':') {
+        Some(host.to_owned())
+    } else {
+        Some(format!("{}:6875", host))
+    }
+}
+
+/// Fetches the Prometheus metrics that materialized exports over HTTP.
+fn fetch_metrics(addr: &str) -> Result<String, failure::Error> {
+    let mut stream = TcpStream::connect(addr)?;
+    write!(
+        stream,
+        "GET /metrics HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        addr
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+/// Returns the sum of the samples of the named metric, or `None` if there
+/// are none.
+fn metric(metrics: &str, name: &str) -> Option<f64> {
+    let mut sum = None;
+    for line in metrics.lines() {
+        let mut parts = line.split_whitespace();
+        let sample = parts.next().unwrap_or("");
+        if sample == name || sample.starts_with(&format!("{}{{", name)) {
+            if let Some(Ok(value)) = parts.next().map(|v| v.parse::<f64>()) {
+                sum = Some(sum.unwrap_or(0.0) + value);
+            }
+        }
+    }
+    sum
+}
+
+/// Parses a duration like `30s`, `15m`, or `4h`.
+fn parse_duration(s: &str) -> Result<Duration, failure::Error> {
+    let (n, unit) = s.split_at(s.len() - s.trim_start_matches(char::is_numeric).len());
+    let n: u64 = n.parse()?;
+    match unit {
+        "s" => Ok(Duration::from_secs(n)),
+        "m" => Ok(Duration::from_secs(n * 60)),
+        "h" => Ok(Duration::from_secs(n * 60 * 60)),
+        _ => bail!("invalid duration {:?}: expected e.g. 30s, 15m, or 4h", s),
+    }
+}
+
+/// A small, seedable pseudorandom number generator (xorshift64*), so that a
+/// failing run can be replayed with `--seed`.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // The state must not be zero.
+        Rng(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number in `0..n`, or zero if `n` is zero.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next() % n
+        }
+    }
+}
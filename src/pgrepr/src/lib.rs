@@ -24,6 +24,8 @@ mod value;
 
 pub use format::Format;
 pub use types::Type;
+pub use value::inet::Inet;
 pub use value::interval::Interval;
 pub use value::numeric::Numeric;
+pub use value::point::Point;
 pub use value::{null_datum, values_from_row, Value};
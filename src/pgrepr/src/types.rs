@@ -16,12 +16,16 @@ pub enum Type {
     Bool,
     /// A byte array, i.e., a variable-length binary string.
     Bytea,
+    /// An IPv4 or IPv6 network.
+    Cidr,
     /// A date.
     Date,
     /// A 4-byte floating point number.
     Float4,
     /// An 8-byte floating point number.
     Float8,
+    /// An IPv4 or IPv6 host address, optionally with a network prefix.
+    Inet,
     /// A 4-byte signed integer.
     Int4,
     /// An 8-byte signed integer.
@@ -32,6 +36,8 @@ pub enum Type {
     Jsonb,
     /// An arbitrary precision number.
     Numeric,
+    /// A point on a plane.
+    Point,
     /// A variable-length string.
     Text,
     /// A time of day without a day.
@@ -49,14 +55,17 @@ impl Type {
         match self {
             Type::Bool => &postgres_types::Type::BOOL,
             Type::Bytea => &postgres_types::Type::BYTEA,
+            Type::Cidr => &postgres_types::Type::CIDR,
             Type::Date => &postgres_types::Type::DATE,
             Type::Float4 => &postgres_types::Type::FLOAT4,
             Type::Float8 => &postgres_types::Type::FLOAT8,
+            Type::Inet => &postgres_types::Type::INET,
             Type::Int4 => &postgres_types::Type::INT4,
             Type::Int8 => &postgres_types::Type::INT8,
             Type::Interval => &postgres_types::Type::INTERVAL,
             Type::Jsonb => &postgres_types::Type::JSONB,
             Type::Numeric => &postgres_types::Type::NUMERIC,
+            Type::Point => &postgres_types::Type::POINT,
             Type::Text => &postgres_types::Type::TEXT,
             Type::Time => &postgres_types::Type::TIME,
             Type::Timestamp => &postgres_types::Type::TIMESTAMP,
@@ -83,14 +92,17 @@ impl Type {
         match self {
             Type::Bool => 1,
             Type::Bytea => -1,
+            Type::Cidr => -1,
             Type::Date => 4,
             Type::Float4 => 4,
             Type::Float8 => 8,
+            Type::Inet => -1,
             Type::Int4 => 4,
             Type::Int8 => 8,
             Type::Interval => 16,
             Type::Jsonb => -1,
             Type::Numeric => -1,
+            Type::Point => 16,
             Type::Text => -1,
             Type::Time => 4,
             Type::Timestamp => 8,
@@ -118,6 +130,14 @@ impl From<ScalarType> for Type {
             ScalarType::Bytes => Type::Bytea,
             ScalarType::String => Type::Text,
             ScalarType::Jsonb => Type::Jsonb,
+            ScalarType::Point => Type::Point,
+            ScalarType::Inet => Type::Inet,
+            ScalarType::Cidr => Type::Cidr,
+            // Enum values are rendered as text before they reach clients, so
+            // this only describes the underlying representation.
+            ScalarType::Enum(_) => Type::Int4,
+            // Lists never reach clients.
+            ScalarType::List => Type::Unknown,
         }
     }
 }
@@ -18,10 +18,12 @@ use repr::decimal::MAX_DECIMAL_PRECISION;
 use repr::jsonb::Jsonb;
 use repr::{strconv, ColumnType, Datum, RelationType, Row, RowArena, ScalarType};
 
-use crate::{Format, Interval, Numeric, Type};
+use crate::{Format, Inet, Interval, Numeric, Point, Type};
 
+pub mod inet;
 pub mod interval;
 pub mod numeric;
+pub mod point;
 
 /// A PostgreSQL datum.
 #[derive(Debug)]
@@ -54,6 +56,12 @@ pub enum Value {
     Numeric(Numeric),
     /// A binary JSON blob.
     Jsonb(Jsonb),
+    /// A point on a plane.
+    Point(Point),
+    /// An IPv4 or IPv6 host address, optionally with a network prefix.
+    Inet(Inet),
+    /// An IPv4 or IPv6 network.
+    Cidr(Inet),
 }
 
 impl Value {
@@ -67,6 +75,7 @@ impl Value {
             (Datum::True, ScalarType::Bool) => Some(Value::Bool(true)),
             (Datum::False, ScalarType::Bool) => Some(Value::Bool(false)),
             (Datum::Int32(i), ScalarType::Int32) => Some(Value::Int4(i)),
+            (Datum::Int32(i), ScalarType::Enum(_)) => Some(Value::Int4(i)),
             (Datum::Int64(i), ScalarType::Int64) => Some(Value::Int8(i)),
             (Datum::Float32(f), ScalarType::Float32) => Some(Value::Float4(*f)),
             (Datum::Float64(f), ScalarType::Float64) => Some(Value::Float8(*f)),
@@ -80,6 +89,9 @@ impl Value {
             }
             (Datum::Bytes(b), ScalarType::Bytes) => Some(Value::Bytea(b.to_vec())),
             (Datum::String(s), ScalarType::String) => Some(Value::Text(s.to_owned())),
+            (Datum::Point(p), ScalarType::Point) => Some(Value::Point(Point(p))),
+            (Datum::Inet(inet), ScalarType::Inet) => Some(Value::Inet(Inet(inet))),
+            (Datum::Inet(inet), ScalarType::Cidr) => Some(Value::Cidr(Inet(inet))),
             (_, ScalarType::Jsonb) => Some(Value::Jsonb(Jsonb::from_datum(datum))),
             _ => panic!("can't serialize {}::{}", datum, typ),
         }
@@ -115,6 +127,9 @@ impl Value {
                 buf.push_row(jsonb.into_row()).unpack_first(),
                 ScalarType::Jsonb,
             ),
+            Value::Point(p) => (Datum::Point(p.0), ScalarType::Point),
+            Value::Inet(inet) => (Datum::Inet(inet.0), ScalarType::Inet),
+            Value::Cidr(inet) => (Datum::Inet(inet.0), ScalarType::Cidr),
         }
     }
 
@@ -144,9 +159,19 @@ impl Value {
             Value::Numeric(n) => strconv::format_decimal(buf, &n.0),
             Value::Text(s) => buf.put(s.as_bytes()),
             Value::Jsonb(js) => strconv::format_jsonb(buf, js),
+            Value::Point(p) => strconv::format_point(buf, p.0),
+            Value::Inet(inet) => strconv::format_inet(buf, inet.0),
+            Value::Cidr(inet) => strconv::format_cidr(buf, inet.0),
         }
     }
 
+    /// Returns the [text encoding](Format::Text) of this value.
+    pub fn to_text(&self) -> String {
+        let mut buf = BytesMut::new();
+        self.encode_text(&mut buf);
+        String::from_utf8(buf.to_vec()).expect("text encoding is valid UTF-8")
+    }
+
     /// Serializes this value to `buf` using the [binary encoding
     /// format](Format::Binary).
     pub fn encode_binary(&self, buf: &mut BytesMut) {
@@ -165,6 +190,9 @@ impl Value {
             Value::Numeric(n) => n.to_sql(&PgType::NUMERIC, buf),
             Value::Text(s) => s.to_sql(&PgType::TEXT, buf),
             Value::Jsonb(jsonb) => jsonb.as_serde_json().to_sql(&PgType::JSONB, buf),
+            Value::Point(p) => p.to_sql(&PgType::POINT, buf),
+            Value::Inet(inet) => inet.to_sql(&PgType::INET, buf),
+            Value::Cidr(inet) => inet.to_sql(&PgType::CIDR, buf),
         }
         .expect("encode_binary should never trigger a to_sql failure");
         match is_null {
@@ -205,6 +233,9 @@ impl Value {
             Type::Text => Value::Text(raw.to_owned()),
             Type::Numeric => Value::Numeric(Numeric(strconv::parse_decimal(raw)?)),
             Type::Jsonb => Value::Jsonb(strconv::parse_jsonb(raw)?),
+            Type::Point => Value::Point(Point(strconv::parse_point(raw)?)),
+            Type::Inet => Value::Inet(Inet(strconv::parse_inet(raw)?)),
+            Type::Cidr => Value::Cidr(Inet(strconv::parse_cidr(raw)?)),
             Type::Unknown => panic!("cannot decode unknown type"),
         })
     }
@@ -215,9 +246,17 @@ impl Value {
         match ty {
             Type::Bool => bool::from_sql(ty.inner(), raw).map(Value::Bool),
             Type::Bytea => Vec::<u8>::from_sql(ty.inner(), raw).map(Value::Bytea),
+            Type::Cidr => {
+                let inet = Inet::from_sql(ty.inner(), raw)?;
+                if inet.0.network() != inet.0 {
+                    return Err("invalid cidr value: value has bits set to right of mask".into());
+                }
+                Ok(Value::Cidr(inet))
+            }
             Type::Date => chrono::NaiveDate::from_sql(ty.inner(), raw).map(Value::Date),
             Type::Float4 => f32::from_sql(ty.inner(), raw).map(Value::Float4),
             Type::Float8 => f64::from_sql(ty.inner(), raw).map(Value::Float8),
+            Type::Inet => Inet::from_sql(ty.inner(), raw).map(Value::Inet),
             Type::Int4 => i32::from_sql(ty.inner(), raw).map(Value::Int4),
             Type::Int8 => i64::from_sql(ty.inner(), raw).map(Value::Int8),
             Type::Interval => Interval::from_sql(ty.inner(), raw).map(Value::Interval),
@@ -226,6 +265,7 @@ impl Value {
                 Ok(Value::Jsonb(Jsonb::new(val)?))
             }
             Type::Numeric => Numeric::from_sql(ty.inner(), raw).map(Value::Numeric),
+            Type::Point => Point::from_sql(ty.inner(), raw).map(Value::Point),
             Type::Text => String::from_sql(ty.inner(), raw).map(Value::Text),
             Type::Time => NaiveTime::from_sql(ty.inner(), raw).map(Value::Time),
             Type::Timestamp => NaiveDateTime::from_sql(ty.inner(), raw).map(Value::Timestamp),
@@ -240,14 +280,17 @@ pub fn null_datum(ty: Type) -> (Datum<'static>, ScalarType) {
     let ty = match ty {
         Type::Bool => ScalarType::Bool,
         Type::Bytea => ScalarType::Bytes,
+        Type::Cidr => ScalarType::Cidr,
         Type::Date => ScalarType::Date,
         Type::Float4 => ScalarType::Float32,
         Type::Float8 => ScalarType::Float64,
+        Type::Inet => ScalarType::Inet,
         Type::Int4 => ScalarType::Int32,
         Type::Int8 => ScalarType::Int64,
         Type::Interval => ScalarType::Interval,
         Type::Jsonb => ScalarType::Jsonb,
         Type::Numeric => ScalarType::Decimal(MAX_DECIMAL_PRECISION, 0),
+        Type::Point => ScalarType::Point,
         Type::Text => ScalarType::String,
         Type::Time => ScalarType::Time,
         Type::Timestamp => ScalarType::Timestamp,
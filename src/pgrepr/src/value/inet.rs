@@ -0,0 +1,106 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use bytes::{BufMut, BytesMut};
+use postgres_types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+
+// The address families that Postgres uses in the binary representation of
+// `inet` and `cidr` values. See `PGSQL_AF_INET` in Postgres's
+// src/include/utils/inet.h.
+const PGSQL_AF_INET: u8 = 2;
+const PGSQL_AF_INET6: u8 = 3;
+
+/// A wrapper for [`repr::Inet`] that can be serialized and deserialized
+/// to the PostgreSQL binary format for the `inet` and `cidr` types.
+#[derive(Debug, Clone)]
+pub struct Inet(pub repr::Inet);
+
+impl fmt::Display for Inet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ToSql for Inet {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + 'static + Send + Sync>> {
+        // Postgres represents an inet or cidr as the address family, the
+        // prefix length, a flag indicating whether the value is a cidr, the
+        // number of bytes in the address, and finally the address itself. See
+        // `network_send` in Postgres's src/backend/utils/adt/network.c.
+        let is_cidr = *ty == Type::CIDR;
+        match self.0.addr {
+            IpAddr::V4(addr) => {
+                out.put_u8(PGSQL_AF_INET);
+                out.put_u8(self.0.prefix_len);
+                out.put_u8(is_cidr as u8);
+                out.put_u8(4);
+                out.put_slice(&addr.octets());
+            }
+            IpAddr::V6(addr) => {
+                out.put_u8(PGSQL_AF_INET6);
+                out.put_u8(self.0.prefix_len);
+                out.put_u8(is_cidr as u8);
+                out.put_u8(16);
+                out.put_slice(&addr.octets());
+            }
+        }
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::INET | Type::CIDR => true,
+            _ => false,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Inet {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Inet, Box<dyn Error + Sync + Send>> {
+        if raw.len() < 4 {
+            return Err("invalid inet message length".into());
+        }
+        let (family, prefix_len, len, addr) = (raw[0], raw[1], raw[3], &raw[4..]);
+        if addr.len() != usize::from(len) {
+            return Err("invalid inet message length".into());
+        }
+        let addr = match (family, addr.len()) {
+            (PGSQL_AF_INET, 4) => {
+                let mut octets = [0; 4];
+                octets.copy_from_slice(addr);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            (PGSQL_AF_INET6, 16) => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(addr);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return Err("invalid address family in inet".into()),
+        };
+        let inet = repr::Inet::new(addr, prefix_len).map_err(|e| e.to_string())?;
+        Ok(Inet(inet))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::INET | Type::CIDR => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright Materialize, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::error::Error;
+use std::fmt;
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use bytes::{BufMut, BytesMut};
+use postgres_types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+
+/// A wrapper for [`repr::Point`] that can be serialized and deserialized
+/// to the PostgreSQL binary format.
+#[derive(Debug, Clone)]
+pub struct Point(pub repr::Point);
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ToSql for Point {
+    fn to_sql(
+        &self,
+        _: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + 'static + Send + Sync>> {
+        // Postgres represents points as two 8-byte floating point numbers, the
+        // x coordinate followed by the y coordinate. See `point_send` in
+        // Postgres's src/backend/utils/adt/geo_ops.c.
+        out.put_f64(self.0.x.into_inner());
+        out.put_f64(self.0.y.into_inner());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::POINT => true,
+            _ => false,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Point {
+    fn from_sql(_: &Type, mut raw: &'a [u8]) -> Result<Point, Box<dyn Error + Sync + Send>> {
+        let x = raw.read_f64::<NetworkEndian>()?;
+        let y = raw.read_f64::<NetworkEndian>()?;
+        Ok(Point(repr::Point::new(x, y)))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::POINT => true,
+            _ => false,
+        }
+    }
+}
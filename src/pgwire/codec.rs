@@ -86,6 +86,7 @@ impl Codec {
         message: String,
         detail: Option<String>,
         hint: Option<String>,
+        constraint: Option<String>,
     ) {
         dst.put_u8(b'S');
         dst.put_string(severity);
@@ -101,6 +102,10 @@ impl Codec {
             dst.put_u8(b'H');
             dst.put_string(hint);
         }
+        if let Some(constraint) = &constraint {
+            dst.put_u8(b'n');
+            dst.put_string(constraint);
+        }
         dst.put_u8(b'\0');
     }
 }
@@ -138,6 +143,7 @@ impl Encoder for Codec {
             BackendMessage::ParameterStatus(_, _) => b'S',
             BackendMessage::PortalSuspended => b's',
             BackendMessage::BackendKeyData { .. } => b'K',
+            BackendMessage::NotificationResponse { .. } => b'A',
             BackendMessage::ParameterDescription(_) => b't',
             BackendMessage::ParseComplete => b'1',
             BackendMessage::BindComplete => b'2',
@@ -229,6 +235,15 @@ impl Encoder for Codec {
                 dst.put_u32(conn_id);
                 dst.put_u32(secret_key);
             }
+            BackendMessage::NotificationResponse {
+                conn_id,
+                channel,
+                payload,
+            } => {
+                dst.put_u32(conn_id);
+                dst.put_string(&channel);
+                dst.put_string(&payload);
+            }
             BackendMessage::ParameterDescription(params) => {
                 dst.put_u16(params.len() as u16);
                 for param in params {
@@ -240,6 +255,7 @@ impl Encoder for Codec {
                 code,
                 message,
                 detail,
+                constraint,
             } => Self::encode_error_notice_response(
                 dst,
                 severity.string(),
@@ -247,6 +263,7 @@ impl Encoder for Codec {
                 message,
                 detail,
                 None,
+                constraint,
             ),
             BackendMessage::NoticeResponse {
                 severity,
@@ -261,6 +278,7 @@ impl Encoder for Codec {
                 message,
                 detail,
                 hint,
+                None,
             ),
         }
 
@@ -257,6 +257,11 @@ pub enum BackendMessage {
         conn_id: u32,
         secret_key: u32,
     },
+    NotificationResponse {
+        conn_id: u32,
+        channel: String,
+        payload: String,
+    },
     ParameterDescription(Vec<pgrepr::Type>),
     PortalSuspended,
     NoData,
@@ -275,6 +280,8 @@ pub enum BackendMessage {
         code: &'static str,
         message: String,
         detail: Option<String>,
+        /// The name of the constraint that the error reports a violation of.
+        constraint: Option<String>,
     },
     CopyOutResponse {
         overall_format: pgrepr::Format,
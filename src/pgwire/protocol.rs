@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cmp;
+use std::collections::HashMap;
 use std::iter;
 use std::mem;
 use std::sync::Arc;
@@ -14,6 +16,7 @@ use std::time::Instant;
 
 use byteorder::{ByteOrder, NetworkEndian};
 use failure::bail;
+use futures::future::{self, Either};
 use futures::sink::{self, SinkExt};
 use futures::stream::{StreamExt, TryStreamExt};
 use itertools::izip;
@@ -24,8 +27,8 @@ use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::time::{self, Duration};
 use tokio_util::codec::Framed;
 
-use coord::{ExecuteResponse, StartupMessage};
-use dataflow_types::{PeekResponse, Update};
+use coord::{ExecuteResponse, Notification, PeekStream, StartupMessage};
+use dataflow_types::{PeekMessage, PeekMetadata, PeekResponse, Update};
 use ore::future::OreSinkExt;
 use repr::{Datum, RelationDesc, Row, RowArena};
 use sql::Session;
@@ -69,6 +72,15 @@ lazy_static! {
     .unwrap();
 }
 
+/// The number of batches of updates that a TAIL buffers for a client that reads
+/// them more slowly than the dataflow produces them.
+const TAIL_BUFFER: usize = 1024;
+
+/// How long a client may leave a TAIL buffer full before the client is deemed
+/// too slow and the TAIL is aborted. Meanwhile, the dataflow's TAIL sink holds
+/// the updates that it cannot send, without blocking the dataflow workers.
+const TAIL_PARK_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Handles an incoming pgwire connection.
 pub async fn serve<A>(
     conn: A,
@@ -91,14 +103,21 @@ where
     };
     CONN_SECRETS.generate(conn_id);
 
+    let (notify_tx, notify_rx) = futures::channel::mpsc::unbounded();
     let mut machine = StateMachine {
         conn: &mut Framed::new(conn, Codec::new()).buffer(32),
         conn_id,
         conn_secrets: CONN_SECRETS.clone(),
         cmdq_tx,
         gather_metrics,
+        notify_tx,
+        notify_rx,
+        row_streams: HashMap::new(),
     };
     let res = machine.start(Session::default()).await;
+    let _ = machine
+        .cmdq_tx
+        .unbounded_send(coord::Command::Terminate { conn_id });
 
     CONN_ID_ALLOCATOR.free(conn_id);
     CONN_SECRETS.free(conn_id);
@@ -125,6 +144,12 @@ pub struct StateMachine<'a, A> {
     conn_secrets: SecretManager,
     cmdq_tx: futures::channel::mpsc::UnboundedSender<coord::Command>,
     gather_metrics: bool,
+    notify_tx: futures::channel::mpsc::UnboundedSender<Notification>,
+    notify_rx: futures::channel::mpsc::UnboundedReceiver<Notification>,
+    /// The rows that the workers have yet to send for each suspended portal
+    /// whose rows are streamed, by portal name. Rows that have arrived but
+    /// have not been sent are held by the portal itself.
+    row_streams: HashMap<String, PeekStream>,
 }
 
 impl<'a, A> StateMachine<'a, A>
@@ -168,7 +193,7 @@ where
     }
 
     async fn advance_ready(&mut self, session: Session) -> Result<State, comm::Error> {
-        let message = self.recv().await?;
+        let message = self.recv_notifying().await?;
         let timer = Instant::now();
         let name = match &message {
             Some(message) => message.name(),
@@ -261,7 +286,12 @@ where
 
         let (tx, rx) = futures::channel::oneshot::channel();
         self.cmdq_tx
-            .send(coord::Command::Startup { session, tx })
+            .send(coord::Command::Startup {
+                session,
+                conn_id: self.conn_id,
+                notify_tx: self.notify_tx.clone(),
+                tx,
+            })
             .await?;
         let (notices, session) = match rx.await? {
             coord::Response {
@@ -294,7 +324,7 @@ where
                 result: Err(err),
                 session,
             } => {
-                return self.error(session, "99999", err.to_string()).await;
+                return self.coord_error(session, err).await;
             }
         };
 
@@ -337,90 +367,105 @@ where
         Ok(State::Startup(session))
     }
 
-    async fn query(&mut self, session: Session, sql: String) -> Result<State, comm::Error> {
-        let run = async {
-            let stmt_name = String::from("");
-            let portal_name = String::from("");
+    async fn query(&mut self, mut session: Session, sql: String) -> Result<State, comm::Error> {
+        // A simple query may contain several statements separated by
+        // semicolons, which are executed in order until one of them fails.
+        // Strings that fail to parse are passed through unchanged, so that
+        // the parse error is reported as usual.
+        let stmts = match sql::parse(sql.clone()) {
+            Ok(stmts) if stmts.len() > 1 => stmts.iter().map(|s| s.to_string()).collect(),
+            _ => vec![sql],
+        };
+        for sql in stmts {
+            match self.query_one(session, sql).await? {
+                State::Startup(_) => unreachable!(),
+                State::Ready(s) => session = s,
+                State::Drain(s) => return self.sync(s).await,
+                State::Done => return Ok(State::Done),
+            }
+        }
+        self.sync(session).await
+    }
 
-            // Parse.
-            let (tx, rx) = futures::channel::oneshot::channel();
-            let cmd = coord::Command::Parse {
-                name: stmt_name.clone(),
-                sql,
-                session,
-                tx,
-            };
-            self.cmdq_tx.send(cmd).await?;
-            let mut session = match rx.await? {
-                coord::Response {
-                    result: Ok(()),
-                    session,
-                } => session,
-                coord::Response {
-                    result: Err(err),
-                    session,
-                } => {
-                    return self.error(session, "99999", err.to_string()).await;
-                }
-            };
+    async fn query_one(&mut self, session: Session, sql: String) -> Result<State, comm::Error> {
+        let stmt_name = String::from("");
+        let portal_name = String::from("");
 
-            let stmt = session.get_prepared_statement(&stmt_name).unwrap();
-            if !stmt.param_types().is_empty() {
-                return self
-                    .error(session, "42P02", "there is no parameter $1")
-                    .await;
+        // Parse.
+        let (tx, rx) = futures::channel::oneshot::channel();
+        let cmd = coord::Command::Parse {
+            name: stmt_name.clone(),
+            sql,
+            session,
+            tx,
+        };
+        self.cmdq_tx.send(cmd).await?;
+        let mut session = match rx.await? {
+            coord::Response {
+                result: Ok(()),
+                session,
+            } => session,
+            coord::Response {
+                result: Err(err),
+                session,
+            } => {
+                return self.coord_error(session, err).await;
             }
-            let row_desc = stmt.desc().cloned();
+        };
 
-            // Bind.
-            let params = vec![];
-            let result_formats = vec![pgrepr::Format::Text; stmt.result_width()];
-            session
-                .set_portal(
-                    portal_name.clone(),
-                    stmt_name.clone(),
-                    params,
-                    result_formats,
-                )
-                .expect("unnamed statement to be present during simple query flow");
+        let stmt = session.get_prepared_statement(&stmt_name).unwrap();
+        if !stmt.param_types().is_empty() {
+            return self
+                .error(session, "42P02", "there is no parameter $1")
+                .await;
+        }
+        let row_desc = stmt.desc().cloned();
 
-            // Maybe send row description.
-            if let Some(desc) = &row_desc {
-                self.send(BackendMessage::RowDescription(
-                    message::row_description_from_desc(&desc),
-                ))
-                .await?;
-            }
+        // Bind.
+        let params = vec![];
+        let result_formats = vec![pgrepr::Format::Text; stmt.result_width()];
+        self.row_streams.remove(&portal_name);
+        session
+            .set_portal(
+                portal_name.clone(),
+                stmt_name.clone(),
+                params,
+                result_formats,
+            )
+            .expect("unnamed statement to be present during simple query flow");
+
+        // Maybe send row description.
+        if let Some(desc) = &row_desc {
+            self.send(BackendMessage::RowDescription(
+                message::row_description_from_desc(&desc),
+            ))
+            .await?;
+        }
 
-            // Execute.
-            let (tx, rx) = futures::channel::oneshot::channel();
-            self.cmdq_tx
-                .send(coord::Command::Execute {
-                    portal_name: portal_name.clone(),
-                    session,
-                    conn_id: self.conn_id,
-                    tx,
-                })
-                .await?;
-            match rx.await? {
-                coord::Response {
-                    result: Ok(response),
-                    session,
-                } => {
-                    let max_rows = 0;
-                    self.send_execute_response(session, response, row_desc, portal_name, max_rows)
-                        .await
-                }
-                coord::Response {
-                    result: Err(err),
-                    session,
-                } => self.error(session, "99999", err.to_string()).await,
+        // Execute.
+        session.set_fetching_incrementally(false);
+        let (tx, rx) = futures::channel::oneshot::channel();
+        self.cmdq_tx
+            .send(coord::Command::Execute {
+                portal_name: portal_name.clone(),
+                session,
+                conn_id: self.conn_id,
+                tx,
+            })
+            .await?;
+        match rx.await? {
+            coord::Response {
+                result: Ok(response),
+                session,
+            } => {
+                let max_rows = 0;
+                self.send_execute_response(session, response, row_desc, portal_name, max_rows)
+                    .await
             }
-        };
-        match run.await? {
-            State::Startup(_) => unreachable!(),
-            State::Ready(session) | State::Drain(session) => self.sync(session).await,
-            State::Done => Ok(State::Done),
+            coord::Response {
+                result: Err(err),
+                session,
+            } => self.coord_error(session, err).await,
         }
     }
 
@@ -451,7 +496,7 @@ where
             coord::Response {
                 result: Err(err),
                 session,
-            } => self.error(session, "99999", err.to_string()).await,
+            } => self.coord_error(session, err).await,
         }
     }
 
@@ -503,6 +548,7 @@ where
             Err(msg) => return self.error(session, "08P01", msg).await,
         };
 
+        self.row_streams.remove(&portal_name);
         session
             .set_portal(portal_name, statement_name, params, result_formats)
             .unwrap();
@@ -526,6 +572,19 @@ where
                 return self.error(session, "26000", "portal does not exist").await;
             }
         };
+        if let Some(stream) = self.row_streams.remove(&portal_name) {
+            let rows = portal.remaining_rows.take().unwrap_or_default();
+            return self
+                .send_row_stream(
+                    session,
+                    row_desc.expect("portal missing row desc on resumption"),
+                    portal_name,
+                    rows,
+                    stream,
+                    max_rows,
+                )
+                .await;
+        }
         if portal.remaining_rows.is_some() {
             let rows = portal.remaining_rows.take().unwrap();
             return self
@@ -539,6 +598,10 @@ where
                 .await;
         }
 
+        // A client that limits the rows of each Execute can be sent them as
+        // the workers produce them, with the rest retained until it asks for
+        // more.
+        session.set_fetching_incrementally(max_rows > 0);
         let (tx, rx) = futures::channel::oneshot::channel();
         self.cmdq_tx
             .send(coord::Command::Execute {
@@ -559,7 +622,7 @@ where
             coord::Response {
                 result: Err(err),
                 session,
-            } => self.error(session, "99999", err.to_string()).await,
+            } => self.coord_error(session, err).await,
         }
     }
 
@@ -613,6 +676,7 @@ where
         mut session: Session,
         name: String,
     ) -> Result<State, comm::Error> {
+        self.row_streams.remove(&name);
         session.remove_portal(&name);
         self.send(BackendMessage::CloseComplete).await?;
         Ok(State::Ready(session))
@@ -687,12 +751,15 @@ where
         }
 
         match response {
+            ExecuteResponse::AlteredObject(o) => command_complete!("ALTER {}", o),
+            ExecuteResponse::Commented => command_complete!("COMMENT"),
             ExecuteResponse::CreatedDatabase { existed } => created!(existed, "42P04", "database"),
             ExecuteResponse::CreatedSchema { existed } => created!(existed, "42P06", "schema"),
             ExecuteResponse::CreatedTable { existed } => created!(existed, "42P07", "table"),
             ExecuteResponse::CreatedIndex { existed } => created!(existed, "42710", "index"),
             ExecuteResponse::CreatedSource { existed } => created!(existed, "42710", "source"),
             ExecuteResponse::CreatedSink { existed } => created!(existed, "42710", "sink"),
+            ExecuteResponse::CreatedType => command_complete!("CREATE TYPE"),
             ExecuteResponse::CreatedView => command_complete!("CREATE VIEW"),
             ExecuteResponse::Deleted(n) => command_complete!("DELETE {}", n),
             ExecuteResponse::DroppedDatabase => command_complete!("DROP DATABASE"),
@@ -701,11 +768,17 @@ where
             ExecuteResponse::DroppedIndex => command_complete!("DROP INDEX"),
             ExecuteResponse::DroppedSink => command_complete!("DROP SINK"),
             ExecuteResponse::DroppedTable => command_complete!("DROP TABLE"),
+            ExecuteResponse::DroppedType => command_complete!("DROP TYPE"),
             ExecuteResponse::DroppedView => command_complete!("DROP VIEW"),
             ExecuteResponse::EmptyQuery => {
                 self.send(BackendMessage::EmptyQueryResponse).await?;
                 Ok(State::Ready(session))
             }
+            ExecuteResponse::ImportedTrace(n) => command_complete!("IMPORT TRACE {}", n),
+            ExecuteResponse::InstalledDemo => command_complete!("INSTALL DEMO"),
+            ExecuteResponse::Listened => command_complete!("LISTEN"),
+            ExecuteResponse::Resumed => command_complete!("RESUME"),
+            ExecuteResponse::Unlistened => command_complete!("UNLISTEN"),
             ExecuteResponse::Inserted(n) => {
                 // "On successful completion, an INSERT command returns a
                 // command tag of the form `INSERT <oid> <count>`."
@@ -719,18 +792,39 @@ where
             ExecuteResponse::SendRows(rx) => {
                 let row_desc =
                     row_desc.expect("missing row description for ExecuteResponse::SendRows");
-                match rx.await? {
+                let (response, metadata) = rx.await?;
+                match metadata {
+                    Some(metadata) if session.peek_metadata() => {
+                        self.send_peek_metadata(&metadata).await?;
+                    }
+                    _ => (),
+                }
+                match response {
                     PeekResponse::Canceled => {
                         self.error(session, "57014", "canceling statement due to user request")
                             .await
                     }
-                    PeekResponse::Error(text) => self.error(session, "99999", text).await,
+                    PeekResponse::Error(err) => {
+                        self.error(session, err_sqlstate(&err), err.to_string())
+                            .await
+                    }
                     PeekResponse::Rows(rows) => {
                         self.send_rows(session, row_desc, portal_name, rows, max_rows)
                             .await
                     }
+                    PeekResponse::Changes(_) | PeekResponse::Updates(_) => {
+                        unreachable!(
+                            "SQL queries never peek at the changes to or history of a collection"
+                        )
+                    }
                 }
             }
+            ExecuteResponse::StreamRows(stream) => {
+                let row_desc =
+                    row_desc.expect("missing row description for ExecuteResponse::StreamRows");
+                self.send_row_stream(session, row_desc, portal_name, vec![], stream, max_rows)
+                    .await
+            }
             ExecuteResponse::SetVariable { name } => {
                 // This code is somewhat awkwardly structured because we
                 // can't hold `var` across an await point.
@@ -798,12 +892,117 @@ where
         Ok(State::Ready(session))
     }
 
+    /// Sends `rows`, and then the rows in the batches that `stream` yields,
+    /// stopping after `max_rows` rows if `max_rows` is positive.
+    ///
+    /// If rows remain, the portal is suspended. The unsent rows of the batch
+    /// at hand are kept as the portal's remaining rows, and the rest of
+    /// `stream` is retained until the client executes the portal again. The
+    /// workers hold the batches that have yet to be read from `stream`.
+    async fn send_row_stream(
+        &mut self,
+        mut session: Session,
+        row_desc: RelationDesc,
+        portal_name: String,
+        mut rows: Vec<Row>,
+        mut stream: PeekStream,
+        max_rows: i32,
+    ) -> Result<State, comm::Error> {
+        let portal = session
+            .get_portal(&portal_name)
+            .expect("valid portal name for send row stream");
+        let formats: Arc<Vec<pgrepr::Format>> = Arc::new(portal.result_formats.clone());
+
+        let mut remaining = if max_rows > 0 {
+            Some(max_rows as usize)
+        } else {
+            None
+        };
+        loop {
+            let n = cmp::min(rows.len(), remaining.unwrap_or(rows.len()));
+            let typ = row_desc.typ();
+            let formats = &formats;
+            self.send_all(rows.drain(..n).map(|row| {
+                BackendMessage::DataRow(pgrepr::values_from_row(row, typ), formats.clone())
+            }))
+            .await?;
+            if let Some(remaining) = &mut remaining {
+                *remaining -= n;
+            }
+            if !rows.is_empty() || remaining == Some(0) {
+                break;
+            }
+            match stream.next().await {
+                Some(Ok(PeekMessage::Batch(PeekResponse::Rows(batch)))) => rows = batch,
+                Some(Ok(PeekMessage::Batch(PeekResponse::Error(err)))) => {
+                    return self
+                        .error(session, err_sqlstate(&err), err.to_string())
+                        .await;
+                }
+                Some(Ok(PeekMessage::Batch(PeekResponse::Canceled))) => {
+                    return self
+                        .error(session, "57014", "canceling statement due to user request")
+                        .await;
+                }
+                Some(Ok(PeekMessage::Batch(_))) => unreachable!(
+                    "SQL queries never peek at the changes to or history of a collection"
+                ),
+                Some(Ok(PeekMessage::Run(_))) => {
+                    unreachable!("peek streams merge the workers' runs")
+                }
+                Some(Ok(PeekMessage::Done(metadata))) => {
+                    if session.peek_metadata() {
+                        self.send_peek_metadata(&metadata).await?;
+                    }
+                    self.send(BackendMessage::CommandComplete {
+                        tag: "SELECT".into(),
+                    })
+                    .await?;
+                    return Ok(State::Ready(session));
+                }
+                Some(Err(err)) => return Err(err),
+                None => unreachable!("peek streams end with their metadata or an error"),
+            }
+        }
+
+        session
+            .get_portal_mut(&portal_name)
+            .expect("valid portal name for send row stream")
+            .set_remaining_rows(rows);
+        self.row_streams.insert(portal_name, stream);
+        self.send(BackendMessage::PortalSuspended).await?;
+        Ok(State::Ready(session))
+    }
+
+    /// Describes how the dataflow workers answered a query, for a session
+    /// that has enabled `peek_metadata`.
+    async fn send_peek_metadata(&mut self, metadata: &PeekMetadata) -> Result<(), comm::Error> {
+        self.send(BackendMessage::NoticeResponse {
+            severity: NoticeSeverity::Notice,
+            code: "00000",
+            message: format!("query answered at timestamp {}", metadata.timestamp),
+            detail: Some(format!(
+                "{} rows, computed by the dataflow workers in {} ms",
+                metadata.rows,
+                metadata.duration.as_millis()
+            )),
+            hint: None,
+        })
+        .await
+    }
+
     async fn stream_rows(
         &mut self,
         session: Session,
         row_desc: RelationDesc,
-        mut rx: comm::mpsc::Receiver<Vec<Update>>,
+        rx: comm::mpsc::Receiver<Vec<Update>>,
     ) -> Result<State, comm::Error> {
+        // Updates are buffered separately from the connection, so that a
+        // client that reads slowly does not immediately stall the dataflow
+        // workers that produce the updates.
+        let (buffer_tx, mut buffer_rx) = futures::channel::mpsc::channel(TAIL_BUFFER);
+        let buffering = tokio::spawn(buffer_updates(rx, buffer_tx));
+
         let typ = row_desc.typ();
         let column_formats = iter::repeat(pgrepr::Format::Text)
             .take(typ.column_types.len())
@@ -816,7 +1015,7 @@ where
 
         let mut count = 0;
         loop {
-            match time::timeout(Duration::from_secs(1), rx.next()).await {
+            match time::timeout(Duration::from_secs(1), buffer_rx.next()).await {
                 Ok(None) => break,
                 Ok(Some(updates)) => {
                     let updates = updates?;
@@ -853,6 +1052,28 @@ where
             self.conn.flush().await?;
         }
 
+        if let Ok(true) = buffering.await {
+            // The dataflow has stopped trying to send updates to this
+            // client, so it is no longer of any use. Drop it.
+            self.cmdq_tx
+                .send(coord::Command::CancelRequest {
+                    conn_id: self.conn_id,
+                })
+                .await?;
+            return self
+                .error(
+                    session,
+                    "53000",
+                    format!(
+                        "client too slow: TAIL aborted after its buffer of {} batches \
+                         was full for {} seconds",
+                        TAIL_BUFFER,
+                        TAIL_PARK_TIMEOUT.as_secs()
+                    ),
+                )
+                .await;
+        }
+
         let tag = format!("COPY {}", count);
         self.send(BackendMessage::CopyDone).await?;
         self.send(BackendMessage::CommandComplete { tag }).await?;
@@ -868,6 +1089,35 @@ where
         Ok(message)
     }
 
+    /// Like `recv`, but delivers the notifications for which the
+    /// session listens while it waits for the next message.
+    async fn recv_notifying(&mut self) -> Result<Option<FrontendMessage>, comm::Error> {
+        loop {
+            let next = future::select(self.conn.try_next(), self.notify_rx.next()).await;
+            let notification = match next {
+                Either::Left((message, _)) => {
+                    let message = message?;
+                    match &message {
+                        Some(message) => trace!("cid={} recv={:?}", self.conn_id, message),
+                        None => trace!("cid={} recv=<eof>", self.conn_id),
+                    }
+                    return Ok(message);
+                }
+                Either::Right((Some(notification), _)) => notification,
+                // The coordinator has shut down, so no more notifications
+                // will arrive.
+                Either::Right((None, _)) => return self.recv().await,
+            };
+            self.send(BackendMessage::NotificationResponse {
+                conn_id: self.conn_id,
+                channel: notification.channel,
+                payload: notification.payload,
+            })
+            .await?;
+            self.conn.flush().await?;
+        }
+    }
+
     async fn send(&mut self, message: BackendMessage) -> Result<(), comm::Error> {
         trace!("cid={} send={:?}", self.conn_id, message);
         Ok(self.conn.enqueue(message).await?)
@@ -887,11 +1137,35 @@ where
 
     async fn error(
         &mut self,
-        mut session: Session,
+        session: Session,
         code: &'static str,
         message: impl Into<String>,
     ) -> Result<State, comm::Error> {
-        let message = message.into();
+        self.send_error(session, code, message.into(), None).await
+    }
+
+    /// Reports an error returned by the coordinator. If the error reports the
+    /// violation of a constraint, the constraint is named in the response.
+    async fn coord_error(
+        &mut self,
+        session: Session,
+        err: failure::Error,
+    ) -> Result<State, comm::Error> {
+        let constraint = err
+            .downcast_ref::<dataflow_types::Error>()
+            .and_then(|err| err.constraint())
+            .map(String::from);
+        self.send_error(session, sqlstate(&err), err.to_string(), constraint)
+            .await
+    }
+
+    async fn send_error(
+        &mut self,
+        mut session: Session,
+        code: &'static str,
+        message: String,
+        constraint: Option<String>,
+    ) -> Result<State, comm::Error> {
         debug!(
             "cid={} error code={} message={}",
             self.conn_id, code, message
@@ -902,6 +1176,7 @@ where
                 code,
                 message,
                 detail: None,
+                constraint,
             })
             .await?;
         session.fail_transaction();
@@ -924,12 +1199,63 @@ where
                 code,
                 message,
                 detail: None,
+                constraint: None,
             })
             .await?;
         Ok(State::Done)
     }
 }
 
+/// Moves the updates that arrive on `rx` into `buffer` until the TAIL that
+/// sends them ends, and reports whether the TAIL was aborted because the client
+/// was too slow to read them.
+///
+/// While `buffer` is full, updates are not read from `rx`, and so they queue up
+/// in the TAIL sink that sends them. If the client does not make room in
+/// `buffer` within `TAIL_PARK_TIMEOUT`, any further updates are dropped. The updates already in `buffer` are still
+/// delivered, so the client observes a prefix of the TAIL followed by an
+/// error.
+async fn buffer_updates(
+    mut rx: comm::mpsc::Receiver<Vec<Update>>,
+    mut buffer: futures::channel::mpsc::Sender<Result<Vec<Update>, comm::Error>>,
+) -> bool {
+    while let Some(updates) = rx.next().await {
+        match time::timeout(TAIL_PARK_TIMEOUT, buffer.send(updates)).await {
+            Ok(Ok(())) => (),
+            // The client has disconnected.
+            Ok(Err(_)) => return false,
+            Err(time::Elapsed { .. }) => return true,
+        }
+    }
+    false
+}
+
+/// Returns the SQLSTATE code for an error returned by the coordinator.
+fn sqlstate(err: &failure::Error) -> &'static str {
+    match err.downcast_ref::<dataflow_types::Error>() {
+        Some(err) => err_sqlstate(err),
+        None => "XX000",
+    }
+}
+
+/// Maps each class of error to the corresponding SQLSTATE class.
+fn err_sqlstate(err: &dataflow_types::Error) -> &'static str {
+    match err {
+        // syntax_error_or_access_rule_violation
+        dataflow_types::Error::Plan(_) => "42000",
+        // data_exception
+        dataflow_types::Error::Eval(_) => "22000",
+        // insufficient_resources
+        dataflow_types::Error::Resource(_) => "53000",
+        // system_error, i.e., an error external to the database
+        dataflow_types::Error::Connector(_) => "58000",
+        // internal_error
+        dataflow_types::Error::Internal(_) => "XX000",
+        // check_violation
+        dataflow_types::Error::CheckViolation { .. } => "23514",
+    }
+}
+
 fn pad_formats(formats: Vec<pgrepr::Format>, n: usize) -> Result<Vec<pgrepr::Format>, String> {
     match (formats.len(), n) {
         (0, e) => Ok(vec![pgrepr::Format::Text; e]),
@@ -11,9 +11,10 @@ use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::fmt;
 use std::mem::{size_of, transmute};
+use std::net::IpAddr;
 
 use crate::decimal::Significand;
-use crate::scalar::Interval;
+use crate::scalar::{Inet, Interval, Point};
 use crate::Datum;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use ordered_float::OrderedFloat;
@@ -159,6 +160,9 @@ enum Tag {
     List,
     Dict,
     JsonNull,
+    Point,
+    InetV4,
+    InetV6,
 }
 
 // --------------------------------------------------------------------------------
@@ -289,6 +293,27 @@ unsafe fn read_datum<'a>(data: &'a [u8], offset: &mut usize) -> Datum<'a> {
             Datum::Dict(DatumDict { data: bytes })
         }
         Tag::JsonNull => Datum::JsonNull,
+        Tag::Point => {
+            let x = read_copy::<f64>(data, offset);
+            let y = read_copy::<f64>(data, offset);
+            Datum::Point(Point::new(x, y))
+        }
+        Tag::InetV4 => {
+            let octets = read_copy::<[u8; 4]>(data, offset);
+            let prefix_len = read_copy::<u8>(data, offset);
+            Datum::Inet(Inet {
+                addr: IpAddr::from(octets),
+                prefix_len,
+            })
+        }
+        Tag::InetV6 => {
+            let octets = read_copy::<[u8; 16]>(data, offset);
+            let prefix_len = read_copy::<u8>(data, offset);
+            Datum::Inet(Inet {
+                addr: IpAddr::from(octets),
+                prefix_len,
+            })
+        }
     }
 }
 
@@ -380,6 +405,24 @@ fn push_datum(data: &mut Vec<u8>, datum: Datum) {
             push_untagged_bytes(data, &dict.data);
         }
         Datum::JsonNull => data.push(Tag::JsonNull as u8),
+        Datum::Point(p) => {
+            data.push(Tag::Point as u8);
+            push_copy!(data, p.x.into_inner(), f64);
+            push_copy!(data, p.y.into_inner(), f64);
+        }
+        Datum::Inet(inet) => {
+            match inet.addr {
+                IpAddr::V4(addr) => {
+                    data.push(Tag::InetV4 as u8);
+                    data.extend_from_slice(&addr.octets());
+                }
+                IpAddr::V6(addr) => {
+                    data.push(Tag::InetV6 as u8);
+                    data.extend_from_slice(&addr.octets());
+                }
+            }
+            data.push(inet.prefix_len);
+        }
     }
 }
 
@@ -408,6 +451,15 @@ pub fn datum_size(datum: &Datum) -> usize {
         Datum::List(list) => 1 + size_of::<usize>() + list.data.len(),
         Datum::Dict(dict) => 1 + size_of::<usize>() + dict.data.len(),
         Datum::JsonNull => 1,
+        Datum::Point(_) => 1 + 2 * size_of::<f64>(),
+        Datum::Inet(Inet {
+            addr: IpAddr::V4(_),
+            ..
+        }) => 1 + 4 + 1,
+        Datum::Inet(Inet {
+            addr: IpAddr::V6(_),
+            ..
+        }) => 1 + 16 + 1,
     }
 }
 
@@ -1014,6 +1066,9 @@ mod tests {
             Datum::Bytes(&[0, 2, 1, 255]),
             Datum::String(""),
             Datum::String("العَرَبِيَّة"),
+            Datum::Point(Point::new(-73.98, 40.75)),
+            Datum::Inet(Inet::new("192.168.0.1".parse().unwrap(), 24).unwrap()),
+            Datum::Inet(Inet::new("2001:db8::".parse().unwrap(), 32).unwrap()),
         ]);
     }
 
@@ -1081,6 +1136,9 @@ mod tests {
             Datum::Bytes(&[]),
             Datum::String(""),
             Datum::JsonNull,
+            Datum::Point(Point::default()),
+            Datum::Inet(Inet::default()),
+            Datum::Inet(Inet::new("::".parse().unwrap(), 0).unwrap()),
         ];
         for value in values_of_interest {
             if !datum_size(&value) == Row::pack(Some(value)).data.len() {
@@ -9,6 +9,7 @@
 
 use std::fmt::{self, Write};
 use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use failure::format_err;
@@ -56,6 +57,10 @@ pub enum Datum<'a> {
     /// An exact decimal number, possibly with a fractional component, with up
     /// to 38 digits of precision.
     Decimal(Significand),
+    /// A point on a plane
+    Point(Point),
+    /// An IP address, optionally with a network prefix
+    Inet(Inet),
     /// A sequence of untyped bytes.
     Bytes(&'a [u8]),
     /// A sequence of Unicode codepoints encoded as UTF-8.
@@ -248,6 +253,20 @@ impl<'a> Datum<'a> {
         }
     }
 
+    pub fn unwrap_point(&self) -> Point {
+        match self {
+            Datum::Point(p) => *p,
+            _ => panic!("Datum::unwrap_point called on {:?}", self),
+        }
+    }
+
+    pub fn unwrap_inet(&self) -> Inet {
+        match self {
+            Datum::Inet(inet) => *inet,
+            _ => panic!("Datum::unwrap_inet called on {:?}", self),
+        }
+    }
+
     pub fn unwrap_decimal(&self) -> Significand {
         match self {
             Datum::Decimal(d) => *d,
@@ -311,6 +330,7 @@ impl<'a> Datum<'a> {
                     (Datum::True, ScalarType::Bool) => true,
                     (Datum::True, _) => false,
                     (Datum::Int32(_), ScalarType::Int32) => true,
+                    (Datum::Int32(_), ScalarType::Enum(_)) => true,
                     (Datum::Int32(_), _) => false,
                     (Datum::Int64(_), ScalarType::Int64) => true,
                     (Datum::Int64(_), _) => false,
@@ -330,10 +350,16 @@ impl<'a> Datum<'a> {
                     (Datum::Interval(_), _) => false,
                     (Datum::Decimal(_), ScalarType::Decimal(_, _)) => true,
                     (Datum::Decimal(_), _) => false,
+                    (Datum::Point(_), ScalarType::Point) => true,
+                    (Datum::Point(_), _) => false,
+                    (Datum::Inet(_), ScalarType::Inet) => true,
+                    (Datum::Inet(_), ScalarType::Cidr) => true,
+                    (Datum::Inet(_), _) => false,
                     (Datum::Bytes(_), ScalarType::Bytes) => true,
                     (Datum::Bytes(_), _) => false,
                     (Datum::String(_), ScalarType::String) => true,
                     (Datum::String(_), _) => false,
+                    (Datum::List(_), ScalarType::List) => true,
                     (Datum::List(_), _) => false,
                     (Datum::Dict(_), _) => false,
                     (Datum::JsonNull, _) => false,
@@ -433,6 +459,18 @@ impl From<Interval> for Datum<'static> {
     }
 }
 
+impl From<Point> for Datum<'static> {
+    fn from(other: Point) -> Datum<'static> {
+        Datum::Point(other)
+    }
+}
+
+impl From<Inet> for Datum<'static> {
+    fn from(other: Inet) -> Datum<'static> {
+        Datum::Inet(other)
+    }
+}
+
 impl<'a> From<&'a str> for Datum<'a> {
     fn from(s: &'a str) -> Datum<'a> {
         Datum::String(s)
@@ -512,6 +550,8 @@ impl fmt::Display for Datum<'_> {
             Datum::TimestampTz(t) => write!(f, "{}", t),
             Datum::Interval(iv) => write!(f, "{}", iv),
             Datum::Decimal(sig) => write!(f, "{}dec", sig.as_i128()),
+            Datum::Point(p) => write!(f, "{}", p),
+            Datum::Inet(inet) => write!(f, "{}", inet),
             Datum::Bytes(dat) => {
                 f.write_str("0x")?;
                 for b in dat.iter() {
@@ -585,6 +625,31 @@ pub enum ScalarType {
     /// Json behaves like postgres' jsonb type but is stored as Datum::JsonNull/True/False/String/Float64/List/Dict.
     /// The sql type system is responsible for preventing these being used as normal sql datums without casting.
     Jsonb,
+    /// A point on a plane
+    ///
+    /// Represented by the [`Point`] struct
+    Point,
+    /// An IPv4 or IPv6 host address, optionally with a network prefix
+    ///
+    /// Represented by the [`Inet`] struct
+    Inet,
+    /// An IPv4 or IPv6 network
+    ///
+    /// Represented by the [`Inet`] struct, whose bits to the right of the
+    /// prefix are always zero
+    Cidr,
+    /// A value of the user-defined enum type with the specified ID
+    ///
+    /// Represented by a [`Datum::Int32`] that holds the position of the
+    /// value's label in the type's list of labels, so that values are compact
+    /// and sort in the order in which the labels were declared. Only the
+    /// catalog knows the labels themselves.
+    Enum(u64),
+    /// A list of datums of any types
+    ///
+    /// Represented by a [`Datum::List`]. Lists only hold intermediate values,
+    /// like the several arguments of an aggregate, and never reach clients.
+    List,
 }
 
 impl<'a> ScalarType {
@@ -614,6 +679,10 @@ impl<'a> ScalarType {
             ScalarType::Bytes => Datum::Bytes(&[]),
             ScalarType::String => Datum::String(""),
             ScalarType::Jsonb => Datum::JsonNull,
+            ScalarType::Point => Datum::Point(Point::default()),
+            ScalarType::Inet | ScalarType::Cidr => Datum::Inet(Inet::default()),
+            ScalarType::Enum(_) => Datum::Int32(0),
+            ScalarType::List => Datum::List(DatumList::empty()),
         }
     }
 }
@@ -626,6 +695,7 @@ impl PartialEq for ScalarType {
         use ScalarType::*;
         match (self, other) {
             (Decimal(_, s1), Decimal(_, s2)) => s1 == s2,
+            (Enum(id1), Enum(id2)) => id1 == id2,
 
             (Unknown, Unknown)
             | (Bool, Bool)
@@ -640,7 +710,11 @@ impl PartialEq for ScalarType {
             | (Interval, Interval)
             | (Bytes, Bytes)
             | (String, String)
-            | (Jsonb, Jsonb) => true,
+            | (Jsonb, Jsonb)
+            | (Point, Point)
+            | (Inet, Inet)
+            | (Cidr, Cidr)
+            | (List, List) => true,
 
             (Unknown, _)
             | (Bool, _)
@@ -656,7 +730,12 @@ impl PartialEq for ScalarType {
             | (Interval, _)
             | (Bytes, _)
             | (String, _)
-            | (Jsonb, _) => false,
+            | (Jsonb, _)
+            | (Point, _)
+            | (Inet, _)
+            | (Cidr, _)
+            | (Enum(_), _)
+            | (List, _) => false,
         }
     }
 }
@@ -685,6 +764,14 @@ impl Hash for ScalarType {
             Bytes => state.write_u8(12),
             String => state.write_u8(13),
             Jsonb => state.write_u8(14),
+            Point => state.write_u8(15),
+            Inet => state.write_u8(16),
+            Cidr => state.write_u8(17),
+            Enum(id) => {
+                state.write_u8(18);
+                state.write_u64(*id);
+            }
+            List => state.write_u8(19),
         }
     }
 }
@@ -713,6 +800,11 @@ impl fmt::Display for ScalarType {
             Bytes => f.write_str("bytes"),
             String => f.write_str("string"),
             Jsonb => f.write_str("jsonb"),
+            Point => f.write_str("point"),
+            Inet => f.write_str("inet"),
+            Cidr => f.write_str("cidr"),
+            Enum(id) => write!(f, "enum({})", id),
+            List => f.write_str("list"),
         }
     }
 }
@@ -904,6 +996,142 @@ impl fmt::Display for Interval {
     }
 }
 
+/// A point on a plane, like PostgreSQL's `point` type.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Point {
+    pub x: OrderedFloat<f64>,
+    pub y: OrderedFloat<f64>,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point {
+            x: OrderedFloat(x),
+            y: OrderedFloat(y),
+        }
+    }
+
+    /// Computes the Euclidean distance between this point and `other`.
+    pub fn distance(&self, other: &Point) -> f64 {
+        (self.x.into_inner() - other.x.into_inner())
+            .hypot(self.y.into_inner() - other.y.into_inner())
+    }
+
+    /// Reports whether this point lies within the box with opposite corners
+    /// `a` and `b`, including on its boundary.
+    pub fn is_within_box(&self, a: &Point, b: &Point) -> bool {
+        let (x_lo, x_hi) = if a.x <= b.x { (a.x, b.x) } else { (b.x, a.x) };
+        let (y_lo, y_hi) = if a.y <= b.y { (a.y, b.y) } else { (b.y, a.y) };
+        x_lo <= self.x && self.x <= x_hi && y_lo <= self.y && self.y <= y_hi
+    }
+}
+
+/// Format a point as `(x,y)`, as PostgreSQL does.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+/// An IP address and the length of its network prefix, like PostgreSQL's
+/// `inet` and `cidr` types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Inet {
+    pub addr: IpAddr,
+    /// The number of leading bits of `addr` that identify the network.
+    pub prefix_len: u8,
+}
+
+impl Default for Inet {
+    fn default() -> Inet {
+        Inet {
+            addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            prefix_len: 32,
+        }
+    }
+}
+
+impl Inet {
+    /// Constructs a new `Inet`, or returns an error if `prefix_len` is longer
+    /// than `addr`.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Inet, failure::Error> {
+        let inet = Inet { addr, prefix_len };
+        if prefix_len > inet.max_prefix_len() {
+            return Err(format_err!(
+                "prefix length {} is too long for address {}",
+                prefix_len,
+                addr
+            ));
+        }
+        Ok(inet)
+    }
+
+    /// The number of bits in the address: 32 for IPv4 and 128 for IPv6.
+    pub fn max_prefix_len(&self) -> u8 {
+        match self.addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// Reports whether the prefix covers the entire address, i.e., whether
+    /// this `Inet` identifies a single host rather than a network.
+    pub fn is_host(&self) -> bool {
+        self.prefix_len == self.max_prefix_len()
+    }
+
+    /// Returns the network that contains this address, which has the same
+    /// prefix but all bits to the right of the prefix set to zero.
+    pub fn network(&self) -> Inet {
+        let addr = match self.addr {
+            IpAddr::V4(addr) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(addr) & mask))
+            }
+            IpAddr::V6(addr) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask))
+            }
+        };
+        Inet {
+            addr,
+            prefix_len: self.prefix_len,
+        }
+    }
+
+    /// Reports whether `other` lies within or is equal to the network
+    /// identified by this `Inet`. Addresses of different families never
+    /// contain one another.
+    pub fn contains(&self, other: &Inet) -> bool {
+        self.addr.is_ipv4() == other.addr.is_ipv4()
+            && self.prefix_len <= other.prefix_len
+            && Inet {
+                addr: other.addr,
+                prefix_len: self.prefix_len,
+            }
+            .network()
+                == self.network()
+    }
+}
+
+/// Format an `Inet` as PostgreSQL formats an `inet`: the address, followed by
+/// the prefix length if it does not cover the entire address.
+impl fmt::Display for Inet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_host() {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix_len)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1086,4 +1314,35 @@ mod test {
         );
         assert_eq!(&mon_dur(-26, false, 6), "-2 years -2 months -00:00:06");
     }
+
+    #[test]
+    fn point() {
+        let p = Point::new(3.0, -4.5);
+        assert_eq!(p.to_string(), "(3,-4.5)");
+        assert_eq!(Point::new(0.0, 0.0).distance(&Point::new(3.0, 4.0)), 5.0);
+        assert!(p.is_within_box(&Point::new(0.0, 0.0), &Point::new(5.0, -5.0)));
+        assert!(p.is_within_box(&Point::new(3.0, -4.5), &Point::new(3.0, -4.5)));
+        assert!(!p.is_within_box(&Point::new(0.0, 0.0), &Point::new(5.0, 5.0)));
+    }
+
+    #[test]
+    fn inet() {
+        let inet = |s: &str, prefix_len| Inet::new(s.parse().unwrap(), prefix_len).unwrap();
+
+        assert_eq!(inet("10.1.2.3", 32).to_string(), "10.1.2.3");
+        assert_eq!(inet("10.1.2.3", 8).to_string(), "10.1.2.3/8");
+        assert_eq!(inet("10.1.2.3", 8).network(), inet("10.0.0.0", 8));
+        assert_eq!(inet("10.1.2.3", 0).network(), inet("0.0.0.0", 0));
+        assert_eq!(inet("2001:db8::1", 32).network(), inet("2001:db8::", 32));
+        assert!(Inet::new("10.1.2.3".parse().unwrap(), 33).is_err());
+
+        assert!(inet("10.0.0.0", 8).contains(&inet("10.1.2.3", 32)));
+        assert!(inet("10.0.0.0", 8).contains(&inet("10.1.0.0", 16)));
+        assert!(inet("10.0.0.0", 8).contains(&inet("10.0.0.0", 8)));
+        assert!(inet("0.0.0.0", 0).contains(&inet("192.168.1.1", 32)));
+        assert!(!inet("10.0.0.0", 8).contains(&inet("11.0.0.1", 32)));
+        assert!(!inet("10.1.0.0", 16).contains(&inet("10.0.0.0", 8)));
+        assert!(!inet("0.0.0.0", 0).contains(&inet("::1", 128)));
+        assert!(inet("2001:db8::", 32).contains(&inet("2001:db8:1::", 48)));
+    }
 }
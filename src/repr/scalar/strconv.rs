@@ -23,6 +23,8 @@
 //! string representations for the corresponding PostgreSQL type. Deviations
 //! should be considered a bug.
 
+use std::net::IpAddr;
+
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
 use failure::bail;
 
@@ -30,7 +32,7 @@ use ore::fmt::FormatBuffer;
 
 use crate::decimal::Decimal;
 use crate::jsonb::Jsonb;
-use crate::Interval;
+use crate::{Inet, Interval, Point};
 
 /// Parses a [`bool`] from `s`.
 ///
@@ -252,6 +254,96 @@ where
     write!(buf, "{:#}", jsonb)
 }
 
+/// Parses a [`Point`] from `s`.
+///
+/// The accepted forms are `(x,y)` and `x,y`. Whitespace around the coordinates
+/// and parentheses is permissible.
+pub fn parse_point(s: &str) -> Result<Point, failure::Error> {
+    let s = s.trim();
+    let inner = if s.starts_with('(') && s.ends_with(')') {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    };
+    let mut coords = inner.splitn(2, ',');
+    match (coords.next(), coords.next()) {
+        (Some(x), Some(y)) => match (parse_float64(x), parse_float64(y)) {
+            (Ok(x), Ok(y)) => Ok(Point::new(x, y)),
+            _ => bail!("invalid input syntax for type point: \"{}\"", s),
+        },
+        _ => bail!("invalid input syntax for type point: \"{}\"", s),
+    }
+}
+
+/// Writes a [`Point`] to `buf`.
+pub fn format_point<F>(buf: &mut F, p: Point)
+where
+    F: FormatBuffer,
+{
+    write!(buf, "{}", p)
+}
+
+/// Parses an [`Inet`] from `s`.
+///
+/// The accepted form is an IPv4 or IPv6 address, optionally followed by a
+/// slash and a network prefix length. If the prefix length is omitted, the
+/// prefix covers the entire address.
+pub fn parse_inet(s: &str) -> Result<Inet, failure::Error> {
+    let s = s.trim();
+    let (addr, prefix_len) = match s.find('/') {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let addr: IpAddr = match addr.parse() {
+        Ok(addr) => addr,
+        Err(_) => bail!("invalid input syntax for type inet: \"{}\"", s),
+    };
+    let prefix_len = match (prefix_len, addr) {
+        (Some(prefix_len), _) => match prefix_len.parse() {
+            Ok(prefix_len) => prefix_len,
+            Err(_) => bail!("invalid input syntax for type inet: \"{}\"", s),
+        },
+        (None, IpAddr::V4(_)) => 32,
+        (None, IpAddr::V6(_)) => 128,
+    };
+    match Inet::new(addr, prefix_len) {
+        Ok(inet) => Ok(inet),
+        Err(_) => bail!("invalid input syntax for type inet: \"{}\"", s),
+    }
+}
+
+/// Writes an [`Inet`] to `buf` as an `inet`.
+pub fn format_inet<F>(buf: &mut F, inet: Inet)
+where
+    F: FormatBuffer,
+{
+    write!(buf, "{}", inet)
+}
+
+/// Parses an [`Inet`] that represents a network from `s`.
+///
+/// The accepted form is the same as for [`parse_inet`], except that the
+/// address must not have any bits set to the right of the prefix.
+pub fn parse_cidr(s: &str) -> Result<Inet, failure::Error> {
+    let inet = parse_inet(s)?;
+    if inet.network() != inet {
+        bail!(
+            "invalid cidr value: \"{}\": value has bits set to right of mask",
+            s.trim()
+        );
+    }
+    Ok(inet)
+}
+
+/// Writes an [`Inet`] to `buf` as a `cidr`, which always includes the prefix
+/// length.
+pub fn format_cidr<F>(buf: &mut F, inet: Inet)
+where
+    F: FormatBuffer,
+{
+    write!(buf, "{}/{}", inet.addr, inet.prefix_len)
+}
+
 fn format_nanos<F>(buf: &mut F, mut nanos: u32)
 where
     F: FormatBuffer,
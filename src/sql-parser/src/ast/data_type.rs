@@ -20,6 +20,8 @@
 
 use std::fmt;
 
+use super::ObjectName;
+
 /// SQL data types
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DataType {
@@ -75,6 +77,14 @@ pub enum DataType {
     Array(Box<DataType>),
     /// Binary JSON
     Jsonb,
+    /// Point on a plane
+    Point,
+    /// IP host address, optionally with a network prefix
+    Inet,
+    /// IP network
+    Cidr,
+    /// A user-defined type, e.g. an enum created with `CREATE TYPE`
+    Custom(ObjectName),
 }
 
 impl fmt::Display for DataType {
@@ -114,6 +124,10 @@ impl fmt::Display for DataType {
             DataType::Bytea => write!(f, "bytea"),
             DataType::Array(ty) => write!(f, "{}[]", ty),
             DataType::Jsonb => write!(f, "jsonb"),
+            DataType::Point => write!(f, "point"),
+            DataType::Inet => write!(f, "inet"),
+            DataType::Cidr => write!(f, "cidr"),
+            DataType::Custom(name) => write!(f, "{}", name),
         }
     }
 }
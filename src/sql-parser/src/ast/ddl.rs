@@ -170,6 +170,8 @@ pub enum ColumnOption {
     },
     // `CHECK (<expr>)`
     Check(Expr),
+    /// `GENERATED ALWAYS AS (<expr>) STORED`
+    Generated(Expr),
 }
 
 impl fmt::Display for ColumnOption {
@@ -192,6 +194,7 @@ impl fmt::Display for ColumnOption {
                 display_comma_separated(referred_columns)
             ),
             Check(expr) => write!(f, "CHECK ({})", expr),
+            Generated(expr) => write!(f, "GENERATED ALWAYS AS ({}) STORED", expr),
         }
     }
 }
@@ -69,6 +69,10 @@ pub enum BinaryOperator {
     JsonDeletePath,
     JsonContainsPath,
     JsonApplyPathPredicate,
+    InetContainedBy,
+    InetContainedByOrEq,
+    InetContains,
+    InetContainsOrEq,
 }
 
 impl fmt::Display for BinaryOperator {
@@ -102,6 +106,10 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::JsonDeletePath => "#-",
             BinaryOperator::JsonContainsPath => "@?",
             BinaryOperator::JsonApplyPathPredicate => "@@",
+            BinaryOperator::InetContainedBy => "<<",
+            BinaryOperator::InetContainedByOrEq => "<<=",
+            BinaryOperator::InetContains => ">>",
+            BinaryOperator::InetContainsOrEq => ">>=",
         })
     }
 }
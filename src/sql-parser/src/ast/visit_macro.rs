@@ -360,12 +360,13 @@ macro_rules! make_visitor {
                 &mut self,
                 name: &'ast $($mut)* ObjectName,
                 connector: &'ast $($mut)* Connector,
-                format: &'ast $($mut)* Format,
+                format: Option<&'ast $($mut)* Format>,
                 envelope: &'ast $($mut)* Envelope,
+                include_metadata: &'ast $($mut)* Vec<SourceMetadata>,
                 if_not_exists: bool,
                 materialized: bool,
             ) {
-                visit_create_source(self, name, connector, format, envelope, if_not_exists, materialized)
+                visit_create_source(self, name, connector, format, envelope, include_metadata, if_not_exists, materialized)
             }
 
             fn visit_connector(
@@ -411,34 +412,46 @@ macro_rules! make_visitor {
             fn visit_create_sink(
                 &mut self,
                 name: &'ast $($mut)* ObjectName,
+                in_cluster: Option<&'ast $($mut)* Ident>,
                 from: &'ast $($mut)* ObjectName,
                 connector: &'ast $($mut)* Connector,
                 format: &'ast $($mut)* Format,
                 if_not_exists: bool,
             ) {
-                visit_create_sink(self, name, from, connector, format, if_not_exists)
+                visit_create_sink(self, name, in_cluster, from, connector, format, if_not_exists)
             }
 
             fn visit_create_view(
                 &mut self,
                 name: &'ast $($mut)* ObjectName,
+                in_cluster: Option<&'ast $($mut)* Ident>,
                 columns: &'ast $($mut)* [Ident],
                 query: &'ast $($mut)* Query,
                 materialized: bool,
                 replace: bool,
                 with_options: &'ast $($mut)* [SqlOption],
             ) {
-                visit_create_view(self, name, columns, query, materialized, replace, with_options)
+                visit_create_view(self, name, in_cluster, columns, query, materialized, replace, with_options)
             }
 
             fn visit_create_index(
                 &mut self,
                 name: &'ast $($mut)* Ident,
+                in_cluster: Option<&'ast $($mut)* Ident>,
+                replicas: &'ast $($mut)* [Ident],
                 on_name: &'ast $($mut)* ObjectName,
                 key_parts: &'ast $($mut)* Vec<Expr>,
                 if_not_exists: bool,
             ){
-                visit_create_index(self, name, on_name, key_parts, if_not_exists)
+                visit_create_index(self, name, in_cluster, replicas, on_name, key_parts, if_not_exists)
+            }
+
+            fn visit_create_type(
+                &mut self,
+                name: &'ast $($mut)* ObjectName,
+                labels: &'ast $($mut)* [String],
+            ) {
+                visit_create_type(self, name, labels)
             }
 
             fn visit_create_table(
@@ -503,6 +516,24 @@ macro_rules! make_visitor {
                 visit_alter_table_operation(self, operation)
             }
 
+            fn visit_alter_tags(
+                &mut self,
+                object_type: ObjectType,
+                name: &'ast $($mut)* ObjectName,
+                tags: &'ast $($mut)* [SqlOption],
+            ) {
+                visit_alter_tags(self, object_type, name, tags)
+            }
+
+            fn visit_comment(
+                &mut self,
+                object_type: ObjectType,
+                name: &'ast $($mut)* ObjectName,
+                comment: Option<&'ast $($mut)* String>,
+            ) {
+                visit_comment(self, object_type, name, comment)
+            }
+
             fn visit_alter_add_constraint(&mut self, table_constraint: &'ast $($mut)* TableConstraint) {
                 visit_alter_add_constraint(self, table_constraint)
             }
@@ -599,6 +630,22 @@ macro_rules! make_visitor {
                 visit_show_create_source(self, source_name)
             }
 
+            fn visit_show_comments(&mut self, from: Option<&'ast $($mut)* ObjectName>) {
+                visit_show_comments(self, from)
+            }
+
+            fn visit_show_tags(&mut self, from: Option<&'ast $($mut)* ObjectName>) {
+                visit_show_tags(self, from)
+            }
+
+            fn visit_show_sink_progress(&mut self, from: Option<&'ast $($mut)* ObjectName>) {
+                visit_show_sink_progress(self, from)
+            }
+
+            fn visit_show_source_errors(&mut self, from: Option<&'ast $($mut)* ObjectName>) {
+                visit_show_source_errors(self, from)
+            }
+
             fn visit_show_statement_filter(&mut self, filter: &'ast $($mut)* ShowStatementFilter) {
                 visit_show_statement_filter(self, filter)
             }
@@ -624,12 +671,59 @@ macro_rules! make_visitor {
 
             }
 
+            fn visit_show_arrangements(&mut self) {}
+
+            fn visit_compact_catalog(&mut self, _vacuum: bool) {}
+
+            fn visit_check_catalog(&mut self) {}
+
+            fn visit_check_view(&mut self, name: &'ast $($mut)* ObjectName) {
+                visit_check_view(self, name)
+            }
+
             fn visit_commit(&mut self, _chain: bool) {}
 
             fn visit_rollback(&mut self, _chain: bool) {}
 
-            fn visit_tail(&mut self, name: &'ast $($mut)* ObjectName) {
-                visit_tail(self, name)
+            fn visit_tail(
+                &mut self,
+                name: &'ast $($mut)* ObjectName,
+                columns: &'ast $($mut)* [Ident],
+                selection: Option<&'ast $($mut)* Expr>,
+            ) {
+                visit_tail(self, name, columns, selection)
+            }
+
+            fn visit_listen(&mut self, channel: &'ast $($mut)* Ident) {
+                visit_listen(self, channel)
+            }
+
+            fn visit_unlisten(&mut self, channel: Option<&'ast $($mut)* Ident>) {
+                visit_unlisten(self, channel)
+            }
+
+            fn visit_install_demo(&mut self, name: &'ast $($mut)* Ident) {
+                visit_install_demo(self, name)
+            }
+
+            fn visit_export_trace(&mut self, name: &'ast $($mut)* ObjectName, path: &'ast $($mut)* String, _history: bool) {
+                visit_export_trace(self, name, path)
+            }
+
+            fn visit_import_trace(&mut self, name: &'ast $($mut)* ObjectName, path: &'ast $($mut)* String) {
+                visit_import_trace(self, name, path)
+            }
+
+            fn visit_quiesce(&mut self, _as_of: Option<u64>) {}
+
+            fn visit_resume(&mut self) {}
+
+            fn visit_flush_sink(&mut self, name: &'ast $($mut)* ObjectName) {
+                visit_flush_sink(self, name)
+            }
+
+            fn visit_flush_sinks(&mut self, from: Option<&'ast $($mut)* ObjectName>) {
+                visit_flush_sinks(self, from)
             }
 
             fn visit_explain(&mut self, stage: &'ast $($mut)* Stage, query: &'ast $($mut)* Query) {
@@ -670,30 +764,36 @@ macro_rules! make_visitor {
                     connector,
                     format,
                     envelope,
+                    include_metadata,
                     if_not_exists,
                     materialized,
-                } => visitor.visit_create_source(name, connector, format, envelope, *if_not_exists, *materialized),
+                } => visitor.visit_create_source(name, connector, format.as_auto_ref(), envelope, include_metadata, *if_not_exists, *materialized),
                 Statement::CreateSink {
                     name,
+                    in_cluster,
                     from,
                     connector,
                     format,
                     if_not_exists,
-                } => visitor.visit_create_sink(name, from, connector, format, *if_not_exists),
+                } => visitor.visit_create_sink(name, in_cluster.as_auto_ref(), from, connector, format, *if_not_exists),
                 Statement::CreateView {
                     name,
+                    in_cluster,
                     columns,
                     query,
                     materialized,
                     replace,
                     with_options,
-                } => visitor.visit_create_view(name, columns, query, *materialized, *replace, with_options),
+                } => visitor.visit_create_view(name, in_cluster.as_auto_ref(), columns, query, *materialized, *replace, with_options),
                 Statement::CreateIndex {
                     name,
+                    in_cluster,
+                    replicas,
                     on_name,
                     key_parts,
                     if_not_exists,
-                } => visitor.visit_create_index(name, on_name, key_parts, *if_not_exists),
+                } => visitor.visit_create_index(name, in_cluster.as_auto_ref(), replicas, on_name, key_parts, *if_not_exists),
+                Statement::CreateType { name, labels } => visitor.visit_create_type(name, labels),
                 Statement::DropDatabase { name, if_exists } => visitor.visit_drop_database(name, *if_exists),
                 Statement::DropObjects {
                     object_type,
@@ -715,6 +815,12 @@ macro_rules! make_visitor {
                     *if_not_exists,
                 ),
                 Statement::AlterTable { name, operation } => visitor.visit_alter_table(name, operation),
+                Statement::AlterTags { object_type, name, tags } => {
+                    visitor.visit_alter_tags(*object_type, name, tags)
+                }
+                Statement::Comment { object_type, name, comment } => {
+                    visitor.visit_comment(*object_type, name, comment.as_auto_ref())
+                }
                 Statement::SetVariable {
                     local,
                     variable,
@@ -738,13 +844,36 @@ macro_rules! make_visitor {
                 } => visitor.visit_show_columns(*extended, *full, table_name, filter.as_auto_ref()),
                 Statement::ShowCreateView { view_name } => visitor.visit_show_create_view(view_name),
                 Statement::ShowCreateSource { source_name } => visitor.visit_show_create_source(source_name),
+                Statement::ShowComments { from } => visitor.visit_show_comments(from.as_auto_ref()),
+                Statement::ShowTags { from } => visitor.visit_show_tags(from.as_auto_ref()),
+                Statement::ShowSinkProgress { from } => {
+                    visitor.visit_show_sink_progress(from.as_auto_ref())
+                }
+                Statement::ShowSourceErrors { from } => {
+                    visitor.visit_show_source_errors(from.as_auto_ref())
+                }
+                Statement::ShowArrangements => visitor.visit_show_arrangements(),
+                Statement::CompactCatalog { vacuum } => visitor.visit_compact_catalog(*vacuum),
+                Statement::CheckCatalog => visitor.visit_check_catalog(),
+                Statement::CheckView { name } => visitor.visit_check_view(name),
                 Statement::StartTransaction { modes } => visitor.visit_start_transaction(modes),
                 Statement::SetTransaction { modes } => visitor.visit_set_transaction(modes),
                 Statement::Commit { chain } => visitor.visit_commit(*chain),
                 Statement::Rollback { chain } => visitor.visit_rollback(*chain),
-                Statement::Tail { name } => {
-                    visitor.visit_tail(name);
-                }
+                Statement::Tail {
+                    name,
+                    columns,
+                    selection,
+                } => visitor.visit_tail(name, columns, selection.as_auto_ref()),
+                Statement::Listen { channel } => visitor.visit_listen(channel),
+                Statement::Unlisten { channel } => visitor.visit_unlisten(channel.as_auto_ref()),
+                Statement::InstallDemo { name } => visitor.visit_install_demo(name),
+                Statement::ExportTrace { name, path, history } => visitor.visit_export_trace(name, path, *history),
+                Statement::ImportTrace { name, path } => visitor.visit_import_trace(name, path),
+                Statement::Quiesce { as_of } => visitor.visit_quiesce(*as_of),
+                Statement::Resume => visitor.visit_resume(),
+                Statement::FlushSink { name } => visitor.visit_flush_sink(name),
+                Statement::FlushSinks { from } => visitor.visit_flush_sinks(from.as_auto_ref()),
                 Statement::Explain { stage, query } => visitor.visit_explain(stage, query),
             }
         }
@@ -1323,14 +1452,17 @@ macro_rules! make_visitor {
             visitor: &mut V,
             name: &'ast $($mut)* ObjectName,
             connector: &'ast $($mut)* Connector,
-            format: &'ast $($mut)* Format,
+            format: Option<&'ast $($mut)* Format>,
             envelope: &'ast $($mut)* Envelope,
+            _include_metadata: &'ast $($mut)* Vec<SourceMetadata>,
             _if_not_exists: bool,
             _materialized: bool,
         ) {
             visitor.visit_object_name(name);
             visitor.visit_connector(connector);
-            visitor.visit_format(format);
+            if let Some(format) = format {
+                visitor.visit_format(format);
+            }
             visitor.visit_source_envelope(envelope);
         }
 
@@ -1339,6 +1471,13 @@ macro_rules! make_visitor {
             connector: &'ast $($mut)* Connector,
         ) {
             match connector {
+                Connector::Elasticsearch { url, index, with_options } => {
+                    visitor.visit_literal_string(url);
+                    visitor.visit_literal_string(index);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
                 Connector::File { path, with_options } => {
                     visitor.visit_literal_string(path);
                     for option in with_options {
@@ -1358,6 +1497,52 @@ macro_rules! make_visitor {
                         visitor.visit_option(option);
                     }
                 }
+                Connector::Mqtt { broker, topic, with_options } => {
+                    visitor.visit_literal_string(broker);
+                    visitor.visit_literal_string(topic);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
+                Connector::Mysql { url, table, columns } => {
+                    visitor.visit_literal_string(url);
+                    visitor.visit_literal_string(table);
+                    for column in columns {
+                        visitor.visit_column_def(column);
+                    }
+                }
+                Connector::Nats { url, subject, with_options } => {
+                    visitor.visit_literal_string(url);
+                    visitor.visit_literal_string(subject);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
+                Connector::Plugin { name, with_options } => {
+                    visitor.visit_literal_string(name);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
+                Connector::Postgres { conn, table, columns } => {
+                    visitor.visit_literal_string(conn);
+                    visitor.visit_literal_string(table);
+                    for column in columns {
+                        visitor.visit_column_def(column);
+                    }
+                }
+                Connector::Redis { url, with_options } => {
+                    visitor.visit_literal_string(url);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
+                Connector::S3 { bucket, with_options } => {
+                    visitor.visit_literal_string(bucket);
+                    for option in with_options {
+                        visitor.visit_option(option);
+                    }
+                }
             }
         }
 
@@ -1367,7 +1552,7 @@ macro_rules! make_visitor {
         ) {
             use Format::*;
             match format {
-                Bytes | Json | Text | Csv { .. } => (),
+                Bytes | Json { .. } | Text | Csv { .. } => (),
                 Avro(avro_schema) => visitor.visit_avro_schema(avro_schema),
                 Protobuf {message_name, schema} => {
                     visitor.visit_literal_string(message_name);
@@ -1415,12 +1600,16 @@ macro_rules! make_visitor {
         pub fn visit_create_sink<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             name: &'ast $($mut)* ObjectName,
+            in_cluster: Option<&'ast $($mut)* Ident>,
             from: &'ast $($mut)* ObjectName,
             connector: &'ast $($mut)* Connector,
             format: &'ast $($mut)* Format,
             _if_not_exists: bool,
         ) {
             visitor.visit_object_name(name);
+            if let Some(in_cluster) = in_cluster {
+                visitor.visit_ident(in_cluster);
+            }
             visitor.visit_object_name(from);
             visitor.visit_connector(connector);
             visitor.visit_format(format);
@@ -1450,6 +1639,7 @@ macro_rules! make_visitor {
         pub fn visit_create_view<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             name: &'ast $($mut)* ObjectName,
+            in_cluster: Option<&'ast $($mut)* Ident>,
             columns: &'ast $($mut)* [Ident],
             query: &'ast $($mut)* Query,
             _materialized: bool,
@@ -1457,6 +1647,9 @@ macro_rules! make_visitor {
             with_options: &'ast $($mut)* [SqlOption],
         ) {
             visitor.visit_object_name(name);
+            if let Some(in_cluster) = in_cluster {
+                visitor.visit_ident(in_cluster);
+            }
             for column in columns {
                 visitor.visit_ident(column);
             }
@@ -1469,17 +1662,36 @@ macro_rules! make_visitor {
         pub fn visit_create_index<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             name: &'ast $($mut)* Ident,
+            in_cluster: Option<&'ast $($mut)* Ident>,
+            replicas: &'ast $($mut)* [Ident],
             on_name: &'ast $($mut)* ObjectName,
             key_parts: &'ast $($mut)* Vec<Expr>,
             _if_not_exists: bool,
         ) {
             visitor.visit_ident(name);
+            if let Some(in_cluster) = in_cluster {
+                visitor.visit_ident(in_cluster);
+            }
+            for replica in replicas {
+                visitor.visit_ident(replica);
+            }
             visitor.visit_object_name(on_name);
             for key_part in key_parts {
                 visitor.visit_expr(key_part);
             }
         }
 
+        pub fn visit_create_type<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            name: &'ast $($mut)* ObjectName,
+            labels: &'ast $($mut)* [String],
+        ) {
+            visitor.visit_object_name(name);
+            for label in labels {
+                visitor.visit_literal_string(label);
+            }
+        }
+
         pub fn visit_create_table<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             name: &'ast $($mut)* ObjectName,
@@ -1527,7 +1739,9 @@ macro_rules! make_visitor {
         ) {
             match column_option {
                 ColumnOption::Null | ColumnOption::NotNull | ColumnOption::Unique { .. } => (),
-                ColumnOption::Default(expr) | ColumnOption::Check(expr) => visitor.visit_expr(expr),
+                ColumnOption::Default(expr)
+                | ColumnOption::Check(expr)
+                | ColumnOption::Generated(expr) => visitor.visit_expr(expr),
                 ColumnOption::ForeignKey {
                     foreign_table,
                     referred_columns,
@@ -1554,6 +1768,32 @@ macro_rules! make_visitor {
             visitor.visit_alter_table_operation(operation);
         }
 
+        pub fn visit_alter_tags<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            object_type: ObjectType,
+            name: &'ast $($mut)* ObjectName,
+            tags: &'ast $($mut)* [SqlOption],
+        ) {
+            visitor.visit_object_type(object_type);
+            visitor.visit_object_name(name);
+            for tag in tags {
+                visitor.visit_option(tag);
+            }
+        }
+
+        pub fn visit_comment<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            object_type: ObjectType,
+            name: &'ast $($mut)* ObjectName,
+            comment: Option<&'ast $($mut)* String>,
+        ) {
+            visitor.visit_object_type(object_type);
+            visitor.visit_object_name(name);
+            if let Some(comment) = comment {
+                visitor.visit_literal_string(comment);
+            }
+        }
+
         pub fn visit_alter_table_operation<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             operation: &'ast $($mut)* AlterTableOperation,
@@ -1741,6 +1981,42 @@ macro_rules! make_visitor {
             visitor.visit_object_name(source_name);
         }
 
+        pub fn visit_show_comments<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            from: Option<&'ast $($mut)* ObjectName>,
+        ) {
+            if let Some(from) = from {
+                visitor.visit_object_name(from);
+            }
+        }
+
+        pub fn visit_show_tags<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            from: Option<&'ast $($mut)* ObjectName>,
+        ) {
+            if let Some(from) = from {
+                visitor.visit_object_name(from);
+            }
+        }
+
+        pub fn visit_show_sink_progress<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            from: Option<&'ast $($mut)* ObjectName>,
+        ) {
+            if let Some(from) = from {
+                visitor.visit_object_name(from);
+            }
+        }
+
+        pub fn visit_show_source_errors<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            from: Option<&'ast $($mut)* ObjectName>,
+        ) {
+            if let Some(from) = from {
+                visitor.visit_object_name(from);
+            }
+        }
+
         pub fn visit_show_statement_filter<'ast, V: $name<'ast> + ?Sized>(
             visitor: &mut V,
             filter: &'ast $($mut)* ShowStatementFilter,
@@ -1783,8 +2059,60 @@ macro_rules! make_visitor {
             }
         }
 
-        pub fn visit_tail<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* ObjectName) {
+        pub fn visit_tail<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            name: &'ast $($mut)* ObjectName,
+            columns: &'ast $($mut)* [Ident],
+            selection: Option<&'ast $($mut)* Expr>,
+        ) {
             visitor.visit_object_name(name);
+            for column in columns {
+                visitor.visit_ident(column);
+            }
+            if let Some(selection) = selection {
+                visitor.visit_where(selection);
+            }
+        }
+
+        pub fn visit_listen<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, channel: &'ast $($mut)* Ident) {
+            visitor.visit_ident(channel);
+        }
+
+        pub fn visit_unlisten<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, channel: Option<&'ast $($mut)* Ident>) {
+            if let Some(channel) = channel {
+                visitor.visit_ident(channel);
+            }
+        }
+
+        pub fn visit_check_view<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* ObjectName) {
+            visitor.visit_object_name(name);
+        }
+
+        pub fn visit_install_demo<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* Ident) {
+            visitor.visit_ident(name);
+        }
+
+        pub fn visit_export_trace<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* ObjectName, path: &'ast $($mut)* String) {
+            visitor.visit_object_name(name);
+            visitor.visit_literal_string(path);
+        }
+
+        pub fn visit_import_trace<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* ObjectName, path: &'ast $($mut)* String) {
+            visitor.visit_object_name(name);
+            visitor.visit_literal_string(path);
+        }
+
+        pub fn visit_flush_sink<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, name: &'ast $($mut)* ObjectName) {
+            visitor.visit_object_name(name);
+        }
+
+        pub fn visit_flush_sinks<'ast, V: $name<'ast> + ?Sized>(
+            visitor: &mut V,
+            from: Option<&'ast $($mut)* ObjectName>,
+        ) {
+            if let Some(from) = from {
+                visitor.visit_object_name(from);
+            }
         }
 
         pub fn visit_explain<'ast, V: $name<'ast> + ?Sized>(visitor: &mut V, _stage: &'ast $($mut)* Stage, query: &'ast $($mut)* Query) {
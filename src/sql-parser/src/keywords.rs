@@ -63,11 +63,14 @@ define_keywords!(
     ALL,
     ALLOCATE,
     ALTER,
+    ALWAYS,
+    ANALYZE,
     AND,
     ANY,
     APPLY,
     ARE,
     ARN,
+    ARRANGEMENTS,
     ARRAY,
     ARRAY_AGG,
     ARRAY_MAX_CARDINALITY,
@@ -91,6 +94,7 @@ define_keywords!(
     BOOLEAN,
     BOTH,
     BROKER,
+    BUCKET,
     BY,
     BYTEA,
     CALL,
@@ -100,6 +104,7 @@ define_keywords!(
     CASCADED,
     CASE,
     CAST,
+    CATALOG,
     CEIL,
     CEILING,
     CENTURY,
@@ -109,18 +114,24 @@ define_keywords!(
     CHARACTER_LENGTH,
     CHAR_LENGTH,
     CHECK,
+    CIDR,
     CLOB,
     CLOSE,
+    CLUSTER,
     COALESCE,
     COLLATE,
     COLLECT,
     COLUMN,
     COLUMNS,
+    COMMENT,
+    COMMENTS,
     COMMIT,
     COMMITTED,
+    COMPACT,
     CONDITION,
     CONFLUENT,
     CONNECT,
+    CONNECTION,
     CONSISTENCY,
     CONSTRAINT,
     CONTAINS,
@@ -165,6 +176,7 @@ define_keywords!(
     DEFAULT,
     DELETE,
     DELIMITED,
+    DEMO,
     DENSE_RANK,
     DEREF,
     DESC,
@@ -178,14 +190,17 @@ define_keywords!(
     DROP,
     DYNAMIC,
     EACH,
+    ELASTICSEARCH,
     ELEMENT,
     ELSE,
     END,
     END_FRAME,
     END_PARTITION,
+    ENUM,
     ENVELOPE,
     EPOCH,
     EQUALS,
+    ERRORS,
     ESCAPE,
     EVERY,
     EXCEPT,
@@ -194,6 +209,7 @@ define_keywords!(
     EXISTS,
     EXP,
     EXPLAIN,
+    EXPORT,
     EXTENDED,
     EXTERNAL,
     EXTRACT,
@@ -206,6 +222,7 @@ define_keywords!(
     FIRST_VALUE,
     FLOAT,
     FLOOR,
+    FLUSH,
     FOLLOWING,
     FOR,
     FOREIGN,
@@ -216,6 +233,7 @@ define_keywords!(
     FULL,
     FUNCTION,
     FUSION,
+    GENERATED,
     GET,
     GLOBAL,
     GRANT,
@@ -224,20 +242,25 @@ define_keywords!(
     GROUPS,
     HAVING,
     HEADER,
+    HISTORY,
     HOLD,
     HOUR,
     HOURS,
     IDENTITY,
     IF,
     IMMEDIATE,
+    IMPORT,
     IN,
+    INCLUDE,
     INDEX,
     INDEXES,
     INDICATOR,
+    INET,
     INNER,
     INOUT,
     INSENSITIVE,
     INSERT,
+    INSTALL,
     INT,
     INTEGER,
     INTERSECT,
@@ -267,6 +290,7 @@ define_keywords!(
     LIKE,
     LIKE_REGEX,
     LIMIT,
+    LISTEN,
     LN,
     LOCAL,
     LOCALTIME,
@@ -291,7 +315,10 @@ define_keywords!(
     MODULE,
     MONTH,
     MONTHS,
+    MQTT,
     MULTISET,
+    MYSQL,
+    NATS,
     NATURAL,
     NCHAR,
     NCLOB,
@@ -331,9 +358,12 @@ define_keywords!(
     PERCENTILE_DISC,
     PERIOD,
     PLAN,
+    PLUGIN,
+    POINT,
     PORTION,
     POSITION,
     POSITION_REGEX,
+    POSTGRES,
     POWER,
     PRECEDES,
     PRECEDING,
@@ -341,8 +371,10 @@ define_keywords!(
     PREPARE,
     PRIMARY,
     PROCEDURE,
+    PROGRESS,
     PROTOBUF,
     QUARTER,
+    QUIESCE,
     RANGE,
     RANK,
     BYTES,
@@ -350,6 +382,7 @@ define_keywords!(
     READS,
     REAL,
     RECURSIVE,
+    REDIS,
     REF,
     REFERENCES,
     REFERENCING,
@@ -368,8 +401,10 @@ define_keywords!(
     RELEASE,
     REPEATABLE,
     REPLACE,
+    REPLICAS,
     RESTRICT,
     RESULT,
+    RESUME,
     RETURN,
     RETURNS,
     REVOKE,
@@ -379,6 +414,7 @@ define_keywords!(
     ROW,
     ROW_NUMBER,
     ROWS,
+    S3,
     SAVEPOINT,
     SCHEMA,
     SCHEMAS,
@@ -416,6 +452,7 @@ define_keywords!(
     STDIN,
     STORED,
     STRING,
+    SUBJECT,
     SUBMULTISET,
     SUBSTRING,
     SUBSTRING_REGEX,
@@ -428,6 +465,7 @@ define_keywords!(
     TABLE,
     TABLES,
     TABLESAMPLE,
+    TAGS,
     TAIL,
     TEXT,
     THEN,
@@ -440,6 +478,7 @@ define_keywords!(
     TIMEZONE_MINUTE,
     TO,
     TOPIC,
+    TRACE,
     TRAILING,
     TRANSACTION,
     TRANSLATE,
@@ -451,18 +490,21 @@ define_keywords!(
     TRIM_ARRAY,
     TRUE,
     TRUNCATE,
+    TYPE,
     UESCAPE,
     UNBOUNDED,
     UNCOMMITTED,
     UNION,
     UNIQUE,
     UNKNOWN,
+    UNLISTEN,
     UNNEST,
     UPDATE,
     UPPER,
     USER,
     USING,
     UUID,
+    VACUUM,
     VALUE,
     VALUES,
     VALUE_OF,
@@ -166,6 +166,73 @@ impl Parser {
                     "INSERT" => Ok(self.parse_insert()?),
                     "UPDATE" => Ok(self.parse_update()?),
                     "ALTER" => Ok(self.parse_alter()?),
+                    "COMMENT" => Ok(self.parse_comment()?),
+                    "COMPACT" => {
+                        self.expect_keyword("CATALOG")?;
+                        Ok(Statement::CompactCatalog { vacuum: false })
+                    }
+                    "VACUUM" => {
+                        self.expect_keyword("CATALOG")?;
+                        Ok(Statement::CompactCatalog { vacuum: true })
+                    }
+                    "CHECK" => match self.expect_one_of_keywords(&["CATALOG", "VIEW"])? {
+                        "CATALOG" => Ok(Statement::CheckCatalog),
+                        "VIEW" => Ok(Statement::CheckView {
+                            name: self.parse_object_name()?,
+                        }),
+                        _ => unreachable!(),
+                    },
+                    "INSTALL" => {
+                        self.expect_keyword("DEMO")?;
+                        Ok(Statement::InstallDemo {
+                            name: self.parse_identifier()?,
+                        })
+                    }
+                    "EXPORT" => {
+                        self.expect_keyword("TRACE")?;
+                        let name = self.parse_object_name()?;
+                        self.expect_keyword("TO")?;
+                        let path = self.parse_literal_string()?;
+                        let history = self.parse_keywords(vec!["WITH", "HISTORY"]);
+                        Ok(Statement::ExportTrace {
+                            name,
+                            path,
+                            history,
+                        })
+                    }
+                    "IMPORT" => {
+                        self.expect_keyword("TRACE")?;
+                        let name = self.parse_object_name()?;
+                        self.expect_keyword("FROM")?;
+                        Ok(Statement::ImportTrace {
+                            name,
+                            path: self.parse_literal_string()?,
+                        })
+                    }
+                    "QUIESCE" => {
+                        let as_of = if self.parse_keywords(vec!["AS", "OF"]) {
+                            Some(self.parse_literal_uint()?)
+                        } else {
+                            None
+                        };
+                        Ok(Statement::Quiesce { as_of })
+                    }
+                    "RESUME" => Ok(Statement::Resume),
+                    "FLUSH" => {
+                        if self.parse_keyword("SINKS") {
+                            let from = if self.parse_one_of_keywords(&["FROM", "IN"]).is_some() {
+                                Some(self.parse_object_name()?)
+                            } else {
+                                None
+                            };
+                            Ok(Statement::FlushSinks { from })
+                        } else {
+                            self.expect_keyword("SINK")?;
+                            Ok(Statement::FlushSink {
+                                name: self.parse_object_name()?,
+                            })
+                        }
+                    }
                     "COPY" => Ok(self.parse_copy()?),
                     "SET" => Ok(self.parse_set()?),
                     "SHOW" => Ok(self.parse_show()?),
@@ -176,9 +243,18 @@ impl Parser {
                     "BEGIN" => Ok(self.parse_begin()?),
                     "COMMIT" => Ok(self.parse_commit()?),
                     "ROLLBACK" => Ok(self.parse_rollback()?),
-                    "TAIL" => Ok(Statement::Tail {
-                        name: self.parse_object_name()?,
+                    "TAIL" => Ok(self.parse_tail()?),
+                    "LISTEN" => Ok(Statement::Listen {
+                        channel: self.parse_identifier()?,
                     }),
+                    "UNLISTEN" => {
+                        let channel = if self.consume_token(&Token::Mult) {
+                            None
+                        } else {
+                            Some(self.parse_identifier()?)
+                        };
+                        Ok(Statement::Unlisten { channel })
+                    }
                     "EXPLAIN" => Ok(self.parse_explain()?),
                     _ => parser_err!(
                         self,
@@ -878,6 +954,10 @@ impl Parser {
             Token::JsonDeletePath => Some(BinaryOperator::JsonDeletePath),
             Token::JsonContainsPath => Some(BinaryOperator::JsonContainsPath),
             Token::JsonApplyPathPredicate => Some(BinaryOperator::JsonApplyPathPredicate),
+            Token::InetContainedBy => Some(BinaryOperator::InetContainedBy),
+            Token::InetContainedByOrEq => Some(BinaryOperator::InetContainedByOrEq),
+            Token::InetContains => Some(BinaryOperator::InetContains),
+            Token::InetContainsOrEq => Some(BinaryOperator::InetContainsOrEq),
             Token::Word(ref k) => match k.keyword.as_ref() {
                 "AND" => Some(BinaryOperator::And),
                 "OR" => Some(BinaryOperator::Or),
@@ -1167,6 +1247,12 @@ impl Parser {
                 Token::Eq | Token::Lt | Token::LtEq | Token::Neq | Token::Gt | Token::GtEq => {
                     Ok(20)
                 }
+                // As in PostgreSQL, these bind more tightly than comparisons
+                // but less tightly than arithmetic.
+                Token::InetContainedBy
+                | Token::InetContainedByOrEq
+                | Token::InetContains
+                | Token::InetContainsOrEq => Ok(25),
                 Token::Plus | Token::Minus => Ok(Self::PLUS_MINUS_PREC),
                 Token::Mult | Token::Div | Token::Mod => Ok(40),
                 Token::DoubleColon => Ok(50),
@@ -1454,10 +1540,12 @@ impl Parser {
             self.parse_create_sink()
         } else if self.parse_keyword("INDEX") {
             self.parse_create_index()
+        } else if self.parse_keyword("TYPE") {
+            self.parse_create_type()
         } else {
             self.expected(
                 self.peek_range(),
-                "DATABASE, SCHEMA, [MATERIALIZED] VIEW, SOURCE, SINK, or INDEX after CREATE",
+                "DATABASE, SCHEMA, [MATERIALIZED] VIEW, SOURCE, SINK, INDEX, or TYPE after CREATE",
                 self.peek_token(),
             )
         }
@@ -1500,8 +1588,14 @@ impl Parser {
             Format::Regex(regex)
         } else if self.parse_keyword("CSV") {
             self.expect_keyword("WITH")?;
-            let n_cols = self.parse_literal_uint()? as usize;
-            self.expect_keyword("COLUMNS")?;
+            let columns = if self.parse_keyword("COLUMNS") {
+                CsvColumns::Typed(self.parse_format_columns()?)
+            } else {
+                let n_cols = self.parse_literal_uint()? as usize;
+                self.expect_keyword("COLUMNS")?;
+                CsvColumns::Count(n_cols)
+            };
+            let header = self.parse_keyword("HEADER");
             let delimiter = if self.parse_keywords(vec!["DELIMITED", "BY"]) {
                 let s = self.parse_literal_string()?;
                 match s.len() {
@@ -1513,9 +1607,18 @@ impl Parser {
             } else {
                 ','
             };
-            Format::Csv { n_cols, delimiter }
+            Format::Csv {
+                columns,
+                header,
+                delimiter,
+            }
         } else if self.parse_keyword("JSON") {
-            Format::Json
+            let columns = if self.parse_keywords(vec!["WITH", "COLUMNS"]) {
+                self.parse_format_columns()?
+            } else {
+                vec![]
+            };
+            Format::Json { columns }
         } else if self.parse_keyword("TEXT") {
             Format::Text
         } else if self.parse_keyword("BYTES") {
@@ -1530,8 +1633,28 @@ impl Parser {
         Ok(format)
     }
 
+    /// Parses the parenthesized list of column names and types into which a
+    /// format decodes each record, as in `CSV WITH COLUMNS (a int, b text)`.
+    fn parse_format_columns(&mut self) -> Result<Vec<ColumnDef>, ParserError> {
+        self.expect_token(&Token::LParen)?;
+        let columns = self.parse_comma_separated(|parser| {
+            Ok(ColumnDef {
+                name: parser.parse_identifier()?,
+                data_type: parser.parse_data_type()?,
+                collation: None,
+                options: vec![],
+            })
+        })?;
+        self.expect_token(&Token::RParen)?;
+        Ok(columns)
+    }
+
     pub fn parse_avro_schema(&mut self) -> Result<AvroSchema, ParserError> {
-        let avro_schema = if self.parse_keywords(vec!["CONFLUENT", "SCHEMA", "REGISTRY"]) {
+        // `CONFLUENT` may be omitted, as no other schema registry is
+        // supported.
+        let avro_schema = if self.parse_keywords(vec!["CONFLUENT", "SCHEMA", "REGISTRY"])
+            || self.parse_keywords(vec!["SCHEMA", "REGISTRY"])
+        {
             let url = self.parse_literal_string()?;
             let seed = if self.parse_keyword("SEED") {
                 let key_schema = if self.parse_keyword("KEY") {
@@ -1584,6 +1707,15 @@ impl Parser {
         Ok(envelope)
     }
 
+    fn parse_source_metadata(&mut self) -> Result<SourceMetadata, ParserError> {
+        match self.expect_one_of_keywords(&["OFFSET", "PARTITION", "TIMESTAMP"])? {
+            "OFFSET" => Ok(SourceMetadata::Offset),
+            "PARTITION" => Ok(SourceMetadata::Partition),
+            "TIMESTAMP" => Ok(SourceMetadata::Timestamp),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn parse_create_source(&mut self) -> Result<Statement, ParserError> {
         let materialized = self.parse_keyword("MATERIALIZED");
         self.expect_keyword("SOURCE")?;
@@ -1591,18 +1723,27 @@ impl Parser {
         let name = self.parse_object_name()?;
         self.expect_keyword("FROM")?;
         let connector = self.parse_connector()?;
-        let format = self.parse_format()?;
+        let format = match connector {
+            Connector::Postgres { .. } | Connector::Mysql { .. } => None,
+            _ => Some(self.parse_format()?),
+        };
         let envelope = if self.parse_keyword("ENVELOPE") {
             self.parse_envelope()?
         } else {
             Default::default()
         };
+        let include_metadata = if self.parse_keyword("INCLUDE") {
+            self.parse_comma_separated(Parser::parse_source_metadata)?
+        } else {
+            vec![]
+        };
 
         Ok(Statement::CreateSource {
             name,
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         })
@@ -1611,6 +1752,7 @@ impl Parser {
     pub fn parse_create_sink(&mut self) -> Result<Statement, ParserError> {
         let if_not_exists = self.parse_if_not_exists()?;
         let name = self.parse_object_name()?;
+        let in_cluster = self.parse_in_cluster()?;
         self.expect_keyword("FROM")?;
         let from = self.parse_object_name()?;
         self.expect_keyword("INTO")?;
@@ -1618,6 +1760,7 @@ impl Parser {
         let format = self.parse_format()?;
         Ok(Statement::CreateSink {
             name,
+            in_cluster,
             from,
             connector,
             format,
@@ -1626,7 +1769,30 @@ impl Parser {
     }
 
     pub fn parse_connector(&mut self) -> Result<Connector, ParserError> {
-        match self.expect_one_of_keywords(&["FILE", "KAFKA", "KINESIS"])? {
+        match self.expect_one_of_keywords(&[
+            "ELASTICSEARCH",
+            "FILE",
+            "KAFKA",
+            "KINESIS",
+            "MQTT",
+            "MYSQL",
+            "NATS",
+            "PLUGIN",
+            "POSTGRES",
+            "REDIS",
+            "S3",
+        ])? {
+            "ELASTICSEARCH" => {
+                let url = self.parse_literal_string()?;
+                self.expect_keyword("INDEX")?;
+                let index = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::Elasticsearch {
+                    url,
+                    index,
+                    with_options,
+                })
+            }
             "FILE" => {
                 let path = self.parse_literal_string()?;
                 let with_options = self.parse_with_options()?;
@@ -1650,6 +1816,75 @@ impl Parser {
                 let with_options = self.parse_with_options()?;
                 Ok(Connector::Kinesis { arn, with_options })
             }
+            "MQTT" => {
+                self.expect_keyword("BROKER")?;
+                let broker = self.parse_literal_string()?;
+                self.expect_keyword("TOPIC")?;
+                let topic = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::Mqtt {
+                    broker,
+                    topic,
+                    with_options,
+                })
+            }
+            "MYSQL" => {
+                let url = self.parse_literal_string()?;
+                self.expect_keyword("TABLE")?;
+                let table = self.parse_literal_string()?;
+                let columns = self.parse_format_columns()?;
+                Ok(Connector::Mysql {
+                    url,
+                    table,
+                    columns,
+                })
+            }
+            "NATS" => {
+                let url = self.parse_literal_string()?;
+                self.expect_keyword("SUBJECT")?;
+                let subject = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::Nats {
+                    url,
+                    subject,
+                    with_options,
+                })
+            }
+            "PLUGIN" => {
+                let name = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::Plugin { name, with_options })
+            }
+            "POSTGRES" => {
+                self.expect_keyword("CONNECTION")?;
+                let conn = self.parse_literal_string()?;
+                self.expect_keyword("TABLE")?;
+                let table = self.parse_literal_string()?;
+                let columns = if self.peek_token() == Some(Token::LParen) {
+                    self.parse_format_columns()?
+                } else {
+                    vec![]
+                };
+                Ok(Connector::Postgres {
+                    conn,
+                    table,
+                    columns,
+                })
+            }
+            "REDIS" => {
+                let url = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::Redis { url, with_options })
+            }
+            "S3" => {
+                self.expect_keyword("BUCKET")?;
+                let bucket = self.parse_literal_string()?;
+                let with_options = self.parse_with_options()?;
+                Ok(Connector::S3 {
+                    bucket,
+                    with_options,
+                })
+            }
             _ => unreachable!(),
         }
     }
@@ -1666,6 +1901,11 @@ impl Parser {
         // Many dialects support `OR REPLACE` | `OR ALTER` right after `CREATE`, but we don't (yet).
         // ANSI SQL and Postgres support RECURSIVE here, but we don't support it either.
         let name = self.parse_object_name()?;
+        let in_cluster = if materialized {
+            self.parse_in_cluster()?
+        } else {
+            None
+        };
         let columns = self.parse_parenthesized_column_list(Optional)?;
         let with_options = self.parse_with_options()?;
         self.expect_keyword("AS")?;
@@ -1673,6 +1913,7 @@ impl Parser {
         // Optional `WITH [ CASCADED | LOCAL ] CHECK OPTION` is widely supported here.
         Ok(Statement::CreateView {
             name,
+            in_cluster,
             columns,
             query,
             materialized,
@@ -1684,6 +1925,15 @@ impl Parser {
     pub fn parse_create_index(&mut self) -> Result<Statement, ParserError> {
         let if_not_exists = self.parse_if_not_exists()?;
         let name = self.parse_identifier()?;
+        let in_cluster = self.parse_in_cluster()?;
+        let replicas = if self.parse_keyword("REPLICAS") {
+            self.expect_token(&Token::LParen)?;
+            let replicas = self.parse_comma_separated(Parser::parse_identifier)?;
+            self.expect_token(&Token::RParen)?;
+            replicas
+        } else {
+            vec![]
+        };
         self.expect_keyword("ON")?;
         let on_name = self.parse_object_name()?;
         self.expect_token(&Token::LParen)?;
@@ -1700,12 +1950,33 @@ impl Parser {
         };
         Ok(Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         })
     }
 
+    pub fn parse_create_type(&mut self) -> Result<Statement, ParserError> {
+        let name = self.parse_object_name()?;
+        self.expect_keywords(&["AS", "ENUM"])?;
+        self.expect_token(&Token::LParen)?;
+        let labels = self.parse_comma_separated(Parser::parse_literal_string)?;
+        self.expect_token(&Token::RParen)?;
+        Ok(Statement::CreateType { name, labels })
+    }
+
+    /// Parses an optional `IN CLUSTER <name>` clause, which assigns the
+    /// dataflow being created to a compute cluster.
+    fn parse_in_cluster(&mut self) -> Result<Option<Ident>, ParserError> {
+        if self.parse_keywords(vec!["IN", "CLUSTER"]) {
+            Ok(Some(self.parse_identifier()?))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn parse_if_exists(&mut self) -> Result<bool, ParserError> {
         if self.parse_keyword("IF") {
             self.expect_keyword("EXISTS")?;
@@ -1726,7 +1997,7 @@ impl Parser {
 
     pub fn parse_drop(&mut self) -> Result<Statement, ParserError> {
         let object_type = match self.parse_one_of_keywords(&[
-            "DATABASE", "SCHEMA", "TABLE", "VIEW", "SOURCE", "SINK", "INDEX",
+            "DATABASE", "SCHEMA", "TABLE", "VIEW", "SOURCE", "SINK", "INDEX", "TYPE",
         ]) {
             Some("DATABASE") => {
                 return Ok(Statement::DropDatabase {
@@ -1740,10 +2011,11 @@ impl Parser {
             Some("SOURCE") => ObjectType::Source,
             Some("SINK") => ObjectType::Sink,
             Some("INDEX") => ObjectType::Index,
+            Some("TYPE") => ObjectType::Type,
             _ => {
                 return self.expected(
                     self.peek_range(),
-                    "DATABASE, SCHEMA, TABLE, VIEW, SOURCE, SINK, or INDEX after DROP",
+                    "DATABASE, SCHEMA, TABLE, VIEW, SOURCE, SINK, INDEX, or TYPE after DROP",
                     self.peek_token(),
                 )
             }
@@ -1870,6 +2142,12 @@ impl Parser {
             let expr = self.parse_expr()?;
             self.expect_token(&Token::RParen)?;
             ColumnOption::Check(expr)
+        } else if self.parse_keywords(vec!["GENERATED", "ALWAYS", "AS"]) {
+            self.expect_token(&Token::LParen)?;
+            let expr = self.parse_expr()?;
+            self.expect_token(&Token::RParen)?;
+            self.expect_keyword("STORED")?;
+            ColumnOption::Generated(expr)
         } else {
             return self.expected(self.peek_range(), "column option", self.peek_token());
         };
@@ -1950,8 +2228,53 @@ impl Parser {
         Ok(SqlOption { name, value })
     }
 
+    /// Parses the type of an object that can be commented on or tagged,
+    /// e.g., the `VIEW` in `COMMENT ON VIEW`.
+    fn parse_annotatable_object_type(&mut self, context: &str) -> Result<ObjectType, ParserError> {
+        match self.parse_one_of_keywords(&["SOURCE", "VIEW", "SINK", "INDEX"]) {
+            Some("SOURCE") => Ok(ObjectType::Source),
+            Some("VIEW") => Ok(ObjectType::View),
+            Some("SINK") => Ok(ObjectType::Sink),
+            Some("INDEX") => Ok(ObjectType::Index),
+            _ => self.expected(
+                self.peek_range(),
+                &format!("SOURCE, VIEW, SINK, or INDEX after {}", context),
+                self.peek_token(),
+            ),
+        }
+    }
+
+    pub fn parse_comment(&mut self) -> Result<Statement, ParserError> {
+        self.expect_keyword("ON")?;
+        let object_type = self.parse_annotatable_object_type("COMMENT ON")?;
+        let name = self.parse_object_name()?;
+        self.expect_keyword("IS")?;
+        let comment = if self.parse_keyword("NULL") {
+            None
+        } else {
+            Some(self.parse_literal_string()?)
+        };
+        Ok(Statement::Comment {
+            object_type,
+            name,
+            comment,
+        })
+    }
+
     pub fn parse_alter(&mut self) -> Result<Statement, ParserError> {
-        self.expect_keyword("TABLE")?;
+        if !self.parse_keyword("TABLE") {
+            let object_type = self.parse_annotatable_object_type("ALTER")?;
+            let name = self.parse_object_name()?;
+            self.expect_keywords(&["SET", "TAGS"])?;
+            self.expect_token(&Token::LParen)?;
+            let tags = self.parse_comma_separated(Parser::parse_sql_option)?;
+            self.expect_token(&Token::RParen)?;
+            return Ok(Statement::AlterTags {
+                object_type,
+                name,
+                tags,
+            });
+        }
         let _ = self.parse_keyword("ONLY");
         let table_name = self.parse_object_name()?;
         let operation = if self.parse_keyword("ADD") {
@@ -2173,11 +2496,13 @@ impl Parser {
                     DataType::Decimal(precision, scale)
                 }
                 "JSON" | "JSONB" => DataType::Jsonb,
-                _ => self.expected(
-                    self.peek_prev_range(),
-                    "a known data type",
-                    Some(Token::Word(k)),
-                )?,
+                "POINT" => DataType::Point,
+                "INET" => DataType::Inet,
+                "CIDR" => DataType::Cidr,
+                _ => {
+                    self.prev_token();
+                    DataType::Custom(self.parse_object_name()?)
+                }
             },
             other => self.expected(self.peek_prev_range(), "a data type name", other)?,
         };
@@ -2633,6 +2958,32 @@ impl Parser {
             Ok(Statement::ShowCreateSource {
                 source_name: self.parse_object_name()?,
             })
+        } else if let Some(kind) = self.parse_one_of_keywords(&["COMMENTS", "TAGS"]) {
+            let from = if self.parse_one_of_keywords(&["FROM", "IN"]).is_some() {
+                Some(self.parse_object_name()?)
+            } else {
+                None
+            };
+            Ok(match kind {
+                "COMMENTS" => Statement::ShowComments { from },
+                _ => Statement::ShowTags { from },
+            })
+        } else if self.parse_keywords(vec!["SINK", "PROGRESS"]) {
+            let from = if self.parse_one_of_keywords(&["FROM", "IN"]).is_some() {
+                Some(self.parse_object_name()?)
+            } else {
+                None
+            };
+            Ok(Statement::ShowSinkProgress { from })
+        } else if self.parse_keywords(vec!["SOURCE", "ERRORS"]) {
+            let from = if self.parse_one_of_keywords(&["FROM", "IN"]).is_some() {
+                Some(self.parse_object_name()?)
+            } else {
+                None
+            };
+            Ok(Statement::ShowSourceErrors { from })
+        } else if self.parse_keyword("ARRANGEMENTS") {
+            Ok(Statement::ShowArrangements)
         } else {
             Ok(Statement::ShowVariable {
                 variable: self.parse_identifier()?,
@@ -3069,15 +3420,38 @@ impl Parser {
         }
     }
 
-    /// Parse an `EXPLAIN [DATAFLOW | PLAN] FOR` statement, assuming that the `EXPLAIN` token
+    /// Parse a `TAIL` statement, assuming that the `TAIL` token has already
+    /// been consumed.
+    pub fn parse_tail(&mut self) -> Result<Statement, ParserError> {
+        let name = self.parse_object_name()?;
+        let columns = self.parse_parenthesized_column_list(Optional)?;
+        let selection = if self.parse_keyword("WHERE") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        Ok(Statement::Tail {
+            name,
+            columns,
+            selection,
+        })
+    }
+
+    /// Parse an `EXPLAIN [DATAFLOW | PLAN | ANALYZE] FOR` statement, assuming that the `EXPLAIN` token
     /// has already been consumed.
     pub fn parse_explain(&mut self) -> Result<Statement, ParserError> {
         let stage = if self.parse_keyword("DATAFLOW") {
             Stage::Dataflow
         } else if self.parse_keyword("PLAN") {
             Stage::Plan
+        } else if self.parse_keyword("ANALYZE") {
+            Stage::Analyze
         } else {
-            self.expected(self.peek_range(), "DATAFLOW or PLAN", self.peek_token())?
+            self.expected(
+                self.peek_range(),
+                "DATAFLOW, PLAN, or ANALYZE",
+                self.peek_token(),
+            )?
         };
         self.expect_keyword("FOR")?;
 
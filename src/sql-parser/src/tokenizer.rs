@@ -100,6 +100,15 @@ pub enum Token {
     JsonContainsPath,
     /// Json apply path predicate operator '@@'
     JsonApplyPathPredicate,
+    // Network address operators are documented at https://www.postgresql.org/docs/current/functions-net.html
+    /// Inet is contained by operator '<<'
+    InetContainedBy,
+    /// Inet is contained by or equals operator '<<='
+    InetContainedByOrEq,
+    /// Inet contains operator '>>'
+    InetContains,
+    /// Inet contains or equals operator '>>='
+    InetContainsOrEq,
     /// Left parenthesis `(`
     LParen,
     /// Right parenthesis `)`
@@ -161,6 +170,10 @@ impl fmt::Display for Token {
             Token::JsonDeletePath => f.write_str("#-"),
             Token::JsonContainsPath => f.write_str("@?"),
             Token::JsonApplyPathPredicate => f.write_str("@@"),
+            Token::InetContainedBy => f.write_str("<<"),
+            Token::InetContainedByOrEq => f.write_str("<<="),
+            Token::InetContains => f.write_str(">>"),
+            Token::InetContainsOrEq => f.write_str(">>="),
             Token::LParen => f.write_str("("),
             Token::RParen => f.write_str(")"),
             Token::Period => f.write_str("."),
@@ -481,6 +494,15 @@ impl Tokenizer {
                         Some('=') => self.consume_and_return(chars, Token::LtEq),
                         Some('>') => self.consume_and_return(chars, Token::Neq),
                         Some('@') => self.consume_and_return(chars, Token::JsonContainedInJson),
+                        Some('<') => {
+                            chars.next(); // consume
+                            match chars.peek() {
+                                Some('=') => {
+                                    self.consume_and_return(chars, Token::InetContainedByOrEq)
+                                }
+                                _ => Ok(Some(Token::InetContainedBy)),
+                            }
+                        }
                         _ => Ok(Some(Token::Lt)),
                     }
                 }
@@ -488,6 +510,15 @@ impl Tokenizer {
                     chars.next(); // consume
                     match chars.peek() {
                         Some('=') => self.consume_and_return(chars, Token::GtEq),
+                        Some('>') => {
+                            chars.next(); // consume
+                            match chars.peek() {
+                                Some('=') => {
+                                    self.consume_and_return(chars, Token::InetContainsOrEq)
+                                }
+                                _ => Ok(Some(Token::InetContains)),
+                            }
+                        }
                         _ => Ok(Some(Token::Gt)),
                     }
                 }
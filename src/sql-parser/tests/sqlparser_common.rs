@@ -975,6 +975,10 @@ fn parse_cast() {
         "SELECT CAST(id AS decimal) FROM customer",
         "SELECT CAST(id AS numeric) FROM customer",
     );
+
+    verified_stmt("SELECT CAST(location AS point) FROM vehicles");
+    verified_stmt("SELECT CAST(src AS inet), CAST(prefix AS cidr) FROM flows");
+    verified_stmt("SELECT CAST(status AS shop.order_status) FROM orders");
 }
 
 #[test]
@@ -1190,6 +1194,34 @@ fn parse_create_table_trailing_comma() {
     one_statement_parses_to(sql, "CREATE TABLE foo (bar int)");
 }
 
+#[test]
+fn parse_create_table_with_generated_columns() {
+    let sql = "CREATE TABLE t (a int, b int DEFAULT 1, c int GENERATED ALWAYS AS (a + b) STORED)";
+    match verified_stmt(sql) {
+        Statement::CreateTable { columns, .. } => {
+            assert_eq!(
+                columns[2].options,
+                vec![ColumnOptionDef {
+                    name: None,
+                    option: ColumnOption::Generated(verified_expr("a + b")),
+                }]
+            );
+        }
+        _ => unreachable!(),
+    }
+
+    let res = parse_sql_statements("CREATE TABLE t (a int GENERATED ALWAYS AS (1))");
+    assert_eq!(
+        ("\
+Parse error:
+CREATE TABLE t (a int GENERATED ALWAYS AS (1))
+                                             ^
+Expected STORED, found: )"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
 #[test]
 fn parse_create_table_empty() {
     // Zero-column tables are weird, but supported by at least PostgreSQL.
@@ -2639,6 +2671,184 @@ fn parse_show_create_source() {
     )
 }
 
+#[test]
+fn parse_comment() {
+    assert_eq!(
+        verified_stmt("COMMENT ON VIEW foo.bar IS 'owned by ''ops'''"),
+        Statement::Comment {
+            object_type: ObjectType::View,
+            name: ObjectName(vec!["foo".into(), "bar".into()]),
+            comment: Some("owned by 'ops'".into()),
+        }
+    );
+    assert_eq!(
+        verified_stmt("COMMENT ON SOURCE foo IS NULL"),
+        Statement::Comment {
+            object_type: ObjectType::Source,
+            name: ObjectName(vec!["foo".into()]),
+            comment: None,
+        }
+    );
+
+    let res = parse_sql_statements("COMMENT ON DATABASE foo IS 'bar'");
+    assert_eq!(
+        ("\
+Parse error:
+COMMENT ON DATABASE foo IS 'bar'
+           ^^^^^^^^
+Expected SOURCE, VIEW, SINK, or INDEX after COMMENT ON, found: DATABASE"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
+#[test]
+fn parse_alter_tags() {
+    assert_eq!(
+        verified_stmt("ALTER SINK foo SET TAGS (owner = 'ops', tier = NULL)"),
+        Statement::AlterTags {
+            object_type: ObjectType::Sink,
+            name: ObjectName(vec!["foo".into()]),
+            tags: vec![
+                SqlOption {
+                    name: "owner".into(),
+                    value: Value::SingleQuotedString("ops".into()),
+                },
+                SqlOption {
+                    name: "tier".into(),
+                    value: Value::Null,
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn parse_show_comments_and_tags() {
+    assert_eq!(
+        verified_stmt("SHOW COMMENTS"),
+        Statement::ShowComments { from: None }
+    );
+    assert_eq!(
+        verified_stmt("SHOW TAGS FROM foo"),
+        Statement::ShowTags {
+            from: Some(ObjectName(vec!["foo".into()])),
+        }
+    );
+}
+
+#[test]
+fn parse_show_sink_progress() {
+    assert_eq!(
+        verified_stmt("SHOW SINK PROGRESS"),
+        Statement::ShowSinkProgress { from: None }
+    );
+    assert_eq!(
+        verified_stmt("SHOW SINK PROGRESS FROM foo.bar"),
+        Statement::ShowSinkProgress {
+            from: Some(ObjectName(vec!["foo".into(), "bar".into()])),
+        }
+    );
+    one_statement_parses_to(
+        "SHOW SINK PROGRESS IN foo",
+        "SHOW SINK PROGRESS FROM foo",
+    );
+}
+
+#[test]
+fn parse_show_source_errors() {
+    assert_eq!(
+        verified_stmt("SHOW SOURCE ERRORS"),
+        Statement::ShowSourceErrors { from: None }
+    );
+    assert_eq!(
+        verified_stmt("SHOW SOURCE ERRORS FROM foo"),
+        Statement::ShowSourceErrors {
+            from: Some(ObjectName(vec!["foo".into()])),
+        }
+    );
+}
+
+#[test]
+fn parse_show_arrangements() {
+    assert_eq!(
+        verified_stmt("SHOW ARRANGEMENTS"),
+        Statement::ShowArrangements
+    );
+}
+
+#[test]
+fn parse_catalog_maintenance() {
+    assert_eq!(
+        verified_stmt("COMPACT CATALOG"),
+        Statement::CompactCatalog { vacuum: false }
+    );
+    assert_eq!(
+        verified_stmt("VACUUM CATALOG"),
+        Statement::CompactCatalog { vacuum: true }
+    );
+    assert_eq!(verified_stmt("CHECK CATALOG"), Statement::CheckCatalog);
+    assert_eq!(
+        verified_stmt("CHECK VIEW db.v"),
+        Statement::CheckView {
+            name: ObjectName(vec![Ident::new("db"), Ident::new("v")]),
+        }
+    );
+
+    let res = parse_sql_statements("VACUUM foo");
+    assert_eq!(
+        ("\
+Parse error:
+VACUUM foo
+       ^^^
+Expected CATALOG, found: foo"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
+#[test]
+fn parse_quiesce() {
+    assert_eq!(verified_stmt("QUIESCE"), Statement::Quiesce { as_of: None });
+    assert_eq!(
+        verified_stmt("QUIESCE AS OF 1587000000000"),
+        Statement::Quiesce {
+            as_of: Some(1587000000000)
+        }
+    );
+    assert_eq!(verified_stmt("RESUME"), Statement::Resume);
+}
+
+#[test]
+fn parse_flush_sinks() {
+    assert_eq!(
+        verified_stmt("FLUSH SINK foo"),
+        Statement::FlushSink {
+            name: ObjectName(vec![Ident::new("foo")])
+        }
+    );
+    assert_eq!(
+        verified_stmt("FLUSH SINKS"),
+        Statement::FlushSinks { from: None }
+    );
+    assert_eq!(
+        verified_stmt("FLUSH SINKS FROM public"),
+        Statement::FlushSinks {
+            from: Some(ObjectName(vec![Ident::new("public")]))
+        }
+    );
+    let res = parse_sql_statements("FLUSH foo");
+    assert_eq!(
+        ("\
+Parse error:
+FLUSH foo
+      ^^^
+Expected SINK, found: foo"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
 #[test]
 fn parse_simple_case_expr() {
     // ANSI calls a CASE expression with an operand "<simple case>"
@@ -3400,6 +3610,7 @@ fn parse_create_view() {
     match verified_stmt(sql) {
         Statement::CreateView {
             name,
+            in_cluster,
             columns,
             query,
             materialized,
@@ -3407,6 +3618,7 @@ fn parse_create_view() {
             with_options,
         } => {
             assert_eq!("myschema.myview", name.to_string());
+            assert_eq!(None, in_cluster);
             assert_eq!(Vec::<Ident>::new(), columns);
             assert_eq!("SELECT foo FROM bar", query.to_string());
             assert!(!materialized);
@@ -3455,6 +3667,7 @@ fn parse_create_view_with_columns() {
     match verified_stmt(sql) {
         Statement::CreateView {
             name,
+            in_cluster,
             columns,
             with_options,
             query,
@@ -3462,6 +3675,7 @@ fn parse_create_view_with_columns() {
             replace,
         } => {
             assert_eq!("v", name.to_string());
+            assert_eq!(None, in_cluster);
             assert_eq!(columns, vec![Ident::new("has"), Ident::new("cols")]);
             assert_eq!(with_options, vec![]);
             assert_eq!("SELECT 1, 2", query.to_string());
@@ -3478,6 +3692,7 @@ fn parse_create_materialized_view() {
     match verified_stmt(sql) {
         Statement::CreateView {
             name,
+            in_cluster,
             columns,
             query,
             materialized,
@@ -3485,6 +3700,7 @@ fn parse_create_materialized_view() {
             with_options,
         } => {
             assert_eq!("myschema.myview", name.to_string());
+            assert_eq!(None, in_cluster);
             assert_eq!(Vec::<Ident>::new(), columns);
             assert_eq!("SELECT foo FROM bar", query.to_string());
             assert!(materialized);
@@ -3504,6 +3720,7 @@ fn parse_create_source_inline_schema() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3516,10 +3733,13 @@ fn parse_create_source_inline_schema() {
                 connector
             );
             assert_eq!(
-                Format::Avro(AvroSchema::Schema(Schema::Inline("baz".into()))),
+                Some(Format::Avro(AvroSchema::Schema(Schema::Inline(
+                    "baz".into()
+                )))),
                 format
             );
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!if_not_exists);
             assert!(!materialized);
         }
@@ -3538,6 +3758,7 @@ fn parse_create_source_kafka() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3559,8 +3780,9 @@ fn parse_create_source_kafka() {
                 },
                 connector
             );
-            assert_eq!(Format::Bytes, format);
+            assert_eq!(Some(Format::Bytes), format);
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!if_not_exists);
             assert!(!materialized);
         }
@@ -3578,6 +3800,7 @@ fn parse_create_source_file_schema_protobuf_multiple_args() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3590,13 +3813,14 @@ fn parse_create_source_file_schema_protobuf_multiple_args() {
                 connector
             );
             assert_eq!(
-                Format::Protobuf {
+                Some(Format::Protobuf {
                     message_name: "somemessage".into(),
                     schema: Schema::File("path".into())
-                },
+                }),
                 format
             );
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!if_not_exists);
             assert!(materialized);
         }
@@ -3615,6 +3839,7 @@ fn parse_create_source_regex() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3629,8 +3854,9 @@ fn parse_create_source_regex() {
                 },
                 connector
             );
-            assert_eq!(Format::Regex("(asdf)|(jkl)".into()), format);
+            assert_eq!(Some(Format::Regex("(asdf)|(jkl)".into())), format);
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(if_not_exists);
             assert!(!materialized);
         }
@@ -3649,6 +3875,7 @@ fn parse_create_source_csv() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3664,13 +3891,15 @@ fn parse_create_source_csv() {
                 connector
             );
             assert_eq!(
-                Format::Csv {
-                    n_cols: 3,
+                Some(Format::Csv {
+                    columns: CsvColumns::Count(3),
+                    header: false,
                     delimiter: ','
-                },
+                }),
                 format
             );
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!if_not_exists);
             assert!(!materialized);
         }
@@ -3689,6 +3918,7 @@ fn parse_create_source_csv_custom_delim() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3704,13 +3934,15 @@ fn parse_create_source_csv_custom_delim() {
                 connector
             );
             assert_eq!(
-                Format::Csv {
-                    n_cols: 3,
+                Some(Format::Csv {
+                    columns: CsvColumns::Count(3),
+                    header: false,
                     delimiter: '|'
-                },
+                }),
                 format
             );
             assert_eq!(Envelope::None, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!if_not_exists);
             assert!(!materialized);
         }
@@ -3718,6 +3950,277 @@ fn parse_create_source_csv_custom_delim() {
     }
 }
 
+#[test]
+fn parse_create_source_csv_typed() {
+    let sql = "CREATE SOURCE foo \
+               FROM FILE 'bar' \
+               FORMAT CSV WITH COLUMNS (a int, b text, c timestamp) HEADER DELIMITED BY ';'";
+    match verified_stmt(sql) {
+        Statement::CreateSource { format, .. } => assert_eq!(
+            Some(Format::Csv {
+                columns: CsvColumns::Typed(vec![
+                    ColumnDef {
+                        name: "a".into(),
+                        data_type: DataType::Int,
+                        collation: None,
+                        options: vec![],
+                    },
+                    ColumnDef {
+                        name: "b".into(),
+                        data_type: DataType::Text,
+                        collation: None,
+                        options: vec![],
+                    },
+                    ColumnDef {
+                        name: "c".into(),
+                        data_type: DataType::Timestamp,
+                        collation: None,
+                        options: vec![],
+                    },
+                ]),
+                header: true,
+                delimiter: ';'
+            }),
+            format
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_source_json_typed() {
+    let sql = "CREATE SOURCE foo \
+               FROM FILE 'bar' \
+               FORMAT JSON WITH COLUMNS (a int, b jsonb)";
+    match verified_stmt(sql) {
+        Statement::CreateSource { format, .. } => assert_eq!(
+            Some(Format::Json {
+                columns: vec![
+                    ColumnDef {
+                        name: "a".into(),
+                        data_type: DataType::Int,
+                        collation: None,
+                        options: vec![],
+                    },
+                    ColumnDef {
+                        name: "b".into(),
+                        data_type: DataType::Jsonb,
+                        collation: None,
+                        options: vec![],
+                    },
+                ],
+            }),
+            format
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_source_plugin() {
+    let sql = "CREATE SOURCE foo FROM PLUGIN 'mqtt' WITH (topic = 'bar', qos = 1) FORMAT TEXT";
+    match verified_stmt(sql) {
+        Statement::CreateSource {
+            connector, format, ..
+        } => {
+            assert_eq!(
+                Connector::Plugin {
+                    name: "mqtt".into(),
+                    with_options: vec![
+                        SqlOption {
+                            name: "topic".into(),
+                            value: Value::SingleQuotedString("bar".into()),
+                        },
+                        SqlOption {
+                            name: "qos".into(),
+                            value: Value::Number("1".into()),
+                        },
+                    ],
+                },
+                connector
+            );
+            assert_eq!(Some(Format::Text), format);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_source_mqtt() {
+    let sql = "CREATE SOURCE foo FROM MQTT BROKER 'localhost:1883' TOPIC 'sensors/+/temp' WITH (qos = 1) FORMAT JSON";
+    match verified_stmt(sql) {
+        Statement::CreateSource {
+            connector, format, ..
+        } => {
+            assert_eq!(
+                Connector::Mqtt {
+                    broker: "localhost:1883".into(),
+                    topic: "sensors/+/temp".into(),
+                    with_options: vec![SqlOption {
+                        name: "qos".into(),
+                        value: Value::Number("1".into()),
+                    }],
+                },
+                connector
+            );
+            assert_eq!(Some(Format::Json { columns: vec![] }), format);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_source_sink_nats() {
+    let sql = "CREATE SOURCE foo FROM NATS 'nats://localhost:4222' SUBJECT 'orders.>' WITH (stream = 'ORDERS', durable = 'mz') FORMAT JSON";
+    match verified_stmt(sql) {
+        Statement::CreateSource { connector, .. } => assert_eq!(
+            Connector::Nats {
+                url: "nats://localhost:4222".into(),
+                subject: "orders.>".into(),
+                with_options: vec![
+                    SqlOption {
+                        name: "stream".into(),
+                        value: Value::SingleQuotedString("ORDERS".into()),
+                    },
+                    SqlOption {
+                        name: "durable".into(),
+                        value: Value::SingleQuotedString("mz".into()),
+                    },
+                ],
+            },
+            connector
+        ),
+        _ => unreachable!(),
+    }
+
+    let sql =
+        "CREATE SINK foo FROM bar INTO NATS 'nats://localhost:4222' SUBJECT 'totals' FORMAT JSON";
+    match verified_stmt(sql) {
+        Statement::CreateSink { connector, .. } => assert_eq!(
+            Connector::Nats {
+                url: "nats://localhost:4222".into(),
+                subject: "totals".into(),
+                with_options: vec![],
+            },
+            connector
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_source_postgres() {
+    let sql = "CREATE SOURCE foo \
+               FROM POSTGRES CONNECTION 'host=localhost dbname=shop' TABLE 'orders'";
+    match verified_stmt(sql) {
+        Statement::CreateSource {
+            connector, format, ..
+        } => {
+            assert_eq!(
+                Connector::Postgres {
+                    conn: "host=localhost dbname=shop".into(),
+                    table: "orders".into(),
+                    columns: vec![],
+                },
+                connector
+            );
+            assert_eq!(None, format);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SOURCE foo \
+               FROM POSTGRES CONNECTION 'host=localhost' TABLE 'public.orders' (id int, note text)";
+    match verified_stmt(sql) {
+        Statement::CreateSource { connector, .. } => assert_eq!(
+            Connector::Postgres {
+                conn: "host=localhost".into(),
+                table: "public.orders".into(),
+                columns: vec![
+                    ColumnDef {
+                        name: "id".into(),
+                        data_type: DataType::Int,
+                        collation: None,
+                        options: vec![],
+                    },
+                    ColumnDef {
+                        name: "note".into(),
+                        data_type: DataType::Text,
+                        collation: None,
+                        options: vec![],
+                    },
+                ],
+            },
+            connector
+        ),
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SOURCE foo \
+               FROM POSTGRES CONNECTION 'host=localhost' TABLE 'orders' FORMAT BYTES";
+    let err = parse_sql_statements(sql).unwrap_err();
+    assert_eq!("Expected end of statement, found: FORMAT", err.message);
+}
+
+#[test]
+fn parse_create_source_mysql() {
+    let sql = "CREATE SOURCE foo \
+               FROM MYSQL 'mysql://root@localhost/shop' TABLE 'orders' (id int)";
+    match verified_stmt(sql) {
+        Statement::CreateSource {
+            connector, format, ..
+        } => {
+            assert_eq!(
+                Connector::Mysql {
+                    url: "mysql://root@localhost/shop".into(),
+                    table: "orders".into(),
+                    columns: vec![ColumnDef {
+                        name: "id".into(),
+                        data_type: DataType::Int,
+                        collation: None,
+                        options: vec![],
+                    }],
+                },
+                connector
+            );
+            assert_eq!(None, format);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SOURCE foo FROM MYSQL 'mysql://localhost/shop' TABLE 'orders'";
+    let err = parse_sql_statements(sql).unwrap_err();
+    assert_eq!("Expected (, found: EOF", err.message);
+}
+
+#[test]
+fn parse_create_source_s3() {
+    let sql = "CREATE SOURCE foo FROM S3 BUCKET 'logs' WITH (region = 'us-east-1', prefix = 'web/') FORMAT TEXT";
+    match verified_stmt(sql) {
+        Statement::CreateSource { connector, .. } => assert_eq!(
+            Connector::S3 {
+                bucket: "logs".into(),
+                with_options: vec![
+                    SqlOption {
+                        name: "region".into(),
+                        value: Value::SingleQuotedString("us-east-1".into()),
+                    },
+                    SqlOption {
+                        name: "prefix".into(),
+                        value: Value::SingleQuotedString("web/".into()),
+                    },
+                ],
+            },
+            connector
+        ),
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SOURCE foo FROM S3 'logs' FORMAT TEXT";
+    let err = parse_sql_statements(sql).unwrap_err();
+    assert_eq!("Expected BUCKET, found: 'logs'", err.message);
+}
+
 #[test]
 fn parse_missing_format() {
     let sql = "CREATE SOURCE foo FROM FILE 'bar' WITH (answer = 42)";
@@ -3746,6 +4249,7 @@ fn parse_create_source_registry() {
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -3758,14 +4262,15 @@ fn parse_create_source_registry() {
                 connector
             );
             assert_eq!(
-                Format::Avro(AvroSchema::CsrUrl {
+                Some(Format::Avro(AvroSchema::CsrUrl {
                     url: "http://localhost:8081".into(),
                     seed: None,
-                }),
+                })),
                 format
             );
             assert!(!if_not_exists);
             assert_eq!(Envelope::Debezium, envelope);
+            assert!(include_metadata.is_empty());
             assert!(!materialized);
         }
         _ => unreachable!(),
@@ -3777,13 +4282,13 @@ fn parse_create_source_registry() {
     match verified_stmt(sql) {
         Statement::CreateSource { format, .. } => {
             assert_eq!(
-                Format::Avro(AvroSchema::CsrUrl {
+                Some(Format::Avro(AvroSchema::CsrUrl {
                     url: "http://localhost:8081".into(),
                     seed: Some(CsrSeed {
                         key_schema: None,
                         value_schema: "blah".into(),
                     }),
-                }),
+                })),
                 format
             );
         }
@@ -3796,18 +4301,51 @@ fn parse_create_source_registry() {
     match verified_stmt(sql) {
         Statement::CreateSource { format, .. } => {
             assert_eq!(
-                Format::Avro(AvroSchema::CsrUrl {
+                Some(Format::Avro(AvroSchema::CsrUrl {
                     url: "http://localhost:8081".into(),
                     seed: Some(CsrSeed {
                         key_schema: Some("a".into()),
                         value_schema: "b".into(),
                     }),
-                }),
+                })),
                 format
             );
         }
         _ => unreachable!(),
     }
+
+    one_statement_parses_to(
+        "CREATE SOURCE foo FROM FILE 'bar' \
+         FORMAT AVRO USING SCHEMA REGISTRY 'http://localhost:8081'",
+        "CREATE SOURCE foo FROM FILE 'bar' \
+         FORMAT AVRO USING CONFLUENT SCHEMA REGISTRY 'http://localhost:8081'",
+    );
+}
+
+#[test]
+fn parse_create_source_include_metadata() {
+    let sql = "CREATE SOURCE foo FROM KAFKA BROKER 'bar' TOPIC 'baz' \
+               FORMAT BYTES INCLUDE OFFSET, PARTITION, TIMESTAMP";
+    match verified_stmt(sql) {
+        Statement::CreateSource {
+            include_metadata, ..
+        } => assert_eq!(
+            vec![
+                SourceMetadata::Offset,
+                SourceMetadata::Partition,
+                SourceMetadata::Timestamp
+            ],
+            include_metadata
+        ),
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SOURCE foo FROM FILE 'bar' FORMAT BYTES INCLUDE KEY";
+    let err = parse_sql_statements(sql).unwrap_err();
+    assert_eq!(
+        "Expected one of OFFSET or PARTITION or TIMESTAMP, found: KEY",
+        err.message
+    );
 }
 
 #[test]
@@ -3845,12 +4383,14 @@ fn parse_create_sink() {
     match verified_stmt(sql) {
         Statement::CreateSink {
             name,
+            in_cluster,
             from,
             connector,
             format,
             if_not_exists,
         } => {
             assert_eq!("foo", name.to_string());
+            assert_eq!(None, in_cluster);
             assert_eq!("bar", from.to_string());
             assert_eq!(
                 Connector::File {
@@ -3883,16 +4423,70 @@ fn parse_create_sink_if_not_exists() {
         .contains("Expected NOT, found: EXISTS"));
 }
 
+#[test]
+fn parse_create_sink_redis() {
+    let sql = "CREATE SINK foo FROM bar INTO REDIS 'redis://localhost:6379' WITH (key = 'id') FORMAT JSON";
+    match verified_stmt(sql) {
+        Statement::CreateSink {
+            connector, format, ..
+        } => {
+            assert_eq!(
+                Connector::Redis {
+                    url: "redis://localhost:6379".into(),
+                    with_options: vec![SqlOption {
+                        name: "key".into(),
+                        value: Value::SingleQuotedString("id".into()),
+                    }],
+                },
+                connector
+            );
+            assert_eq!(Format::Json { columns: vec![] }, format);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parse_create_sink_elasticsearch() {
+    let sql = "CREATE SINK foo FROM bar INTO ELASTICSEARCH 'http://localhost:9200' INDEX 'baz' WITH (key = 'id') FORMAT JSON";
+    match verified_stmt(sql) {
+        Statement::CreateSink { connector, .. } => assert_eq!(
+            Connector::Elasticsearch {
+                url: "http://localhost:9200".into(),
+                index: "baz".into(),
+                with_options: vec![SqlOption {
+                    name: "key".into(),
+                    value: Value::SingleQuotedString("id".into()),
+                }],
+            },
+            connector
+        ),
+        _ => unreachable!(),
+    }
+
+    let res = parse_sql_statements(
+        "CREATE SINK foo FROM bar INTO ELASTICSEARCH 'http://localhost:9200' FORMAT JSON",
+    );
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .contains("Expected INDEX, found: FORMAT"));
+}
+
 #[test]
 fn parse_create_index() {
     let sql = "CREATE INDEX foo ON myschema.bar (a, b)";
     match verified_stmt(sql) {
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         } => {
+            assert_eq!(None, in_cluster);
+            assert!(replicas.is_empty());
             assert_eq!("foo", name.to_string());
             assert_eq!("myschema.bar", on_name.to_string());
             assert_eq!(
@@ -3911,10 +4505,14 @@ fn parse_create_index() {
     match verified_stmt(sql) {
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         } => {
+            assert_eq!(None, in_cluster);
+            assert!(replicas.is_empty());
             assert_eq!("fizz", name.to_string());
             assert_eq!("baz", on_name.to_string());
             assert_matches!(key_parts[0], Expr::Function(..));
@@ -3937,10 +4535,14 @@ fn parse_create_index() {
     match verified_stmt(sql) {
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         } => {
+            assert_eq!(None, in_cluster);
+            assert!(replicas.is_empty());
             assert_eq!("ind", name.to_string());
             assert_eq!("tab", on_name.to_string());
             assert_eq!(
@@ -3959,10 +4561,14 @@ fn parse_create_index() {
     match verified_stmt(sql) {
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         } => {
+            assert_eq!(None, in_cluster);
+            assert!(replicas.is_empty());
             assert_eq!("qualifiers", name.to_string());
             assert_eq!("no_parentheses", on_name.to_string());
             assert_eq!(
@@ -3985,6 +4591,81 @@ fn parse_create_index() {
     }
 }
 
+#[test]
+fn parse_create_type() {
+    let sql = "CREATE TYPE shop.order_status AS ENUM ('pending', 'shipped', 'won''t ship')";
+    match verified_stmt(sql) {
+        Statement::CreateType { name, labels } => {
+            assert_eq!("shop.order_status", name.to_string());
+            assert_eq!(vec!["pending", "shipped", "won't ship"], labels);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE TYPE order_status AS ENUM ()";
+    let err = parse_sql_statements(sql).unwrap_err();
+    assert_eq!("Expected literal string, found: )", err.message);
+}
+
+#[test]
+fn parse_in_cluster() {
+    let sql = "CREATE INDEX foo IN CLUSTER analytics ON bar (a)";
+    match verified_stmt(sql) {
+        Statement::CreateIndex { in_cluster, .. } => {
+            assert_eq!(Some(Ident::new("analytics")), in_cluster);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE MATERIALIZED VIEW v IN CLUSTER analytics AS SELECT 1";
+    match verified_stmt(sql) {
+        Statement::CreateView { in_cluster, .. } => {
+            assert_eq!(Some(Ident::new("analytics")), in_cluster);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "CREATE SINK foo IN CLUSTER analytics FROM bar INTO FILE 'baz' FORMAT BYTES";
+    match verified_stmt(sql) {
+        Statement::CreateSink { in_cluster, .. } => {
+            assert_eq!(Some(Ident::new("analytics")), in_cluster);
+        }
+        _ => unreachable!(),
+    }
+
+    // Unmaterialized views are not maintained by any cluster.
+    let res = parse_sql_statements("CREATE VIEW v IN CLUSTER analytics AS SELECT 1");
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .contains("Expected AS, found: IN"));
+}
+
+#[test]
+fn parse_index_replicas() {
+    let sql = "CREATE INDEX foo IN CLUSTER a REPLICAS (b, c) ON bar (x)";
+    match verified_stmt(sql) {
+        Statement::CreateIndex {
+            in_cluster,
+            replicas,
+            ..
+        } => {
+            assert_eq!(Some(Ident::new("a")), in_cluster);
+            assert_eq!(vec![Ident::new("b"), Ident::new("c")], replicas);
+        }
+        _ => unreachable!(),
+    }
+
+    // Replicas of an index on the default cluster.
+    verified_stmt("CREATE INDEX foo REPLICAS (b) ON bar (x)");
+
+    let res = parse_sql_statements("CREATE INDEX foo REPLICAS () ON bar (x)");
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .contains("Expected identifier, found: )"));
+}
+
 #[test]
 fn parse_invalid_create_index() {
     // Index names should not have a schema in front of it
@@ -4172,17 +4853,137 @@ fn parse_drop_index() {
     }
 }
 
+#[test]
+fn parse_drop_type() {
+    let sql = "DROP TYPE order_status CASCADE";
+    match verified_stmt(sql) {
+        Statement::DropObjects {
+            object_type,
+            names,
+            cascade,
+            ..
+        } => {
+            assert_eq!(
+                vec!["order_status"],
+                names.iter().map(|n| n.to_string()).collect::<Vec<_>>()
+            );
+            assert_eq!(true, cascade);
+            assert_eq!(ObjectType::Type, object_type);
+        }
+        _ => unreachable!(),
+    }
+}
+
 #[test]
 fn parse_tail() {
     let sql = "TAIL foo.bar";
     match verified_stmt(sql) {
-        Statement::Tail { name } => {
+        Statement::Tail {
+            name,
+            columns,
+            selection,
+        } => {
             assert_eq!("foo.bar", name.to_string());
+            assert!(columns.is_empty());
+            assert_eq!(None, selection);
+        }
+        _ => unreachable!(),
+    }
+
+    let sql = "TAIL foo (a, b) WHERE a = 1";
+    match verified_stmt(sql) {
+        Statement::Tail {
+            name,
+            columns,
+            selection,
+        } => {
+            assert_eq!("foo", name.to_string());
+            assert_eq!(vec![Ident::new("a"), Ident::new("b")], columns);
+            assert_eq!("a = 1", selection.unwrap().to_string());
         }
         _ => unreachable!(),
     }
 }
 
+#[test]
+fn parse_listen() {
+    assert_eq!(
+        verified_stmt("LISTEN mz_catalog"),
+        Statement::Listen {
+            channel: Ident::new("mz_catalog"),
+        }
+    );
+    assert_eq!(
+        verified_stmt("UNLISTEN mz_catalog"),
+        Statement::Unlisten {
+            channel: Some(Ident::new("mz_catalog")),
+        }
+    );
+    assert_eq!(
+        verified_stmt("UNLISTEN *"),
+        Statement::Unlisten { channel: None }
+    );
+}
+
+#[test]
+fn parse_install_demo() {
+    assert_eq!(
+        verified_stmt("INSTALL DEMO auction"),
+        Statement::InstallDemo {
+            name: Ident::new("auction"),
+        }
+    );
+
+    let res = parse_sql_statements("INSTALL auction");
+    assert_eq!(
+        ("\
+Parse error:
+INSTALL auction
+        ^^^^^^^
+Expected DEMO, found: auction"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
+#[test]
+fn parse_trace_export() {
+    assert_eq!(
+        verified_stmt("EXPORT TRACE db.v TO '/tmp/v.trace'"),
+        Statement::ExportTrace {
+            name: ObjectName(vec![Ident::new("db"), Ident::new("v")]),
+            path: "/tmp/v.trace".into(),
+            history: false,
+        }
+    );
+    assert_eq!(
+        verified_stmt("EXPORT TRACE v TO 'v.trace' WITH HISTORY"),
+        Statement::ExportTrace {
+            name: ObjectName(vec![Ident::new("v")]),
+            path: "v.trace".into(),
+            history: true,
+        }
+    );
+    assert_eq!(
+        verified_stmt("IMPORT TRACE v_copy FROM 'v.trace'"),
+        Statement::ImportTrace {
+            name: ObjectName(vec![Ident::new("v_copy")]),
+            path: "v.trace".into(),
+        }
+    );
+
+    let res = parse_sql_statements("EXPORT TRACE v TO v.trace");
+    assert_eq!(
+        ("\
+Parse error:
+EXPORT TRACE v TO v.trace
+                  ^
+Expected literal string, found: v"
+            .to_string()),
+        format!("{}", res.unwrap_err())
+    );
+}
+
 #[test]
 fn parse_invalid_subquery_without_parens() {
     let res = parse_sql_statements("SELECT SELECT 1 FROM bar WHERE 1=1 FROM baz");
@@ -4578,6 +5379,15 @@ fn parse_explain() {
             query: Box::new(verified_query("SELECT 665")),
         }
     );
+
+    let ast = verified_stmt("EXPLAIN ANALYZE FOR SELECT 665");
+    assert_eq!(
+        ast,
+        Statement::Explain {
+            stage: Stage::Analyze,
+            query: Box::new(verified_query("SELECT 665")),
+        }
+    );
 }
 
 #[test]
@@ -5092,6 +5902,32 @@ fn parse_json_ops() {
     }
 }
 
+#[test]
+fn parse_inet_ops() {
+    use self::BinaryOperator::*;
+    use self::Expr::*;
+
+    for (op_string, op_enum) in vec![
+        ("<<", InetContainedBy),
+        ("<<=", InetContainedByOrEq),
+        (">>", InetContains),
+        (">>=", InetContainsOrEq),
+    ] {
+        let sql = format!("a {} b", op_string);
+        assert_matches!(
+            &verified_expr(&sql),
+            BinaryOp {op, ..} if *op == op_enum
+        );
+    }
+
+    // The containment operators bind more tightly than AND and comparisons.
+    assert_matches!(
+        &verified_expr("a << b AND c >>= d = true"),
+        BinaryOp { op: And, .. }
+    );
+    assert_matches!(&verified_expr("a >>= d = true"), BinaryOp { op: Eq, .. });
+}
+
 #[test]
 fn test_multiline_errors() {
     assert_eq!(
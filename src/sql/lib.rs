@@ -11,6 +11,9 @@
 
 #![deny(missing_debug_implementations)]
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use ::expr::GlobalId;
 use catalog::names::{DatabaseSpecifier, FullName};
 use catalog::{Catalog, CatalogEntry};
@@ -22,6 +25,7 @@ use sql_parser::parser::Parser as SqlParser;
 pub use session::{InternalSession, PlanSession, PreparedStatement, Session, TransactionStatus};
 pub use sql_parser::ast::{ObjectType, Statement};
 pub use statement::StatementContext;
+pub use transform::inline_params;
 
 pub mod normalize;
 
@@ -70,12 +74,19 @@ pub enum Plan {
         replace: Option<GlobalId>,
         /// whether we should auto-materialize the view
         materialize: bool,
+        /// The compute cluster that should maintain the view's index, if it
+        /// is materialized, or `None` for the default cluster.
+        cluster: Option<String>,
     },
     CreateIndex {
         name: FullName,
         index: Index,
         if_not_exists: bool,
     },
+    CreateType {
+        name: FullName,
+        typ: Type,
+    },
     DropDatabase {
         name: String,
     },
@@ -87,6 +98,17 @@ pub enum Plan {
         items: Vec<GlobalId>,
         ty: ObjectType,
     },
+    SetComment {
+        id: GlobalId,
+        /// The new comment, or `None` to remove the existing comment.
+        comment: Option<String>,
+    },
+    SetTags {
+        id: GlobalId,
+        object_type: ObjectType,
+        /// Tags to set. A value of `None` removes the tag.
+        tags: Vec<(String, Option<String>)>,
+    },
     EmptyQuery,
     ShowAllVariables,
     ShowVariable(String),
@@ -113,9 +135,19 @@ pub enum Plan {
         finishing: RowSetFinishing,
         materialize: bool,
     },
-    Tail(CatalogEntry),
+    Tail {
+        source: CatalogEntry,
+        /// The predicates that streamed rows must satisfy.
+        filter: Vec<::expr::ScalarExpr>,
+        /// The columns to which streamed rows are projected, or `None` to
+        /// stream all columns.
+        project: Option<Vec<usize>>,
+    },
     SendRows(Vec<Row>),
     ExplainPlan(::expr::RelationExpr),
+    /// Run the query in a transient dataflow, and report the statistics of
+    /// each of the dataflow's operators rather than the query's results.
+    ExplainAnalyze(::expr::RelationExpr),
     SendDiffs {
         id: GlobalId,
         updates: Vec<(Row, isize)>,
@@ -128,6 +160,60 @@ pub enum Plan {
         show_queryable: bool,
         limit_materialized: bool,
     },
+    ShowSinkProgress {
+        ids: Vec<(String, GlobalId)>,
+    },
+    /// List the arrangements that each dataflow worker maintains.
+    ShowArrangements,
+    CompactCatalog {
+        /// Whether to also reclaim the space that compaction frees.
+        vacuum: bool,
+    },
+    CheckCatalog,
+    /// Compare the contents of the view `id` with a reference evaluation of
+    /// its definition, and report the rows in which they differ.
+    CheckView {
+        id: GlobalId,
+    },
+    /// Start delivering the notifications sent on `channel` to the session.
+    Listen {
+        channel: String,
+    },
+    /// Stop delivering the notifications sent on `channel` to the session, or
+    /// on any channel if `channel` is `None`.
+    Unlisten {
+        channel: Option<String>,
+    },
+    /// Create the schema and objects of the built-in demo named `name`.
+    InstallDemo {
+        name: String,
+    },
+    /// Write the contents of the index of the view or source `id` to the file
+    /// at `path`, along with the history that the index retains if `history`
+    /// is set.
+    ExportTrace {
+        id: GlobalId,
+        path: PathBuf,
+        history: bool,
+    },
+    /// Create a table named `name` that holds the contents of the trace
+    /// exported to the file at `path`.
+    ImportTrace {
+        name: FullName,
+        path: PathBuf,
+    },
+    /// Stop advancing the frontiers of sources and tables past `as_of`, or
+    /// past the current time if `as_of` is `None`.
+    Quiesce {
+        as_of: Option<u64>,
+    },
+    /// Resume advancing the frontiers that `Quiesce` held back.
+    Resume,
+    /// Wait until each of the sinks in `ids` has emitted its output for all
+    /// of the input that is currently available.
+    FlushSinks {
+        ids: Vec<(String, GlobalId)>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -142,6 +228,9 @@ pub struct Sink {
     pub create_sql: String,
     pub from: GlobalId,
     pub connector: SinkConnector,
+    /// The compute cluster that runs the sink, or `None` for the default
+    /// cluster.
+    pub cluster: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -149,6 +238,21 @@ pub struct View {
     pub create_sql: String,
     pub expr: ::expr::RelationExpr,
     pub desc: RelationDesc,
+    /// If set, the view only observes changes to its inputs at multiples of
+    /// this interval, rather than continuously.
+    pub refresh_interval: Option<Duration>,
+    /// If set, updates that do not change the contents of the view, like the
+    /// retraction and reinsertion of an identical row, are suppressed before
+    /// they reach consumers of the view.
+    pub changes_only: bool,
+    /// If set, names a timestamp column that indicates when each row of the
+    /// view becomes final. Rows are withheld until the dataflow's frontier
+    /// passes that time, and updates that arrive afterwards are discarded.
+    pub emit_final_after: Option<usize>,
+    /// If set, the freshness objective of the view: how far its results may
+    /// trail the wall clock before the view is considered to be falling
+    /// behind.
+    pub max_lag: Option<Duration>,
 }
 
 #[derive(Clone, Debug)]
@@ -156,6 +260,18 @@ pub struct Index {
     pub create_sql: String,
     pub on: GlobalId,
     pub keys: Vec<::expr::ScalarExpr>,
+    /// The compute cluster that maintains the index, or `None` for the
+    /// default cluster.
+    pub cluster: Option<String>,
+    /// The compute clusters that maintain read-only replicas of the index.
+    pub replicas: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Type {
+    pub create_sql: String,
+    /// The labels of the enum type, in the order in which they sort.
+    pub labels: Vec<String>,
 }
 
 #[derive(Debug)]
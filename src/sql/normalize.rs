@@ -13,7 +13,7 @@ use catalog::names::{DatabaseSpecifier, FullName, PartialName};
 use ore::collections::CollectionExt;
 use repr::ColumnName;
 use sql_parser::ast::visit_mut::VisitMut;
-use sql_parser::ast::{Expr, Function, Ident, ObjectName, Statement, TableAlias};
+use sql_parser::ast::{DataType, Expr, Function, Ident, ObjectName, Statement, TableAlias};
 
 use crate::statement::StatementContext;
 
@@ -143,6 +143,12 @@ pub fn create_statement(
         fn visit_ident(&mut self, ident: &'ast mut Ident) {
             norm_ident(ident);
         }
+
+        fn visit_type(&mut self, data_type: &'ast mut DataType) {
+            if let DataType::Custom(name) = data_type {
+                self.visit_object_name(name);
+            }
+        }
     }
 
     // Think very hard before changing any of the branches in this match
@@ -161,6 +167,7 @@ pub fn create_statement(
             connector: _,
             format: _,
             envelope: _,
+            include_metadata: _,
             if_not_exists,
             materialized,
         } => {
@@ -171,18 +178,23 @@ pub fn create_statement(
 
         Statement::CreateSink {
             name,
+            in_cluster,
             from,
             connector: _,
             format: _,
             if_not_exists,
         } => {
             *name = allocate_name(name)?;
+            if let Some(in_cluster) = in_cluster {
+                norm_ident(in_cluster);
+            }
             *from = resolve_name(from)?;
             *if_not_exists = false;
         }
 
         Statement::CreateView {
             name,
+            in_cluster,
             columns,
             query,
             materialized,
@@ -200,17 +212,28 @@ pub fn create_statement(
                     return Err(err);
                 }
             }
+            // The view's index, if any, is recorded separately, along with
+            // the cluster that maintains it.
+            *in_cluster = None;
             *materialized = false;
             *replace = false;
         }
 
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
         } => {
             norm_ident(name);
+            if let Some(in_cluster) = in_cluster {
+                norm_ident(in_cluster);
+            }
+            for replica in replicas {
+                norm_ident(replica);
+            }
             *on_name = resolve_name(on_name)?;
             let mut normalizer = QueryNormalizer { scx, err: None };
             for key_part in key_parts {
@@ -222,6 +245,10 @@ pub fn create_statement(
             *if_not_exists = false;
         }
 
+        Statement::CreateType { name, labels: _ } => {
+            *name = allocate_name(name)?;
+        }
+
         _ => unreachable!(),
     }
 
@@ -66,7 +66,23 @@ pub fn plan_root_query(
 ) -> Result<(RelationExpr, RelationDesc, RowSetFinishing, Vec<ScalarType>), failure::Error> {
     crate::transform::transform(&mut query);
     let qcx = QueryContext::root(scx, lifetime);
-    let (expr, scope, finishing) = plan_query(&qcx, &query)?;
+    let (mut expr, scope, mut finishing) = plan_query(&qcx, &query)?;
+
+    // Output columns whose type is still unknown can only ever be NULL. As in
+    // PostgreSQL, they are resolved as text, so that clients, and the views
+    // that select from them, see a concrete type.
+    let typ = qcx.relation_type(&expr);
+    let mut nulls = vec![];
+    for column in &mut finishing.project {
+        if typ.column_types[*column].scalar_type == ScalarType::Unknown {
+            *column = typ.column_types.len() + nulls.len();
+            nulls.push(ScalarExpr::literal_null(ScalarType::String));
+        }
+    }
+    if !nulls.is_empty() {
+        expr = expr.map(nulls);
+    }
+
     let typ = qcx.relation_type(&expr);
     let typ = RelationType::new(
         finishing
@@ -111,6 +127,33 @@ pub fn plan_index_exprs<'a>(
     Ok(out)
 }
 
+/// Plans the `WHERE` clause of a `TAIL` of a relation described by `desc`.
+pub fn plan_tail_filter(
+    scx: &StatementContext,
+    desc: &RelationDesc,
+    selection: &Expr,
+) -> Result<::expr::ScalarExpr, failure::Error> {
+    let scope = Scope::from_source(None, desc.iter_names(), Some(Scope::empty(None)));
+    let qcx = &QueryContext::root(scx, QueryLifetime::OneShot);
+    let ecx = &ExprContext {
+        qcx: &qcx,
+        name: "WHERE clause",
+        scope: &scope,
+        relation_type: desc.typ(),
+        allow_aggregates: false,
+        allow_subqueries: false,
+    };
+    let expr = plan_expr(ecx, selection, Some(ScalarType::Bool))?;
+    let typ = ecx.column_type(&expr);
+    if typ.scalar_type != ScalarType::Bool && typ.scalar_type != ScalarType::Unknown {
+        bail!(
+            "WHERE clause must have boolean type, not {:?}",
+            typ.scalar_type
+        );
+    }
+    Ok(expr.lower_uncorrelated())
+}
+
 fn plan_expr_or_col_index<'a>(
     ecx: &ExprContext,
     e: &'a Expr,
@@ -267,6 +310,11 @@ fn plan_set_expr(qcx: &QueryContext, q: &SetExpr) -> Result<(RelationExpr, Scope
                 }
             }
 
+            // Columns that are NULL on one side take their type from the
+            // other side, so that both sides have the same type.
+            let left_expr = coerce_unknown_columns(left_expr, &left_types, &right_types);
+            let right_expr = coerce_unknown_columns(right_expr, &right_types, &left_types);
+
             let relation_expr = match op {
                 SetOperator::Union => {
                     if *all {
@@ -323,36 +371,55 @@ fn plan_set_expr(qcx: &QueryContext, q: &SetExpr) -> Result<(RelationExpr, Scope
                 allow_aggregates: false,
                 allow_subqueries: true,
             };
-            let mut expr: Option<RelationExpr> = None;
-            let mut types: Option<Vec<ColumnType>> = None;
-            for row in values {
-                let mut value_exprs = vec![];
-                let mut value_types = vec![];
-                for value in row {
-                    let expr = plan_expr(ecx, value, Some(ScalarType::String))?;
-                    value_types.push(ecx.column_type(&expr));
-                    value_exprs.push(expr);
-                }
-                types = if let Some(types) = types {
-                    if types.len() != value_exprs.len() {
-                        bail!(
-                            "VALUES expression has varying number of columns: {}",
-                            q.to_string()
-                        );
+            let arity = values[0].len();
+            if values.iter().any(|row| row.len() != arity) {
+                bail!(
+                    "VALUES expression has varying number of columns: {}",
+                    q.to_string()
+                );
+            }
+
+            // Each column is planned on its own, so that parameters and NULLs
+            // can take their type from the other rows.
+            let mut rows: Vec<Vec<Option<ScalarExpr>>> = vec![vec![None; arity]; values.len()];
+            for column in 0..arity {
+                let mut typ: Option<ColumnType> = None;
+                for (row, value) in values.iter().map(|row| &row[column]).enumerate() {
+                    if expr_has_unknown_type(ecx, value) {
+                        continue;
                     }
-                    Some(
-                        types
-                            .iter()
-                            .zip(value_types.iter())
-                            .map(|(left_typ, right_typ)| left_typ.union(right_typ))
-                            .collect::<Result<Vec<_>, _>>()?,
-                    )
-                } else {
-                    Some(value_types)
+                    let expr = plan_expr(ecx, value, None)?;
+                    let value_typ = ecx.column_type(&expr);
+                    typ = Some(match typ {
+                        Some(typ) => typ.union(&value_typ)?,
+                        None => value_typ,
+                    });
+                    rows[row][column] = Some(expr);
+                }
+                let target = match typ {
+                    Some(typ) if typ.scalar_type != ScalarType::Unknown => Some(typ.scalar_type),
+                    _ => None,
                 };
+                for (row, value) in values.iter().map(|row| &row[column]).enumerate() {
+                    let expr = match rows[row][column].take() {
+                        Some(expr) => expr,
+                        None => plan_expr(
+                            ecx,
+                            value,
+                            Some(target.clone().unwrap_or(ScalarType::String)),
+                        )?,
+                    };
+                    rows[row][column] = Some(match &target {
+                        Some(target) => plan_cast_internal(ecx, "VALUES", expr, target.clone())?,
+                        None => expr,
+                    });
+                }
+            }
 
+            let mut expr: Option<RelationExpr> = None;
+            for row in rows {
                 let row_expr = RelationExpr::constant(vec![vec![]], RelationType::new(vec![]))
-                    .map(value_exprs);
+                    .map(row.into_iter().map(Option::unwrap).collect());
                 expr = if let Some(expr) = expr {
                     Some(expr.union(row_expr))
                 } else {
@@ -360,7 +427,7 @@ fn plan_set_expr(qcx: &QueryContext, q: &SetExpr) -> Result<(RelationExpr, Scope
                 };
             }
             let mut scope = Scope::empty(Some(qcx.outer_scope.clone()));
-            for i in 0..types.unwrap().len() {
+            for i in 0..arity {
                 let name = Some(format!("column{}", i + 1).into());
                 scope.items.push(ScopeItem::from_column_name(name));
             }
@@ -373,6 +440,31 @@ fn plan_set_expr(qcx: &QueryContext, q: &SetExpr) -> Result<(RelationExpr, Scope
     }
 }
 
+/// Replaces the columns of `expr`, whose types are `types`, that have an unknown
+/// type, and so are always NULL, with NULLs of the corresponding type in
+/// `target_types`.
+fn coerce_unknown_columns(
+    expr: RelationExpr,
+    types: &[ColumnType],
+    target_types: &[ColumnType],
+) -> RelationExpr {
+    let mut nulls = vec![];
+    let mut outputs = vec![];
+    for (i, (typ, target_typ)) in types.iter().zip(target_types).enumerate() {
+        if typ.scalar_type == ScalarType::Unknown && target_typ.scalar_type != ScalarType::Unknown {
+            outputs.push(types.len() + nulls.len());
+            nulls.push(ScalarExpr::literal_null(target_typ.scalar_type.clone()));
+        } else {
+            outputs.push(i);
+        }
+    }
+    if nulls.is_empty() {
+        expr
+    } else {
+        expr.map(nulls).project(outputs)
+    }
+}
+
 fn plan_view_select(
     qcx: &QueryContext,
     s: &Select,
@@ -817,6 +909,24 @@ fn plan_table_function(
         ("regexp_extract", _) | ("csv_extract", _) => {
             bail!("{}() requires exactly two arguments", ident)
         }
+        ("text_tokens", [expr]) => {
+            let expr = plan_expr(ecx, expr, Some(ScalarType::String))?;
+            if ecx.column_type(&expr).scalar_type != ScalarType::String {
+                bail!("Datum to tokenize must be a string");
+            }
+            let call = RelationExpr::FlatMapUnary {
+                input: Box::new(left),
+                func: UnaryTableFunc::TextTokens,
+                expr,
+            };
+            let scope = Scope::from_source(
+                alias,
+                iter::once(Some(ColumnName::from("token"))),
+                Some(ecx.qcx.outer_scope.clone()),
+            );
+            Ok((call, ecx.scope.clone().product(scope)))
+        }
+        ("text_tokens", _) => bail!("{}() requires exactly one argument", ident),
         _ => bail!("unsupported table function: {}", ident),
     }
 }
@@ -1532,7 +1642,10 @@ fn plan_cast<'a>(
     expr: &'a Expr,
     data_type: &'a DataType,
 ) -> Result<(ScalarExpr, Option<ScopeItemName>), failure::Error> {
-    let to_scalar_type = scalar_type_from_sql(data_type)?;
+    let to_scalar_type = match data_type {
+        DataType::Custom(name) => ecx.qcx.scx.resolve_type(name.clone())?,
+        _ => scalar_type_from_sql(data_type)?,
+    };
     let (expr, maybe_name) = plan_expr_returning_name(ecx, expr, Some(to_scalar_type.clone()))?;
     Ok((
         plan_cast_internal(ecx, "CAST", expr, to_scalar_type)?,
@@ -1548,6 +1661,10 @@ fn plan_aggregate(ecx: &ExprContext, sql_func: &Function) -> Result<AggregateExp
         bail!("window functions are not yet supported");
     }
 
+    if name == "mz_match_pattern" {
+        return plan_match_pattern(ecx, sql_func);
+    }
+
     if sql_func.args.len() != 1 {
         bail!("{} function only takes one argument", name);
     }
@@ -1578,6 +1695,61 @@ fn plan_aggregate(ecx: &ExprContext, sql_func: &Function) -> Result<AggregateExp
     })
 }
 
+/// Plans `mz_match_pattern(time, event, pattern, within)`, which counts the
+/// occurrences of the comma-separated event labels in `pattern` within each
+/// group. See `AggregateFunc::MatchPattern` for the precise semantics.
+fn plan_match_pattern(
+    ecx: &ExprContext,
+    sql_func: &Function,
+) -> Result<AggregateExpr, failure::Error> {
+    if sql_func.distinct {
+        bail!("mz_match_pattern does not support DISTINCT");
+    }
+    let (time, event, pattern, within) = match sql_func.args.as_slice() {
+        [time, event, pattern, within] => (time, event, pattern, within),
+        _ => bail!(
+            "mz_match_pattern expects four arguments, got {}",
+            sql_func.args.len()
+        ),
+    };
+    let time = plan_expr(ecx, time, None)?;
+    match ecx.scalar_type(&time) {
+        ScalarType::Timestamp | ScalarType::TimestampTz => (),
+        other => bail!("mz_match_pattern time must be a timestamp, not {:?}", other),
+    }
+    let event = plan_expr(ecx, event, Some(ScalarType::String))?;
+    if ecx.scalar_type(&event) != ScalarType::String {
+        bail!("mz_match_pattern event must be a string");
+    }
+    let pattern: Vec<String> = match pattern {
+        Expr::Value(Value::SingleQuotedString(s)) => {
+            s.split(',').map(|p| p.trim().to_owned()).collect()
+        }
+        _ => bail!("mz_match_pattern pattern must be a string literal"),
+    };
+    if pattern.iter().any(|p| p.is_empty()) {
+        bail!("mz_match_pattern pattern must not contain empty event labels");
+    }
+    let within: repr::Interval = match within {
+        Expr::Value(Value::Interval(iv)) => iv.compute_interval()?.into(),
+        _ => bail!("mz_match_pattern within must be an interval literal"),
+    };
+    if within.months != 0 || !within.is_positive_dur {
+        bail!("mz_match_pattern within must be a positive interval without months or years");
+    }
+    // Bundle the time and event together, so that they can be aggregated as a
+    // single datum. The resulting list never escapes the surrounding reduce.
+    let expr = ScalarExpr::CallVariadic {
+        func: VariadicFunc::ListCreate,
+        exprs: vec![time, event],
+    };
+    Ok(AggregateExpr {
+        func: AggregateFunc::MatchPattern { pattern, within },
+        expr: Box::new(expr),
+        distinct: false,
+    })
+}
+
 fn plan_function<'a>(
     ecx: &ExprContext,
     sql_func: &'a Function,
@@ -1688,7 +1860,7 @@ fn plan_function<'a>(
                 }
                 match ecx.qcx.lifetime {
                     QueryLifetime::OneShot => Ok(ScalarExpr::CallNullary(NullaryFunc::Now)),
-                    QueryLifetime::Static => bail!("{} cannot be used in static queries", ident),
+                    QueryLifetime::Static => bail_volatile(ident),
                 }
             }
 
@@ -1928,7 +2100,7 @@ fn plan_function<'a>(
                     QueryLifetime::OneShot => {
                         Ok(ScalarExpr::CallNullary(NullaryFunc::MzLogicalTimestamp))
                     }
-                    QueryLifetime::Static => bail!("{} cannot be used in static queries", ident),
+                    QueryLifetime::Static => bail_volatile(ident),
                 }
             }
 
@@ -2055,6 +2227,126 @@ fn plan_function<'a>(
                 Ok(expr)
             }
 
+            "host" | "masklen" | "network" => {
+                if sql_func.args.len() != 1 {
+                    bail!(
+                        "{} expects one argument, got {}",
+                        ident,
+                        sql_func.args.len()
+                    );
+                }
+                let expr = plan_inet_arg(ecx, ident, &sql_func.args[0])?;
+                let func = match ident {
+                    "host" => UnaryFunc::InetHost,
+                    "masklen" => UnaryFunc::InetMasklen,
+                    "network" => UnaryFunc::CastInetToCidr,
+                    _ => unreachable!(),
+                };
+                Ok(expr.call_unary(func))
+            }
+
+            "point" => {
+                if sql_func.args.len() != 2 {
+                    bail!("point expects two arguments, got {}", sql_func.args.len());
+                }
+                let x = plan_expr(ecx, &sql_func.args[0], Some(ScalarType::Float64))?;
+                let x = promote_number_float64(ecx, "point", x)?;
+                let y = plan_expr(ecx, &sql_func.args[1], Some(ScalarType::Float64))?;
+                let y = promote_number_float64(ecx, "point", y)?;
+                Ok(x.call_binary(y, BinaryFunc::MakePoint))
+            }
+
+            "point_distance" => {
+                if sql_func.args.len() != 2 {
+                    bail!(
+                        "point_distance expects two arguments, got {}",
+                        sql_func.args.len()
+                    );
+                }
+                let a = plan_point_arg(ecx, "point_distance", &sql_func.args[0])?;
+                let b = plan_point_arg(ecx, "point_distance", &sql_func.args[1])?;
+                Ok(a.call_binary(b, BinaryFunc::PointDistance))
+            }
+
+            "point_in_box" => {
+                if sql_func.args.len() != 3 {
+                    bail!(
+                        "point_in_box expects three arguments, got {}",
+                        sql_func.args.len()
+                    );
+                }
+                let mut exprs = Vec::new();
+                for arg in &sql_func.args {
+                    exprs.push(plan_point_arg(ecx, "point_in_box", arg)?);
+                }
+                Ok(ScalarExpr::CallVariadic {
+                    func: VariadicFunc::PointInBox,
+                    exprs,
+                })
+            }
+
+            "random" => {
+                if !sql_func.args.is_empty() {
+                    bail!("{} does not take any arguments", ident);
+                }
+                match ecx.qcx.lifetime {
+                    QueryLifetime::OneShot => {
+                        // Derive the number from a seed drawn for this call and
+                        // the row's columns, rather than drawing a number each
+                        // time the function is evaluated, so that an update and
+                        // its retraction still cancel out.
+                        let seed = Uuid::new_v4().as_u128() as i64;
+                        let mut exprs = vec![ScalarExpr::literal(
+                            Datum::Int64(seed),
+                            ColumnType::new(ScalarType::Int64),
+                        )];
+                        exprs.extend(
+                            (0..ecx.relation_type.column_types.len())
+                                .map(|column| ScalarExpr::Column(ColumnRef { level: 0, column })),
+                        );
+                        Ok(ScalarExpr::CallVariadic {
+                            func: VariadicFunc::SeededRandom,
+                            exprs,
+                        })
+                    }
+                    QueryLifetime::Static => bail_volatile(ident),
+                }
+            }
+
+            "seeded_random" => {
+                if sql_func.args.is_empty() {
+                    bail!("seeded_random requires at least one argument");
+                }
+                let mut exprs = Vec::new();
+                for arg in &sql_func.args {
+                    exprs.push(plan_expr(ecx, arg, Some(ScalarType::String))?);
+                }
+                Ok(ScalarExpr::CallVariadic {
+                    func: VariadicFunc::SeededRandom,
+                    exprs,
+                })
+            }
+
+            "text_matches" => {
+                if sql_func.args.len() != 2 {
+                    bail!(
+                        "text_matches expects two arguments, got {}",
+                        sql_func.args.len()
+                    );
+                }
+                let text = plan_expr(ecx, &sql_func.args[0], Some(ScalarType::String))?;
+                let query = plan_expr(ecx, &sql_func.args[1], Some(ScalarType::String))?;
+                for expr in &[&text, &query] {
+                    let typ = ecx.column_type(expr);
+                    if typ.scalar_type != ScalarType::String
+                        && typ.scalar_type != ScalarType::Unknown
+                    {
+                        bail!("text_matches does not accept arguments of type {:?}", typ);
+                    }
+                }
+                Ok(text.call_binary(query, BinaryFunc::TextMatches))
+            }
+
             "to_char" => {
                 if sql_func.args.len() != 2 {
                     bail!("to_char requires exactly two arguments");
@@ -2257,6 +2549,12 @@ fn plan_binary_op<'a>(
         JsonDeletePath => plan_json_op(ecx, JsonOp::DeletePath, left, right),
         JsonContainsPath => plan_json_op(ecx, JsonOp::ContainsPath, left, right),
         JsonApplyPathPredicate => plan_json_op(ecx, JsonOp::ApplyPathPredicate, left, right),
+
+        // `a >> b` is equivalent to `b << a`, and likewise for `>>=`.
+        InetContainedBy => plan_inet_op(ecx, op, BinaryFunc::InetContainedBy, left, right),
+        InetContainedByOrEq => plan_inet_op(ecx, op, BinaryFunc::InetContainedByOrEq, left, right),
+        InetContains => plan_inet_op(ecx, op, BinaryFunc::InetContainedBy, right, left),
+        InetContainsOrEq => plan_inet_op(ecx, op, BinaryFunc::InetContainedByOrEq, right, left),
     }
 }
 
@@ -2711,6 +3009,18 @@ fn plan_json_op(
     })
 }
 
+fn plan_inet_op<'a>(
+    ecx: &ExprContext,
+    op: &BinaryOperator,
+    func: BinaryFunc,
+    left: &'a Expr,
+    right: &'a Expr,
+) -> Result<ScalarExpr, failure::Error> {
+    let lexpr = plan_inet_arg(ecx, op, left)?;
+    let rexpr = plan_inet_arg(ecx, op, right)?;
+    Ok(lexpr.call_binary(rexpr, func))
+}
+
 fn plan_between<'a>(
     ecx: &ExprContext,
     expr: &'a Expr,
@@ -3087,6 +3397,9 @@ fn best_target_type(iter: impl IntoIterator<Item = ScalarType>) -> Option<Scalar
             ScalarType::Date => 6,
             ScalarType::Timestamp => 7,
             ScalarType::TimestampTz => 8,
+            // Strings compared with enum values are converted to the enum
+            // type, so that the comparison follows the order of the labels.
+            ScalarType::Enum(_) => 10,
             _ => 9,
         })
 }
@@ -3163,6 +3476,12 @@ where
         (TimestampTz, String) => expr.call_unary(CastTimestampTzToString),
         (Interval, String) => expr.call_unary(CastIntervalToString),
         (Bytes, String) => expr.call_unary(CastBytesToString),
+        (Point, String) => expr.call_unary(CastPointToString),
+        (Inet, String) => expr.call_unary(CastInetToString),
+        (Inet, Cidr) => expr.call_unary(CastInetToCidr),
+        (Cidr, String) => expr.call_unary(CastCidrToString),
+        (Cidr, Inet) => expr.call_unary(CastCidrToInet),
+        (Enum(id), String) => expr.call_unary(CastEnumToString(ecx.qcx.scx.enum_labels(id)?)),
         (Jsonb, String) => expr.call_unary(JsonbStringify),
         (Jsonb, Float64) => expr.call_unary(CastJsonbToFloat64),
         (Jsonb, Bool) => expr.call_unary(CastJsonbToBool),
@@ -3194,6 +3513,23 @@ where
         (String, Interval) => expr.call_unary(CastStringToInterval),
         (String, Bytes) => expr.call_unary(CastStringToBytes),
         (String, Jsonb) => expr.call_unary(CastStringToJsonb),
+        (String, Point) => expr.call_unary(CastStringToPoint),
+        (String, Inet) => expr.call_unary(CastStringToInet),
+        (String, Cidr) => expr.call_unary(CastStringToCidr),
+        (String, Enum(id)) => {
+            let func = CastStringToEnum(id, ecx.qcx.scx.enum_labels(id)?);
+            // A literal label can be checked early. Other labels are checked
+            // as they are evaluated.
+            if let ScalarExpr::Literal(row, _) = &expr {
+                let datum = row.unpack_first();
+                if !datum.is_null() {
+                    if let Some(err) = func.input_error(datum) {
+                        bail!("{}", err);
+                    }
+                }
+            }
+            expr.call_unary(func)
+        }
         (Unknown, _) => {
             ScalarExpr::literal(Datum::Null, ColumnType::new(to_scalar_type).nullable(true))
         }
@@ -3246,6 +3582,35 @@ where
     })
 }
 
+/// Plans an argument to a function that expects a point. Strings, like the
+/// literal `'(1,2)'`, are converted to points.
+fn plan_point_arg(ecx: &ExprContext, name: &str, e: &Expr) -> Result<ScalarExpr, failure::Error> {
+    let expr = plan_expr(ecx, e, Some(ScalarType::Point))?;
+    match ecx.column_type(&expr).scalar_type {
+        ScalarType::Point => Ok(expr),
+        ScalarType::String | ScalarType::Unknown => {
+            plan_cast_internal(ecx, name, expr, ScalarType::Point)
+        }
+        other => bail!("{} does not accept arguments of type {:?}", name, other),
+    }
+}
+
+/// Plans an argument to a function or operator that expects an IP address.
+/// Strings, like the literal `'10.0.0.0/8'`, are converted to `inet`s.
+fn plan_inet_arg<S>(ecx: &ExprContext, name: S, e: &Expr) -> Result<ScalarExpr, failure::Error>
+where
+    S: fmt::Display + Copy,
+{
+    let expr = plan_expr(ecx, e, Some(ScalarType::Inet))?;
+    match ecx.column_type(&expr).scalar_type {
+        ScalarType::Inet | ScalarType::Cidr => Ok(expr),
+        ScalarType::String | ScalarType::Unknown => {
+            plan_cast_internal(ecx, name, expr, ScalarType::Inet)
+        }
+        other => bail!("{} does not accept arguments of type {:?}", name, other),
+    }
+}
+
 fn promote_int_int64<'a, S>(
     ecx: &ExprContext<'a>,
     name: S,
@@ -3327,6 +3692,9 @@ pub fn scalar_type_from_sql(data_type: &DataType) -> Result<ScalarType, failure:
         DataType::Interval => ScalarType::Interval,
         DataType::Bytea => ScalarType::Bytes,
         DataType::Jsonb => ScalarType::Jsonb,
+        DataType::Point => ScalarType::Point,
+        DataType::Inet => ScalarType::Inet,
+        DataType::Cidr => ScalarType::Cidr,
         other @ DataType::Array(_)
         | other @ DataType::Binary(..)
         | other @ DataType::Blob(_)
@@ -3335,7 +3703,8 @@ pub fn scalar_type_from_sql(data_type: &DataType) -> Result<ScalarType, failure:
         | other @ DataType::Time
         | other @ DataType::TimeTz
         | other @ DataType::Uuid
-        | other @ DataType::Varbinary(_) => bail!("Unexpected SQL type: {:?}", other),
+        | other @ DataType::Varbinary(_)
+        | other @ DataType::Custom(_) => bail!("Unexpected SQL type: {:?}", other),
     })
 }
 
@@ -3395,6 +3764,16 @@ impl<'ast> Visit<'ast> for AggregateFuncVisitor<'ast> {
     }
 }
 
+/// Rejects a call to the function `name`, whose result changes over time, in a
+/// query whose result is maintained over time.
+fn bail_volatile(name: &str) -> Result<ScalarExpr, failure::Error> {
+    bail!(
+        "{} cannot be used in static queries, like the queries of views and indexes, \
+         because its result changes over time and so cannot be maintained",
+        name
+    )
+}
+
 /// Specifies how long a query will live. This impacts whether the query is
 /// allowed to reason about the time at which it is running, e.g., by calling
 /// the `now()` function.
@@ -3498,7 +3877,8 @@ fn is_table_func(name: &str) -> bool {
         | "jsonb_each_text"
         | "jsonb_array_elements_text"
         | "regexp_extract"
-        | "csv_extract" => true,
+        | "csv_extract"
+        | "text_tokens" => true,
         _ => false,
     }
 }
@@ -3506,7 +3886,7 @@ fn is_table_func(name: &str) -> bool {
 fn is_aggregate_func(name: &str) -> bool {
     match name {
         // avg is handled by transform::AvgFuncRewriter.
-        "max" | "min" | "sum" | "count" => true,
+        "max" | "min" | "sum" | "count" | "mz_match_pattern" => true,
         _ => false,
     }
 }
@@ -60,6 +60,13 @@ const EXTRA_FLOAT_DIGITS: ServerVar<&i32> = ServerVar {
     description: "Adjusts the number of digits displayed for floating-point values (PostgreSQL).",
 };
 
+const PEEK_METADATA: ServerVar<&bool> = ServerVar {
+    name: unicase::Ascii::new("peek_metadata"),
+    value: &false,
+    description:
+        "Reports the timestamp, row count, and duration of each query in a notice (Materialize).",
+};
+
 const SEARCH_PATH: ServerVar<&[&str]> = ServerVar {
     name: unicase::Ascii::new("search_path"),
     value: &["mz_catalog", "pg_catalog", "public"],
@@ -90,6 +97,7 @@ pub struct Session {
     database: SessionVar<str>,
     date_style: ServerVar<&'static str>,
     extra_float_digits: SessionVar<i32>,
+    peek_metadata: SessionVar<bool>,
     search_path: ServerVar<&'static [&'static str]>,
     server_version: ServerVar<&'static str>,
     sql_safe_updates: SessionVar<bool>,
@@ -102,6 +110,9 @@ pub struct Session {
     /// Portals are primarily a way to retrieve the results for a query with all
     /// parameters bound.
     portals: HashMap<String, Portal>,
+    /// Whether the client fetches the rows of the statement being executed a
+    /// few at a time, suspending its portal in between
+    fetching_incrementally: bool,
 }
 
 impl fmt::Debug for Session {
@@ -112,12 +123,14 @@ impl fmt::Debug for Session {
             .field("database", &self.database())
             .field("date_style", &self.date_style())
             .field("extra_float_digits", &self.extra_float_digits())
+            .field("peek_metadata", &self.peek_metadata())
             .field("search_path", &self.search_path())
             .field("server_version", &self.server_version())
             .field("sql_safe_updates", &self.sql_safe_updates())
             .field("transaction", &self.transaction())
             .field("prepared_statements", &self.prepared_statements.keys())
             .field("portals", &self.portals.keys())
+            .field("fetching_incrementally", &self.fetching_incrementally)
             .finish()
     }
 }
@@ -131,12 +144,14 @@ impl Default for Session {
             database: SessionVar::new(&DATABASE),
             date_style: DATE_STYLE,
             extra_float_digits: SessionVar::new(&EXTRA_FLOAT_DIGITS),
+            peek_metadata: SessionVar::new(&PEEK_METADATA),
             search_path: SEARCH_PATH,
             server_version: SERVER_VERSION,
             sql_safe_updates: SessionVar::new(&SQL_SAFE_UPDATES),
             transaction: TransactionStatus::Idle,
             prepared_statements: HashMap::new(),
             portals: HashMap::new(),
+            fetching_incrementally: false,
         }
     }
 }
@@ -151,6 +166,7 @@ impl Session {
             &self.database,
             &self.date_style,
             &self.extra_float_digits,
+            &self.peek_metadata,
             &self.search_path,
             &self.server_version,
             &self.sql_safe_updates,
@@ -190,6 +206,8 @@ impl Session {
             Ok(&self.date_style)
         } else if name == EXTRA_FLOAT_DIGITS.name {
             Ok(&self.extra_float_digits)
+        } else if name == PEEK_METADATA.name {
+            Ok(&self.peek_metadata)
         } else if name == SEARCH_PATH.name {
             Ok(&self.search_path)
         } else if name == SERVER_VERSION.name {
@@ -219,6 +237,8 @@ impl Session {
             bail!("parameter {} is read only", DATE_STYLE.name);
         } else if name == EXTRA_FLOAT_DIGITS.name {
             self.extra_float_digits.set(value)
+        } else if name == PEEK_METADATA.name {
+            self.peek_metadata.set(value)
         } else if name == SEARCH_PATH.name {
             bail!("parameter {} is read only", SEARCH_PATH.name);
         } else if name == SERVER_VERSION.name {
@@ -255,6 +275,11 @@ impl Session {
         *self.extra_float_digits.value()
     }
 
+    /// Returns the value of the `peek_metadata` configuration parameter.
+    pub fn peek_metadata(&self) -> bool {
+        *self.peek_metadata.value()
+    }
+
     /// Returns the value of the `search_path` configuration parameter.
     pub fn search_path(&self) -> &'static [&'static str] {
         self.search_path.value
@@ -299,6 +324,19 @@ impl Session {
         &self.transaction
     }
 
+    /// Sets whether the client fetches the rows of the statement that it is
+    /// about to execute a few at a time, suspending its portal in between.
+    pub fn set_fetching_incrementally(&mut self, fetching_incrementally: bool) {
+        self.fetching_incrementally = fetching_incrementally;
+    }
+
+    /// Reports whether the client fetches the rows of the statement being
+    /// executed a few at a time, as set by
+    /// [`Session::set_fetching_incrementally`].
+    pub fn fetching_incrementally(&self) -> bool {
+        self.fetching_incrementally
+    }
+
     /// Ensure that the given prepared statement is present in this session
     pub fn set_prepared_statement(&mut self, name: String, statement: PreparedStatement) {
         self.prepared_statements.insert(name, statement);
@@ -14,18 +14,25 @@
 use itertools::join;
 use std::collections::{BTreeMap, HashMap};
 use std::iter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use failure::{bail, format_err, ResultExt};
+use futures::future::{self, Either};
 use itertools::Itertools;
+use tokio_postgres::NoTls;
 use url::Url;
 
 use catalog::names::{DatabaseSpecifier, FullName, PartialName};
-use catalog::{Catalog, CatalogItem, SchemaType};
+use catalog::{Catalog, CatalogEntry, CatalogItem, SchemaType};
 use dataflow_types::{
-    AvroEncoding, Consistency, CsvEncoding, DataEncoding, Envelope, ExternalSourceConnector,
-    FileSourceConnector, KafkaSinkConnector, KafkaSourceConnector, KinesisSourceConnector,
-    PeekWhen, ProtobufEncoding, RowSetFinishing, SinkConnector, SourceConnector,
+    AvroEncoding, Compression, Consistency, CsvEncoding, DataEncoding, Deduplication,
+    ElasticsearchSinkConnector, Envelope, ExternalSourceConnector, FileSourceConnector,
+    JsonEncoding, KafkaSinkConnector, KafkaSourceConnector, KinesisSourceConnector, MqttQos,
+    MqttSourceConnector, MysqlSourceConnector, NatsSinkConnector, NatsSourceConnector, PeekWhen,
+    PluginSinkConnector, PluginSourceConnector, PostgresSourceConnector, ProtobufEncoding,
+    RedisSinkConnector, RedisValueFormat, RowSetFinishing, S3SourceConnector, SinkConnector,
+    SourceConnector,
 };
 use expr::GlobalId;
 use interchange::{avro, protobuf};
@@ -34,13 +41,14 @@ use ore::future::MaybeFuture;
 use repr::strconv;
 use repr::{ColumnType, Datum, RelationDesc, RelationType, Row, RowArena, ScalarType};
 use sql_parser::ast::{
-    AvroSchema, Connector, CsrSeed, Format, Ident, ObjectName, ObjectType, Query, SetVariableValue,
-    ShowStatementFilter, Stage, Statement, Value,
+    AvroSchema, ColumnDef, Connector, CsrSeed, CsvColumns, DataType, Expr, Format, Ident,
+    ObjectName, ObjectType, Query, SetVariableValue, ShowStatementFilter, SqlOption, Stage,
+    Statement, Value,
 };
 
 use crate::expr::like::build_like_regex_from_string;
 use crate::query::QueryLifetime;
-use crate::{normalize, query, Index, Params, Plan, PlanSession, Sink, Source, View};
+use crate::{normalize, query, Index, Params, Plan, PlanSession, Sink, Source, Type, View};
 
 pub fn describe_statement(
     catalog: &Catalog,
@@ -55,21 +63,47 @@ pub fn describe_statement(
         | Statement::CreateSource { .. }
         | Statement::CreateSink { .. }
         | Statement::CreateView { .. }
+        | Statement::CreateType { .. }
         | Statement::DropDatabase { .. }
         | Statement::DropObjects { .. }
+        | Statement::Comment { .. }
+        | Statement::AlterTags { .. }
         | Statement::SetVariable { .. }
         | Statement::StartTransaction { .. }
         | Statement::Rollback { .. }
-        | Statement::Commit { .. } => (None, vec![]),
+        | Statement::Commit { .. }
+        | Statement::Listen { .. }
+        | Statement::Unlisten { .. }
+        | Statement::InstallDemo { .. }
+        | Statement::ImportTrace { .. }
+        | Statement::Resume => (None, vec![]),
+
+        Statement::Explain {
+            stage: Stage::Dataflow,
+            ..
+        } => (
+            Some(RelationDesc::empty().add_column("Dataflow", ScalarType::String)),
+            vec![],
+        ),
 
-        Statement::Explain { stage, .. } => (
-            Some(RelationDesc::empty().add_column(
-                match stage {
-                    Stage::Dataflow => "Dataflow",
-                    Stage::Plan => "Plan",
-                },
-                ScalarType::String,
-            )),
+        Statement::Explain {
+            stage: Stage::Plan, ..
+        } => (
+            Some(RelationDesc::empty().add_column("Plan", ScalarType::String)),
+            vec![],
+        ),
+
+        Statement::Explain {
+            stage: Stage::Analyze,
+            ..
+        } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Id", ScalarType::Int64)
+                    .add_column("Operator", ScalarType::String)
+                    .add_column("Records", ScalarType::Int64)
+                    .add_column("Elapsed", ScalarType::Interval),
+            ),
             vec![],
         ),
 
@@ -131,6 +165,108 @@ pub fn describe_statement(
             vec![],
         ),
 
+        Statement::ShowComments { .. } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Name", ScalarType::String)
+                    .add_column("Comment", ScalarType::String),
+            ),
+            vec![],
+        ),
+
+        Statement::ShowTags { .. } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Name", ScalarType::String)
+                    .add_column("Tag", ScalarType::String)
+                    .add_column("Value", ScalarType::String),
+            ),
+            vec![],
+        ),
+
+        Statement::ShowSinkProgress { .. }
+        | Statement::FlushSink { .. }
+        | Statement::FlushSinks { .. } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Name", ScalarType::String)
+                    .add_column("Frontier", ScalarType::Int64),
+            ),
+            vec![],
+        ),
+
+        Statement::ShowSourceErrors { .. } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Name", ScalarType::String)
+                    .add_column("Error", ScalarType::String),
+            ),
+            vec![],
+        ),
+
+        Statement::ShowArrangements => (
+            Some(RelationDesc::new(
+                RelationType::new(vec![
+                    ColumnType::new(ScalarType::String).nullable(true),
+                    ColumnType::new(ScalarType::Int64),
+                    ColumnType::new(ScalarType::String),
+                    ColumnType::new(ScalarType::String),
+                    ColumnType::new(ScalarType::Bool),
+                    ColumnType::new(ScalarType::String),
+                    ColumnType::new(ScalarType::Int64),
+                    ColumnType::new(ScalarType::Int64),
+                    ColumnType::new(ScalarType::Int64),
+                ]),
+                vec![
+                    "Cluster",
+                    "Worker",
+                    "Index",
+                    "Collection",
+                    "Shared",
+                    "Key_types",
+                    "Batches",
+                    "Records",
+                    "Bytes",
+                ]
+                .iter()
+                .map(|s| Some(*s))
+                .collect::<Vec<_>>(),
+            )),
+            vec![],
+        ),
+
+        Statement::CompactCatalog { .. } => (
+            Some(
+                RelationDesc::empty()
+                    .add_column("Superseded bindings", ScalarType::Int64)
+                    .add_column("Orphaned rows", ScalarType::Int64),
+            ),
+            vec![],
+        ),
+
+        Statement::CheckCatalog => (
+            Some(RelationDesc::empty().add_column("Problem", ScalarType::String)),
+            vec![],
+        ),
+
+        Statement::CheckView { name } => {
+            let (_, view) = resolve_view(scx, name)?;
+            (
+                Some(view.desc.clone().add_column("Divergence", ScalarType::String)),
+                vec![],
+            )
+        }
+
+        Statement::ExportTrace { .. } => (
+            Some(RelationDesc::empty().add_column("Updates", ScalarType::Int64)),
+            vec![],
+        ),
+
+        Statement::Quiesce { .. } => (
+            Some(RelationDesc::empty().add_column("Timestamp", ScalarType::Int64)),
+            vec![],
+        ),
+
         Statement::ShowObjects {
             object_type,
             full,
@@ -179,10 +315,13 @@ pub fn describe_statement(
             }
         }
 
-        Statement::Tail { name, .. } => {
-            let name = scx.resolve_name(name)?;
-            let sql_object = scx.catalog.get(&name)?;
-            (Some(sql_object.desc()?.clone()), vec![])
+        Statement::Tail {
+            name,
+            columns,
+            selection,
+        } => {
+            let (_, desc, _, _) = plan_tail(scx, name, columns, selection)?;
+            (Some(desc), vec![])
         }
 
         Statement::Query(query) => {
@@ -192,7 +331,7 @@ pub fn describe_statement(
             // dance when bind parameters are implemented, so punting for now.
             let (_relation_expr, desc, _finishing, param_types) =
                 query::plan_root_query(scx, *query, QueryLifetime::OneShot)?;
-            (Some(desc), param_types)
+            (Some(render_enum_types(desc)), param_types)
         }
         Statement::CreateTable { .. } => bail!("CREATE TABLE statements are not supported. Try CREATE SOURCE or CREATE [MATERIALIZED] VIEW instead."),
         _ => bail!("unsupported SQL statement: {:?}", stmt),
@@ -206,7 +345,11 @@ fn handle_sync_statement(
 ) -> Result<Plan, failure::Error> {
     match stmt {
         Statement::CreateSource { .. } => unreachable!(),
-        Statement::Tail { name } => handle_tail(scx, name),
+        Statement::Tail {
+            name,
+            columns,
+            selection,
+        } => handle_tail(scx, name, columns, selection),
         Statement::StartTransaction { .. } => handle_start_transaction(),
         Statement::Commit { .. } => handle_commit_transaction(),
         Statement::Rollback { .. } => handle_rollback_transaction(),
@@ -221,6 +364,7 @@ fn handle_sync_statement(
         Statement::CreateView { .. } => handle_create_view(scx, stmt, params),
         Statement::CreateSink { .. } => handle_create_sink(scx, stmt),
         Statement::CreateIndex { .. } => handle_create_index(scx, stmt),
+        Statement::CreateType { .. } => handle_create_type(scx, stmt),
         Statement::DropDatabase { name, if_exists } => handle_drop_database(scx, name, if_exists),
         Statement::DropObjects {
             object_type,
@@ -257,6 +401,49 @@ fn handle_sync_statement(
         } => handle_show_columns(scx, extended, full, table_name, filter.as_ref()),
         Statement::ShowCreateView { view_name } => handle_show_create_view(scx, view_name),
         Statement::ShowCreateSource { source_name } => handle_show_create_source(scx, source_name),
+        Statement::ShowComments { from } => handle_show_comments(scx, from),
+        Statement::ShowTags { from } => handle_show_tags(scx, from),
+        Statement::ShowSinkProgress { from } => handle_show_sink_progress(scx, from),
+        Statement::ShowSourceErrors { from } => handle_show_source_errors(scx, from),
+        Statement::ShowArrangements => Ok(Plan::ShowArrangements),
+        Statement::CompactCatalog { vacuum } => Ok(Plan::CompactCatalog { vacuum }),
+        Statement::CheckCatalog => Ok(Plan::CheckCatalog),
+        Statement::CheckView { name } => {
+            let (id, _) = resolve_view(scx, name)?;
+            Ok(Plan::CheckView { id })
+        }
+        Statement::Listen { channel } => Ok(Plan::Listen {
+            channel: normalize::ident(channel),
+        }),
+        Statement::Unlisten { channel } => Ok(Plan::Unlisten {
+            channel: channel.map(normalize::ident),
+        }),
+        Statement::InstallDemo { name } => Ok(Plan::InstallDemo {
+            name: normalize::ident(name),
+        }),
+        Statement::ExportTrace {
+            name,
+            path,
+            history,
+        } => handle_export_trace(scx, name, path, history),
+        Statement::ImportTrace { name, path } => Ok(Plan::ImportTrace {
+            name: scx.allocate_name(normalize::object_name(name)?),
+            path: PathBuf::from(path),
+        }),
+        Statement::Quiesce { as_of } => Ok(Plan::Quiesce { as_of }),
+        Statement::Resume => Ok(Plan::Resume),
+        Statement::FlushSink { name } => handle_flush_sink(scx, name),
+        Statement::FlushSinks { from } => handle_flush_sinks(scx, from),
+        Statement::Comment {
+            object_type,
+            name,
+            comment,
+        } => handle_comment(scx, object_type, name, comment),
+        Statement::AlterTags {
+            object_type,
+            name,
+            tags,
+        } => handle_alter_tags(scx, object_type, name, tags),
         Statement::Explain { stage, query } => handle_explain(scx, stage, *query, params),
 
         _ => bail!("unsupported SQL statement: {:?}", stmt),
@@ -307,18 +494,84 @@ fn handle_show_variable(_: &StatementContext, variable: Ident) -> Result<Plan, f
     }
 }
 
-fn handle_tail(scx: &StatementContext, from: ObjectName) -> Result<Plan, failure::Error> {
+fn handle_tail(
+    scx: &StatementContext,
+    from: ObjectName,
+    columns: Vec<Ident>,
+    selection: Option<Expr>,
+) -> Result<Plan, failure::Error> {
+    let (source, _, filter, project) = plan_tail(scx, from, columns, selection)?;
+    Ok(Plan::Tail {
+        source,
+        filter,
+        project,
+    })
+}
+
+/// Resolves the source or view that a `TAIL` statement streams, and plans the
+/// statement's column list and `WHERE` clause against it.
+///
+/// Returns the tailed catalog entry, the description of the streamed rows,
+/// the predicates that streamed rows must satisfy, and the columns to which
+/// they are projected, if any.
+#[allow(clippy::type_complexity)]
+fn plan_tail(
+    scx: &StatementContext,
+    from: ObjectName,
+    columns: Vec<Ident>,
+    selection: Option<Expr>,
+) -> Result<
+    (
+        CatalogEntry,
+        RelationDesc,
+        Vec<::expr::ScalarExpr>,
+        Option<Vec<usize>>,
+    ),
+    failure::Error,
+> {
     let from = scx.resolve_name(from)?;
     let entry = scx.catalog.get(&from)?;
     match entry.item() {
-        CatalogItem::View(_) => Ok(Plan::Tail(entry.clone())),
-        CatalogItem::Source(_) => Ok(Plan::Tail(entry.clone())),
+        CatalogItem::View(_) | CatalogItem::Source(_) => (),
         _ => bail!(
             "'{}' cannot be tailed because it is a {}",
             from,
             entry.item().type_string()
         ),
     }
+    let desc = entry.desc()?;
+    for typ in desc.iter_types() {
+        if let ScalarType::Enum(_) = typ.scalar_type {
+            bail!("'{}' cannot be tailed because it has enum columns", from);
+        }
+    }
+
+    let filter = match selection {
+        Some(selection) => vec![query::plan_tail_filter(scx, desc, &selection)?],
+        None => vec![],
+    };
+
+    if columns.is_empty() {
+        return Ok((entry.clone(), desc.clone(), filter, None));
+    }
+    let mut project = vec![];
+    for column in columns {
+        let name = normalize::column_name(column);
+        match desc.get_by_name(&name) {
+            Some((i, _)) => project.push(i),
+            None => bail!("column '{}' does not exist in '{}'", name, from),
+        }
+    }
+    let projected_desc = RelationDesc::new(
+        RelationType::new(
+            project
+                .iter()
+                .map(|i| desc.typ().column_types[*i].clone())
+                .collect(),
+        ),
+        project.iter().map(|i| desc.get_name(*i).cloned()),
+    );
+    Ok((entry.clone(), projected_desc, filter, Some(project)))
 }
 
 fn handle_start_transaction() -> Result<Plan, failure::Error> {
@@ -360,6 +613,7 @@ fn handle_show_objects(
     let classify_id = |id| match id {
         GlobalId::System(_) => "SYSTEM",
         GlobalId::User(_) => "USER",
+        GlobalId::Transient(_) => "TRANSIENT",
     };
     let make_row = |name: &str, class| {
         if full {
@@ -418,36 +672,7 @@ fn handle_show_objects(
             None => build_like_regex_from_string("%")?,
         };
 
-        let empty_schema = BTreeMap::new();
-        let items = if let Some(mut from) = from {
-            if from.0.len() > 2 {
-                bail!(
-                    "schema name '{}' cannot have more than two components",
-                    from
-                );
-            }
-            let schema_name = normalize::ident(from.0.pop().unwrap());
-            let database_spec = from
-                .0
-                .pop()
-                .map(|n| DatabaseSpecifier::Name(normalize::ident(n)))
-                .unwrap_or_else(|| scx.session.database());
-            &scx.catalog
-                .database_resolver(database_spec)?
-                .resolve_schema(&schema_name)
-                .ok_or_else(|| format_err!("schema '{}' does not exist", schema_name))?
-                .0
-                .items
-        } else {
-            let resolver = scx.catalog.database_resolver(scx.session.database())?;
-            scx.session
-                .search_path()
-                .iter()
-                .filter_map(|schema_name| resolver.resolve_schema(schema_name))
-                .find(|(_schema, typ)| *typ == SchemaType::Normal)
-                .map_or_else(|| &empty_schema, |(schema, _typ)| &schema.items)
-        };
-
+        let items = resolve_schema_items(scx, from)?;
         let filtered_items = items
             .iter()
             .map(|(name, id)| (name, scx.catalog.get_by_id(id)))
@@ -475,6 +700,196 @@ fn handle_show_objects(
     }
 }
 
+/// Resolves the items in the schema named by `from`, or, if `from` is not
+/// specified, in the first normal schema in the session's search path.
+fn resolve_schema_items(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Vec<(String, GlobalId)>, failure::Error> {
+    let items = if let Some(mut from) = from {
+        if from.0.len() > 2 {
+            bail!(
+                "schema name '{}' cannot have more than two components",
+                from
+            );
+        }
+        let schema_name = normalize::ident(from.0.pop().unwrap());
+        let database_spec = from
+            .0
+            .pop()
+            .map(|n| DatabaseSpecifier::Name(normalize::ident(n)))
+            .unwrap_or_else(|| scx.session.database());
+        scx.catalog
+            .database_resolver(database_spec)?
+            .resolve_schema(&schema_name)
+            .ok_or_else(|| format_err!("schema '{}' does not exist", schema_name))?
+            .0
+            .items
+            .iter()
+            .map(|(name, id)| (name.clone(), *id))
+            .collect()
+    } else {
+        let resolver = scx.catalog.database_resolver(scx.session.database())?;
+        scx.session
+            .search_path()
+            .iter()
+            .filter_map(|schema_name| resolver.resolve_schema(schema_name))
+            .find(|(_schema, typ)| *typ == SchemaType::Normal)
+            .map_or_else(Vec::new, |(schema, _typ)| {
+                schema
+                    .items
+                    .iter()
+                    .map(|(name, id)| (name.clone(), *id))
+                    .collect()
+            })
+    };
+    Ok(items)
+}
+
+fn handle_show_comments(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Plan, failure::Error> {
+    let rows = resolve_schema_items(scx, from)?
+        .into_iter()
+        .filter_map(|(name, id)| {
+            scx.catalog
+                .get_by_id(&id)
+                .comment()
+                .map(|comment| Row::pack(&[Datum::from(name.as_str()), Datum::from(comment)]))
+        })
+        .collect();
+    Ok(Plan::SendRows(rows))
+}
+
+fn handle_show_tags(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Plan, failure::Error> {
+    let mut rows = vec![];
+    for (name, id) in resolve_schema_items(scx, from)? {
+        for (key, value) in scx.catalog.get_by_id(&id).tags() {
+            rows.push(Row::pack(&[
+                Datum::from(name.as_str()),
+                Datum::from(key.as_str()),
+                Datum::from(value.as_str()),
+            ]));
+        }
+    }
+    Ok(Plan::SendRows(rows))
+}
+
+fn handle_show_sink_progress(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Plan, failure::Error> {
+    let ids = resolve_schema_items(scx, from)?
+        .into_iter()
+        .filter(|(_name, id)| match scx.catalog.get_by_id(id).item() {
+            CatalogItem::Sink(_) => true,
+            _ => false,
+        })
+        .collect();
+    Ok(Plan::ShowSinkProgress { ids })
+}
+
+fn handle_flush_sink(scx: &StatementContext, name: ObjectName) -> Result<Plan, failure::Error> {
+    let name = scx.resolve_name(name)?;
+    let entry = scx.catalog.get(&name)?;
+    match entry.item() {
+        CatalogItem::Sink(_) => Ok(Plan::FlushSinks {
+            ids: vec![(name.item, entry.id())],
+        }),
+        _ => bail!("'{}' is not a sink", name),
+    }
+}
+
+fn handle_flush_sinks(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Plan, failure::Error> {
+    let ids = resolve_schema_items(scx, from)?
+        .into_iter()
+        .filter(|(_name, id)| match scx.catalog.get_by_id(id).item() {
+            CatalogItem::Sink(_) => true,
+            _ => false,
+        })
+        .collect();
+    Ok(Plan::FlushSinks { ids })
+}
+
+fn handle_show_source_errors(
+    scx: &StatementContext,
+    from: Option<ObjectName>,
+) -> Result<Plan, failure::Error> {
+    let mut rows = vec![];
+    for (name, id) in resolve_schema_items(scx, from)? {
+        if let Some(error) = scx.catalog.get_by_id(&id).error() {
+            rows.push(Row::pack(&[Datum::from(name.as_str()), Datum::from(error)]));
+        }
+    }
+    Ok(Plan::SendRows(rows))
+}
+
+/// Resolves the target of a `COMMENT ON` or `ALTER ... SET TAGS` statement,
+/// verifying that it is a user-defined item of the specified type.
+fn resolve_annotation_target<'a>(
+    scx: &'a StatementContext,
+    object_type: ObjectType,
+    name: ObjectName,
+) -> Result<&'a CatalogEntry, failure::Error> {
+    let name = scx.resolve_name(name)?;
+    let entry = scx.catalog.get(&name)?;
+    if let GlobalId::System(_) = entry.id() {
+        bail!(
+            "cannot annotate item {} because it is required by the database system",
+            name
+        );
+    }
+    if !object_type_matches(object_type, entry.item()) {
+        bail!("{} is not of type {}", name, object_type);
+    }
+    Ok(entry)
+}
+
+fn handle_comment(
+    scx: &StatementContext,
+    object_type: ObjectType,
+    name: ObjectName,
+    comment: Option<String>,
+) -> Result<Plan, failure::Error> {
+    let entry = resolve_annotation_target(scx, object_type, name)?;
+    Ok(Plan::SetComment {
+        id: entry.id(),
+        comment,
+    })
+}
+
+fn handle_alter_tags(
+    scx: &StatementContext,
+    object_type: ObjectType,
+    name: ObjectName,
+    tags: Vec<SqlOption>,
+) -> Result<Plan, failure::Error> {
+    let entry = resolve_annotation_target(scx, object_type, name)?;
+    let tags = tags
+        .into_iter()
+        .map(|tag| {
+            let value = match tag.value {
+                Value::SingleQuotedString(s) => Some(s),
+                Value::Null => None,
+                _ => bail!("value of tag {} must be a string or NULL", tag.name),
+            };
+            Ok((normalize::ident(tag.name), value))
+        })
+        .collect::<Result<Vec<_>, failure::Error>>()?;
+    Ok(Plan::SetTags {
+        id: entry.id(),
+        object_type,
+        tags,
+    })
+}
+
 fn handle_show_indexes(
     scx: &StatementContext,
     extended: bool,
@@ -511,6 +926,8 @@ fn handle_show_indexes(
                 keys,
                 on,
                 eval_env: _,
+                cluster: _,
+                replicas: _,
             }) => {
                 let key_sqls = match crate::parse(create_sql.to_owned())
                     .expect("create_sql cannot be invalid")
@@ -577,10 +994,19 @@ fn handle_show_columns(
         .iter()
         .map(|(name, typ)| {
             let name = name.map(|n| n.to_string());
+            let type_name = match typ.scalar_type {
+                ScalarType::Enum(id) => scx
+                    .catalog
+                    .get_by_id(&GlobalId::User(id))
+                    .name()
+                    .item
+                    .clone(),
+                _ => pgrepr::Type::from(typ.scalar_type).name().to_owned(),
+            };
             Row::pack(&[
                 Datum::String(name.as_deref().unwrap_or("?")),
                 Datum::String(if typ.nullable { "YES" } else { "NO" }),
-                Datum::String(pgrepr::Type::from(typ.scalar_type).name()),
+                Datum::String(&type_name),
             ])
         })
         .collect();
@@ -604,6 +1030,37 @@ fn handle_show_create_view(
     ])]))
 }
 
+/// Resolves `name` to the view that `CHECK VIEW` checks.
+fn resolve_view<'a>(
+    scx: &StatementContext<'a>,
+    name: ObjectName,
+) -> Result<(GlobalId, &'a catalog::View), failure::Error> {
+    let name = scx.resolve_name(name)?;
+    let entry = scx.catalog.get(&name)?;
+    match entry.item() {
+        CatalogItem::View(view) => Ok((entry.id(), view)),
+        _ => bail!("'{}' is not a view", name),
+    }
+}
+
+fn handle_export_trace(
+    scx: &StatementContext,
+    name: ObjectName,
+    path: String,
+    history: bool,
+) -> Result<Plan, failure::Error> {
+    let name = scx.resolve_name(name)?;
+    let entry = scx.catalog.get(&name)?;
+    match entry.item() {
+        CatalogItem::Source(_) | CatalogItem::View(_) => Ok(Plan::ExportTrace {
+            id: entry.id(),
+            path: PathBuf::from(path),
+            history,
+        }),
+        _ => bail!("'{}' is not a view or source", name),
+    }
+}
+
 fn handle_show_create_source(
     scx: &StatementContext,
     object_name: ObjectName,
@@ -631,6 +1088,23 @@ fn handle_show_create_source(
                         // TODO https://github.com/MaterializeInc/materialize/issues/1093
                         format!("file://{}", c.path.to_string_lossy())
                     }
+                    ExternalSourceConnector::Mqtt(c) => {
+                        format!("mqtt://{}:{}/{}", c.host, c.port, c.topic)
+                    }
+                    ExternalSourceConnector::Nats(c) => format!("{}/{}", c.url, c.subject),
+                    ExternalSourceConnector::Plugin(c) => format!("plugin://{}", c.name),
+                    // The connection string is not shown, as it may contain a
+                    // password.
+                    ExternalSourceConnector::Postgres(c) => format!("postgres table {}", c.table),
+                    // Likewise, the credentials in the URL are not shown.
+                    ExternalSourceConnector::Mysql(c) => format!(
+                        "mysql://{}:{}/{}.{}",
+                        c.url.host_str().unwrap_or(""),
+                        c.url.port().unwrap_or(3306),
+                        c.database,
+                        c.table
+                    ),
+                    ExternalSourceConnector::S3(c) => format!("s3://{}/{}", c.bucket, c.prefix),
                 }
             }
             SourceConnector::Local => "<internally generated source>".to_string(),
@@ -646,35 +1120,81 @@ fn handle_show_create_source(
 
 fn handle_create_sink(scx: &StatementContext, stmt: Statement) -> Result<Plan, failure::Error> {
     let create_sql = normalize::create_statement(scx, stmt.clone())?;
-    let (name, from, connector, format, if_not_exists) = match stmt {
+    let (name, in_cluster, from, connector, format, if_not_exists) = match stmt {
         Statement::CreateSink {
             name,
+            in_cluster,
             from,
             connector,
             format,
             if_not_exists,
-        } => (name, from, connector, format, if_not_exists),
+        } => (name, in_cluster, from, connector, format, if_not_exists),
         _ => unreachable!(),
     };
 
-    let (mut broker, topic) = match connector {
+    let name = scx.allocate_name(normalize::object_name(name)?);
+    let from = scx.resolve_name(from)?;
+    let catalog_entry = scx.catalog.get(&from)?;
+    let relation_desc = catalog_entry.desc()?;
+
+    let connector = match connector {
+        Connector::Elasticsearch {
+            url,
+            index,
+            with_options,
+        } => build_elasticsearch_sink(url, index, with_options, format, relation_desc)?,
         Connector::File { .. } => bail!("file sinks are not yet supported"),
         Connector::Kafka {
             broker,
             topic,
             with_options,
-        } => {
-            if !with_options.is_empty() {
-                bail!(
-                    "Unexpected WITH options: {}",
-                    join(with_options.into_iter().map(|o| o.name.value), ",")
-                )
-            }
-            (broker, topic)
-        }
+        } => build_kafka_sink(broker, topic, with_options, format, relation_desc)?,
         Connector::Kinesis { .. } => bail!("Kinesis sinks are not yet supported"),
+        Connector::Mqtt { .. } => bail!("MQTT sinks are not yet supported"),
+        Connector::Mysql { .. } => bail!("MySQL sinks are not supported"),
+        Connector::Nats {
+            url,
+            subject,
+            with_options,
+        } => build_nats_sink(url, subject, with_options, format, relation_desc)?,
+        Connector::Plugin { name, with_options } => {
+            build_plugin_sink(name, with_options, format, relation_desc)?
+        }
+        Connector::Postgres { .. } => bail!("PostgreSQL sinks are not supported"),
+        Connector::Redis { url, with_options } => {
+            build_redis_sink(url, with_options, format, relation_desc)?
+        }
+        Connector::S3 { .. } => bail!("S3 sinks are not supported"),
+    };
+
+    let sink = Sink {
+        create_sql,
+        from: catalog_entry.id(),
+        connector,
+        cluster: in_cluster.map(normalize::ident),
     };
 
+    Ok(Plan::CreateSink {
+        name,
+        sink,
+        if_not_exists,
+    })
+}
+
+fn build_kafka_sink(
+    mut broker: String,
+    topic: String,
+    with_options: Vec<SqlOption>,
+    format: Format,
+    relation_desc: &RelationDesc,
+) -> Result<SinkConnector, failure::Error> {
+    if !with_options.is_empty() {
+        bail!(
+            "Unexpected WITH options: {}",
+            join(with_options.into_iter().map(|o| o.name.value), ",")
+        )
+    }
+
     let schema_registry_url = match format {
         Format::Avro(AvroSchema::CsrUrl { url, seed }) => {
             if seed.is_some() {
@@ -691,40 +1211,258 @@ fn handle_create_sink(scx: &StatementContext, stmt: Statement) -> Result<Plan, f
 
     let url = broker.parse()?;
 
-    let name = scx.allocate_name(normalize::object_name(name)?);
-    let from = scx.resolve_name(from)?;
-    let catalog_entry = scx.catalog.get(&from)?;
-
     // Validate that we can actually encode this stream as Avro.
-    let relation_desc = catalog_entry.desc()?.clone();
-    let _ = interchange::avro::encode_schema(&relation_desc)?;
+    let _ = interchange::avro::encode_schema(relation_desc)?;
 
-    let sink = Sink {
-        create_sql,
-        from: catalog_entry.id(),
-        connector: SinkConnector::Kafka(KafkaSinkConnector {
-            url,
-            topic,
-            schema_registry_url: schema_registry_url.parse()?,
-        }),
+    Ok(SinkConnector::Kafka(KafkaSinkConnector {
+        url,
+        topic,
+        schema_registry_url: schema_registry_url.parse()?,
+    }))
+}
+
+fn build_redis_sink(
+    url: String,
+    with_options: Vec<SqlOption>,
+    format: Format,
+    relation_desc: &RelationDesc,
+) -> Result<SinkConnector, failure::Error> {
+    let mut with_options: HashMap<_, _> = with_options
+        .into_iter()
+        .map(|op| (op.name.value.to_ascii_lowercase(), op.value))
+        .collect();
+    let key_indices = match with_options.remove("key") {
+        Some(Value::SingleQuotedString(key)) => sink_key_indices(&key, relation_desc)?,
+        Some(_) => bail!("key must be a string"),
+        None => bail!("Redis sinks require a key option naming the key columns"),
     };
+    let key_prefix = match with_options.remove("key_prefix") {
+        Some(Value::SingleQuotedString(key_prefix)) => key_prefix,
+        Some(_) => bail!("key_prefix must be a string"),
+        None => String::new(),
+    };
+    if !with_options.is_empty() {
+        bail!(
+            "Unexpected WITH options: {}",
+            join(with_options.keys(), ",")
+        )
+    }
 
-    Ok(Plan::CreateSink {
-        name,
-        sink,
-        if_not_exists,
-    })
+    let value_format = match format {
+        Format::Json { columns } if columns.is_empty() => RedisValueFormat::Json,
+        Format::Text => RedisValueFormat::Hash,
+        _ => bail!("Redis sinks support only FORMAT JSON and FORMAT TEXT"),
+    };
+
+    let url: Url = url.parse()?;
+    if url.scheme() != "redis" && url.scheme() != "rediss" {
+        bail!("Redis sink URL must use the redis:// or rediss:// scheme");
+    }
+
+    // Validate that we can actually encode this stream as JSON.
+    let _ = interchange::json::Encoder::new(relation_desc)?;
+
+    Ok(SinkConnector::Redis(RedisSinkConnector {
+        url,
+        key_indices,
+        key_prefix,
+        value_format,
+    }))
+}
+
+fn build_elasticsearch_sink(
+    url: String,
+    index: String,
+    with_options: Vec<SqlOption>,
+    format: Format,
+    relation_desc: &RelationDesc,
+) -> Result<SinkConnector, failure::Error> {
+    let mut with_options: HashMap<_, _> = with_options
+        .into_iter()
+        .map(|op| (op.name.value.to_ascii_lowercase(), op.value))
+        .collect();
+    let key_indices = match with_options.remove("key") {
+        Some(Value::SingleQuotedString(key)) => sink_key_indices(&key, relation_desc)?,
+        Some(_) => bail!("key must be a string"),
+        None => bail!("Elasticsearch sinks require a key option naming the key columns"),
+    };
+    if !with_options.is_empty() {
+        bail!(
+            "Unexpected WITH options: {}",
+            join(with_options.keys(), ",")
+        )
+    }
+
+    match format {
+        Format::Json { columns } if columns.is_empty() => (),
+        _ => bail!("Elasticsearch sinks support only FORMAT JSON"),
+    }
+
+    let url: Url = url.parse()?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        bail!("Elasticsearch sink URL must use the http:// or https:// scheme");
+    }
+    // Elasticsearch index names must be lowercase, and may not contain
+    // characters that are meaningful in URLs or in its own multi-index syntax.
+    if index.is_empty()
+        || index.starts_with(|c: char| c == '_' || c == '-' || c == '+')
+        || index
+            .chars()
+            .any(|c| c.is_uppercase() || r#" "*\<|,>/?#:"#.contains(c))
+    {
+        bail!("invalid Elasticsearch index name: {}", index);
+    }
+
+    // Validate that we can actually encode this stream as JSON.
+    let _ = interchange::json::Encoder::new(relation_desc)?;
+
+    Ok(SinkConnector::Elasticsearch(ElasticsearchSinkConnector {
+        url,
+        index,
+        key_indices,
+    }))
+}
+
+fn build_nats_sink(
+    url: String,
+    subject: String,
+    with_options: Vec<SqlOption>,
+    format: Format,
+    relation_desc: &RelationDesc,
+) -> Result<SinkConnector, failure::Error> {
+    if !with_options.is_empty() {
+        bail!(
+            "Unexpected WITH options: {}",
+            join(with_options.into_iter().map(|o| o.name.value), ",")
+        )
+    }
+
+    match format {
+        Format::Json { columns } if columns.is_empty() => (),
+        _ => bail!("NATS sinks support only FORMAT JSON"),
+    }
+
+    let url = parse_nats_url(&url)?;
+    validate_nats_subject(&subject, false)?;
+
+    // Validate that we can actually encode this stream as JSON.
+    let _ = interchange::json::Encoder::new(relation_desc)?;
+
+    Ok(SinkConnector::Nats(NatsSinkConnector { url, subject }))
+}
+
+fn parse_nats_url(url: &str) -> Result<Url, failure::Error> {
+    let url: Url = url.parse()?;
+    if url.scheme() != "nats" && url.scheme() != "tls" {
+        bail!("NATS URL must use the nats:// or tls:// scheme");
+    }
+    Ok(url)
+}
+
+/// Parses the value of the URL-valued option `name`, which must use the
+/// http:// or https:// scheme.
+fn parse_http_url(url: &str, name: &str) -> Result<Url, failure::Error> {
+    let url: Url = match url.parse() {
+        Ok(url) => url,
+        Err(e) => bail!("invalid {} URL: {}", name, e),
+    };
+    if url.scheme() != "http" && url.scheme() != "https" {
+        bail!("{} URL must use the http:// or https:// scheme", name);
+    }
+    Ok(url)
+}
+
+/// Checks that `subject` is a valid NATS subject. If `wildcards` is set,
+/// `subject` may be a subject filter, in which the wildcard `*` may occupy an
+/// entire token, and the wildcard `>` may occupy the entire last token.
+fn validate_nats_subject(subject: &str, wildcards: bool) -> Result<(), failure::Error> {
+    let tokens: Vec<_> = subject.split('.').collect();
+    for (i, token) in tokens.iter().enumerate() {
+        let valid = match *token {
+            "" => false,
+            "*" => wildcards,
+            ">" => wildcards && i == tokens.len() - 1,
+            _ => !token.contains(|c: char| c == '*' || c == '>' || c.is_whitespace()),
+        };
+        if !valid {
+            bail!("invalid NATS subject: {}", subject);
+        }
+    }
+    Ok(())
+}
+
+fn build_plugin_sink(
+    name: String,
+    with_options: Vec<SqlOption>,
+    format: Format,
+    relation_desc: &RelationDesc,
+) -> Result<SinkConnector, failure::Error> {
+    let options = plugin_options(&with_options)?;
+
+    match format {
+        Format::Json { columns } if columns.is_empty() => (),
+        _ => bail!("plugin sinks support only FORMAT JSON"),
+    }
+
+    // Validate that we can actually encode this stream as JSON.
+    let _ = interchange::json::Encoder::new(relation_desc)?;
+
+    Ok(SinkConnector::Plugin(PluginSinkConnector { name, options }))
+}
+
+/// Converts the `WITH` options of a plugin source or sink to strings.
+///
+/// Plugins interpret their own options, so they are passed along as strings,
+/// whatever their SQL type.
+fn plugin_options(with_options: &[SqlOption]) -> Result<BTreeMap<String, String>, failure::Error> {
+    let mut options = BTreeMap::new();
+    for op in with_options {
+        let value = match &op.value {
+            Value::SingleQuotedString(s) => s.clone(),
+            Value::Number(n) => n.clone(),
+            Value::Boolean(b) => b.to_string(),
+            _ => bail!("option {} must be a string, number, or boolean", op.name),
+        };
+        options.insert(op.name.value.to_ascii_lowercase(), value);
+    }
+    Ok(options)
+}
+
+/// Resolves the comma-separated column names in a sink's `key` option to
+/// column indices.
+fn sink_key_indices(key: &str, desc: &RelationDesc) -> Result<Vec<usize>, failure::Error> {
+    let mut indices = vec![];
+    for name in key.split(',') {
+        let name = name.trim();
+        match desc.get_by_name(&name.into()) {
+            Some((i, _)) if indices.contains(&i) => {
+                bail!("key column {} specified more than once", name)
+            }
+            Some((i, _)) => indices.push(i),
+            None => bail!("key column {} does not exist", name),
+        }
+    }
+    Ok(indices)
 }
 
 fn handle_create_index(scx: &StatementContext, stmt: Statement) -> Result<Plan, failure::Error> {
     let create_sql = normalize::create_statement(scx, stmt.clone())?;
-    let (name, on_name, key_parts, if_not_exists) = match stmt {
+    let (name, in_cluster, replicas, on_name, key_parts, if_not_exists) = match stmt {
         Statement::CreateIndex {
             name,
+            in_cluster,
+            replicas,
+            on_name,
+            key_parts,
+            if_not_exists,
+        } => (
+            name,
+            in_cluster,
+            replicas,
             on_name,
             key_parts,
             if_not_exists,
-        } => (name, on_name, key_parts, if_not_exists),
+        ),
         _ => unreachable!(),
     };
     let on_name = scx.resolve_name(on_name)?;
@@ -749,11 +1487,30 @@ fn handle_create_index(scx: &StatementContext, stmt: Statement) -> Result<Plan,
             create_sql,
             on: catalog_entry.id(),
             keys,
+            cluster: in_cluster.map(normalize::ident),
+            replicas: replicas.into_iter().map(normalize::ident).collect(),
         },
         if_not_exists,
     })
 }
 
+fn handle_create_type(scx: &StatementContext, stmt: Statement) -> Result<Plan, failure::Error> {
+    let create_sql = normalize::create_statement(scx, stmt.clone())?;
+    let (name, labels) = match stmt {
+        Statement::CreateType { name, labels } => (name, labels),
+        _ => unreachable!(),
+    };
+    for (i, label) in labels.iter().enumerate() {
+        if labels[..i].contains(label) {
+            bail!("enum label '{}' used more than once", label);
+        }
+    }
+    Ok(Plan::CreateType {
+        name: scx.allocate_name(normalize::object_name(name)?),
+        typ: Type { create_sql, labels },
+    })
+}
+
 fn handle_create_database(
     _scx: &StatementContext,
     name: Ident,
@@ -796,19 +1553,69 @@ fn handle_create_view(
     params: &Params,
 ) -> Result<Plan, failure::Error> {
     let create_sql = normalize::create_statement(scx, stmt.clone())?;
-    let (name, columns, query, materialized, replace, with_options) = match &mut stmt {
+    let (name, in_cluster, columns, query, materialized, replace, with_options) = match &mut stmt {
         Statement::CreateView {
             name,
+            in_cluster,
             columns,
             query,
             materialized,
             replace,
             with_options,
-        } => (name, columns, query, materialized, replace, with_options),
+        } => (
+            name,
+            in_cluster,
+            columns,
+            query,
+            materialized,
+            replace,
+            with_options,
+        ),
         _ => unreachable!(),
     };
+    let mut with_options: HashMap<_, _> = with_options
+        .iter()
+        .map(|op| (op.name.value.to_ascii_lowercase(), op.value.clone()))
+        .collect();
+    let refresh_interval = match with_options.remove("refresh_interval") {
+        None => None,
+        Some(Value::SingleQuotedString(s)) => {
+            let interval = parse_duration::parse(&s)
+                .map_err(|e| format_err!("invalid refresh_interval: {}", e))?;
+            if interval.as_millis() == 0 {
+                bail!("refresh_interval must be positive");
+            }
+            Some(interval)
+        }
+        Some(_) => bail!("refresh_interval must be a string"),
+    };
+    let changes_only = match with_options.remove("changes_only") {
+        None => false,
+        Some(Value::Boolean(b)) => b,
+        Some(_) => bail!("changes_only must be a boolean"),
+    };
+    let emit_final_after = match with_options.remove("emit_final_after") {
+        None => None,
+        Some(Value::SingleQuotedString(column)) => Some(column),
+        Some(_) => bail!("emit_final_after must be a string"),
+    };
+    let max_lag = match with_options.remove("max_lag") {
+        None => None,
+        Some(Value::SingleQuotedString(s)) => {
+            let max_lag =
+                parse_duration::parse(&s).map_err(|e| format_err!("invalid max_lag: {}", e))?;
+            if max_lag.as_millis() == 0 {
+                bail!("max_lag must be positive");
+            }
+            Some(max_lag)
+        }
+        Some(_) => bail!("max_lag must be a string"),
+    };
     if !with_options.is_empty() {
-        bail!("WITH options are not yet supported");
+        bail!(
+            "Unexpected WITH options: {}",
+            join(with_options.keys(), ",")
+        )
     }
     let name = scx.allocate_name(normalize::object_name(name.to_owned())?);
     let replace = if *replace {
@@ -846,6 +1653,25 @@ fn handle_create_view(
             desc.set_name(i, Some(normalize::column_name(name.clone())));
         }
     }
+    let emit_final_after = match emit_final_after {
+        None => None,
+        Some(column) => {
+            let column_name = normalize::column_name(Ident::new(column));
+            match desc.get_by_name(&column_name) {
+                Some((i, typ))
+                    if typ.scalar_type == ScalarType::Timestamp
+                        || typ.scalar_type == ScalarType::TimestampTz =>
+                {
+                    Some(i)
+                }
+                Some(_) => bail!(
+                    "emit_final_after column {} must be a timestamp",
+                    column_name
+                ),
+                None => bail!("emit_final_after column {} does not exist", column_name),
+            }
+        }
+    };
     let materialize = *materialized; // Normalize for `raw_sql` below.
     Ok(Plan::CreateView {
         name,
@@ -853,9 +1679,14 @@ fn handle_create_view(
             create_sql,
             expr: relation_expr,
             desc,
+            refresh_interval,
+            changes_only,
+            emit_final_after,
+            max_lag,
         },
         replace,
         materialize,
+        cluster: in_cluster.take().map(normalize::ident),
     })
 }
 
@@ -870,6 +1701,17 @@ async fn purify_statement(mut stmt: Statement) -> Result<Statement, failure::Err
         connector, format, ..
     } = &mut stmt
     {
+        if let Connector::Postgres {
+            conn,
+            table,
+            columns,
+        } = connector
+        {
+            if columns.is_empty() {
+                *columns = get_postgres_columns(conn, table).await?;
+            }
+        }
+
         let topic = if let Connector::Kafka { broker, topic, .. } = connector {
             if !broker.contains(':') {
                 *broker += ":9092";
@@ -880,7 +1722,7 @@ async fn purify_statement(mut stmt: Statement) -> Result<Statement, failure::Err
         };
 
         match format {
-            Format::Avro(schema) => match schema {
+            Some(Format::Avro(schema)) => match schema {
                 AvroSchema::CsrUrl { url, seed } => {
                     let topic = if let Some(topic) = topic {
                         topic
@@ -893,7 +1735,9 @@ async fn purify_statement(mut stmt: Statement) -> Result<Statement, failure::Err
                             key_schema,
                             value_schema,
                             ..
-                        } = get_remote_avro_schema(url, topic.clone()).await?;
+                        } = get_remote_avro_schema(url, topic.clone())
+                            .await
+                            .map_err(|e| dataflow_types::Error::Connector(e.to_string()))?;
                         *seed = Some(CsrSeed {
                             key_schema,
                             value_schema,
@@ -906,7 +1750,7 @@ async fn purify_statement(mut stmt: Statement) -> Result<Statement, failure::Err
                 }
                 _ => {}
             },
-            Format::Protobuf { schema, .. } => {
+            Some(Format::Protobuf { schema, .. }) => {
                 if let sql_parser::ast::Schema::File(path) = schema {
                     let descriptors = tokio::fs::read(path).await?;
                     let mut buf = String::new();
@@ -930,6 +1774,7 @@ fn handle_create_dataflow_pure(
             connector,
             format,
             envelope,
+            include_metadata,
             if_not_exists,
             materialized,
         } => {
@@ -938,7 +1783,8 @@ fn handle_create_dataflow_pure(
                 sql_parser::ast::Envelope::Debezium => dataflow_types::Envelope::Debezium,
             };
 
-            let mut source = match connector {
+            let (connector, deduplication_options) = split_deduplication_options(connector);
+            let mut source = match &connector {
                 Connector::Kafka {
                     broker,
                     topic,
@@ -960,21 +1806,60 @@ fn handle_create_dataflow_pure(
                         Some(_) => bail!("ssl_certificate_file must be a string"),
                     };
 
+                    let start_offset = match with_options.remove("start_offset") {
+                        None => None,
+                        Some(Value::Number(n)) => match n.parse::<i64>() {
+                            Ok(n) if n >= 0 => Some(n),
+                            _ => bail!("start_offset must be a nonnegative integer"),
+                        },
+                        Some(_) => bail!("start_offset must be a nonnegative integer"),
+                    };
+
+                    let group_id = match with_options.remove("group_id") {
+                        None => None,
+                        Some(Value::SingleQuotedString(group_id)) => Some(group_id),
+                        Some(_) => bail!("group_id must be a string"),
+                    };
+
+                    // Options whose names contain a dot, like
+                    // "fetch.min.bytes", are passed through to librdkafka.
+                    let mut config_options = BTreeMap::new();
+                    for name in with_options.keys().cloned().collect::<Vec<_>>() {
+                        if !name.contains('.') {
+                            continue;
+                        }
+                        if name == "bootstrap.servers" || name == "group.id" {
+                            bail!(
+                                "{} cannot be set directly; use BROKER or group_id instead",
+                                name
+                            );
+                        }
+                        let value = match with_options.remove(&name).unwrap() {
+                            Value::SingleQuotedString(s) | Value::Number(s) => s,
+                            Value::Boolean(b) => b.to_string(),
+                            _ => bail!(
+                                "librdkafka option {} must be a string, number, or boolean",
+                                name
+                            ),
+                        };
+                        config_options.insert(name, value);
+                    }
+
                     if !with_options.is_empty() {
                         bail!(
                             "Unexpected WITH options: {}",
                             join(with_options.keys(), ",")
                         )
                     }
-                    let url = broker.parse()?;
-                    build_kafka_source(
-                        url,
-                        topic.clone(),
-                        format,
-                        envelope,
-                        consistency,
+                    let connector = KafkaSourceConnector {
+                        url: broker.parse()?,
+                        topic: topic.clone(),
                         ssl_certificate_file,
-                    )?
+                        start_offset,
+                        group_id,
+                        config_options,
+                    };
+                    build_kafka_source(connector, require_format(format)?, envelope, consistency)?
                 }
                 Connector::Kinesis { arn, with_options } => {
                     let mut with_options: HashMap<_, _> = with_options
@@ -1009,7 +1894,7 @@ fn handle_create_dataflow_pure(
                         }
                     }
 
-                    let (encoding, desc) = match format {
+                    let (encoding, desc) = match require_format(format)? {
                         Format::Bytes => (
                             DataEncoding::Bytes,
                             RelationDesc::new(
@@ -1032,6 +1917,8 @@ fn handle_create_dataflow_pure(
                             encoding,
                             envelope: Envelope::None,
                             consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
                         },
                         desc,
                     };
@@ -1052,119 +1939,383 @@ fn handle_create_dataflow_pure(
                         Some(Value::Boolean(b)) => b,
                         Some(_) => bail!("tail must be a boolean"),
                     };
+                    let compression = match with_options.remove("compression") {
+                        // Without an explicit compression, it is inferred
+                        // from the file's extension.
+                        None => match Path::new(path).extension().and_then(|e| e.to_str()) {
+                            Some("gz") => Compression::Gzip,
+                            Some("bz2") => Compression::Bzip2,
+                            _ => Compression::None,
+                        },
+                        Some(Value::SingleQuotedString(s)) => match s.to_lowercase().as_str() {
+                            "gzip" => Compression::Gzip,
+                            "bzip2" => Compression::Bzip2,
+                            "none" => Compression::None,
+                            _ => bail!("compression must be 'gzip', 'bzip2', or 'none'"),
+                        },
+                        Some(_) => bail!("compression must be a string"),
+                    };
                     if !with_options.is_empty() {
                         bail!(
                             "Unexpected WITH options: {}",
                             join(with_options.keys(), ",")
                         )
                     }
-                    let (encoding, desc) = match format {
-                        Format::Bytes => (
-                            DataEncoding::Bytes,
-                            RelationDesc::new(
-                                RelationType::new(vec![
-                                    ColumnType::new(ScalarType::Bytes),
-                                    ColumnType::new(ScalarType::Int64).nullable(true),
-                                ]),
-                                iter::once(Some(String::from("data")))
-                                    .chain(iter::once(Some(String::from("mz_line_no")))),
-                            ),
-                        ),
-                        Format::Avro(_) => bail!("Avro-format file sources are not yet supported"),
-                        Format::Protobuf { .. } => {
-                            bail!("Protobuf-format file sources are not yet supported")
+                    let (encoding, desc) =
+                        build_record_encoding(require_format(format)?, "file", "mz_line_no")?;
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope file sources are not supported")
                         }
-                        Format::Regex(s) => {
-                            let regex = match regex::Regex::new(&s) {
-                                Ok(r) => r,
-                                Err(e) => bail!("Error compiling regex: {}", e),
-                            };
-                            let names: Vec<_> = regex
-                                .capture_names()
-                                .enumerate()
-                                // The first capture is the entire matched string.
-                                // This will often not be useful, so skip it.
-                                // If people want it they can just surround their
-                                // entire regex in an explicit capture group.
-                                .skip(1)
-                                .map(|(i, ocn)| match ocn {
-                                    None => Some(format!("column{}", i)),
-                                    Some(ocn) => Some(String::from(ocn)),
-                                })
-                                .chain(iter::once(Some(String::from("mz_line_no"))))
-                                .collect();
-                            let n_cols = names.len() - 1;
-                            if n_cols == 0 {
-                                bail!("source regex must contain at least one capture group to be useful");
-                            }
-                            let cols =
-                                iter::repeat(ColumnType::new(ScalarType::String).nullable(true))
-                                    .take(n_cols)
-                                    .chain(iter::once(
-                                        ColumnType::new(ScalarType::Int64).nullable(true),
-                                    ))
-                                    .collect();
-                            (
-                                DataEncoding::Regex { regex },
-                                RelationDesc::new(RelationType::new(cols), names),
-                            )
+                    }
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::File(FileSourceConnector {
+                                path: path.clone().into(),
+                                tail,
+                                compression,
+                            }),
+                            encoding,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc,
+                    }
+                }
+                Connector::Mqtt {
+                    broker,
+                    topic,
+                    with_options,
+                } => {
+                    let mut with_options: HashMap<_, _> = with_options
+                        .iter()
+                        .map(|op| (op.name.value.to_ascii_lowercase(), op.value.clone()))
+                        .collect();
+                    let qos = match with_options.remove("qos") {
+                        None => MqttQos::AtMostOnce,
+                        Some(Value::Number(n)) if n == "0" => MqttQos::AtMostOnce,
+                        Some(Value::Number(n)) if n == "1" => MqttQos::AtLeastOnce,
+                        Some(Value::Number(n)) if n == "2" => MqttQos::ExactlyOnce,
+                        Some(_) => bail!("qos must be 0, 1, or 2"),
+                    };
+                    let client_id = match with_options.remove("client_id") {
+                        None => None,
+                        Some(Value::SingleQuotedString(client_id)) => Some(client_id),
+                        Some(_) => bail!("client_id must be a string"),
+                    };
+                    if !with_options.is_empty() {
+                        bail!(
+                            "Unexpected WITH options: {}",
+                            join(with_options.keys(), ",")
+                        )
+                    }
+
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope MQTT sources are not supported")
                         }
-                        Format::Csv { n_cols, delimiter } => {
-                            let delimiter = match *delimiter as u32 {
-                                0..=127 => *delimiter as u8,
-                                _ => bail!("CSV delimiter must be an ASCII character"),
-                            };
-                            let cols = iter::repeat(ColumnType::new(ScalarType::String))
-                                .take(*n_cols)
-                                .chain(iter::once(
-                                    ColumnType::new(ScalarType::Int64).nullable(true),
-                                ))
-                                .collect();
-                            let names = (1..=*n_cols)
-                                .map(|i| Some(format!("column{}", i)))
-                                .chain(iter::once(Some(String::from("mz_line_no"))));
-                            (
-                                DataEncoding::Csv(CsvEncoding {
-                                    n_cols: *n_cols,
-                                    delimiter,
-                                }),
-                                RelationDesc::new(RelationType::new(cols), names),
-                            )
+                    }
+
+                    let (host, port) = parse_mqtt_broker(broker)?;
+                    validate_mqtt_topic_filter(topic)?;
+                    let (encoding, desc) = build_mqtt_encoding(require_format(format)?)?;
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::Mqtt(MqttSourceConnector {
+                                host,
+                                port,
+                                topic: topic.clone(),
+                                qos,
+                                client_id,
+                            }),
+                            encoding,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc,
+                    }
+                }
+                Connector::Mysql {
+                    url,
+                    table,
+                    columns,
+                } => {
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope MySQL sources are not supported")
                         }
-                        Format::Json => bail!("JSON-format file sources are not yet supported"),
-                        Format::Text => (
-                            DataEncoding::Text,
-                            RelationDesc::new(
-                                RelationType::new(vec![
-                                    ColumnType::new(ScalarType::String),
-                                    ColumnType::new(ScalarType::Int64).nullable(true),
-                                ]),
-                                iter::once(Some(String::from("text")))
-                                    .chain(iter::once(Some(String::from("mz_line_no")))),
-                            ),
+                    }
+                    let (url, database, table, get_server_public_key) =
+                        parse_mysql_table(url, table)?;
+                    // As with PostgreSQL sources, any column may be null.
+                    let mut column_types = vec![];
+                    for column in columns {
+                        let typ = query::scalar_type_from_sql(&column.data_type)?;
+                        match typ {
+                            ScalarType::Bool
+                            | ScalarType::Int32
+                            | ScalarType::Int64
+                            | ScalarType::Float64
+                            | ScalarType::Decimal(..)
+                            | ScalarType::String
+                            | ScalarType::Bytes
+                            | ScalarType::Date
+                            | ScalarType::Timestamp
+                            | ScalarType::TimestampTz => (),
+                            _ => bail!("MySQL sources do not support columns of type {}", typ),
+                        }
+                        column_types.push(ColumnType::new(typ).nullable(true));
+                    }
+                    let names = columns
+                        .iter()
+                        .map(|column| Some(normalize::ident(column.name.clone())));
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::Mysql(MysqlSourceConnector {
+                                url,
+                                database,
+                                table,
+                                get_server_public_key,
+                            }),
+                            encoding: DataEncoding::Row,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc: RelationDesc::new(RelationType::new(column_types), names),
+                    }
+                }
+                Connector::Nats {
+                    url,
+                    subject,
+                    with_options,
+                } => {
+                    let mut with_options: HashMap<_, _> = with_options
+                        .iter()
+                        .map(|op| (op.name.value.to_ascii_lowercase(), op.value.clone()))
+                        .collect();
+                    let stream = match with_options.remove("stream") {
+                        Some(Value::SingleQuotedString(stream)) => stream,
+                        Some(_) => bail!("stream must be a string"),
+                        None => bail!(
+                            "NATS sources require a stream option naming the JetStream stream"
                         ),
                     };
+                    let durable = match with_options.remove("durable") {
+                        Some(Value::SingleQuotedString(durable)) => durable,
+                        Some(_) => bail!("durable must be a string"),
+                        None => bail!("NATS sources require a durable option naming the consumer"),
+                    };
+                    if !with_options.is_empty() {
+                        bail!(
+                            "Unexpected WITH options: {}",
+                            join(with_options.keys(), ",")
+                        )
+                    }
+                    for (option, name) in &[("stream", &stream), ("durable", &durable)] {
+                        // JetStream names appear as tokens in API subjects.
+                        if name.is_empty()
+                            || name.contains(|c: char| {
+                                c == '.' || c == '*' || c == '>' || c.is_whitespace()
+                            })
+                        {
+                            bail!("invalid JetStream {} name: {}", option, name);
+                        }
+                    }
+
                     match envelope {
                         dataflow_types::Envelope::None => {}
                         dataflow_types::Envelope::Debezium => {
-                            bail!("Debezium-envelope file sources are not supported")
+                            bail!("Debezium-envelope NATS sources are not supported")
                         }
                     }
+
+                    let url = parse_nats_url(url)?;
+                    validate_nats_subject(subject, true)?;
+                    let (encoding, desc) =
+                        build_record_encoding(require_format(format)?, "NATS", "mz_offset")?;
                     Source {
                         create_sql: "<filled in below>".into(),
                         connector: SourceConnector::External {
-                            connector: ExternalSourceConnector::File(FileSourceConnector {
-                                path: path.clone().into(),
-                                tail,
+                            connector: ExternalSourceConnector::Nats(NatsSourceConnector {
+                                url,
+                                subject: subject.clone(),
+                                stream,
+                                durable,
+                            }),
+                            encoding,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc,
+                    }
+                }
+                Connector::Plugin { name, with_options } => {
+                    let options = plugin_options(with_options)?;
+                    let (encoding, desc) =
+                        build_record_encoding(require_format(format)?, "plugin", "mz_offset")?;
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope plugin sources are not supported")
+                        }
+                    }
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::Plugin(PluginSourceConnector {
+                                name: name.clone(),
+                                options,
+                            }),
+                            encoding,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc,
+                    }
+                }
+                Connector::Postgres {
+                    conn,
+                    table,
+                    columns,
+                } => {
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope PostgreSQL sources are not supported")
+                        }
+                    }
+                    if columns.is_empty() {
+                        bail!("PostgreSQL sources must have at least one column");
+                    }
+                    // Any column may be null, as the table's constraints can
+                    // change without the source noticing.
+                    let mut column_types = vec![];
+                    for column in columns {
+                        let typ = query::scalar_type_from_sql(&column.data_type)?;
+                        column_types.push(ColumnType::new(typ).nullable(true));
+                    }
+                    let names = columns
+                        .iter()
+                        .map(|column| Some(normalize::ident(column.name.clone())));
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::Postgres(PostgresSourceConnector {
+                                conn: conn.clone(),
+                                table: table.clone(),
+                            }),
+                            encoding: DataEncoding::Row,
+                            envelope,
+                            consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
+                        },
+                        desc: RelationDesc::new(RelationType::new(column_types), names),
+                    }
+                }
+                Connector::Elasticsearch { .. } => {
+                    bail!("Elasticsearch sources are not supported")
+                }
+                Connector::Redis { .. } => bail!("Redis sources are not supported"),
+                Connector::S3 {
+                    bucket,
+                    with_options,
+                } => {
+                    let mut with_options: HashMap<_, _> = with_options
+                        .iter()
+                        .map(|op| (op.name.value.to_ascii_lowercase(), op.value.clone()))
+                        .collect();
+                    let mut string_option = |name: &str| match with_options.remove(name) {
+                        None => Ok(None),
+                        Some(Value::SingleQuotedString(s)) => Ok(Some(s)),
+                        Some(_) => bail!("{} must be a string", name),
+                    };
+                    let region = match string_option("region")? {
+                        Some(region) => region,
+                        None => bail!("S3 sources require a `region` option"),
+                    };
+                    let access_key = match string_option("access_key")? {
+                        Some(access_key) => access_key,
+                        None => bail!("S3 sources require an `access_key` option"),
+                    };
+                    let secret_access_key = match string_option("secret_access_key")? {
+                        Some(secret_access_key) => secret_access_key,
+                        None => bail!("S3 sources require a `secret_access_key` option"),
+                    };
+                    let prefix = string_option("prefix")?.unwrap_or_default();
+                    let pattern = string_option("pattern")?;
+                    let sqs_queue = match string_option("sqs_queue")? {
+                        None => None,
+                        Some(url) => Some(parse_http_url(&url, "sqs_queue")?),
+                    };
+                    let endpoint = match string_option("endpoint")? {
+                        None => None,
+                        Some(url) => Some(parse_http_url(&url, "endpoint")?),
+                    };
+                    if !with_options.is_empty() {
+                        bail!(
+                            "Unexpected WITH options: {}",
+                            join(with_options.keys(), ",")
+                        )
+                    }
+                    if bucket.is_empty() || bucket.contains('/') {
+                        bail!("invalid S3 bucket name: {}", bucket);
+                    }
+
+                    match envelope {
+                        dataflow_types::Envelope::None => {}
+                        dataflow_types::Envelope::Debezium => {
+                            bail!("Debezium-envelope S3 sources are not supported")
+                        }
+                    }
+
+                    let (encoding, desc) =
+                        build_record_encoding(require_format(format)?, "S3", "mz_record")?;
+                    Source {
+                        create_sql: "<filled in below>".into(),
+                        connector: SourceConnector::External {
+                            connector: ExternalSourceConnector::S3(S3SourceConnector {
+                                bucket: bucket.clone(),
+                                region,
+                                access_key,
+                                secret_access_key,
+                                prefix,
+                                pattern,
+                                sqs_queue,
+                                endpoint,
                             }),
                             encoding,
                             envelope,
                             consistency: Consistency::RealTime,
+                            metadata: vec![],
+                            deduplication: None,
                         },
                         desc,
                     }
                 }
             };
+            if !include_metadata.is_empty() {
+                include_source_metadata(&mut source, include_metadata)?;
+            }
+            if !deduplication_options.is_empty() {
+                deduplicate_source(&mut source, deduplication_options)?;
+            }
 
             // TODO(benesch): figure out how to get the actual catalog in here.
             // Likely need to return a non-async func that takes the session and
@@ -1189,6 +2340,155 @@ fn handle_create_dataflow_pure(
     }
 }
 
+/// Appends the metadata columns that `include_metadata` requests to the
+/// columns of `source`.
+fn include_source_metadata(
+    source: &mut Source,
+    include_metadata: &[sql_parser::ast::SourceMetadata],
+) -> Result<(), failure::Error> {
+    let metadata = match &mut source.connector {
+        SourceConnector::External {
+            connector,
+            envelope,
+            metadata,
+            ..
+        } => {
+            // These sources decode their own records rather than passing
+            // them through the decoders that append the metadata.
+            match connector {
+                ExternalSourceConnector::Mqtt(_) => bail!("MQTT sources do not support INCLUDE"),
+                ExternalSourceConnector::Postgres(_) => {
+                    bail!("PostgreSQL sources do not support INCLUDE")
+                }
+                ExternalSourceConnector::Mysql(_) => bail!("MySQL sources do not support INCLUDE"),
+                _ => (),
+            }
+            // Retractions would carry the metadata of the record that
+            // retracts them, rather than that of the record they retract.
+            if let Envelope::Debezium = envelope {
+                bail!("Debezium-envelope sources do not support INCLUDE");
+            }
+            metadata
+        }
+        SourceConnector::Local => unreachable!(),
+    };
+    let mut typ = source.desc.typ().clone();
+    let mut names: Vec<_> = source.desc.iter_names().map(|n| n.cloned()).collect();
+    for included in include_metadata {
+        let (m, name, column_type) = match included {
+            sql_parser::ast::SourceMetadata::Offset => (
+                dataflow_types::SourceMetadata::Offset,
+                "mz_offset",
+                ColumnType::new(ScalarType::Int64).nullable(true),
+            ),
+            sql_parser::ast::SourceMetadata::Partition => (
+                dataflow_types::SourceMetadata::Partition,
+                "mz_partition",
+                ColumnType::new(ScalarType::Int32).nullable(true),
+            ),
+            sql_parser::ast::SourceMetadata::Timestamp => (
+                dataflow_types::SourceMetadata::Timestamp,
+                "mz_timestamp",
+                ColumnType::new(ScalarType::TimestampTz),
+            ),
+        };
+        if metadata.contains(&m) {
+            bail!("INCLUDE lists {} more than once", included);
+        }
+        if names
+            .iter()
+            .any(|n| n.as_ref().map(|n| n.as_str()) == Some(name))
+        {
+            bail!("source already has a column named {}", name);
+        }
+        metadata.push(m);
+        typ.column_types.push(column_type);
+        names.push(Some(name.into()));
+    }
+    source.desc = RelationDesc::new(typ, names);
+    Ok(())
+}
+
+/// Separates the `WITH` options of `connector` that configure the
+/// deduplication of the source's records, which apply to sources of every
+/// kind, from the options that the connector itself interprets.
+fn split_deduplication_options(connector: &Connector) -> (Connector, HashMap<String, Value>) {
+    let mut connector = connector.clone();
+    let with_options = match &mut connector {
+        Connector::File { with_options, .. }
+        | Connector::Kafka { with_options, .. }
+        | Connector::Kinesis { with_options, .. }
+        | Connector::Mqtt { with_options, .. }
+        | Connector::Nats { with_options, .. }
+        | Connector::Plugin { with_options, .. }
+        | Connector::S3 { with_options, .. } => with_options,
+        _ => return (connector, HashMap::new()),
+    };
+    let mut deduplication_options = HashMap::new();
+    with_options.retain(|op| {
+        let name = op.name.value.to_ascii_lowercase();
+        if name == "dedup_key" || name == "dedup_window" {
+            deduplication_options.insert(name, op.value.clone());
+            false
+        } else {
+            true
+        }
+    });
+    (connector, deduplication_options)
+}
+
+/// Configures `source` to discard the records that repeat the key of a recent
+/// record, as `with_options` describes.
+fn deduplicate_source(
+    source: &mut Source,
+    mut with_options: HashMap<String, Value>,
+) -> Result<(), failure::Error> {
+    let key = match with_options.remove("dedup_key") {
+        None => bail!("dedup_window requires dedup_key"),
+        Some(Value::SingleQuotedString(key)) => key,
+        Some(_) => bail!("dedup_key must be a string"),
+    };
+    let window = match with_options.remove("dedup_window") {
+        None => Duration::from_secs(60 * 60),
+        Some(Value::SingleQuotedString(s)) => {
+            let window = parse_duration::parse(&s)
+                .map_err(|e| format_err!("invalid dedup_window: {}", e))?;
+            if window.as_millis() == 0 {
+                bail!("dedup_window must be positive");
+            }
+            window
+        }
+        Some(_) => bail!("dedup_window must be a string"),
+    };
+    let names: Vec<_> = source.desc.iter_names().map(|n| n.cloned()).collect();
+    let key = key
+        .split(',')
+        .map(|name| {
+            let name = name.trim();
+            names
+                .iter()
+                .position(|n| n.as_ref().map(|n| n.as_str()) == Some(name))
+                .ok_or_else(|| format_err!("dedup_key names unknown column {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match &mut source.connector {
+        SourceConnector::External {
+            envelope,
+            deduplication,
+            ..
+        } => {
+            // Updates from the Debezium envelope retract earlier records, and
+            // so must never be discarded.
+            if let Envelope::Debezium = envelope {
+                bail!("Debezium-envelope sources do not support dedup_key");
+            }
+            *deduplication = Some(Deduplication { key, window });
+        }
+        SourceConnector::Local => unreachable!(),
+    }
+    Ok(())
+}
+
 async fn handle_create_dataflow(
     stmt: Statement,
     session: Box<dyn PlanSession + Send>,
@@ -1224,9 +2524,11 @@ fn handle_drop_objects(
 ) -> Result<Plan, failure::Error> {
     match object_type {
         ObjectType::Schema => handle_drop_schema(scx, if_exists, names, cascade),
-        ObjectType::Source | ObjectType::View | ObjectType::Index | ObjectType::Sink => {
-            handle_drop_items(scx, object_type, if_exists, names, cascade)
-        }
+        ObjectType::Source
+        | ObjectType::View
+        | ObjectType::Index
+        | ObjectType::Sink
+        | ObjectType::Type => handle_drop_items(scx, object_type, if_exists, names, cascade),
         _ => bail!("unsupported SQL statement: DROP {}", object_type),
     }
 }
@@ -1342,7 +2644,7 @@ fn handle_drop_item(
                                 dep.name()
                             );
                         }
-                        CatalogItem::Index(_) => (),
+                        CatalogItem::Index(_) | CatalogItem::Type(_) => (),
                     }
                 }
             }
@@ -1362,7 +2664,9 @@ fn handle_select(
     query: Query,
     params: &Params,
 ) -> Result<Plan, failure::Error> {
-    let (relation_expr, _, finishing) = handle_query(scx, query, params, QueryLifetime::OneShot)?;
+    let (relation_expr, _, mut finishing) =
+        handle_query(scx, query, params, QueryLifetime::OneShot)?;
+    let relation_expr = render_enum_columns(scx, relation_expr, &mut finishing)?;
     Ok(Plan::Peek {
         source: relation_expr,
         when: PeekWhen::Immediately,
@@ -1381,15 +2685,56 @@ fn handle_explain(
         handle_query(scx, query, params, QueryLifetime::OneShot)?;
     // Previouly we would bail here for ORDER BY and LIMIT; this has been relaxed to silently
     // report the plan without the ORDER BY and LIMIT decorations (which are done in post).
-    if stage == Stage::Dataflow {
-        Ok(Plan::SendRows(vec![Row::pack(&[Datum::String(
+    match stage {
+        Stage::Dataflow => Ok(Plan::SendRows(vec![Row::pack(&[Datum::String(
             &relation_expr.pretty_humanized(scx.catalog),
-        )])]))
+        )])])),
+        Stage::Plan => Ok(Plan::ExplainPlan(relation_expr)),
+        Stage::Analyze => Ok(Plan::ExplainAnalyze(relation_expr)),
+    }
+}
+
+/// Arranges for the values of enum columns in the results of a query to be
+/// rendered as their labels.
+///
+/// The labels are appended to the rows as new columns, which replace the enum
+/// columns in `finishing`'s projection. Rows are still ordered by the enum
+/// values themselves, and so in the order in which the labels were declared.
+fn render_enum_columns(
+    scx: &StatementContext,
+    relation_expr: expr::RelationExpr,
+    finishing: &mut RowSetFinishing,
+) -> Result<expr::RelationExpr, failure::Error> {
+    let typ = relation_expr.typ();
+    let mut labels = vec![];
+    for col in &mut finishing.project {
+        if let ScalarType::Enum(id) = typ.column_types[*col].scalar_type {
+            let func = expr::UnaryFunc::CastEnumToString(scx.enum_labels(id)?);
+            labels.push(expr::ScalarExpr::Column(*col).call_unary(func));
+            *col = typ.column_types.len() + labels.len() - 1;
+        }
+    }
+    if labels.is_empty() {
+        Ok(relation_expr)
     } else {
-        Ok(Plan::ExplainPlan(relation_expr))
+        Ok(relation_expr.map(labels))
     }
 }
 
+/// Describes the results of a query whose enum columns are rendered by
+/// [`render_enum_columns`].
+fn render_enum_types(desc: RelationDesc) -> RelationDesc {
+    let typ = RelationType::new(
+        desc.iter_types()
+            .map(|typ| match typ.scalar_type {
+                ScalarType::Enum(_) => ColumnType::new(ScalarType::String).nullable(typ.nullable),
+                _ => typ.clone(),
+            })
+            .collect(),
+    );
+    RelationDesc::new(typ, desc.iter_names().map(|name| name.cloned()))
+}
+
 /// Plans and decorrelates a `Query`. Like `query::plan_root_query`, but returns
 /// an `::expr::RelationExpr`, which cannot include correlated expressions.
 fn handle_query(
@@ -1403,17 +2748,303 @@ fn handle_query(
     Ok((expr.decorrelate()?, desc, finishing))
 }
 
+/// Returns the format of a source whose connector requires one.
+fn require_format(format: &Option<Format>) -> Result<&Format, failure::Error> {
+    match format {
+        Some(format) => Ok(format),
+        None => bail!("source requires a FORMAT"),
+    }
+}
+
+/// Determines how to decode the records of a source whose records are
+/// independent byte strings, like the lines of a file, and the shape of the
+/// resulting relation.
+///
+/// The relation has an additional column, named `position_column`, holding
+/// the position of each record in the source. `kind` names the kind of source
+/// in error messages.
+fn build_record_encoding(
+    format: &Format,
+    kind: &str,
+    position_column: &str,
+) -> Result<(DataEncoding, RelationDesc), failure::Error> {
+    Ok(match format {
+        Format::Bytes => (
+            DataEncoding::Bytes,
+            RelationDesc::new(
+                RelationType::new(vec![
+                    ColumnType::new(ScalarType::Bytes),
+                    ColumnType::new(ScalarType::Int64).nullable(true),
+                ]),
+                iter::once(Some(String::from("data")))
+                    .chain(iter::once(Some(String::from(position_column)))),
+            ),
+        ),
+        Format::Avro(_) => bail!("Avro-format {} sources are not yet supported", kind),
+        Format::Protobuf { .. } => {
+            bail!("Protobuf-format {} sources are not yet supported", kind)
+        }
+        Format::Regex(s) => {
+            let regex = match regex::Regex::new(&s) {
+                Ok(r) => r,
+                Err(e) => bail!("Error compiling regex: {}", e),
+            };
+            let names: Vec<_> = regex
+                .capture_names()
+                .enumerate()
+                // The first capture is the entire matched string.
+                // This will often not be useful, so skip it.
+                // If people want it they can just surround their
+                // entire regex in an explicit capture group.
+                .skip(1)
+                .map(|(i, ocn)| match ocn {
+                    None => Some(format!("column{}", i)),
+                    Some(ocn) => Some(String::from(ocn)),
+                })
+                .chain(iter::once(Some(String::from(position_column))))
+                .collect();
+            let n_cols = names.len() - 1;
+            if n_cols == 0 {
+                bail!("source regex must contain at least one capture group to be useful");
+            }
+            let cols = iter::repeat(ColumnType::new(ScalarType::String).nullable(true))
+                .take(n_cols)
+                .chain(iter::once(
+                    ColumnType::new(ScalarType::Int64).nullable(true),
+                ))
+                .collect();
+            (
+                DataEncoding::Regex { regex },
+                RelationDesc::new(RelationType::new(cols), names),
+            )
+        }
+        Format::Csv {
+            columns,
+            header,
+            delimiter,
+        } => {
+            let delimiter = match *delimiter as u32 {
+                0..=127 => *delimiter as u8,
+                _ => bail!("CSV delimiter must be an ASCII character"),
+            };
+            if *header && kind != "file" {
+                bail!("CSV headers are only supported for file sources");
+            }
+            // Untyped columns hold the text of each field, so they are never
+            // null. Typed columns are null wherever a field is empty.
+            let (column_types, mut cols, names): (Vec<_>, Vec<_>, Vec<_>) = match columns {
+                CsvColumns::Count(n_cols) => (
+                    vec![ScalarType::String; *n_cols],
+                    vec![ColumnType::new(ScalarType::String); *n_cols],
+                    (1..=*n_cols)
+                        .map(|i| Some(format!("column{}", i)))
+                        .collect(),
+                ),
+                CsvColumns::Typed(columns) => {
+                    let mut column_types = vec![];
+                    for column in columns {
+                        column_types.push(query::scalar_type_from_sql(&column.data_type)?);
+                    }
+                    (
+                        column_types.clone(),
+                        column_types
+                            .into_iter()
+                            .map(|typ| ColumnType::new(typ).nullable(true))
+                            .collect(),
+                        columns
+                            .iter()
+                            .map(|column| Some(normalize::ident(column.name.clone())))
+                            .collect(),
+                    )
+                }
+            };
+            if column_types.is_empty() {
+                bail!("CSV sources must have at least one column");
+            }
+            cols.push(ColumnType::new(ScalarType::Int64).nullable(true));
+            let names = names
+                .into_iter()
+                .chain(iter::once(Some(String::from(position_column))));
+            (
+                DataEncoding::Csv(CsvEncoding {
+                    column_types,
+                    header: *header,
+                    delimiter,
+                }),
+                RelationDesc::new(RelationType::new(cols), names),
+            )
+        }
+        Format::Json { columns } if !columns.is_empty() => {
+            // Field names are matched against column names after
+            // normalization, so fields with uppercase letters must be named
+            // by quoted identifiers.
+            let mut names = vec![];
+            let mut types = vec![];
+            for column in columns {
+                names.push(normalize::ident(column.name.clone()));
+                types.push(query::scalar_type_from_sql(&column.data_type)?);
+            }
+            let cols = types
+                .iter()
+                .map(|typ| ColumnType::new(typ.clone()).nullable(true))
+                .chain(iter::once(
+                    ColumnType::new(ScalarType::Int64).nullable(true),
+                ))
+                .collect();
+            let desc = RelationDesc::new(
+                RelationType::new(cols),
+                names
+                    .iter()
+                    .cloned()
+                    .map(Some)
+                    .chain(iter::once(Some(String::from(position_column)))),
+            );
+            (
+                DataEncoding::Json(JsonEncoding {
+                    columns: names.into_iter().zip(types).collect(),
+                }),
+                desc,
+            )
+        }
+        Format::Json { .. } => (
+            DataEncoding::Json(JsonEncoding { columns: vec![] }),
+            RelationDesc::new(
+                RelationType::new(vec![
+                    ColumnType::new(ScalarType::Jsonb).nullable(true),
+                    ColumnType::new(ScalarType::Int64).nullable(true),
+                ]),
+                iter::once(Some(String::from("data")))
+                    .chain(iter::once(Some(String::from(position_column)))),
+            ),
+        ),
+        Format::Text => (
+            DataEncoding::Text,
+            RelationDesc::new(
+                RelationType::new(vec![
+                    ColumnType::new(ScalarType::String),
+                    ColumnType::new(ScalarType::Int64).nullable(true),
+                ]),
+                iter::once(Some(String::from("text")))
+                    .chain(iter::once(Some(String::from(position_column)))),
+            ),
+        ),
+    })
+}
+
+/// Splits an MQTT broker address of the form `host[:port]` into its host and
+/// port, defaulting to the standard MQTT port, 1883.
+fn parse_mqtt_broker(broker: &str) -> Result<(String, u16), failure::Error> {
+    let (host, port) = match broker.rfind(':') {
+        Some(i) => match broker[i + 1..].parse() {
+            Ok(port) => (&broker[..i], port),
+            Err(_) => bail!("invalid MQTT broker port: {}", &broker[i + 1..]),
+        },
+        None => (broker, 1883),
+    };
+    if host.is_empty() {
+        bail!("MQTT broker must specify a host");
+    }
+    Ok((host.to_owned(), port))
+}
+
+/// Parses the URL of a MySQL source, and determines the database of `table`,
+/// which is either qualified with its database or in the database that the URL
+/// names.
+fn parse_mysql_table(
+    url: &str,
+    table: &str,
+) -> Result<(Url, String, String, bool), failure::Error> {
+    let url: Url = url.parse()?;
+    if url.scheme() != "mysql" {
+        bail!("MySQL source URL must use the mysql scheme: {}", url);
+    }
+    if url.host_str().is_none() {
+        bail!("MySQL source URL must specify a host");
+    }
+    let mut get_server_public_key = false;
+    for (name, value) in url.query_pairs() {
+        match (&*name, &*value) {
+            ("get-server-public-key", "true") => get_server_public_key = true,
+            ("get-server-public-key", "false") => get_server_public_key = false,
+            ("get-server-public-key", _) => bail!("get-server-public-key must be true or false"),
+            _ => bail!("unknown MySQL source URL parameter {}", name),
+        }
+    }
+    let (database, table) = match table.find('.') {
+        Some(i) => (table[..i].to_owned(), table[i + 1..].to_owned()),
+        None => (
+            url.path().trim_start_matches('/').to_owned(),
+            table.to_owned(),
+        ),
+    };
+    if database.is_empty() {
+        bail!("MySQL source must specify the database of table {}", table);
+    }
+    if table.is_empty() {
+        bail!("MySQL source must specify a table");
+    }
+    Ok((url, database, table, get_server_public_key))
+}
+
+/// Checks that `filter` is a valid MQTT topic filter: the single-level
+/// wildcard `+` must occupy an entire level, and the multi-level wildcard `#`
+/// must occupy the entire last level.
+fn validate_mqtt_topic_filter(filter: &str) -> Result<(), failure::Error> {
+    if filter.is_empty() {
+        bail!("MQTT topic filter must not be empty");
+    }
+    let levels: Vec<_> = filter.split('/').collect();
+    for (i, level) in levels.iter().enumerate() {
+        let valid = match *level {
+            "+" => true,
+            "#" => i == levels.len() - 1,
+            _ => !level.contains(|c| c == '+' || c == '#'),
+        };
+        if !valid {
+            bail!("invalid MQTT topic filter: {}", filter);
+        }
+    }
+    Ok(())
+}
+
+/// Describes the records of an MQTT source: the message payload, decoded
+/// according to `format`, followed by the topic that the message was
+/// published to and whether the broker delivered it as a retained message.
+fn build_mqtt_encoding(format: &Format) -> Result<(DataEncoding, RelationDesc), failure::Error> {
+    let (encoding, payload_type, payload_name) = match format {
+        Format::Bytes => (DataEncoding::Bytes, ScalarType::Bytes, "data"),
+        Format::Json { columns } if columns.is_empty() => (
+            DataEncoding::Json(JsonEncoding { columns: vec![] }),
+            ScalarType::Jsonb,
+            "data",
+        ),
+        Format::Text => (DataEncoding::Text, ScalarType::String, "text"),
+        _ => bail!("MQTT sources support only FORMAT BYTES, FORMAT JSON, and FORMAT TEXT"),
+    };
+    let desc = RelationDesc::new(
+        RelationType::new(vec![
+            ColumnType::new(payload_type).nullable(true),
+            ColumnType::new(ScalarType::String),
+            ColumnType::new(ScalarType::Bool),
+        ]),
+        vec![
+            Some(String::from(payload_name)),
+            Some(String::from("mz_topic")),
+            Some(String::from("mz_retained")),
+        ],
+    );
+    Ok((encoding, desc))
+}
+
 fn build_kafka_source(
-    url: Url,
-    topic: String,
+    connector: KafkaSourceConnector,
     format: &Format,
     envelope: Envelope,
     consistency: Consistency,
-    ssl_certificate_file: Option<PathBuf>,
 ) -> Result<Source, failure::Error> {
     match (format, envelope) {
         (Format::Avro(schema), Envelope::Debezium) => {
-            build_kafka_avro_source(schema, url, topic, consistency, ssl_certificate_file)
+            build_kafka_avro_source(schema, connector, consistency)
         }
         (Format::Avro(_), _) => {
             // TODO(brennan) -- there's no reason not to support this
@@ -1425,18 +3056,29 @@ fn build_kafka_source(
                 schema,
             },
             Envelope::None,
-        ) => build_kafka_protobuf_source(
-            schema,
-            url,
-            topic,
-            message_name,
-            consistency,
-            ssl_certificate_file,
-        ),
+        ) => build_kafka_protobuf_source(schema, connector, message_name, consistency),
         (Format::Protobuf { .. }, Envelope::Debezium) => {
             bail!("Currently, Debezium-style envelopes are not supported for protobuf messages.")
         }
-        _ => bail!("Currently, Kafka sources only support Avro and Protobuf formats."), // TODO(brennan)
+        (Format::Regex(_), Envelope::None) => {
+            let (encoding, desc) = build_record_encoding(format, "Kafka", "mz_offset")?;
+            Ok(Source {
+                create_sql: "<filled in later>".into(),
+                connector: SourceConnector::External {
+                    connector: ExternalSourceConnector::Kafka(connector),
+                    encoding,
+                    envelope: Envelope::None,
+                    consistency,
+                    metadata: vec![],
+                    deduplication: None,
+                },
+                desc,
+            })
+        }
+        (Format::Regex(_), Envelope::Debezium) => {
+            bail!("Currently, Debezium-style envelopes are not supported for regex-formatted messages.")
+        }
+        _ => bail!("Currently, Kafka sources only support Avro, Protobuf, and regex formats."), // TODO(brennan)
     }
 }
 
@@ -1469,12 +3111,116 @@ async fn get_remote_avro_schema(url: Url, topic: String) -> Result<Schema, failu
     })
 }
 
+/// Looks up the columns of the PostgreSQL table `table`, in the database that
+/// the connection string `conn` connects to, after checking that the database
+/// can report every change to the table through logical replication.
+async fn get_postgres_columns(conn: &str, table: &str) -> Result<Vec<ColumnDef>, failure::Error> {
+    let (client, connection) = tokio_postgres::connect(conn, NoTls)
+        .await
+        .with_context(|err| format!("connecting to PostgreSQL: {}", err))?;
+    let lookup = async {
+        let wal_level: String = client.query_one("SHOW wal_level", &[]).await?.get(0);
+        if wal_level != "logical" {
+            bail!(
+                "PostgreSQL sources require wal_level = logical, but the database has \
+                 wal_level = {}",
+                wal_level
+            );
+        }
+        // Without REPLICA IDENTITY FULL, updates and deletes report only the
+        // old values of the columns in the table's primary key, which is not
+        // enough to retract the old row.
+        let replica_identity: String = client
+            .query_one(
+                "SELECT relreplident::text FROM pg_catalog.pg_class WHERE oid = $1::text::regclass",
+                &[&table],
+            )
+            .await?
+            .get(0);
+        if replica_identity != "f" {
+            bail!(
+                "PostgreSQL table {} must have REPLICA IDENTITY FULL to be used as a source",
+                table
+            );
+        }
+        let rows = client
+            .query(
+                "SELECT a.attname::text, t.typname::text, a.atttypmod \
+                 FROM pg_catalog.pg_attribute a \
+                 JOIN pg_catalog.pg_type t ON t.oid = a.atttypid \
+                 WHERE a.attrelid = $1::text::regclass AND a.attnum > 0 AND NOT a.attisdropped \
+                 ORDER BY a.attnum",
+                &[&table],
+            )
+            .await?;
+        let mut columns = vec![];
+        for row in rows {
+            let name: String = row.get(0);
+            let typname: String = row.get(1);
+            let typmod: i32 = row.get(2);
+            let data_type = match typname.as_str() {
+                "bool" => DataType::Boolean,
+                "int2" => DataType::SmallInt,
+                "int4" => DataType::Int,
+                "int8" => DataType::BigInt,
+                "float4" => DataType::Real,
+                "float8" => DataType::Double,
+                // The type modifier of a numeric column packs its precision
+                // and scale, offset by the size of the varlena header.
+                "numeric" if typmod >= 4 => DataType::Decimal(
+                    Some((((typmod - 4) >> 16) & 0xffff) as u64),
+                    Some(((typmod - 4) & 0xffff) as u64),
+                ),
+                "numeric" => bail!(
+                    "column {} of PostgreSQL table {} is a numeric column without a precision, \
+                     which is not supported",
+                    name,
+                    table
+                ),
+                "text" | "varchar" | "bpchar" => DataType::Text,
+                "date" => DataType::Date,
+                "timestamp" => DataType::Timestamp,
+                "timestamptz" => DataType::TimestampTz,
+                "interval" => DataType::Interval,
+                "bytea" => DataType::Bytea,
+                "json" | "jsonb" => DataType::Jsonb,
+                "inet" => DataType::Inet,
+                "cidr" => DataType::Cidr,
+                "point" => DataType::Point,
+                _ => bail!(
+                    "column {} of PostgreSQL table {} has unsupported type {}",
+                    name,
+                    table,
+                    typname
+                ),
+            };
+            columns.push(ColumnDef {
+                name: Ident::with_quote('"', name),
+                data_type,
+                collation: None,
+                options: vec![],
+            });
+        }
+        if columns.is_empty() {
+            bail!("PostgreSQL table {} has no columns", table);
+        }
+        Ok::<_, failure::Error>(columns)
+    };
+    // The connection performs the communication with the server, and must be
+    // driven alongside the queries.
+    match future::select(Box::pin(lookup), connection).await {
+        Either::Left((res, _)) => res,
+        Either::Right((res, _)) => {
+            res?;
+            bail!("PostgreSQL closed the connection unexpectedly")
+        }
+    }
+}
+
 fn build_kafka_avro_source(
     schema: &AvroSchema,
-    kafka_url: Url,
-    topic: String,
+    connector: KafkaSourceConnector,
     consistency: Consistency,
-    ssl_certificate_file: Option<PathBuf>,
 ) -> Result<Source, failure::Error> {
     let Schema {
         key_schema,
@@ -1514,17 +3260,15 @@ fn build_kafka_avro_source(
     Ok(Source {
         create_sql: "<filled in later>".into(),
         connector: SourceConnector::External {
-            connector: ExternalSourceConnector::Kafka(KafkaSourceConnector {
-                url: kafka_url,
-                topic,
-                ssl_certificate_file,
-            }),
+            connector: ExternalSourceConnector::Kafka(connector),
             encoding: DataEncoding::Avro(AvroEncoding {
                 raw_schema: value_schema,
                 schema_registry_url,
             }),
             envelope: Envelope::Debezium,
             consistency,
+            metadata: vec![],
+            deduplication: None,
         },
         desc,
     })
@@ -1532,11 +3276,9 @@ fn build_kafka_avro_source(
 
 fn build_kafka_protobuf_source(
     schema: &sql_parser::ast::Schema,
-    url: Url,
-    topic: String,
+    connector: KafkaSourceConnector,
     message_name: &str,
     consistency: Consistency,
-    ssl_certificate_file: Option<PathBuf>,
 ) -> Result<Source, failure::Error> {
     let descriptors = match schema {
         sql_parser::ast::Schema::Inline(bytes) => strconv::parse_bytes(&bytes)?,
@@ -1551,17 +3293,15 @@ fn build_kafka_protobuf_source(
     Ok(Source {
         create_sql: "<filled in later>".into(),
         connector: SourceConnector::External {
-            connector: ExternalSourceConnector::Kafka(KafkaSourceConnector {
-                url,
-                topic,
-                ssl_certificate_file,
-            }),
+            connector: ExternalSourceConnector::Kafka(connector),
             encoding: DataEncoding::Protobuf(ProtobufEncoding {
                 descriptors,
                 message_name: message_name.to_owned(),
             }),
             envelope: Envelope::None,
             consistency,
+            metadata: vec![],
+            deduplication: None,
         },
         desc,
     })
@@ -1581,6 +3321,7 @@ fn object_type_matches(object_type: ObjectType, item: &CatalogItem) -> bool {
         CatalogItem::Sink { .. } => object_type == ObjectType::Sink,
         CatalogItem::View { .. } => object_type == ObjectType::View,
         CatalogItem::Index { .. } => object_type == ObjectType::Index,
+        CatalogItem::Type { .. } => object_type == ObjectType::Type,
     }
 }
 
@@ -1592,6 +3333,7 @@ fn object_type_as_plural_str(object_type: ObjectType) -> &'static str {
         ObjectType::View => "VIEWS",
         ObjectType::Source => "SOURCES",
         ObjectType::Sink => "SINKS",
+        ObjectType::Type => "TYPES",
     }
 }
 
@@ -1619,4 +3361,27 @@ impl<'a> StatementContext<'a> {
         self.catalog
             .resolve(self.session.database(), self.session.search_path(), &name)
     }
+
+    /// Resolves `name` to an enum type created with `CREATE TYPE`.
+    pub fn resolve_type(&self, name: ObjectName) -> Result<ScalarType, failure::Error> {
+        let entry = match self.resolve_name(name.clone()) {
+            Ok(full_name) => self.catalog.get(&full_name)?,
+            Err(_) => bail!("type '{}' does not exist", name),
+        };
+        match (entry.id(), entry.item()) {
+            (GlobalId::User(id), CatalogItem::Type(_)) => Ok(ScalarType::Enum(id)),
+            _ => bail!("{} is not a type", entry.name()),
+        }
+    }
+
+    /// Returns the labels of the enum type whose ID is `id`.
+    pub fn enum_labels(&self, id: u64) -> Result<Vec<String>, failure::Error> {
+        match self.catalog.try_get_by_id(&GlobalId::User(id)) {
+            Some(entry) => match entry.item() {
+                CatalogItem::Type(typ) => Ok(typ.labels.clone()),
+                _ => bail!("{} is not a type", entry.name()),
+            },
+            None => bail!("enum type {} does not exist", id),
+        }
+    }
 }
@@ -13,12 +13,15 @@
 //! are much easier to perform in SQL. Someday, we'll want our own SQL IR,
 //! but for now we just use the parser's AST directly.
 
+use repr::{ColumnType, Datum, ScalarType};
 use sql_parser::ast::visit_mut::{self, VisitMut};
 use sql_parser::ast::{
-    BinaryOperator, Expr, Function, Ident, ObjectName, Query, SelectItem, Value,
+    BinaryOperator, DataType, Expr, Function, Ident, ObjectName, Query, SelectItem, Statement,
+    Value,
 };
 
 use crate::normalize;
+use crate::Params;
 
 pub fn transform(query: &mut Query) {
     AggFuncRewriter.visit_query(query);
@@ -200,3 +203,72 @@ impl<'ast> VisitMut<'ast> for IdentFuncRewriter {
         }
     }
 }
+
+/// Replaces each parameter in `stmt` with a literal of the value that
+/// `params` binds it to, cast to the parameter's type, so that the statement
+/// can be executed again without its parameters.
+///
+/// Parameters of enum types are left in place, as their values cannot be
+/// written without consulting the catalog.
+pub fn inline_params(stmt: &mut Statement, params: &Params) {
+    ParamInliner {
+        datums: params.datums.unpack(),
+        types: &params.types,
+    }
+    .visit_statement(stmt);
+}
+
+struct ParamInliner<'a> {
+    datums: Vec<Datum<'a>>,
+    types: &'a [ScalarType],
+}
+
+impl<'a, 'ast> VisitMut<'ast> for ParamInliner<'a> {
+    fn visit_expr(&mut self, expr: &'ast mut Expr) {
+        visit_mut::visit_expr(self, expr);
+        if let Expr::Parameter(n) = expr {
+            let datum = self.datums.get(*n - 1);
+            let typ = self.types.get(*n - 1);
+            if let (Some(datum), Some(typ)) = (datum, typ) {
+                if let Some(literal) = Self::literal(*datum, *typ) {
+                    *expr = literal;
+                }
+            }
+        }
+    }
+}
+
+impl<'a> ParamInliner<'a> {
+    fn literal(datum: Datum, typ: ScalarType) -> Option<Expr> {
+        let data_type = match typ {
+            ScalarType::Bool => DataType::Boolean,
+            ScalarType::Int32 => DataType::Int,
+            ScalarType::Int64 => DataType::BigInt,
+            ScalarType::Float32 => DataType::Real,
+            ScalarType::Float64 => DataType::Double,
+            ScalarType::Decimal(precision, scale) => {
+                DataType::Decimal(Some(precision.into()), Some(scale.into()))
+            }
+            ScalarType::Date => DataType::Date,
+            ScalarType::Time => DataType::Time,
+            ScalarType::Timestamp => DataType::Timestamp,
+            ScalarType::TimestampTz => DataType::TimestampTz,
+            ScalarType::Interval => DataType::Interval,
+            ScalarType::Bytes => DataType::Bytea,
+            ScalarType::String => DataType::Text,
+            ScalarType::Jsonb => DataType::Jsonb,
+            ScalarType::Point => DataType::Point,
+            ScalarType::Inet => DataType::Inet,
+            ScalarType::Cidr => DataType::Cidr,
+            ScalarType::Unknown | ScalarType::Enum(_) | ScalarType::List => return None,
+        };
+        let value = match pgrepr::Value::from_datum(datum, &ColumnType::new(typ)) {
+            None => Value::Null,
+            Some(value) => Value::SingleQuotedString(value.to_text()),
+        };
+        Some(Expr::Cast {
+            expr: Box::new(Expr::Value(value)),
+            data_type,
+        })
+    }
+}
@@ -18,11 +18,16 @@ use crate::runner::State;
 pub fn fuzz(sqls: &str) {
     let mut state = State::start().unwrap();
     for sql in sqls.split(';') {
-        if let Ok((Some(desc), ExecuteResponse::SendRows(rx))) = state.run_sql(sql) {
-            for row in block_on(rx).unwrap().unwrap_rows() {
-                for (typ, datum) in desc.iter_types().zip(row.iter()) {
-                    assert!(datum.is_instance_of(typ));
-                }
+        let (desc, rx) = match state.run_sql(sql) {
+            Ok((Some(desc), ExecuteResponse::SendRows(rx))) => (desc, rx),
+            Ok((Some(desc), ExecuteResponse::StreamRows(stream))) => {
+                (desc, coord::collect_rows(stream))
+            }
+            _ => continue,
+        };
+        for row in block_on(rx).unwrap().0.unwrap_rows() {
+            for (typ, datum) in desc.iter_types().zip(row.iter()) {
+                assert!(datum.is_instance_of(typ));
             }
         }
     }
@@ -338,6 +338,8 @@ fn format_row(
                 (Type::Text, Datum::Timestamp(d)) => d.to_string(),
                 (Type::Text, Datum::TimestampTz(d)) => d.to_string(),
                 (Type::Text, Datum::Interval(iv)) => iv.to_string(),
+                (Type::Text, Datum::Point(p)) => p.to_string(),
+                (Type::Text, Datum::Inet(inet)) => inet.to_string(),
                 // Bytes are printed as text iff they are valid UTF-8. This
                 // seems guaranteed to confuse everyone, but it is required for
                 // compliance with the CockroachDB sqllogictest runner. [0]
@@ -377,11 +379,16 @@ impl State {
         let mut coord = coord::Coordinator::new(coord::Config {
             switchboard: switchboard.clone(),
             num_timely_workers: NUM_TIMELY_WORKERS,
+            clusters: &[],
             symbiosis_url: Some("postgres://"),
             logging: logging_config.as_ref(),
             data_directory: None,
             executor: &executor,
             timestamp: None,
+            catalog_compaction_interval: None,
+            peek_timeout: None,
+            local_input_timestamps: coord::LocalInputTimestamps::Counter,
+            command_log: None,
         })?;
 
         let coord_thread = thread::spawn(move || coord.serve(cmd_rx)).join_on_drop();
@@ -394,6 +401,9 @@ impl State {
             runtime.handle().clone(),
             true,
             logging_config,
+            None,
+            vec![],
+            None,
         )
         .unwrap();
 
@@ -528,10 +538,14 @@ impl State {
                 desc.expect("RelationDesc missing for query that returns rows"),
                 rx,
             ),
+            Ok((desc, ExecuteResponse::StreamRows(stream))) => (
+                desc.expect("RelationDesc missing for query that returns rows"),
+                coord::collect_rows(stream),
+            ),
             Ok(other) => {
                 return Ok(Outcome::PlanFailure {
                     error: failure::format_err!(
-                        "Query did not result in SendRows, instead got {:?}",
+                        "Query did not result in rows, instead got {:?}",
                         other
                     ),
                 });
@@ -559,7 +573,7 @@ impl State {
         };
 
         // get actual output
-        let raw_output = block_on(rows_rx)?.unwrap_rows();
+        let raw_output = block_on(rows_rx)?.0.unwrap_rows();
 
         // unpack expected output
         let QueryOutput {
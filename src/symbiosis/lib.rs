@@ -27,11 +27,13 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::env;
+use std::error::Error as _;
 
 use chrono::Utc;
 use failure::{bail, format_err};
 use sql_parser::ast::ColumnOption;
-use sql_parser::ast::{DataType, ObjectType, Statement};
+use sql_parser::ast::{DataType, Expr, Ident, ObjectName, ObjectType, Statement};
+use tokio_postgres::error::{DbError, SqlState};
 use tokio_postgres::types::FromSql;
 
 use catalog::names::FullName;
@@ -44,6 +46,11 @@ use sql::{normalize, scalar_type_from_sql, MutationKind, Plan, Session, Statemen
 pub struct Postgres {
     client: tokio_postgres::Client,
     table_types: HashMap<FullName, (Vec<DataType>, RelationDesc)>,
+    /// The generated columns of each table, with the expressions that compute
+    /// them. Postgres 11 does not support generated columns, so they are
+    /// created as ordinary columns and filled in by symbiosis after every
+    /// insert or update.
+    generated_columns: HashMap<FullName, Vec<(Ident, Expr)>>,
 }
 
 impl Postgres {
@@ -105,6 +112,7 @@ END $$;
         Ok(Self {
             client,
             table_types: HashMap::new(),
+            generated_columns: HashMap::new(),
         })
     }
 
@@ -134,7 +142,23 @@ END $$;
                 if_not_exists,
                 ..
             } => {
-                self.client.execute(&*stmt.to_string(), &[]).await?;
+                let mut generated = vec![];
+                let mut stripped_stmt = stmt.clone();
+                if let Statement::CreateTable { columns, .. } = &mut stripped_stmt {
+                    for column in columns {
+                        let name = &column.name;
+                        column.options.retain(|o| match &o.option {
+                            ColumnOption::Generated(expr) => {
+                                generated.push((name.clone(), expr.clone()));
+                                false
+                            }
+                            _ => true,
+                        });
+                    }
+                }
+                self.client
+                    .execute(&*stripped_stmt.to_string(), &[])
+                    .await?;
                 let sql_types = columns
                     .iter()
                     .map(|column| column.data_type.clone())
@@ -200,6 +224,7 @@ END $$;
                 let desc = RelationDesc::new(typ, names);
                 self.table_types
                     .insert(name.clone(), (sql_types, desc.clone()));
+                self.generated_columns.insert(name.clone(), generated);
                 Plan::CreateTable {
                     name,
                     desc,
@@ -256,11 +281,16 @@ END $$;
                     kind: MutationKind::Delete,
                 }
             }
-            Statement::Insert { table_name, .. } => {
+            Statement::Insert {
+                table_name,
+                columns,
+                ..
+            } => {
                 let mut updates = vec![];
+                let raw_table_name = table_name;
                 let table_name = scx.resolve_name(table_name.clone())?;
-                let sql = format!("{} RETURNING *", stmt.to_string());
-                for row in self.run_query(&table_name, sql).await? {
+                self.check_not_generated(&table_name, columns)?;
+                for row in self.run_mutation(&table_name, raw_table_name, stmt).await? {
                     updates.push((row, 1));
                 }
                 let affected_rows = updates.len();
@@ -273,12 +303,15 @@ END $$;
             }
             Statement::Update {
                 table_name,
+                assignments,
                 selection,
-                ..
             } => {
                 let mut updates = vec![];
                 let mut sql = format!("SELECT * FROM {}", table_name);
+                let raw_table_name = table_name;
                 let table_name = scx.resolve_name(table_name.clone())?;
+                let assigned = assignments.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
+                self.check_not_generated(&table_name, &assigned)?;
                 if let Some(selection) = selection {
                     sql += &format!(" WHERE {}", selection);
                 }
@@ -286,8 +319,7 @@ END $$;
                     updates.push((row, -1))
                 }
                 let affected_rows = updates.len();
-                let sql = format!("{} RETURNING *", stmt.to_string());
-                for row in self.run_query(&table_name, sql).await? {
+                for row in self.run_mutation(&table_name, raw_table_name, stmt).await? {
                     updates.push((row, 1));
                 }
                 assert_eq!(affected_rows * 2, updates.len());
@@ -302,6 +334,65 @@ END $$;
         })
     }
 
+    /// Returns an error if any of `columns` is a generated column of
+    /// `table_name`, as generated columns cannot be written directly.
+    fn check_not_generated(
+        &self,
+        table_name: &FullName,
+        columns: &[Ident],
+    ) -> Result<(), failure::Error> {
+        if let Some(generated) = self.generated_columns.get(table_name) {
+            for column in columns {
+                if generated.iter().any(|(name, _)| name == column) {
+                    bail!("cannot write to generated column {}", column);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the `INSERT` or `UPDATE` statement `stmt` against `table_name`,
+    /// returning the rows it wrote.
+    ///
+    /// If the table has generated columns, they are computed from the written
+    /// rows by a follow-up `UPDATE` that targets exactly those rows.
+    async fn run_mutation(
+        &mut self,
+        table_name: &FullName,
+        raw_table_name: &ObjectName,
+        stmt: &Statement,
+    ) -> Result<Vec<Row>, failure::Error> {
+        let generated = match self.generated_columns.get(table_name) {
+            Some(generated) if !generated.is_empty() => generated.clone(),
+            _ => {
+                let sql = format!("{} RETURNING *", stmt);
+                return self.run_query(table_name, sql).await;
+            }
+        };
+        let ctids = self
+            .client
+            .query(&*format!("{} RETURNING ctid::text", stmt), &[])
+            .await
+            .map_err(classify_error)?
+            .iter()
+            .map(|row| format!("'{}'", row.get::<_, String>(0)))
+            .collect::<Vec<_>>();
+        if ctids.is_empty() {
+            return Ok(vec![]);
+        }
+        let assignments = generated
+            .iter()
+            .map(|(name, expr)| format!("{} = ({})", name, expr))
+            .collect::<Vec<_>>();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE ctid IN ({}) RETURNING *",
+            raw_table_name,
+            assignments.join(", "),
+            ctids.join(", ")
+        );
+        self.run_query(table_name, sql).await
+    }
+
     async fn run_query(
         &mut self,
         table_name: &FullName,
@@ -313,7 +404,11 @@ END $$;
             .ok_or_else(|| format_err!("Unknown table: {:?}", table_name))?
             .clone();
         let mut rows = vec![];
-        let postgres_rows = self.client.query(&*query, &[]).await?;
+        let postgres_rows = self
+            .client
+            .query(&*query, &[])
+            .await
+            .map_err(classify_error)?;
         for postgres_row in postgres_rows.iter() {
             // NOTE We can't use Row::pack here because PostgresRow::get_opt insists on allocating data for strings,
             // which has to live somewhere while the iterator is running.
@@ -399,6 +494,14 @@ fn push_column(
             let iv = get_column_inner::<pgrepr::Interval>(postgres_row, i, nullable)?.unwrap();
             row.push(Datum::Interval(iv.0));
         }
+        DataType::Point => {
+            let p = get_column_inner::<pgrepr::Point>(postgres_row, i, nullable)?;
+            row.push(p.map(|p| p.0).into());
+        }
+        DataType::Inet | DataType::Cidr => {
+            let inet = get_column_inner::<pgrepr::Inet>(postgres_row, i, nullable)?;
+            row.push(inet.map(|inet| inet.0).into());
+        }
         DataType::Decimal(_, _) => {
             let desired_scale = match scalar_type_from_sql(sql_type).unwrap() {
                 ScalarType::Decimal(_precision, desired_scale) => desired_scale,
@@ -442,6 +545,22 @@ fn push_column(
     Ok(row)
 }
 
+/// Classifies an error returned by Postgres. Violations of `CHECK`
+/// constraints are reported with the name of the violated constraint, so
+/// that clients can tell which constraint rejected the row.
+fn classify_error(err: tokio_postgres::Error) -> failure::Error {
+    if err.code() == Some(&SqlState::CHECK_VIOLATION) {
+        if let Some(db_err) = err.source().and_then(|e| e.downcast_ref::<DbError>()) {
+            return dataflow_types::Error::CheckViolation {
+                constraint: db_err.constraint().unwrap_or("").into(),
+                message: db_err.message().into(),
+            }
+            .into();
+        }
+    }
+    err.into()
+}
+
 fn get_column_inner<'a, T>(
     postgres_row: &'a tokio_postgres::Row,
     i: usize,